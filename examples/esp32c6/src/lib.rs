@@ -0,0 +1,48 @@
+//! Board support of the ESP32-C6 for the backend independent examples in `examples/shared`
+
+#![no_std]
+#![deny(missing_docs)]
+
+use esp_backtrace as _;
+use esp_hal as _;
+
+pub use esp32c6 as pac;
+pub use esp_println::println;
+
+/// An `#[rtic::app]` on the ESP32-C6 with `dispatchers = N`, up to the 4 CPU interrupts
+#[macro_export]
+macro_rules! app {
+    (dispatchers = 0; $($app:tt)*) => {
+        #[rtic::app(device = board::pac)]
+        $($app)*
+    };
+    (dispatchers = 1; $($app:tt)*) => {
+        #[rtic::app(device = board::pac, dispatchers = [FROM_CPU_INTR0])]
+        $($app)*
+    };
+    (dispatchers = 2; $($app:tt)*) => {
+        #[rtic::app(device = board::pac, dispatchers = [FROM_CPU_INTR0, FROM_CPU_INTR1])]
+        $($app)*
+    };
+    (dispatchers = 3; $($app:tt)*) => {
+        #[rtic::app(
+            device = board::pac,
+            dispatchers = [FROM_CPU_INTR0, FROM_CPU_INTR1, FROM_CPU_INTR2]
+        )]
+        $($app)*
+    };
+    (dispatchers = 4; $($app:tt)*) => {
+        #[rtic::app(
+            device = board::pac,
+            dispatchers = [FROM_CPU_INTR0, FROM_CPU_INTR1, FROM_CPU_INTR2, FROM_CPU_INTR3]
+        )]
+        $($app)*
+    };
+}
+
+/// Ends the example, there is nothing to exit to on the target
+pub fn exit() -> ! {
+    loop {
+        core::hint::spin_loop();
+    }
+}
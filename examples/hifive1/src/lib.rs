@@ -0,0 +1,24 @@
+//! Board support of the HiFive1 for the backend independent examples in `examples/shared`
+
+#![no_std]
+#![deny(missing_docs)]
+
+use hifive1 as _;
+use riscv_rt as _;
+
+pub use e310x as pac;
+pub use semihosting::println;
+
+/// An `#[rtic::app]` on the HiFive1, the SLIC provides any number of dispatchers
+#[macro_export]
+macro_rules! app {
+    (dispatchers = $n:literal; $($app:tt)*) => {
+        #[rtic::app(device = board::pac, backend = HART0)]
+        $($app)*
+    };
+}
+
+/// Ends the example, exiting QEMU
+pub fn exit() -> ! {
+    semihosting::process::exit(0)
+}
@@ -0,0 +1,74 @@
+//! examples/async-channel-timeout.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0, QEI0])]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use rtic_monotonics::systick::prelude::*;
+    use rtic_sync::{channel::*, make_channel};
+
+    systick_monotonic!(Mono, 1_000);
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    const CAPACITY: usize = 1;
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        Mono::start(cx.core.SYST, 12_000_000);
+
+        let (s, r) = make_channel!(u32, CAPACITY);
+
+        sender::spawn(s).unwrap();
+        receiver::spawn(r).unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 2)]
+    async fn sender(_: sender::Context, mut sender: Sender<'static, u32, CAPACITY>) {
+        sender.send_timeout::<Mono>(1, 10.millis()).await.unwrap();
+        hprintln!("Sender sent 1");
+
+        // The queue is full until the receiver wakes up
+        if let Err(SendTimeoutError::Timeout(val)) =
+            sender.send_timeout::<Mono>(2, 10.millis()).await
+        {
+            hprintln!("Sender timed out, got {} back", val);
+        }
+
+        // Keep the channel open
+        Mono::delay(100.millis()).await;
+    }
+
+    #[task(priority = 1)]
+    async fn receiver(_: receiver::Context, mut receiver: Receiver<'static, u32, CAPACITY>) {
+        Mono::delay(50.millis()).await;
+
+        loop {
+            match receiver.recv_timeout::<Mono>(10.millis()).await {
+                Ok(val) => hprintln!("Receiver got: {}", val),
+                Err(RecvTimeoutError::Timeout) => {
+                    hprintln!("Receiver timed out");
+                    break;
+                }
+                Err(RecvTimeoutError::NoSender) => {
+                    hprintln!("Receiver lost the sender");
+                    break;
+                }
+            }
+        }
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+}
@@ -0,0 +1,50 @@
+//! examples/brownout.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0], backend = [nvic_prio_bits = 3])]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        hprintln!("init");
+
+        foo::spawn().unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 1)]
+    async fn foo(_: foo::Context) {
+        hprintln!("foo");
+
+        // The supply drops below the warning threshold
+        rtic::pend(Interrupt::GPIOA);
+
+        hprintln!("foo never resumes");
+    }
+
+    // Runs at the highest priority within a bounded time of the warning on `GPIOA`
+    #[shutdown(binds = [GPIOA])]
+    fn safe_state() {
+        hprintln!("outputs safe");
+
+        rtic::crash::failures(|failure| hprintln!("{}", failure.message));
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+}
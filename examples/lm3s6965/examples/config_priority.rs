@@ -0,0 +1,47 @@
+//! examples/config_priority.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+use rtic::app;
+
+// The priority of `control` is set in the `rtic.toml` next to `Cargo.toml`, and
+// `sleep_on_exit` falls back to `false` as the file does not set it
+#[app(
+    device = lm3s6965,
+    dispatchers = [SSI0, QEI0],
+    sleep_on_exit = config!("app.sleep_on_exit", false)
+)]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        logger::spawn().unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 1)]
+    async fn logger(_: logger::Context) {
+        hprintln!("logger - start");
+        control::spawn().unwrap();
+        hprintln!("logger - end");
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+
+    #[task(priority = config!("control.priority"))]
+    async fn control(_: control::Context) {
+        hprintln!(" control");
+    }
+}
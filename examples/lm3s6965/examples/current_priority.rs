@@ -0,0 +1,53 @@
+//! examples/current_priority.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965)]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        rtic::pend(Interrupt::UART0);
+
+        (Shared {}, Local {})
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        hprintln!("idle: {}", rtic::current_priority());
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+
+        loop {
+            cortex_m::asm::nop();
+        }
+    }
+
+    #[task(binds = UART0, priority = 1)]
+    fn foo(_: foo::Context) {
+        hprintln!("foo: {}", rtic::current_priority());
+
+        rtic::pend(Interrupt::UART1);
+
+        hprintln!("foo: {}", rtic::current_priority());
+    }
+
+    #[task(binds = UART1, priority = 2)]
+    fn bar(_: bar::Context) {
+        hprintln!("bar: {}", rtic::current_priority());
+    }
+}
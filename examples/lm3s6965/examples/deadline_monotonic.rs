@@ -0,0 +1,52 @@
+//! examples/deadline_monotonic.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+use rtic::app;
+
+#[app(device = lm3s6965, dispatchers = [SSI0, QEI0])]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        log::spawn().unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    // Longest deadline, priority 1
+    #[task(deadline = 100.millis())]
+    async fn log(_: log::Context) {
+        hprintln!("log - start");
+        control::spawn().unwrap();
+        hprintln!("log - end");
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+
+    // Priority 2
+    #[task(deadline = 10.millis())]
+    async fn control(_: control::Context) {
+        hprintln!(" control - start");
+        rtic::pend(Interrupt::UART0);
+        hprintln!(" control - end");
+    }
+
+    // Shortest deadline, priority 3
+    #[task(binds = UART0, deadline = 500.micros())]
+    fn uart0(_: uart0::Context) {
+        hprintln!("  uart0");
+    }
+}
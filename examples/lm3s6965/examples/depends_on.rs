@@ -0,0 +1,81 @@
+//! examples/depends_on.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+/// The clock tree, configured first
+pub struct Clocks {
+    hclk: u32,
+}
+
+/// A DMA controller, clocked by `hclk`
+pub struct Dma {
+    channels: u8,
+}
+
+/// A serial port, using the clocks for its baud rate and a DMA channel
+pub struct Serial {
+    divisor: u32,
+    channel: u8,
+}
+
+#[rtic::app(device = lm3s6965)]
+mod app {
+    use super::{Clocks, Dma, Serial};
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {
+        clocks: Clocks,
+        #[depends_on(clocks)]
+        dma: Dma,
+    }
+
+    #[local]
+    struct Local {
+        #[depends_on(clocks, dma)]
+        serial: Serial,
+    }
+
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        rtic::pend(Interrupt::UART0);
+
+        // Calling `serial` before `dma`, or `build` before `serial`, does not compile
+        cx.resources
+            .clocks(|| {
+                hprintln!("clocks");
+                Clocks { hclk: 12_000_000 }
+            })
+            .dma(|clocks| {
+                hprintln!("dma at {} Hz", clocks.hclk);
+                Dma { channels: 2 }
+            })
+            .serial(|clocks, dma| {
+                hprintln!("serial with {} DMA channels", dma.channels);
+                Serial {
+                    divisor: clocks.hclk / 115_200,
+                    channel: dma.channels - 1,
+                }
+            })
+            .build()
+    }
+
+    #[task(binds = UART0, shared = [clocks, dma], local = [serial])]
+    fn uart0(cx: uart0::Context) {
+        let serial = cx.local.serial;
+        hprintln!(
+            "UART0: divisor {}, DMA channel {}",
+            serial.divisor,
+            serial.channel
+        );
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+}
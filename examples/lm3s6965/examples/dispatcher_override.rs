@@ -0,0 +1,59 @@
+//! examples/dispatcher_override.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+// `SSI0` dispatches priority 1, `UART0` one of priorities 2 and 3, `QEI0` the remaining one
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0 => 1, UART0 => 2..=3, QEI0])]
+mod app {
+    use cortex_m::{
+        interrupt::InterruptNumber,
+        peripheral::{scb::VectActive, SCB},
+    };
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        low::spawn().unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 1)]
+    async fn low(_: low::Context) {
+        hprintln!("low runs in SSI0: {}", runs_in(Interrupt::SSI0));
+        mid::spawn().unwrap();
+        high::spawn().unwrap();
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+
+    #[task(priority = 2)]
+    async fn mid(_: mid::Context) {
+        hprintln!("mid runs in UART0: {}", runs_in(Interrupt::UART0));
+    }
+
+    #[task(priority = 3)]
+    async fn high(_: high::Context) {
+        hprintln!("high runs in QEI0: {}", runs_in(Interrupt::QEI0));
+    }
+
+    fn runs_in(interrupt: Interrupt) -> bool {
+        matches!(
+            SCB::vect_active(),
+            VectActive::Interrupt { irqn } if u16::from(irqn) == interrupt.number()
+        )
+    }
+}
@@ -0,0 +1,73 @@
+//! examples/dma.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0])]
+mod app {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+    use rtic::dma::{Peripheral, Status, Transfer};
+
+    static LEN: AtomicUsize = AtomicUsize::new(0);
+
+    /// Stands in for a DMA channel, fills the buffer and raises its interrupt at once
+    #[derive(Debug)]
+    pub struct FakeDma;
+
+    impl Peripheral for FakeDma {
+        type Word = u8;
+
+        fn start(&mut self, buffer: &mut [u8]) {
+            for (i, word) in buffer.iter_mut().enumerate() {
+                *word = i as u8;
+            }
+
+            LEN.store(buffer.len(), Ordering::Relaxed);
+            rtic::pend(Interrupt::UART0);
+        }
+
+        fn abort(&mut self) {}
+    }
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init(local = [buf: [u8; 4] = [0; 4]])]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        receive::spawn(cx.local.buf).unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 1)]
+    async fn receive(_: receive::Context, buf: &'static mut [u8]) {
+        // Only compiles with the completion of a task bound in this app
+        let transfer = Transfer::start(buf, FakeDma, uart0::dma()).unwrap();
+        hprintln!("transfer started");
+
+        let (buf, _dma, status) = transfer.await;
+        hprintln!("{:?} {:?}", buf, status);
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+
+    #[task(binds = UART0, priority = 2)]
+    fn uart0(_: uart0::Context) {
+        hprintln!("UART0: transfer complete");
+
+        uart0::dma().complete(Status {
+            transferred: LEN.load(Ordering::Relaxed),
+            error: false,
+        });
+    }
+}
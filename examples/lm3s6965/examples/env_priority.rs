@@ -0,0 +1,43 @@
+//! examples/env_priority.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+use rtic::app;
+
+// The priority of `control` is set by the build script of a product variant through
+// `cargo:rustc-env=CONTROL_PRIORITY=..`, the default applies when it is not set
+#[app(device = lm3s6965, dispatchers = [SSI0, QEI0])]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        logger::spawn().unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 1)]
+    async fn logger(_: logger::Context) {
+        hprintln!("logger - start");
+        control::spawn().unwrap();
+        hprintln!("logger - end");
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+
+    #[task(priority = env!("CONTROL_PRIORITY", 2))]
+    async fn control(_: control::Context) {
+        hprintln!(" control");
+    }
+}
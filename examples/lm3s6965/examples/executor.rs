@@ -0,0 +1,91 @@
+//! examples/executor.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use core::cell::Cell;
+use cortex_m::interrupt::{self, Mutex};
+use panic_semihosting as _;
+use rtic::app;
+
+/// Polls the ready tasks earliest deadline first
+pub struct Edf {
+    ready: Mutex<Cell<u32>>,
+    /// Relative deadline of each task, by `EXECUTOR_INDEX`: `bar`, `baz`, `foo`
+    deadlines: [u32; 3],
+}
+
+impl rtic::dispatcher::Executor for Edf {
+    type Pass = core::iter::Take<core::array::IntoIter<usize, 3>>;
+
+    fn wake(&self, index: usize) {
+        interrupt::free(|cs| {
+            let ready = self.ready.borrow(cs);
+            ready.set(ready.get() | 1 << index);
+        });
+    }
+
+    fn poll_ready(&self) -> Self::Pass {
+        let ready = interrupt::free(|cs| self.ready.borrow(cs).replace(0));
+
+        let mut tasks = [0; 3];
+        let mut n = 0;
+        for index in 0..3 {
+            if ready & 1 << index != 0 {
+                tasks[n] = index;
+                n += 1;
+            }
+        }
+        tasks[..n].sort_unstable_by_key(|index| self.deadlines[*index]);
+
+        tasks.into_iter().take(n)
+    }
+
+    fn is_empty(&self) -> bool {
+        interrupt::free(|cs| self.ready.borrow(cs).get() == 0)
+    }
+}
+
+static EDF: Edf = Edf {
+    ready: Mutex::new(Cell::new(0)),
+    deadlines: [20, 30, 10],
+};
+
+#[app(device = lm3s6965, dispatchers = [#[executor(crate::EDF)] SSI0])]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        bar::spawn().unwrap();
+        baz::spawn().unwrap();
+        foo::spawn().unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 1)]
+    async fn foo(_: foo::Context) {
+        hprintln!("foo, deadline 10");
+    }
+
+    #[task(priority = 1)]
+    async fn bar(_: bar::Context) {
+        hprintln!("bar, deadline 20");
+    }
+
+    #[task(priority = 1)]
+    async fn baz(_: baz::Context) {
+        hprintln!("baz, deadline 30");
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+}
@@ -0,0 +1,69 @@
+//! examples/handoff.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0])]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use rtic_sync::handoff::Handoff;
+
+    /// Stands in for a UART driver of a HAL
+    pub struct Uart {
+        sent: u32,
+    }
+
+    impl Uart {
+        fn send(&mut self, what: &str) {
+            self.sent += 1;
+            hprintln!("{} (message {})", what, self.sent);
+        }
+    }
+
+    static UART: Handoff<Uart> = Handoff::new();
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {
+        uart: Option<Uart>,
+    }
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        application::spawn().unwrap();
+        bootloader::spawn().unwrap();
+
+        (
+            Shared {},
+            Local {
+                uart: Some(Uart { sent: 0 }),
+            },
+        )
+    }
+
+    // The application owns the UART once the bootloader is done
+    #[task]
+    async fn application(_: application::Context) {
+        hprintln!("application: waiting for the UART");
+        let mut uart = UART.take().await.unwrap();
+        uart.send("application: started");
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+
+    // The bootloader protocol owns the UART first
+    #[task(local = [uart])]
+    async fn bootloader(cx: bootloader::Context) {
+        let mut uart = cx.local.uart.take().unwrap();
+        uart.send("bootloader: no update");
+
+        UART.give(uart).ok().unwrap();
+    }
+}
@@ -0,0 +1,59 @@
+//! examples/interrupt_storm.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0], peripherals = true)]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+    use rtic_monotonics::systick::prelude::*;
+
+    systick_monotonic!(Mono, 100);
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        Mono::start(cx.core.SYST, 12_000_000);
+
+        rtic::pend(Interrupt::UART0);
+
+        (Shared {}, Local {})
+    }
+
+    // The line is stuck for the first 8 interrupts, more than 5 within 100 ms mask it for 500 ms
+    #[task(
+        binds = UART0,
+        priority = 2,
+        local = [runs: u32 = 0],
+        storm = (limit = 5, window = 100.millis(), cooldown = 500.millis(), notify = storm),
+    )]
+    fn uart0(cx: uart0::Context) {
+        *cx.local.runs += 1;
+        hprintln!("UART0 {}", cx.local.runs);
+
+        if *cx.local.runs < 8 {
+            rtic::pend(Interrupt::UART0);
+        } else {
+            debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+        }
+    }
+
+    #[task(priority = 1)]
+    async fn storm(_: storm::Context) {
+        hprintln!("interrupt storm, UART0 masked");
+
+        // Still asserted, it fires again once unmasked
+        rtic::pend(Interrupt::UART0);
+    }
+}
@@ -0,0 +1,60 @@
+//! examples/log.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0, QEI0])]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        rtic::log!("init");
+        low::spawn().unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        rtic::log::drain(|record| {
+            hprintln!(
+                "[{}] {}{}",
+                record.priority,
+                record.message(),
+                if record.truncated { "..." } else { "" }
+            );
+        });
+        hprintln!("dropped {}", rtic::log::dropped());
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+
+        loop {
+            cortex_m::asm::nop();
+        }
+    }
+
+    #[task(priority = 1)]
+    async fn low(_: low::Context) {
+        let reading = 1234;
+        rtic::log!("low: reading {reading}");
+        high::spawn(reading * 2).unwrap();
+        rtic::log!("low: {}", "a message longer than the sixty-four bytes of a single log record");
+    }
+
+    #[task(priority = 2)]
+    async fn high(_: high::Context, value: u32) {
+        rtic::log!("high: {} preempts low", value);
+    }
+}
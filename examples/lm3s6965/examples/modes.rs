@@ -0,0 +1,77 @@
+//! examples/modes.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(
+    device = lm3s6965,
+    dispatchers = [SSI0, QEI0],
+    modes = [Init, Run, LowPower],
+)]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+    use rtic_monotonics::systick::prelude::*;
+
+    systick_monotonic!(Mono, 1_000);
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        Mono::start(cx.core.SYST, 12_000_000);
+
+        supervisor::spawn().unwrap();
+        sampler::spawn().unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 2)]
+    async fn supervisor(_: supervisor::Context) {
+        hprintln!("{:?}", mode());
+        // Masked, runs once the application is in `Run`
+        rtic::pend(Interrupt::UART0);
+
+        hprintln!("-> Run");
+        set_mode(Mode::Run);
+        Mono::delay(25.millis()).await;
+
+        hprintln!("-> LowPower");
+        set_mode(Mode::LowPower);
+        rtic::pend(Interrupt::UART0);
+        Mono::delay(30.millis()).await;
+
+        hprintln!("-> Run");
+        set_mode(Mode::Run);
+        Mono::delay(5.millis()).await;
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+
+    // Paused outside of `Run`
+    #[task(priority = 1, modes = [Run])]
+    async fn sampler(_: sampler::Context) {
+        let mut n = 0;
+        loop {
+            hprintln!("sample {}", n);
+            n += 1;
+            Mono::delay(10.millis()).await;
+        }
+    }
+
+    // Masked outside of `Run`
+    #[task(binds = UART0, priority = 3, modes = [Run])]
+    fn uart0(_: uart0::Context) {
+        hprintln!("uart0");
+    }
+}
@@ -0,0 +1,51 @@
+//! examples/nesting_limit.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+use rtic::app;
+
+#[app(device = lm3s6965, dispatchers = [SSI0, QEI0], max_nesting = 2)]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        rtic::nesting::set_hook(|depth| {
+            hprintln!("   nesting depth {} over budget", depth);
+        });
+
+        foo::spawn().unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 1)]
+    async fn foo(_: foo::Context) {
+        hprintln!("foo - depth {}", rtic::nesting::depth());
+        rtic::pend(lm3s6965::Interrupt::UART0);
+        hprintln!("max depth {}", rtic::nesting::max_depth());
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+
+    #[task(priority = 3)]
+    async fn bar(_: bar::Context) {
+        hprintln!("  bar - depth {}", rtic::nesting::depth());
+    }
+
+    #[task(binds = UART0, priority = 2)]
+    fn uart0(_: uart0::Context) {
+        hprintln!(" uart0 - depth {}", rtic::nesting::depth());
+        bar::spawn().unwrap();
+    }
+}
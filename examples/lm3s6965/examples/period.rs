@@ -0,0 +1,52 @@
+//! examples/period.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0, QEI0], peripherals = true)]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use rtic_monotonics::systick::prelude::*;
+
+    systick_monotonic!(Mono, 1000);
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        Mono::start(cx.core.SYST, 12_000_000);
+
+        sample::spawn().unwrap();
+
+        (Shared {}, Local {})
+    }
+
+    // The body runs every period, from the spawn on
+    #[task(priority = 1, period = 100.millis(), local = [n: u32 = 0])]
+    async fn sample(cx: sample::Context) {
+        *cx.local.n += 1;
+        hprintln!("sample {} every {} ms", cx.local.n, sample::period().to_millis());
+
+        match *cx.local.n {
+            3 => command::spawn(20).unwrap(),
+            6 => debug::exit(debug::EXIT_SUCCESS), // Exit QEMU simulator
+            _ => {}
+        }
+    }
+
+    // E.g. a command received on a serial port
+    #[task(priority = 2)]
+    async fn command(_: command::Context, rate_hz: u32) {
+        hprintln!("command: sample at {} Hz", rate_hz);
+        sample::set_period((1000 / rate_hz).millis());
+    }
+}
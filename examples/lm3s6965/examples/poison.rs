@@ -0,0 +1,54 @@
+//! examples/poison.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965)]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {
+        #[poison] // <- track locks that do not complete
+        counter: u64,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        rtic::pend(Interrupt::UART0);
+
+        (Shared { counter: 0 }, Local {})
+    }
+
+    #[task(binds = UART0, shared = [counter])]
+    fn foo(mut c: foo::Context) {
+        if c.shared.counter.is_poisoned() {
+            // A lock was left half way, e.g. by a panic in a handler that does not halt:
+            // bring the value back to a consistent state before using it
+            c.shared.counter.lock(|counter| *counter = 0);
+            c.shared.counter.clear_poison();
+        }
+
+        let counter = c.shared.counter.lock(|counter| {
+            *counter += 1;
+            *counter
+        });
+
+        hprintln!(
+            "counter = {}, poisoned = {}",
+            counter,
+            c.shared.counter.is_poisoned()
+        );
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+}
@@ -0,0 +1,45 @@
+//! examples/relocate_vectors.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+// As if launched by a bootloader, the vector table of the application is copied to RAM and
+// `VTOR` points at the copy before `init`
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0], relocate_vectors = true)]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        // The RAM of the LM3S6965 starts at 0x2000_0000
+        hprintln!("vector table in RAM: {}", cx.core.SCB.vtor.read() >= 0x2000_0000);
+        rtic::pend(Interrupt::UART0);
+
+        (Shared {}, Local {})
+    }
+
+    #[task(binds = UART0)]
+    fn uart0(_: uart0::Context) {
+        hprintln!("UART0");
+        foo::spawn().unwrap();
+    }
+
+    #[task]
+    async fn foo(_: foo::Context) {
+        hprintln!("foo");
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+}
@@ -0,0 +1,93 @@
+//! examples/sd_log.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0, QEI0])]
+mod app {
+    use core::convert::Infallible;
+    use cortex_m_semihosting::{debug, hprint, hprintln};
+    use lm3s6965::Interrupt;
+    use rtic_sync::sdlog::{Card, SdLog};
+
+    /// Stands in for a log file opened with `embedded-sdmmc`
+    pub struct LogFile {
+        written: usize,
+    }
+
+    impl Card for LogFile {
+        type Error = Infallible;
+
+        fn open(&mut self) -> Result<(), Infallible> {
+            hprintln!("card mounted");
+            Ok(())
+        }
+
+        fn write(&mut self, bytes: &[u8]) -> Result<(), Infallible> {
+            hprint!("{}", core::str::from_utf8(bytes).unwrap());
+            self.written += bytes.len();
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), Infallible> {
+            hprintln!("flushed {} bytes", self.written);
+            debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+            Ok(())
+        }
+
+        fn close(&mut self) {
+            hprintln!("card removed");
+        }
+    }
+
+    static LOG: SdLog<256> = SdLog::new();
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {
+        card: LogFile,
+    }
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        writer::spawn().unwrap();
+        sample::spawn().unwrap();
+
+        // The card is inserted
+        rtic::pend(Interrupt::GPIOA);
+
+        (
+            Shared {},
+            Local {
+                card: LogFile { written: 0 },
+            },
+        )
+    }
+
+    // Reads the card detect pin on a real board
+    #[task(binds = GPIOA, priority = 3)]
+    fn card_detect(_: card_detect::Context) {
+        LOG.detect(true);
+    }
+
+    // Never blocks on the card
+    #[task(priority = 2)]
+    async fn sample(_: sample::Context) {
+        LOG.log(b"sample 1\n").unwrap();
+        LOG.log(b"sample 2\n").unwrap();
+        LOG.log(b"sample 3\n").unwrap();
+    }
+
+    // Writes behind the other tasks
+    #[task(priority = 1, local = [card])]
+    async fn writer(cx: writer::Context) {
+        match LOG.run(cx.local.card).await {}
+    }
+}
@@ -0,0 +1,66 @@
+//! examples/set_priority.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965)]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {
+        // Ceiling 2, `foo` can be moved to priority 2 at most
+        counter: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (Shared { counter: 0 }, Local {})
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        rtic::pend(Interrupt::UART0);
+
+        hprintln!("{:?}", set_priority(Interrupt::UART0, 3));
+        hprintln!("{:?}", set_priority(Interrupt::UART0, 2));
+
+        // `bar` no longer preempts `foo`
+        rtic::pend(Interrupt::UART0);
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+
+        loop {
+            cortex_m::asm::nop();
+        }
+    }
+
+    #[task(binds = UART0, priority = 1, shared = [counter])]
+    fn foo(mut cx: foo::Context) {
+        hprintln!("foo - start");
+
+        rtic::pend(Interrupt::UART1);
+        cx.shared.counter.lock(|counter| *counter += 1);
+
+        hprintln!("foo - end");
+    }
+
+    #[task(binds = UART1, priority = 2, shared = [counter])]
+    fn bar(mut cx: bar::Context) {
+        let counter = cx.shared.counter.lock(|counter| {
+            *counter += 1;
+            *counter
+        });
+
+        hprintln!(" bar - counter = {}", counter);
+    }
+}
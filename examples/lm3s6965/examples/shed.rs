@@ -0,0 +1,63 @@
+//! examples/shed.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0])]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        rtic::pend(Interrupt::UART0);
+
+        (Shared {}, Local {})
+    }
+
+    // Simulates a burst of events overloading the dispatcher at priority 1
+    #[task(binds = UART0, priority = 2)]
+    fn uart0(_: uart0::Context) {
+        control::spawn(1).unwrap();
+
+        // One task is ready, the backlog is not above 1 yet
+        telemetry::spawn(2).unwrap();
+
+        // Two tasks are ready, the spawn is dropped
+        log::spawn(3).unwrap();
+
+        hprintln!(
+            "uart0: telemetry shed {}, log shed {}",
+            telemetry::shed_count(),
+            log::shed_count()
+        );
+    }
+
+    #[task(priority = 1)]
+    async fn control(_: control::Context, value: u32) {
+        hprintln!("control {}", value);
+    }
+
+    #[task(priority = 1, shed = 1)]
+    async fn telemetry(_: telemetry::Context, value: u32) {
+        hprintln!("telemetry {}", value);
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+
+    #[task(priority = 1, shed = 1)]
+    async fn log(_: log::Context, value: u32) {
+        hprintln!("log {}", value);
+    }
+}
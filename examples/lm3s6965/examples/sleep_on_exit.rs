@@ -0,0 +1,43 @@
+//! examples/sleep_on_exit.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+// No `idle`: once `init` returns the core only wakes up to run the tasks
+#[rtic::app(device = lm3s6965, sleep_on_exit = true)]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        hprintln!("init");
+        rtic::pend(Interrupt::UART0);
+
+        (Shared {}, Local {})
+    }
+
+    #[task(binds = UART0, local = [times: u32 = 0])]
+    fn uart0(cx: uart0::Context) {
+        *cx.local.times += 1;
+        hprintln!("UART0 called {} time(s)", cx.local.times);
+
+        if *cx.local.times == 3 {
+            debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+        } else {
+            // Stands in for the next interrupt of the peripheral
+            rtic::pend(Interrupt::UART0);
+        }
+    }
+}
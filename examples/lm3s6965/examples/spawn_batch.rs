@@ -0,0 +1,47 @@
+//! examples/spawn_batch.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0])]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        rtic::pend(Interrupt::UART0);
+
+        (Shared {}, Local {})
+    }
+
+    // Simulates a DMA half/full complete interrupt delivering a burst of samples
+    #[task(binds = UART0, priority = 2)]
+    fn dma(_: dma::Context) {
+        let mut samples = [10u16, 11, 12, 13, 14, 15].into_iter();
+
+        // Room for the running instance plus 4 queued payloads
+        let taken = process::spawn_batch(&mut samples);
+        hprintln!("dma: {} samples taken, {} left", taken, samples.len());
+    }
+
+    #[task(priority = 1, batch = 4)]
+    async fn process(_: process::Context, sample: u16) {
+        hprintln!("process {}", sample);
+
+        if sample == 14 {
+            debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+        }
+    }
+}
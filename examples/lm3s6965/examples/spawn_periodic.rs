@@ -0,0 +1,44 @@
+//! examples/spawn_periodic.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0], peripherals = true)]
+mod app {
+    use core::sync::atomic::{AtomicU8, Ordering};
+    use cortex_m_semihosting::{debug, hprintln};
+    use rtic_monotonics::systick::prelude::*;
+
+    systick_monotonic!(Mono, 100);
+
+    static SLOW_RUNS: AtomicU8 = AtomicU8::new(0);
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        Mono::start(cx.core.SYST, 12_000_000);
+
+        spawn_periodic!(Mono, 100.millis(), || hprintln!("fast"));
+        spawn_periodic!(250.millis(), || {
+            let runs = SLOW_RUNS.load(Ordering::Relaxed) + 1;
+            SLOW_RUNS.store(runs, Ordering::Relaxed);
+            hprintln!("slow {}", runs);
+
+            if runs == 2 {
+                debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+            }
+        });
+
+        (Shared {}, Local {})
+    }
+}
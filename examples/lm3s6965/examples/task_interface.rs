@@ -0,0 +1,68 @@
+//! examples/task_interface.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+/// Stands in for a driver crate, which knows nothing of the application
+pub mod driver {
+    /// The tasks the driver spawns in the application
+    #[rtic::task_interface]
+    pub trait Events {
+        /// A frame of `len` bytes was received
+        fn received(len: usize);
+        /// The line went idle
+        fn line_idle();
+    }
+
+    /// Handles the interrupt of the peripheral, spawning the tasks of the application `E`
+    pub fn on_interrupt<E: Events>(len: usize) {
+        if len > 0 {
+            E::received(len).ok();
+        } else {
+            E::line_idle().ok();
+        }
+    }
+}
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0])]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        rtic::pend(Interrupt::UART0);
+
+        (Shared {}, Local {})
+    }
+
+    #[task(binds = UART0)]
+    fn uart0(_: uart0::Context) {
+        // The driver calls back into the tasks below
+        crate::driver::on_interrupt::<Tasks>(16);
+        crate::driver::on_interrupt::<Tasks>(0);
+    }
+
+    #[task(implements = crate::driver::Events)]
+    async fn received(_: received::Context, len: usize) {
+        hprintln!("received {} bytes", len);
+    }
+
+    #[task(implements = crate::driver::Events)]
+    async fn line_idle(_: line_idle::Context) {
+        hprintln!("idle line");
+
+        debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+    }
+}
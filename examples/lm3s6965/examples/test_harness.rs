@@ -0,0 +1,60 @@
+//! examples/test_harness.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0, QEI0], test)]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use rtic::harness::Event;
+
+    #[shared]
+    struct Shared {
+        counter: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        rtic::harness::set_reporter(|event| match event {
+            Event::Start { name, index, total } => {
+                hprintln!("test {}/{}: {}", index + 1, total, name)
+            }
+            Event::Pass { name } => hprintln!("{} ... ok", name),
+            Event::Finished { total } => {
+                hprintln!("{} tests passed", total);
+                debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+            }
+        });
+
+        (Shared { counter: 0 }, Local {})
+    }
+
+    #[test_case]
+    #[task(priority = 1, shared = [counter])]
+    async fn increments(mut cx: increments::Context) {
+        helper::spawn().unwrap();
+
+        // `helper` preempts the test case
+        cx.shared.counter.lock(|counter| assert_eq!(*counter, 1));
+    }
+
+    #[test_case]
+    #[task(priority = 2, shared = [counter])]
+    async fn resets(mut cx: resets::Context) {
+        cx.shared.counter.lock(|counter| *counter = 0);
+        cx.shared.counter.lock(|counter| assert_eq!(*counter, 0));
+    }
+
+    #[task(priority = 2, shared = [counter])]
+    async fn helper(mut cx: helper::Context) {
+        cx.shared.counter.lock(|counter| *counter += 1);
+    }
+}
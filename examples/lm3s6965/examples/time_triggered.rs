@@ -0,0 +1,63 @@
+//! examples/time_triggered.rs
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+#[rtic::app(
+    device = lm3s6965,
+    dispatchers = [SSI0, QEI0],
+    peripherals = true,
+    schedule = (
+        cycle = 100.millis(),
+        table = [(0.millis(), sample), (30.millis(), control), (60.millis(), sample)],
+    ),
+)]
+mod app {
+    use cortex_m_semihosting::{debug, hprintln};
+    use lm3s6965::Interrupt;
+    use rtic_monotonics::systick::prelude::*;
+
+    systick_monotonic!(Mono, 100);
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        Mono::start(cx.core.SYST, 12_000_000);
+
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 2)]
+    async fn sample(_: sample::Context) {
+        hprintln!("sample");
+    }
+
+    #[task(priority = 1, local = [cycles: u32 = 0])]
+    async fn control(cx: control::Context) {
+        *cx.local.cycles += 1;
+        hprintln!("control");
+
+        if *cx.local.cycles == 1 {
+            // Event-driven work runs alongside the schedule
+            rtic::pend(Interrupt::UART0);
+        } else {
+            hprintln!("overruns {}", rtic::schedule::overruns());
+            debug::exit(debug::EXIT_SUCCESS); // Exit QEMU simulator
+        }
+    }
+
+    #[task(binds = UART0, priority = 3)]
+    fn uart0(_: uart0::Context) {
+        hprintln!("event");
+    }
+}
@@ -0,0 +1,54 @@
+//! Board support of the lm3s6965 for the backend independent examples in `examples/shared`
+
+#![no_std]
+#![deny(missing_docs)]
+
+use panic_semihosting as _;
+
+pub use cortex_m_semihosting::hprintln as println;
+pub use lm3s6965 as pac;
+
+rtic_monotonics::systick_monotonic!(Mono, 100);
+
+/// Extension traits of the monotonic
+pub mod prelude {
+    pub use rtic_monotonics::systick::prelude::*;
+}
+
+/// Starts [`Mono`] from `init`, given its context
+#[macro_export]
+macro_rules! start_monotonic {
+    ($cx:expr) => {
+        $crate::Mono::start($cx.core.SYST, 12_000_000)
+    };
+}
+
+/// An `#[rtic::app]` on the lm3s6965 with `dispatchers = N`
+#[macro_export]
+macro_rules! app {
+    (dispatchers = 0; $($app:tt)*) => {
+        #[rtic::app(device = board::pac)]
+        $($app)*
+    };
+    (dispatchers = 1; $($app:tt)*) => {
+        #[rtic::app(device = board::pac, dispatchers = [SSI0])]
+        $($app)*
+    };
+    (dispatchers = 2; $($app:tt)*) => {
+        #[rtic::app(device = board::pac, dispatchers = [SSI0, QEI0])]
+        $($app)*
+    };
+    (dispatchers = 3; $($app:tt)*) => {
+        #[rtic::app(device = board::pac, dispatchers = [SSI0, QEI0, GPIOA])]
+        $($app)*
+    };
+}
+
+/// Ends the example, exiting QEMU
+pub fn exit() -> ! {
+    cortex_m_semihosting::debug::exit(cortex_m_semihosting::debug::EXIT_SUCCESS);
+
+    loop {
+        cortex_m::asm::nop();
+    }
+}
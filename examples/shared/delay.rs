@@ -0,0 +1,50 @@
+//! examples/shared/delay.rs
+//!
+//! Requires: `dispatchers = 1`, `monotonic`
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+board::app! {
+    dispatchers = 1;
+
+    mod app {
+        use board::{prelude::*, println, Mono};
+
+        #[shared]
+        struct Shared {}
+
+        #[local]
+        struct Local {}
+
+        #[init]
+        fn init(cx: init::Context) -> (Shared, Local) {
+            println!("init");
+            board::start_monotonic!(cx);
+
+            foo::spawn().unwrap();
+            bar::spawn().unwrap();
+
+            (Shared {}, Local {})
+        }
+
+        #[task(priority = 1)]
+        async fn foo(_: foo::Context) {
+            println!("foo - waiting");
+            Mono::delay(100.millis()).await;
+            println!("foo - done");
+
+            board::exit();
+        }
+
+        #[task(priority = 1)]
+        async fn bar(_: bar::Context) {
+            println!("bar - waiting");
+            Mono::delay(50.millis()).await;
+            println!("bar - done");
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! examples/shared/priorities.rs
+//!
+//! Requires: `dispatchers = 2`
+
+#![no_main]
+#![no_std]
+#![deny(warnings)]
+#![deny(unsafe_code)]
+#![deny(missing_docs)]
+
+board::app! {
+    dispatchers = 2;
+
+    mod app {
+        use board::println;
+
+        #[shared]
+        struct Shared {}
+
+        #[local]
+        struct Local {}
+
+        #[init]
+        fn init(_: init::Context) -> (Shared, Local) {
+            println!("init");
+            low::spawn().unwrap();
+
+            (Shared {}, Local {})
+        }
+
+        #[task(priority = 1)]
+        async fn low(_: low::Context) {
+            println!("low - start");
+
+            // `high` preempts `low` right away
+            high::spawn().unwrap();
+
+            println!("low - end");
+            board::exit();
+        }
+
+        #[task(priority = 2)]
+        async fn high(_: high::Context) {
+            println!(" high");
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Fault injection for robustness testing.
+//!
+//! With the `fault-injection` feature the edges of the framework consult an [`Injector`] before
+//! doing their work: a spawn of a task fails, a message sent on a channel is lost. Each task and
+//! each channel has its own, so the error handling of one path is exercised at a time, on the
+//! target or on a host backend. Timer queues delay their deadlines by a [`Random`] jitter.
+//!
+//! An injector does nothing until configured, and the counts are kept across reconfigurations:
+//!
+//! ```
+//! use rtic_common::fault::Injector;
+//!
+//! static SPAWN: Injector = Injector::new();
+//!
+//! SPAWN.fail_every(3);
+//! let failed: Vec<bool> = (0..6).map(|_| SPAWN.inject()).collect();
+//! assert_eq!(failed, [false, false, true, false, false, true]);
+//! assert_eq!(SPAWN.injected(), 2);
+//! ```
+
+use core::cell::Cell;
+use critical_section::Mutex;
+
+#[derive(Clone, Copy)]
+struct State {
+    /// Events left to fail
+    next: u32,
+    /// Fail each `every`th event, 0 to not fail
+    every: u32,
+    /// Events seen since `every` was set
+    seen: u32,
+    /// Failures injected
+    injected: u32,
+}
+
+/// Decides which events of a framework edge fail.
+pub struct Injector {
+    state: Mutex<Cell<State>>,
+}
+
+impl Default for Injector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Injector {
+    /// An injector that fails nothing.
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(Cell::new(State {
+                next: 0,
+                every: 0,
+                seen: 0,
+                injected: 0,
+            })),
+        }
+    }
+
+    fn update<R>(&self, f: impl FnOnce(&mut State) -> R) -> R {
+        critical_section::with(|cs| {
+            let cell = self.state.borrow(cs);
+            let mut state = cell.get();
+            let r = f(&mut state);
+            cell.set(state);
+            r
+        })
+    }
+
+    /// Fails the next `count` events, before any [`fail_every`](Self::fail_every).
+    pub fn fail_next(&self, count: u32) {
+        self.update(|state| state.next = count);
+    }
+
+    /// Fails every `n`th event from now on, `0` stops.
+    pub fn fail_every(&self, n: u32) {
+        self.update(|state| {
+            state.every = n;
+            state.seen = 0;
+        });
+    }
+
+    /// Stops failing events.
+    pub fn clear(&self) {
+        self.update(|state| {
+            state.next = 0;
+            state.every = 0;
+        });
+    }
+
+    /// Records an event, returns `true` if it is to fail.
+    ///
+    /// Called by the framework edge, not by the application.
+    pub fn inject(&self) -> bool {
+        self.update(|state| {
+            let fail = if state.next > 0 {
+                state.next -= 1;
+                true
+            } else if state.every > 0 {
+                state.seen += 1;
+                if state.seen == state.every {
+                    state.seen = 0;
+                    true
+                } else {
+                    false
+                }
+            } else {
+                false
+            };
+
+            if fail {
+                state.injected = state.injected.wrapping_add(1);
+            }
+
+            fail
+        })
+    }
+
+    /// The number of failures injected.
+    pub fn injected(&self) -> u32 {
+        self.update(|state| state.injected)
+    }
+}
+
+/// A xorshift generator for injected jitter, reproducible from its seed.
+pub struct Random {
+    state: Mutex<Cell<u32>>,
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Random {
+    const SEED: u32 = 0x2545_f491;
+
+    /// A generator with a fixed seed.
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(Cell::new(Self::SEED)),
+        }
+    }
+
+    /// Restarts the sequence from `seed`, `0` is replaced by the default seed.
+    pub fn seed(&self, seed: u32) {
+        let seed = if seed == 0 { Self::SEED } else { seed };
+        critical_section::with(|cs| self.state.borrow(cs).set(seed));
+    }
+
+    /// The next number of the sequence.
+    pub fn next_u32(&self) -> u32 {
+        critical_section::with(|cs| {
+            let cell = self.state.borrow(cs);
+            let mut x = cell.get();
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            cell.set(x);
+            x
+        })
+    }
+
+    /// A fraction in `0..=1_000_000` parts per million, uniform up to rounding.
+    pub fn ppm(&self) -> u32 {
+        self.next_u32() % 1_000_001
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fail_next_then_every() {
+        let injector = Injector::new();
+        assert!(!injector.inject());
+
+        injector.fail_next(2);
+        injector.fail_every(2);
+        let failed: std::vec::Vec<bool> = (0..6).map(|_| injector.inject()).collect();
+        assert_eq!(failed, [true, true, false, true, false, true]);
+        assert_eq!(injector.injected(), 4);
+
+        injector.clear();
+        assert!(!injector.inject());
+        assert_eq!(injector.injected(), 4);
+    }
+
+    #[test]
+    fn random_is_reproducible() {
+        let random = Random::new();
+        let first: std::vec::Vec<u32> = (0..4).map(|_| random.ppm()).collect();
+        assert!(first.iter().all(|ppm| *ppm <= 1_000_000));
+
+        random.seed(0);
+        let again: std::vec::Vec<u32> = (0..4).map(|_| random.ppm()).collect();
+        assert_eq!(first, again);
+    }
+}
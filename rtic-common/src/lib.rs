@@ -8,5 +8,8 @@
 extern crate std;
 
 pub mod dropper;
+#[cfg(feature = "fault-injection")]
+pub mod fault;
 pub mod wait_queue;
+pub mod wake_source;
 pub mod waker_registration;
@@ -0,0 +1,100 @@
+//! Wake source hints for instrumented executors.
+//!
+//! Primitives that wake tasks wrap the wake in [`with`], so an executor that keeps statistics
+//! can attribute the wake to its cause with [`current`]. Without the `wake-source` feature the
+//! hints compile to nothing and [`current`] is always [`WakeSource::Unknown`].
+
+#[cfg(feature = "wake-source")]
+use portable_atomic::{AtomicU8, Ordering};
+
+/// The cause of a wake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WakeSource {
+    /// The cause was not recorded.
+    Unknown = 0,
+    /// The task was spawned.
+    Spawn,
+    /// A deadline of a timer queue expired.
+    Timer,
+    /// A channel had a message or a free slot for the task.
+    Channel,
+    /// An interrupt handler, e.g. of a HAL driver.
+    Interrupt,
+    /// The task woke itself while being polled, e.g. by yielding.
+    Task,
+}
+
+impl WakeSource {
+    /// Converts back from `source as u8`.
+    pub const fn from_u8(raw: u8) -> Self {
+        match raw {
+            1 => Self::Spawn,
+            2 => Self::Timer,
+            3 => Self::Channel,
+            4 => Self::Interrupt,
+            5 => Self::Task,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[cfg(feature = "wake-source")]
+static CURRENT: AtomicU8 = AtomicU8::new(WakeSource::Unknown as u8);
+
+/// Runs `f`, attributing the wakes it performs to `source`.
+///
+/// Nesting, e.g. by a preempting interrupt handler, restores the outer source on return.
+#[inline(always)]
+pub fn with<R>(source: WakeSource, f: impl FnOnce() -> R) -> R {
+    #[cfg(feature = "wake-source")]
+    {
+        // Plain load and store suffice: anything preempting this restores the source before
+        // returning here.
+        let outer = CURRENT.load(Ordering::Relaxed);
+        CURRENT.store(source as u8, Ordering::Relaxed);
+        let r = f();
+        CURRENT.store(outer, Ordering::Relaxed);
+        r
+    }
+
+    #[cfg(not(feature = "wake-source"))]
+    {
+        let _ = source;
+        f()
+    }
+}
+
+/// The source of the wake currently in progress.
+#[inline(always)]
+pub fn current() -> WakeSource {
+    #[cfg(feature = "wake-source")]
+    {
+        WakeSource::from_u8(CURRENT.load(Ordering::Relaxed))
+    }
+
+    #[cfg(not(feature = "wake-source"))]
+    {
+        WakeSource::Unknown
+    }
+}
+
+#[cfg(all(test, feature = "wake-source"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_sources_are_restored() {
+        assert_eq!(current(), WakeSource::Unknown);
+
+        with(WakeSource::Timer, || {
+            assert_eq!(current(), WakeSource::Timer);
+            with(WakeSource::Channel, || {
+                assert_eq!(current(), WakeSource::Channel)
+            });
+            assert_eq!(current(), WakeSource::Timer);
+        });
+
+        assert_eq!(current(), WakeSource::Unknown);
+    }
+}
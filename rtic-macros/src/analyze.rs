@@ -1,11 +1,11 @@
-use core::ops;
+use core::ops::{self, RangeInclusive};
 use std::collections::{BTreeMap, BTreeSet};
 
 use crate::syntax::{
     analyze::{self, Priority},
     ast::{App, Dispatcher},
 };
-use syn::Ident;
+use syn::{parse, Ident};
 
 /// Extend the upstream `Analysis` struct with our field
 pub struct Analysis {
@@ -22,38 +22,105 @@ impl ops::Deref for Analysis {
     }
 }
 
-// Assign an interrupt to each priority level
-pub fn app(analysis: analyze::Analysis, app: &App) -> Analysis {
-    let mut available_dispatchers = app.args.dispatchers.clone();
-
-    // the set of priorities (each priority only once)
+/// Assigns a dispatcher to each priority level of the software tasks
+///
+/// The dispatchers restricted with `=> priorities` are matched first, as many levels as possible
+/// getting one of them. The remaining levels take the unrestricted dispatchers, the highest level
+/// the last one listed.
+pub fn assign_dispatchers(app: &App) -> parse::Result<BTreeMap<Priority, Ident>> {
+    // the set of priorities (each priority only once), 0 prio tasks are run in main
     let priorities = app
         .software_tasks
         .values()
         .map(|task| task.args.priority)
+        .filter(|prio| *prio > 0)
         .collect::<BTreeSet<_>>();
 
-    // map from priorities to interrupts (holding name and attributes)
-
-    let nonzero_priorities = priorities
+    let (restricted, mut unrestricted): (Vec<_>, Vec<_>) = app
+        .args
+        .dispatchers
         .iter()
-        // 0 prio tasks are run in main
-        .filter(|prio| **prio > 0);
-    assert!(
-        available_dispatchers.len() >= nonzero_priorities.clone().count(),
-        "The number of dispatchers must be equal to or greater than the number of distinct task priorities."
-    );
-    let interrupts: BTreeMap<Priority, _> = nonzero_priorities
-        .copied()
-        .rev()
-        .map(|p| {
-            (
-                p,
-                available_dispatchers
-                    .pop()
-                    // EXPECT: covered by above assertion
-                    .expect("UNREACHABLE"),
-            )
+        .partition(|(_, dispatcher)| dispatcher.priorities.is_some());
+
+    let mut assigned = BTreeMap::new();
+    for (name, dispatcher) in &restricted {
+        let range = dispatcher.priorities.as_ref().expect("UNREACHABLE");
+        augment(name, range, &priorities, &restricted, &mut assigned, &mut vec![]);
+    }
+
+    for priority in priorities.iter().rev() {
+        if assigned.contains_key(priority) {
+            continue;
+        }
+
+        let Some((name, _)) = unrestricted.pop() else {
+            let task = app
+                .software_tasks
+                .iter()
+                .find(|(_, task)| task.args.priority == *priority)
+                .map(|(name, _)| name)
+                .expect("UNREACHABLE");
+
+            return Err(parse::Error::new(
+                task.span(),
+                format!(
+                    "no dispatcher left for priority {priority}, the ones restricted with `=>` do not cover it"
+                ),
+            ));
+        };
+        assigned.insert(*priority, (*name).clone());
+    }
+
+    Ok(assigned)
+}
+
+/// Finds a level in `range` for dispatcher `name`, moving the dispatcher holding it to another
+/// level of its range if needed
+fn augment(
+    name: &Ident,
+    range: &RangeInclusive<u8>,
+    priorities: &BTreeSet<Priority>,
+    restricted: &[(&Ident, &Dispatcher)],
+    assigned: &mut BTreeMap<Priority, Ident>,
+    visited: &mut Vec<Priority>,
+) -> bool {
+    for priority in priorities.range(range.clone()) {
+        if visited.contains(priority) {
+            continue;
+        }
+        visited.push(*priority);
+
+        let moved = match assigned.get(priority).cloned() {
+            None => true,
+            Some(holder) => {
+                let (_, dispatcher) = restricted
+                    .iter()
+                    .find(|(name, _)| **name == holder)
+                    .expect("UNREACHABLE");
+                let range = dispatcher.priorities.as_ref().expect("UNREACHABLE");
+                augment(&holder, range, priorities, restricted, assigned, visited)
+            }
+        };
+
+        if moved {
+            assigned.insert(*priority, name.clone());
+            return true;
+        }
+    }
+
+    false
+}
+
+// Assign an interrupt to each priority level
+pub fn app(analysis: analyze::Analysis, app: &App) -> Analysis {
+    // map from priorities to interrupts (holding name and attributes)
+    let interrupts: BTreeMap<Priority, _> = assign_dispatchers(app)
+        // EXPECT: checked by `check::app`
+        .expect("UNREACHABLE")
+        .into_iter()
+        .map(|(priority, name)| {
+            let dispatcher = app.args.dispatchers[&name].clone();
+            (priority, (name, dispatcher))
         })
         .collect();
 
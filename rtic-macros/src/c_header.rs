@@ -0,0 +1,93 @@
+//! C header of the resources exported with `#[c_export]`, `rtic-resources-<crate>.h`
+//!
+//! Declares the getter and the setter generated for each exported resource. Both lock the
+//! resource, so C code running at or below the priority given to `#[c_export]` can use them
+//! once `init` has returned.
+
+use crate::syntax::ast::App;
+use std::fmt::Write;
+use syn::Type;
+
+/// The header, `None` if the application exports no resource
+pub fn header(app: &App) -> Option<String> {
+    let name = &app.name;
+    let guard = format!("RTIC_{}_RESOURCES_H", name.to_string().to_uppercase());
+
+    let mut declarations = String::new();
+    for (resource, res) in &app.shared_resources {
+        let Some(priority) = res.properties.c_export else {
+            continue;
+        };
+        let ty = c_type(&res.ty).expect("UNREACHABLE");
+
+        writeln!(declarations).ok();
+        let callers = if priority == 0 {
+            "priority 0".to_string()
+        } else {
+            format!("priority {priority} and below")
+        };
+        writeln!(
+            declarations,
+            "/* `{resource}`, for callers at {callers} once `init` has returned */"
+        )
+        .ok();
+        writeln!(declarations, "{ty} {name}_{resource}_get(void);").ok();
+        writeln!(declarations, "void {name}_{resource}_set({ty} value);").ok();
+    }
+
+    if declarations.is_empty() {
+        return None;
+    }
+
+    let mut s = String::new();
+    writeln!(
+        s,
+        "/* C bindings of the resources exported by the RTIC application `{name}` */"
+    )
+    .ok();
+    writeln!(s, "/* Generated by `#[rtic::app]`, changes are overwritten */").ok();
+    writeln!(s).ok();
+    writeln!(s, "#ifndef {guard}").ok();
+    writeln!(s, "#define {guard}").ok();
+    writeln!(s).ok();
+    writeln!(s, "#include <stdbool.h>").ok();
+    writeln!(s, "#include <stdint.h>").ok();
+    writeln!(s).ok();
+    writeln!(s, "#ifdef __cplusplus").ok();
+    writeln!(s, "extern \"C\" {{").ok();
+    writeln!(s, "#endif").ok();
+    s.push_str(&declarations);
+    writeln!(s).ok();
+    writeln!(s, "#ifdef __cplusplus").ok();
+    writeln!(s, "}}").ok();
+    writeln!(s, "#endif").ok();
+    writeln!(s).ok();
+    writeln!(s, "#endif").ok();
+
+    Some(s)
+}
+
+/// The C type of a resource exported with `#[c_export]`, only primitive types have one
+pub fn c_type(ty: &Type) -> Option<&'static str> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let ident = path.path.get_ident()?;
+
+    Some(match &*ident.to_string() {
+        "bool" => "bool",
+        "u8" => "uint8_t",
+        "u16" => "uint16_t",
+        "u32" => "uint32_t",
+        "u64" => "uint64_t",
+        "usize" => "uintptr_t",
+        "i8" => "int8_t",
+        "i16" => "int16_t",
+        "i32" => "int32_t",
+        "i64" => "int64_t",
+        "isize" => "intptr_t",
+        "f32" => "float",
+        "f64" => "double",
+        _ => return None,
+    })
+}
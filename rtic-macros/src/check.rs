@@ -3,5 +3,8 @@ use crate::syntax::{analyze::Analysis, ast::App};
 use syn::parse;
 
 pub fn app(app: &App, analysis: &Analysis) -> parse::Result<()> {
-    architecture_specific_analysis(app, analysis)
+    architecture_specific_analysis(app, analysis)?;
+
+    // Covered by the dispatcher count of the backend unless restricted with `=>`
+    crate::analyze::assign_dispatchers(app).map(|_| ())
 }
@@ -8,12 +8,15 @@ pub mod bindings;
 
 mod assertions;
 mod async_dispatchers;
+mod docs;
 mod extra_mods;
 mod hardware_tasks;
 mod idle;
 mod init;
+mod interfaces;
 mod local_resources;
 mod local_resources_struct;
+mod modes;
 mod module;
 mod post_init;
 mod pre_init;
@@ -39,6 +42,9 @@ pub fn app(app: &App, analysis: &Analysis) -> TokenStream2 {
     let hardware_tasks_codegen = hardware_tasks::codegen(app, analysis);
     let software_tasks_codegen = software_tasks::codegen(app, analysis);
     let async_dispatchers_codegen = async_dispatchers::codegen(app, analysis);
+    let modes_codegen = modes::codegen(app, analysis);
+    let interfaces_codegen = interfaces::codegen(app);
+    let docs = docs::codegen(app, analysis);
 
     let user_imports = &app.user_imports;
     let user_code = &app.user_code;
@@ -48,14 +54,33 @@ pub fn app(app: &App, analysis: &Analysis) -> TokenStream2 {
     let rt_err = util::rt_err_ident();
     let async_limit = bindings::async_prio_limit(app, analysis);
 
+    // Read again by the compiler, which tracks them, to rebuild when an argument changes
+    let env_vars = &app.env_vars;
+    let config_file = app.config_file.iter().map(|path| path.to_string_lossy());
+
+    let core_cfg = core_cfg(app);
+    let lp_core = &app.lp_core;
+    let lp_core = (!lp_core.is_empty()).then(|| {
+        quote!(
+            #[cfg(rtic_core = "lp")]
+            pub mod #name {
+                #(#lp_core)*
+            }
+        )
+    });
+
     quote!(
-        /// The RTIC application module
+        #(#docs)*
+        #core_cfg
         pub mod #name {
             /// Always include the device crate which contains the vector table
             use #device as #rt_err;
 
             #(#async_limit)*
 
+            #(const _: Option<&str> = option_env!(#env_vars);)*
+            #(const _: &[u8] = include_bytes!(#config_file);)*
+
             #(#user_imports)*
 
             #(#user_code)*
@@ -75,7 +100,32 @@ pub fn app(app: &App, analysis: &Analysis) -> TokenStream2 {
 
             #async_dispatchers_codegen
 
+            #modes_codegen
+
+            #interfaces_codegen
+
             #main
         }
+
+        #lp_core
     )
 }
+
+/// nRF5340: the image of the core the application is built into, see `rtic::nrf5340`
+#[cfg(feature = "nrf5340")]
+fn core_cfg(app: &App) -> TokenStream2 {
+    let core = app.args.backend.as_ref().map_or(0, |backend| backend.core).to_string();
+    quote!(#[cfg(rtic_core = #core)])
+}
+
+/// ESP32-C6: the application of the HP core, left out of the image of the LP core, see
+/// `rtic::esp32c6_lp`
+#[cfg(all(feature = "esp32c6-lp-core", not(feature = "nrf5340")))]
+fn core_cfg(_: &App) -> TokenStream2 {
+    quote!(#[cfg(not(rtic_core = "lp"))])
+}
+
+#[cfg(not(any(feature = "nrf5340", feature = "esp32c6-lp-core")))]
+fn core_cfg(_: &App) -> TokenStream2 {
+    quote!()
+}
@@ -1,13 +1,14 @@
-use crate::syntax::ast::App;
+use crate::syntax::ast::{App, TaskLocal};
 use crate::{
     analyze::Analysis,
     codegen::{
-        bindings::{async_entry, handler_config, interrupt_entry, interrupt_exit, interrupt_mod},
-        util,
+        bindings::{async_entry, handler_config, interrupt_entry, interrupt_exit},
+        modes, util,
     },
 };
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
+use syn::Ident;
 
 /// Generates task dispatchers
 pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
@@ -16,7 +17,7 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
     let interrupts = &analysis.interrupts;
 
     // Generate executor definition and priority in global scope
-    for (name, _) in app.software_tasks.iter() {
+    for (name, task) in app.software_tasks.iter() {
         let exec_name = util::internal_task_ident(name, "EXEC");
 
         items.push(quote!(
@@ -24,6 +25,48 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
             static #exec_name: rtic::export::executor::AsyncTaskExecutorPtr =
                 rtic::export::executor::AsyncTaskExecutorPtr::new();
         ));
+
+        if let Some(capacity) = task.args.batch {
+            let batch_name = util::internal_task_ident(name, "BATCH");
+            let (_, _, _, input_ty) = util::regroup_inputs(&task.inputs);
+
+            items.push(quote!(
+                #[allow(non_upper_case_globals)]
+                static #batch_name: rtic::export::batch::BatchQueue<#input_ty, #capacity> =
+                    rtic::export::batch::BatchQueue::new();
+            ));
+        }
+
+        if let Some(capacity) = task.args.cross_core {
+            let cross_core_name = util::internal_task_ident(name, "CROSS_CORE");
+            let (_, _, _, input_ty) = util::regroup_inputs(&task.inputs);
+
+            items.push(quote!(
+                #[allow(non_upper_case_globals)]
+                static #cross_core_name: rtic::export::cross_core::CrossCoreQueue<#input_ty, #capacity> =
+                    rtic::export::cross_core::CrossCoreQueue::new();
+            ));
+        }
+
+        if task.args.shed.is_some() {
+            let shed_name = util::internal_task_ident(name, "SHED");
+
+            items.push(quote!(
+                #[allow(non_upper_case_globals)]
+                static #shed_name: rtic::export::shed::Shed = rtic::export::shed::Shed::new();
+            ));
+        }
+
+        if let Some(period) = &task.args.period {
+            let period_name = util::internal_task_ident(name, "PERIOD");
+            let monotonic = &period.monotonic;
+
+            items.push(quote!(
+                #[allow(non_upper_case_globals)]
+                static #period_name: rtic::export::period::Period<<#monotonic as Monotonic>::Duration> =
+                    rtic::export::period::Period::new();
+            ));
+        }
     }
 
     for (&level, channel) in &analysis.channels {
@@ -35,16 +78,65 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
             util::zero_prio_dispatcher_ident()
         };
 
-        let pend_interrupt = if level > 0 {
-            let int_mod = interrupt_mod(app);
+        let drain = level > 0 && cfg!(feature = "drain-dispatchers");
+        let dispatching = util::dispatching_ident(level);
 
-            quote!(rtic::export::pend(#int_mod::#dispatcher_name);)
-        } else {
-            // For 0 priority tasks we don't need to pend anything
-            quote!()
-        };
+        let pend_interrupt = util::pend_dispatcher(app, analysis, level);
+
+        let ready_name = util::ready_set(analysis, level);
+        let user_executor = interrupts
+            .get(&level)
+            .is_some_and(|(_, dispatcher)| dispatcher.executor.is_some());
+        if !user_executor {
+            let words = channel.tasks.len().div_ceil(32);
+            items.push(quote!(
+                #[allow(non_upper_case_globals)]
+                static #ready_name: rtic::export::ready::ReadyBitmap<#words> =
+                    rtic::export::ready::ReadyBitmap::new();
+            ));
+        }
+
+        if channel
+            .tasks
+            .iter()
+            .any(|name| app.software_tasks[name].args.shed.is_some())
+        {
+            let backlog = util::backlog_ident(level);
+            let counts = channel.tasks.iter().map(|name| {
+                let task = &app.software_tasks[name];
+                let exec_name = util::internal_task_ident(name, "EXEC");
+                let from_ptr_n_args = util::from_ptr_n_args_ident(task.inputs.len());
+                let queued = task.args.batch.map(|_| {
+                    let batch_name = util::internal_task_ident(name, "BATCH");
+                    quote!(+ #batch_name.len())
+                });
 
-        for name in channel.tasks.iter() {
+                quote!(
+                    rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name).is_ready() as usize
+                    #queued
+                )
+            });
+
+            items.push(quote!(
+                /// The tasks of the dispatcher waiting to be polled and the payloads queued for them
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                fn #backlog() -> usize {
+                    // SAFETY: The executors are set up in `main`, before any task can spawn.
+                    unsafe { 0 #(+ #counts)* }
+                }
+            ));
+        }
+
+        // Spawns the tasks with a payload queued by the other core, see `cross_core = N`
+        let handovers = channel
+            .tasks
+            .iter()
+            .filter(|name| app.software_tasks[*name].args.cross_core.is_some())
+            .map(|name| handover(app, analysis, name, quote!()))
+            .collect::<Vec<_>>();
+
+        for (index, name) in channel.tasks.iter().enumerate() {
             let exec_name = util::internal_task_ident(name, "EXEC");
             let from_ptr_n_args =
                 util::from_ptr_n_args_ident(app.software_tasks[name].inputs.len());
@@ -53,16 +145,133 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
             // let task = &app.software_tasks[name];
             // let cfgs = &task.cfgs;
 
-            stmts.push(quote!(
-                let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name);
+            let task = &app.software_tasks[name];
+            let poll = quote!(
                 exec.poll(|| {
                     let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name);
                     exec.set_pending();
+                    rtic::dispatcher::Executor::wake(&#ready_name, #index);
                     #pend_interrupt
                 });
-            ));
+            );
+
+            // A task paused by the mode is left pending, `set_mode` marks it ready on resume
+            let active = modes::is_active(&task.args.modes, &quote!(mode()));
+
+            if task.args.batch.is_some() {
+                let batch_name = util::internal_task_ident(name, "BATCH");
+                let (_, input_tupled, input_untupled, _) = util::regroup_inputs(&task.inputs);
+
+                // Drop the queued payloads while the dispatcher is overloaded
+                let shed = task.args.shed.map(|threshold| {
+                    let backlog = util::backlog_ident(level);
+                    let shed_name = util::internal_task_ident(name, "SHED");
+                    quote!(
+                        if #backlog() > #threshold {
+                            #shed_name.record(#batch_name.clear());
+                            break;
+                        }
+                    )
+                });
+
+                // Run queued payloads back to back for as long as the task completes
+                stmts.push(quote!(
+                    #index if #active => {
+                    let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name);
+                    loop {
+                        #poll
+                        #shed
+
+                        match #batch_name.pop_if(|| exec.try_allocate()) {
+                            Some(#input_tupled) => {
+                                exec.spawn(#name(unsafe { #name::Context::new() } #(,#input_untupled)*));
+                            }
+                            None => break,
+                        }
+                    }
+                    }
+                ));
+            } else if let Some(stack) = task.args.isolated {
+                let pmp_name = util::internal_task_ident(name, "PMP");
+                let entry_name = util::internal_task_ident(name, "ENTRY");
+
+                items.push(quote!(
+                    #[allow(non_upper_case_globals)]
+                    static #pmp_name: rtic::pmp::Task<#stack> = rtic::pmp::Task::new();
+
+                    /// Polls the task in user mode
+                    #[allow(non_snake_case)]
+                    #[doc(hidden)]
+                    extern "C" fn #entry_name() {
+                        // SAFETY: The executors are set up in `main`, before any task can spawn.
+                        unsafe {
+                            let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name);
+                            #poll
+                        }
+                    }
+                ));
+
+                // The regions of the task: its executor and its local resources
+                let locals = task.args.local_resources.iter().map(|(local, task_local)| {
+                    let (cfgs, mangled_name) = match task_local {
+                        TaskLocal::External => (
+                            &app.local_resources[local].cfgs,
+                            util::static_local_resource_ident(local),
+                        ),
+                        TaskLocal::Declared(declared) => (
+                            &declared.cfgs,
+                            util::declared_static_local_resource_ident(local, name),
+                        ),
+                    };
+                    quote!(
+                        #(#cfgs)*
+                        {
+                            regions[granted] = rtic::pmp::Region::of(&#mangled_name);
+                            granted += 1;
+                        }
+                    )
+                });
+
+                stmts.push(quote!(
+                    #index if #active => {
+                        let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name);
+                        let mut regions = [rtic::pmp::Region::new(0, 0); 6];
+                        regions[0] = rtic::pmp::Region::of(&#exec_name);
+                        regions[1] = rtic::pmp::Region::of(exec);
+                        #[allow(unused_mut)]
+                        let mut granted = 2;
+                        #(#locals)*
+                        #pmp_name.run(&regions[..granted], #entry_name);
+                    }
+                ));
+            } else {
+                // The next payload of the other core is spawned once the task completes
+                let next = task
+                    .args
+                    .cross_core
+                    .map(|_| handover(app, analysis, name, pend_interrupt.clone()));
+
+                stmts.push(quote!(
+                    #index if #active => {
+                        let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name);
+                        #poll
+                        #next
+                    }
+                ));
+            }
         }
 
+        // Visits the tasks the executor picks, the built-in one takes those that were ready when
+        // the pass started in index order
+        let pass = quote!(
+            for index in rtic::dispatcher::Executor::poll_ready(&#ready_name) {
+                match index {
+                    #(#stmts)*
+                    _ => {}
+                }
+            }
+        );
+
         if level > 0 {
             let doc = format!("Interrupt handler to dispatch async tasks at priority {level}");
             let attribute = &interrupts.get(&level).expect("UNREACHABLE").1.attrs;
@@ -70,6 +279,45 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
             let exit_stmts = interrupt_exit(app, analysis);
             let async_entry_stmts = async_entry(app, analysis, dispatcher_name.clone());
             let config = handler_config(app, analysis, dispatcher_name.clone());
+            let (nesting_enter, nesting_exit) = util::nesting_tracking(app);
+
+            let start_pass = if cfg!(feature = "coalesce-wakes") {
+                let coalescer = util::coalescer_ident(level);
+                items.push(quote!(
+                    #[allow(non_upper_case_globals)]
+                    static #coalescer: rtic::export::coalesce::Coalescer =
+                        rtic::export::coalesce::Coalescer::new();
+                ));
+
+                // Requests from here on are not covered by this pass
+                quote!(#coalescer.start_pass();)
+            } else {
+                quote!()
+            };
+
+            let run = if drain {
+                items.push(quote!(
+                    #[allow(non_upper_case_globals)]
+                    static #dispatching: core::sync::atomic::AtomicBool =
+                        core::sync::atomic::AtomicBool::new(false);
+                ));
+
+                // Poll until no task is left ready, instead of relying on a pend per wake
+                quote!(
+                    loop {
+                        #dispatching.store(true, core::sync::atomic::Ordering::SeqCst);
+                        #pass
+                        #dispatching.store(false, core::sync::atomic::Ordering::SeqCst);
+
+                        if rtic::dispatcher::Executor::is_empty(&#ready_name) {
+                            break;
+                        }
+                    }
+                )
+            } else {
+                pass
+            };
+
             items.push(quote!(
                 #[allow(non_snake_case)]
                 #[doc = #doc]
@@ -83,24 +331,75 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
                     /// The priority of this interrupt handler
                     const PRIORITY: u8 = #level;
 
+                    #nesting_enter
                     rtic::export::run(PRIORITY, || {
-                        #(#stmts)*
+                        #start_pass
+                        #(#handovers)*
+                        #run
                     });
+                    #nesting_exit
 
                     #(#exit_stmts)*
                 }
             ));
         } else {
+            // Sleeps once no task is ready, the backend keeps a wake between the check and the
+            // sleep from being lost
             items.push(quote!(
                 #[allow(non_snake_case)]
                 unsafe fn #dispatcher_name() -> ! {
                     loop {
-                        #(#stmts)*
+                        #pass
+
+                        rtic::export::idle_wait(|| rtic::dispatcher::Executor::is_empty(&#ready_name));
                     }
                 }
             ));
         }
     }
 
+    if cfg!(feature = "coalesce-wakes") {
+        let (levels, coalescers): (Vec<_>, Vec<_>) = analysis
+            .channels
+            .keys()
+            .filter(|level| **level > 0)
+            .map(|level| (*level, util::coalescer_ident(*level)))
+            .unzip();
+
+        items.push(quote!(
+            /// Pend statistics of the dispatcher of the software tasks at `priority`, `None` if
+            /// there is no such dispatcher
+            pub fn coalesce_stats(priority: u8) -> Option<rtic::stats::CoalesceStats> {
+                match priority {
+                    #(#levels => Some(#coalescers.stats()),)*
+                    _ => None,
+                }
+            }
+        ));
+    }
+
     quote!(#(#items)*)
 }
+
+/// Spawns the task with the oldest payload the other core queued for it, if it is not running,
+/// followed by `then`
+fn handover(app: &App, analysis: &Analysis, name: &Ident, then: TokenStream2) -> TokenStream2 {
+    let task = &app.software_tasks[name];
+    let exec_name = util::internal_task_ident(name, "EXEC");
+    let cross_core_name = util::internal_task_ident(name, "CROSS_CORE");
+    let from_ptr_n_args = util::from_ptr_n_args_ident(task.inputs.len());
+    let (_, input_tupled, input_untupled, _) = util::regroup_inputs(&task.inputs);
+    let mark_ready = util::mark_ready(analysis, task.args.priority, name);
+
+    // SAFETY: If `try_allocate` succeeds one must call `spawn`, which we do.
+    quote!(
+        {
+            let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name);
+            if let Some(#input_tupled) = #cross_core_name.pop_if(|| exec.try_allocate()) {
+                exec.spawn(#name(unsafe { #name::Context::new() } #(,#input_untupled)*));
+                #mark_ready
+                #then
+            }
+        }
+    )
+}
@@ -4,7 +4,19 @@
     feature = "test-template",
     feature = "riscv-esp32c3",
     feature = "riscv-esp32c6",
+    feature = "riscv-esp32p4",
     feature = "riscv-slic",
+    feature = "riscv-soft-slic",
+    feature = "riscv-eclic",
+    feature = "riscv-plic",
+    feature = "xtensa-esp32s3",
+    feature = "riscv-rp2350",
+    feature = "avr",
+    feature = "null",
+    feature = "std",
+    feature = "wasm",
+    feature = "polling",
+    feature = "riscv-ch32",
 )))]
 compile_error!("No backend selected");
 
@@ -32,8 +44,80 @@ pub use esp32c6::*;
 #[cfg(feature = "riscv-esp32c6")]
 mod esp32c6;
 
+#[cfg(feature = "riscv-esp32p4")]
+pub use esp32p4::*;
+
+#[cfg(feature = "riscv-esp32p4")]
+mod esp32p4;
+
 #[cfg(feature = "riscv-slic")]
 pub use riscv_slic::*;
 
 #[cfg(feature = "riscv-slic")]
 mod riscv_slic;
+
+#[cfg(feature = "riscv-eclic")]
+pub use riscv_eclic::*;
+
+#[cfg(feature = "riscv-eclic")]
+mod riscv_eclic;
+
+#[cfg(feature = "riscv-plic")]
+pub use riscv_plic::*;
+
+#[cfg(feature = "riscv-plic")]
+mod riscv_plic;
+
+#[cfg(feature = "xtensa-esp32s3")]
+pub use esp32s3::*;
+
+#[cfg(feature = "xtensa-esp32s3")]
+mod esp32s3;
+
+#[cfg(feature = "riscv-rp2350")]
+pub use rp2350::*;
+
+#[cfg(feature = "riscv-rp2350")]
+mod rp2350;
+
+#[cfg(feature = "avr")]
+pub use avr::*;
+
+#[cfg(feature = "avr")]
+mod avr;
+
+#[cfg(feature = "null")]
+pub use null::*;
+
+#[cfg(feature = "null")]
+mod null;
+
+#[cfg(feature = "std")]
+pub use std_sim::*;
+
+#[cfg(feature = "std")]
+mod std_sim;
+
+#[cfg(feature = "wasm")]
+pub use wasm::*;
+
+#[cfg(feature = "wasm")]
+mod wasm;
+
+#[cfg(feature = "polling")]
+pub use polling::*;
+
+#[cfg(feature = "polling")]
+mod polling;
+
+#[cfg(feature = "riscv-soft-slic")]
+pub use riscv_soft_slic::*;
+
+#[cfg(feature = "riscv-soft-slic")]
+mod riscv_soft_slic;
+
+#[cfg(feature = "riscv-ch32")]
+pub use ch32::*;
+
+#[cfg(feature = "riscv-ch32")]
+mod ch32;
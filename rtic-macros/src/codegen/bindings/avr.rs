@@ -0,0 +1,321 @@
+use crate::{
+    analyze::Analysis as CodegenAnalysis,
+    codegen::util,
+    syntax::{
+        analyze::Analysis as SyntaxAnalysis,
+        ast::{App, Dispatcher},
+        backend::max_priority,
+    },
+};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use std::collections::HashSet;
+use syn::{parse, spanned::Spanned, Attribute, Ident, Path};
+
+/// The software dispatchers are numbered by a bit of a `u16`
+const MAX_DISPATCHERS: usize = 16;
+
+/// The type holding the numbers of the software dispatchers, see `extra_modules`
+fn dispatcher_numbers_ident() -> Ident {
+    util::mark_internal_name("Swi")
+}
+
+/// The crate providing the `interrupt` attribute and the chip passed to it
+///
+/// `device = avr_device::atmega328p` gives `avr_device` and `atmega328p`, `backend = [chip = ..]`
+/// overrides the chip.
+fn vector_attribute(app: &App) -> parse::Result<(Path, Ident)> {
+    let device = &app.args.device;
+    let chip = app
+        .args
+        .backend
+        .as_ref()
+        .and_then(|args| args.chip.clone())
+        .or_else(|| device.segments.last().map(|segment| segment.ident.clone()));
+
+    let mut krate = device.clone();
+    krate.segments.pop();
+    krate.segments.pop_punct();
+
+    match chip {
+        Some(chip) if !krate.segments.is_empty() => Ok((krate, chip)),
+        _ => Err(parse::Error::new(
+            device.span(),
+            "the AVR backend expects `device = avr_device::<chip>`, the handlers are registered with `avr_device::interrupt`",
+        )),
+    }
+}
+
+pub fn interrupt_ident() -> Ident {
+    let span = Span::call_site();
+    Ident::new("Interrupt", span)
+}
+
+/// The dispatchers are pended by their number, see `extra_modules`
+pub fn interrupt_mod(_app: &App) -> TokenStream2 {
+    let numbers = dispatcher_numbers_ident();
+    quote!(#numbers)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn impl_mutex(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    cfgs: &[Attribute],
+    resources_prefix: bool,
+    name: &Ident,
+    ty: &TokenStream2,
+    ceiling: u8,
+    ptr: &TokenStream2,
+) -> TokenStream2 {
+    let path = if resources_prefix {
+        quote!(shared_resources::#name)
+    } else {
+        quote!(#name)
+    };
+    quote!(
+        #(#cfgs)*
+        impl<'a> rtic::Mutex for #path<'a> {
+            type T = #ty;
+
+            #[inline(always)]
+            fn lock<RTIC_INTERNAL_R>(&mut self, f: impl FnOnce(&mut #ty) -> RTIC_INTERNAL_R) -> RTIC_INTERNAL_R {
+                /// Priority ceiling
+                const CEILING: u8 = #ceiling;
+                unsafe {
+                    rtic::export::lock(
+                        #ptr,
+                        CEILING,
+                        f,
+                    )
+                }
+            }
+        }
+    )
+}
+
+pub fn extra_assertions(_: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// AVR cannot pend an interrupt from software, the dispatchers are generated here, one per
+/// priority level of the software tasks
+pub fn pre_init_preprocessing(app: &mut App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    app.args.core = false; // There are no Cortex-M like core peripherals
+    if !app.args.dispatchers.is_empty() {
+        return Err(parse::Error::new(
+            Span::call_site(),
+            "the AVR backend dispatches in software; remove the `dispatchers` argument from `#[app]`",
+        ));
+    }
+
+    let soft_priorities = app
+        .software_tasks
+        .values()
+        .map(|task| task.args.priority)
+        .filter(|prio| *prio > 0)
+        .collect::<HashSet<_>>();
+
+    for i in 0..soft_priorities.len() {
+        let dispatcher_ident = Ident::new(&format!("__RTICDispatcher{i}"), Span::call_site());
+        app.args.dispatchers.insert(
+            dispatcher_ident,
+            Dispatcher {
+                attrs: vec![],
+                executor: None,
+                priorities: None,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+pub fn pre_init_checks(_app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// The interrupts are enabled by the peripherals, which the application configures in `init`
+pub fn pre_init_enable_interrupts(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn post_init_checks(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// The handlers run with interrupts disabled: the hardware tasks share one priority, above the
+/// software tasks
+pub fn architecture_specific_analysis(app: &App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    vector_attribute(app)?;
+
+    let max = max_priority(app.args.backend.as_ref(), Span::call_site())?;
+    let mut hardware = app.hardware_tasks.iter();
+    if let Some((_, first)) = hardware.next() {
+        let priority = first.args.priority;
+        for (name, task) in hardware {
+            if task.args.priority != priority {
+                return Err(parse::Error::new(
+                    name.span(),
+                    "AVR has no interrupt priorities, all hardware tasks must have the same priority",
+                ));
+            }
+        }
+
+        for (name, task) in &app.software_tasks {
+            if task.args.priority >= priority {
+                return Err(parse::Error::new(
+                    name.span(),
+                    format!("software tasks run below the hardware tasks on AVR, this priority must be lower than {priority}"),
+                ));
+            }
+        }
+    }
+
+    for (name, task) in &app.software_tasks {
+        if task.args.priority > max {
+            return Err(parse::Error::new(
+                name.span(),
+                format!("the AVR backend has the priorities 1..={max}, this priority is higher"),
+            ));
+        }
+    }
+
+    if app.args.dispatchers.len() > MAX_DISPATCHERS {
+        return Err(parse::Error::new(
+            Span::call_site(),
+            format!("the AVR backend dispatches at most {MAX_DISPATCHERS} priority levels of software tasks"),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn interrupt_entry(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn interrupt_exit(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn check_stack_overflow_before_init(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+) -> Vec<TokenStream2> {
+    vec![quote!(
+        // Check for stack overflow using the symbols of the avr-libc linker scripts, the stack
+        // grows down from the end of the RAM towards the statics.
+        extern "C" {
+            pub static __heap_start: u8;
+        }
+
+        if rtic::export::read_sp() as usize <= &__heap_start as *const _ as usize {
+            panic!("Stack overflow after allocating executors");
+        }
+    )]
+}
+
+pub fn async_entry(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    _dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let max = if let Some(max) = analysis.max_async_prio {
+        max
+    } else {
+        // No limit
+        max_priority(app.args.backend.as_ref(), Span::call_site()).expect("UNREACHABLE")
+    };
+
+    vec![quote!(
+        /// Holds the maximum priority level for use by async HAL drivers.
+        #[no_mangle]
+        static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
+    )]
+}
+
+pub fn handler_config(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    _dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// The priorities and numbers of the dispatchers, and the vectors of the hardware tasks
+/// registered with `avr_device::interrupt`
+pub fn extra_modules(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let (krate, chip) = vector_attribute(app).expect("UNREACHABLE");
+    let numbers = dispatcher_numbers_ident();
+
+    // Numbered by decreasing priority, the first pending one above the current priority runs
+    let mut dispatchers = analysis
+        .interrupts
+        .iter()
+        .map(|(priority, (name, _))| (*priority, name))
+        .collect::<Vec<_>>();
+    dispatchers.sort_by_key(|(priority, _)| core::cmp::Reverse(*priority));
+    let (priorities, names): (Vec<_>, Vec<_>) = dispatchers.into_iter().unzip();
+    let indices = (0..names.len() as u8).collect::<Vec<_>>();
+
+    let hardware_priority = app
+        .hardware_tasks
+        .values()
+        .map(|task| task.args.priority)
+        .next()
+        .unwrap_or_else(|| priorities.first().map_or(1, |priority| priority + 1));
+
+    let (cfgs, binds): (Vec<_>, Vec<_>) = app
+        .hardware_tasks
+        .values()
+        .map(|task| (&task.cfgs, &task.args.binds))
+        .unzip();
+
+    vec![quote!(
+        #[doc(hidden)]
+        #[no_mangle]
+        static __RTIC_AVR: rtic::export::AvrConfig = rtic::export::AvrConfig {
+            hardware_priority: #hardware_priority,
+            dispatchers: &[#((#priorities, #names as unsafe fn())),*],
+        };
+
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        struct #numbers;
+
+        #[allow(non_upper_case_globals)]
+        impl #numbers {
+            #(const #names: u8 = #indices;)*
+        }
+
+        #[doc(hidden)]
+        mod __rtic_vectors {
+            #(
+                #(#cfgs)*
+                #[#krate::interrupt(#chip)]
+                #[allow(non_snake_case)]
+                fn #binds() {
+                    unsafe { super::#binds() };
+                }
+            )*
+        }
+    )]
+}
+
+/// The attributes of the generated `main`, called by the startup code of avr-libc
+pub fn main_linkage(_app: &App) -> TokenStream2 {
+    quote!(#[no_mangle])
+}
+
+pub fn priority_ceiling(_app: &App, _binds: &Ident) -> Option<TokenStream2> {
+    None
+}
+
+pub fn max_priority_const(_app: &App) -> Option<TokenStream2> {
+    Some(quote!(rtic::export::MAX_PRIORITY))
+}
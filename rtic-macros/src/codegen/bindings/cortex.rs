@@ -1,12 +1,16 @@
 use crate::{
     analyze::Analysis as CodegenAnalysis,
     codegen::util,
-    syntax::{analyze::Analysis as SyntaxAnalysis, ast::App},
+    syntax::{
+        analyze::Analysis as SyntaxAnalysis,
+        ast::{App, TaskLocal},
+        backend::Security,
+    },
 };
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::quote;
 use std::collections::HashSet;
-use syn::{parse, Attribute, Ident};
+use syn::{parse, Attribute, Expr, ExprLit, Ident, Lit, Meta};
 
 #[cfg(feature = "cortex-m-basepri")]
 pub use basepri::*;
@@ -108,6 +112,10 @@ mod source_masking {
             mask_ids.push(quote!(#device::Interrupt::#name as u32));
         }
 
+        // Only the interrupts of the tasks that contend for the resource are masked, the other
+        // ones of priorities up to the ceiling keep running
+        let contenders = contenders(app, analysis, name);
+
         // Call rtic::export::create_mask([Mask; N]), where the array is the list of shifts
 
         let mut mask_arr = Vec::new();
@@ -124,6 +132,21 @@ mod source_masking {
             ));
         }
 
+        let mask = match contenders {
+            Some(contenders) => {
+                let ids = contenders
+                    .iter()
+                    .map(|name| quote!(#device::Interrupt::#name as u32));
+                quote!(
+                    const MASK: rtic::export::Mask<N_CHUNKS> = rtic::export::create_mask([#(#ids),*]);
+                )
+            }
+            None => quote!(
+                const MASKS: [rtic::export::Mask<N_CHUNKS>; 3] = [#(#mask_arr),*];
+                const MASK: rtic::export::Mask<N_CHUNKS> = rtic::export::ceiling_mask(CEILING, &MASKS);
+            ),
+        };
+
         quote!(
             #(#cfgs)*
             impl<'a> rtic::Mutex for #path<'a> {
@@ -134,13 +157,13 @@ mod source_masking {
                     /// Priority ceiling
                     const CEILING: u8 = #ceiling;
                     const N_CHUNKS: usize = rtic::export::compute_mask_chunks([#(#mask_ids),*]);
-                    const MASKS: [rtic::export::Mask<N_CHUNKS>; 3] = [#(#mask_arr),*];
+                    #mask
 
                     unsafe {
                         rtic::export::lock(
                             #ptr,
                             CEILING,
-                            &MASKS,
+                            MASK,
                             f,
                         )
                     }
@@ -149,6 +172,48 @@ mod source_masking {
         )
     }
 
+    /// The interrupts of the tasks accessing the resource locked through `proxy`: the hardware
+    /// tasks and the dispatchers of the software tasks
+    ///
+    /// `None` if C code exports the resource, its callers can run in any interrupt up to the
+    /// ceiling.
+    fn contenders<'a>(
+        app: &'a App,
+        analysis: &'a CodegenAnalysis,
+        proxy: &Ident,
+    ) -> Option<Vec<&'a Ident>> {
+        let (resource, res) = app.shared_resources.iter().find(|(resource, _)| {
+            util::need_to_lock_ident(resource) == *proxy
+                || util::unpoisoned_ident(resource) == *proxy
+                || util::spinlocked_ident(resource) == *proxy
+        })?;
+        if res.properties.c_export.is_some() {
+            return None;
+        }
+
+        let hardware_tasks = app
+            .hardware_tasks
+            .values()
+            .filter(|task| {
+                task.args.shared_resources.contains_key(resource) && !is_exception(&task.args.binds)
+            })
+            .map(|task| &task.args.binds);
+        let dispatchers = app
+            .software_tasks
+            .values()
+            .filter(|task| task.args.shared_resources.contains_key(resource))
+            .filter_map(|task| analysis.interrupts.get(&task.args.priority))
+            .map(|(dispatcher, _)| dispatcher);
+
+        let mut contenders = vec![];
+        for name in hardware_tasks.chain(dispatchers) {
+            if !contenders.contains(&name) {
+                contenders.push(name);
+            }
+        }
+        Some(contenders)
+    }
+
     pub fn extra_assertions(_: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
         vec![]
     }
@@ -177,6 +242,19 @@ mod basepri {
         };
 
         let device = &app.args.device;
+
+        // A gateway also masks the interrupts of the non-secure image up to the ceiling
+        let gateway = app
+            .args
+            .backend
+            .as_ref()
+            .is_some_and(|backend| backend.gateways.contains(name));
+        let lock = if gateway {
+            quote!(lock_gateway)
+        } else {
+            quote!(lock)
+        };
+
         quote!(
             #(#cfgs)*
             impl<'a> rtic::Mutex for #path<'a> {
@@ -188,7 +266,7 @@ mod basepri {
                     const CEILING: u8 = #ceiling;
 
                     unsafe {
-                        rtic::export::lock(
+                        rtic::export::#lock(
                             #ptr,
                             CEILING,
                             #device::NVIC_PRIO_BITS,
@@ -221,6 +299,20 @@ pub fn pre_init_checks(app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
         stmts.push(quote!(let _ = #rt_err::#interrupt::#name;));
     }
 
+    // check that the declared NVIC priority bits match the device
+    if let Some(nvic_prio_bits) = app.args.backend.as_ref().and_then(|b| b.nvic_prio_bits) {
+        let device = &app.args.device;
+        let es = format!(
+            "`nvic_prio_bits = {nvic_prio_bits}` does not match the `NVIC_PRIO_BITS` of the device"
+        );
+
+        stmts.push(quote!(
+            const _: () = if #device::NVIC_PRIO_BITS as u8 != #nvic_prio_bits {
+                ::core::panic!(#es);
+            };
+        ));
+    }
+
     stmts
 }
 
@@ -232,6 +324,58 @@ pub fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<
     let device = &app.args.device;
     let nvic_prio_bits = quote!(#device::NVIC_PRIO_BITS);
     let interrupt_ids = analysis.interrupts.iter().map(|(p, (id, _))| (p, id));
+    let backend = app.args.backend.as_ref();
+    let secure = backend.and_then(|backend| backend.security) == Some(Security::Secure);
+
+    // A position independent image copies `.data` from where it runs before anything else, core 1
+    // of an RP2040 finds it copied by core 0
+    let pic = cfg!(feature = "position-independent");
+    if pic {
+        let offset = if second_core(app) {
+            quote!(rtic::export::pic::load_offset())
+        } else {
+            quote!(rtic::export::pic::fixup())
+        };
+        stmts.push(quote!(let offset = #offset;));
+    }
+
+    // Switch to a copy of the vector table of the application in RAM, a bootloader leaves `VTOR`
+    // at its own one. The one of a position independent image holds the linked addresses of the
+    // handlers, the copy moves them to the running image.
+    if app.args.relocate_vectors || pic {
+        let irqs = analysis
+            .interrupts
+            .values()
+            .map(|(id, _)| id)
+            .chain(
+                app.hardware_tasks
+                    .values()
+                    .map(|task| &task.args.binds)
+                    .filter(|binds| !is_exception(binds)),
+            )
+            .map(|name| quote!(#rt_err::#interrupt::#name as usize));
+
+        let install = if pic {
+            quote!(install_moved(LEN, offset))
+        } else {
+            quote!(install(LEN))
+        };
+
+        stmts.push(quote!(
+            {
+                const LEN: usize = rtic::export::vector_table_len(&[#(#irqs),*]);
+                static VECTORS: rtic::export::RamVectors<{ rtic::export::ram_vectors_words(LEN) }> =
+                    rtic::export::RamVectors::new();
+                VECTORS.#install;
+            }
+        ));
+    }
+
+    // ARMv8-M with TrustZone: the handlers of the non-secure image are taken from its own vector
+    // table
+    for name in backend.iter().flat_map(|backend| &backend.nonsecure_interrupts) {
+        stmts.push(quote!(rtic::export::set_target_nonsecure(#rt_err::#interrupt::#name);));
+    }
 
     // Unmask interrupts and set their priorities
     for (&priority, name) in interrupt_ids.chain(app.hardware_tasks.values().filter_map(|task| {
@@ -250,6 +394,11 @@ pub fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<
             const _: () =  if (1 << #nvic_prio_bits) < #priority as usize { ::core::panic!(#es); };
         ));
 
+        // Taken from the secure vector table, whatever the secure boot code targeted it to
+        if secure {
+            stmts.push(quote!(rtic::export::set_target_secure(#rt_err::#interrupt::#name);));
+        }
+
         stmts.push(quote!(
             core.NVIC.set_priority(
                 #rt_err::#interrupt::#name,
@@ -287,6 +436,56 @@ pub fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<
     stmts
 }
 
+/// Checks in debug builds that the priorities set before `init` are still in place after it
+pub fn post_init_checks(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let mut stmts = vec![];
+
+    let interrupt = interrupt_ident();
+    let rt_err = util::rt_err_ident();
+    let device = &app.args.device;
+    let nvic_prio_bits = quote!(#device::NVIC_PRIO_BITS);
+    let interrupt_ids = analysis
+        .interrupts
+        .iter()
+        .map(|(p, (id, _))| (p, id, false));
+
+    // The interrupts of hardware tasks with `modes` are masked in the modes they are not active in
+    for (&priority, name, maskable) in interrupt_ids.chain(app.hardware_tasks.values().map(|task| {
+        let maskable = !task.args.modes.is_empty();
+        (&task.args.priority, &task.args.binds, maskable)
+    })) {
+        let es = format!(
+            "interrupt `{name}` was reconfigured during `init`, RTIC assumes it enabled at priority {priority}"
+        );
+
+        let (current, enabled) = if is_exception(name) {
+            (
+                quote!(rtic::export::SCB::get_priority(rtic::export::SystemHandler::#name)),
+                quote!(true),
+            )
+        } else if maskable {
+            (
+                quote!(rtic::export::NVIC::get_priority(#rt_err::#interrupt::#name)),
+                quote!(true),
+            )
+        } else {
+            (
+                quote!(rtic::export::NVIC::get_priority(#rt_err::#interrupt::#name)),
+                quote!(rtic::export::NVIC::is_enabled(#rt_err::#interrupt::#name)),
+            )
+        };
+
+        stmts.push(quote!(
+            ::core::assert!(
+                #current == rtic::export::cortex_logical2hw(#priority, #nvic_prio_bits) && #enabled,
+                #es
+            );
+        ));
+    }
+
+    stmts
+}
+
 pub fn architecture_specific_analysis(app: &App, _: &SyntaxAnalysis) -> parse::Result<()> {
     // Check that external (device-specific) interrupts are not named after known (Cortex-M)
     // exceptions
@@ -306,6 +505,14 @@ pub fn architecture_specific_analysis(app: &App, _: &SyntaxAnalysis) -> parse::R
         }
     }
 
+    // The test cases are listed in a table of the linked addresses of their functions
+    if cfg!(feature = "position-independent") && app.args.test {
+        return Err(parse::Error::new(
+            app.name.span(),
+            "`#[app(test)]` is not supported with the `position-independent` feature, the test cases are called through the addresses the image was linked at",
+        ));
+    }
+
     // Check that there are enough external interrupts to dispatch the software tasks and the timer
     // queue handler
     let mut first = None;
@@ -348,6 +555,223 @@ pub fn architecture_specific_analysis(app: &App, _: &SyntaxAnalysis) -> parse::R
 
             _ => {}
         }
+
+        if !task.args.modes.is_empty() && is_exception(&task.args.binds) {
+            return Err(parse::Error::new(
+                task.args.binds.span(),
+                "exceptions can not be masked, only hardware tasks bound to interrupts can have `modes`",
+            ));
+        }
+    }
+
+    // With `SCR.SLEEPONEXIT` the core never returns to thread mode after `init`
+    if app.args.sleep_on_exit {
+        if let Some(idle) = &app.idle {
+            return Err(parse::Error::new(
+                idle.name.span(),
+                "`idle` never runs with `sleep_on_exit`, remove it",
+            ));
+        }
+
+        if let Some((name, _)) = app
+            .software_tasks
+            .iter()
+            .find(|(_, task)| task.args.priority == 0)
+        {
+            return Err(parse::Error::new(
+                name.span(),
+                "priority 0 tasks are run from thread mode, which `sleep_on_exit` never returns to",
+            ));
+        }
+    }
+
+    check_nonsecure_placement(app)?;
+    check_security_boundary(app)
+}
+
+/// The section of a `#[link_section = ".."]` attribute
+fn link_section(attrs: &[Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| match &attr.meta {
+        Meta::NameValue(nv) if nv.path.is_ident("link_section") => match &nv.value {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(s), ..
+            }) => Some(s.value()),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// ARMv8-M with TrustZone: resources of non-secure tasks must be placed in non-secure RAM,
+/// otherwise the first access raises a SecureFault
+fn check_nonsecure_placement(app: &App) -> parse::Result<()> {
+    let Some(backend) = &app.args.backend else {
+        return Ok(());
+    };
+
+    let nonsecure = backend
+        .nonsecure_sections
+        .iter()
+        .map(|s| s.value())
+        .collect::<HashSet<_>>();
+    let placement = |name: &Ident, task: &Ident, attrs: &[Attribute]| match link_section(attrs) {
+        Some(section) if nonsecure.contains(&section) => Ok(()),
+        section => {
+            let place = match section {
+                Some(section) => format!("section `{section}`"),
+                None => "the default data section".to_string(),
+            };
+            Err(parse::Error::new(
+                name.span(),
+                format!(
+                    "`{name}` is accessed by the non-secure task `{task}` but lands in secure RAM \
+                     ({place}); place it in one of the `nonsecure_sections` with \
+                     `#[link_section = \"..\"]`"
+                ),
+            ))
+        }
+    };
+
+    for task in &backend.nonsecure_tasks {
+        let (shared, local) = if let Some(t) = app.hardware_tasks.get(task) {
+            (&t.args.shared_resources, &t.args.local_resources)
+        } else if let Some(t) = app.software_tasks.get(task) {
+            (&t.args.shared_resources, &t.args.local_resources)
+        } else {
+            return Err(parse::Error::new(
+                task.span(),
+                "this task has NOT been declared",
+            ));
+        };
+
+        for name in shared.keys() {
+            if let Some(resource) = app.shared_resources.get(name) {
+                placement(name, task, &resource.attrs)?;
+            }
+        }
+
+        for (name, local) in local {
+            match local {
+                TaskLocal::External => {
+                    if let Some(resource) = app.local_resources.get(name) {
+                        placement(name, task, &resource.attrs)?;
+                    }
+                }
+                TaskLocal::Declared(local) => placement(name, task, &local.attrs)?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// ARMv8-M with TrustZone: the interrupts of the non-secure image are not handled by the
+/// application, and resources only cross the security boundary through a gateway
+fn check_security_boundary(app: &App) -> parse::Result<()> {
+    let Some(backend) = &app.args.backend else {
+        return Ok(());
+    };
+
+    if let Some(security) = backend.security {
+        if !cfg!(feature = "cortex-m-basepri") {
+            return Err(parse::Error::new(
+                Span::call_site(),
+                "`security` partitions an ARMv8-M Mainline core, it needs the `thumbv8main-backend`",
+            ));
+        }
+
+        if security == Security::NonSecure {
+            let secure_only = [
+                ("nonsecure_sections", backend.nonsecure_sections.is_empty()),
+                ("nonsecure_tasks", backend.nonsecure_tasks.is_empty()),
+                ("nonsecure_interrupts", backend.nonsecure_interrupts.is_empty()),
+                ("gateways", backend.gateways.is_empty()),
+            ];
+            if let Some((arg, _)) = secure_only.iter().find(|(_, empty)| !empty) {
+                return Err(parse::Error::new(
+                    Span::call_site(),
+                    format!("`{arg}` partitions the secure image, the non-secure one cannot use it"),
+                ));
+            }
+        }
+    }
+
+    if !backend.nonsecure_interrupts.is_empty() && backend.security != Some(Security::Secure) {
+        return Err(parse::Error::new(
+            backend.nonsecure_interrupts[0].span(),
+            "only the secure image hands interrupts to the non-secure one, add `security = secure`",
+        ));
+    }
+
+    for interrupt in &backend.nonsecure_interrupts {
+        let bound = app
+            .hardware_tasks
+            .values()
+            .any(|task| task.args.binds == *interrupt);
+        if bound || app.args.dispatchers.contains_key(interrupt) {
+            return Err(parse::Error::new(
+                interrupt.span(),
+                format!("`{interrupt}` is handed to the non-secure image, the secure application cannot handle it"),
+            ));
+        }
+    }
+
+    if !backend.gateways.is_empty() && !cfg!(feature = "cortex-m-basepri") {
+        return Err(parse::Error::new(
+            backend.gateways[0].span(),
+            "gateways are locked with `BASEPRI_NS`, they need the `thumbv8main-backend`",
+        ));
+    }
+
+    for gateway in &backend.gateways {
+        if !app.shared_resources.contains_key(gateway) {
+            return Err(parse::Error::new(
+                gateway.span(),
+                "only shared resources can be gateways",
+            ));
+        }
+    }
+
+    // The shared resources of every context, named by their task
+    let accesses = app
+        .idle
+        .iter()
+        .map(|idle| (&idle.name, &idle.args.shared_resources))
+        .chain(
+            app.hardware_tasks
+                .iter()
+                .map(|(name, task)| (name, &task.args.shared_resources)),
+        )
+        .chain(
+            app.software_tasks
+                .iter()
+                .map(|(name, task)| (name, &task.args.shared_resources)),
+        )
+        .collect::<Vec<_>>();
+
+    for (nonsecure, shared) in accesses
+        .iter()
+        .filter(|(name, _)| backend.nonsecure_tasks.contains(name))
+    {
+        for resource in shared.keys() {
+            if backend.gateways.contains(resource) {
+                continue;
+            }
+
+            let secure = accesses.iter().find(|(name, shared)| {
+                !backend.nonsecure_tasks.contains(name) && shared.contains_key(resource)
+            });
+            if let Some((secure, _)) = secure {
+                return Err(parse::Error::new(
+                    resource.span(),
+                    format!(
+                        "`{resource}` is shared between the non-secure task `{nonsecure}` and the \
+                         secure task `{secure}`; declare it in `gateways` to lock it against both \
+                         security states"
+                    ),
+                ));
+            }
+        }
     }
 
     Ok(())
@@ -370,6 +794,11 @@ pub fn async_entry(
 }
 
 pub fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    // Provided by the application of core 0
+    if second_core(app) {
+        return vec![];
+    }
+
     let max = if let Some(max) = analysis.max_async_prio {
         quote!(#max)
     } else {
@@ -392,6 +821,65 @@ pub fn handler_config(
     vec![]
 }
 
-pub fn extra_modules(_app: &App, _analysis: &SyntaxAnalysis) -> Vec<TokenStream2> {
-    vec![]
+pub fn extra_modules(app: &App, _analysis: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    // Provided by the application of core 0
+    if second_core(app) {
+        return vec![];
+    }
+
+    let device = &app.args.device;
+
+    // Backs `rtic::current_priority`, which converts NVIC priorities to logical ones
+    vec![quote!(
+        #[doc(hidden)]
+        #[no_mangle]
+        fn __rtic_nvic_prio_bits() -> u8 {
+            #device::NVIC_PRIO_BITS as u8
+        }
+    )]
+}
+
+/// The NVIC priorities of the interrupts can be changed at runtime, up to the highest one of the
+/// device, the ones of the exceptions are left fixed
+pub fn priority_ceiling(app: &App, binds: &Ident) -> Option<TokenStream2> {
+    if is_exception(binds) {
+        return None;
+    }
+
+    let device = &app.args.device;
+    Some(quote!(rtic::export::max_priority(#device::NVIC_PRIO_BITS)))
+}
+
+/// Fixed with source masking, from the NVIC priority bits with `BASEPRI`: the declared ones, else
+/// the ones of the device
+pub fn max_priority_const(app: &App) -> Option<TokenStream2> {
+    if cfg!(feature = "cortex-m-source-masking") {
+        return Some(quote!(rtic::export::MAX_PRIORITY));
+    }
+
+    let device = &app.args.device;
+    let nvic_prio_bits = match app.args.backend.as_ref().and_then(|b| b.nvic_prio_bits) {
+        Some(bits) => quote!(#bits),
+        None => quote!(#device::NVIC_PRIO_BITS),
+    };
+    Some(quote!(rtic::export::max_priority(#nvic_prio_bits)))
+}
+
+/// The application runs on the second core of an RP2040, next to the one of core 0
+///
+/// Each core of the nRF5340 has an image of its own, with its own entry point.
+fn second_core(app: &App) -> bool {
+    cfg!(feature = "rp2040") && app.args.backend.as_ref().is_some_and(|b| b.core == 1)
+}
+
+/// The attributes of the generated `main`
+///
+/// On the second core of an RP2040 `main` is not the entry point of the runtime, it is public
+/// to be launched on core 1 by the application of core 0.
+pub fn main_linkage(app: &App) -> TokenStream2 {
+    if second_core(app) {
+        quote!(pub)
+    } else {
+        quote!(#[no_mangle])
+    }
 }
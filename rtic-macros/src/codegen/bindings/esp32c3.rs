@@ -84,7 +84,6 @@ mod esp32c3 {
         let mut stmts = vec![];
         let mut curr_cpu_id: u8 = 1; //cpu interrupt id 0 is reserved
         let rt_err = util::rt_err_ident();
-        let max_prio: usize = 15; //unfortunately this is not part of pac, but we know that max prio is 15.
         let interrupt_ids = analysis.interrupts.iter().map(|(p, (id, _))| (p, id));
         // Unmask interrupts and set their priorities
         for (&priority, name) in interrupt_ids.chain(
@@ -97,7 +96,7 @@ mod esp32c3 {
             );
             // Compile time assert that this priority is supported by the device
             stmts.push(quote!(
-                const _: () =  if (#max_prio) <= #priority as usize { ::core::panic!(#es); };
+                const _: () =  if rtic::export::MAX_PRIORITY < #priority { ::core::panic!(#es); };
             ));
             stmts.push(quote!(
                 rtic::export::enable(
@@ -111,6 +110,35 @@ mod esp32c3 {
         stmts
     }
 
+    /// Checks in debug builds that the interrupts enabled before `init` are still configured the
+    /// same way after it
+    pub fn post_init_checks(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        let mut stmts = vec![];
+        let rt_err = util::rt_err_ident();
+        let interrupt_ids = analysis.interrupts.iter().map(|(p, (id, _))| (p, id));
+
+        // Same CPU interrupt assignment as in `pre_init_enable_interrupts`
+        for (cpu_int_id, (&priority, name)) in (1u8..).zip(
+            interrupt_ids.chain(
+                app.hardware_tasks
+                    .values()
+                    .map(|task| (&task.args.priority, &task.args.binds)),
+            ),
+        ) {
+            let es = format!(
+                "interrupt `{name}` was reconfigured during `init`, RTIC assumes it enabled at priority {priority}"
+            );
+            stmts.push(quote!(
+                ::core::assert!(
+                    rtic::export::is_enabled(#rt_err::Interrupt::#name, #priority, #cpu_int_id),
+                    #es
+                );
+            ));
+        }
+
+        stmts
+    }
+
     pub fn architecture_specific_analysis(
         app: &App,
         _analysis: &SyntaxAnalysis,
@@ -245,4 +273,17 @@ mod esp32c3 {
     pub fn extra_modules(_app: &App, _analysis: &SyntaxAnalysis) -> Vec<TokenStream2> {
         vec![]
     }
+
+    /// The attributes of the generated `main`, exported as the entry point of the runtime
+    pub fn main_linkage(_app: &App) -> TokenStream2 {
+        quote!(#[no_mangle])
+    }
+
+    pub fn priority_ceiling(_app: &App, _binds: &Ident) -> Option<TokenStream2> {
+        None
+    }
+
+    pub fn max_priority_const(_app: &App) -> Option<TokenStream2> {
+        Some(quote!(rtic::export::MAX_PRIORITY))
+    }
 }
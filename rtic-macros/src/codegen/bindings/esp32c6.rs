@@ -6,7 +6,10 @@ mod esp32c6 {
     use crate::{
         analyze::Analysis as CodegenAnalysis,
         codegen::util,
-        syntax::{analyze::Analysis as SyntaxAnalysis, ast::App},
+        syntax::{
+            analyze::Analysis as SyntaxAnalysis,
+            ast::{App, Trigger},
+        },
     };
     use proc_macro2::{Span, TokenStream as TokenStream2};
     use quote::quote;
@@ -80,37 +83,90 @@ mod esp32c6 {
         }
         stmts
     }
+
+    /// The CPU interrupts of the ESP32-C6, 0 is reserved
+    const CPU_INTERRUPTS: u8 = 31;
+
+    /// Maps each dispatcher and each bound interrupt, in this order, to a free CPU interrupt and
+    /// its interrupt type
+    ///
+    /// The lowest free CPU interrupt is taken first, leaving out the ones given with
+    /// `backend = [reserved = [..]]`. `architecture_specific_analysis` has checked there are
+    /// enough of them. Dispatchers and tasks without a `trigger` are edge triggered.
+    fn cpu_interrupts<'a>(
+        app: &'a App,
+        analysis: &'a CodegenAnalysis,
+    ) -> impl Iterator<Item = (u8, &'a Ident, u8, TokenStream2)> {
+        let reserved = app
+            .args
+            .backend
+            .as_ref()
+            .map(|backend| backend.reserved.as_slice())
+            .unwrap_or_default();
+        let free = (1..=CPU_INTERRUPTS).filter(move |id| !reserved.contains(id));
+
+        analysis
+            .interrupts
+            .iter()
+            .map(|(&priority, (name, _))| (priority, name, None))
+            .chain(app.hardware_tasks.values().map(|task| {
+                (task.args.priority, &task.args.binds, task.args.trigger)
+            }))
+            .zip(free)
+            .map(|((priority, name, trigger), cpu_int_id)| {
+                let trigger = match trigger {
+                    Some(Trigger::Level) => quote!(rtic::export::Trigger::Level),
+                    Some(Trigger::Edge) | None => quote!(rtic::export::Trigger::Edge),
+                };
+                (priority, name, cpu_int_id, trigger)
+            })
+    }
+
     pub fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
         let mut stmts = vec![];
-        let mut curr_cpu_id: u8 = 1; //cpu interrupt id 0 is reserved
         let rt_err = util::rt_err_ident();
-        let max_prio: usize = 15; //unfortunately this is not part of pac, but we know that max prio is 15.
-        let interrupt_ids = analysis.interrupts.iter().map(|(p, (id, _))| (p, id));
         // Unmask interrupts and set their priorities
-        for (&priority, name) in interrupt_ids.chain(
-            app.hardware_tasks
-                .values()
-                .filter_map(|task| Some((&task.args.priority, &task.args.binds))),
-        ) {
+        for (priority, name, cpu_int_id, trigger) in cpu_interrupts(app, analysis) {
             let es = format!(
                 "Maximum priority used by interrupt vector '{name}' is more than supported by hardware"
             );
             // Compile time assert that this priority is supported by the device
             stmts.push(quote!(
-                const _: () =  if (#max_prio) <= #priority as usize { ::core::panic!(#es); };
+                const _: () =  if rtic::export::MAX_PRIORITY < #priority { ::core::panic!(#es); };
             ));
             stmts.push(quote!(
                 rtic::export::enable(
                     #rt_err::Interrupt::#name,
                     #priority,
-                    #curr_cpu_id,
+                    #cpu_int_id,
+                    #trigger,
                 );
             ));
-            curr_cpu_id += 1;
         }
         stmts
     }
 
+    /// Checks in debug builds that the interrupts enabled before `init` are still configured the
+    /// same way after it
+    pub fn post_init_checks(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+        let mut stmts = vec![];
+        let rt_err = util::rt_err_ident();
+
+        for (priority, name, cpu_int_id, trigger) in cpu_interrupts(app, analysis) {
+            let es = format!(
+                "interrupt `{name}` was reconfigured during `init`, RTIC assumes it enabled at priority {priority}"
+            );
+            stmts.push(quote!(
+                ::core::assert!(
+                    rtic::export::is_enabled(#rt_err::Interrupt::#name, #priority, #cpu_int_id, #trigger),
+                    #es
+                );
+            ));
+        }
+
+        stmts
+    }
+
     pub fn architecture_specific_analysis(
         app: &App,
         _analysis: &SyntaxAnalysis,
@@ -157,6 +213,27 @@ mod esp32c6 {
             // "custom attribute panicked" due to unwrap on None
             return Err(parse::Error::new(first.unwrap().span(), s));
         }
+
+        // Check that there is a free CPU interrupt for each dispatcher and bound interrupt
+        let reserved = app
+            .args
+            .backend
+            .as_ref()
+            .map_or(0, |backend| backend.reserved.len());
+        let free = usize::from(CPU_INTERRUPTS) - reserved;
+        if need + app.hardware_tasks.len() > free {
+            let s = format!(
+                "no free CPU interrupt left for this interrupt: the {need} dispatchers and {} bound \
+                    interrupts need more than the {free} CPU interrupts not reserved",
+                app.hardware_tasks.len()
+            );
+            let span = match app.hardware_tasks.values().nth(free.saturating_sub(need)) {
+                Some(task) => task.args.binds.span(),
+                None => first.unwrap().span(),
+            };
+            return Err(parse::Error::new(span, s));
+        }
+
         Ok(())
     }
 
@@ -224,19 +301,11 @@ mod esp32c6 {
         dispatcher_name: Ident,
     ) -> Vec<TokenStream2> {
         let mut stmts = vec![];
-        let mut curr_cpu_id = 1;
-        //let mut ret = "";
-        let interrupt_ids = analysis.interrupts.iter().map(|(p, (id, _))| (p, id));
-        for (_, name) in interrupt_ids.chain(
-            app.hardware_tasks
-                .values()
-                .filter_map(|task| Some((&task.args.priority, &task.args.binds))),
-        ) {
+        for (_, name, cpu_int_id, _) in cpu_interrupts(app, analysis) {
             if *name == dispatcher_name {
-                let ret = &("cpu_int_".to_owned() + &curr_cpu_id.to_string() + "_handler");
+                let ret = &("cpu_int_".to_owned() + &cpu_int_id.to_string() + "_handler");
                 stmts.push(quote!(#[export_name = #ret]));
             }
-            curr_cpu_id += 1;
         }
 
         stmts
@@ -245,4 +314,17 @@ mod esp32c6 {
     pub fn extra_modules(_app: &App, _analysis: &SyntaxAnalysis) -> Vec<TokenStream2> {
         vec![]
     }
+
+    /// The attributes of the generated `main`, exported as the entry point of the runtime
+    pub fn main_linkage(_app: &App) -> TokenStream2 {
+        quote!(#[no_mangle])
+    }
+
+    pub fn priority_ceiling(_app: &App, _binds: &Ident) -> Option<TokenStream2> {
+        None
+    }
+
+    pub fn max_priority_const(_app: &App) -> Option<TokenStream2> {
+        Some(quote!(rtic::export::MAX_PRIORITY))
+    }
 }
@@ -0,0 +1,359 @@
+use crate::{
+    analyze::Analysis as CodegenAnalysis,
+    codegen::util,
+    syntax::{analyze::Analysis as SyntaxAnalysis, ast::App, backend::max_priority},
+};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use std::collections::{BTreeMap, HashSet};
+use syn::{parse, Attribute, Ident};
+
+/// The level triggered CPU interrupts of each level handled in Rust, the interrupt level of a
+/// CPU interrupt is fixed by its number
+///
+/// The timer, software, profiling and edge triggered CPU interrupts are left out.
+const CPU_INTERRUPTS: [&[u8]; 3] = [
+    &[0, 1, 2, 3, 4, 5, 8, 9, 12, 13, 17, 18],
+    &[19, 20, 21],
+    &[23, 27],
+];
+
+/// The dispatchers and hardware tasks with their priority and CPU interrupt
+///
+/// Assigned in order from the pool of their level, the number of interrupts per level is
+/// checked in `architecture_specific_analysis`.
+fn cpu_interrupts<'a>(app: &'a App, analysis: &'a CodegenAnalysis) -> Vec<(u8, &'a Ident, u8)> {
+    let mut next = [0; 3];
+    let dispatchers = analysis.interrupts.iter().map(|(p, (id, _))| (*p, id));
+    let hardware_tasks = app
+        .hardware_tasks
+        .values()
+        .map(|task| (task.args.priority, &task.args.binds));
+
+    dispatchers
+        .chain(hardware_tasks)
+        .map(|(priority, name)| {
+            let level = priority as usize - 1;
+            let cpu_int = CPU_INTERRUPTS[level][next[level]];
+            next[level] += 1;
+            (priority, name, cpu_int)
+        })
+        .collect()
+}
+
+fn handler_ident(cpu_int: u8) -> String {
+    format!("__rtic_cpu_int_{cpu_int}")
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn impl_mutex(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    cfgs: &[Attribute],
+    resources_prefix: bool,
+    name: &Ident,
+    ty: &TokenStream2,
+    ceiling: u8,
+    ptr: &TokenStream2,
+) -> TokenStream2 {
+    let path = if resources_prefix {
+        quote!(shared_resources::#name)
+    } else {
+        quote!(#name)
+    };
+    quote!(
+        #(#cfgs)*
+        impl<'a> rtic::Mutex for #path<'a> {
+            type T = #ty;
+
+            #[inline(always)]
+            fn lock<RTIC_INTERNAL_R>(&mut self, f: impl FnOnce(&mut #ty) -> RTIC_INTERNAL_R) -> RTIC_INTERNAL_R {
+                /// Priority ceiling
+                const CEILING: u8 = #ceiling;
+                unsafe {
+                    rtic::export::lock(
+                        #ptr,
+                        CEILING,
+                        f,
+                    )
+                }
+            }
+        }
+    )
+}
+
+pub fn interrupt_ident() -> Ident {
+    let span = Span::call_site();
+    Ident::new("Interrupt", span)
+}
+
+/// The dispatchers are pended through the software interrupt registers, without the PAC
+pub fn interrupt_mod(_app: &App) -> TokenStream2 {
+    quote!(rtic::export::SoftwareInterrupt)
+}
+
+pub fn extra_assertions(_: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn pre_init_preprocessing(app: &mut App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    app.args.core = false; // There are no Cortex-M like core peripherals
+    Ok(())
+}
+
+pub fn pre_init_checks(app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    let mut stmts = vec![];
+    // check that all dispatchers exists in the `Interrupt` enumeration regardless of whether
+    // they are used or not
+    let interrupt = interrupt_ident();
+    let rt_err = util::rt_err_ident();
+
+    for name in app.args.dispatchers.keys() {
+        stmts.push(quote!(let _ = #rt_err::#interrupt::#name;));
+    }
+    stmts
+}
+
+pub fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let interrupt = interrupt_ident();
+    let rt_err = util::rt_err_ident();
+
+    // Route the sources to their CPU interrupt, whose level is the priority
+    cpu_interrupts(app, analysis)
+        .into_iter()
+        .map(|(_, name, cpu_int)| {
+            quote!(
+                rtic::export::enable(#rt_err::#interrupt::#name as u16, #cpu_int);
+            )
+        })
+        .collect()
+}
+
+/// Checks in debug builds that the interrupts enabled before `init` are still configured the
+/// same way after it
+pub fn post_init_checks(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let interrupt = interrupt_ident();
+    let rt_err = util::rt_err_ident();
+
+    cpu_interrupts(app, analysis)
+        .into_iter()
+        .map(|(priority, name, cpu_int)| {
+            let es = format!(
+                "interrupt `{name}` was reconfigured during `init`, RTIC assumes it routed to CPU interrupt {cpu_int} (priority {priority})"
+            );
+            quote!(
+                ::core::assert!(
+                    rtic::export::is_enabled(#rt_err::#interrupt::#name as u16, #cpu_int),
+                    #es
+                );
+            )
+        })
+        .collect()
+}
+
+pub fn architecture_specific_analysis(app: &App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    // Check if the dispatchers are supported
+    for name in app.args.dispatchers.keys() {
+        let name_s = name.to_string();
+        match &*name_s {
+            "FROM_CPU_INTR0" | "FROM_CPU_INTR1" | "FROM_CPU_INTR2" | "FROM_CPU_INTR3" => {}
+
+            _ => {
+                return Err(parse::Error::new(
+                    name.span(),
+                    "Only FROM_CPU_INTRX are supported as dispatchers",
+                ));
+            }
+        }
+    }
+
+    // Check that the priorities are interrupt levels handled in Rust
+    let max = max_priority(None, Span::call_site())?;
+    let priorities = app
+        .software_tasks
+        .iter()
+        .map(|(name, task)| (name, task.args.priority))
+        .chain(
+            app.hardware_tasks
+                .iter()
+                .map(|(name, task)| (name, task.args.priority)),
+        );
+    for (name, priority) in priorities {
+        if priority > max {
+            return Err(parse::Error::new(
+                name.span(),
+                format!("the esp32s3 handles interrupt levels up to {max} in Rust, this priority is higher"),
+            ));
+        }
+    }
+
+    // Check that there are enough external interrupts to dispatch the software tasks and the timer
+    // queue handler
+    let mut first = None;
+    let priorities = app
+        .software_tasks
+        .iter()
+        .map(|(name, task)| {
+            first = Some(name);
+            task.args.priority
+        })
+        .filter(|prio| *prio > 0)
+        .collect::<HashSet<_>>();
+
+    let need = priorities.len();
+    let given = app.args.dispatchers.len();
+    if need > given {
+        let s = {
+            format!(
+                "not enough interrupts to dispatch \
+                    all software tasks (need: {need}; given: {given})"
+            )
+        };
+
+        // If not enough tasks and first still is None, may cause
+        // "custom attribute panicked" due to unwrap on None
+        return Err(parse::Error::new(first.unwrap().span(), s));
+    }
+
+    // Check that each level has enough CPU interrupts, one per dispatcher and hardware task
+    let mut used = BTreeMap::new();
+    for priority in priorities {
+        *used.entry(priority).or_insert(0) += 1;
+    }
+    for (name, task) in &app.hardware_tasks {
+        let count = used.entry(task.args.priority).or_insert(0);
+        *count += 1;
+
+        let available = CPU_INTERRUPTS[task.args.priority as usize - 1].len();
+        if *count > available {
+            return Err(parse::Error::new(
+                name.span(),
+                format!(
+                    "the esp32s3 has {available} CPU interrupts at level {}, used by the dispatcher and hardware tasks of this priority",
+                    task.args.priority
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn interrupt_entry(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn interrupt_exit(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn check_stack_overflow_before_init(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+) -> Vec<TokenStream2> {
+    vec![quote!(
+        // Check for stack overflow using symbols from `xtensa-lx-rt`, the stack grows down to
+        // `_stack_end_cpu0`.
+        extern "C" {
+            pub static _stack_end_cpu0: u32;
+        }
+
+        let stack_end = &_stack_end_cpu0 as *const _ as u32;
+
+        if rtic::export::read_sp() <= stack_end {
+            panic!("Stack overflow after allocating executors");
+        }
+    )]
+}
+
+pub fn async_entry(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    // The software interrupt sources are level triggered, clear the request
+    vec![quote!(
+        rtic::export::unpend(rtic::export::SoftwareInterrupt::#dispatcher_name);
+    )]
+}
+
+pub fn async_prio_limit(_app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let max = if let Some(max) = analysis.max_async_prio {
+        max
+    } else {
+        // No limit
+        max_priority(None, Span::call_site()).expect("UNREACHABLE")
+    };
+
+    vec![quote!(
+        /// Holds the maximum priority level for use by async HAL drivers.
+        #[no_mangle]
+        static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
+    )]
+}
+
+/// The handler of a CPU interrupt is called by the handler of its level, see `extra_modules`
+pub fn handler_config(
+    app: &App,
+    analysis: &CodegenAnalysis,
+    dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    cpu_interrupts(app, analysis)
+        .into_iter()
+        .filter(|(_, name, _)| **name == dispatcher_name)
+        .map(|(_, _, cpu_int)| {
+            let symbol = handler_ident(cpu_int);
+            quote!(#[export_name = #symbol])
+        })
+        .collect()
+}
+
+/// The handlers of the interrupt levels called by `xtensa-lx-rt`, running the handlers of the
+/// pending CPU interrupts of their level
+pub fn extra_modules(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let mut levels = BTreeMap::<u8, Vec<u8>>::new();
+    for (priority, _, cpu_int) in cpu_interrupts(app, analysis) {
+        levels.entry(priority).or_default().push(cpu_int);
+    }
+
+    levels
+        .into_iter()
+        .map(|(level, cpu_ints)| {
+            let level_handler = format_ident!("__level_{level}_interrupt");
+            let handlers = cpu_ints
+                .iter()
+                .map(|cpu_int| format_ident!("{}", handler_ident(*cpu_int)))
+                .collect::<Vec<_>>();
+
+            quote!(
+                #[doc(hidden)]
+                #[no_mangle]
+                unsafe extern "C" fn #level_handler(_level: u32, _save_frame: *mut u8) {
+                    extern "Rust" {
+                        #(fn #handlers();)*
+                    }
+
+                    let pending = rtic::export::pending_cpu_interrupts();
+                    #(
+                        if pending & (1 << #cpu_ints) != 0 {
+                            #handlers();
+                        }
+                    )*
+                }
+            )
+        })
+        .collect()
+}
+
+/// The attributes of the generated `main`, exported as the entry point of the runtime
+pub fn main_linkage(_app: &App) -> TokenStream2 {
+    quote!(#[no_mangle])
+}
+
+pub fn priority_ceiling(_app: &App, _binds: &Ident) -> Option<TokenStream2> {
+    None
+}
+
+pub fn max_priority_const(_app: &App) -> Option<TokenStream2> {
+    Some(quote!(rtic::export::MAX_PRIORITY))
+}
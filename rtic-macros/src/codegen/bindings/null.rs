@@ -0,0 +1,203 @@
+use crate::{
+    analyze::Analysis as CodegenAnalysis,
+    codegen::util,
+    syntax::{
+        analyze::Analysis as SyntaxAnalysis,
+        ast::{App, Dispatcher},
+        backend::max_priority,
+    },
+};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use std::collections::HashSet;
+use syn::{parse, Attribute, Ident};
+
+/// The dispatchers are numbered by a bit of a `u32`
+const MAX_DISPATCHERS: usize = 32;
+
+/// The type holding the numbers of the dispatchers, see `extra_modules`
+fn dispatcher_numbers_ident() -> Ident {
+    util::mark_internal_name("Swi")
+}
+
+pub fn interrupt_ident() -> Ident {
+    let span = Span::call_site();
+    Ident::new("Interrupt", span)
+}
+
+/// The dispatchers are pended by their number, see `extra_modules`
+pub fn interrupt_mod(_app: &App) -> TokenStream2 {
+    let numbers = dispatcher_numbers_ident();
+    quote!(#numbers)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn impl_mutex(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    cfgs: &[Attribute],
+    resources_prefix: bool,
+    name: &Ident,
+    ty: &TokenStream2,
+    ceiling: u8,
+    ptr: &TokenStream2,
+) -> TokenStream2 {
+    let path = if resources_prefix {
+        quote!(shared_resources::#name)
+    } else {
+        quote!(#name)
+    };
+    quote!(
+        #(#cfgs)*
+        impl<'a> rtic::Mutex for #path<'a> {
+            type T = #ty;
+
+            #[inline(always)]
+            fn lock<RTIC_INTERNAL_R>(&mut self, f: impl FnOnce(&mut #ty) -> RTIC_INTERNAL_R) -> RTIC_INTERNAL_R {
+                /// Priority ceiling
+                const CEILING: u8 = #ceiling;
+                unsafe {
+                    rtic::export::lock(
+                        #ptr,
+                        CEILING,
+                        f,
+                    )
+                }
+            }
+        }
+    )
+}
+
+pub fn extra_assertions(_: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// The dispatchers of an application written for another backend are kept, without them one is
+/// generated per priority level of the software tasks
+pub fn pre_init_preprocessing(app: &mut App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    app.args.core = false; // There are no Cortex-M like core peripherals
+    if !app.args.dispatchers.is_empty() {
+        return Ok(());
+    }
+
+    let soft_priorities = app
+        .software_tasks
+        .values()
+        .map(|task| task.args.priority)
+        .filter(|prio| *prio > 0)
+        .collect::<HashSet<_>>();
+
+    for i in 0..soft_priorities.len() {
+        let dispatcher_ident = Ident::new(&format!("__RTICDispatcher{i}"), Span::call_site());
+        app.args.dispatchers.insert(
+            dispatcher_ident,
+            Dispatcher {
+                attrs: vec![],
+                executor: None,
+                priorities: None,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+pub fn pre_init_checks(_app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn pre_init_enable_interrupts(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn post_init_checks(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn architecture_specific_analysis(app: &App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    if app.args.dispatchers.len() > MAX_DISPATCHERS {
+        return Err(parse::Error::new(
+            Span::call_site(),
+            format!("the null backend numbers at most {MAX_DISPATCHERS} dispatchers"),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn interrupt_entry(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn interrupt_exit(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn check_stack_overflow_before_init(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn async_entry(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    _dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let max = if let Some(max) = analysis.max_async_prio {
+        max
+    } else {
+        // No limit
+        max_priority(app.args.backend.as_ref(), Span::call_site()).expect("UNREACHABLE")
+    };
+
+    vec![quote!(
+        /// Holds the maximum priority level for use by async HAL drivers.
+        #[no_mangle]
+        static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
+    )]
+}
+
+pub fn handler_config(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    _dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// The numbers of the dispatchers, in the order they are listed
+pub fn extra_modules(app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let numbers = dispatcher_numbers_ident();
+    let dispatchers = app.args.dispatchers.keys().collect::<Vec<_>>();
+    let indices = (0..dispatchers.len() as u8).collect::<Vec<_>>();
+
+    vec![quote!(
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        struct #numbers;
+
+        #[allow(non_upper_case_globals, dead_code)]
+        impl #numbers {
+            #(const #dispatchers: u8 = #indices;)*
+        }
+    )]
+}
+
+/// The generated `main` is not exported, the host program has its own
+pub fn main_linkage(_app: &App) -> TokenStream2 {
+    quote!(#[allow(dead_code)])
+}
+
+pub fn priority_ceiling(_app: &App, _binds: &Ident) -> Option<TokenStream2> {
+    None
+}
+
+pub fn max_priority_const(_app: &App) -> Option<TokenStream2> {
+    Some(quote!(rtic::export::MAX_PRIORITY))
+}
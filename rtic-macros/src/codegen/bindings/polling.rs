@@ -0,0 +1,239 @@
+use crate::{
+    analyze::Analysis as CodegenAnalysis,
+    codegen::util,
+    syntax::{
+        analyze::Analysis as SyntaxAnalysis,
+        ast::{App, Dispatcher},
+        backend::max_priority,
+    },
+};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use std::collections::HashSet;
+use syn::{parse, Attribute, Ident};
+
+/// The dispatchers are numbered by a bit of a `u32`
+const MAX_DISPATCHERS: usize = 32;
+
+/// The module holding the numbers of the dispatchers, see `extra_modules`
+fn dispatcher_numbers_ident() -> Ident {
+    Ident::new("dispatchers", Span::call_site())
+}
+
+pub fn interrupt_ident() -> Ident {
+    let span = Span::call_site();
+    Ident::new("Interrupt", span)
+}
+
+/// The dispatchers are pended by their number, see `extra_modules`
+pub fn interrupt_mod(_app: &App) -> TokenStream2 {
+    let numbers = dispatcher_numbers_ident();
+    quote!(#numbers)
+}
+
+/// Nothing preempts on the polling backend, a lock is a plain access
+#[allow(clippy::too_many_arguments)]
+pub fn impl_mutex(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    cfgs: &[Attribute],
+    resources_prefix: bool,
+    name: &Ident,
+    ty: &TokenStream2,
+    _ceiling: u8,
+    ptr: &TokenStream2,
+) -> TokenStream2 {
+    let path = if resources_prefix {
+        quote!(shared_resources::#name)
+    } else {
+        quote!(#name)
+    };
+    quote!(
+        #(#cfgs)*
+        impl<'a> rtic::Mutex for #path<'a> {
+            type T = #ty;
+
+            #[inline(always)]
+            fn lock<RTIC_INTERNAL_R>(&mut self, f: impl FnOnce(&mut #ty) -> RTIC_INTERNAL_R) -> RTIC_INTERNAL_R {
+                unsafe { rtic::export::lock(#ptr, f) }
+            }
+        }
+    )
+}
+
+pub fn extra_assertions(_: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// The dispatchers of an application written for another backend are kept, without them one is
+/// generated per priority level of the software tasks
+///
+/// Without priority 0 tasks, `main` runs the superloop after `init` in place of sleeping, with
+/// them the zero priority dispatcher runs it in `idle_wait`.
+pub fn pre_init_preprocessing(app: &mut App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    app.args.core = false; // There are no Cortex-M like core peripherals
+    app.args.sleep_on_exit = app.idle.is_none()
+        && app
+            .software_tasks
+            .values()
+            .all(|task| task.args.priority > 0);
+
+    if !app.args.dispatchers.is_empty() {
+        return Ok(());
+    }
+
+    let soft_priorities = app
+        .software_tasks
+        .values()
+        .map(|task| task.args.priority)
+        .filter(|prio| *prio > 0)
+        .collect::<HashSet<_>>();
+
+    for i in 0..soft_priorities.len() {
+        let dispatcher_ident = Ident::new(&format!("__RTICDispatcher{i}"), Span::call_site());
+        app.args.dispatchers.insert(
+            dispatcher_ident,
+            Dispatcher {
+                attrs: vec![],
+                executor: None,
+                priorities: None,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+pub fn pre_init_checks(_app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn pre_init_enable_interrupts(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn post_init_checks(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn architecture_specific_analysis(app: &App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    if let Some(task) = app.hardware_tasks.values().next() {
+        return Err(parse::Error::new(
+            task.args.binds.span(),
+            "the polling backend runs without interrupts, poll the peripheral from a software task instead of binding it",
+        ));
+    }
+
+    if let Some(idle) = &app.idle {
+        return Err(parse::Error::new(
+            idle.name.span(),
+            "`idle` is not supported by the polling backend, `main` runs the superloop in its place; use a priority 0 task",
+        ));
+    }
+
+    if app.args.test {
+        return Err(parse::Error::new(
+            Span::call_site(),
+            "`test` is not supported by the polling backend, the harness spins without running the superloop",
+        ));
+    }
+
+    if app.args.dispatchers.len() > MAX_DISPATCHERS {
+        return Err(parse::Error::new(
+            Span::call_site(),
+            format!("the polling backend numbers at most {MAX_DISPATCHERS} dispatchers"),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn interrupt_entry(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn interrupt_exit(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn check_stack_overflow_before_init(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn async_entry(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    _dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let max = if let Some(max) = analysis.max_async_prio {
+        max
+    } else {
+        // No limit
+        max_priority(app.args.backend.as_ref(), Span::call_site()).expect("UNREACHABLE")
+    };
+
+    vec![quote!(
+        /// Holds the maximum priority level for use by async HAL drivers.
+        #[no_mangle]
+        static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
+    )]
+}
+
+pub fn handler_config(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    _dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// The used dispatchers, numbered from the highest priority down so that the superloop runs
+/// them in that order
+pub fn extra_modules(_app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let numbers = dispatcher_numbers_ident();
+
+    // `interrupts` is ordered by priority
+    let names = analysis
+        .interrupts
+        .values()
+        .rev()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    let handlers = names.iter().map(|name| util::suffixed(&name.to_string()));
+    let indices = (0..names.len() as u8).collect::<Vec<_>>();
+
+    vec![quote!(
+        #[doc(hidden)]
+        #[no_mangle]
+        static __RTIC_POLLING: rtic::export::PollingConfig = rtic::export::PollingConfig {
+            dispatchers: &[#(|| unsafe { #handlers() }),*],
+        };
+
+        /// The numbers of the dispatchers, in the order the superloop runs them
+        #[allow(non_upper_case_globals, dead_code)]
+        #[doc(hidden)]
+        pub mod #numbers {
+            #(pub const #names: u8 = #indices;)*
+        }
+    )]
+}
+
+/// The generated `main` is the entry point of the runtime, and called by the tests on the host
+pub fn main_linkage(_app: &App) -> TokenStream2 {
+    quote!(#[cfg_attr(not(test), no_mangle)] pub)
+}
+
+pub fn priority_ceiling(_app: &App, _binds: &Ident) -> Option<TokenStream2> {
+    None
+}
+
+pub fn max_priority_const(_app: &App) -> Option<TokenStream2> {
+    Some(quote!(rtic::export::MAX_PRIORITY))
+}
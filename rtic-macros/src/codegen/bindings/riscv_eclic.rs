@@ -0,0 +1,420 @@
+use crate::{
+    analyze::Analysis as CodegenAnalysis,
+    codegen::util,
+    syntax::{
+        analyze::Analysis as SyntaxAnalysis,
+        ast::App,
+        backend::{max_priority, BackendArgs, Variant},
+    },
+};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use std::collections::HashSet;
+use syn::{parse, Attribute, Ident};
+
+/// The backend arguments, checked to be present in `architecture_specific_analysis`
+fn backend(app: &App) -> &BackendArgs {
+    app.args.backend.as_ref().expect("UNREACHABLE")
+}
+
+pub fn interrupt_ident() -> Ident {
+    let span = Span::call_site();
+    Ident::new("Interrupt", span)
+}
+
+pub fn interrupt_mod(app: &App) -> TokenStream2 {
+    let device = &app.args.device;
+    let interrupt = interrupt_ident();
+    quote!(#device::#interrupt)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn impl_mutex(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    cfgs: &[Attribute],
+    resources_prefix: bool,
+    name: &Ident,
+    ty: &TokenStream2,
+    ceiling: u8,
+    ptr: &TokenStream2,
+) -> TokenStream2 {
+    let path = if resources_prefix {
+        quote!(shared_resources::#name)
+    } else {
+        quote!(#name)
+    };
+    quote!(
+        #(#cfgs)*
+        impl<'a> rtic::Mutex for #path<'a> {
+            type T = #ty;
+
+            #[inline(always)]
+            fn lock<RTIC_INTERNAL_R>(&mut self, f: impl FnOnce(&mut #ty) -> RTIC_INTERNAL_R) -> RTIC_INTERNAL_R {
+                /// Priority ceiling
+                const CEILING: u8 = #ceiling;
+                unsafe {
+                    rtic::export::lock(
+                        #ptr,
+                        CEILING,
+                        f,
+                    )
+                }
+            }
+        }
+    )
+}
+
+pub fn extra_assertions(_: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn pre_init_preprocessing(app: &mut App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    app.args.core = false; // There are no Cortex-M like core peripherals
+    Ok(())
+}
+
+pub fn pre_init_checks(app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    let mut stmts = vec![];
+    // check that all dispatchers exists in the `Interrupt` enumeration regardless of whether
+    // they are used or not
+    let interrupt = interrupt_ident();
+    let rt_err = util::rt_err_ident();
+
+    for name in app.args.dispatchers.keys() {
+        stmts.push(quote!(let _ = #rt_err::#interrupt::#name;));
+    }
+    stmts
+}
+
+pub fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let mut stmts = vec![];
+    let interrupt = interrupt_ident();
+    let rt_err = util::rt_err_ident();
+
+    // Set the level/priority split and clear the threshold
+    stmts.push(quote!(rtic::export::configure();));
+
+    // Dispatchers are pended by software, which only latches for edge triggered interrupts
+    let dispatchers = analysis
+        .interrupts
+        .iter()
+        .map(|(p, (id, _))| (p, id, true, &[][..]));
+    // Hardware tasks keep the trigger configured by the HAL
+    let hardware_tasks = app
+        .hardware_tasks
+        .values()
+        .map(|task| (&task.args.priority, &task.args.binds, false, &task.cfgs[..]));
+    let interrupts: Vec<_> = dispatchers.chain(hardware_tasks).collect();
+
+    let vectored = backend(app).vectored.is_some();
+    for &(&priority, name, edge, _) in &interrupts {
+        stmts.push(quote!(
+            rtic::export::enable(#rt_err::#interrupt::#name, #priority, #edge, #vectored);
+        ));
+    }
+
+    if let Some(len) = backend(app).vectored {
+        let handlers: Vec<_> = interrupts
+            .iter()
+            .map(|&(_, name, _, cfgs)| (name, cfgs))
+            .collect();
+        stmts.push(vector_table(len, &handlers));
+    }
+    stmts
+}
+
+/// The `mtvt` table of the handlers, each entered through a stub saving the caller saved
+/// registers that the controller leaves to software in vectored mode
+fn vector_table(len: u16, handlers: &[(&Ident, &[Attribute])]) -> TokenStream2 {
+    let interrupt = interrupt_ident();
+    let rt_err = util::rt_err_ident();
+    let len = len as usize;
+    // The controller ignores the low bits of `mtvt` below the size of the table, rounded up to a
+    // power of two and at least 64 bytes. Sized for 8 byte entries to cover RV64 as well.
+    let align = proc_macro2::Literal::usize_unsuffixed((len * 8).next_power_of_two().max(64));
+
+    let mut items = vec![];
+    let mut entries = vec![];
+    for &(name, cfgs) in handlers {
+        let body = util::mark_internal_name(&format!("{name}_vector"));
+        let entry = format!("__rtic_vector_{name}");
+        let entry_ident = Ident::new(&entry, Span::call_site());
+        let rv32 = vector_entry(&entry, 4);
+        let rv64 = vector_entry(&entry, 8);
+        let es = format!("interrupt `{name}` is outside of the vector table, increase `vectored`");
+
+        items.push(quote!(
+            #(#cfgs)*
+            #[allow(non_snake_case)]
+            unsafe extern "C" fn #body() {
+                #name();
+            }
+
+            #(#cfgs)*
+            #[cfg(target_arch = "riscv32")]
+            ::core::arch::global_asm!(#rv32, body = sym #body);
+
+            #(#cfgs)*
+            #[cfg(target_arch = "riscv64")]
+            ::core::arch::global_asm!(#rv64, body = sym #body);
+
+            #(#cfgs)*
+            extern "C" {
+                fn #entry_ident();
+            }
+
+            const _: () = ::core::assert!((#rt_err::#interrupt::#name as usize) < #len, #es);
+        ));
+        entries.push(quote!(
+            #(#cfgs)*
+            {
+                table[#rt_err::#interrupt::#name as usize] =
+                    Some(#entry_ident as unsafe extern "C" fn());
+            }
+        ));
+    }
+
+    quote!({
+        #[cfg(any(target_feature = "f", target_feature = "d"))]
+        ::core::compile_error!(
+            "the vector table entries only save the integer registers, `vectored` needs a target without hardware floating point"
+        );
+
+        #(#items)*
+
+        #[repr(C, align(#align))]
+        struct Vectors([Option<unsafe extern "C" fn()>; #len]);
+
+        static VECTORS: Vectors = {
+            let mut table: [Option<unsafe extern "C" fn()>; #len] = [None; #len];
+            #(#entries)*
+            Vectors(table)
+        };
+
+        rtic::export::set_vector_table(&VECTORS as *const Vectors as *const ());
+    })
+}
+
+/// The entry of a vectored interrupt for registers of `xlen` bytes, calling `{body}`
+fn vector_entry(label: &str, xlen: usize) -> String {
+    let (store, load) = if xlen == 4 { ("sw", "lw") } else { ("sd", "ld") };
+    let regs = [
+        "ra", "t0", "t1", "t2", "t3", "t4", "t5", "t6", "a0", "a1", "a2", "a3", "a4", "a5", "a6",
+        "a7",
+    ];
+    let frame = regs.len() * xlen;
+
+    let mut asm = format!(
+        ".section .text.{label}, \"ax\"\n.global {label}\n.align 2\n{label}:\n    addi sp, sp, -{frame}\n"
+    );
+    for (i, reg) in regs.iter().enumerate() {
+        asm += &format!("    {store} {reg}, {}(sp)\n", i * xlen);
+    }
+    asm += "    call {body}\n";
+    for (i, reg) in regs.iter().enumerate() {
+        asm += &format!("    {load} {reg}, {}(sp)\n", i * xlen);
+    }
+    asm += &format!("    addi sp, sp, {frame}\n    mret\n");
+    asm
+}
+
+/// Checks in debug builds that the interrupts enabled before `init` are still configured the
+/// same way after it
+pub fn post_init_checks(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let mut stmts = vec![];
+    let interrupt = interrupt_ident();
+    let rt_err = util::rt_err_ident();
+    let interrupt_ids = analysis.interrupts.iter().map(|(p, (id, _))| (p, id));
+
+    for (&priority, name) in interrupt_ids.chain(
+        app.hardware_tasks
+            .values()
+            .map(|task| (&task.args.priority, &task.args.binds)),
+    ) {
+        let es = format!(
+            "interrupt `{name}` was reconfigured during `init`, RTIC assumes it enabled at priority {priority}"
+        );
+        stmts.push(quote!(
+            ::core::assert!(
+                rtic::export::is_enabled(#rt_err::#interrupt::#name, #priority),
+                #es
+            );
+        ));
+    }
+
+    stmts
+}
+
+pub fn architecture_specific_analysis(
+    app: &App,
+    _analysis: &SyntaxAnalysis,
+) -> parse::Result<()> {
+    let Some(backend) = &app.args.backend else {
+        return Err(parse::Error::new(
+            Span::call_site(),
+            "the ECLIC backend requires `backend = [variant = nuclei | thead, level_bits = N]`",
+        ));
+    };
+
+    // Check that all priorities fit in the level bits
+    let max = max_priority(Some(backend), Span::call_site())?;
+    let level_bits = backend.level_bits;
+    let priorities = app
+        .software_tasks
+        .iter()
+        .map(|(name, task)| (name, task.args.priority))
+        .chain(
+            app.hardware_tasks
+                .iter()
+                .map(|(name, task)| (name, task.args.priority)),
+        );
+    for (name, priority) in priorities {
+        if priority > max {
+            return Err(parse::Error::new(
+                name.span(),
+                format!("this priority does not fit in `level_bits = {level_bits}`, the maximum is {max}"),
+            ));
+        }
+    }
+
+    // Check that there are enough external interrupts to dispatch the software tasks and the timer
+    // queue handler
+    let mut first = None;
+    let priorities = app
+        .software_tasks
+        .iter()
+        .map(|(name, task)| {
+            first = Some(name);
+            task.args.priority
+        })
+        .filter(|prio| *prio > 0)
+        .collect::<HashSet<_>>();
+
+    let need = priorities.len();
+    let given = app.args.dispatchers.len();
+    if need > given {
+        let s = {
+            format!(
+                "not enough interrupts to dispatch \
+                    all software tasks (need: {need}; given: {given})"
+            )
+        };
+
+        // If not enough tasks and first still is None, may cause
+        // "custom attribute panicked" due to unwrap on None
+        return Err(parse::Error::new(first.unwrap().span(), s));
+    }
+    Ok(())
+}
+
+/// The controller masks all interrupts on entry, save what a nested interrupt overwrites and
+/// re-enable them so higher levels can preempt the handler
+pub fn interrupt_entry(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let nested = util::mark_internal_name("nested");
+    vec![quote!(let #nested = rtic::export::enter_nested();)]
+}
+
+pub fn interrupt_exit(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let nested = util::mark_internal_name("nested");
+    vec![quote!(rtic::export::exit_nested(#nested);)]
+}
+
+pub fn check_stack_overflow_before_init(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+) -> Vec<TokenStream2> {
+    vec![quote!(
+        // Check for stack overflow using symbols from `risc-v-rt`.
+        extern "C" {
+            pub static _stack_start: u32;
+            pub static _ebss: u32;
+        }
+
+        let stack_start = &_stack_start as *const _ as u32;
+        let ebss = &_ebss as *const _ as u32;
+
+        if stack_start > ebss {
+            // No flip-link usage, check the SP for overflow.
+            if rtic::export::read_sp() <= ebss {
+                panic!("Stack overflow after allocating executors");
+            }
+        }
+    )]
+}
+
+pub fn async_entry(
+    app: &App,
+    _analysis: &CodegenAnalysis,
+    dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    // Taking an edge triggered interrupt through the vector table clears its pending bit
+    if backend(app).vectored.is_some() {
+        return vec![];
+    }
+
+    let int_mod = interrupt_mod(app);
+
+    // Outside of vectored mode edge triggered interrupts stay pending until cleared
+    vec![quote!(
+        rtic::export::unpend(#int_mod::#dispatcher_name);
+    )]
+}
+
+pub fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let max = if let Some(max) = analysis.max_async_prio {
+        max
+    } else {
+        // No limit
+        max_priority(Some(backend(app)), Span::call_site()).expect("UNREACHABLE")
+    };
+
+    vec![quote!(
+        /// Holds the maximum priority level for use by async HAL drivers.
+        #[no_mangle]
+        static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
+    )]
+}
+
+pub fn handler_config(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    _dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn extra_modules(app: &App, _analysis: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    let backend = backend(app);
+    let variant = match backend.variant {
+        Variant::Nuclei => quote!(Nuclei),
+        Variant::THead => quote!(THead),
+    };
+    let base = backend.base as usize;
+    let level_bits = backend.level_bits;
+
+    // The runtime reads the controller configuration from the app
+    vec![quote!(
+        #[doc(hidden)]
+        #[no_mangle]
+        static __RTIC_ECLIC: rtic::export::EclicConfig = rtic::export::EclicConfig {
+            variant: rtic::export::EclicVariant::#variant,
+            base: #base,
+            level_bits: #level_bits,
+        };
+    )]
+}
+
+/// The attributes of the generated `main`, exported as the entry point of the runtime
+pub fn main_linkage(_app: &App) -> TokenStream2 {
+    quote!(#[no_mangle])
+}
+
+pub fn priority_ceiling(_app: &App, _binds: &Ident) -> Option<TokenStream2> {
+    None
+}
+
+pub fn max_priority_const(app: &App) -> Option<TokenStream2> {
+    let max = max_priority(app.args.backend.as_ref(), Span::call_site()).ok()?;
+    Some(quote!(#max))
+}
@@ -0,0 +1,354 @@
+use crate::{
+    analyze::Analysis as CodegenAnalysis,
+    codegen::util,
+    syntax::{
+        analyze::Analysis as SyntaxAnalysis,
+        ast::{App, Dispatcher},
+        backend::{max_priority, BackendArgs},
+    },
+};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use std::collections::HashSet;
+use syn::{parse, Attribute, Ident};
+
+/// The software dispatchers are numbered by a bit of a `u32`
+const MAX_DISPATCHERS: usize = 32;
+
+/// The type holding the numbers of the software dispatchers, see `extra_modules`
+fn dispatcher_numbers_ident() -> Ident {
+    util::mark_internal_name("Swi")
+}
+
+pub fn interrupt_ident() -> Ident {
+    let span = Span::call_site();
+    Ident::new("Interrupt", span)
+}
+
+/// The dispatchers are pended by their number, see `extra_modules`
+pub fn interrupt_mod(_app: &App) -> TokenStream2 {
+    let numbers = dispatcher_numbers_ident();
+    quote!(#numbers)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn impl_mutex(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    cfgs: &[Attribute],
+    resources_prefix: bool,
+    name: &Ident,
+    ty: &TokenStream2,
+    ceiling: u8,
+    ptr: &TokenStream2,
+) -> TokenStream2 {
+    let path = if resources_prefix {
+        quote!(shared_resources::#name)
+    } else {
+        quote!(#name)
+    };
+    quote!(
+        #(#cfgs)*
+        impl<'a> rtic::Mutex for #path<'a> {
+            type T = #ty;
+
+            #[inline(always)]
+            fn lock<RTIC_INTERNAL_R>(&mut self, f: impl FnOnce(&mut #ty) -> RTIC_INTERNAL_R) -> RTIC_INTERNAL_R {
+                /// Priority ceiling
+                const CEILING: u8 = #ceiling;
+                unsafe {
+                    rtic::export::lock(
+                        #ptr,
+                        CEILING,
+                        f,
+                    )
+                }
+            }
+        }
+    )
+}
+
+pub fn extra_assertions(_: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// The PLIC cannot pend its sources from software, the dispatchers are software interrupts
+/// generated here, one per priority level of the software tasks
+pub fn pre_init_preprocessing(app: &mut App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    app.args.core = false; // There are no Cortex-M like core peripherals
+    if !app.args.dispatchers.is_empty() {
+        return Err(parse::Error::new(
+            Span::call_site(),
+            "the PLIC backend dispatches through software interrupts; remove the `dispatchers` argument from `#[app]`",
+        ));
+    }
+
+    let soft_priorities = app
+        .software_tasks
+        .values()
+        .map(|task| task.args.priority)
+        .filter(|prio| *prio > 0)
+        .collect::<HashSet<_>>();
+
+    for i in 0..soft_priorities.len() {
+        let dispatcher_ident = Ident::new(&format!("__RTICDispatcher{i}"), Span::call_site());
+        app.args.dispatchers.insert(
+            dispatcher_ident,
+            Dispatcher {
+                attrs: vec![],
+                executor: None,
+                priorities: None,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+pub fn pre_init_checks(_app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn pre_init_enable_interrupts(app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let mut stmts = vec![];
+    let interrupt = interrupt_ident();
+    let rt_err = util::rt_err_ident();
+
+    // Clear the threshold and enable the external and software interrupts of the hart
+    stmts.push(quote!(rtic::export::configure();));
+
+    for task in app.hardware_tasks.values() {
+        let name = &task.args.binds;
+        let priority = task.args.priority;
+        stmts.push(quote!(
+            rtic::export::enable(#rt_err::#interrupt::#name as u16, #priority);
+        ));
+    }
+    stmts
+}
+
+/// Checks in debug builds that the sources enabled before `init` are still configured the same
+/// way after it
+pub fn post_init_checks(app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let interrupt = interrupt_ident();
+    let rt_err = util::rt_err_ident();
+
+    app.hardware_tasks
+        .values()
+        .map(|task| {
+            let name = &task.args.binds;
+            let priority = task.args.priority;
+            let es = format!(
+                "interrupt `{name}` was reconfigured during `init`, RTIC assumes it enabled at priority {priority}"
+            );
+            quote!(
+                ::core::assert!(
+                    rtic::export::is_enabled(#rt_err::#interrupt::#name as u16, #priority),
+                    #es
+                );
+            )
+        })
+        .collect()
+}
+
+pub fn architecture_specific_analysis(app: &App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    // Check that all priorities are levels of the PLIC
+    let max = max_priority(app.args.backend.as_ref(), Span::call_site())?;
+    let priorities = app
+        .software_tasks
+        .iter()
+        .map(|(name, task)| (name, task.args.priority))
+        .chain(
+            app.hardware_tasks
+                .iter()
+                .map(|(name, task)| (name, task.args.priority)),
+        );
+    for (name, priority) in priorities {
+        if priority > max {
+            return Err(parse::Error::new(
+                name.span(),
+                format!("the PLIC has {max} priority levels, set with `backend = [levels = N]`, this priority is higher"),
+            ));
+        }
+    }
+
+    if app.args.dispatchers.len() > MAX_DISPATCHERS {
+        return Err(parse::Error::new(
+            Span::call_site(),
+            format!("the PLIC backend dispatches at most {MAX_DISPATCHERS} priority levels of software tasks"),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn interrupt_entry(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn interrupt_exit(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn check_stack_overflow_before_init(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+) -> Vec<TokenStream2> {
+    vec![quote!(
+        // Check for stack overflow using symbols from `riscv-rt`.
+        extern "C" {
+            pub static _stack_start: u32;
+            pub static _ebss: u32;
+        }
+
+        let stack_start = &_stack_start as *const _ as u32;
+        let ebss = &_ebss as *const _ as u32;
+
+        if stack_start > ebss {
+            // No flip-link usage, check the SP for overflow.
+            if rtic::export::read_sp() <= ebss {
+                panic!("Stack overflow after allocating executors");
+            }
+        }
+    )]
+}
+
+pub fn async_entry(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    _dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let max = if let Some(max) = analysis.max_async_prio {
+        max
+    } else {
+        // No limit
+        max_priority(app.args.backend.as_ref(), Span::call_site()).expect("UNREACHABLE")
+    };
+
+    vec![quote!(
+        /// Holds the maximum priority level for use by async HAL drivers.
+        #[no_mangle]
+        static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
+    )]
+}
+
+pub fn handler_config(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    _dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// The configuration of the PLIC, the numbers of the dispatchers and the `MachineExternal` and
+/// `MachineSoft` handlers called by `riscv-rt`, running the handlers of the claimed sources and
+/// of the pending dispatchers
+pub fn extra_modules(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let default = BackendArgs::default();
+    let backend = app.args.backend.as_ref().unwrap_or(&default);
+    let base = backend.base as usize;
+    let context = backend.context as usize;
+    let msip = backend.msip as usize;
+    let levels = backend.levels;
+
+    let device = &app.args.device;
+    let interrupt = interrupt_ident();
+    let numbers = dispatcher_numbers_ident();
+
+    let dispatchers = app.args.dispatchers.keys().collect::<Vec<_>>();
+    let indices = (0..dispatchers.len() as u8).collect::<Vec<_>>();
+
+    // Highest priority first, as `next_dispatcher` takes the first pending one
+    let mut by_priority = analysis
+        .interrupts
+        .iter()
+        .map(|(priority, (name, _))| {
+            let index = app.args.dispatchers.get_index_of(name).expect("UNREACHABLE") as u8;
+            (*priority, index, name)
+        })
+        .collect::<Vec<_>>();
+    by_priority.sort_by_key(|(priority, ..)| core::cmp::Reverse(*priority));
+    let (soft_priorities, (soft_indices, soft_names)): (Vec<_>, (Vec<_>, Vec<_>)) = by_priority
+        .into_iter()
+        .map(|(priority, index, name)| (priority, (index, name)))
+        .unzip();
+
+    let (cfgs, binds): (Vec<_>, Vec<_>) = app
+        .hardware_tasks
+        .values()
+        .map(|task| (&task.cfgs, &task.args.binds))
+        .unzip();
+
+    vec![quote!(
+        #[doc(hidden)]
+        #[no_mangle]
+        static __RTIC_PLIC: rtic::export::PlicConfig = rtic::export::PlicConfig {
+            base: #base,
+            context: #context,
+            msip: #msip,
+            levels: #levels,
+        };
+
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        struct #numbers;
+
+        #[allow(non_upper_case_globals)]
+        impl #numbers {
+            #(const #dispatchers: u8 = #indices;)*
+        }
+
+        #[doc(hidden)]
+        #[no_mangle]
+        unsafe extern "C" fn MachineExternal() {
+            loop {
+                let id = rtic::export::claim();
+                if id == 0 {
+                    break;
+                }
+
+                #(
+                    #(#cfgs)*
+                    if id == #device::#interrupt::#binds as u16 {
+                        #binds();
+                    }
+                )*
+
+                rtic::export::complete(id);
+            }
+        }
+
+        #[doc(hidden)]
+        #[no_mangle]
+        unsafe extern "C" fn MachineSoft() {
+            rtic::export::clear_soft();
+
+            const DISPATCHERS: &[(u8, u8)] = &[#((#soft_indices, #soft_priorities)),*];
+            while let Some(index) = rtic::export::next_dispatcher(DISPATCHERS) {
+                #(
+                    if index == #soft_indices {
+                        #soft_names();
+                    }
+                )*
+            }
+        }
+    )]
+}
+
+/// The attributes of the generated `main`, exported as the entry point of the runtime
+pub fn main_linkage(_app: &App) -> TokenStream2 {
+    quote!(#[no_mangle])
+}
+
+pub fn priority_ceiling(_app: &App, _binds: &Ident) -> Option<TokenStream2> {
+    None
+}
+
+pub fn max_priority_const(app: &App) -> Option<TokenStream2> {
+    let max = max_priority(app.args.backend.as_ref(), Span::call_site()).ok()?;
+    Some(quote!(#max))
+}
@@ -84,7 +84,11 @@ pub fn pre_init_preprocessing(app: &mut App, _analysis: &SyntaxAnalysis) -> pars
         let dispatcher_ident = Ident::new(&format!("__RTICDispatcher{}", i), Span::call_site());
         app.args
             .dispatchers
-            .insert(dispatcher_ident, Dispatcher { attrs: vec![] });
+            .insert(dispatcher_ident, Dispatcher {
+                    attrs: vec![],
+                    executor: None,
+                    priorities: None,
+                });
     }
 
     Ok(())
@@ -127,6 +131,11 @@ pub fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<
     stmts
 }
 
+/// The SLIC priorities live in software and are only set by RTIC, there is nothing to check.
+pub fn post_init_checks(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
 /// Any additional checks that depend on the system architecture.
 pub fn architecture_specific_analysis(app: &App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
     // Check that there are enough external interrupts to dispatch the software tasks and the timer queue handler
@@ -251,5 +260,43 @@ pub fn extra_modules(app: &App, _analysis: &SyntaxAnalysis) -> Vec<TokenStream2>
 
     stmts.push(quote!(rtic::export::codegen!(pac = #device, swi = [#(#swi_slice,)*], backend = [hart_id = #hart_id]);));
 
+    // Backs `rtic::swi::request`, which can only pend the software interrupts of the SLIC
+    let swi_request = if swi_slice.is_empty() {
+        quote!({
+            let _ = n;
+            false
+        })
+    } else {
+        quote!(
+            match <slic::Interrupt as rtic::export::InterruptNumber>::from_number(n) {
+                Ok(interrupt) => {
+                    rtic::export::pend(interrupt);
+                    true
+                }
+                Err(_) => false,
+            }
+        )
+    };
+    stmts.push(quote!(
+        #[doc(hidden)]
+        #[no_mangle]
+        fn __rtic_swi_request(n: u16) -> bool {
+            #swi_request
+        }
+    ));
+
     stmts
 }
+
+/// The attributes of the generated `main`, exported as the entry point of the runtime
+pub fn main_linkage(_app: &App) -> TokenStream2 {
+    quote!(#[no_mangle])
+}
+
+pub fn priority_ceiling(_app: &App, _binds: &Ident) -> Option<TokenStream2> {
+    None
+}
+
+pub fn max_priority_const(_app: &App) -> Option<TokenStream2> {
+    Some(quote!(rtic::export::MAX_PRIORITY))
+}
@@ -0,0 +1,299 @@
+use crate::{
+    analyze::Analysis as CodegenAnalysis,
+    codegen::util,
+    syntax::{
+        analyze::Analysis as SyntaxAnalysis,
+        ast::{App, Dispatcher},
+        backend::max_priority,
+    },
+};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use std::collections::HashSet;
+use syn::{parse, Attribute, Ident};
+
+/// The interrupts are numbered by a bit of a `u32`
+const MAX_INTERRUPTS: usize = 32;
+
+/// The module holding the numbers of the interrupts, see `extra_modules`
+fn interrupt_numbers_ident() -> Ident {
+    Ident::new("irq", Span::call_site())
+}
+
+pub fn interrupt_ident() -> Ident {
+    let span = Span::call_site();
+    Ident::new("Interrupt", span)
+}
+
+/// The interrupts are pended by their number, see `extra_modules`
+pub fn interrupt_mod(_app: &App) -> TokenStream2 {
+    let numbers = interrupt_numbers_ident();
+    quote!(#numbers)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn impl_mutex(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    cfgs: &[Attribute],
+    resources_prefix: bool,
+    name: &Ident,
+    ty: &TokenStream2,
+    ceiling: u8,
+    ptr: &TokenStream2,
+) -> TokenStream2 {
+    let path = if resources_prefix {
+        quote!(shared_resources::#name)
+    } else {
+        quote!(#name)
+    };
+    quote!(
+        #(#cfgs)*
+        impl<'a> rtic::Mutex for #path<'a> {
+            type T = #ty;
+
+            #[inline(always)]
+            fn lock<RTIC_INTERNAL_R>(&mut self, f: impl FnOnce(&mut #ty) -> RTIC_INTERNAL_R) -> RTIC_INTERNAL_R {
+                /// Priority ceiling
+                const CEILING: u8 = #ceiling;
+                unsafe {
+                    rtic::export::lock(
+                        #ptr,
+                        CEILING,
+                        f,
+                    )
+                }
+            }
+        }
+    )
+}
+
+pub fn extra_assertions(_: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// The dispatchers are software interrupts of the soft SLIC generated here, one per priority level
+/// of the software tasks
+pub fn pre_init_preprocessing(app: &mut App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    app.args.core = false; // There are no Cortex-M like core peripherals
+    if !app.args.dispatchers.is_empty() {
+        return Err(parse::Error::new(
+            Span::call_site(),
+            "the soft SLIC backend dispatches through software interrupts; remove the `dispatchers` argument from `#[app]`",
+        ));
+    }
+
+    let soft_priorities = app
+        .software_tasks
+        .values()
+        .map(|task| task.args.priority)
+        .filter(|prio| *prio > 0)
+        .collect::<HashSet<_>>();
+
+    for i in 0..soft_priorities.len() {
+        let dispatcher_ident = Ident::new(&format!("__RTICDispatcher{i}"), Span::call_site());
+        app.args.dispatchers.insert(
+            dispatcher_ident,
+            Dispatcher {
+                attrs: vec![],
+                executor: None,
+                priorities: None,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+pub fn pre_init_checks(_app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn pre_init_enable_interrupts(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    // Clear the software priority and enable the machine external interrupt of the hart
+    vec![quote!(rtic::export::configure();)]
+}
+
+pub fn post_init_checks(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn architecture_specific_analysis(app: &App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    if app.args.backend.is_none() {
+        return Err(parse::Error::new(
+            Span::call_site(),
+            "the soft SLIC backend needs `backend = [claim = path::to::claim, complete = path::to::complete]`",
+        ));
+    }
+
+    if app.args.dispatchers.len() + app.hardware_tasks.len() > MAX_INTERRUPTS {
+        return Err(parse::Error::new(
+            Span::call_site(),
+            format!(
+                "the soft SLIC numbers at most {MAX_INTERRUPTS} dispatchers and hardware tasks"
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn interrupt_entry(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn interrupt_exit(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn check_stack_overflow_before_init(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+) -> Vec<TokenStream2> {
+    vec![quote!(
+        // Check for stack overflow using symbols from `riscv-rt`.
+        extern "C" {
+            pub static _stack_start: u32;
+            pub static _ebss: u32;
+        }
+
+        let stack_start = &_stack_start as *const _ as u32;
+        let ebss = &_ebss as *const _ as u32;
+
+        if stack_start > ebss {
+            // No flip-link usage, check the SP for overflow.
+            if rtic::export::read_sp() <= ebss {
+                panic!("Stack overflow after allocating executors");
+            }
+        }
+    )]
+}
+
+pub fn async_entry(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    _dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let max = if let Some(max) = analysis.max_async_prio {
+        max
+    } else {
+        // No limit
+        max_priority(app.args.backend.as_ref(), Span::call_site()).expect("UNREACHABLE")
+    };
+
+    vec![quote!(
+        /// Holds the maximum priority level for use by async HAL drivers.
+        #[no_mangle]
+        static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
+    )]
+}
+
+pub fn handler_config(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    _dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// The interrupts of the soft SLIC: the used dispatchers followed by the hardware tasks, and the
+/// `MachineExternal` handler called by `riscv-rt`, pending the claimed sources and running the
+/// pending interrupts above the current priority
+///
+/// A hardware task completes its source once it has run. `irq` is public, so the interrupts can be
+/// requested by their number with `rtic::swi::request`.
+pub fn extra_modules(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let backend = app.args.backend.as_ref().expect("UNREACHABLE");
+    let (claim, complete) = (&backend.claim, &backend.complete);
+    let numbers = interrupt_numbers_ident();
+    let interrupt = interrupt_ident();
+    let rt_err = util::rt_err_ident();
+    let no_cfgs = Vec::new();
+
+    let dispatchers = app
+        .args
+        .dispatchers
+        .keys()
+        .filter_map(|name| {
+            analysis
+                .interrupts
+                .iter()
+                .find(|(_, (dispatcher, _))| dispatcher == name)
+                .map(|(priority, _)| (&no_cfgs, *priority, name, None))
+        })
+        .collect::<Vec<_>>();
+    let (hw_cfgs, hw_binds): (Vec<_>, Vec<_>) = app
+        .hardware_tasks
+        .values()
+        .map(|task| (&task.cfgs, &task.args.binds))
+        .unzip();
+    let interrupts = dispatchers.into_iter().chain(app.hardware_tasks.values().map(|task| {
+        let binds = &task.args.binds;
+        let complete = quote!(#complete(#rt_err::#interrupt::#binds as u16););
+        (&task.cfgs, task.args.priority, binds, Some(complete))
+    }));
+
+    let (mut cfgs, mut priorities, mut names, mut completes) = (vec![], vec![], vec![], vec![]);
+    for (cfg, priority, name, complete) in interrupts {
+        cfgs.push(cfg);
+        priorities.push(priority);
+        names.push(name);
+        completes.push(complete);
+    }
+    let indices = (0..names.len() as u16).collect::<Vec<_>>();
+
+    vec![quote!(
+        #[doc(hidden)]
+        #[no_mangle]
+        static __RTIC_SOFT_SLIC: rtic::export::SoftSlicConfig = rtic::export::SoftSlicConfig {
+            interrupts: &[#((#priorities, || {
+                #(#cfgs)*
+                unsafe {
+                    #names();
+                    #completes
+                };
+            })),*],
+        };
+
+        /// The numbers of the interrupts of the soft SLIC, requested with `rtic::swi::request`
+        #[allow(non_upper_case_globals, dead_code)]
+        pub mod #numbers {
+            #(
+                #(#cfgs)*
+                pub const #names: u16 = #indices;
+            )*
+        }
+
+        #[doc(hidden)]
+        #[no_mangle]
+        unsafe extern "C" fn MachineExternal() {
+            while let ::core::option::Option::Some(source) = #claim() {
+                #(
+                    #(#hw_cfgs)*
+                    if source == #rt_err::#interrupt::#hw_binds as u16 {
+                        rtic::export::pend(#numbers::#hw_binds);
+                    }
+                )*
+            }
+
+            rtic::export::on_external();
+        }
+    )]
+}
+
+/// The attributes of the generated `main`, exported as the entry point of the runtime
+pub fn main_linkage(_app: &App) -> TokenStream2 {
+    quote!(#[no_mangle])
+}
+
+pub fn priority_ceiling(_app: &App, _binds: &Ident) -> Option<TokenStream2> {
+    None
+}
+
+pub fn max_priority_const(_app: &App) -> Option<TokenStream2> {
+    Some(quote!(rtic::export::MAX_PRIORITY))
+}
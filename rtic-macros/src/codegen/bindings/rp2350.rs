@@ -0,0 +1,280 @@
+use crate::{
+    analyze::Analysis as CodegenAnalysis,
+    codegen::util,
+    syntax::{analyze::Analysis as SyntaxAnalysis, ast::App, backend::max_priority},
+};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use std::collections::HashSet;
+use syn::{parse, Attribute, Ident};
+
+/// The dispatchers and hardware tasks with their priority
+fn interrupts<'a>(
+    app: &'a App,
+    analysis: &'a CodegenAnalysis,
+) -> impl Iterator<Item = (u8, &'a Ident)> {
+    let dispatchers = analysis.interrupts.iter().map(|(p, (id, _))| (*p, id));
+    let hardware_tasks = app
+        .hardware_tasks
+        .values()
+        .map(|task| (task.args.priority, &task.args.binds));
+
+    dispatchers.chain(hardware_tasks)
+}
+
+/// The type holding the interrupt numbers of the dispatchers, as pended by the spawns
+fn irq_numbers_ident() -> Ident {
+    util::mark_internal_name("Irq")
+}
+
+/// Generates a `Mutex` implementation
+///
+/// The lock masks the interrupts of all priorities up to the ceiling, computed here as the
+/// ceiling is known.
+#[allow(clippy::too_many_arguments)]
+pub fn impl_mutex(
+    app: &App,
+    analysis: &CodegenAnalysis,
+    cfgs: &[Attribute],
+    resources_prefix: bool,
+    name: &Ident,
+    ty: &TokenStream2,
+    ceiling: u8,
+    ptr: &TokenStream2,
+) -> TokenStream2 {
+    let path = if resources_prefix {
+        quote!(shared_resources::#name)
+    } else {
+        quote!(#name)
+    };
+
+    let device = &app.args.device;
+    let masked = interrupts(app, analysis)
+        .filter(|(priority, _)| *priority <= ceiling)
+        .map(|(_, name)| quote!(#device::Interrupt::#name as u16));
+
+    quote!(
+        #(#cfgs)*
+        impl<'a> rtic::Mutex for #path<'a> {
+            type T = #ty;
+
+            #[inline(always)]
+            fn lock<RTIC_INTERNAL_R>(&mut self, f: impl FnOnce(&mut #ty) -> RTIC_INTERNAL_R) -> RTIC_INTERNAL_R {
+                /// Priority ceiling
+                const CEILING: u8 = #ceiling;
+                const MASK: rtic::export::Mask = rtic::export::create_mask([#(#masked),*]);
+
+                unsafe {
+                    rtic::export::lock(
+                        #ptr,
+                        CEILING,
+                        MASK,
+                        f,
+                    )
+                }
+            }
+        }
+    )
+}
+
+pub fn interrupt_ident() -> Ident {
+    let span = Span::call_site();
+    Ident::new("Interrupt", span)
+}
+
+/// The dispatchers are pended by their number, see `extra_modules`
+pub fn interrupt_mod(_app: &App) -> TokenStream2 {
+    let irq_numbers = irq_numbers_ident();
+    quote!(#irq_numbers)
+}
+
+pub fn extra_assertions(_: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn pre_init_preprocessing(app: &mut App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    app.args.core = false; // There are no Cortex-M like core peripherals
+    Ok(())
+}
+
+pub fn pre_init_checks(app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    let mut stmts = vec![];
+    // check that all dispatchers exists in the `Interrupt` enumeration regardless of whether
+    // they are used or not
+    let interrupt = interrupt_ident();
+    let rt_err = util::rt_err_ident();
+
+    for name in app.args.dispatchers.keys() {
+        stmts.push(quote!(let _ = #rt_err::#interrupt::#name;));
+    }
+    stmts
+}
+
+pub fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let interrupt = interrupt_ident();
+    let rt_err = util::rt_err_ident();
+
+    // Set the priorities in `meipra` and enable the interrupts in `meiea`
+    interrupts(app, analysis)
+        .map(|(priority, name)| {
+            let es = format!(
+                "Maximum priority used by interrupt vector '{name}' is more than supported by hardware"
+            );
+            quote!(
+                const _: () =  if rtic::export::MAX_PRIORITY < #priority { ::core::panic!(#es); };
+                rtic::export::enable(#rt_err::#interrupt::#name as u16, #priority);
+            )
+        })
+        .collect()
+}
+
+/// Checks in debug builds that the interrupts enabled before `init` are still configured the
+/// same way after it
+pub fn post_init_checks(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let interrupt = interrupt_ident();
+    let rt_err = util::rt_err_ident();
+
+    interrupts(app, analysis)
+        .map(|(priority, name)| {
+            let es = format!(
+                "interrupt `{name}` was reconfigured during `init`, RTIC assumes it enabled at priority {priority}"
+            );
+            quote!(
+                ::core::assert!(
+                    rtic::export::is_enabled(#rt_err::#interrupt::#name as u16, #priority),
+                    #es
+                );
+            )
+        })
+        .collect()
+}
+
+pub fn architecture_specific_analysis(app: &App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    // Check that there are enough external interrupts to dispatch the software tasks and the timer
+    // queue handler
+    let mut first = None;
+    let priorities = app
+        .software_tasks
+        .iter()
+        .map(|(name, task)| {
+            first = Some(name);
+            task.args.priority
+        })
+        .filter(|prio| *prio > 0)
+        .collect::<HashSet<_>>();
+
+    let need = priorities.len();
+    let given = app.args.dispatchers.len();
+    if need > given {
+        let s = {
+            format!(
+                "not enough interrupts to dispatch \
+                    all software tasks (need: {need}; given: {given})"
+            )
+        };
+
+        // If not enough tasks and first still is None, may cause
+        // "custom attribute panicked" due to unwrap on None
+        return Err(parse::Error::new(first.unwrap().span(), s));
+    }
+
+    Ok(())
+}
+
+pub fn interrupt_entry(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn interrupt_exit(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn check_stack_overflow_before_init(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+) -> Vec<TokenStream2> {
+    vec![quote!(
+        // Check for stack overflow using symbols from `riscv-rt`.
+        extern "C" {
+            pub static _stack_start: u32;
+            pub static _ebss: u32;
+        }
+
+        let stack_start = &_stack_start as *const _ as u32;
+        let ebss = &_ebss as *const _ as u32;
+
+        if stack_start > ebss {
+            // No flip-link usage, check the SP for overflow.
+            if rtic::export::read_sp() <= ebss {
+                panic!("Stack overflow after allocating executors");
+            }
+        }
+    )]
+}
+
+pub fn async_entry(
+    app: &App,
+    _analysis: &CodegenAnalysis,
+    dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    // A forced interrupt stays pending until the force is cleared
+    let device = &app.args.device;
+    vec![quote!(
+        rtic::export::unpend(#device::Interrupt::#dispatcher_name as u16);
+    )]
+}
+
+pub fn async_prio_limit(_app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let max = if let Some(max) = analysis.max_async_prio {
+        max
+    } else {
+        // No limit
+        max_priority(None, Span::call_site()).expect("UNREACHABLE")
+    };
+
+    vec![quote!(
+        /// Holds the maximum priority level for use by async HAL drivers.
+        #[no_mangle]
+        static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
+    )]
+}
+
+pub fn handler_config(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    _dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// The interrupt numbers of the dispatchers, `rtic::export::pend` takes the number as the PAC
+/// enumeration implements no trait of `rtic` to get it from
+pub fn extra_modules(app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let device = &app.args.device;
+    let irq_numbers = irq_numbers_ident();
+    let dispatchers = app.args.dispatchers.keys().collect::<Vec<_>>();
+
+    vec![quote!(
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        struct #irq_numbers;
+
+        #[allow(non_upper_case_globals)]
+        impl #irq_numbers {
+            #(const #dispatchers: u16 = #device::Interrupt::#dispatchers as u16;)*
+        }
+    )]
+}
+
+/// The attributes of the generated `main`, exported as the entry point of the runtime
+pub fn main_linkage(_app: &App) -> TokenStream2 {
+    quote!(#[no_mangle])
+}
+
+pub fn priority_ceiling(_app: &App, _binds: &Ident) -> Option<TokenStream2> {
+    None
+}
+
+pub fn max_priority_const(_app: &App) -> Option<TokenStream2> {
+    Some(quote!(rtic::export::MAX_PRIORITY))
+}
@@ -46,6 +46,10 @@ pub fn pre_init_enable_interrupts(app: &App, analysis: &CodegenAnalysis) -> Vec<
     vec![]
 }
 
+pub fn post_init_checks(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
 pub fn architecture_specific_analysis(app: &App, analysis: &SyntaxAnalysis) -> parse::Result<()> {
     Ok(())
 }
@@ -88,3 +92,20 @@ pub fn handler_config(
 pub fn extra_modules(app: &App, analysis: &SyntaxAnalysis) -> Vec<TokenStream2> {
     vec![]
 }
+
+/// The attributes of the generated `main`, exported as the entry point of the runtime
+pub fn main_linkage(_app: &App) -> TokenStream2 {
+    quote!(#[no_mangle])
+}
+
+/// The highest logical priority `set_priority` can move the hardware task bound to `binds` to,
+/// `None` if the backend keeps its priority fixed
+pub fn priority_ceiling(_app: &App, _binds: &Ident) -> Option<TokenStream2> {
+    None
+}
+
+/// The highest priority of the backend, `MAX_PRIORITY` of the application module, `None` to
+/// leave it out
+pub fn max_priority_const(_app: &App) -> Option<TokenStream2> {
+    None
+}
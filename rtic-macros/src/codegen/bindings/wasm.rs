@@ -0,0 +1,266 @@
+use crate::{
+    analyze::Analysis as CodegenAnalysis,
+    syntax::{
+        analyze::Analysis as SyntaxAnalysis,
+        ast::{App, Dispatcher},
+        backend::max_priority,
+    },
+};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use std::collections::HashSet;
+use syn::{parse, Attribute, Ident};
+
+/// The interrupts are numbered by a bit of a `u64`
+const MAX_INTERRUPTS: usize = 64;
+
+/// The module holding the numbers of the interrupts, see `extra_modules`
+fn interrupt_numbers_ident() -> Ident {
+    Ident::new("irq", Span::call_site())
+}
+
+pub fn interrupt_ident() -> Ident {
+    let span = Span::call_site();
+    Ident::new("Interrupt", span)
+}
+
+/// The interrupts are pended by their number, see `extra_modules`
+pub fn interrupt_mod(_app: &App) -> TokenStream2 {
+    let numbers = interrupt_numbers_ident();
+    quote!(#numbers)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn impl_mutex(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    cfgs: &[Attribute],
+    resources_prefix: bool,
+    name: &Ident,
+    ty: &TokenStream2,
+    ceiling: u8,
+    ptr: &TokenStream2,
+) -> TokenStream2 {
+    let path = if resources_prefix {
+        quote!(shared_resources::#name)
+    } else {
+        quote!(#name)
+    };
+    quote!(
+        #(#cfgs)*
+        impl<'a> rtic::Mutex for #path<'a> {
+            type T = #ty;
+
+            #[inline(always)]
+            fn lock<RTIC_INTERNAL_R>(&mut self, f: impl FnOnce(&mut #ty) -> RTIC_INTERNAL_R) -> RTIC_INTERNAL_R {
+                /// Priority ceiling
+                const CEILING: u8 = #ceiling;
+                unsafe {
+                    rtic::export::lock(
+                        #ptr,
+                        CEILING,
+                        f,
+                    )
+                }
+            }
+        }
+    )
+}
+
+pub fn extra_assertions(_: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// The dispatchers of an application written for another backend are kept, without them one is
+/// generated per priority level of the software tasks
+///
+/// Without priority 0 tasks, `main` waits on the host after `init` the way `sleep_on_exit` does,
+/// with them the zero priority dispatcher waits on it in `idle_wait`.
+pub fn pre_init_preprocessing(app: &mut App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    app.args.core = false; // There are no Cortex-M like core peripherals
+    app.args.sleep_on_exit = app.idle.is_none()
+        && app
+            .software_tasks
+            .values()
+            .all(|task| task.args.priority > 0);
+
+    if !app.args.dispatchers.is_empty() {
+        return Ok(());
+    }
+
+    let soft_priorities = app
+        .software_tasks
+        .values()
+        .map(|task| task.args.priority)
+        .filter(|prio| *prio > 0)
+        .collect::<HashSet<_>>();
+
+    for i in 0..soft_priorities.len() {
+        let dispatcher_ident = Ident::new(&format!("__RTICDispatcher{i}"), Span::call_site());
+        app.args.dispatchers.insert(
+            dispatcher_ident,
+            Dispatcher {
+                attrs: vec![],
+                executor: None,
+                priorities: None,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+pub fn pre_init_checks(_app: &App, _: &SyntaxAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn pre_init_enable_interrupts(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn post_init_checks(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn architecture_specific_analysis(app: &App, _analysis: &SyntaxAnalysis) -> parse::Result<()> {
+    if let Some(idle) = &app.idle {
+        return Err(parse::Error::new(
+            idle.name.span(),
+            "`idle` is not supported by the wasm backend, `main` waits on the host in its place",
+        ));
+    }
+
+    if app.args.test {
+        return Err(parse::Error::new(
+            Span::call_site(),
+            "`test` is not supported by the wasm backend, the harness spins without waiting on the host",
+        ));
+    }
+
+    if app.args.dispatchers.len() + app.hardware_tasks.len() > MAX_INTERRUPTS {
+        return Err(parse::Error::new(
+            Span::call_site(),
+            format!(
+                "the wasm backend numbers at most {MAX_INTERRUPTS} dispatchers and hardware tasks"
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn interrupt_entry(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn interrupt_exit(_app: &App, _analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn check_stack_overflow_before_init(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn async_entry(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    _dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+pub fn async_prio_limit(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let max = if let Some(max) = analysis.max_async_prio {
+        max
+    } else {
+        // No limit
+        max_priority(app.args.backend.as_ref(), Span::call_site()).expect("UNREACHABLE")
+    };
+
+    vec![quote!(
+        /// Holds the maximum priority level for use by async HAL drivers.
+        #[no_mangle]
+        static RTIC_ASYNC_MAX_LOGICAL_PRIO: u8 = #max;
+    )]
+}
+
+pub fn handler_config(
+    _app: &App,
+    _analysis: &CodegenAnalysis,
+    _dispatcher_name: Ident,
+) -> Vec<TokenStream2> {
+    vec![]
+}
+
+/// The interrupts: the used dispatchers followed by the hardware tasks
+///
+/// The host pends the hardware tasks by the numbers of `irq`, through the exported `rtic_pend`.
+pub fn extra_modules(app: &App, analysis: &CodegenAnalysis) -> Vec<TokenStream2> {
+    let numbers = interrupt_numbers_ident();
+    let no_cfgs = Vec::new();
+
+    let mut interrupts = app
+        .args
+        .dispatchers
+        .keys()
+        .filter_map(|name| {
+            analysis
+                .interrupts
+                .iter()
+                .find(|(_, (dispatcher, _))| dispatcher == name)
+                .map(|(priority, _)| (&no_cfgs, *priority, name))
+        })
+        .collect::<Vec<_>>();
+    interrupts.extend(
+        app.hardware_tasks
+            .values()
+            .map(|task| (&task.cfgs, task.args.priority, &task.args.binds)),
+    );
+
+    let (mut cfgs, mut priorities, mut names) = (vec![], vec![], vec![]);
+    for (cfg, priority, name) in interrupts {
+        cfgs.push(cfg);
+        priorities.push(priority);
+        names.push(name);
+    }
+    let indices = (0..names.len() as u16).collect::<Vec<_>>();
+
+    vec![quote!(
+        #[doc(hidden)]
+        #[no_mangle]
+        static __RTIC_WASM: rtic::export::WasmConfig = rtic::export::WasmConfig {
+            interrupts: &[#((#priorities, || {
+                #(#cfgs)*
+                unsafe { #names() };
+            })),*],
+        };
+
+        /// The numbers of the interrupts, pended with `rtic::pend` or by the host
+        #[allow(non_upper_case_globals, dead_code)]
+        pub mod #numbers {
+            #(
+                #(#cfgs)*
+                pub const #names: u16 = #indices;
+            )*
+        }
+    )]
+}
+
+/// The generated `main` is exported as `rtic_main`, called by the host once the module is
+/// instantiated
+///
+/// `main` is not used as the name, `wasm-ld` renames it for the C runtime.
+pub fn main_linkage(_app: &App) -> TokenStream2 {
+    quote!(#[export_name = "rtic_main"])
+}
+
+pub fn priority_ceiling(_app: &App, _binds: &Ident) -> Option<TokenStream2> {
+    None
+}
+
+pub fn max_priority_const(_app: &App) -> Option<TokenStream2> {
+    Some(quote!(rtic::export::MAX_PRIORITY))
+}
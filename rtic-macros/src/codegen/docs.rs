@@ -0,0 +1,270 @@
+use crate::analyze::Analysis;
+use crate::syntax::{
+    analyze::Ownership,
+    ast::{App, LocalResources, SharedResources},
+    latency,
+};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+
+/// A row of the task table
+struct TaskRow<'a> {
+    name: String,
+    kind: &'static str,
+    priority: u8,
+    trigger: String,
+    shared: Option<&'a SharedResources>,
+    local: &'a LocalResources,
+}
+
+/// Generates the rustdoc of the application module
+///
+/// The documentation describes the concurrency architecture of the application: its tasks and
+/// their priorities, the resources each task can access and which interrupt dispatches which
+/// priority level.
+pub fn codegen(app: &App, analysis: &Analysis) -> Vec<TokenStream2> {
+    let rows = task_rows(app, analysis);
+
+    let mut lines = vec![
+        "The RTIC application module".to_string(),
+        String::new(),
+        "# Tasks".to_string(),
+        String::new(),
+        "| Task | Kind | Priority | Triggered by | Shared resources | Local resources |"
+            .to_string(),
+        "|------|------|----------|--------------|------------------|-----------------|"
+            .to_string(),
+    ];
+
+    for row in &rows {
+        let shared = row.shared.map_or_else(String::new, |shared| {
+            join(shared.iter().map(|(name, access)| {
+                if access.is_shared() {
+                    format!("`&{name}`")
+                } else {
+                    format!("`{name}`")
+                }
+            }))
+        });
+        let local = join(row.local.keys().map(|name| format!("`{name}`")));
+
+        lines.push(format!(
+            "| `{}` | {} | {} | {} | {} | {} |",
+            row.name, row.kind, row.priority, row.trigger, shared, local
+        ));
+    }
+
+    let mut deadlines = app
+        .hardware_tasks
+        .iter()
+        .map(|(name, task)| (name, task.args.deadline, task.args.priority))
+        .chain(
+            app.software_tasks
+                .iter()
+                .map(|(name, task)| (name, task.args.deadline, task.args.priority)),
+        )
+        .filter_map(|(name, deadline, priority)| Some((name, deadline?, priority)))
+        .collect::<Vec<_>>();
+    if !deadlines.is_empty() {
+        deadlines.sort_by_key(|(_, deadline, _)| *deadline);
+
+        lines.push(String::new());
+        lines.push("# Deadline-monotonic priorities".to_string());
+        lines.push(String::new());
+        lines.push("| Task | Deadline | Priority |".to_string());
+        lines.push("|------|----------|----------|".to_string());
+
+        for (name, deadline, priority) in deadlines {
+            lines.push(format!("| `{name}` | {deadline} µs | {priority} |"));
+        }
+    }
+
+    let spawners = analysis
+        .spawns
+        .keys()
+        .filter(|spawner| !analysis.spawned_by(spawner).is_empty())
+        .collect::<Vec<_>>();
+    if !spawners.is_empty() {
+        lines.push(String::new());
+        lines.push("# Task graph".to_string());
+        lines.push(String::new());
+
+        for spawner in spawners {
+            let spawned = join(
+                analysis
+                    .spawned_by(spawner)
+                    .into_iter()
+                    .map(|task| format!("`{task}`")),
+            );
+            lines.push(format!("- `{spawner}` spawns {spawned}"));
+        }
+
+        for cycle in analysis.spawn_cycles() {
+            let path = cycle
+                .iter()
+                .map(|task| format!("`{task}`"))
+                .collect::<Vec<_>>()
+                .join(" → ");
+            lines.push(String::new());
+            lines.push(format!("**Warning:** tasks spawn each other in a cycle: {path}"));
+        }
+    }
+
+    if !app.shared_resources.is_empty() {
+        lines.push(String::new());
+        lines.push("# Shared resources".to_string());
+        lines.push(String::new());
+        lines.push("| Resource | Ceiling | Accessed by |".to_string());
+        lines.push("|----------|---------|-------------|".to_string());
+
+        for name in app.shared_resources.keys() {
+            let ceiling = match analysis.ownerships.get(name) {
+                Some(Ownership::Owned { priority } | Ownership::CoOwned { priority }) => {
+                    format!("{priority} (no locking needed)")
+                }
+                Some(Ownership::Contended { ceiling }) => ceiling.to_string(),
+                None => "unused".to_string(),
+            };
+            let users = join(
+                rows.iter()
+                    .filter(|row| row.shared.is_some_and(|shared| shared.contains_key(name)))
+                    .map(|row| format!("`{}`", row.name)),
+            );
+
+            lines.push(format!("| `{name}` | {ceiling} | {users} |"));
+        }
+    }
+
+    let budgets = app
+        .hardware_tasks
+        .iter()
+        .filter_map(|(name, task)| Some((name, task.args.priority, task.args.max_latency?)))
+        .collect::<Vec<_>>();
+    if !budgets.is_empty() {
+        lines.push(String::new());
+        lines.push("# Latency budgets".to_string());
+        lines.push(String::new());
+        lines.push("| Task | Priority | `max_latency` | Worst blocking | Blocked by |".to_string());
+        lines.push("|------|----------|---------------|----------------|------------|".to_string());
+
+        for (name, priority, budget) in budgets {
+            // Checked during parsing, an unknown blocking was rejected
+            let (worst, by) = match latency::blocking(app, analysis, priority) {
+                Ok(Some(worst)) => (
+                    format!("{} µs", worst.wcet),
+                    format!(
+                        "`{}` locking `{}` (ceiling {})",
+                        worst.task, worst.resource, worst.ceiling
+                    ),
+                ),
+                _ => ("0 µs".to_string(), String::new()),
+            };
+
+            lines.push(format!(
+                "| `{name}` | {priority} | {budget} µs | {worst} | {by} |"
+            ));
+        }
+    }
+
+    if !analysis.interrupts.is_empty() {
+        lines.push(String::new());
+        lines.push("# Dispatchers".to_string());
+        lines.push(String::new());
+        lines.push("| Interrupt | Priority | Allowed | Tasks |".to_string());
+        lines.push("|-----------|----------|---------|-------|".to_string());
+
+        for (interrupt, dispatcher) in &app.args.dispatchers {
+            let allowed = match &dispatcher.priorities {
+                Some(range) if range.start() == range.end() => format!("`=> {}`", range.start()),
+                Some(range) => format!("`=> {}..={}`", range.start(), range.end()),
+                None => "any".to_string(),
+            };
+            let assigned = analysis
+                .interrupts
+                .iter()
+                .find(|(_, (name, _))| name == interrupt);
+
+            let (priority, tasks) = match assigned {
+                Some((priority, _)) => (
+                    priority.to_string(),
+                    analysis
+                        .channels
+                        .get(priority)
+                        .map(|channel| {
+                            join(channel.tasks.iter().map(|task| format!("`{task}`")))
+                        })
+                        .unwrap_or_default(),
+                ),
+                None => ("unused".to_string(), String::new()),
+            };
+
+            lines.push(format!(
+                "| `{interrupt}` | {priority} | {allowed} | {tasks} |"
+            ));
+        }
+    }
+
+    lines
+        .into_iter()
+        .map(|line| {
+            let line = if line.is_empty() { line } else { format!(" {line}") };
+            quote!(#[doc = #line])
+        })
+        .collect()
+}
+
+fn task_rows<'a>(app: &'a App, analysis: &Analysis) -> Vec<TaskRow<'a>> {
+    let mut rows = vec![TaskRow {
+        name: app.init.name.to_string(),
+        kind: "init",
+        priority: 0,
+        trigger: "reset".to_string(),
+        shared: None,
+        local: &app.init.args.local_resources,
+    }];
+
+    if let Some(idle) = &app.idle {
+        rows.push(TaskRow {
+            name: idle.name.to_string(),
+            kind: "idle",
+            priority: 0,
+            trigger: "`main`".to_string(),
+            shared: Some(&idle.args.shared_resources),
+            local: &idle.args.local_resources,
+        });
+    }
+
+    for (name, task) in &app.hardware_tasks {
+        rows.push(TaskRow {
+            name: name.to_string(),
+            kind: "hardware",
+            priority: task.args.priority,
+            trigger: format!("`{}`", task.args.binds),
+            shared: Some(&task.args.shared_resources),
+            local: &task.args.local_resources,
+        });
+    }
+
+    for (name, task) in &app.software_tasks {
+        let priority = task.args.priority;
+        let trigger = match analysis.interrupts.get(&priority) {
+            Some((interrupt, _)) => format!("`{interrupt}` (dispatcher)"),
+            None => "`main` (executor)".to_string(),
+        };
+
+        rows.push(TaskRow {
+            name: name.to_string(),
+            kind: "software",
+            priority,
+            trigger,
+            shared: Some(&task.args.shared_resources),
+            local: &task.args.local_resources,
+        });
+    }
+
+    rows
+}
+
+fn join(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<_>>().join(", ")
+}
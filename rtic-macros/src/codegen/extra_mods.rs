@@ -1,9 +1,19 @@
-use super::bindings::extra_modules;
+use super::bindings::{extra_modules, max_priority_const};
 use crate::analyze::Analysis;
 use crate::syntax::ast::App;
 use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
 
 /// Generates code that runs before `#[init]`
 pub fn codegen(app: &App, analysis: &Analysis) -> Vec<TokenStream2> {
-    extra_modules(app, analysis)
+    let mut items = extra_modules(app, analysis);
+
+    if let Some(max) = max_priority_const(app) {
+        items.push(quote!(
+            /// The highest priority of the backend, what `priority = max` stands for
+            pub const MAX_PRIORITY: u8 = #max;
+        ));
+    }
+
+    items
 }
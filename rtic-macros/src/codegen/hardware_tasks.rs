@@ -1,9 +1,12 @@
-use crate::syntax::{ast::App, Context};
+use crate::syntax::{
+    ast::{App, HardwareTask},
+    Context,
+};
 use crate::{
     analyze::Analysis,
     codegen::{
-        bindings::{interrupt_entry, interrupt_exit, handler_config},
-        local_resources_struct, module, shared_resources_struct,
+        bindings::{handler_config, interrupt_entry, interrupt_exit, interrupt_ident, priority_ceiling},
+        local_resources_struct, module, shared_resources_struct, util,
     },
 };
 use proc_macro2::TokenStream as TokenStream2;
@@ -14,6 +17,7 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
     let mut mod_app = vec![];
     let mut root = vec![];
     let mut user_tasks = vec![];
+    let mut bounds = vec![];
 
     for (name, task) in &app.hardware_tasks {
         let symbol = task.args.binds.clone();
@@ -23,6 +27,17 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
         let entry_stmts = interrupt_entry(app, analysis);
         let exit_stmts = interrupt_exit(app, analysis);
         let config = handler_config(app, analysis, symbol.clone());
+        let (nesting_enter, nesting_exit) = util::nesting_tracking(app);
+
+        // A task `set_priority` can move takes the general path of `run`, which restores the
+        // `BASEPRI` it found instead of assuming the one of priority 1
+        let bound = priority_ceiling(app, &symbol)
+            .and_then(|ceiling| Some((ceiling, util::priority_bound(app, analysis, name)?)));
+        let run_priority = match &bound {
+            Some((_, bound)) if *bound > priority => *bound,
+            _ => priority,
+        };
+        bounds.push((task, bound));
 
         mod_app.push(quote!(
             #[allow(non_snake_case)]
@@ -33,13 +48,15 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
             unsafe fn #symbol() {
                 #(#entry_stmts)*
 
-                const PRIORITY: u8 = #priority;
+                const PRIORITY: u8 = #run_priority;
 
+                #nesting_enter
                 rtic::export::run(PRIORITY, || {
                     #name(
                         #name::Context::new()
                     )
                 });
+                #nesting_exit
 
                 #(#exit_stmts)*
             }
@@ -88,6 +105,8 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
         }
     }
 
+    mod_app.push(set_priority(app, &bounds));
+
     quote!(
         #(#mod_app)*
 
@@ -96,3 +115,61 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
         #(#user_tasks)*
     )
 }
+
+/// `set_priority`, moving a hardware task to another priority at runtime
+///
+/// A task can be moved anywhere from priority 1 up to the lowest ceiling of the shared resources
+/// it accesses, which keeps the ceilings of the resources above the priorities of all their
+/// users. Not generated if the backend keeps the priorities of all tasks fixed.
+fn set_priority(app: &App, tasks: &[(&HardwareTask, Option<(TokenStream2, u8)>)]) -> TokenStream2 {
+    if tasks.iter().all(|(task, _)| priority_ceiling(app, &task.args.binds).is_none()) {
+        return quote!();
+    }
+
+    let rt_err = util::rt_err_ident();
+    let interrupt = interrupt_ident();
+    let arms = tasks.iter().filter_map(|(task, bound)| {
+        let binds = &task.args.binds;
+        let cfgs = &task.cfgs;
+        // Exceptions are not interrupts of the device
+        priority_ceiling(app, binds)?;
+
+        // Only the arms accepting the priority move the task
+        let arm = match bound {
+            Some((ceiling, bound)) => quote!({
+                let max = if #bound < #ceiling { #bound } else { #ceiling };
+                if priority == 0 || priority > max {
+                    return Err(rtic::PriorityError::OutOfRange { max });
+                }
+
+                // SAFETY: The priority is within the ceilings of the resources of the task
+                unsafe { rtic::export::set_priority(interrupt, priority) };
+                Ok(())
+            }),
+            None => quote!(Err(rtic::PriorityError::Fixed)),
+        };
+
+        Some(quote!(
+            #(#cfgs)*
+            #rt_err::#interrupt::#binds => #arm,
+        ))
+    });
+
+    quote!(
+        /// Moves the hardware task bound to `interrupt` to the logical `priority`
+        ///
+        /// The priority must be above 0 and at most the lowest ceiling of the shared resources
+        /// the task accesses, so the locks of the other users keep excluding it. A task accessing
+        /// a `#[lock_free]` resource stays at its priority.
+        pub fn set_priority(
+            interrupt: #rt_err::#interrupt,
+            priority: u8,
+        ) -> Result<(), rtic::PriorityError> {
+            #[allow(unreachable_patterns)]
+            match interrupt {
+                #(#arms)*
+                _ => Err(rtic::PriorityError::Unbound),
+            }
+        }
+    )
+}
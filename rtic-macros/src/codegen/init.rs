@@ -3,7 +3,7 @@ use quote::quote;
 
 use crate::{
     analyze::Analysis,
-    codegen::{local_resources_struct, module},
+    codegen::{local_resources_struct, module, util},
     syntax::{ast::App, Context},
 };
 
@@ -22,9 +22,11 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
     let local = &init.user_local_struct;
     let local_vis = &app.local_resources_vis;
 
+    // `#[cross_core]` resources are initialized by the core holding them
     let shared_resources: Vec<_> = app
         .shared_resources
         .iter()
+        .filter(|(_, v)| v.properties.cross_core.is_none_or(|c| c.stored_by.is_none()))
         .map(|(k, v)| {
             let ty = &v.ty;
             let cfgs = &v.cfgs;
@@ -93,6 +95,10 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
         mod_app = Some(constructor);
     }
 
+    if util::has_resource_builder(app) {
+        root_init.push(resource_builder(app));
+    }
+
     root_init.push(module::codegen(Context::Init, app, analysis));
 
     quote!(
@@ -103,3 +109,134 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
         #user_init
     )
 }
+
+/// `init::Resources`, constructing the resources in the order of their `#[depends_on(..)]`
+///
+/// A type parameter per resource, `init::Unset` or `init::Set`, tracks which ones are
+/// constructed. The constructor of a resource only exists once its dependencies are set, and
+/// `build` once all of them are.
+fn resource_builder(app: &App) -> TokenStream2 {
+    let init = &app.init.name;
+    let builder = util::resource_builder_ident();
+    let shared = &app.init.user_shared_struct;
+    let local = &app.init.user_local_struct;
+
+    let resources = app
+        .shared_resources
+        .iter()
+        .map(|(name, res)| (name, &res.ty, &res.depends_on))
+        .chain(
+            app.local_resources
+                .iter()
+                .map(|(name, res)| (name, &res.ty, &res.depends_on)),
+        )
+        .collect::<Vec<_>>();
+
+    let names = resources.iter().map(|(name, ..)| *name).collect::<Vec<_>>();
+    let tys = resources.iter().map(|(_, ty, _)| *ty).collect::<Vec<_>>();
+    let fields = names
+        .iter()
+        .map(|name| util::mark_internal_name(&format!("resource_{name}")))
+        .collect::<Vec<_>>();
+    let states = names
+        .iter()
+        .map(|name| util::mark_internal_name(&format!("state_{name}")))
+        .collect::<Vec<_>>();
+    let shared_names = app.shared_resources.keys();
+    let shared_fields = &fields[..app.shared_resources.len()];
+    let local_names = app.local_resources.keys();
+    let local_fields = &fields[app.shared_resources.len()..];
+    let unset = names.iter().map(|_| quote!(#init::Unset));
+    let set = names.iter().map(|_| quote!(#init::Set));
+
+    let setters = resources.iter().map(|(name, ty, deps)| {
+        let (mut free, mut before, mut after) = (vec![], vec![], vec![]);
+        let (mut field, mut others) = (None, vec![]);
+        for ((other, state), other_field) in names.iter().zip(&states).zip(&fields) {
+            if other == name {
+                field = Some(other_field);
+                before.push(quote!(#init::Unset));
+                after.push(quote!(#init::Set));
+            } else if deps.contains(other) {
+                others.push(other_field);
+                before.push(quote!(#init::Set));
+                after.push(quote!(#init::Set));
+            } else {
+                others.push(other_field);
+                free.push(state);
+                before.push(quote!(#state));
+                after.push(quote!(#state));
+            }
+        }
+        let (dep_fields, dep_tys): (Vec<_>, Vec<_>) = deps
+            .iter()
+            .map(|dep| {
+                let i = names.iter().position(|name| *name == dep).expect("UNREACHABLE");
+                (&fields[i], tys[i])
+            })
+            .unzip();
+        let doc = if deps.is_empty() {
+            format!("Constructs `{name}`")
+        } else {
+            let deps = deps.iter().map(|dep| format!("`{dep}`")).collect::<Vec<_>>();
+            format!("Constructs `{name}` from {}", deps.join(", "))
+        };
+
+        quote!(
+            impl<#(#free),*> #builder<#(#before),*> {
+                #[doc = #doc]
+                #[inline(always)]
+                pub fn #name(self, f: impl FnOnce(#(&#dep_tys),*) -> #ty) -> #builder<#(#after),*> {
+                    // SAFETY: the dependencies are `Set`
+                    let value = unsafe { f(#(self.#dep_fields.as_ref().unwrap_unchecked()),*) };
+                    #builder {
+                        #field: ::core::option::Option::Some(value),
+                        #(#others: self.#others,)*
+                        __rtic_internal_state: ::core::marker::PhantomData,
+                    }
+                }
+            }
+        )
+    });
+
+    quote!(
+        /// The resources of `init`, constructed in the order of their `#[depends_on(..)]`
+        #[allow(non_camel_case_types)]
+        #[doc(hidden)]
+        pub struct #builder<#(#states),*> {
+            #(#fields: ::core::option::Option<#tys>,)*
+            __rtic_internal_state: ::core::marker::PhantomData<(#(#states,)*)>,
+        }
+
+        impl #builder<#(#unset),*> {
+            #[inline(always)]
+            #[doc(hidden)]
+            pub const fn __rtic_internal_new() -> Self {
+                #builder {
+                    #(#fields: ::core::option::Option::None,)*
+                    __rtic_internal_state: ::core::marker::PhantomData,
+                }
+            }
+        }
+
+        impl #builder<#(#set),*> {
+            /// The resources returned by `init`, once all of them are constructed
+            #[inline(always)]
+            pub fn build(self) -> (#shared, #local) {
+                // SAFETY: all the resources are `Set`
+                unsafe {
+                    (
+                        #shared {
+                            #(#shared_names: self.#shared_fields.unwrap_unchecked(),)*
+                        },
+                        #local {
+                            #(#local_names: self.#local_fields.unwrap_unchecked(),)*
+                        },
+                    )
+                }
+            }
+        }
+
+        #(#setters)*
+    )
+}
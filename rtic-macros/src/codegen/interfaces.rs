@@ -0,0 +1,55 @@
+use crate::codegen::util;
+use crate::syntax::ast::App;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens};
+use syn::Path;
+
+/// Generates `Tasks`, implementing the `#[task_interface]` traits with the tasks of the app
+pub fn codegen(app: &App) -> TokenStream2 {
+    // The methods of each trait, in the order of the tasks
+    let mut interfaces: Vec<(Path, Vec<TokenStream2>)> = vec![];
+
+    for (name, task) in &app.software_tasks {
+        let Some(interface) = &task.args.implements else {
+            continue;
+        };
+
+        let cfgs = &task.cfgs;
+        let (input_args, _, input_untupled, input_ty) = util::regroup_inputs(&task.inputs);
+        let method = quote!(
+            #(#cfgs)*
+            fn #name(#(#input_args,)*) -> ::core::result::Result<(), #input_ty> {
+                #name::spawn(#(#input_untupled,)*)
+            }
+        );
+
+        // A trait has to be spelled the same by all of its tasks
+        let key = interface.to_token_stream().to_string();
+        match interfaces
+            .iter_mut()
+            .find(|(path, _)| path.to_token_stream().to_string() == key)
+        {
+            Some((_, methods)) => methods.push(method),
+            None => interfaces.push((interface.clone(), vec![method])),
+        }
+    }
+
+    if interfaces.is_empty() {
+        return quote!();
+    }
+
+    let impls = interfaces.iter().map(|(interface, methods)| {
+        quote!(
+            impl #interface for Tasks {
+                #(#methods)*
+            }
+        )
+    });
+
+    quote!(
+        /// The tasks of the application, implementing the task interfaces of libraries
+        pub struct Tasks;
+
+        #(#impls)*
+    )
+}
@@ -18,7 +18,37 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
 
     let post_init_stmts = post_init::codegen(app, analysis);
 
-    let call_idle = if let Some(idle) = &app.idle {
+    let call_idle = if app.args.test {
+        // Each test case is spawned and runs to completion before the next one, or only the
+        // selected one runs
+        let cases = app
+            .software_tasks
+            .iter()
+            .filter(|(_, task)| task.is_test_case)
+            .map(|(name, task)| {
+                let name_str = name.to_string();
+                let cfgs = &task.cfgs;
+                let exec_name = util::internal_task_ident(name, "EXEC");
+                let from_ptr_n_args = util::from_ptr_n_args_ident(task.inputs.len());
+
+                quote!(
+                    #(#cfgs)*
+                    rtic::harness::TestCase {
+                        name: #name_str,
+                        spawn: || #name::spawn().is_ok(),
+                        is_running: || {
+                            rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name)
+                                .is_running()
+                        },
+                    },
+                )
+            });
+
+        // `RTIC_TEST_CASE` at build time selects the test case to run
+        quote!(rtic::harness::run(&[#(#cases)*], option_env!("RTIC_TEST_CASE")))
+    } else if app.args.sleep_on_exit {
+        quote!(rtic::export::sleep_on_exit())
+    } else if let Some(idle) = &app.idle {
         let name = &idle.name;
         quote!(#name(#name::Context::new()))
     } else if analysis.channels.contains_key(&0) {
@@ -52,11 +82,13 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
 
     let msp_check = bindings::check_stack_overflow_before_init(app, analysis);
 
+    let linkage = bindings::main_linkage(app);
+
     quote!(
         #(#extra_mods_stmts)*
 
         #[doc(hidden)]
-        #[no_mangle]
+        #linkage
         unsafe extern "C" fn #main() -> ! {
             #(#assertion_stmts)*
 
@@ -0,0 +1,122 @@
+use crate::syntax::ast::App;
+use crate::{
+    analyze::Analysis,
+    codegen::{bindings::interrupt_ident, util},
+};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::Ident;
+
+/// `matches!(mode, Mode::A | Mode::B)`, `true` if `modes` is empty
+pub fn is_active(modes: &[Ident], mode: &TokenStream2) -> TokenStream2 {
+    if modes.is_empty() {
+        quote!(true)
+    } else {
+        quote!(matches!(#mode, #(Mode::#modes)|*))
+    }
+}
+
+/// Generates the mode manager, `Mode`, `mode` and `set_mode`
+pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
+    let modes = &app.args.modes;
+    if modes.is_empty() {
+        return quote!();
+    }
+
+    let values = (0..modes.len()).map(|value| value as u8);
+    let current = util::mark_internal_name("MODE");
+    let apply = util::mark_internal_name("apply_mode");
+    let rt_err = util::rt_err_ident();
+    let interrupt = interrupt_ident();
+
+    let applied = quote!(applied);
+
+    // Polls from now on are charged at the current of the new mode
+    let energy = if cfg!(feature = "energy-stats") {
+        quote!(rtic::energy::set_mode(mode as u8);)
+    } else {
+        quote!()
+    };
+    let mut stmts = vec![];
+
+    for task in app.hardware_tasks.values() {
+        if task.args.modes.is_empty() {
+            continue;
+        }
+
+        let active = is_active(&task.args.modes, &applied);
+        let binds = &task.args.binds;
+        stmts.push(quote!(
+            if #active {
+                unsafe { rtic::export::NVIC::unmask(#rt_err::#interrupt::#binds) };
+            } else {
+                rtic::export::NVIC::mask(#rt_err::#interrupt::#binds);
+            }
+        ));
+    }
+
+    for (name, task) in &app.software_tasks {
+        if task.args.modes.is_empty() {
+            continue;
+        }
+
+        // The dispatcher kept the task pending if it was woken while paused
+        let active = is_active(&task.args.modes, &applied);
+        let priority = task.args.priority;
+        let mark_ready = util::mark_ready(analysis, priority, name);
+        let pend_interrupt = util::pend_dispatcher(app, analysis, priority);
+        stmts.push(quote!(
+            if #active {
+                #mark_ready
+                #pend_interrupt
+            }
+        ));
+    }
+
+    quote!(
+        /// Operating modes of the application
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum Mode {
+            #(
+                #[allow(missing_docs)]
+                #modes,
+            )*
+        }
+
+        #[allow(non_upper_case_globals)]
+        static #current: core::sync::atomic::AtomicU8 = core::sync::atomic::AtomicU8::new(0);
+
+        /// The mode the application is in
+        pub fn mode() -> Mode {
+            match #current.load(core::sync::atomic::Ordering::Relaxed) {
+                #(#values => Mode::#modes,)*
+                _ => unreachable!(),
+            }
+        }
+
+        /// Switches the application to `mode`
+        ///
+        /// Masks the interrupts of the hardware tasks not active in `mode` and unmasks the others,
+        /// pauses the software tasks not active in `mode` and resumes the others.
+        pub fn set_mode(mode: Mode) {
+            #current.store(mode as u8, core::sync::atomic::Ordering::Relaxed);
+            #energy
+            #apply();
+        }
+
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        pub fn #apply() {
+            // A switch preempting this one leaves its mode applied when it returns, apply again
+            // if it happened part way
+            loop {
+                let applied = mode();
+                #(#stmts)*
+
+                if mode() == applied {
+                    break;
+                }
+            }
+        }
+    )
+}
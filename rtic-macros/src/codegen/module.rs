@@ -1,5 +1,7 @@
 use crate::syntax::{ast::App, Context};
-use crate::{analyze::Analysis, codegen::bindings::interrupt_mod, codegen::util};
+use crate::{analyze::Analysis, codegen::util};
+#[cfg(feature = "rp2040")]
+use crate::codegen::bindings::interrupt_mod;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 
@@ -48,6 +50,33 @@ pub fn codegen(ctxt: Context, app: &App, analysis: &Analysis) -> TokenStream2 {
 
             values.push(quote!(cs: rtic::export::CriticalSection::new()));
             values.push(quote!(executors_size));
+
+            if util::has_resource_builder(app) {
+                let builder = util::resource_builder_ident();
+                let unset = app
+                    .shared_resources
+                    .keys()
+                    .chain(app.local_resources.keys())
+                    .map(|_| quote!(#name::Unset));
+
+                module_items.push(quote!(
+                    /// A resource of `Resources` that is constructed
+                    pub struct Set;
+
+                    /// A resource of `Resources` that is not constructed yet
+                    pub struct Unset;
+
+                    #[doc(inline)]
+                    pub use super::#builder as Resources;
+                ));
+
+                fields.push(quote!(
+                    /// Constructs the resources in the order of their `#[depends_on(..)]`
+                    pub resources: #name::Resources<#(#unset),*>
+                ));
+
+                values.push(quote!(resources: #builder::__rtic_internal_new()));
+            }
         }
 
         Context::Idle | Context::HardwareTask(_) | Context::SoftwareTask(_) => {}
@@ -86,10 +115,24 @@ pub fn codegen(ctxt: Context, app: &App, analysis: &Analysis) -> TokenStream2 {
     }
 
     let doc = match ctxt {
-        Context::Idle => "Idle loop",
-        Context::Init => "Initialization function",
-        Context::HardwareTask(_) => "Hardware task",
-        Context::SoftwareTask(_) => "Software task",
+        Context::Idle => "Idle loop".to_string(),
+        Context::Init => "Initialization function".to_string(),
+        Context::HardwareTask(t) => {
+            let args = &app.hardware_tasks[t].args;
+            format!(
+                "Hardware task bound to `{}`, running at priority {}",
+                args.binds, args.priority
+            )
+        }
+        Context::SoftwareTask(t) => {
+            let priority = app.software_tasks[t].args.priority;
+            match analysis.interrupts.get(&priority) {
+                Some((interrupt, _)) => format!(
+                    "Software task running at priority {priority}, dispatched by `{interrupt}`"
+                ),
+                None => format!("Software task running at priority {priority}"),
+            }
+        }
     };
 
     let v = Vec::new();
@@ -149,19 +192,65 @@ pub fn codegen(ctxt: Context, app: &App, analysis: &Analysis) -> TokenStream2 {
         // Store a copy of the task cfgs
         task_cfgs.clone_from(cfgs);
 
-        let pend_interrupt = if priority > 0 {
-            let int_mod = interrupt_mod(app);
-            let interrupt = &analysis.interrupts.get(&priority).expect("UREACHABLE").0;
-            quote!(rtic::export::pend(#int_mod::#interrupt);)
-        } else {
-            quote!()
-        };
+        let pend_interrupt = util::pend_dispatcher(app, analysis, priority);
+        let mark_ready = util::mark_ready(analysis, priority, name);
 
         let internal_spawn_ident = util::internal_task_ident(name, "spawn");
         let from_ptr_n_args = util::from_ptr_n_args_ident(spawnee.inputs.len());
         let (input_args, input_tupled, input_untupled, input_ty) =
             util::regroup_inputs(&spawnee.inputs);
 
+        // Drops the spawn while the dispatcher is overloaded, see `shed = N`
+        let shed_name = util::internal_task_ident(name, "SHED");
+        let overloaded = spawnee.args.shed.map(|threshold| {
+            let backlog = util::backlog_ident(priority);
+            quote!(#backlog() > #threshold)
+        });
+        let shed_spawn = overloaded.as_ref().map(|overloaded| {
+            quote!(
+                if #overloaded {
+                    #shed_name.record(1);
+                    return Ok(());
+                }
+            )
+        });
+
+        // Fails the spawn when injected, see `fault-injection`
+        let fault_name = util::internal_task_ident(name, "FAULT");
+        let fault_spawn = cfg!(feature = "fault-injection").then(|| {
+            quote!(
+                if #fault_name.inject() {
+                    return Err(#input_tupled);
+                }
+            )
+        });
+        let fault_batch = cfg!(feature = "fault-injection").then(|| {
+            quote!(
+                if #fault_name.inject() {
+                    return 0;
+                }
+            )
+        });
+
+        // Queues the payload and rings the doorbell of the core of the task when spawned from the
+        // other one, see `cross_core = N`
+        #[cfg(feature = "rp2040")]
+        let remote_spawn = spawnee.args.cross_core.map(|_| {
+            let core = app.args.backend.as_ref().map_or(0, |backend| backend.core);
+            let cross_core_name = util::internal_task_ident(name, "CROSS_CORE");
+            let int_mod = interrupt_mod(app);
+            let interrupt = &analysis.interrupts.get(&priority).expect("UNREACHABLE").0;
+            quote!(
+                if rtic::export::core_id() != #core {
+                    return rtic::export::pend_remote(#int_mod::#interrupt, #input_tupled, |payload| {
+                        #cross_core_name.push(payload)
+                    });
+                }
+            )
+        });
+        #[cfg(not(feature = "rp2040"))]
+        let remote_spawn: Option<TokenStream2> = None;
+
         // Spawn caller
         items.push(quote!(
             #(#cfgs)*
@@ -169,11 +258,16 @@ pub fn codegen(ctxt: Context, app: &App, analysis: &Analysis) -> TokenStream2 {
             #[allow(non_snake_case)]
             #[doc(hidden)]
             pub fn #internal_spawn_ident(#(#input_args,)*) -> ::core::result::Result<(), #input_ty> {
+                #remote_spawn
+                #shed_spawn
+                #fault_spawn
+
                 // SAFETY: If `try_allocate` succeeds one must call `spawn`, which we do.
                 unsafe {
                     let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name);
                     if exec.try_allocate() {
                         exec.spawn(#name(unsafe { #name::Context::new() } #(,#input_untupled)*));
+                        #mark_ready
                         #pend_interrupt
 
                         Ok(())
@@ -189,6 +283,217 @@ pub fn codegen(ctxt: Context, app: &App, analysis: &Analysis) -> TokenStream2 {
             #[doc(inline)]
             pub use super::#internal_spawn_ident as spawn;
         ));
+
+        let index = util::dispatcher_index(analysis, priority, name);
+        module_items.push(quote!(
+            #(#cfgs)*
+            /// Index of the task among the tasks of its dispatcher, as passed to its
+            /// `rtic::dispatcher::Executor`
+            pub const EXECUTOR_INDEX: usize = #index;
+        ));
+
+        if let Some(capacity) = spawnee.args.batch {
+            let internal_spawn_batch_ident = util::internal_task_ident(name, "spawn_batch");
+            let batch_name = util::internal_task_ident(name, "BATCH");
+
+            // Drops the queued payloads and a batch worth of new ones
+            let shed_batch = overloaded.as_ref().map(|overloaded| {
+                quote!(
+                    if #overloaded {
+                        let dropped = payloads.into_iter().take(#capacity).count();
+                        #shed_name.record(#batch_name.clear() + dropped);
+                        return dropped;
+                    }
+                )
+            });
+
+            items.push(quote!(
+                #(#cfgs)*
+                /// Spawns the task once per payload, queueing the payloads in a single critical
+                /// section and pending the dispatcher once, `payloads` runs outside of it
+                ///
+                /// Stops taking payloads when the batch queue is full and returns how many were
+                /// taken, pass `&mut iter` to keep the rest.
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                pub fn #internal_spawn_batch_ident<I: IntoIterator<Item = #input_ty>>(payloads: I) -> usize {
+                    #shed_batch
+                    #fault_batch
+
+                    // SAFETY: If `try_allocate` succeeds one must call `spawn`, which we do.
+                    let taken = #batch_name.extend(&mut payloads.into_iter(), |#input_tupled| unsafe {
+                        let exec = rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name);
+                        if exec.try_allocate() {
+                            exec.spawn(#name(unsafe { #name::Context::new() } #(,#input_untupled)*));
+                            #mark_ready
+                            None
+                        } else {
+                            Some(#input_tupled)
+                        }
+                    });
+
+                    if taken > 0 {
+                        #pend_interrupt
+                    }
+
+                    taken
+                }
+            ));
+
+            module_items.push(quote!(
+                #(#cfgs)*
+                #[doc(inline)]
+                pub use super::#internal_spawn_batch_ident as spawn_batch;
+            ));
+        }
+
+        if spawnee.args.shed.is_some() {
+            let internal_shed_count_ident = util::internal_task_ident(name, "shed_count");
+
+            items.push(quote!(
+                #(#cfgs)*
+                /// The spawns of the task dropped while its dispatcher was overloaded
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                pub fn #internal_shed_count_ident() -> u32 {
+                    #shed_name.count()
+                }
+            ));
+
+            module_items.push(quote!(
+                #(#cfgs)*
+                #[doc(inline)]
+                pub use super::#internal_shed_count_ident as shed_count;
+            ));
+        }
+
+        if cfg!(feature = "fault-injection") {
+            let internal_spawn_fault_ident = util::internal_task_ident(name, "spawn_fault");
+
+            items.push(quote!(
+                #(#cfgs)*
+                #[allow(non_upper_case_globals)]
+                static #fault_name: rtic::fault::Injector = rtic::fault::Injector::new();
+
+                #(#cfgs)*
+                /// The fault injector of the spawns of the task, a spawn it fails returns its
+                /// arguments as when the task is still running, a `spawn_batch` takes no payload
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                pub fn #internal_spawn_fault_ident() -> &'static rtic::fault::Injector {
+                    &#fault_name
+                }
+            ));
+
+            module_items.push(quote!(
+                #(#cfgs)*
+                #[doc(inline)]
+                pub use super::#internal_spawn_fault_ident as spawn_fault;
+            ));
+        }
+
+        if spawnee.args.isolated.is_some() {
+            let internal_faulted_ident = util::internal_task_ident(name, "faulted");
+            let pmp_name = util::internal_task_ident(name, "PMP");
+
+            items.push(quote!(
+                #(#cfgs)*
+                /// Whether a poll of the task faulted, it is not polled anymore and its spawns fail
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                pub fn #internal_faulted_ident() -> bool {
+                    #pmp_name.faulted()
+                }
+            ));
+
+            module_items.push(quote!(
+                #(#cfgs)*
+                #[doc(inline)]
+                pub use super::#internal_faulted_ident as faulted;
+            ));
+        }
+
+        if let Some(period) = &spawnee.args.period {
+            let internal_set_period_ident = util::internal_task_ident(name, "set_period");
+            let internal_period_ident = util::internal_task_ident(name, "period");
+            let period_name = util::internal_task_ident(name, "PERIOD");
+            let monotonic = &period.monotonic;
+            let initial = &period.period;
+
+            items.push(quote!(
+                #(#cfgs)*
+                /// Changes the period of the task, its pending release included
+                ///
+                /// The next release is the last one plus the new period, right away if that is
+                /// already past.
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                pub fn #internal_set_period_ident(period: <#monotonic as Monotonic>::Duration) {
+                    #period_name.set(period);
+                }
+
+                #(#cfgs)*
+                /// The current period of the task
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                pub fn #internal_period_ident() -> <#monotonic as Monotonic>::Duration {
+                    #period_name.get().unwrap_or_else(|| #initial)
+                }
+            ));
+
+            module_items.push(quote!(
+                #(#cfgs)*
+                #[doc(inline)]
+                pub use super::#internal_set_period_ident as set_period;
+                #(#cfgs)*
+                #[doc(inline)]
+                pub use super::#internal_period_ident as period;
+            ));
+        }
+
+        if cfg!(feature = "executor-stats") {
+            let internal_stats_ident = util::internal_task_ident(name, "stats");
+
+            items.push(quote!(
+                #(#cfgs)*
+                /// Poll and wake statistics of the task
+                #[allow(non_snake_case)]
+                #[doc(hidden)]
+                pub fn #internal_stats_ident() -> rtic::stats::TaskStats {
+                    // SAFETY: The executor is set up in `main`, before any task can run.
+                    unsafe {
+                        rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name).stats()
+                    }
+                }
+            ));
+
+            module_items.push(quote!(
+                #(#cfgs)*
+                #[doc(inline)]
+                pub use super::#internal_stats_ident as stats;
+            ));
+        }
+    }
+
+    if let Context::HardwareTask(..) = ctxt {
+        let internal_dma_ident = util::internal_task_ident(name, "dma");
+
+        items.push(quote!(
+            #(#cfgs)*
+            /// The completion of the DMA transfers finished by this task
+            #[allow(non_snake_case)]
+            #[doc(hidden)]
+            pub fn #internal_dma_ident() -> &'static rtic::dma::Completion {
+                static COMPLETION: rtic::dma::Completion = rtic::dma::Completion::new();
+                &COMPLETION
+            }
+        ));
+
+        module_items.push(quote!(
+            #(#cfgs)*
+            #[doc(inline)]
+            pub use super::#internal_dma_ident as dma;
+        ));
     }
 
     if items.is_empty() {
@@ -1,4 +1,8 @@
-use crate::{analyze::Analysis, codegen::util, syntax::ast::App};
+use crate::{
+    analyze::Analysis,
+    codegen::{bindings::post_init_checks, util},
+    syntax::ast::App,
+};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 
@@ -11,7 +15,12 @@ pub fn codegen(app: &App, analysis: &Analysis) -> Vec<TokenStream2> {
         let mangled_name = util::static_shared_resource_ident(name);
         // If it's live
         let cfgs = res.cfgs.clone();
-        if analysis.shared_resources.get(name).is_some() {
+        // `#[cross_core]` resources are held by core 0 for the tasks of core 1 too
+        let live = match res.properties.cross_core {
+            Some(cross_core) => cross_core.stored_by.is_none(),
+            None => analysis.shared_resources.get(name).is_some(),
+        };
+        if live {
             stmts.push(quote!(
                 // We include the cfgs
                 #(#cfgs)*
@@ -23,6 +32,17 @@ pub fn codegen(app: &App, analysis: &Analysis) -> Vec<TokenStream2> {
         }
     }
 
+    // The application of core 1 waits for the `#[cross_core]` resources before its tasks run
+    let mut cross_core = app.shared_resources.values().filter_map(|r| r.properties.cross_core);
+    if let Some(cross_core) = cross_core.next() {
+        let ready = util::cross_core_ready_ident();
+        stmts.push(if cross_core.stored_by.is_none() {
+            quote!(#ready.store(true, rtic::export::atomic::Ordering::Release);)
+        } else {
+            quote!(while !#ready.load(rtic::export::atomic::Ordering::Acquire) {})
+        });
+    }
+
     // Initialize local resources
     for (name, res) in &app.local_resources {
         let mangled_name = util::static_local_resource_ident(name);
@@ -40,6 +60,36 @@ pub fn codegen(app: &App, analysis: &Analysis) -> Vec<TokenStream2> {
         }
     }
 
+    // Catch code in `init` that reconfigured the interrupts set up by RTIC
+    let checks = post_init_checks(app, analysis);
+    if !checks.is_empty() {
+        stmts.push(quote!(
+            #[cfg(debug_assertions)]
+            {
+                #(#checks)*
+            }
+        ));
+    }
+
+    // Mask the interrupts and pause the tasks not active in the mode `init` left the application in
+    if !app.args.modes.is_empty() {
+        let apply = util::mark_internal_name("apply_mode");
+        stmts.push(quote!(#apply();));
+    }
+
+    // `init` may block, the limit of `rtic::blocking::assert_may_block` applies from here on
+    if let Some(priority) = app.args.blocking_priority {
+        stmts.push(quote!(
+            #[cfg(debug_assertions)]
+            rtic::blocking::set_blocking_priority(#priority);
+        ));
+    }
+
+    // Nesting during `init` is not limited, only from the handlers enabled below
+    if let Some(depth) = app.args.max_nesting {
+        stmts.push(quote!(rtic::nesting::set_limit(#depth);));
+    }
+
     // Enable the interrupts -- this completes the `init`-ialization phase
     stmts.push(quote!(rtic::export::interrupt::enable();));
 
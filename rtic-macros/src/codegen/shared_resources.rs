@@ -1,7 +1,10 @@
-use crate::syntax::{analyze::Ownership, ast::App};
+use crate::syntax::{
+    analyze::Ownership,
+    ast::{App, CrossCore},
+};
 use crate::{analyze::Analysis, codegen::util};
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
+use quote::{format_ident, quote};
 
 use super::bindings::impl_mutex;
 
@@ -30,23 +33,40 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
 
         // For future use
         // let doc = format!(" RTIC internal: {}:{}", file!(), line!());
-        mod_app.push(quote!(
-            #[allow(non_camel_case_types)]
-            #[allow(non_upper_case_globals)]
-            // #[doc = #doc]
-            #[doc(hidden)]
-            #(#attrs)*
-            #(#cfgs)*
-            #section
-            static #mangled_name: rtic::RacyCell<core::mem::MaybeUninit<#ty>> = rtic::RacyCell::new(core::mem::MaybeUninit::uninit());
-        ));
+        match res.properties.cross_core {
+            // Held by the application of the core declaring it
+            Some(CrossCore {
+                stored_by: Some(core),
+                ..
+            }) => {
+                let core = format_ident!("core{}", core);
+                mod_app.push(quote!(
+                    #(#cfgs)*
+                    use super::#core::#mangled_name;
+                ));
+            }
+
+            cross_core => {
+                let vis = cross_core.map(|_| quote!(pub(super)));
+                mod_app.push(quote!(
+                    #[allow(non_camel_case_types)]
+                    #[allow(non_upper_case_globals)]
+                    // #[doc = #doc]
+                    #[doc(hidden)]
+                    #(#attrs)*
+                    #(#cfgs)*
+                    #section
+                    #vis static #mangled_name: rtic::RacyCell<core::mem::MaybeUninit<#ty>> = rtic::RacyCell::new(core::mem::MaybeUninit::uninit());
+                ));
+            }
+        }
 
         // For future use
         // let doc = format!(" RTIC internal: {}:{}", file!(), line!());
 
         let shared_name = util::need_to_lock_ident(name);
 
-        if !res.properties.lock_free {
+        if !res.properties.lock_free && res.properties.atomic.is_none() {
             mod_resources.push(quote!(
                 // #[doc = #doc]
                 #[doc(hidden)]
@@ -65,6 +85,120 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
                 }
             ));
 
+            // A `#[poison]` resource is locked through a second proxy, its own lock wraps the
+            // closure in setting and clearing the flag
+            let mutex_name = if res.properties.poison {
+                let unpoisoned = util::unpoisoned_ident(name);
+                let poisoned = util::poison_ident(name);
+
+                mod_resources.push(quote!(
+                    #[doc(hidden)]
+                    #[allow(non_camel_case_types)]
+                    #(#cfgs)*
+                    pub struct #unpoisoned<'a> {
+                        __rtic_internal_p: ::core::marker::PhantomData<&'a ()>,
+                    }
+
+                    #(#cfgs)*
+                    impl<'a> #unpoisoned<'a> {
+                        #[inline(always)]
+                        pub unsafe fn new() -> Self {
+                            #unpoisoned { __rtic_internal_p: ::core::marker::PhantomData }
+                        }
+                    }
+
+                    #(#cfgs)*
+                    impl<'a> #shared_name<'a> {
+                        /// Returns `true` if a lock of this resource did not complete, e.g. due to
+                        /// a panic, and its value may be inconsistent
+                        #[inline(always)]
+                        pub fn is_poisoned(&self) -> bool {
+                            super::#poisoned.load(core::sync::atomic::Ordering::SeqCst)
+                        }
+
+                        /// Marks the value of this resource as consistent again, after recovery
+                        #[inline(always)]
+                        pub fn clear_poison(&mut self) {
+                            super::#poisoned.store(false, core::sync::atomic::Ordering::SeqCst);
+                        }
+                    }
+                ));
+
+                mod_app.push(quote!(
+                    #[allow(non_upper_case_globals)]
+                    #[doc(hidden)]
+                    #(#cfgs)*
+                    static #poisoned: core::sync::atomic::AtomicBool = core::sync::atomic::AtomicBool::new(false);
+
+                    #(#cfgs)*
+                    impl<'a> rtic::Mutex for shared_resources::#shared_name<'a> {
+                        type T = #ty;
+
+                        #[inline(always)]
+                        fn lock<RTIC_INTERNAL_R>(&mut self, f: impl FnOnce(&mut #ty) -> RTIC_INTERNAL_R) -> RTIC_INTERNAL_R {
+                            // SAFETY: `self` stands for the exclusive access to the resource
+                            let mut unpoisoned = unsafe { shared_resources::#unpoisoned::new() };
+
+                            rtic::Mutex::lock(&mut unpoisoned, |r| {
+                                #poisoned.store(true, core::sync::atomic::Ordering::SeqCst);
+                                let ret = f(r);
+                                #poisoned.store(false, core::sync::atomic::Ordering::SeqCst);
+                                ret
+                            })
+                        }
+                    }
+                ));
+
+                unpoisoned
+            } else if let Some(CrossCore { spinlock, .. }) = res.properties.cross_core {
+                // A `#[cross_core]` resource is locked through a second proxy too, raising the
+                // ceiling of this core, then claiming the spinlock against the other core
+                let spinlocked = util::spinlocked_ident(name);
+                let spinlock = spinlock as usize;
+
+                mod_resources.push(quote!(
+                    #[doc(hidden)]
+                    #[allow(non_camel_case_types)]
+                    #(#cfgs)*
+                    pub struct #spinlocked<'a> {
+                        __rtic_internal_p: ::core::marker::PhantomData<&'a ()>,
+                    }
+
+                    #(#cfgs)*
+                    impl<'a> #spinlocked<'a> {
+                        #[inline(always)]
+                        pub unsafe fn new() -> Self {
+                            #spinlocked { __rtic_internal_p: ::core::marker::PhantomData }
+                        }
+                    }
+                ));
+
+                mod_app.push(quote!(
+                    #(#cfgs)*
+                    impl<'a> rtic::Mutex for shared_resources::#shared_name<'a> {
+                        type T = #ty;
+
+                        #[inline(always)]
+                        fn lock<RTIC_INTERNAL_R>(&mut self, f: impl FnOnce(&mut #ty) -> RTIC_INTERNAL_R) -> RTIC_INTERNAL_R {
+                            // SAFETY: `self` stands for the exclusive access to the resource
+                            let mut spinlocked = unsafe { shared_resources::#spinlocked::new() };
+
+                            rtic::Mutex::lock(&mut spinlocked, |r| {
+                                rtic::rp2040::Spinlock::<#spinlock>::claim();
+                                let ret = f(r);
+                                // SAFETY: claimed above
+                                unsafe { rtic::rp2040::Spinlock::<#spinlock>::release() };
+                                ret
+                            })
+                        }
+                    }
+                ));
+
+                spinlocked
+            } else {
+                shared_name
+            };
+
             let ptr = quote!(
                 #(#cfgs)*
                 #mangled_name.get_mut() as *mut _
@@ -84,14 +218,58 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
                 analysis,
                 cfgs,
                 true,
-                &shared_name,
+                &mutex_name,
                 &quote!(#ty),
                 ceiling,
                 &ptr,
             ));
+
+            if res.properties.c_export.is_some() {
+                let proxy = util::need_to_lock_ident(name);
+                let get = format_ident!("{}_{name}_get", app.name);
+                let set = format_ident!("{}_{name}_set", app.name);
+
+                mod_app.push(quote!(
+                    /// Reads the resource under its lock, for C code running at or below the
+                    /// priority declared by `#[c_export]` once `init` has returned
+                    #(#cfgs)*
+                    #[no_mangle]
+                    pub extern "C" fn #get() -> #ty {
+                        // SAFETY: the C callers take part in the ceiling like a task of their
+                        // priority
+                        let mut resource = unsafe { shared_resources::#proxy::new() };
+                        rtic::Mutex::lock(&mut resource, |value| *value)
+                    }
+
+                    /// Writes the resource under its lock, see the getter
+                    #(#cfgs)*
+                    #[no_mangle]
+                    pub extern "C" fn #set(value: #ty) {
+                        // SAFETY: as in the getter
+                        let mut resource = unsafe { shared_resources::#proxy::new() };
+                        rtic::Mutex::lock(&mut resource, |resource| *resource = value)
+                    }
+                ));
+            }
         }
     }
 
+    let cross_core = app.shared_resources.values().find_map(|r| r.properties.cross_core);
+    if let Some(cross_core) = cross_core {
+        let ready = util::cross_core_ready_ident();
+        mod_app.push(match cross_core.stored_by {
+            Some(core) => {
+                let core = format_ident!("core{}", core);
+                quote!(use super::#core::#ready;)
+            }
+            None => quote!(
+                #[doc(hidden)]
+                #[allow(non_upper_case_globals)]
+                pub(super) static #ready: rtic::export::atomic::AtomicBool = rtic::export::atomic::AtomicBool::new(false);
+            ),
+        });
+    }
+
     let mod_resources = if mod_resources.is_empty() {
         quote!()
     } else {
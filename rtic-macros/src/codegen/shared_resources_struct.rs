@@ -37,7 +37,22 @@ pub fn codegen(ctxt: Context, app: &App) -> (TokenStream2, TokenStream2) {
         let mangled_name = util::static_shared_resource_ident(name);
         let shared_name = util::need_to_lock_ident(name);
 
-        if res.properties.lock_free {
+        if let Some(atomic) = &res.properties.atomic {
+            // `#[atomic]` resources are accessed in place through the atomic type of the same
+            // layout, their operations need no lock
+            fields.push(quote!(
+                #(#cfgs)*
+                #[allow(missing_docs)]
+                pub #name: &'a rtic::export::atomic::#atomic
+            ));
+
+            values.push(quote!(
+                #(#cfgs)*
+                #name: &*(#mangled_name.get() as *const rtic::export::atomic::#atomic)
+            ));
+
+            continue;
+        } else if res.properties.lock_free {
             // Lock free resources of `idle` and `init` get 'static lifetime
             let lt = if ctxt.runs_once() {
                 quote!('static)
@@ -1,10 +1,14 @@
-use crate::syntax::{ast::App, Context};
+use crate::syntax::{
+    ast::{App, Period, SoftwareTask},
+    Context,
+};
 use crate::{
     analyze::Analysis,
-    codegen::{local_resources_struct, module, shared_resources_struct},
+    codegen::{local_resources_struct, module, shared_resources_struct, util},
 };
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
+use syn::Ident;
 
 pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
     let mut mod_app = vec![];
@@ -38,22 +42,30 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
             let stmts = &task.stmts;
             let inputs = &task.inputs;
 
-            user_tasks.push(quote!(
-                #(#attrs)*
-                #(#cfgs)*
-                #[allow(non_snake_case)]
-                async fn #name<'a>(#context: #name::Context<'a> #(,#inputs)*) {
-                    use rtic::Mutex as _;
-                    use rtic::mutex::prelude::*;
+            if let Some(period) = &task.args.period {
+                user_tasks.push(periodic_task(name, task, period));
+            } else {
+                user_tasks.push(quote!(
+                    #(#attrs)*
+                    #(#cfgs)*
+                    #[allow(non_snake_case)]
+                    async fn #name<'a>(#context: #name::Context<'a> #(,#inputs)*) {
+                        use rtic::Mutex as _;
+                        use rtic::mutex::prelude::*;
 
-                    #(#stmts)*
-                }
-            ));
+                        #(#stmts)*
+                    }
+                ));
+            }
         }
 
         root.push(module::codegen(Context::SoftwareTask(name), app, analysis));
     }
 
+    if cfg!(feature = "starvation-detector") {
+        root.push(starvation_check(app));
+    }
+
     quote!(
         #(#mod_app)*
 
@@ -62,3 +74,82 @@ pub fn codegen(app: &App, analysis: &Analysis) -> TokenStream2 {
         #(#user_tasks)*
     )
 }
+
+/// Generates a task with `period = ..`, which runs its body once per period from its spawn on
+///
+/// A change of the period wakes the task waiting for its next release, which is computed again
+/// from the last release with the new period.
+fn periodic_task(name: &Ident, task: &SoftwareTask, period: &Period) -> TokenStream2 {
+    let context = &task.context;
+    let attrs = &task.attrs;
+    let cfgs = &task.cfgs;
+    let stmts = &task.stmts;
+    let monotonic = &period.monotonic;
+    let period_name = util::internal_task_ident(name, "PERIOD");
+
+    quote!(
+        #(#attrs)*
+        #(#cfgs)*
+        #[allow(non_snake_case)]
+        async fn #name<'a>(_: #name::Context<'a>) {
+            use rtic::Mutex as _;
+            use rtic::mutex::prelude::*;
+
+            let mut release = #monotonic::now();
+            loop {
+                {
+                    // SAFETY: The context of the previous run has been dropped.
+                    let #context = unsafe { #name::Context::new() };
+                    async move { #(#stmts)* }.await;
+                }
+
+                loop {
+                    let changes = #period_name.changes();
+                    let next = release + #name::period();
+                    if #monotonic::timeout_at(next, #period_name.changed(changes)).await.is_err() {
+                        release = next;
+                        break;
+                    }
+                }
+            }
+        }
+    )
+}
+
+/// Generates `check_starvation`, which reports the software tasks that stay ready without running
+fn starvation_check(app: &App) -> TokenStream2 {
+    let checks = app.software_tasks.iter().map(|(name, task)| {
+        let cfgs = &task.cfgs;
+        let exec_name = util::internal_task_ident(name, "EXEC");
+        let from_ptr_n_args = util::from_ptr_n_args_ident(task.inputs.len());
+        let name_str = name.to_string();
+
+        quote!(
+            #(#cfgs)*
+            {
+                // SAFETY: The executor is set up in `main`, before any task can run.
+                let checks = unsafe {
+                    rtic::export::executor::AsyncTaskExecutor::#from_ptr_n_args(#name, &#exec_name)
+                }
+                .check_starvation();
+
+                if checks >= threshold {
+                    on_starved(#name_str, checks);
+                }
+            }
+        )
+    });
+
+    quote!(
+        /// Reports the software tasks that have been ready to run, spawned or woken, without being
+        /// polled for at least `threshold` consecutive calls, with the number of calls.
+        ///
+        /// Call it periodically, e.g. from a timer task, at a priority above the tasks it watches.
+        /// A task that is reported has been kept from running by tasks of higher or equal
+        /// priority for at least `threshold` periods.
+        #[allow(unused_variables, unused_mut)]
+        pub fn check_starvation(threshold: u32, mut on_starved: impl FnMut(&'static str, u32)) {
+            #(#checks)*
+        }
+    )
+}
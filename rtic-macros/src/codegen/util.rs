@@ -1,4 +1,6 @@
-use crate::syntax::{ast::App, Context};
+use crate::analyze::Analysis;
+use crate::codegen::bindings::interrupt_mod;
+use crate::syntax::{analyze::Ownership, ast::App, Context};
 use core::sync::atomic::{AtomicUsize, Ordering};
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::quote;
@@ -11,6 +13,22 @@ pub fn mark_internal_name(name: &str) -> Ident {
     Ident::new(&format!("{RTIC_INTERNAL}_{name}"), Span::call_site())
 }
 
+/// `init::Resources`, generated when a resource has `#[depends_on(..)]`
+pub fn has_resource_builder(app: &App) -> bool {
+    app.shared_resources
+        .values()
+        .any(|res| !res.depends_on.is_empty())
+        || app
+            .local_resources
+            .values()
+            .any(|res| !res.depends_on.is_empty())
+}
+
+/// The type of `init::Resources`
+pub fn resource_builder_ident() -> Ident {
+    mark_internal_name("init_Resources")
+}
+
 /// Generate an internal identifier for tasks
 pub fn internal_task_ident(task: &Ident, ident_name: &str) -> Ident {
     mark_internal_name(&format!("{task}_{ident_name}"))
@@ -140,6 +158,26 @@ pub fn static_shared_resource_ident(name: &Ident) -> Ident {
     mark_internal_name(&format!("shared_resource_{name}"))
 }
 
+/// The lock-in-progress flag of a `#[poison]` shared resource
+pub fn poison_ident(name: &Ident) -> Ident {
+    mark_internal_name(&format!("shared_resource_{name}_poisoned"))
+}
+
+/// The proxy doing the actual locking of a `#[poison]` shared resource
+pub fn unpoisoned_ident(name: &Ident) -> Ident {
+    mark_internal_name(&format!("{name}_unpoisoned"))
+}
+
+/// The proxy raising the ceiling of a `#[cross_core]` shared resource, before the spinlock
+pub fn spinlocked_ident(name: &Ident) -> Ident {
+    mark_internal_name(&format!("{name}_spinlocked"))
+}
+
+/// Set once the application of core 0 has initialized the `#[cross_core]` resources
+pub fn cross_core_ready_ident() -> Ident {
+    mark_internal_name("cross_core_ready")
+}
+
 pub fn static_local_resource_ident(name: &Ident) -> Ident {
     mark_internal_name(&format!("local_resource_{name}"))
 }
@@ -152,6 +190,127 @@ pub fn need_to_lock_ident(name: &Ident) -> Ident {
     Ident::new(&format!("{name}_that_needs_to_be_locked"), name.span())
 }
 
+/// Flag set while the dispatcher of priority `level` polls its tasks, see `drain-dispatchers`
+pub fn dispatching_ident(level: u8) -> Ident {
+    mark_internal_name(&format!("dispatching_{level}"))
+}
+
+/// Bitmap of the ready tasks of the dispatcher of priority `level`
+pub fn ready_ident(level: u8) -> Ident {
+    mark_internal_name(&format!("ready_{level}"))
+}
+
+/// The executor tracking the ready tasks of priority `level`: the one given with
+/// `#[executor(..)]` on its dispatcher, or the built-in bitmap
+pub fn ready_set(analysis: &Analysis, level: u8) -> TokenStream2 {
+    match analysis
+        .interrupts
+        .get(&level)
+        .and_then(|(_, dispatcher)| dispatcher.executor.as_ref())
+    {
+        Some(executor) => quote!(#executor),
+        None => {
+            let ready = ready_ident(level);
+            quote!(#ready)
+        }
+    }
+}
+
+/// Index of software task `task` among the tasks of its dispatcher
+pub fn dispatcher_index(analysis: &Analysis, level: u8, task: &Ident) -> usize {
+    analysis.channels[&level]
+        .tasks
+        .iter()
+        .position(|name| name == task)
+        .expect("UNREACHABLE")
+}
+
+/// Marks software task `task` as ready for the pass of its dispatcher
+pub fn mark_ready(analysis: &Analysis, level: u8, task: &Ident) -> TokenStream2 {
+    let ready = ready_set(analysis, level);
+    let index = dispatcher_index(analysis, level, task);
+
+    quote!(rtic::dispatcher::Executor::wake(&#ready, #index);)
+}
+
+/// Pend statistics of the dispatcher of priority `level`, see `coalesce-wakes`
+pub fn coalescer_ident(level: u8) -> Ident {
+    mark_internal_name(&format!("coalescer_{level}"))
+}
+
+/// Function counting the ready tasks and queued payloads of the dispatcher of priority `level`
+pub fn backlog_ident(level: u8) -> Ident {
+    mark_internal_name(&format!("backlog_{level}"))
+}
+
+/// Pends the dispatcher of priority `level` on a spawn or wake of one of its tasks
+pub fn pend_dispatcher(app: &App, analysis: &Analysis, level: u8) -> TokenStream2 {
+    if level == 0 {
+        // The zero priority dispatcher runs in `main`, wake it if it sleeps
+        return quote!(rtic::export::wake_idle(););
+    }
+
+    let int_mod = interrupt_mod(app);
+    let interrupt = &analysis.interrupts.get(&level).expect("UNREACHABLE").0;
+    let mut pend = quote!(rtic::export::pend(#int_mod::#interrupt););
+
+    if cfg!(feature = "coalesce-wakes") {
+        // Shares the next pass of an already pended dispatcher
+        let coalescer = coalescer_ident(level);
+        pend = quote!(
+            if #coalescer.request() {
+                #pend
+            }
+        );
+    }
+
+    if cfg!(feature = "drain-dispatchers") {
+        // A running dispatcher re-checks its tasks before returning
+        let dispatching = dispatching_ident(level);
+        pend = quote!(
+            if !#dispatching.load(core::sync::atomic::Ordering::SeqCst) {
+                #pend
+            }
+        );
+    }
+
+    pend
+}
+
+/// Statements tracking the nesting depth around the task run by a handler, see `max_nesting`
+pub fn nesting_tracking(app: &App) -> (TokenStream2, TokenStream2) {
+    if app.args.max_nesting.is_none() {
+        return (quote!(), quote!());
+    }
+
+    (
+        quote!(rtic::nesting::enter();),
+        quote!(rtic::nesting::exit();),
+    )
+}
+
+/// The highest priority `set_priority` can move the hardware task `name` to, the lowest ceiling
+/// of the shared resources it accesses, `u8::MAX` without any
+///
+/// `None` if the task accesses a `#[lock_free]` resource, which is not locked at all.
+pub fn priority_bound(app: &App, analysis: &Analysis, name: &Ident) -> Option<u8> {
+    let mut bound = u8::MAX;
+    for resource in app.hardware_tasks[name].args.shared_resources.keys() {
+        if app.shared_resources[resource].properties.lock_free {
+            return None;
+        }
+
+        let ceiling = match analysis.ownerships.get(resource) {
+            Some(Ownership::Owned { priority } | Ownership::CoOwned { priority }) => *priority,
+            Some(Ownership::Contended { ceiling }) => *ceiling,
+            None => continue,
+        };
+        bound = bound.min(ceiling);
+    }
+
+    Some(bound)
+}
+
 pub fn zero_prio_dispatcher_ident() -> Ident {
     Ident::new("__rtic_internal_async_0_prio_dispatcher", Span::call_site())
 }
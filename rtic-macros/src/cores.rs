@@ -0,0 +1,616 @@
+//! Multi-core applications, `core = N` on the tasks, `#[init]`, `#[idle]`, `#[shared]` and
+//! `#[local]` of a dual core device
+//!
+//! An application placing items on core 1 is split into one application per core before it is
+//! parsed, each expanded as if declared on its own with `backend = [core = N]`. The items of
+//! the module without an RTIC attribute are kept once in the application module, the
+//! application of each core is a module `coreN` in it which sees them through a glob import.
+//! Core 1 takes the arguments of the application, with those given in `core1 = [..]` replaced.
+//!
+//! A resource belongs to the core of its `#[shared]` or `#[local]` struct, the ceiling analysis
+//! of each core only covers its own tasks, so accessing it from the other core is an error. The
+//! exception are the `#[cross_core]` resources of core 0, added to the resources of core 1 and
+//! locked by both with a spinlock on top of the ceiling of the core.
+
+use proc_macro2::{Delimiter, Group, Punct, Spacing, Span, TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use std::collections::HashSet;
+use syn::{parse, spanned::Spanned, Attribute, Field, Fields, ForeignItem, Ident, Item, ItemMod, LitInt, Meta};
+
+/// The cores of the device
+const CORES: u8 = 2;
+
+/// The module of the runtime passing data between the cores
+const DEVICE: &str = if cfg!(feature = "rp2040") {
+    "rp2040"
+} else {
+    "nrf5340"
+};
+
+/// The attributes taking `core = N`
+const PLACED: [&str; 5] = ["init", "idle", "task", "shared", "local"];
+
+/// The attributes of items only core 0 runs
+const CORE0: [&str; 3] = ["shutdown", "lp_task", "test_case"];
+
+/// Arguments, each a list of tokens
+type Segments = Vec<Vec<TokenTree>>;
+
+/// The application of a core, the arguments and module of its `#[app]`
+pub struct Core {
+    pub core: u8,
+    pub args: TokenStream2,
+    pub input: TokenStream2,
+}
+
+/// An application split by core
+pub struct Split {
+    /// The application module, holding the items shared by the cores
+    pub module: ItemMod,
+    pub cores: Vec<Core>,
+}
+
+impl Split {
+    /// The application module around the expansion of each core
+    pub fn wrap(&self, expansions: Vec<TokenStream2>) -> TokenStream2 {
+        let ItemMod {
+            attrs,
+            vis,
+            ident,
+            content,
+            ..
+        } = &self.module;
+        let items = content.iter().flat_map(|(_, items)| items);
+
+        quote!(
+            #(#attrs)*
+            #vis mod #ident {
+                #(#items)*
+
+                #(#expansions)*
+            }
+        )
+    }
+}
+
+/// Splits the application by core, `None` if no item is placed with `core = N`
+pub fn split(args: TokenStream2, input: TokenStream2) -> parse::Result<Option<Split>> {
+    let module: ItemMod = syn::parse2(input)?;
+    let Some((_, items)) = &module.content else {
+        return Ok(None);
+    };
+
+    let mut per_core: Vec<Vec<Item>> = vec![vec![]; CORES as usize];
+    let mut common = vec![];
+    let mut placed = None;
+
+    for item in items {
+        match item {
+            Item::Fn(f) => {
+                let mut f = f.clone();
+                match place(&mut f.attrs)? {
+                    Some((core, explicit)) => {
+                        placed = placed.or(explicit);
+                        per_core[core as usize].push(Item::Fn(f));
+                    }
+                    None => common.push(item.clone()),
+                }
+            }
+
+            Item::Struct(s) => {
+                let mut s = s.clone();
+                match place(&mut s.attrs)? {
+                    Some((core, explicit)) => {
+                        placed = placed.or(explicit);
+                        per_core[core as usize].push(Item::Struct(s));
+                    }
+                    None => common.push(item.clone()),
+                }
+            }
+
+            // `extern` tasks, each placed on its core in a copy of the block
+            Item::ForeignMod(block) => {
+                let mut blocks = vec![block.clone(); CORES as usize];
+                blocks.iter_mut().for_each(|b| b.items.clear());
+
+                for foreign in &block.items {
+                    let (core, foreign) = match foreign {
+                        ForeignItem::Fn(f) => {
+                            let mut f = f.clone();
+                            let core = match place(&mut f.attrs)? {
+                                Some((core, explicit)) => {
+                                    placed = placed.or(explicit);
+                                    core
+                                }
+                                None => 0,
+                            };
+                            (core, ForeignItem::Fn(f))
+                        }
+                        _ => (0, foreign.clone()),
+                    };
+                    blocks[core as usize].items.push(foreign);
+                }
+
+                for (core, block) in blocks.into_iter().enumerate() {
+                    if !block.items.is_empty() {
+                        per_core[core].push(Item::ForeignMod(block));
+                    }
+                }
+            }
+
+            _ => common.push(item.clone()),
+        }
+    }
+
+    let Some(span) = placed else {
+        return Ok(None);
+    };
+
+    if !cfg!(any(feature = "rp2040", feature = "nrf5340")) {
+        return Err(parse::Error::new(
+            span,
+            "`core = N` places items on a core of a dual core device, it needs the \
+             `thumbv6-rp2040-backend` or the `thumbv8main-nrf5340-backend`",
+        ));
+    }
+
+    share_cross_core(&mut per_core)?;
+
+    let resources: Vec<_> = per_core.iter().map(|items| Resources::of(items)).collect();
+    for (core, items) in per_core.iter().enumerate() {
+        if items.is_empty() {
+            continue;
+        }
+
+        let declared = &resources[core];
+        let (init, shared, local) = if core == 0 {
+            ("#[init]", "#[shared]", "#[local]")
+        } else {
+            ("#[init(core = 1)]", "#[shared(core = 1)]", "#[local(core = 1)]")
+        };
+        for (missing, what) in [
+            (!declared.init, init),
+            (!declared.has_shared, shared),
+            (!declared.has_local, local),
+        ] {
+            if missing {
+                return Err(parse::Error::new(
+                    module.ident.span(),
+                    format!("core {core} runs tasks of the application, it needs its own `{what}`"),
+                ));
+            }
+        }
+
+        check_accesses(core, items, &resources)?;
+    }
+
+    let (base, core1) = core_args(args)?;
+    let mut cores = vec![];
+    for (core, items) in per_core.into_iter().enumerate() {
+        if items.is_empty() {
+            continue;
+        }
+
+        let core = core as u8;
+        let args = if core == 0 {
+            join(&base)
+        } else {
+            with_core(override_args(&base, &core1), core)?
+        };
+        let ident = format_ident!("core{}", core);
+        let input = quote!(
+            mod #ident {
+                use super::*;
+
+                #(#items)*
+            }
+        );
+
+        cores.push(Core { core, args, input });
+    }
+
+    let mut module = module;
+    if let Some((_, items)) = &mut module.content {
+        *items = common;
+    }
+
+    Ok(Some(Split { module, cores }))
+}
+
+/// The core of an item, with `core = N` removed from its attributes, `None` for an item of no
+/// core, and the span of `core` if it was given explicitly
+fn place(attrs: &mut [Attribute]) -> parse::Result<Option<(u8, Option<Span>)>> {
+    let placed = |attr: &Attribute| PLACED.iter().any(|name| attr.path().is_ident(name));
+    if let Some(attr) = attrs.iter_mut().find(|attr| placed(attr)) {
+        return Ok(Some(match take_core(attr)? {
+            Some((core, span)) => (core, Some(span)),
+            None => (0, None),
+        }));
+    }
+
+    let core0 = |attr: &Attribute| CORE0.iter().any(|name| attr.path().is_ident(name));
+    if attrs.iter().any(core0) {
+        return Ok(Some((0, None)));
+    }
+
+    Ok(None)
+}
+
+/// Removes `core = N` from the arguments of the attribute
+fn take_core(attr: &mut Attribute) -> parse::Result<Option<(u8, Span)>> {
+    let Meta::List(list) = &attr.meta else {
+        return Ok(None);
+    };
+
+    let mut segments = segments(list.tokens.clone());
+    let Some(pos) = segments.iter().position(|s| key(s).is_some_and(|k| k == "core")) else {
+        return Ok(None);
+    };
+
+    let segment = segments.remove(pos);
+    let value: TokenStream2 = segment[2..].iter().cloned().collect();
+    let lit: LitInt = syn::parse2(value).map_err(|_| {
+        parse::Error::new(segment[0].span(), "expected `core = N`, the core of the item")
+    })?;
+    let core = match lit.base10_parse::<u8>() {
+        Ok(core) if core < CORES => core,
+        _ => {
+            return Err(parse::Error::new(
+                lit.span(),
+                "the device has two cores, this literal must be 0 or 1",
+            ))
+        }
+    };
+
+    attr.meta = if segments.is_empty() {
+        Meta::Path(list.path.clone())
+    } else {
+        let mut list = list.clone();
+        list.tokens = join(&segments);
+        Meta::List(list)
+    };
+
+    Ok(Some((core, segment[0].span())))
+}
+
+/// Assigns the spinlocks of the `#[cross_core]` resources of core 0 and adds them to the
+/// resources of core 1, as held by core 0
+///
+/// Spinlocks not given with `#[cross_core(spinlock = N)]` are taken from 30 downwards.
+fn share_cross_core(per_core: &mut [Vec<Item>]) -> parse::Result<()> {
+    let is_shared = |item: &Item| match item {
+        Item::Struct(s) => s.attrs.iter().any(|a| a.path().is_ident("shared")),
+        _ => false,
+    };
+    fn fields(item: &mut Item) -> Vec<&mut Field> {
+        match item {
+            Item::Struct(s) => s.fields.iter_mut().collect(),
+            _ => vec![],
+        }
+    }
+    let is_cross_core = |attr: &Attribute| attr.path().is_ident("cross_core");
+
+    for item in per_core[1..].iter_mut().flatten().filter(|item| is_shared(item)) {
+        if let Some(attr) = fields(item).iter().flat_map(|f| &f.attrs).find(|a| is_cross_core(a)) {
+            return Err(parse::Error::new(
+                attr.path().span(),
+                "`#[cross_core]` resources are declared in the `#[shared]` struct of core 0, \
+                 which initializes them",
+            ));
+        }
+    }
+
+    let Some(shared) = per_core[0].iter_mut().find(|item| is_shared(item)) else {
+        return Ok(());
+    };
+    let mut cross_core: Vec<_> = fields(shared)
+        .into_iter()
+        .filter_map(|field| {
+            let pos = field.attrs.iter().position(is_cross_core)?;
+            Some((field, pos))
+        })
+        .collect();
+    if cross_core.is_empty() {
+        return Ok(());
+    }
+
+    if !cfg!(feature = "rp2040") {
+        let (field, pos) = &cross_core[0];
+        return Err(parse::Error::new(
+            field.attrs[*pos].path().span(),
+            "`#[cross_core]` resources are guarded by the hardware spinlocks of the RP2040, they \
+             need the `thumbv6-rp2040-backend`",
+        ));
+    }
+
+    // The spinlocks given explicitly, then the others
+    let mut spinlocks = vec![None; cross_core.len()];
+    for ((field, pos), spinlock) in cross_core.iter().zip(&mut spinlocks) {
+        if let Meta::List(list) = &field.attrs[*pos].meta {
+            *spinlock = segments(list.tokens.clone())
+                .into_iter()
+                .find(|s| key(s).is_some_and(|k| k == "spinlock"))
+                .and_then(|s| syn::parse2::<LitInt>(s[2..].iter().cloned().collect()).ok())
+                .and_then(|lit| lit.base10_parse::<u8>().ok());
+        }
+    }
+    let free: Vec<_> = (0..31u8)
+        .rev()
+        .filter(|n| !spinlocks.contains(&Some(*n)))
+        .collect();
+    let mut free = free.into_iter();
+    for spinlock in &mut spinlocks {
+        if spinlock.is_none() {
+            *spinlock = free.next();
+        }
+    }
+
+    let mut remote = vec![];
+    for ((field, pos), spinlock) in cross_core.iter_mut().zip(spinlocks) {
+        let Some(spinlock) = spinlock else {
+            return Err(parse::Error::new(
+                field.attrs[*pos].path().span(),
+                "all the spinlocks of the RP2040 are taken",
+            ));
+        };
+
+        // Errors on the attribute point at the one of the user
+        let span = field.attrs[*pos].path().span();
+        let mut spinlock = proc_macro2::Literal::u8_unsuffixed(spinlock);
+        spinlock.set_span(span);
+        field.attrs[*pos] = syn::parse_quote_spanned!(span=> #[cross_core(spinlock = #spinlock)]);
+
+        let mut field = field.clone();
+        field.attrs[*pos] =
+            syn::parse_quote_spanned!(span=> #[cross_core(spinlock = #spinlock, core = 0)]);
+        remote.push(field);
+    }
+
+    if let Some(Item::Struct(s)) = per_core[1].iter_mut().find(|item| is_shared(item)) {
+        if let Fields::Named(fields) = &mut s.fields {
+            fields.named.extend(remote);
+        }
+    }
+
+    Ok(())
+}
+
+/// The resources and entry point a core declares
+#[derive(Default)]
+struct Resources {
+    init: bool,
+    has_shared: bool,
+    has_local: bool,
+    shared: HashSet<Ident>,
+    local: HashSet<Ident>,
+}
+
+impl Resources {
+    fn of(items: &[Item]) -> Self {
+        let mut resources = Resources::default();
+
+        for item in items {
+            match item {
+                Item::Fn(f) if f.attrs.iter().any(|a| a.path().is_ident("init")) => {
+                    resources.init = true;
+                }
+
+                Item::Struct(s) => {
+                    let fields = match &s.fields {
+                        Fields::Named(fields) => fields.named.iter().flat_map(|f| f.ident.clone()),
+                        _ => continue,
+                    };
+
+                    if s.attrs.iter().any(|a| a.path().is_ident("shared")) {
+                        resources.has_shared = true;
+                        resources.shared.extend(fields);
+                    } else if s.attrs.iter().any(|a| a.path().is_ident("local")) {
+                        resources.has_local = true;
+                        resources.local.extend(fields);
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        resources
+    }
+}
+
+/// Rejects accesses of the tasks of `core` to the resources of the other core
+fn check_accesses(core: usize, items: &[Item], resources: &[Resources]) -> parse::Result<()> {
+    let attrs = items.iter().flat_map(|item| match item {
+        Item::Fn(f) => f.attrs.iter().collect::<Vec<_>>(),
+        Item::ForeignMod(block) => block
+            .items
+            .iter()
+            .flat_map(|foreign| match foreign {
+                ForeignItem::Fn(f) => f.attrs.iter().collect(),
+                _ => vec![],
+            })
+            .collect(),
+        _ => vec![],
+    });
+
+    for attr in attrs {
+        let Meta::List(list) = &attr.meta else {
+            continue;
+        };
+        if !["init", "idle", "task"].iter().any(|name| list.path.is_ident(name)) {
+            continue;
+        }
+
+        for segment in segments(list.tokens.clone()) {
+            let kind = match key(&segment).as_deref() {
+                Some("shared") => "shared",
+                Some("local") => "local",
+                _ => continue,
+            };
+            let Some(TokenTree::Group(group)) = segment.get(2) else {
+                continue;
+            };
+
+            for access in segments(group.stream()) {
+                // `x`, `&x` or a task local `x: T = ..`
+                let Some(name) = access.iter().find_map(|tt| match tt {
+                    TokenTree::Ident(ident) => Some(ident.clone()),
+                    _ => None,
+                }) else {
+                    continue;
+                };
+
+                let of = |resources: &Resources| {
+                    if kind == "shared" {
+                        resources.shared.contains(&name)
+                    } else {
+                        resources.local.contains(&name)
+                    }
+                };
+                if of(&resources[core]) {
+                    continue;
+                }
+
+                if let Some(other) = resources.iter().position(of) {
+                    return Err(parse::Error::new(
+                        name.span(),
+                        format!(
+                            "`{name}` is a {kind} resource of core {other}, it can not be \
+                             accessed from core {core}; pass data between the cores through \
+                             memory both reach, see `rtic::{DEVICE}`"
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The arguments of the application and the overrides of core 1 in `core1 = [..]`
+fn core_args(args: TokenStream2) -> parse::Result<(Segments, Segments)> {
+    let mut base = vec![];
+    let mut core1 = vec![];
+
+    for segment in segments(args) {
+        match key(&segment).as_deref() {
+            Some("core1") => match segment.get(2) {
+                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => {
+                    core1 = segments(group.stream());
+                }
+                _ => {
+                    return Err(parse::Error::new(
+                        segment[0].span(),
+                        "expected `core1 = [..]`, the arguments of the application of core 1",
+                    ))
+                }
+            },
+
+            Some("backend") if backend_core(&segment).is_some() => {
+                return Err(parse::Error::new(
+                    backend_core(&segment).unwrap(),
+                    "the cores of a multi-core application are given by `core = N` of its items",
+                ));
+            }
+
+            _ => base.push(segment),
+        }
+    }
+
+    Ok((base, core1))
+}
+
+/// The arguments of the application with the ones of `overrides` replaced or added
+fn override_args(base: &[Vec<TokenTree>], overrides: &[Vec<TokenTree>]) -> Segments {
+    let mut args = base.to_vec();
+
+    for segment in overrides {
+        match args.iter_mut().find(|arg| key(arg).is_some() && key(arg) == key(segment)) {
+            Some(arg) => *arg = segment.clone(),
+            None => args.push(segment.clone()),
+        }
+    }
+
+    args
+}
+
+/// Adds `core = N` to the `backend` argument
+fn with_core(mut args: Segments, core: u8) -> parse::Result<TokenStream2> {
+    if let Some(span) = args.iter().find_map(|arg| backend_core(arg)) {
+        return Err(parse::Error::new(
+            span,
+            "the cores of a multi-core application are given by `core = N` of its items",
+        ));
+    }
+
+    let core = proc_macro2::Literal::u8_unsuffixed(core);
+    let core = quote!(core = #core);
+    match args.iter_mut().find(|arg| key(arg).is_some_and(|k| k == "backend")) {
+        Some(arg) => {
+            let Some(TokenTree::Group(group)) = arg.get(2) else {
+                return Err(parse::Error::new(arg[0].span(), "expected `backend = [..]`"));
+            };
+
+            let mut inner = segments(group.stream());
+            inner.push(core.into_iter().collect());
+            let mut group = Group::new(Delimiter::Bracket, join(&inner));
+            group.set_span(arg[2].span());
+            arg[2] = TokenTree::Group(group);
+        }
+        None => args.push(quote!(backend = [#core]).into_iter().collect()),
+    }
+
+    Ok(join(&args))
+}
+
+/// The span of `core` in a `backend = [..]` argument giving it
+fn backend_core(segment: &[TokenTree]) -> Option<Span> {
+    let Some(TokenTree::Group(group)) = segment.get(2) else {
+        return None;
+    };
+
+    segments(group.stream())
+        .into_iter()
+        .find(|s| key(s).is_some_and(|k| k == "core"))
+        .map(|s| s[0].span())
+}
+
+/// The tokens split at the commas outside of groups
+fn segments(tokens: TokenStream2) -> Segments {
+    let mut segments = vec![vec![]];
+
+    for tt in tokens {
+        match &tt {
+            TokenTree::Punct(p) if p.as_char() == ',' => segments.push(vec![]),
+            _ => segments.last_mut().unwrap().push(tt),
+        }
+    }
+
+    segments.retain(|s| !s.is_empty());
+    segments
+}
+
+/// The tokens of the segments separated by commas
+fn join(segments: &[Vec<TokenTree>]) -> TokenStream2 {
+    let mut tokens = TokenStream2::new();
+
+    for (i, segment) in segments.iter().enumerate() {
+        if i > 0 {
+            Punct::new(',', Spacing::Alone).to_tokens(&mut tokens);
+        }
+        tokens.extend(segment.iter().cloned());
+    }
+
+    tokens
+}
+
+/// `name` of a `name = value` segment
+fn key(segment: &[TokenTree]) -> Option<String> {
+    match segment {
+        [TokenTree::Ident(ident), TokenTree::Punct(eq), ..] if eq.as_char() == '=' => {
+            Some(ident.to_string())
+        }
+        _ => None,
+    }
+}
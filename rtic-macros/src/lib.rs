@@ -16,13 +16,29 @@ macro_rules! with_backend {
             feature = "test-template",
             feature = "riscv-esp32c3",
             feature = "riscv-esp32c6",
+            feature = "riscv-esp32p4",
             feature = "riscv-slic",
+            feature = "riscv-soft-slic",
+            feature = "riscv-eclic",
+            feature = "riscv-plic",
+            feature = "xtensa-esp32s3",
+            feature = "riscv-rp2350",
+            feature = "avr",
+            feature = "null",
+            feature = "std",
+            feature = "wasm",
+            feature = "polling",
+            feature = "riscv-ch32",
         ))]
         $($tokens)*
     };
 }
 
-with_backend! { mod: [analyze, check, codegen, preprocess, syntax] }
+with_backend! { mod: [analyze, c_header, check, cores, codegen, preprocess, stack, syntax, task_interface, vectors] }
+with_backend! {
+    #[cfg(feature = "model-export")]
+    mod model;
+}
 with_backend! { use std::{fs, env, path::Path}; }
 with_backend! { use proc_macro::TokenStream; }
 
@@ -39,6 +55,20 @@ with_backend! {
     }
 }
 
+with_backend! {
+    /// Attribute used to declare a trait of tasks a library spawns, implemented by the tasks of
+    /// an application
+    ///
+    /// For user documentation see the [RTIC book](https://rtic.rs)
+    #[proc_macro_attribute]
+    pub fn task_interface(args: TokenStream, input: TokenStream) -> TokenStream {
+        match task_interface::expand(args.into(), input.into()) {
+            Ok(ts) => ts.into(),
+            Err(e) => e.to_compile_error().into(),
+        }
+    }
+}
+
 with_backend! {
     /// Attribute used to declare a RTIC application
     ///
@@ -48,27 +78,113 @@ with_backend! {
     ///
     /// Should never panic, cargo feeds a path which is later converted to a string
     #[proc_macro_attribute]
-    pub fn app(_args: TokenStream, _input: TokenStream) -> TokenStream {
-        let (mut app, analysis) = match syntax::parse(_args, _input) {
+    pub fn app(args: TokenStream, input: TokenStream) -> TokenStream {
+        let split = match cores::split(args.clone().into(), input.clone().into()) {
             Err(e) => return e.to_compile_error().into(),
-            Ok(x) => x,
+            Ok(split) => split,
         };
 
-        // Modify app based on backend before continuing
-        if let Err(e) = preprocess::app(&mut app, &analysis) {
-            return e.to_compile_error().into();
+        let Some(split) = split else {
+            return match expand(args.into(), input.into(), "") {
+                Err(e) => e.to_compile_error().into(),
+                Ok(ts) => ts.into(),
+            };
+        };
+
+        // One application per core, in the module of the application
+        let mut expansions = vec![];
+        for core in &split.cores {
+            let suffix = if core.core == 0 {
+                String::new()
+            } else {
+                format!("-core{}", core.core)
+            };
+
+            match expand(core.args.clone(), core.input.clone(), &suffix) {
+                Err(e) => return e.to_compile_error().into(),
+                Ok(ts) => expansions.push(ts),
+            }
         }
+
+        let ts = split.wrap(expansions);
+        if let Some(out_dir) = out_dir() {
+            fs::write(format!("{out_dir}/rtic-expansion.rs"), ts.to_string()).ok();
+        }
+
+        ts.into()
+    }
+}
+
+with_backend! {
+    /// Parses, checks and generates the code of an application, `suffix` tells apart the files
+    /// written for each core of a multi-core application
+    fn expand(
+        args: proc_macro2::TokenStream,
+        input: proc_macro2::TokenStream,
+        suffix: &str,
+    ) -> Result<proc_macro2::TokenStream, syn::parse::Error> {
+        let (mut app, analysis) = syntax::parse2(args, input)?;
+
+        // Modify app based on backend before continuing
+        preprocess::app(&mut app, &analysis)?;
         let app = app;
         // App is not mutable after this point
 
-        if let Err(e) = check::app(&app, &analysis) {
-            return e.to_compile_error().into();
-        }
+        check::app(&app, &analysis)?;
 
         let analysis = analyze::app(analysis, &app);
 
         let ts = codegen::app(&app, &analysis);
 
+        // Try to write the expanded code to disk
+        if let Some(out_str) = out_dir() {
+            if suffix.is_empty() {
+                fs::write(format!("{out_str}/rtic-expansion.rs"), ts.to_string()).ok();
+            }
+
+            // One model per crate, `cargo xtask stack` reads it back after building each example
+            let krate = env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "app".to_string());
+            let krate = format!("{krate}{suffix}");
+            fs::write(
+                format!("{out_str}/rtic-stack-model-{krate}.txt"),
+                stack::model(&app, &analysis),
+            )
+            .ok();
+
+            // Read back by `cargo xtask vectors`, with the symbols of the linked example
+            fs::write(
+                format!("{out_str}/rtic-vector-model-{krate}.txt"),
+                vectors::model(&app, &analysis),
+            )
+            .ok();
+
+            // For the C code of mixed projects, only written if a resource is exported
+            if let Some(header) = c_header::header(&app) {
+                fs::write(format!("{out_str}/rtic-resources-{krate}.h"), header).ok();
+            }
+
+            if app.args.strict {
+                fs::write(
+                    format!("{out_str}/rtic-strict-report{suffix}.md"),
+                    syntax::strict::report(&app, &analysis),
+                )
+                .ok();
+            }
+
+            #[cfg(feature = "model-export")]
+            {
+                fs::write(format!("{out_str}/rtic_model.tla"), model::tla(&app, &analysis)).ok();
+                fs::write(format!("{out_str}/rtic-model.xml"), model::uppaal(&app, &analysis)).ok();
+            }
+        }
+
+        Ok(ts)
+    }
+}
+
+with_backend! {
+    /// The directory the expansion and the models are written to
+    fn out_dir() -> Option<String> {
         // Default output path: <project_dir>/target/
         let mut out_dir = Path::new("target");
 
@@ -105,12 +221,7 @@ with_backend! {
             }
         }
 
-        // Try to write the expanded code to disk
-        if let Some(out_str) = out_dir.to_str() {
-            fs::write(format!("{out_str}/rtic-expansion.rs"), ts.to_string()).ok();
-        }
-
-        ts.into()
+        out_dir.to_str().map(str::to_string)
     }
 }
 
@@ -120,6 +231,18 @@ with_backend! {
     feature = "test-template",
     feature = "riscv-esp32c3",
     feature = "riscv-esp32c6",
+    feature = "riscv-esp32p4",
     feature = "riscv-slic",
+    feature = "riscv-soft-slic",
+    feature = "riscv-eclic",
+    feature = "riscv-plic",
+    feature = "xtensa-esp32s3",
+    feature = "riscv-rp2350",
+    feature = "avr",
+    feature = "null",
+    feature = "std",
+    feature = "wasm",
+    feature = "polling",
+    feature = "riscv-ch32",
 )))]
 compile_error!("Cannot compile. No backend feature selected.");
@@ -0,0 +1,413 @@
+//! Export of the scheduling model of an application for formal verification tools
+//!
+//! The model holds what the macro knows about the application: its tasks with their priorities,
+//! the shared resources with their ceilings and which task uses which resource. Execution times
+//! and arrival rates depend on the code and the environment, they are left for the user to fill
+//! in.
+
+use crate::analyze::Analysis;
+use crate::syntax::{analyze::Ownership, ast::App};
+use std::fmt::Write;
+use syn::Ident;
+
+/// A task as seen by the scheduler
+struct Task {
+    name: String,
+    priority: u8,
+    resources: Vec<String>,
+    spawns: Vec<String>,
+}
+
+/// A shared resource and its ceiling
+struct Resource {
+    name: String,
+    ceiling: u8,
+}
+
+struct Model {
+    name: String,
+    tasks: Vec<Task>,
+    resources: Vec<Resource>,
+    /// Tasks spawned by `init`
+    initial: Vec<String>,
+    /// Cycles in the spawn graph, as the task names along them
+    cycles: Vec<Vec<String>>,
+}
+
+impl Model {
+    fn new(app: &App, analysis: &Analysis) -> Self {
+        // `idle` is the background of the model rather than a task with jobs
+        let mut tasks = vec![];
+
+        for (name, task) in &app.hardware_tasks {
+            tasks.push(Task {
+                name: name.to_string(),
+                priority: task.args.priority,
+                resources: task.args.shared_resources.keys().map(|r| r.to_string()).collect(),
+                spawns: spawned_by(analysis, name),
+            });
+        }
+
+        for (name, task) in &app.software_tasks {
+            tasks.push(Task {
+                name: name.to_string(),
+                priority: task.args.priority,
+                resources: task.args.shared_resources.keys().map(|r| r.to_string()).collect(),
+                spawns: spawned_by(analysis, name),
+            });
+        }
+
+        let resources = app
+            .shared_resources
+            .keys()
+            .filter_map(|name| {
+                let ceiling = match analysis.ownerships.get(name)? {
+                    Ownership::Owned { priority } | Ownership::CoOwned { priority } => *priority,
+                    Ownership::Contended { ceiling } => *ceiling,
+                };
+
+                Some(Resource {
+                    name: name.to_string(),
+                    ceiling,
+                })
+            })
+            .collect();
+
+        Model {
+            name: app.name.to_string(),
+            tasks,
+            resources,
+            initial: spawned_by(analysis, &app.init.name),
+            cycles: analysis
+                .spawn_cycles()
+                .iter()
+                .map(|cycle| cycle.iter().map(|task| task.to_string()).collect())
+                .collect(),
+        }
+    }
+}
+
+fn spawned_by(analysis: &Analysis, task: &Ident) -> Vec<String> {
+    analysis
+        .spawned_by(task)
+        .into_iter()
+        .map(|task| task.to_string())
+        .collect()
+}
+
+/// The model as a TLA+ module, `rtic_model.tla`
+///
+/// Tasks and resources are records keyed by name, so specifications can refer to them as
+/// `Priority.foo` or iterate over `Tasks`.
+pub fn tla(app: &App, analysis: &Analysis) -> String {
+    let model = Model::new(app, analysis);
+    let mut s = String::new();
+
+    let record = |entries: Vec<String>| {
+        if entries.is_empty() {
+            "[x \\in {} |-> 0]".to_string()
+        } else {
+            format!("[{}]", entries.join(", "))
+        }
+    };
+    let set = |items: Vec<String>| format!("{{{}}}", items.join(", "));
+
+    writeln!(s, "---- MODULE rtic_model ----").ok();
+    writeln!(s, "\\* Scheduling model of the RTIC application `{}`", model.name).ok();
+    writeln!(s, "\\* Generated by `#[rtic::app]`, changes are overwritten").ok();
+    writeln!(s, "EXTENDS Naturals").ok();
+    writeln!(s).ok();
+
+    writeln!(
+        s,
+        "Tasks == {}",
+        set(model.tasks.iter().map(|t| format!("\"{}\"", t.name)).collect())
+    )
+    .ok();
+    writeln!(
+        s,
+        "Resources == {}",
+        set(model
+            .resources
+            .iter()
+            .map(|r| format!("\"{}\"", r.name))
+            .collect())
+    )
+    .ok();
+    writeln!(s).ok();
+
+    writeln!(s, "\\* Static priority of each task, higher preempts lower").ok();
+    writeln!(
+        s,
+        "Priority == {}",
+        record(
+            model
+                .tasks
+                .iter()
+                .map(|t| format!("{} |-> {}", t.name, t.priority))
+                .collect()
+        )
+    )
+    .ok();
+    writeln!(s, "\\* Ceiling of each resource under the Stack Resource Policy").ok();
+    writeln!(
+        s,
+        "Ceiling == {}",
+        record(
+            model
+                .resources
+                .iter()
+                .map(|r| format!("{} |-> {}", r.name, r.ceiling))
+                .collect()
+        )
+    )
+    .ok();
+    writeln!(s, "\\* Shared resources each task may lock").ok();
+    writeln!(
+        s,
+        "Uses == {}",
+        record(
+            model
+                .tasks
+                .iter()
+                .map(|t| {
+                    let resources = t.resources.iter().map(|r| format!("\"{r}\"")).collect();
+                    format!("{} |-> {}", t.name, set(resources))
+                })
+                .collect()
+        )
+    )
+    .ok();
+    writeln!(s, "\\* Software tasks each task spawns, the activation graph").ok();
+    writeln!(
+        s,
+        "Spawns == {}",
+        record(
+            model
+                .tasks
+                .iter()
+                .map(|t| {
+                    let spawns = t.spawns.iter().map(|t| format!("\"{t}\"")).collect();
+                    format!("{} |-> {}", t.name, set(spawns))
+                })
+                .collect()
+        )
+    )
+    .ok();
+    writeln!(s, "\\* Software tasks spawned by `init`").ok();
+    writeln!(
+        s,
+        "Initial == {}",
+        set(model.initial.iter().map(|t| format!("\"{t}\"")).collect())
+    )
+    .ok();
+    for cycle in &model.cycles {
+        writeln!(s, "\\* WARNING: spawn cycle {}", cycle.join(" -> ")).ok();
+    }
+    writeln!(s).ok();
+
+    writeln!(s, "\\* TODO: worst case execution time of each task").ok();
+    writeln!(
+        s,
+        "WCET == {}",
+        record(
+            model
+                .tasks
+                .iter()
+                .map(|t| format!("{} |-> 0", t.name))
+                .collect()
+        )
+    )
+    .ok();
+    writeln!(s, "\\* TODO: minimum inter-arrival time of each task").ok();
+    writeln!(
+        s,
+        "MinInterArrival == {}",
+        record(
+            model
+                .tasks
+                .iter()
+                .map(|t| format!("{} |-> 0", t.name))
+                .collect()
+        )
+    )
+    .ok();
+    writeln!(s, "====").ok();
+
+    s
+}
+
+/// The model as an UPPAAL timed automata network, `rtic-model.xml`
+///
+/// Each task is an instance of the `Task` template. The skeleton releases, dispatches and
+/// completes jobs within their WCET; preemption (stopwatches) and resource locking are to be
+/// refined by the user.
+pub fn uppaal(app: &App, analysis: &Analysis) -> String {
+    let model = Model::new(app, analysis);
+    let mut decl = String::new();
+
+    let array = |items: Vec<String>| format!("{{{}}}", items.join(", "));
+    let n = model.tasks.len();
+    let r = model.resources.len();
+
+    writeln!(decl, "// Scheduling model of the RTIC application `{}`", model.name).ok();
+    writeln!(decl, "// Generated by `#[rtic::app]`, changes are overwritten").ok();
+    writeln!(decl, "const int N = {n};").ok();
+    writeln!(decl, "typedef int[0, N - 1] id_t;").ok();
+    writeln!(decl).ok();
+    writeln!(
+        decl,
+        "// Tasks: {}",
+        model
+            .tasks
+            .iter()
+            .enumerate()
+            .map(|(i, t)| format!("{i} = {}", t.name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+    .ok();
+    writeln!(
+        decl,
+        "const int PRIO[N] = {};",
+        array(model.tasks.iter().map(|t| t.priority.to_string()).collect())
+    )
+    .ok();
+    writeln!(decl, "// TODO: worst case execution time of each task").ok();
+    writeln!(decl, "const int WCET[N] = {};", array(vec!["0".into(); n])).ok();
+    writeln!(decl, "// TODO: minimum inter-arrival time of each task").ok();
+    writeln!(decl, "const int MIAT[N] = {};", array(vec!["0".into(); n])).ok();
+
+    if r > 0 {
+        writeln!(decl).ok();
+        writeln!(
+            decl,
+            "// Resources: {}",
+            model
+                .resources
+                .iter()
+                .enumerate()
+                .map(|(i, r)| format!("{i} = {}", r.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+        .ok();
+        writeln!(decl, "const int R = {r};").ok();
+        writeln!(
+            decl,
+            "const int CEIL[R] = {};",
+            array(model.resources.iter().map(|r| r.ceiling.to_string()).collect())
+        )
+        .ok();
+        writeln!(
+            decl,
+            "const bool USES[N][R] = {};",
+            array(
+                model
+                    .tasks
+                    .iter()
+                    .map(|t| array(
+                        model
+                            .resources
+                            .iter()
+                            .map(|r| t.resources.contains(&r.name).to_string())
+                            .collect()
+                    ))
+                    .collect()
+            )
+        )
+        .ok();
+    }
+
+    writeln!(decl).ok();
+    writeln!(decl, "// SPAWNS[i][j]: task i spawns task j, the activation graph").ok();
+    writeln!(
+        decl,
+        "const bool SPAWNS[N][N] = {};",
+        array(
+            model
+                .tasks
+                .iter()
+                .map(|t| array(
+                    model
+                        .tasks
+                        .iter()
+                        .map(|s| t.spawns.contains(&s.name).to_string())
+                        .collect()
+                ))
+                .collect()
+        )
+    )
+    .ok();
+    for cycle in &model.cycles {
+        writeln!(decl, "// WARNING: spawn cycle {}", cycle.join(" -> ")).ok();
+    }
+
+    writeln!(decl).ok();
+    writeln!(decl, "// Priority of the running task, 0 when idle").ok();
+    writeln!(decl, "int running = 0;").ok();
+
+    let instances = model
+        .tasks
+        .iter()
+        .enumerate()
+        .map(|(i, t)| format!("{} = Task({i});", t.name))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let system = model
+        .tasks
+        .iter()
+        .map(|t| t.name.clone())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let system = if system.is_empty() {
+        String::new()
+    } else {
+        format!("{instances}\nsystem {system};")
+    };
+
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<!DOCTYPE nta PUBLIC '-//Uppaal Team//DTD Flat System 1.1//EN' 'http://www.it.uu.se/research/group/darts/uppaal/flat-1_2.dtd'>
+<nta>
+  <declaration>{decl}</declaration>
+  <template>
+    <name>Task</name>
+    <parameter>const id_t id</parameter>
+    <declaration>clock x;
+int preempted;</declaration>
+    <location id="idle"><name>Idle</name></location>
+    <location id="ready"><name>Ready</name></location>
+    <location id="running"><name>Running</name><label kind="invariant">{running_inv}</label></location>
+    <init ref="idle"/>
+    <transition>
+      <source ref="idle"/><target ref="ready"/>
+      <label kind="guard">{release_guard}</label>
+      <label kind="assignment">x = 0</label>
+    </transition>
+    <transition>
+      <source ref="ready"/><target ref="running"/>
+      <label kind="guard">{dispatch_guard}</label>
+      <label kind="assignment">preempted = running, running = PRIO[id], x = 0</label>
+    </transition>
+    <transition>
+      <source ref="running"/><target ref="idle"/>
+      <label kind="assignment">running = preempted</label>
+    </transition>
+  </template>
+  <system>{system}</system>
+</nta>
+"#,
+        decl = escape(&decl),
+        running_inv = escape("x <= WCET[id]"),
+        release_guard = escape("x >= MIAT[id]"),
+        dispatch_guard = escape("PRIO[id] > running"),
+        system = escape(&system),
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,53 @@
+//! Stack model of an application, `rtic-stack-model-<crate>.txt`
+//!
+//! Lists the functions that start a stack frame at each priority level: the interrupt handlers of
+//! the hardware tasks and dispatchers, and at level 0 `main` with the functions it calls one after
+//! the other. Under the priority ceiling protocol at most one handler per level is on the stack,
+//! so the worst case stack depth is the deepest handler of each level summed over the levels.
+//! The frame sizes are only known after linking, `cargo xtask stack` combines the two.
+
+use crate::analyze::Analysis;
+use crate::syntax::ast::App;
+use std::{collections::BTreeMap, fmt::Write};
+
+/// The model as `<priority> <symbol>` lines
+///
+/// Symbols at level 0 other than `main` are paths relative to the crate root, they run one after
+/// the other from `main` so only the deepest of them counts.
+pub fn model(app: &App, analysis: &Analysis) -> String {
+    let mut levels: BTreeMap<u8, Vec<String>> = BTreeMap::new();
+    let name = &app.name;
+
+    let background = levels.entry(0).or_default();
+    background.push("main".to_string());
+    background.push(format!("{name}::main::__rtic_init_resources"));
+    background.push(format!("{name}::{}", app.init.name));
+    if let Some(idle) = &app.idle {
+        background.push(format!("{name}::{}", idle.name));
+    } else if analysis.channels.contains_key(&0) {
+        background.push(format!("{name}::__rtic_internal_async_0_prio_dispatcher"));
+    }
+
+    for task in app.hardware_tasks.values() {
+        levels
+            .entry(task.args.priority)
+            .or_default()
+            .push(task.args.binds.to_string());
+    }
+
+    for (level, (interrupt, _)) in &analysis.interrupts {
+        levels.entry(*level).or_default().push(interrupt.to_string());
+    }
+
+    let mut s = String::new();
+    writeln!(s, "# Stack model of the RTIC application `{name}`").ok();
+    writeln!(s, "# Generated by `#[rtic::app]`, changes are overwritten").ok();
+    writeln!(s, "# <priority> <symbol>").ok();
+    for (level, symbols) in levels {
+        for symbol in symbols {
+            writeln!(s, "{level} {symbol}").ok();
+        }
+    }
+
+    s
+}
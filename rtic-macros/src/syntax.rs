@@ -12,9 +12,13 @@ use crate::syntax::ast::App;
 mod accessors;
 pub mod analyze;
 pub mod ast;
-mod backend;
+pub(crate) mod backend;
+pub mod blocking;
 mod check;
+pub mod latency;
 mod parse;
+pub mod scan;
+pub mod strict;
 
 /// An ordered map keyed by identifier
 pub type Map<T> = IndexMap<Ident, T>;
@@ -109,11 +113,20 @@ pub fn parse2(
     let app = parse::app(args, input)?;
     check::app(&app)?;
 
-    match analyze::app(&app) {
-        Err(e) => Err(e),
-        // If no errors, return the app and analysis results
-        Ok(analysis) => Ok((app, analysis)),
+    if let Some(priority) = app.args.blocking_priority {
+        blocking::app(&app, priority)?;
     }
+
+    let analysis = analyze::app(&app)?;
+
+    if app.args.strict {
+        strict::app(&app, &analysis)?;
+    }
+
+    latency::app(&app, &analysis)?;
+
+    // If no errors, return the app and analysis results
+    Ok((app, analysis))
 }
 
 enum Either<A, B> {
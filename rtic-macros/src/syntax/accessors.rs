@@ -29,6 +29,12 @@ impl App {
                     .iter()
                     .map(move |(name, access)| (Some(task.args.priority), name, *access))
             }))
+            // The C callers of an exported resource lock it like a task of their priority
+            .chain(self.shared_resources.iter().filter_map(|(name, res)| {
+                res.properties
+                    .c_export
+                    .map(|priority| (Some(priority), name, Access::Exclusive))
+            }))
     }
 
     fn is_external(task_local: &TaskLocal) -> bool {
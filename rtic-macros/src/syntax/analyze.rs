@@ -8,7 +8,7 @@ use syn::{Ident, Type};
 
 use crate::syntax::{
     ast::{App, LocalResources, TaskLocal},
-    Set,
+    scan, Map, Set,
 };
 
 pub(crate) fn app(app: &App) -> Result<Analysis, syn::Error> {
@@ -297,8 +297,15 @@ pub(crate) fn app(app: &App) -> Result<Analysis, syn::Error> {
     // No channel should ever be empty
     debug_assert!(channels.values().all(|channel| !channel.tasks.is_empty()));
 
+    // Collect which tasks spawn which
+    let mut spawns = Spawns::new();
+    for (name, body) in scan::bodies(app) {
+        spawns.insert(name.clone(), scan::spawns(app, body));
+    }
+
     Ok(Analysis {
         channels,
+        spawns,
         shared_resources: used_shared_resource,
         local_resources: used_local_resource,
         ownerships,
@@ -324,6 +331,11 @@ pub struct Analysis {
     /// SPSC message channels
     pub channels: Channels,
 
+    /// Software tasks spawned by each task, one entry per spawn site
+    ///
+    /// Tasks defined outside of the application (`extern`) are not listed
+    pub spawns: Spawns,
+
     /// Shared resources
     ///
     /// If a resource is not listed here it means that's a "dead" (never
@@ -346,6 +358,9 @@ pub struct Analysis {
     pub sync_types: SyncTypes,
 }
 
+/// Spawn sites, keyed by spawning task
+pub type Spawns = Map<Vec<Ident>>;
+
 /// All channels, keyed by dispatch priority
 pub type Channels = BTreeMap<Priority, Channel>;
 
@@ -364,6 +379,63 @@ pub type SendTypes = Set<Box<Type>>;
 /// These types must implement the `Sync` trait
 pub type SyncTypes = Set<Box<Type>>;
 
+impl Analysis {
+    /// The software tasks `task` spawns, each listed once
+    pub fn spawned_by(&self, task: &Ident) -> Set<&Ident> {
+        self.spawns.get(task).into_iter().flatten().collect()
+    }
+
+    /// Cycles in the spawn graph, one through each task that is not on a previously found cycle
+    ///
+    /// A cycle starts with the name of a task and continues with the spawn sites along the cycle,
+    /// the last of which spawns the first task again.
+    pub fn spawn_cycles(&self) -> Vec<Vec<Ident>> {
+        let mut cycles: Vec<Vec<Ident>> = vec![];
+
+        for task in self.spawns.keys() {
+            if cycles.iter().any(|cycle| cycle.contains(task)) {
+                continue;
+            }
+
+            // Breadth first from `task`, remembering the spawn site each task was reached by
+            let mut reached_by: Map<(Ident, Ident)> = Map::new();
+            let mut queue = vec![task.clone()];
+            let mut closing = None;
+
+            while closing.is_none() && !queue.is_empty() {
+                let spawner = queue.remove(0);
+
+                for site in self.spawns.get(&spawner).into_iter().flatten() {
+                    if site == task {
+                        closing = Some((spawner.clone(), site.clone()));
+                        break;
+                    }
+
+                    if !reached_by.contains_key(site) {
+                        reached_by.insert(site.clone(), (spawner.clone(), site.clone()));
+                        queue.push(site.clone());
+                    }
+                }
+            }
+
+            if let Some((mut spawner, site)) = closing {
+                let mut cycle = vec![site];
+                while spawner != *task {
+                    let (previous, site) = reached_by[&spawner].clone();
+                    cycle.push(site);
+                    spawner = previous;
+                }
+                cycle.push(task.clone());
+                cycle.reverse();
+
+                cycles.push(cycle);
+            }
+        }
+
+        cycles
+    }
+}
+
 /// A channel used to send messages
 #[derive(Debug, Default)]
 pub struct Channel {
@@ -1,5 +1,7 @@
 //! Abstract Syntax Tree
 
+use std::ops::RangeInclusive;
+
 use syn::{Attribute, Expr, Ident, Item, ItemUse, Pat, PatType, Path, Stmt, Type};
 
 use crate::syntax::{backend::BackendArgs, Map};
@@ -41,6 +43,15 @@ pub struct App {
 
     /// Async software tasks: `#[task]`
     pub software_tasks: Map<SoftwareTask>,
+
+    /// Environment variables read by `env!(..)` task arguments
+    pub env_vars: Vec<String>,
+
+    /// The `rtic.toml` read by `config!(..)` arguments, if any
+    pub config_file: Option<std::path::PathBuf>,
+
+    /// The image of the ESP32-C6 LP core: the `#[lp_task]`s and the loop running them
+    pub lp_core: Vec<Item>,
 }
 
 /// Interrupts used to dispatch software tasks
@@ -52,6 +63,12 @@ pub type Dispatchers = Map<Dispatcher>;
 pub struct Dispatcher {
     /// Attributes that will apply to this interrupt handler
     pub attrs: Vec<Attribute>,
+
+    /// `#[executor(PATH)]`: static picking the ready tasks instead of the built-in bitmap
+    pub executor: Option<Path>,
+
+    /// `=> N` or `=> N..=M`: the priorities this interrupt may dispatch, any if `None`
+    pub priorities: Option<RangeInclusive<u8>>,
 }
 
 /// The arguments of the `#[app]` attribute
@@ -72,6 +89,47 @@ pub struct AppArgs {
     /// Backend-specific arguments
     #[allow(dead_code)]
     pub backend: Option<BackendArgs>,
+
+    /// Reject constructs problematic for certification
+    pub strict: bool,
+
+    /// The highest priority allowed to call blocking helpers
+    pub blocking_priority: Option<u8>,
+
+    /// The deepest interrupt nesting allowed, tracked at run time
+    pub max_nesting: Option<u8>,
+
+    /// Time-triggered schedule, run by a generated software task
+    pub schedule: Option<Schedule>,
+
+    /// Operating modes, the application starts in the first one
+    pub modes: Vec<Ident>,
+
+    /// Test harness mode, `idle` runs the `#[test_case]` tasks
+    pub test: bool,
+
+    /// `SCR.SLEEPONEXIT` instead of `idle`, the core never returns to thread mode after `init`
+    pub sleep_on_exit: bool,
+
+    /// Copy the vector table of the application to RAM and point the core at it before `init`
+    #[allow(dead_code)]
+    pub relocate_vectors: bool,
+}
+
+/// `schedule = (..)`: tasks spawned at fixed offsets within a major cycle
+#[derive(Debug)]
+pub struct Schedule {
+    /// The monotonic measuring the cycle
+    pub monotonic: Path,
+
+    /// The length of the major cycle
+    pub cycle: Expr,
+
+    /// The priority of the task walking the table
+    pub priority: Option<u8>,
+
+    /// `(offset, task)` pairs in the order of their offsets
+    pub table: Vec<(Expr, Ident)>,
 }
 
 /// The `init`-ialization function
@@ -167,6 +225,32 @@ impl Default for IdleArgs {
 pub struct SharedResourceProperties {
     /// A lock free (exclusive resource)
     pub lock_free: bool,
+
+    /// Tracks locks that did not complete, e.g. due to a panic
+    pub poison: bool,
+
+    /// The longest critical section on the resource, in microseconds
+    pub wcet: Option<u64>,
+
+    /// The highest priority of the C code calling the accessors of `#[c_export]`
+    pub c_export: Option<u8>,
+
+    /// The atomic type standing in for an `#[atomic]` resource, e.g. `AtomicU32`
+    pub atomic: Option<Ident>,
+
+    /// A `#[cross_core]` resource of a multi-core application
+    pub cross_core: Option<CrossCore>,
+}
+
+/// A resource of both cores of a multi-core application, locked by each core with its own
+/// ceiling and a hardware spinlock
+#[derive(Debug, Clone, Copy)]
+pub struct CrossCore {
+    /// The RP2040 spinlock guarding the resource
+    pub spinlock: u8,
+
+    /// The core holding the resource, `None` for the core declaring it
+    pub stored_by: Option<u8>,
 }
 
 /// A shared resource, defined in `#[shared]`
@@ -188,6 +272,9 @@ pub struct SharedResource {
     /// Shared resource properties
     pub properties: SharedResourceProperties,
 
+    /// The resources `init` constructs before this one, `#[depends_on(..)]`
+    pub depends_on: Vec<Ident>,
+
     /// The visibility of this resource
     pub vis: syn::Visibility,
 }
@@ -208,6 +295,9 @@ pub struct LocalResource {
     /// The type of this resource
     pub ty: Box<Type>,
 
+    /// The resources `init` constructs before this one, `#[depends_on(..)]`
+    pub depends_on: Vec<Ident>,
+
     /// The visibility of this resource
     pub vis: syn::Visibility,
 }
@@ -236,6 +326,9 @@ pub struct SoftwareTask {
 
     /// The task is declared externally
     pub is_extern: bool,
+
+    /// The task is a `#[test_case]` of the test harness
+    pub is_test_case: bool,
 }
 
 /// Software task metadata
@@ -250,6 +343,41 @@ pub struct SoftwareTaskArgs {
 
     /// Shared resources that can be accessed from this context
     pub shared_resources: SharedResources,
+
+    /// Capacity of the payload queue used by `spawn_batch`
+    pub batch: Option<usize>,
+
+    /// Backlog of the dispatcher above which the spawns of this task are dropped
+    pub shed: Option<usize>,
+
+    /// Relative deadline in microseconds, the priority is assigned from it
+    pub deadline: Option<u64>,
+
+    /// Modes the task is active in, all modes when empty
+    pub modes: Vec<Ident>,
+
+    /// `#[task_interface]` trait the task implements a method of
+    pub implements: Option<Path>,
+
+    /// The task body runs once per period, which can be changed at runtime
+    pub period: Option<Period>,
+
+    /// RISC-V: the task is polled in user mode on a stack of this many bytes, see `rtic::pmp`
+    pub isolated: Option<usize>,
+
+    /// RP2040: capacity of the queue of payloads spawned from the other core, see
+    /// `rtic::export::cross_core`
+    pub cross_core: Option<usize>,
+}
+
+/// `period = ..`: the initial period of a periodic software task
+#[derive(Debug)]
+pub struct Period {
+    /// The monotonic measuring the period
+    pub monotonic: Path,
+
+    /// The initial period, a duration of the monotonic
+    pub period: Expr,
 }
 
 impl Default for SoftwareTaskArgs {
@@ -258,6 +386,14 @@ impl Default for SoftwareTaskArgs {
             priority: 0,
             local_resources: LocalResources::new(),
             shared_resources: SharedResources::new(),
+            batch: None,
+            shed: None,
+            deadline: None,
+            modes: vec![],
+            implements: None,
+            period: None,
+            isolated: None,
+            cross_core: None,
         }
     }
 }
@@ -300,6 +436,29 @@ pub struct HardwareTaskArgs {
 
     /// Shared resources that can be accessed from this context
     pub shared_resources: SharedResources,
+
+    /// Relative deadline in microseconds, the priority is assigned from it
+    pub deadline: Option<u64>,
+
+    /// Modes the task is active in, all modes when empty
+    pub modes: Vec<Ident>,
+
+    /// Worst case blocking allowed, in microseconds
+    pub max_latency: Option<u64>,
+
+    /// ESP32-C6: how the bound interrupt is triggered, edge when `None`
+    #[allow(dead_code)]
+    pub trigger: Option<Trigger>,
+}
+
+/// `trigger = ..`: the interrupt type of the CPU interrupt a hardware task is bound to
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Trigger {
+    /// `edge`, taken once per edge of the source
+    Edge,
+
+    /// `level`, taken for as long as the source is asserted
+    Level,
 }
 
 /// A `static mut` variable local to and owned by a context
@@ -0,0 +1,54 @@
+use proc_macro2::Span;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    Error, Ident, Result, Token,
+};
+
+/// The highest priority, the priorities are kept in software
+const MAX_PRIORITY: u8 = 15;
+
+#[derive(Debug, Default)]
+pub struct BackendArgs {
+    /// The chip passed to `avr_device::interrupt`, the last segment of `device` by default
+    pub chip: Option<Ident>,
+}
+
+impl Parse for BackendArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        // [(chip = #ident)?]
+        let content;
+        bracketed!(content in input);
+
+        let mut args = BackendArgs::default();
+        while !content.is_empty() {
+            let ident: Ident = content.parse()?;
+            let _: Token![=] = content.parse()?;
+
+            if ident != "chip" {
+                return Err(Error::new(
+                    ident.span(),
+                    "unexpected argument; the AVR backend accepts `chip`",
+                ));
+            }
+            if args.chip.is_some() {
+                return Err(Error::new(
+                    ident.span(),
+                    "argument appears more than once",
+                ));
+            }
+            args.chip = Some(content.parse()?);
+
+            if !content.is_empty() {
+                let _: Token![,] = content.parse()?;
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// The highest priority a task can have, used to resolve `priority = max`
+pub fn max_priority(_args: Option<&BackendArgs>, _span: Span) -> Result<u8> {
+    Ok(MAX_PRIORITY)
+}
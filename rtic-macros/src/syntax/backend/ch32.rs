@@ -0,0 +1,73 @@
+use proc_macro2::Span;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    Error, Ident, LitInt, Result, Token,
+};
+
+#[derive(Debug)]
+pub struct BackendArgs {
+    /// Bits of the PFIC priority byte that preempt, the nesting depth is `2^preemption_bits`
+    pub preemption_bits: u8,
+}
+
+impl Default for BackendArgs {
+    fn default() -> Self {
+        // Two nesting levels are supported by all QingKe V4 cores
+        Self { preemption_bits: 1 }
+    }
+}
+
+impl Parse for BackendArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        // [(preemption_bits = #lit)?]
+        let content;
+        bracketed!(content in input);
+
+        let mut args = BackendArgs::default();
+        let mut seen = false;
+        while !content.is_empty() {
+            let ident: Ident = content.parse()?;
+            let _: Token![=] = content.parse()?;
+
+            if ident != "preemption_bits" {
+                return Err(Error::new(
+                    ident.span(),
+                    "unexpected argument; the CH32 backend accepts `preemption_bits`",
+                ));
+            }
+            if seen {
+                return Err(Error::new(
+                    ident.span(),
+                    "argument appears more than once",
+                ));
+            }
+            seen = true;
+
+            let lit: LitInt = content.parse()?;
+            args.preemption_bits = match lit.base10_parse::<u8>() {
+                Ok(bits) if (1..=3).contains(&bits) => bits,
+                _ => {
+                    return Err(Error::new(
+                        lit.span(),
+                        "this literal must be in the range 1...3",
+                    ))
+                }
+            };
+
+            if !content.is_empty() {
+                let _: Token![,] = content.parse()?;
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// The highest priority a task can have, used to resolve `priority = max`
+pub fn max_priority(args: Option<&BackendArgs>, _span: Span) -> Result<u8> {
+    let preemption_bits = args.map_or(BackendArgs::default().preemption_bits, |args| {
+        args.preemption_bits
+    });
+    Ok(1 << preemption_bits)
+}
@@ -1,16 +1,174 @@
+use proc_macro2::Span;
 use syn::{
+    bracketed,
     parse::{Parse, ParseStream},
-    Error, Result,
+    punctuated::Punctuated,
+    Error, Ident, LitInt, LitStr, Result, Token,
 };
 
-#[derive(Debug)]
-pub struct BackendArgs();
+/// ARMv8-M with TrustZone: the security state the application runs in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Security {
+    Secure,
+    NonSecure,
+}
+
+#[derive(Debug, Default)]
+pub struct BackendArgs {
+    /// Number of priority bits implemented by the NVIC of the device
+    pub nvic_prio_bits: Option<u8>,
+    /// ARMv8-M with TrustZone: linker sections mapped to non-secure RAM by the SAU/IDAU
+    pub nonsecure_sections: Vec<LitStr>,
+    /// ARMv8-M with TrustZone: tasks whose resources are accessed from the non-secure state
+    pub nonsecure_tasks: Vec<Ident>,
+    /// ARMv8-M with TrustZone: the security state of the application
+    pub security: Option<Security>,
+    /// ARMv8-M with TrustZone: interrupts handed to the non-secure image
+    pub nonsecure_interrupts: Vec<Ident>,
+    /// ARMv8-M with TrustZone: resources shared between non-secure and secure tasks
+    pub gateways: Vec<Ident>,
+    /// RP2040 and nRF5340: the core the application runs on
+    pub core: u8,
+}
 
 impl Parse for BackendArgs {
     fn parse(input: ParseStream) -> Result<Self> {
-        Err(Error::new(
-            input.span(),
-            "cortex backend does not accept any arguments",
-        ))
+        // [nvic_prio_bits = #lit, nonsecure_sections = [#lit, ..], nonsecure_tasks = [#ident, ..],
+        //  security = secure | nonsecure, nonsecure_interrupts = [#ident, ..],
+        //  gateways = [#ident, ..], core = #lit]
+        let content;
+        bracketed!(content in input);
+
+        let mut args = BackendArgs::default();
+        let mut seen = Vec::new();
+
+        while !content.is_empty() {
+            let ident: Ident = content.parse()?;
+            if seen.contains(&ident) {
+                return Err(Error::new(
+                    ident.span(),
+                    "argument appears more than once",
+                ));
+            }
+            seen.push(ident.clone());
+
+            let _: Token![=] = content.parse()?;
+
+            match &*ident.to_string() {
+                "nvic_prio_bits" => {
+                    let lit: LitInt = content.parse()?;
+                    args.nvic_prio_bits = match lit.base10_parse::<u8>() {
+                        Ok(bits) if (1..=7).contains(&bits) => Some(bits),
+                        _ => {
+                            return Err(Error::new(
+                                lit.span(),
+                                "this literal must be in the range 1...7",
+                            ))
+                        }
+                    };
+                }
+
+                "nonsecure_sections" => {
+                    let list;
+                    bracketed!(list in content);
+                    args.nonsecure_sections =
+                        Punctuated::<LitStr, Token![,]>::parse_terminated(&list)?
+                            .into_iter()
+                            .collect();
+                }
+
+                "nonsecure_tasks" => {
+                    let list;
+                    bracketed!(list in content);
+                    args.nonsecure_tasks = Punctuated::<Ident, Token![,]>::parse_terminated(&list)?
+                        .into_iter()
+                        .collect();
+                }
+
+                "security" => {
+                    let security: Ident = content.parse()?;
+                    args.security = match &*security.to_string() {
+                        "secure" => Some(Security::Secure),
+                        "nonsecure" => Some(Security::NonSecure),
+                        _ => {
+                            return Err(Error::new(
+                                security.span(),
+                                "expected `secure` or `nonsecure`",
+                            ))
+                        }
+                    };
+                }
+
+                "nonsecure_interrupts" => {
+                    let list;
+                    bracketed!(list in content);
+                    args.nonsecure_interrupts =
+                        Punctuated::<Ident, Token![,]>::parse_terminated(&list)?
+                            .into_iter()
+                            .collect();
+                }
+
+                "gateways" => {
+                    let list;
+                    bracketed!(list in content);
+                    args.gateways = Punctuated::<Ident, Token![,]>::parse_terminated(&list)?
+                        .into_iter()
+                        .collect();
+                }
+
+                "core" if cfg!(any(feature = "rp2040", feature = "nrf5340")) => {
+                    let lit: LitInt = content.parse()?;
+                    args.core = match lit.base10_parse::<u8>() {
+                        Ok(core) if core <= 1 => core,
+                        _ => {
+                            return Err(Error::new(
+                                lit.span(),
+                                "the device has two cores, this literal must be 0 or 1",
+                            ))
+                        }
+                    };
+                }
+
+                "core" => {
+                    return Err(Error::new(
+                        ident.span(),
+                        "`core` selects a core of the RP2040 or the nRF5340, it needs the \
+                         `thumbv6-rp2040-backend` or the `thumbv8main-nrf5340-backend`",
+                    ));
+                }
+
+                _ => {
+                    return Err(Error::new(
+                        ident.span(),
+                        "unexpected argument; the cortex backend accepts `nvic_prio_bits`, \
+                         `nonsecure_sections`, `nonsecure_tasks`, `security`, \
+                         `nonsecure_interrupts`, `gateways` and `core`",
+                    ));
+                }
+            }
+
+            if !content.is_empty() {
+                let _: Token![,] = content.parse()?;
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// The highest priority a task can have, used to resolve `priority = max`
+pub fn max_priority(args: Option<&BackendArgs>, span: Span) -> Result<u8> {
+    match args {
+        Some(BackendArgs {
+            nvic_prio_bits: Some(bits),
+            ..
+        }) => Ok(1 << bits),
+        // ARMv6-M and ARMv8-M Baseline always implement 2 priority bits
+        _ if cfg!(feature = "cortex-m-source-masking") => Ok(1 << 2),
+        _ => Err(Error::new(
+            span,
+            "the maximum priority depends on the device; \
+             declare it with `backend = [nvic_prio_bits = N]` in `#[app]`",
+        )),
     }
 }
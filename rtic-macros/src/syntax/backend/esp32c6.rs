@@ -1,16 +1,82 @@
+use proc_macro2::Span;
 use syn::{
+    bracketed,
     parse::{Parse, ParseStream},
-    Error, Result,
+    punctuated::Punctuated,
+    Error, Ident, LitInt, Result, Token,
 };
 
-#[derive(Debug)]
-pub struct BackendArgs();
+#[derive(Debug, Default)]
+pub struct BackendArgs {
+    /// CPU interrupts left out of the allocation, e.g. the ones used by the HAL or the radio
+    pub reserved: Vec<u8>,
+}
 
 impl Parse for BackendArgs {
     fn parse(input: ParseStream) -> Result<Self> {
-        Err(Error::new(
-            input.span(),
-            "esp32c6 backend does not accept any arguments",
-        ))
+        // [reserved = [#lit, ..]]
+        let content;
+        bracketed!(content in input);
+
+        let mut args = BackendArgs::default();
+        let mut seen = Vec::new();
+
+        while !content.is_empty() {
+            let ident: Ident = content.parse()?;
+            if seen.contains(&ident) {
+                return Err(Error::new(
+                    ident.span(),
+                    "argument appears more than once",
+                ));
+            }
+            seen.push(ident.clone());
+
+            let _: Token![=] = content.parse()?;
+
+            match &*ident.to_string() {
+                "reserved" => {
+                    let list;
+                    bracketed!(list in content);
+                    for lit in Punctuated::<LitInt, Token![,]>::parse_terminated(&list)? {
+                        match lit.base10_parse::<u8>() {
+                            Ok(id) if (1..=31).contains(&id) && !args.reserved.contains(&id) => {
+                                args.reserved.push(id)
+                            }
+                            Ok(id) if (1..=31).contains(&id) => {
+                                return Err(Error::new(
+                                    lit.span(),
+                                    "this CPU interrupt is listed more than once",
+                                ))
+                            }
+                            _ => {
+                                return Err(Error::new(
+                                    lit.span(),
+                                    "this literal must be in the range 1...31, CPU interrupt 0 \
+                                     is always reserved",
+                                ))
+                            }
+                        }
+                    }
+                }
+
+                _ => {
+                    return Err(Error::new(
+                        ident.span(),
+                        "unexpected argument; the esp32c6 backend accepts `reserved`",
+                    ));
+                }
+            }
+
+            if !content.is_empty() {
+                let _: Token![,] = content.parse()?;
+            }
+        }
+
+        Ok(args)
     }
 }
+
+/// The highest priority a task can have, used to resolve `priority = max`
+pub fn max_priority(_args: Option<&BackendArgs>, _span: Span) -> Result<u8> {
+    Ok(15)
+}
@@ -0,0 +1,24 @@
+use proc_macro2::Span;
+use syn::{
+    parse::{Parse, ParseStream},
+    Error, Result,
+};
+
+#[derive(Debug)]
+pub struct BackendArgs();
+
+impl Parse for BackendArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Err(Error::new(
+            input.span(),
+            "esp32s3 backend does not accept any arguments",
+        ))
+    }
+}
+
+/// The highest priority a task can have, used to resolve `priority = max`
+///
+/// The interrupt levels above 3 (`EXCMLEVEL`) can not be handled in Rust.
+pub fn max_priority(_args: Option<&BackendArgs>, _span: Span) -> Result<u8> {
+    Ok(3)
+}
@@ -0,0 +1,25 @@
+use proc_macro2::Span;
+use syn::{
+    parse::{Parse, ParseStream},
+    Error, Result,
+};
+
+#[derive(Debug)]
+pub struct BackendArgs();
+
+impl Parse for BackendArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Err(Error::new(
+            input.span(),
+            "polling backend does not accept any arguments",
+        ))
+    }
+}
+
+/// The highest priority a task can have, used to resolve `priority = max`
+///
+/// Priorities only order the runs of the superloop on the polling backend, any priority is
+/// accepted.
+pub fn max_priority(_args: Option<&BackendArgs>, _span: Span) -> Result<u8> {
+    Ok(u8::MAX)
+}
@@ -0,0 +1,149 @@
+use proc_macro2::Span;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    Error, Ident, LitInt, Result, Token,
+};
+
+/// Vendor flavour of the ECLIC-like interrupt controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// Nuclei N-class ECLIC
+    Nuclei,
+    /// T-Head E9xx CLIC
+    THead,
+}
+
+impl Variant {
+    /// Base address of the controller on the reference designs of the vendor
+    pub fn default_base(self) -> u64 {
+        match self {
+            Variant::Nuclei => 0xD200_0000,
+            Variant::THead => 0xE080_0000,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BackendArgs {
+    pub variant: Variant,
+    /// Bits of `clicintctl` encoding the interrupt level, the remaining ones encode the priority
+    /// within a level and are left unused
+    pub level_bits: u8,
+    /// Base address of the controller
+    pub base: u64,
+    /// Length of the hardware vector table, `None` to leave dispatch to the trap handler of the
+    /// runtime
+    pub vectored: Option<u16>,
+}
+
+impl Parse for BackendArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        // [variant = nuclei | thead, level_bits = #lit (, base = #lit)? (, vectored = #lit)?]
+        let content;
+        bracketed!(content in input);
+
+        let mut variant = None;
+        let mut level_bits = None;
+        let mut base = None;
+        let mut vectored = None;
+
+        while !content.is_empty() {
+            let ident: Ident = content.parse()?;
+            let _: Token![=] = content.parse()?;
+
+            match &*ident.to_string() {
+                "variant" if variant.is_none() => {
+                    let value: Ident = content.parse()?;
+                    variant = Some(match &*value.to_string() {
+                        "nuclei" => Variant::Nuclei,
+                        "thead" => Variant::THead,
+                        _ => {
+                            return Err(Error::new(
+                                value.span(),
+                                "expected `nuclei` or `thead`",
+                            ))
+                        }
+                    });
+                }
+
+                "level_bits" if level_bits.is_none() => {
+                    let lit: LitInt = content.parse()?;
+                    level_bits = Some(match lit.base10_parse::<u8>() {
+                        Ok(bits) if (1..=8).contains(&bits) => bits,
+                        _ => {
+                            return Err(Error::new(
+                                lit.span(),
+                                "this literal must be in the range 1...8",
+                            ))
+                        }
+                    });
+                }
+
+                "base" if base.is_none() => {
+                    let lit: LitInt = content.parse()?;
+                    base = Some(lit.base10_parse::<u32>()? as u64);
+                }
+
+                "vectored" if vectored.is_none() => {
+                    let lit: LitInt = content.parse()?;
+                    vectored = Some(match lit.base10_parse::<u16>() {
+                        Ok(len) if (1..=4096).contains(&len) => len,
+                        _ => {
+                            return Err(Error::new(
+                                lit.span(),
+                                "this literal must be in the range 1...4096",
+                            ))
+                        }
+                    });
+                }
+
+                "variant" | "level_bits" | "base" | "vectored" => {
+                    return Err(Error::new(
+                        ident.span(),
+                        "argument appears more than once",
+                    ));
+                }
+
+                _ => {
+                    return Err(Error::new(
+                        ident.span(),
+                        "unexpected argument; the ECLIC backend accepts `variant`, `level_bits`, `base` and `vectored`",
+                    ));
+                }
+            }
+
+            if !content.is_empty() {
+                let _: Token![,] = content.parse()?;
+            }
+        }
+
+        let variant = variant.ok_or_else(|| {
+            Error::new(
+                content.span(),
+                "missing `variant = nuclei` or `variant = thead`",
+            )
+        })?;
+        let level_bits = level_bits
+            .ok_or_else(|| Error::new(content.span(), "missing `level_bits = N`"))?;
+
+        Ok(BackendArgs {
+            variant,
+            level_bits,
+            base: base.unwrap_or_else(|| variant.default_base()),
+            vectored,
+        })
+    }
+}
+
+/// The highest priority a task can have, used to resolve `priority = max`
+pub fn max_priority(args: Option<&BackendArgs>, span: Span) -> Result<u8> {
+    match args {
+        // Level 0 is the level of `idle`, interrupts use the levels above it
+        Some(args) => Ok(((1u16 << args.level_bits) - 1) as u8),
+        None => Err(Error::new(
+            span,
+            "the ECLIC backend needs `backend = [variant = .., level_bits = N]` in `#[app]`",
+        )),
+    }
+}
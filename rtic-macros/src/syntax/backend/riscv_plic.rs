@@ -0,0 +1,99 @@
+use proc_macro2::Span;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    Error, Ident, LitInt, Result, Token,
+};
+
+/// Base address of the PLIC on the SiFive and Kendryte designs
+const DEFAULT_BASE: u64 = 0x0C00_0000;
+
+/// `msip` register of hart 0 in the CLINT of the SiFive and Kendryte designs
+const DEFAULT_MSIP: u64 = 0x0200_0000;
+
+/// Priority levels of the PLIC of the SiFive and Kendryte designs
+const DEFAULT_LEVELS: u8 = 7;
+
+#[derive(Debug)]
+pub struct BackendArgs {
+    /// Base address of the controller
+    pub base: u64,
+    /// The context of the machine mode of the hart running the application
+    pub context: u32,
+    /// The CLINT `msip` register of the hart, raising its machine software interrupt
+    pub msip: u64,
+    /// The priority levels of the PLIC, the priorities are `1..=levels`
+    pub levels: u8,
+}
+
+impl Default for BackendArgs {
+    fn default() -> Self {
+        BackendArgs {
+            base: DEFAULT_BASE,
+            context: 0,
+            msip: DEFAULT_MSIP,
+            levels: DEFAULT_LEVELS,
+        }
+    }
+}
+
+impl Parse for BackendArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        // [(base = #lit)?, (context = #lit)?, (msip = #lit)?, (levels = #lit)?]
+        let content;
+        bracketed!(content in input);
+
+        let mut args = BackendArgs::default();
+        let mut seen: Vec<String> = vec![];
+
+        while !content.is_empty() {
+            let ident: Ident = content.parse()?;
+            let _: Token![=] = content.parse()?;
+            let lit: LitInt = content.parse()?;
+
+            let name = ident.to_string();
+            if seen.contains(&name) {
+                return Err(Error::new(
+                    ident.span(),
+                    "argument appears more than once",
+                ));
+            }
+
+            match &*name {
+                "base" => args.base = lit.base10_parse::<u64>()?,
+                "context" => args.context = lit.base10_parse::<u32>()?,
+                "msip" => args.msip = lit.base10_parse::<u64>()?,
+                "levels" => {
+                    args.levels = match lit.base10_parse::<u8>() {
+                        Ok(levels) if levels >= 1 => levels,
+                        _ => {
+                            return Err(Error::new(
+                                lit.span(),
+                                "this literal must be in the range 1...255",
+                            ))
+                        }
+                    }
+                }
+                _ => {
+                    return Err(Error::new(
+                        ident.span(),
+                        "unexpected argument; the PLIC backend accepts `base`, `context`, `msip` and `levels`",
+                    ));
+                }
+            }
+            seen.push(name);
+
+            if !content.is_empty() {
+                let _: Token![,] = content.parse()?;
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// The highest priority a task can have, used to resolve `priority = max`
+pub fn max_priority(args: Option<&BackendArgs>, _span: Span) -> Result<u8> {
+    // Priority 0 never interrupts, the sources use the levels above it
+    Ok(args.map_or(DEFAULT_LEVELS, |args| args.levels))
+}
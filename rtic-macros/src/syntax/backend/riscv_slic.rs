@@ -1,3 +1,4 @@
+use proc_macro2::Span;
 use syn::{
     parse::{Parse, ParseStream},
     Ident, Result,
@@ -14,3 +15,8 @@ impl Parse for BackendArgs {
         Ok(BackendArgs { hart_id })
     }
 }
+
+/// The highest priority a task can have, used to resolve `priority = max`
+pub fn max_priority(_args: Option<&BackendArgs>, _span: Span) -> Result<u8> {
+    Ok(u8::MAX)
+}
@@ -0,0 +1,67 @@
+use proc_macro2::Span;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    Error, Ident, Path, Result, Token,
+};
+
+#[derive(Debug)]
+pub struct BackendArgs {
+    /// `fn() -> Option<u16>`, the number of a pending external source, masked until `complete`
+    pub claim: Path,
+    /// `fn(u16)`, unmasks the source once its handler has run
+    pub complete: Path,
+}
+
+impl Parse for BackendArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        // [claim = #path, complete = #path]
+        let content;
+        bracketed!(content in input);
+
+        let mut claim = None;
+        let mut complete = None;
+
+        while !content.is_empty() {
+            let ident: Ident = content.parse()?;
+            let _: Token![=] = content.parse()?;
+            let path: Path = content.parse()?;
+
+            let slot = match &*ident.to_string() {
+                "claim" => &mut claim,
+                "complete" => &mut complete,
+                _ => {
+                    return Err(Error::new(
+                        ident.span(),
+                        "unexpected argument; the soft SLIC backend accepts `claim` and `complete`",
+                    ));
+                }
+            };
+            if slot.replace(path).is_some() {
+                return Err(Error::new(
+                    ident.span(),
+                    "argument appears more than once",
+                ));
+            }
+
+            if !content.is_empty() {
+                let _: Token![,] = content.parse()?;
+            }
+        }
+
+        match (claim, complete) {
+            (Some(claim), Some(complete)) => Ok(BackendArgs { claim, complete }),
+            _ => Err(Error::new(
+                content.span(),
+                "the soft SLIC backend needs `backend = [claim = path::to::claim, complete = path::to::complete]`",
+            )),
+        }
+    }
+}
+
+/// The highest priority a task can have, used to resolve `priority = max`
+///
+/// The priorities are kept in software by the soft SLIC, any priority is accepted.
+pub fn max_priority(_args: Option<&BackendArgs>, _span: Span) -> Result<u8> {
+    Ok(u8::MAX)
+}
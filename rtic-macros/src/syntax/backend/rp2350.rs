@@ -0,0 +1,24 @@
+use proc_macro2::Span;
+use syn::{
+    parse::{Parse, ParseStream},
+    Error, Result,
+};
+
+#[derive(Debug)]
+pub struct BackendArgs();
+
+impl Parse for BackendArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Err(Error::new(
+            input.span(),
+            "rp2350 backend does not accept any arguments",
+        ))
+    }
+}
+
+/// The highest priority a task can have, used to resolve `priority = max`
+///
+/// `meipra` holds 4-bit priorities.
+pub fn max_priority(_args: Option<&BackendArgs>, _span: Span) -> Result<u8> {
+    Ok(15)
+}
@@ -1,3 +1,4 @@
+use proc_macro2::Span;
 use syn::{
     parse::{Parse, ParseStream},
     Result,
@@ -13,3 +14,7 @@ impl Parse for BackendArgs {
         todo!("define how to parse your backend-specific arguments")
     }
 }
+
+pub fn max_priority(args: Option<&BackendArgs>, span: Span) -> Result<u8> {
+    todo!("return the highest priority supported by your backend")
+}
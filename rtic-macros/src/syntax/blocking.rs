@@ -0,0 +1,38 @@
+//! The `blocking_priority` application argument
+//!
+//! Blocking delays and busy-wait flash erases hold off every task of lower priority for their
+//! whole duration. Tasks above `blocking_priority` are rejected if they call a known blocking
+//! helper, see [`scan::blocking`]; calls hidden in functions of other crates are left to the
+//! runtime check `rtic::blocking::assert_may_block`.
+
+use crate::syntax::{ast::App, scan};
+use syn::parse;
+
+/// Checks that no task above `blocking_priority` calls a blocking helper
+pub fn app(app: &App, blocking_priority: u8) -> parse::Result<()> {
+    for (name, body) in scan::bodies(app) {
+        let priority = if let Some(task) = app.hardware_tasks.get(name) {
+            task.args.priority
+        } else if let Some(task) = app.software_tasks.get(name) {
+            task.args.priority
+        } else {
+            // `init` and `idle`
+            0
+        };
+
+        if priority <= blocking_priority {
+            continue;
+        }
+
+        if let Some((construct, span)) = scan::blocking(body).into_iter().next() {
+            return Err(parse::Error::new(
+                span,
+                format!(
+                    "`{construct}` blocks, tasks above `blocking_priority = {blocking_priority}` must not block"
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
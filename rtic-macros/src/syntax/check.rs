@@ -1,6 +1,6 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use syn::parse;
+use syn::{parse, Ident};
 
 use crate::syntax::ast::App;
 
@@ -62,5 +62,179 @@ pub fn app(app: &App) -> parse::Result<()> {
         }
     }
 
+    // check the test harness mode, where `idle` runs the test cases
+    if app.args.test {
+        if let Some(idle) = &app.idle {
+            return Err(parse::Error::new(
+                idle.name.span(),
+                "`#[app(test)]` runs the test cases from `idle`, it can not be defined",
+            ));
+        }
+    }
+
+    for (name, task) in &app.software_tasks {
+        if task.is_test_case && !app.args.test {
+            return Err(parse::Error::new(
+                name.span(),
+                "`#[test_case]` tasks require the test harness mode, `#[app(test)]`",
+            ));
+        }
+
+        if task.is_test_case && !task.inputs.is_empty() {
+            return Err(parse::Error::new(
+                name.span(),
+                "test cases can not take arguments",
+            ));
+        }
+
+        if task.args.period.is_some() && (task.is_extern || !task.inputs.is_empty()) {
+            return Err(parse::Error::new(
+                name.span(),
+                "periodic tasks can not take arguments or be declared externally",
+            ));
+        }
+
+        if task.args.isolated.is_some() {
+            let reason = if !task.args.shared_resources.is_empty() {
+                Some("isolated tasks can only access their local resources, not shared ones")
+            } else if task.args.batch.is_some() || task.args.period.is_some() {
+                Some("isolated tasks can not be spawned in batches or be periodic")
+            } else if task.args.priority == 0 {
+                Some("isolated tasks are polled by a dispatcher, their priority must be above 0")
+            } else if task.args.local_resources.len() > 4 {
+                Some("isolated tasks have at most 4 local resources, the PMP has 16 entries")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(parse::Error::new(name.span(), reason));
+            }
+        }
+
+        if task.args.cross_core.is_some() {
+            let reason = if task.args.priority == 0 {
+                Some("tasks spawned from the other core are pended through the doorbell, their priority must be above 0")
+            } else if task.args.batch.is_some() {
+                Some("tasks spawned from the other core queue their payloads already, they can not be spawned in batches")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(parse::Error::new(name.span(), reason));
+            }
+        }
+
+        if app.args.test && task.args.priority == 0 {
+            return Err(parse::Error::new(
+                name.span(),
+                "in `#[app(test)]` `idle` runs the test cases, tasks must have a priority above 0",
+            ));
+        }
+    }
+
+    depends_on(app)?;
+
+    Ok(())
+}
+
+/// Checks the `#[depends_on(..)]` of the resources, which `init::Resources` constructs in order
+fn depends_on(app: &App) -> parse::Result<()> {
+    let resources = app
+        .shared_resources
+        .iter()
+        .map(|(name, res)| (name, &res.depends_on, &res.cfgs))
+        .chain(
+            app.local_resources
+                .iter()
+                .map(|(name, res)| (name, &res.depends_on, &res.cfgs)),
+        )
+        .collect::<Vec<_>>();
+    if resources.iter().all(|(_, deps, _)| deps.is_empty()) {
+        return Ok(());
+    }
+
+    let mut graph = HashMap::new();
+    for (name, deps, cfgs) in &resources {
+        if graph.insert(*name, *deps).is_some() {
+            return Err(parse::Error::new(
+                name.span(),
+                "with `#[depends_on]` the shared and local resources need distinct names",
+            ));
+        }
+
+        if !cfgs.is_empty() {
+            return Err(parse::Error::new(
+                name.span(),
+                "`#[cfg]` resources can not be constructed through `init::Resources`, which `#[depends_on]` generates",
+            ));
+        }
+
+        if *name == "build" {
+            return Err(parse::Error::new(
+                name.span(),
+                "`build` is the method finishing `init::Resources`, rename this resource",
+            ));
+        }
+    }
+
+    for (name, deps, _) in &resources {
+        let mut seen = HashSet::new();
+        for dep in deps.iter() {
+            if !graph.contains_key(dep) {
+                return Err(parse::Error::new(
+                    dep.span(),
+                    "this resource has NOT been declared",
+                ));
+            }
+
+            if dep == *name || !seen.insert(dep) {
+                return Err(parse::Error::new(
+                    dep.span(),
+                    "a resource depends on itself or on the same resource twice",
+                ));
+            }
+        }
+    }
+
+    // A cycle leaves its resources without a constructor to call first
+    fn visit<'a>(
+        name: &'a Ident,
+        graph: &HashMap<&'a Ident, &'a Vec<Ident>>,
+        path: &mut Vec<&'a Ident>,
+        done: &mut HashSet<&'a Ident>,
+    ) -> parse::Result<()> {
+        if done.contains(name) {
+            return Ok(());
+        }
+
+        if let Some(start) = path.iter().position(|visited| *visited == name) {
+            let cycle = path[start..]
+                .iter()
+                .chain([&name])
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(parse::Error::new(
+                name.span(),
+                format!("the resources depend on each other in a cycle: {cycle}"),
+            ));
+        }
+
+        path.push(name);
+        for dep in graph[name] {
+            let (dep, _) = graph.get_key_value(dep).expect("UNREACHABLE");
+            visit(dep, graph, path, done)?;
+        }
+        path.pop();
+        done.insert(name);
+
+        Ok(())
+    }
+
+    let mut done = HashSet::new();
+    for (name, _, _) in &resources {
+        visit(name, &graph, &mut vec![], &mut done)?;
+    }
+
     Ok(())
 }
@@ -0,0 +1,125 @@
+//! The `max_latency` argument of hardware tasks
+//!
+//! Under the Stack Resource Policy a task is blocked at most once, by a single critical section
+//! of a lower priority task on a resource whose ceiling is at or above the priority of the task.
+//! With the longest critical section of each such resource annotated with `#[wcet = ..]`, the
+//! worst case blocking of a task is known at compile time and checked against its budget.
+//!
+//! Preemption by tasks of higher priority and the execution of tasks of the same priority are not
+//! part of the blocking, the budget only covers the time the task can be held off by locks.
+
+use crate::syntax::{
+    analyze::{Analysis, Ownership},
+    ast::{App, SharedResources},
+};
+use syn::{parse, Ident};
+
+/// The critical section blocking a task the longest
+pub struct Blocking<'a> {
+    /// The task holding the lock
+    pub task: &'a Ident,
+    /// Its priority
+    pub priority: u8,
+    /// The resource it locks
+    pub resource: &'a Ident,
+    /// The ceiling of the resource
+    pub ceiling: u8,
+    /// The longest critical section on the resource, in microseconds
+    pub wcet: u64,
+}
+
+/// The tasks with their priority and shared resources, `idle` included
+fn tasks(app: &App) -> Vec<(&Ident, u8, &SharedResources)> {
+    let idle = app
+        .idle
+        .iter()
+        .map(|idle| (&idle.name, 0, &idle.args.shared_resources));
+    let hardware = app
+        .hardware_tasks
+        .iter()
+        .map(|(name, task)| (name, task.args.priority, &task.args.shared_resources));
+    let software = app
+        .software_tasks
+        .iter()
+        .map(|(name, task)| (name, task.args.priority, &task.args.shared_resources));
+
+    idle.chain(hardware).chain(software).collect()
+}
+
+/// The worst case blocking of a task at `priority`, `None` if no lock can block it
+///
+/// Fails on the first resource which can block the task but has no `#[wcet = ..]`.
+pub fn blocking<'a>(
+    app: &'a App,
+    analysis: &Analysis,
+    priority: u8,
+) -> Result<Option<Blocking<'a>>, (&'a Ident, &'a Ident)> {
+    let mut worst: Option<Blocking<'a>> = None;
+
+    for (task, task_priority, shared) in tasks(app) {
+        if task_priority >= priority {
+            continue;
+        }
+
+        for resource in shared.keys() {
+            let Some(Ownership::Contended { ceiling }) = analysis.ownerships.get(resource) else {
+                continue;
+            };
+            let properties = &app.shared_resources[resource].properties;
+            if *ceiling < priority || properties.lock_free || properties.atomic.is_some() {
+                continue;
+            }
+
+            let Some(wcet) = properties.wcet else {
+                return Err((task, resource));
+            };
+
+            if worst.as_ref().is_none_or(|worst| wcet > worst.wcet) {
+                worst = Some(Blocking {
+                    task,
+                    priority: task_priority,
+                    resource,
+                    ceiling: *ceiling,
+                    wcet,
+                });
+            }
+        }
+    }
+
+    Ok(worst)
+}
+
+/// Checks the hardware tasks with a `max_latency` against their worst case blocking
+pub fn app(app: &App, analysis: &Analysis) -> parse::Result<()> {
+    for (name, task) in &app.hardware_tasks {
+        let Some(budget) = task.args.max_latency else {
+            continue;
+        };
+        let priority = task.args.priority;
+
+        match blocking(app, analysis, priority) {
+            Err((holder, resource)) => {
+                return Err(parse::Error::new(
+                    resource.span(),
+                    format!(
+                        "`{holder}` locks `{resource}`, which can block `{name}` with its `max_latency`, \
+                         annotate the longest critical section on `{resource}` with `#[wcet = ..]`"
+                    ),
+                ));
+            }
+            Ok(Some(worst)) if worst.wcet > budget => {
+                return Err(parse::Error::new(
+                    name.span(),
+                    format!(
+                        "`{name}` (priority {priority}) can be blocked for {} µs, above its `max_latency` of {budget} µs: \
+                         `{}` (priority {}) locks `{}` (ceiling {}) for up to {} µs",
+                        worst.wcet, worst.task, worst.priority, worst.resource, worst.ceiling, worst.wcet
+                    ),
+                ));
+            }
+            Ok(_) => {}
+        }
+    }
+
+    Ok(())
+}
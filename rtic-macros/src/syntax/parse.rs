@@ -1,9 +1,25 @@
 mod app;
+mod deadline;
+mod dispatchers;
+pub(crate) mod config;
+#[cfg(feature = "rp2040")]
+mod doorbell;
+pub(crate) mod env;
 mod hardware_task;
 mod idle;
 mod init;
+#[cfg(feature = "nrf5340")]
+mod ipc;
+#[cfg(feature = "esp32c6-lp-core")]
+mod lp_core;
+mod modes;
+mod period;
+mod periodic;
 mod resource;
+mod schedule;
+mod shutdown;
 mod software_task;
+mod storm;
 mod util;
 
 use proc_macro2::TokenStream as TokenStream2;
@@ -11,20 +27,43 @@ use syn::{
     braced,
     parse::{self, Parse, ParseStream, Parser},
     token::Brace,
-    Ident, Item, LitInt, Token,
+    Ident, Item, LitInt, Path, Token,
 };
 
 use crate::syntax::{
-    ast::{App, AppArgs, HardwareTaskArgs, IdleArgs, InitArgs, SoftwareTaskArgs, TaskLocal},
+    ast::{
+        App, AppArgs, HardwareTaskArgs, IdleArgs, InitArgs, Period, SoftwareTaskArgs, TaskLocal,
+        Trigger,
+    },
+    backend::{self, BackendArgs},
     Either,
 };
 
 // Parse the app, both app arguments and body (input)
 pub fn app(args: TokenStream2, input: TokenStream2) -> parse::Result<App> {
-    let args = AppArgs::parse(args)?;
-    let input: Input = syn::parse2(input)?;
+    // Left over by an application that failed to parse
+    config::take();
 
-    App::parse(args, input)
+    let args = AppArgs::parse(args)?;
+    let mut input: Input = syn::parse2(input)?;
+    periodic::expand(&mut input.items)?;
+    storm::expand(&args, &mut input.items)?;
+    schedule::expand(&args, &mut input.items);
+    shutdown::expand(&mut input.items)?;
+    #[cfg(feature = "nrf5340")]
+    ipc::expand(&args, &mut input.items)?;
+    #[cfg(feature = "rp2040")]
+    doorbell::expand(&args, &mut input.items)?;
+    #[cfg(feature = "esp32c6-lp-core")]
+    let lp_core = lp_core::expand(&mut input.items)?;
+
+    #[allow(unused_mut)]
+    let mut app = App::parse(args, input)?;
+    #[cfg(feature = "esp32c6-lp-core")]
+    {
+        app.lp_core = lp_core;
+    }
+    Ok(app)
 }
 
 pub(crate) struct Input {
@@ -179,7 +218,10 @@ fn idle_args(tokens: TokenStream2) -> parse::Result<IdleArgs> {
     .parse2(tokens)
 }
 
-fn task_args(tokens: TokenStream2) -> parse::Result<Either<HardwareTaskArgs, SoftwareTaskArgs>> {
+fn task_args(
+    tokens: TokenStream2,
+    backend: Option<&BackendArgs>,
+) -> parse::Result<Either<HardwareTaskArgs, SoftwareTaskArgs>> {
     (|input: ParseStream<'_>| -> parse::Result<Either<HardwareTaskArgs, SoftwareTaskArgs>> {
         if input.is_empty() {
             return Ok(Either::Right(SoftwareTaskArgs::default()));
@@ -190,6 +232,16 @@ fn task_args(tokens: TokenStream2) -> parse::Result<Either<HardwareTaskArgs, Sof
         let mut shared_resources = None;
         let mut local_resources = None;
         let mut prio_span = None;
+        let mut batch = None;
+        let mut shed = None;
+        let mut deadline = None;
+        let mut modes = None;
+        let mut max_latency = None;
+        let mut implements = None;
+        let mut period = None;
+        let mut isolated = None;
+        let mut cross_core = None;
+        let mut trigger = None;
 
         loop {
             if input.is_empty() {
@@ -226,48 +278,258 @@ fn task_args(tokens: TokenStream2) -> parse::Result<Either<HardwareTaskArgs, Sof
                         ));
                     }
 
-                    // #lit
-                    let lit: LitInt = input.parse()?;
+                    if input.peek(Ident) && !env::peek(input) {
+                        // max | max - #lit
+                        let max: Ident = input.parse()?;
+                        if max != "max" {
+                            return Err(parse::Error::new(
+                                max.span(),
+                                "expected an integer literal or `max`",
+                            ));
+                        }
+
+                        let max_priority = backend::max_priority(backend, max.span())?;
+                        let offset = if input.peek(Token![-]) {
+                            let _: Token![-] = input.parse()?;
+                            let lit: LitInt = input.parse()?;
+                            lit.base10_parse::<u8>().ok().filter(|offset| *offset <= max_priority).ok_or_else(|| {
+                                parse::Error::new(
+                                    lit.span(),
+                                    format!("this literal must be in the range 0...{max_priority}"),
+                                )
+                            })?
+                        } else {
+                            0
+                        };
+
+                        prio_span = Some(max.span());
+                        priority = Some(max_priority - offset);
+                    } else {
+                        // #lit | env!(..)
+                        let lit = env::parse_int(input)?;
+
+                        if !lit.suffix().is_empty() {
+                            return Err(parse::Error::new(
+                                lit.span(),
+                                "this literal must be unsuffixed",
+                            ));
+                        }
+
+                        let value = lit.base10_parse::<u8>().ok();
+                        if value.is_none() {
+                            return Err(parse::Error::new(
+                                lit.span(),
+                                "this literal must be in the range 0...255",
+                            ));
+                        }
+
+                        prio_span = Some(lit.span());
+                        priority = Some(value.unwrap());
+                    }
+                }
 
-                    if !lit.suffix().is_empty() {
+                "shared" => {
+                    if shared_resources.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    shared_resources = Some(util::parse_shared_resources(input)?);
+                }
+
+                "local" => {
+                    if local_resources.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    local_resources = Some(util::parse_local_resources(input)?);
+                }
+
+                "batch" => {
+                    if batch.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    let lit = env::parse_int(input)?;
+                    let value = lit.base10_parse::<usize>().ok().filter(|n| *n > 0);
+                    if value.is_none() || !lit.suffix().is_empty() {
                         return Err(parse::Error::new(
                             lit.span(),
-                            "this literal must be unsuffixed",
+                            "this literal must be an unsuffixed non-zero integer",
                         ));
                     }
 
-                    let value = lit.base10_parse::<u8>().ok();
-                    if value.is_none() {
+                    batch = Some((ident.span(), value.unwrap()));
+                }
+
+                "shed" => {
+                    if shed.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    let lit = env::parse_int(input)?;
+                    let value = lit.base10_parse::<usize>().ok().filter(|n| *n > 0);
+                    if value.is_none() || !lit.suffix().is_empty() {
                         return Err(parse::Error::new(
                             lit.span(),
-                            "this literal must be in the range 0...255",
+                            "this literal must be an unsuffixed non-zero integer",
                         ));
                     }
 
-                    prio_span = Some(lit.span());
-                    priority = Some(value.unwrap());
+                    shed = Some((ident.span(), value.unwrap()));
                 }
 
-                "shared" => {
-                    if shared_resources.is_some() {
+                "deadline" => {
+                    if deadline.is_some() {
                         return Err(parse::Error::new(
                             ident.span(),
                             "argument appears more than once",
                         ));
                     }
 
-                    shared_resources = Some(util::parse_shared_resources(input)?);
+                    deadline = Some((ident.span(), deadline::parse(input)?));
                 }
 
-                "local" => {
-                    if local_resources.is_some() {
+                "modes" => {
+                    if modes.is_some() {
                         return Err(parse::Error::new(
                             ident.span(),
                             "argument appears more than once",
                         ));
                     }
 
-                    local_resources = Some(util::parse_local_resources(input)?);
+                    modes = Some((ident.span(), modes::parse(input)?));
+                }
+
+                "max_latency" => {
+                    if max_latency.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    max_latency = Some((ident.span(), deadline::parse(input)?));
+                }
+
+                "implements" => {
+                    if implements.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    implements = Some((ident.span(), input.parse::<Path>()?));
+                }
+
+                "period" => {
+                    if period.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    period = Some((ident.span(), input.parse::<Period>()?));
+                }
+
+                "trigger" => {
+                    if trigger.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    if !cfg!(feature = "riscv-esp32c6") {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "the interrupt `trigger` can only be chosen with the ESP32-C6 backend",
+                        ));
+                    }
+
+                    let kind: Ident = input.parse()?;
+                    let value = match &*kind.to_string() {
+                        "edge" => Trigger::Edge,
+                        "level" => Trigger::Level,
+                        _ => {
+                            return Err(parse::Error::new(
+                                kind.span(),
+                                "expected `edge` or `level`",
+                            ));
+                        }
+                    };
+
+                    trigger = Some((ident.span(), value));
+                }
+
+                "isolated" => {
+                    if isolated.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    if !cfg!(feature = "riscv-pmp") {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "isolated tasks need the `riscv-pmp` feature and a RISC-V backend",
+                        ));
+                    }
+
+                    let lit = env::parse_int(input)?;
+                    let value = lit
+                        .base10_parse::<usize>()
+                        .ok()
+                        .filter(|n| *n > 0 && n % 16 == 0);
+                    if value.is_none() || !lit.suffix().is_empty() {
+                        return Err(parse::Error::new(
+                            lit.span(),
+                            "this literal must be an unsuffixed non-zero multiple of 16, the size of the stack in bytes",
+                        ));
+                    }
+
+                    isolated = Some((ident.span(), value.unwrap()));
+                }
+
+                "cross_core" => {
+                    if cross_core.is_some() {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "argument appears more than once",
+                        ));
+                    }
+
+                    if !cfg!(feature = "rp2040") {
+                        return Err(parse::Error::new(
+                            ident.span(),
+                            "tasks are spawned from the other core through the doorbell of the RP2040, `cross_core` needs the `thumbv6-rp2040-backend`",
+                        ));
+                    }
+
+                    let lit = env::parse_int(input)?;
+                    let value = lit.base10_parse::<usize>().ok().filter(|n| *n > 0);
+                    if value.is_none() || !lit.suffix().is_empty() {
+                        return Err(parse::Error::new(
+                            lit.span(),
+                            "this literal must be an unsuffixed non-zero integer",
+                        ));
+                    }
+
+                    cross_core = Some((ident.span(), value.unwrap()));
                 }
 
                 _ => {
@@ -282,6 +544,28 @@ fn task_args(tokens: TokenStream2) -> parse::Result<Either<HardwareTaskArgs, Sof
             // Handle comma: ,
             let _: Token![,] = input.parse()?;
         }
+
+        if let (Some((span, _)), Some(_)) = (deadline, priority) {
+            return Err(parse::Error::new(
+                span,
+                "the priority of a task with a `deadline` is assigned from it, remove its `priority`",
+            ));
+        }
+        let deadline = deadline.map(|(_, deadline)| deadline);
+
+        if let (Some((span, _)), Some(_)) = (&modes, &binds) {
+            if !cfg!(any(
+                feature = "cortex-m-source-masking",
+                feature = "cortex-m-basepri"
+            )) {
+                return Err(parse::Error::new(
+                    *span,
+                    "hardware tasks can only have `modes` with the Cortex-M backends",
+                ));
+            }
+        }
+        let modes = modes.map(|(_, modes)| modes).unwrap_or_default();
+
         let shared_resources = shared_resources.unwrap_or_default();
         let local_resources = local_resources.unwrap_or_default();
 
@@ -296,13 +580,64 @@ fn task_args(tokens: TokenStream2) -> parse::Result<Either<HardwareTaskArgs, Sof
                 ));
             }
 
+            if let Some((span, _)) = batch {
+                return Err(parse::Error::new(
+                    span,
+                    "only software tasks can be spawned in batches",
+                ));
+            }
+
+            if let Some((span, _)) = shed {
+                return Err(parse::Error::new(span, "only software tasks can be shed"));
+            }
+
+            if let Some((span, _)) = implements {
+                return Err(parse::Error::new(
+                    span,
+                    "only software tasks can implement a task interface",
+                ));
+            }
+
+            if let Some((span, _)) = period {
+                return Err(parse::Error::new(span, "only software tasks can be periodic"));
+            }
+
+            if let Some((span, _)) = isolated {
+                return Err(parse::Error::new(span, "only software tasks can be isolated"));
+            }
+
+            if let Some((span, _)) = cross_core {
+                return Err(parse::Error::new(
+                    span,
+                    "only software tasks can be spawned from the other core",
+                ));
+            }
+
             Either::Left(HardwareTaskArgs {
                 binds,
                 priority,
                 shared_resources,
                 local_resources,
+                deadline,
+                modes,
+                max_latency: max_latency.map(|(_, max_latency)| max_latency),
+                trigger: trigger.map(|(_, trigger)| trigger),
             })
         } else {
+            if let Some((span, _)) = max_latency {
+                return Err(parse::Error::new(
+                    span,
+                    "only hardware tasks can have a `max_latency`",
+                ));
+            }
+
+            if let Some((span, _)) = trigger {
+                return Err(parse::Error::new(
+                    span,
+                    "only hardware tasks have an interrupt `trigger`",
+                ));
+            }
+
             // Software tasks start at idle priority
             let priority = priority.unwrap_or(0);
 
@@ -310,6 +645,14 @@ fn task_args(tokens: TokenStream2) -> parse::Result<Either<HardwareTaskArgs, Sof
                 priority,
                 shared_resources,
                 local_resources,
+                batch: batch.map(|(_, capacity)| capacity),
+                shed: shed.map(|(_, threshold)| threshold),
+                deadline,
+                modes,
+                implements: implements.map(|(_, implements)| implements),
+                period: period.map(|(_, period)| period),
+                isolated: isolated.map(|(_, stack)| stack),
+                cross_core: cross_core.map(|(_, capacity)| capacity),
             })
         })
     })
@@ -5,13 +5,13 @@ use proc_macro2::TokenStream as TokenStream2;
 use syn::{
     parse::{self, ParseStream, Parser},
     spanned::Spanned,
-    Expr, ExprArray, Fields, ForeignItem, Ident, Item, LitBool, Path, Token, Visibility,
+    Fields, ForeignItem, Ident, Item, LitBool, LitInt, Path, Token, Visibility,
 };
 
 use crate::syntax::{
     ast::{
-        App, AppArgs, Dispatcher, Dispatchers, HardwareTask, Idle, IdleArgs, Init, InitArgs,
-        LocalResource, SharedResource, SoftwareTask,
+        App, AppArgs, Dispatchers, HardwareTask, Idle, IdleArgs, Init, InitArgs,
+        LocalResource, Schedule, SharedResource, SoftwareTask,
     },
     backend::BackendArgs,
     parse::{self as syntax_parse, util},
@@ -29,6 +29,14 @@ impl AppArgs {
             let mut peripherals = true;
             let mut dispatchers = Dispatchers::new();
             let mut backend = None;
+            let mut strict = false;
+            let mut blocking_priority = None;
+            let mut max_nesting = None;
+            let mut schedule = None;
+            let mut modes = vec![];
+            let mut test = false;
+            let mut sleep_on_exit = None;
+            let mut relocate_vectors = false;
 
             loop {
                 if input.is_empty() {
@@ -37,7 +45,6 @@ impl AppArgs {
 
                 // #ident = ..
                 let ident: Ident = input.parse()?;
-                let _eq_token: Token![=] = input.parse()?;
 
                 if custom.contains(&ident) {
                     return Err(parse::Error::new(
@@ -50,7 +57,27 @@ impl AppArgs {
 
                 let ks = ident.to_string();
 
+                // `test` is a flag, `test = true` is accepted as well
+                if ks == "test" && !input.peek(Token![=]) {
+                    test = true;
+                } else {
+                    let _eq_token: Token![=] = input.parse()?;
+                }
+
                 match &*ks {
+                    "test" if test => {}
+
+                    "test" => {
+                        if let Some(p) = parse_bool(input)? {
+                            test = p.value;
+                        } else {
+                            return Err(parse::Error::new(
+                                ident.span(),
+                                "unexpected argument value; this should be a boolean",
+                            ));
+                        }
+                    }
+
                     "device" => {
                         if let Ok(p) = input.parse::<Path>() {
                             device = Some(p);
@@ -63,7 +90,7 @@ impl AppArgs {
                     }
 
                     "core" => {
-                        if let Ok(p) = input.parse::<LitBool>() {
+                        if let Some(p) = parse_bool(input)? {
                             core = p.value;
                         } else {
                             return Err(parse::Error::new(
@@ -74,7 +101,7 @@ impl AppArgs {
                     }
 
                     "peripherals" => {
-                        if let Ok(p) = input.parse::<LitBool>() {
+                        if let Some(p) = parse_bool(input)? {
                             peripherals = p.value;
                         } else {
                             return Err(parse::Error::new(
@@ -85,40 +112,8 @@ impl AppArgs {
                     }
 
                     "dispatchers" => {
-                        if let Ok(p) = input.parse::<ExprArray>() {
-                            for e in p.elems {
-                                match e {
-                                    Expr::Path(ep) => {
-                                        let path = ep.path;
-                                        let ident = if path.leading_colon.is_some()
-                                            || path.segments.len() != 1
-                                        {
-                                            return Err(parse::Error::new(
-                                                path.span(),
-                                                "interrupt must be an identifier, not a path",
-                                            ));
-                                        } else {
-                                            path.segments[0].ident.clone()
-                                        };
-                                        let span = ident.span();
-                                        if dispatchers.contains_key(&ident) {
-                                            return Err(parse::Error::new(
-                                                span,
-                                                "this extern interrupt is listed more than once",
-                                            ));
-                                        } else {
-                                            dispatchers
-                                                .insert(ident, Dispatcher { attrs: ep.attrs });
-                                        }
-                                    }
-                                    _ => {
-                                        return Err(parse::Error::new(
-                                            e.span(),
-                                            "interrupt must be an identifier",
-                                        ));
-                                    }
-                                }
-                            }
+                        if input.peek(syn::token::Bracket) {
+                            dispatchers = syntax_parse::dispatchers::parse(input)?;
                         } else {
                             return Err(parse::Error::new(
                                 ident.span(),
@@ -128,6 +123,97 @@ impl AppArgs {
                         }
                     }
 
+                    "strict" => {
+                        if let Some(p) = parse_bool(input)? {
+                            strict = p.value;
+                        } else {
+                            return Err(parse::Error::new(
+                                ident.span(),
+                                "unexpected argument value; this should be a boolean",
+                            ));
+                        }
+                    }
+
+                    "sleep_on_exit" => {
+                        if let Some(p) = parse_bool(input)? {
+                            if !cfg!(any(
+                                feature = "cortex-m-source-masking",
+                                feature = "cortex-m-basepri"
+                            )) {
+                                return Err(parse::Error::new(
+                                    ident.span(),
+                                    "`sleep_on_exit` is only supported by the Cortex-M backends",
+                                ));
+                            }
+                            sleep_on_exit = p.value.then_some(ident.span());
+                        } else {
+                            return Err(parse::Error::new(
+                                ident.span(),
+                                "unexpected argument value; this should be a boolean",
+                            ));
+                        }
+                    }
+
+                    "relocate_vectors" => {
+                        if let Some(p) = parse_bool(input)? {
+                            if !cfg!(any(
+                                feature = "cortex-m-source-masking",
+                                feature = "cortex-m-basepri"
+                            )) {
+                                return Err(parse::Error::new(
+                                    ident.span(),
+                                    "`relocate_vectors` is only supported by the Cortex-M backends, the other runtimes point the core at the trap handler of the application themselves",
+                                ));
+                            }
+                            relocate_vectors = p.value;
+                        } else {
+                            return Err(parse::Error::new(
+                                ident.span(),
+                                "unexpected argument value; this should be a boolean",
+                            ));
+                        }
+                    }
+
+                    "blocking_priority" => {
+                        if let Ok(p) = input.parse::<LitInt>() {
+                            blocking_priority = Some(p.base10_parse::<u8>()?);
+                        } else {
+                            return Err(parse::Error::new(
+                                ident.span(),
+                                "unexpected argument value; this should be an integer",
+                            ));
+                        }
+                    }
+
+                    "max_nesting" => {
+                        if let Ok(p) = input.parse::<LitInt>() {
+                            match p.base10_parse::<u8>() {
+                                Ok(depth) if depth > 0 => max_nesting = Some(depth),
+                                _ => {
+                                    return Err(parse::Error::new(
+                                        p.span(),
+                                        "this literal must be in the range 1...255",
+                                    ))
+                                }
+                            }
+                        } else {
+                            return Err(parse::Error::new(
+                                ident.span(),
+                                "unexpected argument value; this should be an integer",
+                            ));
+                        }
+                    }
+
+                    "schedule" => {
+                        let content;
+                        syn::parenthesized!(content in input);
+                        schedule = Some(content.parse::<Schedule>()?);
+                    }
+
+                    "modes" => {
+                        modes = syntax_parse::modes::parse(input)?;
+                    }
+
                     "backend" => {
                         if let Ok(p) = input.parse::<BackendArgs>() {
                             backend = Some(p);
@@ -152,6 +238,13 @@ impl AppArgs {
                 let _: Token![,] = input.parse()?;
             }
 
+            if let (Some(span), true) = (sleep_on_exit, test) {
+                return Err(parse::Error::new(
+                    span,
+                    "the test harness runs the test cases from `idle`, it can not sleep on exit",
+                ));
+            }
+
             let device = if let Some(device) = device {
                 device
             } else {
@@ -164,6 +257,14 @@ impl AppArgs {
                 peripherals,
                 dispatchers,
                 backend,
+                strict,
+                blocking_priority,
+                max_nesting,
+                schedule,
+                modes,
+                test,
+                sleep_on_exit: sleep_on_exit.is_some(),
+                relocate_vectors,
             })
         })
         .parse2(tokens)
@@ -172,6 +273,9 @@ impl AppArgs {
 
 impl App {
     pub(crate) fn parse(args: AppArgs, input: Input) -> parse::Result<Self> {
+        // Left over by an application that failed to parse
+        syntax_parse::env::take();
+
         let mut init = None;
         let mut idle = None;
 
@@ -277,6 +381,7 @@ impl App {
 
                         match syntax_parse::task_args(
                             item.attrs.remove(pos).parse_args().unwrap_or_default(),
+                            args.backend.as_ref(),
                         )? {
                             Either::Left(args) => {
                                 check_binding(&args.binds)?;
@@ -460,6 +565,7 @@ impl App {
 
                                 match syntax_parse::task_args(
                                     item.attrs.remove(pos).parse_args().unwrap_or_default(),
+                                    args.backend.as_ref(),
                                 )? {
                                     Either::Left(args) => {
                                         check_binding(&args.binds)?;
@@ -530,6 +636,9 @@ impl App {
             ));
         }
 
+        syntax_parse::deadline::assign(&mut hardware_tasks, &mut software_tasks);
+        syntax_parse::modes::check(&args, &hardware_tasks, &software_tasks)?;
+
         Ok(App {
             args,
             name: input.ident,
@@ -543,6 +652,18 @@ impl App {
             user_code,
             hardware_tasks,
             software_tasks,
+            env_vars: syntax_parse::env::take(),
+            config_file: syntax_parse::config::take(),
+            lp_core: vec![],
         })
     }
 }
+
+/// A boolean literal or `config!(..)`, `None` for anything else
+fn parse_bool(input: ParseStream<'_>) -> parse::Result<Option<LitBool>> {
+    if input.peek(LitBool) || syntax_parse::config::peek(input) {
+        syntax_parse::config::parse_bool(input).map(Some)
+    } else {
+        Ok(None)
+    }
+}
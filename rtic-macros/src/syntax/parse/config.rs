@@ -0,0 +1,143 @@
+//! `config!("key")` in place of the integer or boolean literal of an argument
+//!
+//! Keeps the configuration of a product in one reviewed file: the `rtic.toml` next to the
+//! `Cargo.toml` of the application supplies the priorities, capacities and toggles, and the
+//! application refers to them by their dotted key. `config!("key", default)` falls back to
+//! `default` when the file or the key is missing.
+//!
+//! ```toml
+//! [control]
+//! priority = 3
+//! batch = 4
+//!
+//! [app]
+//! sleep_on_exit = true
+//! ```
+
+use std::{cell::RefCell, path::PathBuf};
+
+use syn::{
+    parse::{self, Parse, ParseStream},
+    Ident, LitBool, LitInt, LitStr, Token,
+};
+use toml::{Table, Value};
+
+/// The name of the file, relative to the manifest directory of the application
+pub const FILE: &str = "rtic.toml";
+
+thread_local! {
+    /// The file read while parsing the application, see [`take`]
+    static READ: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Takes the path of the file read since the last call, if any
+///
+/// The generated code includes the file with `include_bytes!`, so the compiler records it as a
+/// dependency and Cargo rebuilds the application when it changes.
+pub fn take() -> Option<PathBuf> {
+    READ.with(|read| read.borrow_mut().take())
+}
+
+/// Whether the next argument value is a `config!(..)` invocation
+pub fn peek(input: ParseStream<'_>) -> bool {
+    input.fork().parse::<Ident>().is_ok_and(|ident| ident == "config") && input.peek2(Token![!])
+}
+
+/// Parses `config!("key"[, default])` after its `config` identifier, returning the value as a
+/// literal spanned at the invocation so it is checked like a written one
+pub fn parse_int_after(ident: Ident, input: ParseStream<'_>) -> parse::Result<LitInt> {
+    let span = ident.span();
+    let (key, default) = parse_invocation::<LitInt>(input)?;
+    match lookup(&key)? {
+        Some(Value::Integer(value)) if value >= 0 => Ok(LitInt::new(&value.to_string(), span)),
+        Some(value) => Err(mismatch(&key, "a non-negative integer", &value)),
+        None => default.ok_or_else(|| missing(&key)),
+    }
+}
+
+/// Parses a boolean literal or `config!("key"[, default])`
+pub fn parse_bool(input: ParseStream<'_>) -> parse::Result<LitBool> {
+    if !peek(input) {
+        return input.parse();
+    }
+
+    let ident: Ident = input.parse()?;
+    let span = ident.span();
+    let (key, default) = parse_invocation::<LitBool>(input)?;
+    match lookup(&key)? {
+        Some(Value::Boolean(value)) => Ok(LitBool::new(value, span)),
+        Some(value) => Err(mismatch(&key, "a boolean", &value)),
+        None => default.ok_or_else(|| missing(&key)),
+    }
+}
+
+/// `!("key"[, default])`
+fn parse_invocation<T: Parse>(input: ParseStream<'_>) -> parse::Result<(LitStr, Option<T>)> {
+    let _: Token![!] = input.parse()?;
+
+    let content;
+    syn::parenthesized!(content in input);
+    let key: LitStr = content.parse()?;
+    let default = if content.is_empty() {
+        None
+    } else {
+        let _: Token![,] = content.parse()?;
+        Some(content.parse()?)
+    };
+    if !content.is_empty() {
+        return Err(content.error("expected `config!(\"key\")` or `config!(\"key\", default)`"));
+    }
+
+    Ok((key, default))
+}
+
+/// The value at the dotted `key`, `None` when the file or the key is missing
+fn lookup(key: &LitStr) -> parse::Result<Option<Value>> {
+    let Some(dir) = std::env::var_os("CARGO_MANIFEST_DIR") else {
+        return Ok(None);
+    };
+    let path = PathBuf::from(dir).join(FILE);
+    let Ok(text) = std::fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    READ.with(|read| *read.borrow_mut() = Some(path));
+
+    let table: Table = text.parse().map_err(|e: toml::de::Error| {
+        parse::Error::new(
+            key.span(),
+            format!("`{FILE}` is not valid TOML: {}", e.message()),
+        )
+    })?;
+
+    let value = key.value();
+    let mut segments = value.split('.');
+    let mut current = segments.next().and_then(|first| table.get(first));
+    for segment in segments {
+        current = current
+            .and_then(Value::as_table)
+            .and_then(|table| table.get(segment));
+    }
+
+    Ok(current.cloned())
+}
+
+fn mismatch(key: &LitStr, expected: &str, found: &Value) -> parse::Error {
+    parse::Error::new(
+        key.span(),
+        format!(
+            "`{}` in `{FILE}` must be {expected}, found a value of type `{}`",
+            key.value(),
+            found.type_str(),
+        ),
+    )
+}
+
+fn missing(key: &LitStr) -> parse::Error {
+    parse::Error::new(
+        key.span(),
+        format!(
+            "`{}` is not set in the `{FILE}` of the application; add it or give a default",
+            key.value()
+        ),
+    )
+}
@@ -0,0 +1,81 @@
+//! `deadline = ..` argument of tasks
+//!
+//! Tasks declaring a relative deadline instead of a priority get their priority assigned
+//! deadline-monotonically: the distinct deadlines are ranked from the longest, which gets
+//! priority 1, to the shortest, which gets the highest. Tasks with the same deadline share a
+//! priority. Tasks without a deadline keep their own priority.
+
+use syn::{
+    parse::{self, ParseStream},
+    spanned::Spanned,
+    Expr, ExprLit, Lit,
+};
+
+use crate::syntax::{
+    ast::{HardwareTask, SoftwareTask},
+    Map,
+};
+
+/// Parses `N.micros()`, `N.millis()` or `N.secs()` into microseconds
+pub fn parse(input: ParseStream<'_>) -> parse::Result<u64> {
+    let expr: Expr = input.parse()?;
+    let error = || {
+        parse::Error::new(
+            expr.span(),
+            "expected a non-zero duration literal, e.g. `5.millis()`",
+        )
+    };
+
+    let Expr::MethodCall(call) = &expr else {
+        return Err(error());
+    };
+    let Expr::Lit(ExprLit {
+        lit: Lit::Int(lit), ..
+    }) = &*call.receiver
+    else {
+        return Err(error());
+    };
+
+    let scale = match &*call.method.to_string() {
+        "micros" => 1,
+        "millis" => 1_000,
+        "secs" => 1_000_000,
+        _ => return Err(error()),
+    };
+
+    lit.base10_parse::<u64>()
+        .ok()
+        .filter(|value| *value > 0 && call.args.is_empty())
+        .and_then(|value| value.checked_mul(scale))
+        .ok_or_else(error)
+}
+
+/// Assigns the priorities of the tasks with a deadline
+pub fn assign(hardware_tasks: &mut Map<HardwareTask>, software_tasks: &mut Map<SoftwareTask>) {
+    let mut deadlines = hardware_tasks
+        .values()
+        .filter_map(|task| task.args.deadline)
+        .chain(software_tasks.values().filter_map(|task| task.args.deadline))
+        .collect::<Vec<_>>();
+
+    // Longest first, its index + 1 is the priority
+    deadlines.sort_unstable_by(|a, b| b.cmp(a));
+    deadlines.dedup();
+
+    let priority = |deadline: u64| {
+        let rank = deadlines.iter().position(|d| *d == deadline).unwrap();
+        u8::try_from(rank + 1).unwrap_or(u8::MAX)
+    };
+
+    for task in hardware_tasks.values_mut() {
+        if let Some(deadline) = task.args.deadline {
+            task.args.priority = priority(deadline);
+        }
+    }
+
+    for task in software_tasks.values_mut() {
+        if let Some(deadline) = task.args.deadline {
+            task.args.priority = priority(deadline);
+        }
+    }
+}
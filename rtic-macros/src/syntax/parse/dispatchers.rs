@@ -0,0 +1,117 @@
+//! `dispatchers = [..]` argument of `#[app]`
+//!
+//! Each entry is an interrupt, optionally restricted to the priorities it may dispatch with
+//! `=> 2` or `=> 2..=3`. The priorities are assigned to the dispatchers after parsing, see
+//! `analyze::assign_dispatchers`.
+
+use std::ops::RangeInclusive;
+
+use syn::{
+    bracketed,
+    parse::{self, ParseStream},
+    spanned::Spanned,
+    Attribute, LitInt, Path, Token,
+};
+
+use crate::syntax::ast::{Dispatcher, Dispatchers};
+
+/// Parses `[#[attrs] INTERRUPT => priorities, ..]`
+pub fn parse(input: ParseStream<'_>) -> parse::Result<Dispatchers> {
+    let content;
+    bracketed!(content in input);
+
+    let mut dispatchers = Dispatchers::new();
+    while !content.is_empty() {
+        let attrs = content.call(Attribute::parse_outer)?;
+        let path = content
+            .parse::<Path>()
+            .map_err(|e| parse::Error::new(e.span(), "interrupt must be an identifier"))?;
+        let ident = if path.leading_colon.is_some() || path.segments.len() != 1 {
+            return Err(parse::Error::new(
+                path.span(),
+                "interrupt must be an identifier, not a path",
+            ));
+        } else {
+            path.segments[0].ident.clone()
+        };
+
+        if dispatchers.contains_key(&ident) {
+            return Err(parse::Error::new(
+                ident.span(),
+                "this extern interrupt is listed more than once",
+            ));
+        }
+
+        let priorities = if content.parse::<Option<Token![=>]>>()?.is_some() {
+            Some(priorities(&content)?)
+        } else {
+            None
+        };
+
+        let mut other_attrs = vec![];
+        let mut executor = None;
+        for attr in attrs {
+            if attr.path().is_ident("executor") {
+                if executor.is_some() {
+                    return Err(parse::Error::new(
+                        attr.span(),
+                        "this dispatcher already has an executor",
+                    ));
+                }
+                executor = Some(attr.parse_args::<Path>()?);
+            } else {
+                other_attrs.push(attr);
+            }
+        }
+
+        dispatchers.insert(
+            ident,
+            Dispatcher {
+                attrs: other_attrs,
+                executor,
+                priorities,
+            },
+        );
+
+        if content.is_empty() {
+            break;
+        }
+        content.parse::<Token![,]>()?;
+    }
+
+    Ok(dispatchers)
+}
+
+/// Parses `N` or `N..=M`
+fn priorities(input: ParseStream<'_>) -> parse::Result<RangeInclusive<u8>> {
+    let start = priority(input)?;
+    let span = input.span();
+    let end = if input.parse::<Option<Token![..=]>>()?.is_some() {
+        priority(input)?
+    } else {
+        start
+    };
+
+    if end < start {
+        return Err(parse::Error::new(
+            span,
+            "empty priority range, expected `N..=M` with `N <= M`",
+        ));
+    }
+
+    Ok(start..=end)
+}
+
+fn priority(input: ParseStream<'_>) -> parse::Result<u8> {
+    let lit = input.parse::<LitInt>()?;
+    let priority = lit.base10_parse::<u8>()?;
+
+    if priority == 0 {
+        return Err(parse::Error::new(
+            lit.span(),
+            "dispatchers run at priority 1 or higher, priority 0 tasks run in `main`",
+        ));
+    }
+
+    Ok(priority)
+}
@@ -0,0 +1,65 @@
+//! The doorbell of the tasks spawned from the other core on the RP2040, see `cross_core = N`
+//!
+//! A spawn from the other core rings the doorbell of this core with the dispatcher of the task,
+//! which `rtic::rp2040::drain` pends. An application with `cross_core` tasks that does not bind
+//! the `SIO_IRQ_PROCn` of its core itself gets a hardware task draining the doorbell, at the
+//! highest priority of these tasks.
+
+use proc_macro2::{Span, TokenTree};
+use quote::format_ident;
+use syn::{parse, parse_quote, Ident, Item, LitInt, Meta};
+
+use super::util;
+use crate::syntax::ast::AppArgs;
+
+/// Adds the doorbell task of the core if a task is spawned from the other one
+pub fn expand(args: &AppArgs, items: &mut Vec<Item>) -> parse::Result<()> {
+    let core = args.backend.as_ref().map_or(0, |backend| backend.core);
+    let doorbell = format_ident!("SIO_IRQ_PROC{}", core);
+    let mut cross_core = false;
+    let mut priority = 1;
+
+    for item in items.iter() {
+        let Item::Fn(task) = item else { continue };
+        let Some(attr) = task.attrs.iter().find(|attr| util::attr_eq(attr, "task")) else {
+            continue;
+        };
+        let Meta::List(list) = &attr.meta else {
+            continue;
+        };
+
+        let task_args = util::split(list.tokens.clone());
+        if let Some((_, [TokenTree::Ident(binds)])) = util::find(&task_args, "binds") {
+            if *binds == doorbell {
+                // Drained by the application
+                return Ok(());
+            }
+        }
+        if util::find(&task_args, "cross_core").is_none() {
+            continue;
+        }
+        cross_core = true;
+
+        // The doorbell runs at the highest literal priority of the tasks, at least 1
+        if let Some((_, [TokenTree::Literal(lit)])) = util::find(&task_args, "priority") {
+            if let Ok(task_priority) = lit.to_string().parse::<u8>() {
+                priority = priority.max(task_priority);
+            }
+        }
+    }
+
+    if !cross_core {
+        return Ok(());
+    }
+
+    let priority = LitInt::new(&priority.to_string(), Span::call_site());
+    let name = Ident::new("__rtic_doorbell", Span::call_site());
+    items.push(parse_quote!(
+        #[task(binds = #doorbell, priority = #priority)]
+        fn #name(_: #name::Context) {
+            rtic::rp2040::drain();
+        }
+    ));
+
+    Ok(())
+}
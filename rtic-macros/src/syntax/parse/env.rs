@@ -0,0 +1,94 @@
+//! `env!("NAME")` in place of the integer literal of a task argument
+//!
+//! Lets product variants tune the schedule without editing the application: the build script
+//! of the application selects the values, e.g. from its features, and passes them with
+//! `cargo:rustc-env=NAME=value`. They are read from the environment of the compiler, which runs
+//! the macro. `env!("NAME", default)` falls back to `default` when `NAME` is not set.
+
+use std::cell::RefCell;
+
+use syn::{
+    parse::{self, ParseStream},
+    Ident, LitInt, LitStr, Token,
+};
+
+thread_local! {
+    /// The variables read while parsing the application, see [`take`]
+    static READ: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Takes the names of the variables read since the last call
+///
+/// The generated code reads them again with `option_env!`, so the compiler records them as
+/// dependencies and Cargo rebuilds the application when one changes.
+pub fn take() -> Vec<String> {
+    READ.with(|read| std::mem::take(&mut *read.borrow_mut()))
+}
+
+/// Whether the next argument value is an `env!(..)` or `config!(..)` invocation
+pub fn peek(input: ParseStream<'_>) -> bool {
+    input.peek(Ident) && input.peek2(Token![!])
+}
+
+/// Parses an integer literal or `env!("NAME"[, default])`, the value of the variable is returned
+/// as a literal spanned at the invocation so it is checked like a written one
+///
+/// `config!("key"[, default])` is handed to [`config`](super::config).
+pub fn parse_int(input: ParseStream<'_>) -> parse::Result<LitInt> {
+    if !peek(input) {
+        return input.parse();
+    }
+
+    let ident: Ident = input.parse()?;
+    if ident == "config" {
+        return super::config::parse_int_after(ident, input);
+    }
+    if ident != "env" {
+        return Err(parse::Error::new(
+            ident.span(),
+            "expected an integer literal, `env!(\"NAME\")` or `config!(\"key\")`",
+        ));
+    }
+    let _: Token![!] = input.parse()?;
+
+    let content;
+    syn::parenthesized!(content in input);
+    let name: LitStr = content.parse()?;
+    let default = if content.is_empty() {
+        None
+    } else {
+        let _: Token![,] = content.parse()?;
+        Some(content.parse::<LitInt>()?)
+    };
+    if !content.is_empty() {
+        return Err(content.error("expected `env!(\"NAME\")` or `env!(\"NAME\", default)`"));
+    }
+
+    let span = ident.span();
+    READ.with(|read| read.borrow_mut().push(name.value()));
+    match std::env::var(name.value()) {
+        Ok(value) => {
+            let value = value.trim();
+            if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(parse::Error::new(
+                    name.span(),
+                    format!(
+                        "environment variable `{}` must be an unsuffixed integer, found `{value}`",
+                        name.value()
+                    ),
+                ));
+            }
+
+            Ok(LitInt::new(value, span))
+        }
+        Err(_) => default.ok_or_else(|| {
+            parse::Error::new(
+                name.span(),
+                format!(
+                    "environment variable `{}` is not set; set it with `cargo:rustc-env` from the build script or give a default",
+                    name.value()
+                ),
+            )
+        }),
+    }
+}
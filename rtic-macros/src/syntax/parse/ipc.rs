@@ -0,0 +1,134 @@
+//! `ipc = N` argument of software tasks on the nRF5340
+//!
+//! A task with `ipc = N` is spawned from the other core through channel `N` of the IPC
+//! peripheral. The channels are enabled at the start of `#[init]`, and a hardware task bound to
+//! `IPC`, at the highest priority of these tasks, spawns the tasks of the signalled channels.
+//! Every application gets `spawn_remote(N)`, which signals channel `N` to the other core.
+
+use proc_macro2::{Span, TokenStream as TokenStream2, TokenTree};
+use quote::quote;
+use syn::{parse, parse_quote, Ident, Item, LitInt, Meta, Stmt};
+
+use super::util;
+use crate::syntax::{
+    ast::AppArgs,
+    backend::{BackendArgs, Security},
+};
+
+/// The IPC peripheral of the core the application runs on
+fn ipc(backend: Option<&BackendArgs>) -> TokenStream2 {
+    match backend {
+        Some(BackendArgs { core: 1, .. }) => quote!(rtic::nrf5340::Ipc::NET),
+        Some(BackendArgs {
+            security: Some(Security::NonSecure),
+            ..
+        }) => quote!(rtic::nrf5340::Ipc::APP_NONSECURE),
+        _ => quote!(rtic::nrf5340::Ipc::APP),
+    }
+}
+
+/// Moves the `ipc = N` arguments of the tasks into the generated `IPC` handler
+pub fn expand(args: &AppArgs, items: &mut Vec<Item>) -> parse::Result<()> {
+    let ipc = ipc(args.backend.as_ref());
+    let mut channels: Vec<(u8, Ident)> = vec![];
+    let mut priority = 1;
+
+    for item in items.iter_mut() {
+        let Item::Fn(task) = item else { continue };
+        let name = task.sig.ident.clone();
+        let inputs = task.sig.inputs.len();
+        let Some(attr) = task.attrs.iter_mut().find(|attr| util::attr_eq(attr, "task")) else {
+            continue;
+        };
+        let Meta::List(list) = &mut attr.meta else {
+            continue;
+        };
+
+        let mut task_args = util::split(list.tokens.clone());
+        let Some((position, value)) = util::find(&task_args, "ipc") else {
+            continue;
+        };
+
+        let lit: LitInt = syn::parse2(value.iter().cloned().collect())?;
+        let channel = lit
+            .base10_parse::<u8>()
+            .ok()
+            .filter(|channel| *channel < 16)
+            .ok_or_else(|| parse::Error::new(lit.span(), "this literal must be in the range 0...15"))?;
+        if channels.iter().any(|(used, _)| *used == channel) {
+            return Err(parse::Error::new(
+                lit.span(),
+                "this IPC channel spawns another task",
+            ));
+        }
+        if util::find(&task_args, "binds").is_some() {
+            return Err(parse::Error::new(
+                lit.span(),
+                "only software tasks can be spawned from the other core",
+            ));
+        }
+        if inputs != 1 {
+            return Err(parse::Error::new(
+                name.span(),
+                "tasks spawned from the other core can not take arguments",
+            ));
+        }
+
+        // The handler runs at the highest literal priority of the tasks, at least 1
+        if let Some((_, [TokenTree::Literal(lit)])) = util::find(&task_args, "priority") {
+            if let Ok(task_priority) = lit.to_string().parse::<u8>() {
+                priority = priority.max(task_priority);
+            }
+        }
+
+        task_args.remove(position);
+        let task_args = task_args.into_iter().map(TokenStream2::from_iter);
+        list.tokens = quote!(#(#task_args),*);
+
+        channels.push((channel, name));
+    }
+
+    items.push(parse_quote!(
+        /// Spawns the task of the other core declared with `ipc = channel`
+        pub fn spawn_remote(channel: u8) {
+            #ipc.send(channel);
+        }
+    ));
+
+    if channels.is_empty() {
+        return Ok(());
+    }
+
+    let mask = channels
+        .iter()
+        .fold(0u16, |mask, (channel, _)| mask | 1 << channel);
+    for item in items.iter_mut() {
+        if let Item::Fn(init) = item {
+            if init.attrs.iter().any(|attr| util::attr_eq(attr, "init")) {
+                let listen: Stmt = parse_quote!(#ipc.listen(#mask););
+                init.block.stmts.insert(0, listen);
+            }
+        }
+    }
+
+    let priority = LitInt::new(&priority.to_string(), Span::call_site());
+    let name = Ident::new("__rtic_ipc", Span::call_site());
+    let spawns = channels.iter().map(|(channel, task)| {
+        quote!(
+            if #ipc.take(#channel) {
+                // A task still running coalesces the signal
+                let _ = #task::spawn();
+            }
+        )
+    });
+
+    items.push(parse_quote!(
+        #[task(binds = IPC, priority = #priority)]
+        fn #name(_: #name::Context) {
+            #(#spawns)*
+        }
+    ));
+
+    Ok(())
+}
+
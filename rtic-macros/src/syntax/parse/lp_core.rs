@@ -0,0 +1,335 @@
+//! `#[lp_task]`s of the ESP32-C6, placed on the LP core
+//!
+//! The `#[lp_task]`s are moved out of the application of the HP core into the image of the LP
+//! core, where `run()` runs them as the HP core posts their slots, in declaration order. The HP
+//! application gets `NAME::spawn()` for each of them. The HP software tasks listed in their
+//! `spawns = [..]` get `hp::NAME::spawn()` in the LP image, and a hardware task bound to `PMU`,
+//! at the highest priority of these tasks, spawns them on the HP core.
+
+use proc_macro2::{Span, TokenTree};
+use quote::{format_ident, quote};
+use syn::{
+    bracketed,
+    parse::{self, Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+    Ident, Item, ItemFn, LitInt, Meta, Stmt, Token,
+};
+
+use super::util;
+use crate::syntax::{
+    ast::{LocalResources, TaskLocal},
+    Map,
+};
+
+/// The arguments of `#[lp_task(..)]`
+#[derive(Default)]
+struct LpTaskArgs {
+    local: LocalResources,
+    spawns: Vec<Ident>,
+}
+
+impl Parse for LpTaskArgs {
+    fn parse(input: ParseStream<'_>) -> parse::Result<Self> {
+        let mut local = None;
+        let mut spawns = None;
+
+        while !input.is_empty() {
+            let ident: Ident = input.parse()?;
+            let _: Token![=] = input.parse()?;
+
+            let duplicate = match &*ident.to_string() {
+                "local" => local
+                    .replace(util::parse_local_resources(input)?)
+                    .is_some(),
+                "spawns" => {
+                    let content;
+                    bracketed!(content in input);
+                    let tasks = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+                    spawns.replace(tasks.into_iter().collect()).is_some()
+                }
+                _ => {
+                    return Err(parse::Error::new(ident.span(), "unexpected argument"));
+                }
+            };
+            if duplicate {
+                return Err(parse::Error::new(
+                    ident.span(),
+                    "argument appears more than once",
+                ));
+            }
+
+            if input.is_empty() {
+                break;
+            }
+            let _: Token![,] = input.parse()?;
+        }
+
+        Ok(LpTaskArgs {
+            local: local.unwrap_or_else(Map::new),
+            spawns: spawns.unwrap_or_default(),
+        })
+    }
+}
+
+/// Moves the `#[lp_task]`s into the items of the LP image, which are returned
+pub fn expand(items: &mut Vec<Item>) -> parse::Result<Vec<Item>> {
+    let mut tasks: Vec<(ItemFn, LpTaskArgs)> = vec![];
+    for item in std::mem::take(items) {
+        match item {
+            Item::Fn(mut task) if task.attrs.iter().any(|attr| util::attr_eq(attr, "lp_task")) => {
+                let position = task
+                    .attrs
+                    .iter()
+                    .position(|attr| util::attr_eq(attr, "lp_task"))
+                    .expect("UNREACHABLE");
+                let attr = task.attrs.remove(position);
+                let args = match attr.meta {
+                    Meta::Path(_) => LpTaskArgs::default(),
+                    _ => attr.parse_args()?,
+                };
+
+                let name = &task.sig.ident;
+                if !util::check_fn_signature(&task, false)
+                    || task.sig.inputs.len() != 1
+                    || !util::type_is_unit(&task.sig.output)
+                {
+                    return Err(parse::Error::new(
+                        name.span(),
+                        format!("this LP task must have type signature `fn({name}::Context)`"),
+                    ));
+                }
+                if tasks.len() == 32 {
+                    return Err(parse::Error::new(
+                        name.span(),
+                        "the LP core runs at most 32 `#[lp_task]`s",
+                    ));
+                }
+                for (local, task_local) in &args.local {
+                    if let TaskLocal::External = task_local {
+                        return Err(parse::Error::new(
+                            local.span(),
+                            "LP tasks only have local resources declared in place, `name: Type = init`",
+                        ));
+                    }
+                }
+
+                tasks.push((task, args));
+            }
+            item => items.push(item),
+        }
+    }
+
+    if tasks.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // The HP software tasks spawned from the LP core, slot `n` for the `n`th of them
+    let mut spawns: Vec<Ident> = vec![];
+    let mut priority = 1;
+    for hp_task in tasks.iter().flat_map(|(_, args)| &args.spawns) {
+        if spawns.contains(hp_task) {
+            continue;
+        }
+        if spawns.len() == 32 {
+            return Err(parse::Error::new(
+                hp_task.span(),
+                "the LP core spawns at most 32 tasks of the HP core",
+            ));
+        }
+
+        let declaration = items.iter().find_map(|item| match item {
+            Item::Fn(task) if task.sig.ident == *hp_task => task
+                .attrs
+                .iter()
+                .find(|attr| util::attr_eq(attr, "task"))
+                .map(|attr| (task, attr)),
+            _ => None,
+        });
+        let Some((task, attr)) = declaration else {
+            return Err(parse::Error::new(
+                hp_task.span(),
+                "this task of the HP core has NOT been declared",
+            ));
+        };
+
+        let task_args = match &attr.meta {
+            Meta::List(list) => util::split(list.tokens.clone()),
+            _ => vec![],
+        };
+        if util::find(&task_args, "binds").is_some() {
+            return Err(parse::Error::new(
+                hp_task.span(),
+                "only software tasks can be spawned from the LP core",
+            ));
+        }
+        if task.sig.inputs.len() != 1 {
+            return Err(parse::Error::new(
+                hp_task.span(),
+                "tasks spawned from the LP core can not take arguments",
+            ));
+        }
+
+        // The handler runs at the highest literal priority of the tasks, at least 1
+        if let Some((_, [TokenTree::Literal(lit)])) = util::find(&task_args, "priority") {
+            if let Ok(task_priority) = lit.to_string().parse::<u8>() {
+                priority = priority.max(task_priority);
+            }
+        }
+
+        spawns.push(hp_task.clone());
+    }
+
+    let mailbox = quote!(rtic::esp32c6_lp::Mailbox);
+
+    // HP image: the spawns of the LP tasks, and the handler of the spawns from the LP core
+    for (slot, (task, _)) in tasks.iter().enumerate() {
+        let name = &task.sig.ident;
+        let slot = slot as u8;
+        let doc = format!("Spawns the LP task `{name}`, a spawn still pending is coalesced");
+        items.push(parse_quote!(
+            #[allow(non_snake_case)]
+            pub mod #name {
+                #[doc = #doc]
+                #[inline]
+                pub fn spawn() {
+                    #mailbox::HP_TO_LP.post(#slot);
+                }
+            }
+        ));
+    }
+
+    if !spawns.is_empty() {
+        for item in items.iter_mut() {
+            if let Item::Fn(init) = item {
+                if init.attrs.iter().any(|attr| util::attr_eq(attr, "init")) {
+                    let listen: Stmt = parse_quote!(rtic::esp32c6_lp::listen(););
+                    init.block.stmts.insert(0, listen);
+                }
+            }
+        }
+
+        let priority = LitInt::new(&priority.to_string(), Span::call_site());
+        let name = Ident::new("__rtic_lp_core", Span::call_site());
+        let takes = spawns.iter().enumerate().map(|(slot, task)| {
+            let slot = slot as u8;
+            quote!(
+                if #mailbox::LP_TO_HP.take(#slot) {
+                    // A task still running coalesces the post
+                    let _ = #task::spawn();
+                }
+            )
+        });
+
+        items.push(parse_quote!(
+            #[task(binds = PMU, priority = #priority)]
+            fn #name(_: #name::Context) {
+                rtic::esp32c6_lp::acknowledge();
+                #(#takes)*
+            }
+        ));
+    }
+
+    // LP image: the tasks, their context, the spawns of HP tasks and the loop running the tasks
+    let mut lp = vec![];
+    let mut locals = vec![];
+    let mut runs = vec![];
+    for (slot, (task, args)) in tasks.iter().enumerate() {
+        let name = &task.sig.ident;
+        let slot = slot as u8;
+
+        let mut fields = vec![];
+        let mut values = vec![];
+        for (local, task_local) in &args.local {
+            let TaskLocal::Declared(declared) = task_local else {
+                unreachable!()
+            };
+            let cfgs = &declared.cfgs;
+            let ty = &declared.ty;
+            let expr = &declared.expr;
+            let var = format_ident!("__rtic_internal_{}_{}", name, local);
+
+            locals.push(quote!(
+                #(#cfgs)*
+                let mut #var: #ty = #expr;
+            ));
+            fields.push(quote!(
+                #(#cfgs)*
+                pub #local: &'a mut #ty,
+            ));
+            values.push(quote!(
+                #(#cfgs)*
+                #local: &mut #var,
+            ));
+        }
+
+        let doc = format!("Local resources and context of the LP task `{name}`");
+        lp.push(parse_quote!(
+            #[doc = #doc]
+            #[allow(non_snake_case)]
+            pub mod #name {
+                /// Local resources the task has access to
+                pub struct LocalResources<'a> {
+                    #(#fields)*
+                    #[doc(hidden)]
+                    pub __rtic_internal_marker: ::core::marker::PhantomData<&'a ()>,
+                }
+
+                /// Execution context
+                pub struct Context<'a> {
+                    /// Local resources the task has access to
+                    pub local: LocalResources<'a>,
+                }
+            }
+        ));
+        lp.push(Item::Fn(task.clone()));
+
+        runs.push(quote!(
+            if #mailbox::HP_TO_LP.take(#slot) {
+                #name(#name::Context {
+                    local: #name::LocalResources {
+                        #(#values)*
+                        __rtic_internal_marker: ::core::marker::PhantomData,
+                    },
+                });
+            }
+        ));
+    }
+
+    let hp_spawns = spawns.iter().enumerate().map(|(slot, task)| {
+        let slot = slot as u8;
+        let doc = format!("Spawns the task `{task}` of the HP core, a spawn still pending is coalesced");
+        quote!(
+            #[allow(non_snake_case)]
+            pub mod #task {
+                #[doc = #doc]
+                #[inline]
+                pub fn spawn() {
+                    #mailbox::LP_TO_HP.post(#slot);
+                }
+            }
+        )
+    });
+    lp.push(parse_quote!(
+        /// The tasks of the HP core spawned from the LP core
+        pub mod hp {
+            #(#hp_spawns)*
+        }
+    ));
+
+    lp.push(parse_quote!(
+        /// Runs the LP tasks the HP core spawns, called from the entry point of the LP runtime
+        pub fn run() -> ! {
+            #(#locals)*
+
+            loop {
+                #(#runs)*
+
+                // A post after its slot was taken triggers another pass
+                rtic::esp32c6_lp::wait();
+            }
+        }
+    ));
+
+    Ok(lp)
+}
@@ -0,0 +1,76 @@
+//! `modes = [..]` argument of `#[app]` and of tasks
+//!
+//! The application declares its operating modes, the first one being the mode it starts in, and a
+//! task declares the modes it is active in, all of them when it does not. The interrupt of a
+//! hardware task is masked while the application is in a mode the task is not active in, and a
+//! software task is not polled, pausing it at its current `await`.
+
+use syn::{
+    bracketed,
+    parse::{self, ParseStream},
+    punctuated::Punctuated,
+    Ident, Token,
+};
+
+use crate::syntax::{
+    ast::{AppArgs, HardwareTask, SoftwareTask},
+    Map,
+};
+
+/// Parses `[Mode, ..]`
+pub fn parse(input: ParseStream<'_>) -> parse::Result<Vec<Ident>> {
+    let content;
+    let brackets = bracketed!(content in input);
+    let modes = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+
+    if modes.is_empty() {
+        return Err(parse::Error::new(
+            brackets.span.join(),
+            "expected at least one mode",
+        ));
+    }
+
+    let mut seen: Vec<Ident> = vec![];
+    for mode in modes {
+        if seen.contains(&mode) {
+            return Err(parse::Error::new(
+                mode.span(),
+                "this mode appears more than once",
+            ));
+        }
+        seen.push(mode);
+    }
+
+    Ok(seen)
+}
+
+/// Checks that the tasks are only active in modes of the application
+pub fn check(
+    args: &AppArgs,
+    hardware_tasks: &Map<HardwareTask>,
+    software_tasks: &Map<SoftwareTask>,
+) -> parse::Result<()> {
+    let modes = hardware_tasks
+        .values()
+        .map(|task| &task.args.modes)
+        .chain(software_tasks.values().map(|task| &task.args.modes))
+        .flatten();
+
+    for mode in modes {
+        if args.modes.is_empty() {
+            return Err(parse::Error::new(
+                mode.span(),
+                "the application has no modes, declare them with `#[app(.., modes = [..])]`",
+            ));
+        }
+
+        if !args.modes.contains(mode) {
+            return Err(parse::Error::new(
+                mode.span(),
+                "this mode is not declared in `#[app(.., modes = [..])]`",
+            ));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! `period = ..` argument of software tasks
+//!
+//! `period = 10.millis()`, or `period = (Mono, 10.millis())` with another monotonic than `Mono`,
+//! makes the body of the task run once per period from the spawn of the task on. The period is
+//! changed at runtime with the generated `set_period`, which moves the pending release of the
+//! task as well.
+
+use syn::{
+    parse::{self, Parse, ParseStream},
+    parse_quote, Expr,
+};
+
+use crate::syntax::ast::Period;
+
+impl Parse for Period {
+    fn parse(input: ParseStream<'_>) -> parse::Result<Self> {
+        let expr: Expr = input.parse()?;
+
+        if let Expr::Tuple(tuple) = &expr {
+            let mut elems = tuple.elems.iter();
+            return match (elems.next(), elems.next(), elems.next()) {
+                (Some(Expr::Path(monotonic)), Some(period), None) => Ok(Period {
+                    monotonic: monotonic.path.clone(),
+                    period: period.clone(),
+                }),
+                _ => Err(parse::Error::new_spanned(
+                    expr,
+                    "expected `period = duration` or `period = (Mono, duration)`",
+                )),
+            };
+        }
+
+        Ok(Period {
+            monotonic: parse_quote!(Mono),
+            period: expr,
+        })
+    }
+}
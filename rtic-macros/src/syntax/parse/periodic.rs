@@ -0,0 +1,77 @@
+//! `spawn_periodic!` in `#[init]`
+//!
+//! `spawn_periodic!(Mono, period, || body)` declares a tiny periodic job inline. Each use becomes
+//! a software task at priority 1 with a generated name, running `body` every `period` of the
+//! monotonic `Mono` (`Mono` when omitted), and is replaced by a spawn of that task.
+
+use quote::format_ident;
+use syn::{
+    parse::{self, Parser},
+    parse_quote,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Expr, Item, Stmt, Token,
+};
+
+use super::util;
+
+/// Expands the `spawn_periodic!` statements of the `#[init]` function into software tasks
+pub fn expand(items: &mut Vec<Item>) -> parse::Result<()> {
+    let mut tasks = vec![];
+
+    for item in items.iter_mut() {
+        let Item::Fn(init) = item else { continue };
+        if !init.attrs.iter().any(|attr| util::attr_eq(attr, "init")) {
+            continue;
+        }
+
+        for stmt in &mut init.block.stmts {
+            let Stmt::Macro(m) = stmt else { continue };
+            if !m.mac.path.is_ident("spawn_periodic") {
+                continue;
+            }
+
+            let args = Punctuated::<Expr, Token![,]>::parse_terminated.parse2(m.mac.tokens.clone())?;
+            let (monotonic, period, job) = match args.len() {
+                2 => (parse_quote!(Mono), &args[0], &args[1]),
+                3 => (args[0].clone(), &args[1], &args[2]),
+                _ => {
+                    return Err(parse::Error::new(
+                        m.mac.span(),
+                        "expected `spawn_periodic!(Mono, period, || ..)` or `spawn_periodic!(period, || ..)`",
+                    ))
+                }
+            };
+
+            match job {
+                Expr::Closure(closure)
+                    if closure.inputs.is_empty() && closure.asyncness.is_none() => {}
+                _ => {
+                    return Err(parse::Error::new(
+                        job.span(),
+                        "the periodic job must be a closure without arguments, `|| ..`",
+                    ))
+                }
+            }
+
+            let name = format_ident!("__rtic_periodic_{}", tasks.len());
+            tasks.push(Item::Fn(parse_quote!(
+                #[task(priority = 1)]
+                async fn #name(_: #name::Context) {
+                    let mut next = #monotonic::now();
+                    loop {
+                        (#job)();
+                        next = next + #period;
+                        #monotonic::delay_until(next).await;
+                    }
+                }
+            )));
+
+            *stmt = parse_quote!(#name::spawn().unwrap(););
+        }
+    }
+
+    items.extend(tasks);
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
-use syn::{parse, Field};
+use syn::{parse, spanned::Spanned, Field};
 
+use crate::c_header;
 use crate::syntax::parse::util::FilterAttrs;
 use crate::syntax::{
     ast::{LocalResource, SharedResource, SharedResourceProperties},
@@ -15,13 +16,94 @@ impl SharedResource {
         } = util::filter_attributes(item.attrs.clone());
 
         let lock_free = util::extract_lock_free(&mut attrs)?;
+        let poison = util::extract_poison(&mut attrs)?;
+        let wcet = util::extract_wcet(&mut attrs)?;
+        let depends_on = util::extract_depends_on(&mut attrs)?;
+        let c_export = util::extract_c_export(&mut attrs)?;
+        let atomic = util::extract_atomic(&mut attrs, &item.ty)?;
+        let cross_core_span = attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("cross_core"))
+            .map(|attr| attr.span());
+        let cross_core = util::extract_cross_core(&mut attrs)?;
+
+        if let (true, Some(span)) = (lock_free, poison) {
+            return Err(parse::Error::new(
+                span,
+                "`#[lock_free]` resources are never locked, they can not be poisoned",
+            ));
+        }
+
+        if let Some((_, span)) = c_export {
+            if lock_free {
+                return Err(parse::Error::new(
+                    span,
+                    "`#[lock_free]` resources are never locked, they can not be exported to C",
+                ));
+            }
+
+            if c_header::c_type(&item.ty).is_none() {
+                return Err(parse::Error::new(
+                    item.ty.span(),
+                    "`#[c_export]` resources must have a primitive type, e.g. `u32`, `i16`, `f32` or `bool`",
+                ));
+            }
+        }
+
+        if let Some(atomic) = &atomic {
+            let other = if lock_free {
+                Some("`#[lock_free]`")
+            } else if poison.is_some() {
+                Some("`#[poison]`")
+            } else if c_export.is_some() {
+                Some("`#[c_export]`")
+            } else {
+                None
+            };
+
+            if let Some(other) = other {
+                return Err(parse::Error::new(
+                    atomic.span(),
+                    format!("`#[atomic]` resources are never locked, they can not be combined with {other}"),
+                ));
+            }
+        }
+
+        if let (Some(_), Some(span)) = (cross_core, cross_core_span) {
+            let other = if lock_free {
+                Some("`#[lock_free]`")
+            } else if poison.is_some() {
+                Some("`#[poison]`")
+            } else if c_export.is_some() {
+                Some("`#[c_export]`")
+            } else if atomic.is_some() {
+                Some("`#[atomic]`")
+            } else {
+                None
+            };
+
+            if let Some(other) = other {
+                return Err(parse::Error::new(
+                    span,
+                    format!("`#[cross_core]` resources can not be combined with {other}"),
+                ));
+            }
+        }
 
         Ok(SharedResource {
             cfgs,
             attrs,
             docs,
             ty: Box::new(item.ty.clone()),
-            properties: SharedResourceProperties { lock_free },
+            properties: SharedResourceProperties {
+                lock_free,
+                poison: poison.is_some(),
+                wcet,
+                c_export: c_export.map(|(priority, _)| priority),
+                atomic,
+                cross_core,
+            },
+            depends_on,
             vis: item.vis.clone(),
         })
     }
@@ -29,13 +111,20 @@ impl SharedResource {
 
 impl LocalResource {
     pub(crate) fn parse(item: &Field) -> parse::Result<Self> {
-        let FilterAttrs { cfgs, attrs, docs } = util::filter_attributes(item.attrs.clone());
+        let FilterAttrs {
+            cfgs,
+            mut attrs,
+            docs,
+        } = util::filter_attributes(item.attrs.clone());
+
+        let depends_on = util::extract_depends_on(&mut attrs)?;
 
         Ok(LocalResource {
             cfgs,
             attrs,
             docs,
             ty: Box::new(item.ty.clone()),
+            depends_on,
             vis: item.vis.clone(),
         })
     }
@@ -0,0 +1,185 @@
+//! `schedule = (..)` argument of `#[app]`
+//!
+//! `schedule = (cycle = 100.millis(), table = [(0.millis(), sample), (40.millis(), control)])`
+//! declares a static time-triggered schedule. A generated software task, spawned at the start of
+//! `#[init]`, spawns each task of the table at its offset within every major cycle of `cycle`,
+//! measured on the monotonic (`Mono` unless given with `monotonic = ..`). It runs at the highest
+//! priority of the scheduled tasks unless given with `priority = ..`. A slot whose task is still
+//! running from its last slot is an overrun: it is skipped and counted in
+//! `rtic::schedule::overruns`.
+
+use proc_macro2::{Span, TokenTree};
+use quote::quote;
+use syn::{
+    parse::{self, Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Expr, ExprLit, Ident, Item, Lit, LitInt, Meta, MetaNameValue, Stmt, Token,
+};
+
+use super::util;
+use crate::syntax::ast::{AppArgs, Schedule};
+
+impl Parse for Schedule {
+    fn parse(input: ParseStream<'_>) -> parse::Result<Self> {
+        let args = Punctuated::<MetaNameValue, Token![,]>::parse_terminated(input)?;
+        let (mut monotonic, mut cycle, mut priority, mut table) = (None, None, None, None);
+
+        for arg in args {
+            let name = arg.path.get_ident().map(Ident::to_string);
+            let duplicate = match name.as_deref() {
+                Some("monotonic") => match arg.value {
+                    Expr::Path(path) => monotonic.replace(path.path).is_some(),
+                    value => return Err(parse::Error::new(value.span(), "expected a path")),
+                },
+                Some("cycle") => cycle.replace(arg.value).is_some(),
+                Some("priority") => {
+                    let value = match &arg.value {
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Int(lit), ..
+                        }) => lit.base10_parse::<u8>().ok().filter(|p| *p > 0),
+                        _ => None,
+                    };
+                    let value = value.ok_or_else(|| {
+                        parse::Error::new(
+                            arg.value.span(),
+                            "this literal must be in the range 1...255",
+                        )
+                    })?;
+                    priority.replace(value).is_some()
+                }
+                Some("table") => table.replace(parse_table(arg.value)?).is_some(),
+                _ => return Err(parse::Error::new(arg.path.span(), "unexpected argument")),
+            };
+
+            if duplicate {
+                return Err(parse::Error::new(
+                    arg.path.span(),
+                    "argument appears more than once",
+                ));
+            }
+        }
+
+        let (Some(cycle), Some(table)) = (cycle, table) else {
+            return Err(parse::Error::new(
+                input.span(),
+                "expected `schedule = (cycle = .., table = [(offset, task), ..])`",
+            ));
+        };
+
+        Ok(Schedule {
+            monotonic: monotonic.unwrap_or_else(|| parse_quote!(Mono)),
+            cycle,
+            priority,
+            table,
+        })
+    }
+}
+
+fn parse_table(value: Expr) -> parse::Result<Vec<(Expr, Ident)>> {
+    let Expr::Array(array) = value else {
+        return Err(parse::Error::new(
+            value.span(),
+            "expected a table, `[(offset, task), ..]`",
+        ));
+    };
+
+    if array.elems.is_empty() {
+        return Err(parse::Error::new(array.span(), "the table is empty"));
+    }
+
+    array
+        .elems
+        .into_iter()
+        .map(|slot| {
+            let span = slot.span();
+            let error = || parse::Error::new(span, "expected a slot, `(offset, task)`");
+
+            let Expr::Tuple(tuple) = slot else {
+                return Err(error());
+            };
+            let mut elems = tuple.elems.into_iter();
+            match (elems.next(), elems.next(), elems.next()) {
+                (Some(offset), Some(Expr::Path(task)), None) => {
+                    Ok((offset, task.path.get_ident().cloned().ok_or_else(error)?))
+                }
+                _ => Err(error()),
+            }
+        })
+        .collect()
+}
+
+/// Generates the task walking the table and spawns it in `#[init]`
+pub fn expand(args: &AppArgs, items: &mut Vec<Item>) {
+    let Some(schedule) = &args.schedule else {
+        return;
+    };
+
+    let Schedule {
+        monotonic,
+        cycle,
+        priority,
+        table,
+    } = schedule;
+    let priority = priority.unwrap_or_else(|| highest_priority(items, table));
+    let priority = LitInt::new(&priority.to_string(), Span::call_site());
+    let name = Ident::new("__rtic_schedule", Span::call_site());
+
+    let slots = table.iter().map(|(offset, task)| {
+        quote!(
+            #monotonic::delay_until(start + #offset).await;
+            if #task::spawn().is_err() {
+                rtic::schedule::overrun();
+            }
+        )
+    });
+
+    for item in items.iter_mut() {
+        if let Item::Fn(init) = item {
+            if init.attrs.iter().any(|attr| util::attr_eq(attr, "init")) {
+                let spawn: Stmt = parse_quote!(#name::spawn().unwrap(););
+                init.block.stmts.insert(0, spawn);
+            }
+        }
+    }
+
+    items.push(parse_quote!(
+        #[task(priority = #priority)]
+        async fn #name(_: #name::Context) {
+            let mut start = #monotonic::now();
+            loop {
+                #(#slots)*
+                start = start + #cycle;
+            }
+        }
+    ));
+}
+
+/// The highest literal priority among the `#[task]`s of the table, at least 1
+fn highest_priority(items: &[Item], table: &[(Expr, Ident)]) -> u8 {
+    let priority_of = |item: &Item| {
+        let Item::Fn(task) = item else { return None };
+        if !table.iter().any(|(_, name)| *name == task.sig.ident) {
+            return None;
+        }
+
+        let attr = task.attrs.iter().find(|attr| util::attr_eq(attr, "task"))?;
+        let Meta::List(list) = &attr.meta else {
+            return None;
+        };
+
+        // `priority = N` among the arguments, which are not all expressions
+        let trees: Vec<TokenTree> = list.tokens.clone().into_iter().collect();
+        trees.windows(3).find_map(|window| match window {
+            [TokenTree::Ident(name), TokenTree::Punct(eq), TokenTree::Literal(lit)]
+                if name == "priority" && eq.as_char() == '=' =>
+            {
+                lit.to_string().parse::<u8>().ok()
+            }
+            _ => None,
+        })
+    };
+
+    items.iter().filter_map(priority_of).max().unwrap_or(1).max(1)
+}
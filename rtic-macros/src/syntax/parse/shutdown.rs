@@ -0,0 +1,126 @@
+//! `#[shutdown]` function
+//!
+//! `#[shutdown(binds = [PVD, COMP1])] fn safe_state() { .. }` declares the outputs-safe sequence
+//! of the application. It is registered as the shutdown hook of `rtic::crash` at the start of
+//! `#[init]`, so a failed `assert_bounded!` runs it, and each interrupt of `binds`, a brown-out or
+//! supply voltage warning, gets a hardware task at `priority = max` that records the warning in
+//! the crash ring and runs it through `rtic::crash::fail`.
+
+use quote::format_ident;
+use syn::{
+    parse::{self, Parser},
+    parse_quote,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Expr, Ident, Item, Meta, MetaNameValue, Stmt, Token,
+};
+
+use super::util;
+
+/// The interrupts of `binds = [..]`
+fn parse_binds(attr: &syn::Attribute) -> parse::Result<Vec<Ident>> {
+    let Meta::List(list) = &attr.meta else {
+        return Ok(vec![]);
+    };
+
+    let args = Punctuated::<MetaNameValue, Token![,]>::parse_terminated.parse2(list.tokens.clone())?;
+    let mut binds: Option<Vec<Ident>> = None;
+    for arg in args {
+        if !arg.path.is_ident("binds") {
+            return Err(parse::Error::new(arg.path.span(), "unexpected argument"));
+        }
+
+        let Expr::Array(array) = &arg.value else {
+            return Err(parse::Error::new(
+                arg.value.span(),
+                "expected `binds = [INTERRUPT, ..]`",
+            ));
+        };
+        let mut interrupts = vec![];
+        for elem in &array.elems {
+            let interrupt = match elem {
+                Expr::Path(path) => path.path.get_ident().cloned(),
+                _ => None,
+            }
+            .ok_or_else(|| parse::Error::new(elem.span(), "expected an interrupt"))?;
+
+            if interrupts.contains(&interrupt) {
+                return Err(parse::Error::new(
+                    interrupt.span(),
+                    "this interrupt is listed more than once",
+                ));
+            }
+            interrupts.push(interrupt);
+        }
+
+        if binds.replace(interrupts).is_some() {
+            return Err(parse::Error::new(
+                arg.path.span(),
+                "argument appears more than once",
+            ));
+        }
+    }
+
+    Ok(binds.unwrap_or_default())
+}
+
+/// Registers the `#[shutdown]` function and binds its power warning interrupts
+pub fn expand(items: &mut Vec<Item>) -> parse::Result<()> {
+    let mut shutdown = None;
+    for item in items.iter_mut() {
+        let Item::Fn(function) = item else { continue };
+        let Some(position) = function
+            .attrs
+            .iter()
+            .position(|attr| util::attr_eq(attr, "shutdown"))
+        else {
+            continue;
+        };
+
+        let name = function.sig.ident.clone();
+        if shutdown.is_some() {
+            return Err(parse::Error::new(
+                name.span(),
+                "only one `#[shutdown]` function can be declared",
+            ));
+        }
+        if !util::check_fn_signature(function, false)
+            || !function.sig.inputs.is_empty()
+            || !util::type_is_unit(&function.sig.output)
+        {
+            return Err(parse::Error::new(
+                name.span(),
+                "the `#[shutdown]` function must have type signature `fn()`",
+            ));
+        }
+
+        let attr = function.attrs.remove(position);
+        shutdown = Some((name, parse_binds(&attr)?));
+    }
+
+    let Some((name, binds)) = shutdown else {
+        return Ok(());
+    };
+
+    for item in items.iter_mut() {
+        if let Item::Fn(init) = item {
+            if init.attrs.iter().any(|attr| util::attr_eq(attr, "init")) {
+                let register: Stmt = parse_quote!(rtic::crash::set_shutdown_hook(#name););
+                init.block.stmts.insert(0, register);
+            }
+        }
+    }
+
+    for interrupt in binds {
+        let task = format_ident!("__rtic_shutdown_{}", interrupt.to_string().to_lowercase());
+        let message = format!("power warning: {interrupt}");
+        items.push(parse_quote!(
+            #[task(binds = #interrupt, priority = max)]
+            fn #task(_: #task::Context) {
+                rtic::crash::fail(file!(), line!(), #message);
+            }
+        ));
+    }
+
+    Ok(())
+}
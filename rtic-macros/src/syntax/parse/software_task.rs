@@ -7,13 +7,15 @@ use crate::syntax::{
 };
 
 impl SoftwareTask {
-    pub(crate) fn parse(args: SoftwareTaskArgs, item: ItemFn) -> parse::Result<Self> {
+    pub(crate) fn parse(args: SoftwareTaskArgs, mut item: ItemFn) -> parse::Result<Self> {
         let valid_signature = util::check_fn_signature(&item, true)
             && util::type_is_unit(&item.sig.output)
             && item.sig.asyncness.is_some();
 
         let span = item.sig.ident.span();
 
+        let is_test_case = util::extract_test_case(&mut item.attrs);
+
         let name = item.sig.ident.to_string();
 
         if valid_signature {
@@ -28,6 +30,7 @@ impl SoftwareTask {
                     inputs,
                     stmts: item.block.stmts,
                     is_extern: false,
+                    is_test_case,
                 });
             }
         }
@@ -42,7 +45,7 @@ impl SoftwareTask {
 impl SoftwareTask {
     pub(crate) fn parse_foreign(
         args: SoftwareTaskArgs,
-        item: ForeignItemFn,
+        mut item: ForeignItemFn,
     ) -> parse::Result<Self> {
         let valid_signature = util::check_foreign_fn_signature(&item, true)
             && util::type_is_unit(&item.sig.output)
@@ -50,6 +53,8 @@ impl SoftwareTask {
 
         let span = item.sig.ident.span();
 
+        let is_test_case = util::extract_test_case(&mut item.attrs);
+
         let name = item.sig.ident.to_string();
 
         if valid_signature {
@@ -64,6 +69,7 @@ impl SoftwareTask {
                     inputs,
                     stmts: Vec::<Stmt>::new(),
                     is_extern: true,
+                    is_test_case,
                 });
             }
         }
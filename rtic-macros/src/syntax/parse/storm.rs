@@ -0,0 +1,247 @@
+//! `storm = (..)` argument of hardware tasks
+//!
+//! `#[task(binds = UART0, storm = (limit = 8, window = 10.millis(), cooldown = 1.secs()))]`
+//! guards a hardware task against a stuck interrupt line. A generated software task at priority 1,
+//! spawned at the start of `#[init]`, cuts the time into windows of `window` on the monotonic
+//! (`Mono` unless given with `monotonic = ..`). An interrupt over `limit` within a window masks
+//! the interrupt instead of running the handler and spawns the `notify` task, if any. At the end
+//! of that window the generated task waits `cooldown` more and unmasks the interrupt again.
+
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote};
+use syn::{
+    parse::{self, Parser},
+    parse_quote,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Expr, Ident, Item, Meta, MetaNameValue, Path, Stmt, Token,
+};
+
+use super::util;
+use crate::syntax::ast::AppArgs;
+
+struct Storm {
+    limit: Expr,
+    window: Expr,
+    cooldown: Expr,
+    monotonic: Path,
+    notify: Option<Path>,
+}
+
+/// Expands the `storm` arguments of hardware tasks into their guards and window tasks
+pub fn expand(args: &AppArgs, items: &mut Vec<Item>) -> parse::Result<()> {
+    let mut generated = vec![];
+    let mut spawns: Vec<Stmt> = vec![];
+
+    for item in items.iter_mut() {
+        let Item::Fn(task) = item else { continue };
+        let Some(attr) = task.attrs.iter_mut().find(|attr| util::attr_eq(attr, "task")) else {
+            continue;
+        };
+        let Meta::List(list) = &mut attr.meta else {
+            continue;
+        };
+        let Some((storm, binds)) = take_storm(&mut list.tokens)? else {
+            continue;
+        };
+
+        let Some(binds) = binds else {
+            return Err(parse::Error::new(
+                task.sig.ident.span(),
+                "only hardware tasks, with `binds = ..`, can have a `storm` guard",
+            ));
+        };
+
+        if !cfg!(any(
+            feature = "cortex-m-source-masking",
+            feature = "cortex-m-basepri"
+        )) {
+            return Err(parse::Error::new(
+                binds.span(),
+                "the `storm` guard is only supported by the Cortex-M backends",
+            ));
+        }
+
+        if is_exception(&binds) {
+            return Err(parse::Error::new(
+                binds.span(),
+                "exceptions can not be masked, the `storm` guard needs a device interrupt",
+            ));
+        }
+
+        let Storm {
+            limit,
+            window,
+            cooldown,
+            monotonic,
+            notify,
+        } = storm;
+        let device = &args.device;
+        let name = format_ident!("__rtic_storm_{}", task.sig.ident);
+        let state = format_ident!("__RTIC_STORM_{}", task.sig.ident.to_string().to_uppercase());
+        let notify = notify.map(|notify| quote!(let _ = #notify::spawn();));
+
+        let guard: Stmt = parse_quote!(
+            if #state.record(#limit) {
+                rtic::export::storm::mask(#device::interrupt::#binds);
+                #notify
+                return;
+            }
+        );
+        task.block.stmts.insert(0, guard);
+        spawns.push(parse_quote!(#name::spawn().unwrap();));
+
+        generated.push(Item::Static(parse_quote!(
+            static #state: rtic::export::storm::Storm = rtic::export::storm::Storm::new();
+        )));
+        generated.push(Item::Fn(parse_quote!(
+            #[task(priority = 1)]
+            async fn #name(_: #name::Context) {
+                let mut next = #monotonic::now();
+                loop {
+                    next = next + #window;
+                    #monotonic::delay_until(next).await;
+
+                    if #state.close_window(#limit) {
+                        #monotonic::delay(#cooldown).await;
+                        #state.reset();
+                        rtic::export::storm::unmask(#device::interrupt::#binds);
+                        next = #monotonic::now();
+                    }
+                }
+            }
+        )));
+    }
+
+    if !spawns.is_empty() {
+        let init = items.iter_mut().find_map(|item| match item {
+            Item::Fn(init) if init.attrs.iter().any(|attr| util::attr_eq(attr, "init")) => {
+                Some(init)
+            }
+            _ => None,
+        });
+
+        if let Some(init) = init {
+            init.block.stmts.splice(0..0, spawns);
+        }
+    }
+
+    items.extend(generated);
+
+    Ok(())
+}
+
+/// Removes `storm = (..)` from the arguments of a `#[task]`, also returns the `binds` ident
+fn take_storm(tokens: &mut TokenStream2) -> parse::Result<Option<(Storm, Option<Ident>)>> {
+    let trees: Vec<TokenTree> = tokens.clone().into_iter().collect();
+    let mut binds = None;
+    let mut storm = None;
+    let mut kept = vec![];
+    let mut i = 0;
+
+    while i < trees.len() {
+        let is_arg = |name: &str| {
+            matches!(&trees[i], TokenTree::Ident(ident) if ident == name)
+                && matches!(trees.get(i + 1), Some(TokenTree::Punct(p)) if p.as_char() == '=')
+        };
+
+        if is_arg("binds") {
+            if let Some(TokenTree::Ident(ident)) = trees.get(i + 2) {
+                binds = Some(ident.clone());
+            }
+        } else if is_arg("storm") {
+            let Some(TokenTree::Group(group)) = trees.get(i + 2) else {
+                return Err(parse::Error::new(
+                    trees[i].span(),
+                    "expected `storm = (limit = .., window = .., cooldown = ..)`",
+                ));
+            };
+
+            if storm.is_some() {
+                return Err(parse::Error::new(
+                    trees[i].span(),
+                    "argument appears more than once",
+                ));
+            }
+
+            storm = Some(parse_storm(group.stream(), group.span())?);
+
+            // Drop `storm = (..)` and its comma
+            i += 3;
+            if matches!(trees.get(i), Some(TokenTree::Punct(p)) if p.as_char() == ',') {
+                i += 1;
+            }
+            continue;
+        }
+
+        kept.push(trees[i].clone());
+        i += 1;
+    }
+
+    Ok(storm.map(|storm| {
+        *tokens = kept.into_iter().collect();
+        (storm, binds)
+    }))
+}
+
+fn parse_storm(tokens: TokenStream2, span: proc_macro2::Span) -> parse::Result<Storm> {
+    let args = Punctuated::<MetaNameValue, Token![,]>::parse_terminated.parse2(tokens)?;
+    let (mut limit, mut window, mut cooldown, mut monotonic, mut notify) =
+        (None, None, None, None, None);
+
+    for arg in args {
+        let path = |value: Expr| match value {
+            Expr::Path(path) => Ok(path.path),
+            value => Err(parse::Error::new(value.span(), "expected a path")),
+        };
+
+        let slot = match arg.path.get_ident().map(Ident::to_string).as_deref() {
+            Some("limit") => &mut limit,
+            Some("window") => &mut window,
+            Some("cooldown") => &mut cooldown,
+            Some("monotonic") => {
+                monotonic = Some(path(arg.value)?);
+                continue;
+            }
+            Some("notify") => {
+                notify = Some(path(arg.value)?);
+                continue;
+            }
+            _ => return Err(parse::Error::new(arg.path.span(), "unexpected argument")),
+        };
+
+        if slot.replace(arg.value).is_some() {
+            return Err(parse::Error::new(
+                arg.path.span(),
+                "argument appears more than once",
+            ));
+        }
+    }
+
+    let required = |value: Option<Expr>, name: &str| {
+        value.ok_or_else(|| parse::Error::new(span, format!("the `storm` guard needs a `{name}`")))
+    };
+
+    Ok(Storm {
+        limit: required(limit, "limit")?,
+        window: required(window, "window")?,
+        cooldown: required(cooldown, "cooldown")?,
+        monotonic: monotonic.unwrap_or_else(|| parse_quote!(Mono)),
+        notify,
+    })
+}
+
+/// Cortex-M exceptions, which are not behind the NVIC
+fn is_exception(name: &Ident) -> bool {
+    matches!(
+        &*name.to_string(),
+        "MemoryManagement"
+            | "BusFault"
+            | "UsageFault"
+            | "SecureFault"
+            | "SVCall"
+            | "DebugMonitor"
+            | "PendSV"
+            | "SysTick"
+    )
+}
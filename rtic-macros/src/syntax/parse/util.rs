@@ -1,14 +1,20 @@
+use proc_macro2::Span;
+#[cfg(any(feature = "nrf5340", feature = "esp32c6-lp-core", feature = "rp2040"))]
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::ToTokens;
 use syn::{
     bracketed,
-    parse::{self, ParseStream},
+    parse::{self, ParseStream, Parser},
     punctuated::Punctuated,
     spanned::Spanned,
-    Abi, AttrStyle, Attribute, Expr, ExprPath, FnArg, ForeignItemFn, Ident, ItemFn, Pat, PatType,
-    Path, PathArguments, ReturnType, Token, Type, Visibility,
+    Abi, AttrStyle, Attribute, Expr, ExprPath, FnArg, ForeignItemFn, Ident, ItemFn, Meta, Pat,
+    PatType, Path, PathArguments, ReturnType, Token, Type, Visibility,
 };
 
+use super::deadline;
+
 use crate::syntax::{
-    ast::{Access, Local, LocalResources, SharedResources, TaskLocal},
+    ast::{Access, CrossCore, Local, LocalResources, SharedResources, TaskLocal},
     Map,
 };
 
@@ -91,6 +97,184 @@ pub fn extract_lock_free(attrs: &mut Vec<Attribute>) -> parse::Result<bool> {
     }
 }
 
+pub fn extract_poison(attrs: &mut Vec<Attribute>) -> parse::Result<Option<Span>> {
+    if let Some(pos) = attrs.iter().position(|attr| attr_eq(attr, "poison")) {
+        Ok(Some(attrs.remove(pos).span()))
+    } else {
+        Ok(None)
+    }
+}
+
+/// `#[atomic]`, the atomic type for the type of the resource or an error
+pub fn extract_atomic(attrs: &mut Vec<Attribute>, ty: &Type) -> parse::Result<Option<Ident>> {
+    let Some(pos) = attrs.iter().position(|attr| attr_eq(attr, "atomic")) else {
+        return Ok(None);
+    };
+
+    let attr = attrs.remove(pos);
+    let atomic = match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.get_ident().and_then(|ident| {
+            Some(match &*ident.to_string() {
+                "bool" => "AtomicBool",
+                "u8" => "AtomicU8",
+                "u16" => "AtomicU16",
+                "u32" => "AtomicU32",
+                "usize" => "AtomicUsize",
+                "i8" => "AtomicI8",
+                "i16" => "AtomicI16",
+                "i32" => "AtomicI32",
+                "isize" => "AtomicIsize",
+                _ => return None,
+            })
+        }),
+        _ => None,
+    };
+
+    match atomic {
+        Some(atomic) => Ok(Some(Ident::new(atomic, attr.span()))),
+        None => Err(parse::Error::new(
+            ty.span(),
+            "`#[atomic]` resources must be a `bool` or an integer of at most 32 bits, e.g. `u32`",
+        )),
+    }
+}
+
+/// `#[cross_core(spinlock = N)]` or `#[cross_core(spinlock = N, core = C)]`, as written by the
+/// split of a multi-core application, see `crate::cores`
+pub fn extract_cross_core(attrs: &mut Vec<Attribute>) -> parse::Result<Option<CrossCore>> {
+    let Some(pos) = attrs.iter().position(|attr| attr_eq(attr, "cross_core")) else {
+        return Ok(None);
+    };
+
+    let attr = attrs.remove(pos);
+    let span = attr.span();
+    if let Meta::Path(_) = attr.meta {
+        return Err(parse::Error::new(
+            span,
+            "`#[cross_core]` resources are shared by the cores of a multi-core application, place \
+             its items on the cores with `core = N`",
+        ));
+    }
+
+    let mut cross_core = CrossCore {
+        spinlock: u8::MAX,
+        stored_by: None,
+    };
+    attr.parse_args_with(|input: ParseStream| {
+        while !input.is_empty() {
+            let ident: Ident = input.parse()?;
+            let _: Token![=] = input.parse()?;
+            let lit: syn::LitInt = input.parse()?;
+
+            match &*ident.to_string() {
+                "spinlock" => match lit.base10_parse::<u8>() {
+                    Ok(spinlock) if spinlock < 31 => cross_core.spinlock = spinlock,
+                    _ => {
+                        return Err(parse::Error::new(
+                            lit.span(),
+                            "the spinlocks are 0 to 30, 31 is used by the `critical-section` \
+                             implementation of the HAL",
+                        ))
+                    }
+                },
+                "core" => cross_core.stored_by = Some(lit.base10_parse()?),
+                _ => return Err(parse::Error::new(ident.span(), "expected `spinlock`")),
+            }
+
+            if !input.is_empty() {
+                let _: Token![,] = input.parse()?;
+            }
+        }
+
+        Ok(())
+    })?;
+
+    if cross_core.spinlock == u8::MAX {
+        return Err(parse::Error::new(span, "expected `#[cross_core(spinlock = N)]`"));
+    }
+
+    Ok(Some(cross_core))
+}
+
+/// `#[wcet = 5.micros()]`, in microseconds
+pub fn extract_wcet(attrs: &mut Vec<Attribute>) -> parse::Result<Option<u64>> {
+    let Some(pos) = attrs.iter().position(|attr| attr_eq(attr, "wcet")) else {
+        return Ok(None);
+    };
+
+    let attr = attrs.remove(pos);
+    let Meta::NameValue(nv) = &attr.meta else {
+        return Err(parse::Error::new(
+            attr.span(),
+            "expected `#[wcet = ..]`, e.g. `#[wcet = 5.micros()]`",
+        ));
+    };
+
+    deadline::parse
+        .parse2(nv.value.to_token_stream())
+        .map(Some)
+}
+
+/// `#[c_export]` or `#[c_export(priority = N)]`, the priority of the C callers, `0` by default
+pub fn extract_c_export(attrs: &mut Vec<Attribute>) -> parse::Result<Option<(u8, Span)>> {
+    let Some(pos) = attrs.iter().position(|attr| attr_eq(attr, "c_export")) else {
+        return Ok(None);
+    };
+
+    let attr = attrs.remove(pos);
+    let span = attr.span();
+    if let Meta::Path(_) = attr.meta {
+        return Ok(Some((0, span)));
+    }
+
+    let priority = attr
+        .parse_args_with(|input: ParseStream| {
+            let ident: Ident = input.parse()?;
+            if ident != "priority" {
+                return Err(parse::Error::new(ident.span(), "expected `priority`"));
+            }
+            let _: Token![=] = input.parse()?;
+            let lit: syn::LitInt = input.parse()?;
+            lit.base10_parse::<u8>()
+        })
+        .map_err(|_| {
+            parse::Error::new(
+                span,
+                "expected `#[c_export]` or `#[c_export(priority = N)]` with the highest priority of the C callers",
+            )
+        })?;
+
+    Ok(Some((priority, span)))
+}
+
+/// `#[depends_on(clocks, dma)]`, the resources to construct first
+pub fn extract_depends_on(attrs: &mut Vec<Attribute>) -> parse::Result<Vec<Ident>> {
+    let Some(pos) = attrs.iter().position(|attr| attr_eq(attr, "depends_on")) else {
+        return Ok(vec![]);
+    };
+
+    let attr = attrs.remove(pos);
+    let names = attr
+        .parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)
+        .map_err(|_| {
+            parse::Error::new(
+                attr.span(),
+                "expected `#[depends_on(..)]` with the names of resources, e.g. `#[depends_on(clocks)]`",
+            )
+        })?;
+
+    Ok(names.into_iter().collect())
+}
+
+pub fn extract_test_case(attrs: &mut Vec<Attribute>) -> bool {
+    if let Some(pos) = attrs.iter().position(|attr| attr_eq(attr, "test_case")) {
+        attrs.remove(pos);
+        true
+    } else {
+        false
+    }
+}
+
 pub fn parse_shared_resources(content: ParseStream<'_>) -> parse::Result<SharedResources> {
     let inner;
     bracketed!(inner in content);
@@ -385,3 +569,30 @@ pub fn type_is_unit(ty: &ReturnType) -> bool {
         true
     }
 }
+
+/// The arguments of an attribute, split at the top level commas
+#[cfg(any(feature = "nrf5340", feature = "esp32c6-lp-core", feature = "rp2040"))]
+pub fn split(tokens: TokenStream2) -> Vec<Vec<TokenTree>> {
+    let mut args = vec![vec![]];
+    for tree in tokens {
+        match &tree {
+            TokenTree::Punct(comma) if comma.as_char() == ',' => args.push(vec![]),
+            _ => args.last_mut().unwrap().push(tree),
+        }
+    }
+    args.retain(|arg| !arg.is_empty());
+    args
+}
+
+/// The value of `name = value` among the arguments
+#[cfg(any(feature = "nrf5340", feature = "esp32c6-lp-core", feature = "rp2040"))]
+pub fn find<'a>(args: &'a [Vec<TokenTree>], name: &str) -> Option<(usize, &'a [TokenTree])> {
+    args.iter().enumerate().find_map(|(i, arg)| match arg.as_slice() {
+        [TokenTree::Ident(ident), TokenTree::Punct(eq), value @ ..]
+            if ident == name && eq.as_char() == '=' =>
+        {
+            Some((i, value))
+        }
+        _ => None,
+    })
+}
@@ -0,0 +1,183 @@
+//! Token level scanning of the bodies of tasks
+//!
+//! Scanning tokens instead of the syntax tree also finds constructs inside macro invocations,
+//! e.g. a `spawn` within `defmt::unwrap!(..)`.
+
+use proc_macro2::{Delimiter, Span, TokenStream as TokenStream2, TokenTree};
+use quote::ToTokens;
+use syn::{Ident, Item, Stmt};
+
+use crate::syntax::ast::App;
+
+/// Macros that panic when reached, or when their condition does not hold
+const PANICKING_MACROS: &[&str] = &[
+    "panic",
+    "unreachable",
+    "todo",
+    "unimplemented",
+    "assert",
+    "assert_eq",
+    "assert_ne",
+];
+
+/// Methods that panic on `None` or `Err`
+const PANICKING_METHODS: &[&str] = &["unwrap", "expect", "unwrap_err", "expect_err"];
+
+/// Macros that wait in a loop, e.g. `nb::block!`
+const BLOCKING_MACROS: &[&str] = &["block"];
+
+/// Methods that wait in a loop: blocking delays and busy-wait flash erases, besides the
+/// `blocking_` methods of HALs
+const BLOCKING_METHODS: &[&str] = &[
+    "delay_ms",
+    "delay_us",
+    "delay_ns",
+    "erase",
+    "erase_page",
+    "erase_sector",
+];
+
+/// The bodies of the tasks of `app` that are defined within it, keyed by task name
+pub fn bodies(app: &App) -> Vec<(&Ident, TokenStream2)> {
+    fn tokens(stmts: &[Stmt]) -> TokenStream2 {
+        let mut ts = TokenStream2::new();
+        for stmt in stmts {
+            stmt.to_tokens(&mut ts);
+        }
+        ts
+    }
+
+    let mut bodies = vec![];
+
+    if !app.init.is_extern {
+        bodies.push((&app.init.name, tokens(&app.init.stmts)));
+    }
+
+    if let Some(idle) = app.idle.as_ref().filter(|idle| !idle.is_extern) {
+        bodies.push((&idle.name, tokens(&idle.stmts)));
+    }
+
+    for (name, task) in &app.hardware_tasks {
+        if !task.is_extern {
+            bodies.push((name, tokens(&task.stmts)));
+        }
+    }
+
+    for (name, task) in &app.software_tasks {
+        if !task.is_extern {
+            bodies.push((name, tokens(&task.stmts)));
+        }
+    }
+
+    bodies
+}
+
+/// The software tasks spawned in `tokens`, with the span of each spawn
+///
+/// A spawn is `task::spawn`, with any path in front of `task`. Calls to functions defined in the
+/// application module are followed, as far as the call graph can be determined from their names.
+pub fn spawns(app: &App, tokens: TokenStream2) -> Vec<Ident> {
+    let mut spawns = vec![];
+    collect_spawns(app, tokens, &mut vec![], &mut spawns);
+
+    spawns
+}
+
+fn collect_spawns(
+    app: &App,
+    tokens: TokenStream2,
+    visited: &mut Vec<Ident>,
+    spawns: &mut Vec<Ident>,
+) {
+    let mut calls = vec![];
+
+    scan(tokens, &mut |window| match window {
+        [TokenTree::Ident(task), TokenTree::Punct(a), TokenTree::Punct(b), TokenTree::Ident(spawn), ..]
+            if a.as_char() == ':'
+                && b.as_char() == ':'
+                && spawn == "spawn"
+                && app.software_tasks.contains_key(task) =>
+        {
+            spawns.push(task.clone());
+        }
+        [TokenTree::Ident(function), TokenTree::Group(args), ..]
+            if args.delimiter() == Delimiter::Parenthesis =>
+        {
+            calls.push(function.clone());
+        }
+        _ => {}
+    });
+
+    for function in calls {
+        if visited.contains(&function) {
+            continue;
+        }
+
+        let body = app.user_code.iter().find_map(|item| match item {
+            Item::Fn(f) if f.sig.ident == function => Some(f.block.to_token_stream()),
+            _ => None,
+        });
+
+        if let Some(body) = body {
+            visited.push(function);
+            collect_spawns(app, body, visited, spawns);
+        }
+    }
+}
+
+/// The constructs in `tokens` that can panic, with their span
+pub fn panics(tokens: TokenStream2) -> Vec<(String, Span)> {
+    let mut panics = vec![];
+
+    scan(tokens, &mut |window| match window {
+        [TokenTree::Ident(name), TokenTree::Punct(bang), ..]
+            if bang.as_char() == '!' && PANICKING_MACROS.iter().any(|m| name == m) =>
+        {
+            panics.push((format!("{name}!"), name.span()));
+        }
+        [TokenTree::Punct(dot), TokenTree::Ident(name), TokenTree::Group(_), ..]
+            if dot.as_char() == '.' && PANICKING_METHODS.iter().any(|m| name == m) =>
+        {
+            panics.push((format!(".{name}()"), name.span()));
+        }
+        _ => {}
+    });
+
+    panics
+}
+
+/// The calls to known blocking helpers in `tokens`, with their span
+pub fn blocking(tokens: TokenStream2) -> Vec<(String, Span)> {
+    let mut blocking = vec![];
+
+    scan(tokens, &mut |window| match window {
+        [TokenTree::Ident(name), TokenTree::Punct(bang), ..]
+            if bang.as_char() == '!' && BLOCKING_MACROS.iter().any(|m| name == m) =>
+        {
+            blocking.push((format!("{name}!"), name.span()));
+        }
+        [TokenTree::Punct(dot), TokenTree::Ident(name), TokenTree::Group(_), ..]
+            if dot.as_char() == '.'
+                && (BLOCKING_METHODS.iter().any(|m| name == m)
+                    || name.to_string().starts_with("blocking_")) =>
+        {
+            blocking.push((format!(".{name}()"), name.span()));
+        }
+        _ => {}
+    });
+
+    blocking
+}
+
+/// Calls `f` with every suffix of every token sequence in `tokens`, recursing into groups
+fn scan(tokens: TokenStream2, f: &mut impl FnMut(&[TokenTree])) {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+
+    for i in 0..tokens.len() {
+        f(&tokens[i..]);
+
+        if let TokenTree::Group(group) = &tokens[i] {
+            scan(group.stream(), f);
+        }
+    }
+}
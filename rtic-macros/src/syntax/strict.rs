@@ -0,0 +1,141 @@
+//! The `strict` application mode
+//!
+//! Rejects constructs that are problematic for certification and produces a report justifying
+//! that the application is free of them:
+//!
+//! - dynamic spawn: spawns the analysis can not attribute to a task, i.e. from `extern` tasks or
+//!   from code outside of the tasks
+//! - panics in tasks, as there is no way to recover from them
+//! - recursion through cycles of tasks spawning each other
+
+use crate::syntax::{analyze::Analysis, ast::App, scan};
+use quote::ToTokens;
+use syn::{parse, Ident};
+
+/// Checks the rules of the strict mode
+pub fn app(app: &App, analysis: &Analysis) -> parse::Result<()> {
+    // Dynamic spawn: every spawn must be attributable to a task of the application
+    if let Some(name) = extern_tasks(app).into_iter().next() {
+        return Err(parse::Error::new(
+            name.span(),
+            "strict mode: `extern` tasks are not allowed, their spawns and panics can not be analyzed",
+        ));
+    }
+
+    for item in &app.user_code {
+        if let Some(task) = scan::spawns(app, item.to_token_stream()).first() {
+            return Err(parse::Error::new(
+                task.span(),
+                "strict mode: software tasks can only be spawned from within tasks",
+            ));
+        }
+    }
+
+    // Panics in tasks, `init` runs before any task and may panic
+    for (name, body) in scan::bodies(app) {
+        if *name == app.init.name {
+            continue;
+        }
+
+        if let Some((construct, span)) = scan::panics(body).into_iter().next() {
+            return Err(parse::Error::new(
+                span,
+                format!("strict mode: `{construct}` can panic, tasks must handle all errors"),
+            ));
+        }
+    }
+
+    // Recursion via spawn
+    if let Some(cycle) = analysis.spawn_cycles().into_iter().next() {
+        let path = cycle
+            .iter()
+            .map(|task| format!("`{task}`"))
+            .collect::<Vec<_>>()
+            .join(" -> ");
+
+        return Err(parse::Error::new(
+            cycle.last().expect("UNREACHABLE").span(),
+            format!("strict mode: tasks spawn each other in a cycle: {path}"),
+        ));
+    }
+
+    Ok(())
+}
+
+/// The justification report for an application that passed [`app`]
+pub fn report(app: &App, analysis: &Analysis) -> String {
+    let tasks = scan::bodies(app)
+        .into_iter()
+        .filter(|(name, _)| **name != app.init.name)
+        .map(|(name, _)| format!("`{name}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut lines = vec![
+        format!("# Strict mode report of the RTIC application `{}`", app.name),
+        String::new(),
+        "Generated by `#[rtic::app(strict = true)]`; the application compiles only if all rules hold.".to_string(),
+        String::new(),
+        "## No dynamic spawn".to_string(),
+        String::new(),
+        "All tasks are defined within the application, none is `extern`, and no code outside of the tasks spawns a task. Every spawn is attributed to a task in the spawn graph below.".to_string(),
+        String::new(),
+        "## No panics in tasks".to_string(),
+        String::new(),
+        format!("The tasks {tasks} contain no `panic!`, `unreachable!`, `todo!`, `unimplemented!`, `assert!`, `assert_eq!` or `assert_ne!` invocations and no calls to `unwrap`, `expect`, `unwrap_err` or `expect_err`. `init` is exempt as it runs before any task. Panics from indexing, arithmetic overflow checks and called functions are out of scope of this check."),
+        String::new(),
+        "## No recursion via spawn".to_string(),
+        String::new(),
+        "The spawn graph is acyclic:".to_string(),
+        String::new(),
+    ];
+
+    for spawner in analysis.spawns.keys() {
+        let spawned = analysis.spawned_by(spawner);
+
+        if !spawned.is_empty() {
+            lines.push(format!(
+                "- `{spawner}` spawns {}",
+                spawned
+                    .iter()
+                    .map(|t| format!("`{t}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+
+    if analysis.spawns.values().all(Vec::is_empty) {
+        lines.push("- no task spawns another task".to_string());
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn extern_tasks(app: &App) -> Vec<&Ident> {
+    let mut names = vec![];
+
+    if app.init.is_extern {
+        names.push(&app.init.name);
+    }
+
+    if let Some(idle) = app.idle.as_ref().filter(|idle| idle.is_extern) {
+        names.push(&idle.name);
+    }
+
+    names.extend(
+        app.hardware_tasks
+            .iter()
+            .filter(|(_, task)| task.is_extern)
+            .map(|(name, _)| name),
+    );
+    names.extend(
+        app.software_tasks
+            .iter()
+            .filter(|(_, task)| task.is_extern)
+            .map(|(name, _)| name),
+    );
+
+    names
+}
@@ -0,0 +1,65 @@
+//! `#[task_interface]`, a trait of operations a library spawns as tasks of the application
+//!
+//! Each method of the trait is written as the signature of the task, `fn received(len: usize);`,
+//! and becomes the signature of its `spawn`, `fn received(len: usize) -> Result<(), usize>;`. The
+//! application implements the trait with its tasks, `#[task(implements = driver::Events)]`, on the
+//! generated `Tasks` type, which the library takes as a type parameter.
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse, spanned::Spanned, FnArg, ItemTrait, ReturnType, TraitItem};
+
+pub fn expand(args: TokenStream2, input: TokenStream2) -> parse::Result<TokenStream2> {
+    if !args.is_empty() {
+        return Err(parse::Error::new(
+            args.span(),
+            "`#[task_interface]` takes no arguments",
+        ));
+    }
+
+    let mut item: ItemTrait = syn::parse2(input)?;
+
+    for trait_item in &mut item.items {
+        let TraitItem::Fn(method) = trait_item else {
+            return Err(parse::Error::new(
+                trait_item.span(),
+                "a task interface only contains the tasks, as `fn name(args..);`",
+            ));
+        };
+        let sig = &mut method.sig;
+
+        if method.default.is_some()
+            || sig.asyncness.is_some()
+            || !sig.generics.params.is_empty()
+            || sig.output != ReturnType::Default
+        {
+            return Err(parse::Error::new(
+                sig.span(),
+                "a task is declared as `fn name(args..);`, without a body, `async`, generics or return type",
+            ));
+        }
+
+        let mut inputs = vec![];
+        for input in &sig.inputs {
+            match input {
+                FnArg::Typed(pat_type) => inputs.push(&pat_type.ty),
+                FnArg::Receiver(receiver) => {
+                    return Err(parse::Error::new(
+                        receiver.span(),
+                        "tasks are spawned without an instance, remove `self`",
+                    ))
+                }
+            }
+        }
+
+        // The payload given back when the task is already running, as with `spawn`
+        let payload = match &inputs[..] {
+            [] => quote!(()),
+            [ty] => quote!(#ty),
+            tys => quote!((#(#tys,)*)),
+        };
+        sig.output = syn::parse_quote!(-> ::core::result::Result<(), #payload>);
+    }
+
+    Ok(quote!(#item))
+}
@@ -0,0 +1,31 @@
+//! Interrupt vector model of an application, `rtic-vector-model-<crate>.txt`
+//!
+//! Lists the interrupts the application takes: those bound by its hardware tasks and its
+//! dispatchers. Which other interrupts the device has, and which of them the monotonics and the
+//! HAL take, is only known after linking, `cargo xtask vectors` combines the two.
+
+use crate::analyze::Analysis;
+use crate::syntax::ast::App;
+use std::fmt::Write;
+
+/// The model as `<interrupt> <kind> <priority> <task>` lines, `-` for the task of a dispatcher
+pub fn model(app: &App, analysis: &Analysis) -> String {
+    let name = &app.name;
+
+    let mut s = String::new();
+    writeln!(s, "# Interrupt vector model of the RTIC application `{name}`").ok();
+    writeln!(s, "# Generated by `#[rtic::app]`, changes are overwritten").ok();
+    writeln!(s, "# <interrupt> <kind> <priority> <task>").ok();
+
+    for (task, hardware_task) in &app.hardware_tasks {
+        let binds = &hardware_task.args.binds;
+        let priority = hardware_task.args.priority;
+        writeln!(s, "{binds} task {priority} {task}").ok();
+    }
+
+    for (level, (interrupt, _)) in &analysis.interrupts {
+        writeln!(s, "{interrupt} dispatcher {level} -").ok();
+    }
+
+    s
+}
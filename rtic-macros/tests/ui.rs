@@ -4,4 +4,14 @@ use trybuild::TestCases;
 fn ui() {
     let t = TestCases::new();
     t.compile_fail("ui/*.rs");
+
+    // The priorities of the Cortex-M BASEPRI backend depend on the bits the device implements
+    #[cfg(feature = "cortex-m-basepri")]
+    t.compile_fail("ui/cortex-m-basepri/*.rs");
+
+    // Options rejected by all backends but the ones supporting them
+    #[cfg(not(feature = "riscv-esp32c6"))]
+    t.compile_fail("ui/backend/task-trigger-backend.rs");
+    #[cfg(not(feature = "rp2040"))]
+    t.compile_fail("ui/backend/task-cross-core-no-rp2040.rs");
 }
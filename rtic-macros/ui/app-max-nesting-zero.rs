@@ -0,0 +1,4 @@
+#![no_main]
+
+#[rtic_macros::mock_app(device = mock, max_nesting = 0)]
+mod app {}
@@ -0,0 +1,25 @@
+#![no_main]
+
+#[rtic_macros::mock_app(device = mock, dispatchers = [EXTI0], blocking_priority = 1)]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 1)]
+    async fn background(_: background::Context) {
+        Delay.delay_ms(10);
+    }
+
+    #[task(binds = UART0, priority = 2)]
+    fn uart(_: uart::Context) {
+        Delay.delay_ms(10);
+    }
+}
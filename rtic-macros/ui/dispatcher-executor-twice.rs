@@ -0,0 +1,4 @@
+#![no_main]
+
+#[rtic_macros::mock_app(device = mock, dispatchers = [#[executor(A)] #[executor(B)] EXTI0])]
+mod app {}
@@ -0,0 +1,4 @@
+#![no_main]
+
+#[rtic_macros::mock_app(device = mock, dispatchers = [EXTI0 => 3..=2])]
+mod app {}
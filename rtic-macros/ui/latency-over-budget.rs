@@ -0,0 +1,32 @@
+#![no_main]
+
+#[rtic_macros::mock_app(device = mock, dispatchers = [EXTI0])]
+mod app {
+    #[shared]
+    struct Shared {
+        #[wcet = 5.micros()]
+        short: u32,
+        #[wcet = 30.micros()]
+        buffer: [u8; 16],
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (
+            Shared {
+                short: 0,
+                buffer: [0; 16],
+            },
+            Local {},
+        )
+    }
+
+    #[task(priority = 1, shared = [short, buffer])]
+    async fn log(_: log::Context) {}
+
+    #[task(binds = UART0, priority = 2, shared = [short, buffer], max_latency = 20.micros())]
+    fn uart0(_: uart0::Context) {}
+}
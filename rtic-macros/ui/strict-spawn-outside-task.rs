@@ -0,0 +1,22 @@
+#![no_main]
+
+#[rtic_macros::mock_app(device = mock, dispatchers = [EXTI0], strict = true)]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (Shared {}, Local {})
+    }
+
+    fn helper() {
+        foo::spawn().ok();
+    }
+
+    #[task]
+    async fn foo(_: foo::Context) {}
+}
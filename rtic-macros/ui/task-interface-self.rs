@@ -0,0 +1,8 @@
+#![no_main]
+
+#[rtic_macros::task_interface]
+pub trait Events {
+    fn received(&self, len: usize);
+}
+
+fn main() {}
@@ -0,0 +1,212 @@
+//! [`Monotonic`](rtic_time::Monotonic) based on the DWT cycle counter of Cortex-M, with a
+//! resolution of a single core clock cycle.
+//!
+//! The 32 bit `CYCCNT` counter is extended to 64 bits in software, and SysTick is used as the
+//! compare timer: it fires at the next deadline, and at least every 2^24 cycles in between so
+//! that no wrap of `CYCCNT` goes unnoticed. The instants are therefore good for profiling as
+//! well as delays, at the cost of taking over the SysTick interrupt.
+//!
+//! The DWT cycle counter is only available on Armv7-M and up (Cortex-M3, M4, M7, M33, ..), and
+//! this monotonic can not be used together with the [`systick`](crate::systick) one. The cycle
+//! counter must not be written by anything else once the monotonic is started, note that some
+//! debuggers reset it when attaching.
+//!
+//! # Example
+//!
+//! ```
+//! use rtic_monotonics::dwt::prelude::*;
+//! dwt_monotonic!(Mono, 64_000_000);
+//!
+//! fn init() {
+//!     # // These are normally provided by the selected PAC
+//!     # let dcb = unsafe { core::mem::transmute(()) };
+//!     # let dwt = unsafe { core::mem::transmute(()) };
+//!     # let systick = unsafe { core::mem::transmute(()) };
+//!     #
+//!     // Start the monotonic
+//!     Mono::start(dcb, dwt, systick, 64_000_000);
+//! }
+//!
+//! async fn usage() {
+//!     loop {
+//!          // Use the monotonic
+//!          let start = Mono::now();
+//!          Mono::delay(250.micros()).await;
+//!          let cycles = (Mono::now() - start).ticks();
+//!     }
+//! }
+//! ```
+
+/// Common definitions and traits for using the DWT monotonic
+pub mod prelude {
+    pub use crate::dwt_monotonic;
+
+    pub use crate::Monotonic;
+
+    pub use fugit::{self, ExtU64, ExtU64Ceil};
+}
+
+pub use cortex_m::peripheral::{DCB, DWT, SYST};
+
+use portable_atomic::{AtomicU32, Ordering};
+use rtic_time::timer_queue::TimerQueue;
+
+use crate::TimerQueueBackend;
+
+/// The widest SysTick period, bounding the time between two reads of `CYCCNT`
+const MAX_RELOAD: u32 = 0x00ff_ffff;
+
+/// The shortest SysTick period used for a deadline, so a close one does not keep the core in
+/// the interrupt
+const MIN_RELOAD: u32 = 64;
+
+/// The wraps of `CYCCNT` seen so far, the upper half of the 64 bit count
+static HIGH: AtomicU32 = AtomicU32::new(0);
+
+/// The value of `CYCCNT` at the last read, a lower value on the next read is a wrap
+static LAST: AtomicU32 = AtomicU32::new(0);
+
+static DWT_TIMER_QUEUE: TimerQueue<DwtBackend> = TimerQueue::new();
+
+/// DWT cycle counter based [`TimerQueueBackend`].
+pub struct DwtBackend;
+
+impl DwtBackend {
+    /// Starts the monotonic timer.
+    ///
+    /// **Do not use this function directly.**
+    ///
+    /// Use the prelude macros instead.
+    pub fn _start(mut dcb: DCB, mut dwt: DWT, mut systick: SYST, sysclk: u32, timer_hz: u32) {
+        assert_eq!(
+            sysclk, timer_hz,
+            "the DWT monotonic ticks at the core clock, its rate must be equal to sysclk"
+        );
+
+        dcb.enable_trace();
+        DWT::unlock();
+        dwt.set_cycle_count(0);
+        dwt.enable_cycle_counter();
+
+        systick.disable_counter();
+        systick.set_clock_source(cortex_m::peripheral::syst::SystClkSource::Core);
+        systick.set_reload(MAX_RELOAD);
+        systick.clear_current();
+        systick.enable_interrupt();
+        systick.enable_counter();
+
+        DWT_TIMER_QUEUE.initialize(DwtBackend {});
+    }
+
+    fn systick() -> SYST {
+        unsafe { core::mem::transmute::<(), SYST>(()) }
+    }
+
+    /// Restarts SysTick to fire after `cycles`
+    fn set_period(cycles: u32) {
+        let mut systick = Self::systick();
+        systick.set_reload(cycles.clamp(MIN_RELOAD, MAX_RELOAD) - 1);
+        systick.clear_current();
+    }
+}
+
+impl TimerQueueBackend for DwtBackend {
+    type Ticks = u64;
+
+    fn now() -> Self::Ticks {
+        cortex_m::interrupt::free(|_| {
+            let low = DWT::cycle_count();
+            let high = if low < LAST.load(Ordering::Relaxed) {
+                HIGH.fetch_add(1, Ordering::Relaxed) + 1
+            } else {
+                HIGH.load(Ordering::Relaxed)
+            };
+            LAST.store(low, Ordering::Relaxed);
+
+            (u64::from(high) << 32) | u64::from(low)
+        })
+    }
+
+    fn set_compare(instant: Self::Ticks) {
+        let cycles = instant.saturating_sub(Self::now());
+        Self::set_period(cycles.min(u64::from(MAX_RELOAD)) as u32);
+    }
+
+    fn clear_compare_flag() {
+        // NOOP with SysTick interrupt
+    }
+
+    fn pend_interrupt() {
+        cortex_m::peripheral::SCB::set_pendst();
+    }
+
+    fn on_interrupt() {
+        // Fold in a wrap of `CYCCNT`, and fire again before the next one when the queue is empty.
+        Self::now();
+        Self::set_period(MAX_RELOAD);
+    }
+
+    fn timer_queue() -> &'static TimerQueue<Self> {
+        &DWT_TIMER_QUEUE
+    }
+}
+
+/// Create a DWT cycle counter based monotonic and register the SysTick interrupt for it.
+///
+/// See [`crate::dwt`] for more details.
+///
+/// # Arguments
+///
+/// * `name` - The name that the monotonic type will have.
+/// * `core_clock_hz` - The frequency of the core clock, which is the tick rate.
+#[macro_export]
+macro_rules! dwt_monotonic {
+    ($name:ident, $core_clock_hz:expr) => {
+        /// A `Monotonic` based on the DWT cycle counter.
+        pub struct $name;
+
+        impl $name {
+            /// Starts the `Monotonic`.
+            ///
+            /// The `sysclk` parameter is the speed of the core clock. This value should come from
+            /// the clock generation function of the used HAL.
+            ///
+            /// Panics if `sysclk` differs from the core clock given to the macro.
+            ///
+            /// This method must be called only once.
+            pub fn start(
+                dcb: $crate::dwt::DCB,
+                dwt: $crate::dwt::DWT,
+                systick: $crate::dwt::SYST,
+                sysclk: u32,
+            ) {
+                #[no_mangle]
+                #[allow(non_snake_case)]
+                unsafe extern "C" fn SysTick() {
+                    use $crate::TimerQueueBackend;
+                    $crate::dwt::DwtBackend::timer_queue().on_monotonic_interrupt();
+                }
+                $crate::__internal_mark_monotonic_interrupt!(SysTick);
+
+                $crate::dwt::DwtBackend::_start(dcb, dwt, systick, sysclk, $core_clock_hz);
+            }
+        }
+
+        impl $crate::TimerQueueBasedMonotonic for $name {
+            type Backend = $crate::dwt::DwtBackend;
+            type Instant = $crate::fugit::Instant<
+                <Self::Backend as $crate::TimerQueueBackend>::Ticks,
+                1,
+                { $core_clock_hz },
+            >;
+            type Duration = $crate::fugit::Duration<
+                <Self::Backend as $crate::TimerQueueBackend>::Ticks,
+                1,
+                { $core_clock_hz },
+            >;
+        }
+
+        $crate::rtic_time::impl_embedded_hal_delay_fugit!($name);
+        $crate::rtic_time::impl_embedded_hal_async_delay_fugit!($name);
+    };
+}
@@ -55,6 +55,8 @@ macro_rules! __internal_create_imxrt_timer_interrupt {
             use $crate::TimerQueueBackend;
             $crate::imxrt::$mono_backend::timer_queue().on_monotonic_interrupt();
         }
+
+        $crate::__internal_mark_monotonic_interrupt!($timer);
     };
 }
 
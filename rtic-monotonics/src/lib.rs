@@ -6,6 +6,15 @@
 //! # Cortex-M Systick
 //! The `systick` monotonic works on all cortex-M parts, and requires that the feature `cortex-m-systick` is enabled.
 //!
+//! # Cortex-M DWT
+//! The `dwt` monotonic counts core clock cycles with the DWT cycle counter, extended to 64 bits,
+//! for timestamps and delays of single cycle resolution. It works on Armv7-M parts and up, uses
+//! SysTick for its interrupt and requires that the feature `cortex-m-dwt` is enabled.
+//!
+//! # wasm
+//! The `wasm` monotonic is based on `performance.now()` of the host and works with the wasm
+//! backend of RTIC, it requires that the feature `wasm` is enabled.
+//!
 //! # RP2040
 //! The RP2040 monotonics require that the `rp2040` feature is enabled.
 //!
@@ -21,7 +30,7 @@
 //! `Available on crate features X only` tag are available on any `nrf52*` feature.
 //!
 // To build these docs correctly:
-// RUSTFLAGS="--cfg docsrs" cargo +nightly doc --features thumbv7-backend,cortex-m-systick,rp2040,nrf52840,imxrt_gpt1,imxrt_gpt2,imxrt-ral/imxrt1011,stm32h725ag,stm32_tim2,stm32_tim3,stm32_tim4,stm32_tim5,stm32_tim15
+// RUSTFLAGS="--cfg docsrs" cargo +nightly doc --features thumbv7-backend,cortex-m-systick,cortex-m-dwt,rp2040,nrf52840,imxrt_gpt1,imxrt_gpt2,imxrt-ral/imxrt1011,stm32h725ag,stm32_tim2,stm32_tim3,stm32_tim4,stm32_tim5,stm32_tim15
 
 #![no_std]
 #![deny(missing_docs)]
@@ -37,6 +46,12 @@ pub use rtic_time::{
 #[cfg(feature = "cortex-m-systick")]
 pub mod systick;
 
+#[cfg(feature = "cortex-m-dwt")]
+pub mod dwt;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 #[cfg(feature = "rp2040")]
 pub mod rp2040;
 
@@ -60,6 +75,20 @@ pub mod nrf;
 #[cfg(stm32)]
 pub mod stm32;
 
+/// Marks `$interrupt` as taken by a monotonic, for the vector report of `cargo xtask vectors`
+///
+/// Expands to statements, used in the `start` of the monotonic next to its handler.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __internal_mark_monotonic_interrupt {
+    ($interrupt:ident) => {
+        #[export_name = concat!("__rtic_monotonic_", stringify!($interrupt))]
+        static MONOTONIC_INTERRUPT: u8 = 0;
+        // Keeps the marker in the binary, `start` runs once
+        ::core::hint::black_box(&MONOTONIC_INTERRUPT);
+    };
+}
+
 #[allow(dead_code)]
 pub(crate) const fn cortex_logical2hw(logical: u8, nvic_prio_bits: u8) -> u8 {
     ((1 << nvic_prio_bits) - logical) << (8 - nvic_prio_bits)
@@ -77,6 +77,8 @@ macro_rules! __internal_create_nrf_rtc_interrupt {
             use $crate::TimerQueueBackend;
             $crate::nrf::rtc::$mono_backend::timer_queue().on_monotonic_interrupt();
         }
+
+        $crate::__internal_mark_monotonic_interrupt!($rtc);
     };
 }
 
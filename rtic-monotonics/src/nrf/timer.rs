@@ -87,6 +87,8 @@ macro_rules! __internal_create_nrf_timer_interrupt {
             use $crate::TimerQueueBackend;
             $crate::nrf::timer::$mono_backend::timer_queue().on_monotonic_interrupt();
         }
+
+        $crate::__internal_mark_monotonic_interrupt!($timer);
     };
 }
 
@@ -143,6 +143,7 @@ macro_rules! rp2040_timer_monotonic {
                     use $crate::TimerQueueBackend;
                     $crate::rp2040::TimerBackend::timer_queue().on_monotonic_interrupt();
                 }
+                $crate::__internal_mark_monotonic_interrupt!(TIMER_IRQ_0);
 
                 $crate::rp2040::TimerBackend::_start(timer, resets);
             }
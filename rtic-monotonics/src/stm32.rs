@@ -76,6 +76,8 @@ macro_rules! __internal_create_stm32_timer_interrupt {
             use $crate::TimerQueueBackend;
             $crate::stm32::$mono_backend::timer_queue().on_monotonic_interrupt();
         }
+
+        $crate::__internal_mark_monotonic_interrupt!($interrupt_name);
     };
 }
 
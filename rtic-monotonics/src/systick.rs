@@ -167,6 +167,7 @@ macro_rules! systick_monotonic {
                     use $crate::TimerQueueBackend;
                     $crate::systick::SystickBackend::timer_queue().on_monotonic_interrupt();
                 }
+                $crate::__internal_mark_monotonic_interrupt!(SysTick);
 
                 $crate::systick::SystickBackend::_start(systick, sysclk, $tick_rate_hz);
             }
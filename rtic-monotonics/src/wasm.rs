@@ -0,0 +1,153 @@
+//! [`Monotonic`](rtic_time::Monotonic) for the wasm backend of RTIC, based on `performance.now()`
+//! of the host.
+//!
+//! The monotonic ticks at 1 MHz. The host provides two imports in the `rtic` module of the
+//! WebAssembly instance, next to the `wait` of the backend:
+//!
+//! - `now_ms()`, returning `performance.now()`,
+//! - `set_timer(at_ms)`, asking for the exported `rtic_monotonic_timer` to be called from `wait`
+//!   once `performance.now()` reaches `at_ms`, replacing the previous request.
+//!
+//! ```js
+//! let deadline = Infinity;
+//! const rtic = {
+//!     now_ms: () => performance.now(),
+//!     set_timer: (at_ms) => { deadline = at_ms; },
+//!     wait: () => {
+//!         // Wait for an event of the host until `deadline`, e.g. with `Atomics.wait`, ...
+//!         if (performance.now() >= deadline) {
+//!             deadline = Infinity;
+//!             instance.exports.rtic_monotonic_timer();
+//!         }
+//!     },
+//! };
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! use rtic_monotonics::wasm::prelude::*;
+//! wasm_monotonic!(Mono);
+//!
+//! fn init() {
+//!     // Start the monotonic
+//!     Mono::start();
+//! }
+//!
+//! async fn usage() {
+//!     loop {
+//!          // Use the monotonic
+//!          let timestamp = Mono::now();
+//!          Mono::delay(100.millis()).await;
+//!     }
+//! }
+//! ```
+
+/// Common definitions and traits for using the wasm monotonic
+pub mod prelude {
+    pub use crate::wasm_monotonic;
+
+    pub use crate::Monotonic;
+
+    pub use fugit::{self, ExtU64, ExtU64Ceil};
+}
+
+use rtic_time::timer_queue::TimerQueue;
+
+use crate::TimerQueueBackend;
+
+#[link(wasm_import_module = "rtic")]
+extern "C" {
+    /// `performance.now()` of the host, in milliseconds
+    fn now_ms() -> f64;
+
+    /// Asks the host to call `rtic_monotonic_timer` at `at_ms`, in the time of `now_ms`
+    fn set_timer(at_ms: f64);
+}
+
+static WASM_TIMER_QUEUE: TimerQueue<WasmBackend> = TimerQueue::new();
+
+/// `performance.now()` based [`TimerQueueBackend`].
+pub struct WasmBackend;
+
+impl WasmBackend {
+    /// Starts the monotonic timer.
+    ///
+    /// **Do not use this function directly.**
+    ///
+    /// Use the prelude macros instead.
+    pub fn _start() {
+        WASM_TIMER_QUEUE.initialize(WasmBackend {});
+    }
+}
+
+impl TimerQueueBackend for WasmBackend {
+    type Ticks = u64;
+
+    fn now() -> Self::Ticks {
+        // SAFETY: provided by the host, see the module documentation
+        (unsafe { now_ms() } * 1_000.0) as u64
+    }
+
+    fn set_compare(instant: Self::Ticks) {
+        // SAFETY: as in `now`
+        unsafe { set_timer(instant as f64 / 1_000.0) };
+    }
+
+    fn clear_compare_flag() {
+        // NOOP, the host calls the handler once per `set_timer`
+    }
+
+    fn pend_interrupt() {
+        // A time in the past, the host calls the handler from its next `wait`
+        // SAFETY: as in `now`
+        unsafe { set_timer(0.0) };
+    }
+
+    fn timer_queue() -> &'static TimerQueue<Self> {
+        &WASM_TIMER_QUEUE
+    }
+}
+
+/// Create a `performance.now()` based monotonic and export the `rtic_monotonic_timer` handler
+/// for it.
+///
+/// See [`crate::wasm`] for more details.
+///
+/// # Arguments
+///
+/// * `name` - The name that the monotonic type will have.
+#[macro_export]
+macro_rules! wasm_monotonic {
+    ($name:ident) => {
+        /// A `Monotonic` based on `performance.now()` of the host.
+        pub struct $name;
+
+        impl $name {
+            /// Starts the `Monotonic`.
+            ///
+            /// This method must be called only once.
+            pub fn start() {
+                #[no_mangle]
+                extern "C" fn rtic_monotonic_timer() {
+                    use $crate::TimerQueueBackend;
+                    // SAFETY: called by the host from the `wait` of the backend
+                    unsafe {
+                        $crate::wasm::WasmBackend::timer_queue().on_monotonic_interrupt();
+                    }
+                }
+
+                $crate::wasm::WasmBackend::_start();
+            }
+        }
+
+        impl $crate::TimerQueueBasedMonotonic for $name {
+            type Backend = $crate::wasm::WasmBackend;
+            type Instant = $crate::fugit::Instant<u64, 1, 1_000_000>;
+            type Duration = $crate::fugit::Duration<u64, 1, 1_000_000>;
+        }
+
+        $crate::rtic_time::impl_embedded_hal_delay_fugit!($name);
+        $crate::rtic_time::impl_embedded_hal_async_delay_fugit!($name);
+    };
+}
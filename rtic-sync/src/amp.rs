@@ -0,0 +1,443 @@
+//! A channel between the cores of an asymmetric multiprocessing device, e.g. the Cortex-M7 and
+//! the Cortex-M4 of a dual core STM32H7, in a RAM region both cores reach.
+//!
+//! Each core runs its own image, both declare the same [`Channel`] as a `static` placed by their
+//! linker scripts at the same address of the shared region. Each image only takes its own end of
+//! the channel, the [`Sender`] on one core and the [`Receiver`] on the other. Nothing but the
+//! indices and the messages is read by the other core, so the messages are [`Copy`] and the
+//! layout is `repr(C)`, identical in both images.
+//!
+//! A core with a data cache keeps its view of the region coherent through the [`SharedMemory`]
+//! it passes as `M`, which cleans the lines it wrote and invalidates the lines it is about to read.
+//! The same trait signals the other core after each send and receive, e.g. with a hardware
+//! semaphore interrupt, from which the other core calls [`Channel::wake_receiver`] or
+//! [`Channel::wake_sender`] to wake its end.
+//!
+//! ```ignore
+//! struct M7;
+//!
+//! impl SharedMemory for M7 {
+//!     fn clean(addr: *const u8, len: usize) {
+//!         // SAFETY: the lines only hold data of the channel
+//!         unsafe { SCB::steal().clean_dcache_by_address(addr as usize, len) };
+//!     }
+//!
+//!     fn invalidate(addr: *const u8, len: usize) {
+//!         // SAFETY: the lines only hold data of the channel, written by the other core
+//!         unsafe { SCB::steal().invalidate_dcache_by_address(addr as usize, len) };
+//!     }
+//!
+//!     fn notify() {
+//!         // Take and release a hardware semaphore, raising the HSEM interrupt of the M4
+//!     }
+//! }
+//!
+//! // Both images, the M4 one with its own `SharedMemory`, e.g. `NoCache`
+//! #[link_section = ".sram4"]
+//! static TO_M4: Channel<Sample, M7, 8> = Channel::new();
+//!
+//! // M7, before releasing the M4
+//! unsafe { TO_M4.reset() };
+//! let mut sender = unsafe { TO_M4.sender() };
+//! sender.send(sample).await;
+//!
+//! // M4, in the task bound to the HSEM interrupt
+//! TO_M4.wake_receiver();
+//!
+//! // M4
+//! let mut receiver = unsafe { TO_M4.receiver() };
+//! let sample = receiver.recv().await;
+//! ```
+//!
+//! The indices, the wakers of each core and the messages are on cache lines of their own, of up
+//! to 64 bytes, so a line cleaned by one core never holds data the other core writes.
+
+use core::{cell::UnsafeCell, future::poll_fn, marker::PhantomData, mem::MaybeUninit, task::Poll};
+use portable_atomic::{AtomicU32, Ordering};
+use rtic_common::waker_registration::CriticalSectionWakerRegistration;
+
+/// How a core reaches the memory it shares with the other core.
+pub trait SharedMemory {
+    /// Writes the cache lines covering `len` bytes at `addr` back to memory, for the other core
+    /// to read what this core wrote.
+    fn clean(addr: *const u8, len: usize);
+
+    /// Discards the cache lines covering `len` bytes at `addr`, for this core to read what the
+    /// other core wrote.
+    fn invalidate(addr: *const u8, len: usize);
+
+    /// Signals the other core that the channel changed, which wakes its end with
+    /// [`Channel::wake_receiver`] or [`Channel::wake_sender`].
+    ///
+    /// Without a signal the other core has to wake its end periodically, or only use the
+    /// non-blocking `try_` methods.
+    fn notify() {}
+}
+
+/// A core without a data cache, or a region it does not cache.
+pub struct NoCache;
+
+impl SharedMemory for NoCache {
+    #[inline]
+    fn clean(_: *const u8, _: usize) {}
+
+    #[inline]
+    fn invalidate(_: *const u8, _: usize) {}
+}
+
+/// The channel is full, the message was not sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Full<T>(pub T);
+
+/// Data on cache lines of its own.
+#[repr(C, align(64))]
+struct Line<T>(T);
+
+impl<T> Line<T> {
+    fn clean<M: SharedMemory>(&self) {
+        M::clean(
+            self as *const Self as *const u8,
+            core::mem::size_of::<Self>(),
+        );
+    }
+
+    fn invalidate<M: SharedMemory>(&self) {
+        M::invalidate(
+            self as *const Self as *const u8,
+            core::mem::size_of::<Self>(),
+        );
+    }
+}
+
+/// A channel of up to `N` messages from one core to the other, in memory both cores reach.
+#[repr(C)]
+pub struct Channel<T: Copy, M: SharedMemory, const N: usize> {
+    /// Position of the next send, modulo `2 * N`, written by the sending core
+    tail: Line<AtomicU32>,
+    /// Position of the next receive, modulo `2 * N`, written by the receiving core
+    head: Line<AtomicU32>,
+    /// Only used by the receiving core
+    receiver_waker: Line<CriticalSectionWakerRegistration>,
+    /// Only used by the sending core
+    sender_waker: Line<CriticalSectionWakerRegistration>,
+    slots: Line<[UnsafeCell<MaybeUninit<T>>; N]>,
+    _memory: PhantomData<M>,
+}
+
+// SAFETY: A slot is only written by the sender while it is outside the queued ones, and only
+// read by the receiver while it is one of them.
+unsafe impl<T: Copy + Send, M: SharedMemory, const N: usize> Sync for Channel<T, M, N> {}
+
+impl<T: Copy, M: SharedMemory, const N: usize> Default for Channel<T, M, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy, M: SharedMemory, const N: usize> Channel<T, M, N> {
+    const VALID: () = assert!(
+        N > 0 && N <= u32::MAX as usize / 2,
+        "the capacity must be above 0"
+    );
+
+    /// Create a new, empty channel.
+    pub const fn new() -> Self {
+        let () = Self::VALID;
+
+        Self {
+            tail: Line(AtomicU32::new(0)),
+            head: Line(AtomicU32::new(0)),
+            receiver_waker: Line(CriticalSectionWakerRegistration::new()),
+            sender_waker: Line(CriticalSectionWakerRegistration::new()),
+            slots: Line([const { UnsafeCell::new(MaybeUninit::uninit()) }; N]),
+            _memory: PhantomData,
+        }
+    }
+
+    /// Empties the channel, for a region the startup code of neither image initializes.
+    ///
+    /// # Safety
+    ///
+    /// Call it from one core only, before either core takes its end of the channel.
+    pub unsafe fn reset(&self) {
+        // SAFETY: Neither end is in use, the caller has exclusive access.
+        unsafe { core::ptr::write(self as *const Self as *mut Self, Self::new()) };
+        self.tail.clean::<M>();
+        self.head.clean::<M>();
+    }
+
+    /// Takes the sending end, on the core sending the messages.
+    ///
+    /// # Safety
+    ///
+    /// There is at most one [`Sender`] of the channel, across both cores.
+    pub unsafe fn sender(&self) -> Sender<'_, T, M, N> {
+        Sender { channel: self }
+    }
+
+    /// Takes the receiving end, on the core receiving the messages.
+    ///
+    /// # Safety
+    ///
+    /// There is at most one [`Receiver`] of the channel, across both cores.
+    pub unsafe fn receiver(&self) -> Receiver<'_, T, M, N> {
+        Receiver { channel: self }
+    }
+
+    /// Wakes the [`Receiver`] waiting for a message, call it on the receiving core when
+    /// signalled by [`SharedMemory::notify`] of the sending core.
+    pub fn wake_receiver(&self) {
+        self.receiver_waker.0.wake();
+    }
+
+    /// Wakes the [`Sender`] waiting for room, call it on the sending core when signalled by
+    /// [`SharedMemory::notify`] of the receiving core.
+    pub fn wake_sender(&self) {
+        self.sender_waker.0.wake();
+    }
+
+    fn slot(&self, position: u32) -> &UnsafeCell<MaybeUninit<T>> {
+        &self.slots.0[position as usize % N]
+    }
+
+    /// The number of messages from `head` to `tail`
+    fn distance(head: u32, tail: u32) -> usize {
+        (tail as usize + 2 * N - head as usize) % (2 * N)
+    }
+
+    fn next(position: u32) -> u32 {
+        ((position as usize + 1) % (2 * N)) as u32
+    }
+}
+
+/// The sending end of a [`Channel`].
+pub struct Sender<'a, T: Copy, M: SharedMemory, const N: usize> {
+    channel: &'a Channel<T, M, N>,
+}
+
+impl<T: Copy, M: SharedMemory, const N: usize> Sender<'_, T, M, N> {
+    /// Sends `value` if the channel has room.
+    pub fn try_send(&mut self, value: T) -> Result<(), Full<T>> {
+        let channel = self.channel;
+        let tail = channel.tail.0.load(Ordering::Relaxed);
+        channel.head.invalidate::<M>();
+        if Channel::<T, M, N>::distance(channel.head.0.load(Ordering::Acquire), tail) == N {
+            return Err(Full(value));
+        }
+
+        let slot = channel.slot(tail);
+        // SAFETY: The slot is outside of the queued ones, the receiver does not read it.
+        unsafe { (*slot.get()).write(value) };
+        M::clean(slot.get() as *const u8, core::mem::size_of::<T>());
+
+        // Publishes the message once it reached memory
+        channel
+            .tail
+            .0
+            .store(Channel::<T, M, N>::next(tail), Ordering::Release);
+        channel.tail.clean::<M>();
+        M::notify();
+
+        Ok(())
+    }
+
+    /// Sends `value`, waiting for the receiving core to make room.
+    pub async fn send(&mut self, value: T) {
+        poll_fn(|cx| {
+            if self.try_send(value).is_ok() {
+                return Poll::Ready(());
+            }
+
+            self.channel.sender_waker.0.register(cx.waker());
+
+            // A receive before the registration notified no one, check again
+            match self.try_send(value) {
+                Ok(()) => Poll::Ready(()),
+                Err(_) => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    /// The number of queued messages, as seen by this core.
+    pub fn len(&self) -> usize {
+        // Only the index of the other core is invalidated, this one may not be cleaned yet
+        self.channel.head.invalidate::<M>();
+        let tail = self.channel.tail.0.load(Ordering::Relaxed);
+        Channel::<T, M, N>::distance(self.channel.head.0.load(Ordering::Acquire), tail)
+    }
+
+    /// Whether no message is queued, as seen by this core.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the channel is full, as seen by this core.
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+}
+
+/// The receiving end of a [`Channel`].
+pub struct Receiver<'a, T: Copy, M: SharedMemory, const N: usize> {
+    channel: &'a Channel<T, M, N>,
+}
+
+impl<T: Copy, M: SharedMemory, const N: usize> Receiver<'_, T, M, N> {
+    /// Receives a message if one is queued.
+    pub fn try_recv(&mut self) -> Option<T> {
+        let channel = self.channel;
+        let head = channel.head.0.load(Ordering::Relaxed);
+        channel.tail.invalidate::<M>();
+        if head == channel.tail.0.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let slot = channel.slot(head);
+        M::invalidate(slot.get() as *const u8, core::mem::size_of::<T>());
+        // SAFETY: The slot is the oldest of the queued ones, written by the sender before it
+        // published it.
+        let value = unsafe { (*slot.get()).assume_init_read() };
+
+        // Hands the slot back to the sender
+        channel
+            .head
+            .0
+            .store(Channel::<T, M, N>::next(head), Ordering::Release);
+        channel.head.clean::<M>();
+        M::notify();
+
+        Some(value)
+    }
+
+    /// Receives a message, waiting for the sending core to send one.
+    pub async fn recv(&mut self) -> T {
+        poll_fn(|cx| {
+            if let Some(value) = self.try_recv() {
+                return Poll::Ready(value);
+            }
+
+            self.channel.receiver_waker.0.register(cx.waker());
+
+            // A send before the registration notified no one, check again
+            match self.try_recv() {
+                Some(value) => Poll::Ready(value),
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    /// The number of queued messages, as seen by this core.
+    pub fn len(&self) -> usize {
+        // Only the index of the other core is invalidated, this one may not be cleaned yet
+        self.channel.tail.invalidate::<M>();
+        let tail = self.channel.tail.0.load(Ordering::Acquire);
+        Channel::<T, M, N>::distance(self.channel.head.0.load(Ordering::Relaxed), tail)
+    }
+
+    /// Whether no message is queued, as seen by this core.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use static_cell::StaticCell;
+
+    use super::*;
+
+    std::thread_local! {
+        static CLEANED: Cell<usize> = const { Cell::new(0) };
+        static INVALIDATED: Cell<usize> = const { Cell::new(0) };
+        static NOTIFIED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Counts the cache maintenance of the channel
+    struct Counting;
+
+    impl SharedMemory for Counting {
+        fn clean(_: *const u8, len: usize) {
+            CLEANED.set(CLEANED.get() + len);
+        }
+
+        fn invalidate(_: *const u8, len: usize) {
+            INVALIDATED.set(INVALIDATED.get() + len);
+        }
+
+        fn notify() {
+            NOTIFIED.set(NOTIFIED.get() + 1);
+        }
+    }
+
+    #[test]
+    fn layout() {
+        assert_eq!(core::mem::offset_of!(Channel<u32, NoCache, 4>, head), 64);
+        assert_eq!(core::mem::align_of::<Channel<u8, NoCache, 1>>(), 64);
+    }
+
+    #[test]
+    fn wraps_around() {
+        let channel = Channel::<u16, NoCache, 3>::new();
+        let (mut sender, mut receiver) = unsafe { (channel.sender(), channel.receiver()) };
+
+        for i in 0..10 {
+            sender.try_send(i).unwrap();
+            sender.try_send(i + 100).unwrap();
+            assert_eq!(receiver.try_recv(), Some(i));
+            assert_eq!(receiver.try_recv(), Some(i + 100));
+        }
+        assert_eq!(receiver.try_recv(), None);
+    }
+
+    #[test]
+    fn full() {
+        let channel = Channel::<u8, NoCache, 2>::new();
+        let (mut sender, mut receiver) = unsafe { (channel.sender(), channel.receiver()) };
+
+        sender.try_send(1).unwrap();
+        sender.try_send(2).unwrap();
+        assert!(sender.is_full());
+        assert_eq!(sender.try_send(3), Err(Full(3)));
+
+        assert_eq!(receiver.try_recv(), Some(1));
+        sender.try_send(3).unwrap();
+        assert_eq!(receiver.len(), 2);
+    }
+
+    #[test]
+    fn cache_maintenance() {
+        let channel = Channel::<[u8; 12], Counting, 4>::new();
+        let (mut sender, mut receiver) = unsafe { (channel.sender(), channel.receiver()) };
+
+        sender.try_send([7; 12]).unwrap();
+        // The message and the tail cleaned, the head invalidated
+        assert_eq!(CLEANED.get(), 12 + 64);
+        assert_eq!(INVALIDATED.get(), 64);
+        assert_eq!(NOTIFIED.get(), 1);
+
+        assert_eq!(receiver.try_recv(), Some([7; 12]));
+        // The tail and the message invalidated, the head cleaned
+        assert_eq!(CLEANED.get(), 12 + 64 + 64);
+        assert_eq!(INVALIDATED.get(), 64 + 64 + 12);
+        assert_eq!(NOTIFIED.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn woken_by_notification() {
+        static CHANNEL: Channel<u32, NoCache, 1> = Channel::new();
+        static RECEIVER: StaticCell<Receiver<u32, NoCache, 1>> = StaticCell::new();
+        let mut sender = unsafe { CHANNEL.sender() };
+        let receiver = RECEIVER.init(unsafe { CHANNEL.receiver() });
+
+        let handle = tokio::spawn(receiver.recv());
+        tokio::task::yield_now().await;
+        assert!(!handle.is_finished());
+
+        sender.try_send(5).unwrap();
+        CHANNEL.wake_receiver();
+        assert_eq!(handle.await.unwrap(), 5);
+    }
+}
@@ -381,6 +381,98 @@ pub mod i2c {
     }
 }
 
+/// Random number generator sharing using [`Arbiter`]
+///
+/// Any number of tasks can draw from one hardware TRNG through an [`ArbiterDevice`] each. A task
+/// that needs the generator while another one fills a buffer waits for its turn instead of
+/// contending on a lock. Libraries that take a [`rand_core`] RNG get one with
+/// [`ArbiterDevice::access`].
+///
+/// ```rust
+/// # use rand_core::{CryptoRng, RngCore};
+/// use rtic_sync::arbiter::{rng::ArbiterDevice, Arbiter};
+/// # struct Trng;
+/// # impl RngCore for Trng {
+/// #     fn next_u32(&mut self) -> u32 { 4 }
+/// #     fn next_u64(&mut self) -> u64 { 4 }
+/// #     fn fill_bytes(&mut self, dest: &mut [u8]) { dest.fill(4) }
+/// #     fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+/// #         Ok(self.fill_bytes(dest))
+/// #     }
+/// # }
+/// # impl CryptoRng for Trng {}
+/// # fn sign<R: CryptoRng + RngCore>(rng: &mut R) {}
+///
+/// async fn nonce(rng: &ArbiterDevice<'_, Trng>) -> [u8; 12] {
+///     let mut nonce = [0; 12];
+///     rng.fill_bytes(&mut nonce).await.unwrap();
+///     nonce
+/// }
+///
+/// async fn signature(rng: &ArbiterDevice<'_, Trng>) {
+///     // Keeps the generator for the whole signature
+///     sign(&mut rng.access().await);
+/// }
+/// ```
+#[cfg(feature = "rng")]
+pub mod rng {
+    use super::{Arbiter, ExclusiveAccess};
+    use rand_core::{CryptoRng, Error, RngCore};
+
+    /// [`Arbiter`]-based shared random number generator.
+    pub struct ArbiterDevice<'a, RNG> {
+        rng: &'a Arbiter<RNG>,
+    }
+
+    impl<'a, RNG> ArbiterDevice<'a, RNG> {
+        /// Create a new [`ArbiterDevice`] for a random number generator.
+        pub fn new(rng: &'a Arbiter<RNG>) -> Self {
+            Self { rng }
+        }
+    }
+
+    impl<'a, RNG: RngCore> ArbiterDevice<'a, RNG> {
+        /// Fill `dest` with random bytes, waiting for other users of the generator to finish.
+        pub async fn fill_bytes(&self, dest: &mut [u8]) -> Result<(), Error> {
+            self.rng.access().await.try_fill_bytes(dest)
+        }
+
+        /// Wait for exclusive access to the generator, as a [`rand_core`] RNG.
+        ///
+        /// Other users wait until the returned [`Rng`] is dropped.
+        pub async fn access(&self) -> Rng<'a, RNG> {
+            Rng {
+                rng: self.rng.access().await,
+            }
+        }
+    }
+
+    /// Exclusive access to a shared random number generator, see [`ArbiterDevice::access`].
+    pub struct Rng<'a, RNG> {
+        rng: ExclusiveAccess<'a, RNG>,
+    }
+
+    impl<'a, RNG: RngCore> RngCore for Rng<'a, RNG> {
+        fn next_u32(&mut self) -> u32 {
+            self.rng.next_u32()
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.rng.next_u64()
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            self.rng.fill_bytes(dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            self.rng.try_fill_bytes(dest)
+        }
+    }
+
+    impl<'a, RNG: CryptoRng> CryptoRng for Rng<'a, RNG> {}
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -404,4 +496,50 @@ mod tests {
 
         assert_eq!(*ARB.access().await, NUM_RUNS)
     }
+
+    #[cfg(feature = "rng")]
+    #[tokio::test]
+    async fn shared_rng() {
+        use rand_core::{Error, RngCore};
+
+        struct Counter(u8);
+
+        impl RngCore for Counter {
+            fn next_u32(&mut self) -> u32 {
+                rand_core::impls::next_u32_via_fill(self)
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                rand_core::impls::next_u64_via_fill(self)
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                for byte in dest {
+                    self.0 += 1;
+                    *byte = self.0;
+                }
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+
+        let arbiter = Arbiter::new(Counter(0));
+        let a = rng::ArbiterDevice::new(&arbiter);
+        let b = rng::ArbiterDevice::new(&arbiter);
+
+        // While one user holds the generator the other waits, bytes are never handed out twice
+        let held = a.access().await;
+        let mut buf = [0; 4];
+        assert!(arbiter.try_access().is_none());
+        drop(held);
+
+        b.fill_bytes(&mut buf).await.unwrap();
+        assert_eq!(buf, [1, 2, 3, 4]);
+
+        let mut rng = a.access().await;
+        assert_eq!(rng.next_u32().to_le_bytes(), [5, 6, 7, 8]);
+    }
 }
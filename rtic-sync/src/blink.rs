@@ -0,0 +1,194 @@
+//! LED blink and dimming patterns driven by a monotonic.
+//!
+//! The pattern and its period are published on a [`Watch`](crate::watch::Watch) of [`Control`],
+//! the task driving the LED runs [`blink`] for a plain output pin or [`dim`] for a PWM channel. A
+//! new control value takes effect immediately and restarts the pattern from its beginning.
+//!
+//! ```ignore
+//! static LED: Watch<Control<MonoDuration>> = Watch::new();
+//!
+//! #[task(priority = 1, local = [led])]
+//! async fn led(cx: led::Context) {
+//!     let mut control = LED.receiver();
+//!     blink::<Mono, _>(cx.local.led, &mut control).await.ok();
+//! }
+//!
+//! // Anywhere else
+//! LED.sender().send(Control::new(Pattern::Flashes(3), 1000.millis()));
+//! ```
+
+use crate::watch::WatchReceiver;
+use core::{convert::Infallible, ops::Div};
+use embedded_hal::{digital::OutputPin, pwm::SetDutyCycle};
+use rtic_time::Monotonic;
+
+/// Number of steps a period is divided in.
+pub const STEPS: u8 = 32;
+
+/// The shape of the brightness over a period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt-03", derive(defmt::Format))]
+pub enum Pattern {
+    /// Always off.
+    Off,
+    /// Always on.
+    On,
+    /// On for the first half of the period, off for the second.
+    Blink,
+    /// `n` short flashes followed by a pause, at most 8 flashes fit in a period.
+    Flashes(u8),
+    /// Fades in over the first half of the period and out over the second.
+    Breathe,
+}
+
+impl Pattern {
+    /// The brightness in percent at `step` of [`STEPS`].
+    pub fn level(self, step: u8) -> u8 {
+        let half = STEPS / 2;
+        match self {
+            Pattern::Off => 0,
+            Pattern::On => 100,
+            Pattern::Blink if step < half => 100,
+            Pattern::Blink => 0,
+            Pattern::Flashes(n) if step < 4 * n.min(STEPS / 4) && step % 4 < 2 => 100,
+            Pattern::Flashes(_) => 0,
+            Pattern::Breathe if step < half => (step as u32 * 100 / half as u32) as u8,
+            Pattern::Breathe => ((STEPS - step) as u32 * 100 / half as u32) as u8,
+        }
+    }
+
+    /// Constant patterns do not need to be stepped.
+    fn is_constant(self) -> bool {
+        matches!(self, Pattern::Off | Pattern::On)
+    }
+}
+
+/// A pattern and the duration of one repetition of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt-03", derive(defmt::Format))]
+pub struct Control<D> {
+    /// The pattern to show.
+    pub pattern: Pattern,
+    /// The duration of one repetition of the pattern.
+    pub period: D,
+}
+
+impl<D> Control<D> {
+    /// Create a control value.
+    pub const fn new(pattern: Pattern, period: D) -> Self {
+        Self { pattern, period }
+    }
+}
+
+/// Shows the patterns of `control` on `pin`, a step is on from a brightness of 50 %.
+///
+/// Off until the first control value is received. Only returns on an error of the pin.
+pub async fn blink<Mono, P>(
+    pin: &mut P,
+    control: &mut WatchReceiver<'_, Control<Mono::Duration>>,
+) -> Result<Infallible, P::Error>
+where
+    Mono: Monotonic,
+    Mono::Duration: Div<u32, Output = Mono::Duration>,
+    P: OutputPin,
+{
+    pin.set_low()?;
+    run::<Mono, _>(control, |level| {
+        if level >= 50 {
+            pin.set_high()
+        } else {
+            pin.set_low()
+        }
+    })
+    .await
+}
+
+/// Shows the patterns of `control` on the PWM channel `pwm`, with the brightness as duty cycle.
+///
+/// Off until the first control value is received. Only returns on an error of the channel.
+pub async fn dim<Mono, P>(
+    pwm: &mut P,
+    control: &mut WatchReceiver<'_, Control<Mono::Duration>>,
+) -> Result<Infallible, P::Error>
+where
+    Mono: Monotonic,
+    Mono::Duration: Div<u32, Output = Mono::Duration>,
+    P: SetDutyCycle,
+{
+    pwm.set_duty_cycle_fully_off()?;
+    run::<Mono, _>(control, |level| pwm.set_duty_cycle_percent(level)).await
+}
+
+async fn run<Mono, E>(
+    control: &mut WatchReceiver<'_, Control<Mono::Duration>>,
+    mut output: impl FnMut(u8) -> Result<(), E>,
+) -> Result<Infallible, E>
+where
+    Mono: Monotonic,
+    Mono::Duration: Div<u32, Output = Mono::Duration>,
+{
+    let mut current = match control.get() {
+        Some(current) => current,
+        None => control.changed().await,
+    };
+
+    loop {
+        if current.pattern.is_constant() {
+            output(current.pattern.level(0))?;
+            current = control.changed().await;
+            continue;
+        }
+
+        let step = current.period / STEPS as u32;
+        let mut next = Mono::now();
+        let mut i = 0;
+
+        current = loop {
+            output(current.pattern.level(i))?;
+            i = (i + 1) % STEPS;
+            next = next + step;
+
+            if let Ok(changed) = Mono::timeout_at(next, control.changed()).await {
+                break changed;
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(pattern: Pattern) -> [u8; STEPS as usize] {
+        core::array::from_fn(|step| pattern.level(step as u8))
+    }
+
+    #[test]
+    fn blink_and_flashes() {
+        let blink = levels(Pattern::Blink);
+        assert!(blink[..16].iter().all(|&l| l == 100));
+        assert!(blink[16..].iter().all(|&l| l == 0));
+
+        let on: std::vec::Vec<_> = levels(Pattern::Flashes(3))
+            .iter()
+            .enumerate()
+            .filter(|(_, &l)| l == 100)
+            .map(|(step, _)| step)
+            .collect();
+        assert_eq!(on, [0, 1, 4, 5, 8, 9]);
+
+        // Saturates at 8 flashes
+        assert_eq!(levels(Pattern::Flashes(20)), levels(Pattern::Flashes(8)));
+    }
+
+    #[test]
+    fn breathe() {
+        let breathe = levels(Pattern::Breathe);
+        assert_eq!(
+            (breathe[0], breathe[8], breathe[16], breathe[24]),
+            (0, 50, 100, 50)
+        );
+        assert!(breathe[..16].windows(2).all(|w| w[0] <= w[1]));
+        assert!(breathe[16..].windows(2).all(|w| w[0] >= w[1]));
+    }
+}
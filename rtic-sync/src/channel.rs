@@ -16,7 +16,13 @@ use rtic_common::waker_registration::CriticalSectionWakerRegistration as WakerRe
 use rtic_common::{
     dropper::OnDrop,
     wait_queue::{Link, WaitQueue},
+    wake_source::{self, WakeSource},
 };
+#[cfg(feature = "time")]
+use rtic_time::{Monotonic, TimeoutError};
+
+#[cfg(feature = "fault-injection")]
+use rtic_common::fault::Injector;
 
 #[cfg(feature = "defmt-03")]
 use crate::defmt;
@@ -40,6 +46,9 @@ pub struct Channel<T, const N: usize> {
     receiver_dropped: UnsafeCell<bool>,
     // Keep track of the number of senders.
     num_senders: UnsafeCell<usize>,
+    // Decides which of the sent messages are lost.
+    #[cfg(feature = "fault-injection")]
+    fault: Injector,
 }
 
 unsafe impl<T, const N: usize> Send for Channel<T, N> {}
@@ -72,6 +81,8 @@ impl<T, const N: usize> Channel<T, N> {
             wait_queue: WaitQueue::new(),
             receiver_dropped: UnsafeCell::new(false),
             num_senders: UnsafeCell::new(0),
+            #[cfg(feature = "fault-injection")]
+            fault: Injector::new(),
         }
     }
 
@@ -146,6 +157,16 @@ pub enum TrySendError<T> {
     Full(T),
 }
 
+/// Errors that `send_timeout` can have.
+#[cfg(feature = "time")]
+#[cfg_attr(feature = "defmt-03", derive(defmt::Format))]
+pub enum SendTimeoutError<T> {
+    /// Error state for when the receiver has been dropped.
+    NoReceiver(T),
+    /// Error state when the queue stayed full for the whole timeout.
+    Timeout(T),
+}
+
 impl<T> core::fmt::Debug for NoReceiver<T>
 where
     T: core::fmt::Debug,
@@ -181,6 +202,33 @@ where
     }
 }
 
+#[cfg(feature = "time")]
+impl<T> core::fmt::Debug for SendTimeoutError<T>
+where
+    T: core::fmt::Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SendTimeoutError::NoReceiver(v) => write!(f, "NoReceiver({v:?})"),
+            SendTimeoutError::Timeout(v) => write!(f, "Timeout({v:?})"),
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+impl<T> PartialEq for SendTimeoutError<T>
+where
+    T: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (SendTimeoutError::NoReceiver(v1), SendTimeoutError::NoReceiver(v2)) => v1.eq(v2),
+            (SendTimeoutError::Timeout(v1), SendTimeoutError::Timeout(v2)) => v1.eq(v2),
+            _ => false,
+        }
+    }
+}
+
 /// A `Sender` can send to the channel and can be cloned.
 pub struct Sender<'a, T, const N: usize>(&'a Channel<T, N>);
 
@@ -218,6 +266,26 @@ impl<'a, T, const N: usize> defmt::Format for Sender<'a, T, N> {
 impl<'a, T, const N: usize> Sender<'a, T, N> {
     #[inline(always)]
     fn send_footer(&mut self, idx: u8, val: T) {
+        // An injected loss hands the slot back, as if the message was received and dropped.
+        #[cfg(feature = "fault-injection")]
+        if self.0.fault.inject() {
+            drop(val);
+
+            critical_section::with(|cs| {
+                assert!(!self.0.access(cs).freeq.is_full());
+                unsafe { self.0.access(cs).freeq.push_back_unchecked(idx) }
+            });
+
+            fence(Ordering::SeqCst);
+
+            // If someone is waiting in the WaiterQueue, wake the first one up.
+            if let Some(wait_head) = self.0.wait_queue.pop() {
+                wake_source::with(WakeSource::Channel, || wait_head.wake());
+            }
+
+            return;
+        }
+
         // Write the value to the slots, note; this memcpy is not under a critical section.
         unsafe {
             ptr::write(
@@ -235,7 +303,7 @@ impl<'a, T, const N: usize> Sender<'a, T, N> {
         fence(Ordering::SeqCst);
 
         // If there is a receiver waker, wake it.
-        self.0.receiver_waker.wake();
+        wake_source::with(WakeSource::Channel, || self.0.receiver_waker.wake());
     }
 
     /// Try to send a value, non-blocking. If the channel is full this will return an error.
@@ -265,6 +333,36 @@ impl<'a, T, const N: usize> Sender<'a, T, N> {
     /// Send a value. If there is no place left in the queue this will wait until there is.
     /// If the receiver does not exist this will return an error.
     pub async fn send(&mut self, val: T) -> Result<(), NoReceiver<T>> {
+        match self.wait_slot().await {
+            Ok(idx) => {
+                self.send_footer(idx, val);
+                Ok(())
+            }
+            Err(()) => Err(NoReceiver(val)),
+        }
+    }
+
+    /// Send a value, waiting at most `duration` of the monotonic `Mono` for a place in the queue.
+    /// The value is given back if the receiver does not exist or on timeout.
+    #[cfg(feature = "time")]
+    pub async fn send_timeout<Mono: Monotonic>(
+        &mut self,
+        val: T,
+        duration: Mono::Duration,
+    ) -> Result<(), SendTimeoutError<T>> {
+        // Only the wait for a slot is raced against the timeout, the value stays here
+        match Mono::timeout_after(duration, self.wait_slot()).await {
+            Ok(Ok(idx)) => {
+                self.send_footer(idx, val);
+                Ok(())
+            }
+            Ok(Err(())) => Err(SendTimeoutError::NoReceiver(val)),
+            Err(TimeoutError) => Err(SendTimeoutError::Timeout(val)),
+        }
+    }
+
+    /// Waits for a free slot, `Err` if the receiver does not exist.
+    async fn wait_slot(&mut self) -> Result<u8, ()> {
         let mut link_ptr: Option<Link<Waker>> = None;
 
         // Make this future `Drop`-safe.
@@ -335,13 +433,7 @@ impl<'a, T, const N: usize> Sender<'a, T, N> {
         // Make sure the link is removed from the queue.
         drop(dropper);
 
-        if let Ok(idx) = idx {
-            self.send_footer(idx, val);
-
-            Ok(())
-        } else {
-            Err(NoReceiver(val))
-        }
+        idx
     }
 
     /// Returns true if there is no `Receiver`s.
@@ -358,6 +450,13 @@ impl<'a, T, const N: usize> Sender<'a, T, N> {
     pub fn is_empty(&self) -> bool {
         critical_section::with(|cs| self.0.access(cs).freeq.is_full())
     }
+
+    /// The fault injector of the channel, a send it fails is lost: it returns `Ok` and the
+    /// receiver never sees the message.
+    #[cfg(feature = "fault-injection")]
+    pub fn fault(&self) -> &'a Injector {
+        &self.0.fault
+    }
 }
 
 impl<'a, T, const N: usize> Drop for Sender<'a, T, N> {
@@ -371,7 +470,7 @@ impl<'a, T, const N: usize> Drop for Sender<'a, T, N> {
 
         // If there are no senders, wake the receiver to do error handling.
         if num_senders == 0 {
-            self.0.receiver_waker.wake();
+            wake_source::with(WakeSource::Channel, || self.0.receiver_waker.wake());
         }
     }
 }
@@ -415,6 +514,17 @@ pub enum ReceiveError {
     Empty,
 }
 
+/// Possible errors of `recv_timeout`.
+#[cfg(feature = "time")]
+#[cfg_attr(feature = "defmt-03", derive(defmt::Format))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RecvTimeoutError {
+    /// Error state for when all senders has been dropped.
+    NoSender,
+    /// Error state when the queue stayed empty for the whole timeout.
+    Timeout,
+}
+
 impl<'a, T, const N: usize> Receiver<'a, T, N> {
     /// Receives a value if there is one in the channel, non-blocking.
     pub fn try_recv(&mut self) -> Result<T, ReceiveError> {
@@ -435,7 +545,7 @@ impl<'a, T, const N: usize> Receiver<'a, T, N> {
 
             // If someone is waiting in the WaiterQueue, wake the first one up.
             if let Some(wait_head) = self.0.wait_queue.pop() {
-                wait_head.wake();
+                wake_source::with(WakeSource::Channel, || wait_head.wake());
             }
 
             Ok(r)
@@ -471,6 +581,20 @@ impl<'a, T, const N: usize> Receiver<'a, T, N> {
         .await
     }
 
+    /// Receives a value, waiting at most `duration` of the monotonic `Mono` for one.
+    /// If all senders are dropped this will error with `NoSender`.
+    #[cfg(feature = "time")]
+    pub async fn recv_timeout<Mono: Monotonic>(
+        &mut self,
+        duration: Mono::Duration,
+    ) -> Result<T, RecvTimeoutError> {
+        match Mono::timeout_after(duration, self.recv()).await {
+            Ok(Ok(val)) => Ok(val),
+            Ok(Err(_)) => Err(RecvTimeoutError::NoSender),
+            Err(TimeoutError) => Err(RecvTimeoutError::Timeout),
+        }
+    }
+
     /// Returns true if there are no `Sender`s.
     pub fn is_closed(&self) -> bool {
         critical_section::with(|cs| *self.0.access(cs).num_senders == 0)
@@ -485,6 +609,12 @@ impl<'a, T, const N: usize> Receiver<'a, T, N> {
     pub fn is_empty(&self) -> bool {
         critical_section::with(|cs| self.0.access(cs).readyq.is_empty())
     }
+
+    /// The fault injector of the channel, see [`Sender::fault`].
+    #[cfg(feature = "fault-injection")]
+    pub fn fault(&self) -> &'a Injector {
+        &self.0.fault
+    }
 }
 
 impl<'a, T, const N: usize> Drop for Receiver<'a, T, N> {
@@ -493,7 +623,7 @@ impl<'a, T, const N: usize> Drop for Receiver<'a, T, N> {
         critical_section::with(|cs| *self.0.access(cs).receiver_dropped = true);
 
         while let Some(waker) = self.0.wait_queue.pop() {
-            waker.wake();
+            wake_source::with(WakeSource::Channel, || waker.wake());
         }
     }
 }
@@ -559,6 +689,27 @@ mod tests {
         assert_eq!(r.try_recv(), Err(ReceiveError::Empty));
     }
 
+    #[cfg(feature = "fault-injection")]
+    #[test]
+    fn injected_loss() {
+        let (mut s, mut r) = make_channel!(u32, 2);
+
+        // The slot of a lost message is free again
+        s.fault().fail_every(3);
+        let received: std::vec::Vec<u32> = (0..6)
+            .filter_map(|i| {
+                s.try_send(i).unwrap();
+                r.try_recv().ok()
+            })
+            .collect();
+        assert_eq!(received, [0, 1, 3, 4]);
+        assert_eq!(r.fault().injected(), 2);
+
+        s.fault().clear();
+        s.try_send(7).unwrap();
+        assert_eq!(r.try_recv(), Ok(7));
+    }
+
     #[test]
     fn closed_recv() {
         let (s, mut r) = make_channel!(u32, 10);
@@ -625,4 +776,91 @@ mod tests {
     fn tuple_channel() {
         let _ = make_channel!((i32, u32), 10);
     }
+
+    /// Times out with the timer of tokio
+    #[cfg(feature = "time")]
+    struct Mono;
+
+    #[cfg(feature = "time")]
+    impl Monotonic for Mono {
+        type Instant = fugit::Instant<u64, 1, 1_000_000>;
+        type Duration = fugit::Duration<u64, 1, 1_000_000>;
+
+        fn now() -> Self::Instant {
+            unimplemented!()
+        }
+
+        async fn delay(duration: Self::Duration) {
+            tokio::time::sleep(std::time::Duration::from_micros(duration.ticks())).await;
+        }
+
+        async fn delay_until(_: Self::Instant) {
+            unimplemented!()
+        }
+
+        async fn timeout_at<F: core::future::Future>(
+            _: Self::Instant,
+            _: F,
+        ) -> Result<F::Output, TimeoutError> {
+            unimplemented!()
+        }
+
+        async fn timeout_after<F: core::future::Future>(
+            duration: Self::Duration,
+            future: F,
+        ) -> Result<F::Output, TimeoutError> {
+            let duration = std::time::Duration::from_micros(duration.ticks());
+            tokio::time::timeout(duration, future)
+                .await
+                .map_err(|_| TimeoutError)
+        }
+    }
+
+    #[cfg(feature = "time")]
+    #[tokio::test]
+    async fn send_timeout() {
+        let (mut s, mut r) = make_channel!(u32, 1);
+        let ms = fugit::Duration::<u64, 1, 1_000_000>::millis;
+
+        s.send_timeout::<Mono>(1, ms(1)).await.unwrap();
+        assert_eq!(
+            s.send_timeout::<Mono>(2, ms(1)).await,
+            Err(SendTimeoutError::Timeout(2))
+        );
+
+        // The timed out send left the wait queue
+        assert_eq!(r.try_recv(), Ok(1));
+        s.try_send(3).unwrap();
+        assert_eq!(r.try_recv(), Ok(3));
+
+        drop(r);
+        assert_eq!(
+            s.send_timeout::<Mono>(4, ms(1)).await,
+            Err(SendTimeoutError::NoReceiver(4))
+        );
+    }
+
+    #[cfg(feature = "time")]
+    #[tokio::test]
+    async fn recv_timeout() {
+        let (mut s, mut r) = make_channel!(u32, 2);
+        let ms = fugit::Duration::<u64, 1, 1_000_000>::millis;
+
+        assert_eq!(
+            r.recv_timeout::<Mono>(ms(1)).await,
+            Err(RecvTimeoutError::Timeout)
+        );
+
+        let sender = tokio::spawn(async move {
+            Mono::delay(ms(1)).await;
+            s.send(1).await.unwrap();
+        });
+        assert_eq!(r.recv_timeout::<Mono>(ms(1_000)).await, Ok(1));
+
+        sender.await.unwrap();
+        assert_eq!(
+            r.recv_timeout::<Mono>(ms(1)).await,
+            Err(RecvTimeoutError::NoSender)
+        );
+    }
 }
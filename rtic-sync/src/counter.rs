@@ -0,0 +1,239 @@
+//! Lock-free event counters and rate metrics.
+//!
+//! A [`Counter`] is a `static` incremented from any priority, e.g. once per received packet in an
+//! interrupt handler, without a lock or a channel. A task reads it through a [`RateMeter`], which
+//! turns the counts into a rate per second over windows timed by a monotonic, with the `time`
+//! feature.
+//!
+//! ```ignore
+//! static PACKETS: Counter = Counter::new();
+//!
+//! #[task(binds = ETH, priority = 3)]
+//! fn eth(_: eth::Context) {
+//!     PACKETS.increment();
+//! }
+//!
+//! #[task(priority = 1)]
+//! async fn metrics(_: metrics::Context) {
+//!     let mut meter = RateMeter::<Mono, 1, 1_000>::new(&PACKETS, 1000.millis());
+//!     loop {
+//!         let per_second = meter.next().await;
+//!         // Report `per_second`
+//!     }
+//! }
+//! ```
+
+use portable_atomic::{AtomicU32, Ordering};
+#[cfg(feature = "time")]
+use rtic_time::Monotonic;
+
+/// A count of events, incremented from any priority without a lock.
+///
+/// The count wraps around at `u32::MAX`, a [`RateMeter`] handles a wrap within a window.
+pub struct Counter {
+    count: AtomicU32,
+}
+
+impl Default for Counter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Counter {
+    /// Create a counter at 0.
+    pub const fn new() -> Self {
+        Self {
+            count: AtomicU32::new(0),
+        }
+    }
+
+    /// Count one event.
+    #[inline]
+    pub fn increment(&self) {
+        self.add(1);
+    }
+
+    /// Count `n` events, e.g. the bytes of a transfer.
+    #[inline]
+    pub fn add(&self, n: u32) {
+        self.count.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// The events counted so far, wrapped around at `u32::MAX`.
+    #[inline]
+    pub fn get(&self) -> u32 {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+/// The rate of the events of a [`Counter`] over windows timed by `Mono`.
+///
+/// `NOM / DENOM` is the length of a tick of `Mono` in seconds, e.g. `1, 1_000_000` for a
+/// monotonic counting microseconds, as in its [`fugit`] duration type. Only the task owning the
+/// meter reads the counter, any number of meters may read the same counter.
+#[cfg(feature = "time")]
+pub struct RateMeter<'a, Mono: Monotonic, const NOM: u32, const DENOM: u32> {
+    counter: &'a Counter,
+    window: Mono::Duration,
+    start: Option<(u32, Mono::Instant)>,
+    rate: u32,
+}
+
+#[cfg(feature = "time")]
+impl<'a, Mono, const NOM: u32, const DENOM: u32> RateMeter<'a, Mono, NOM, DENOM>
+where
+    Mono: Monotonic,
+    Mono::Duration: Into<fugit::Duration<u64, NOM, DENOM>>,
+{
+    /// Create a meter of the events of `counter` per `window`.
+    pub fn new(counter: &'a Counter, window: Mono::Duration) -> Self {
+        Self {
+            counter,
+            window,
+            start: None,
+            rate: 0,
+        }
+    }
+
+    /// The events per second of the last complete window, 0 before the first one.
+    pub fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    /// Sample the counter at `now`, e.g. from a periodic task.
+    ///
+    /// Returns the events per second of the window if it ended, which starts the next one. The
+    /// first sample only starts a window.
+    pub fn sample(&mut self, now: Mono::Instant) -> Option<u32> {
+        let count = self.counter.get();
+
+        let Some((start_count, start_at)) = self.start else {
+            self.start = Some((count, now));
+            return None;
+        };
+
+        if now < start_at + self.window {
+            return None;
+        }
+
+        let ticks = (now - start_at).into().ticks();
+        let counts = count.wrapping_sub(start_count) as u64;
+        let per_second = counts * DENOM as u64 / (ticks * NOM as u64).max(1);
+        self.rate = per_second.min(u32::MAX as u64) as u32;
+        self.start = Some((count, now));
+
+        Some(self.rate)
+    }
+
+    /// Wait for the end of the current window and return its events per second.
+    ///
+    /// Starts a window first if none is running. The next window starts where this one ended, so
+    /// the windows stay aligned when the caller is late.
+    pub async fn next(&mut self) -> u32 {
+        if self.start.is_none() {
+            self.sample(Mono::now());
+        }
+
+        loop {
+            let (_, start_at) = self.start.expect("started above");
+            Mono::delay_until(start_at + self.window).await;
+
+            if let Some(rate) = self.sample(start_at + self.window) {
+                return rate;
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "time"))]
+mod tests {
+    use super::*;
+    use rtic_time::TimeoutError;
+
+    static NOW: AtomicU32 = AtomicU32::new(0);
+
+    /// A monotonic whose delays move the time to their end
+    struct Mono;
+
+    impl Monotonic for Mono {
+        type Instant = fugit::Instant<u32, 1, 1_000>;
+        type Duration = fugit::Duration<u32, 1, 1_000>;
+
+        fn now() -> Self::Instant {
+            Self::Instant::from_ticks(NOW.load(Ordering::Relaxed))
+        }
+
+        async fn delay(duration: Self::Duration) {
+            Self::delay_until(Self::now() + duration).await;
+        }
+
+        async fn delay_until(instant: Self::Instant) {
+            NOW.store(instant.ticks(), Ordering::Relaxed);
+        }
+
+        async fn timeout_at<F: core::future::Future>(
+            _: Self::Instant,
+            future: F,
+        ) -> Result<F::Output, TimeoutError> {
+            Ok(future.await)
+        }
+
+        async fn timeout_after<F: core::future::Future>(
+            _: Self::Duration,
+            future: F,
+        ) -> Result<F::Output, TimeoutError> {
+            Ok(future.await)
+        }
+    }
+
+    fn poll<T>(future: impl core::future::Future<Output = T>) -> Option<T> {
+        let mut future = core::pin::pin!(future);
+        cassette::Cassette::new(&mut future).poll_on()
+    }
+
+    #[test]
+    fn sampled_windows() {
+        static COUNTER: Counter = Counter::new();
+        let mut meter = RateMeter::<Mono, 1, 1_000>::new(
+            &COUNTER,
+            fugit::Duration::<u32, 1, 1_000>::from_ticks(500),
+        );
+        let at = fugit::Instant::<u32, 1, 1_000>::from_ticks;
+
+        assert_eq!(meter.sample(at(0)), None);
+        COUNTER.add(100);
+        assert_eq!(meter.sample(at(499)), None);
+        assert_eq!(meter.sample(at(500)), Some(200));
+
+        // Saturated
+        COUNTER.add(u32::MAX - 149);
+        assert_eq!(meter.sample(at(1_000)), Some(u32::MAX));
+
+        // Across the wrap of the counter
+        COUNTER.add(300);
+        assert_eq!(COUNTER.get(), 250);
+        assert_eq!(meter.sample(at(1_500)), Some(600));
+    }
+
+    #[test]
+    fn timed_windows() {
+        static COUNTER: Counter = Counter::new();
+        let mut meter = RateMeter::<Mono, 1, 1_000>::new(
+            &COUNTER,
+            fugit::Duration::<u32, 1, 1_000>::from_ticks(250),
+        );
+
+        // The delays of the test monotonic return at once
+        COUNTER.increment();
+        assert_eq!(poll(meter.next()), Some(0));
+        assert_eq!(NOW.load(Ordering::Relaxed), 250);
+
+        for _ in 0..10 {
+            COUNTER.increment();
+        }
+        assert_eq!(poll(meter.next()), Some(40));
+        assert_eq!(meter.rate(), 40);
+        assert_eq!(NOW.load(Ordering::Relaxed), 500);
+    }
+}
@@ -0,0 +1,156 @@
+//! Position and velocity estimation for quadrature encoders.
+//!
+//! The timer of the encoder counts the edges in hardware. A task samples the counter, e.g. bound
+//! to the update interrupt of a second timer, and passes it with its timestamp to
+//! [`Encoder::update`]. The estimate is published on a [`Watch`], so any number of tasks read the
+//! latest position and velocity without locking the encoder.
+//!
+//! ```ignore
+//! static SPEED: Watch<Estimate<Mono>> = Watch::new();
+//!
+//! #[task(binds = TIM6, local = [qei, encoder: Encoder<'static, Mono, 1, 1_000_000> = Encoder::new(&SPEED)])]
+//! fn sample(cx: sample::Context) {
+//!     cx.local.encoder.update(cx.local.qei.count(), Mono::now());
+//! }
+//!
+//! #[task(priority = 1)]
+//! async fn speed_control(_: speed_control::Context) {
+//!     let mut speed = SPEED.receiver();
+//!     loop {
+//!         let estimate = speed.changed().await;
+//!         // Track the velocity setpoint with `estimate.velocity`
+//!     }
+//! }
+//! ```
+
+use crate::watch::{Watch, WatchSender};
+use rtic_time::Monotonic;
+
+/// An estimate of the state of an encoder.
+pub struct Estimate<Mono: Monotonic> {
+    /// Counts since the first sample, not limited to the range of the hardware counter.
+    pub position: i64,
+    /// Counts per second between the last two samples.
+    pub velocity: i32,
+    /// The time of the last sample.
+    pub at: Mono::Instant,
+}
+
+impl<Mono: Monotonic> Clone for Estimate<Mono> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Mono: Monotonic> Copy for Estimate<Mono> {}
+
+/// Estimation from the samples of a 16 bit encoder counter, timestamped by `Mono`.
+///
+/// `NOM / DENOM` is the length of a tick of `Mono` in seconds, e.g. `1, 1_000_000` for a
+/// monotonic counting microseconds, as in its [`fugit`] duration type.
+pub struct Encoder<'a, Mono: Monotonic, const NOM: u32, const DENOM: u32> {
+    watch: WatchSender<'a, Estimate<Mono>>,
+    last: Option<(u16, Mono::Instant)>,
+    position: i64,
+}
+
+impl<'a, Mono, const NOM: u32, const DENOM: u32> Encoder<'a, Mono, NOM, DENOM>
+where
+    Mono: Monotonic,
+    Mono::Duration: Into<fugit::Duration<u64, NOM, DENOM>>,
+{
+    /// Create an encoder publishing its estimates on `watch`.
+    pub fn new(watch: &'a Watch<Estimate<Mono>>) -> Self {
+        Self {
+            watch: watch.sender(),
+            last: None,
+            position: 0,
+        }
+    }
+
+    /// Update the estimate with the counter value `count` sampled at `at`, and publish it.
+    ///
+    /// The counter may wrap between samples, as long as it moves less than half its range.
+    /// The velocity is 0 until the second sample.
+    pub fn update(&mut self, count: u16, at: Mono::Instant) -> Estimate<Mono> {
+        let mut velocity = 0;
+
+        if let Some((last_count, last_at)) = self.last {
+            let counts = count.wrapping_sub(last_count) as i16 as i64;
+            self.position += counts;
+
+            let ticks = (at - last_at).into().ticks();
+            if ticks > 0 {
+                let per_second = counts * DENOM as i64 / NOM as i64;
+                velocity =
+                    (per_second / ticks as i64).clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+            }
+        }
+
+        self.last = Some((count, at));
+
+        let estimate = Estimate {
+            position: self.position,
+            velocity,
+            at,
+        };
+        self.watch.send(estimate);
+
+        estimate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rtic_time::TimeoutError;
+
+    struct Mono;
+
+    impl Monotonic for Mono {
+        type Instant = fugit::Instant<u32, 1, 1_000_000>;
+        type Duration = fugit::Duration<u32, 1, 1_000_000>;
+
+        fn now() -> Self::Instant {
+            unimplemented!()
+        }
+
+        async fn delay(_: Self::Duration) {}
+
+        async fn delay_until(_: Self::Instant) {}
+
+        async fn timeout_at<F: core::future::Future>(
+            _: Self::Instant,
+            future: F,
+        ) -> Result<F::Output, TimeoutError> {
+            Ok(future.await)
+        }
+
+        async fn timeout_after<F: core::future::Future>(
+            _: Self::Duration,
+            future: F,
+        ) -> Result<F::Output, TimeoutError> {
+            Ok(future.await)
+        }
+    }
+
+    #[test]
+    fn estimate() {
+        static WATCH: Watch<Estimate<Mono>> = Watch::new();
+        let mut encoder = Encoder::<Mono, 1, 1_000_000>::new(&WATCH);
+        let mut reader = WATCH.receiver();
+        let at = fugit::Instant::<u32, 1, 1_000_000>::from_ticks;
+
+        encoder.update(65_000, at(0));
+        assert_eq!(reader.try_changed().map(|e| e.velocity), Some(0));
+
+        // Forward across the wrap of the counter, 1000 counts in 1 ms
+        let estimate = encoder.update(464, at(1_000));
+        assert_eq!((estimate.position, estimate.velocity), (1_000, 1_000_000));
+
+        // Backwards
+        let estimate = encoder.update(264, at(2_000));
+        assert_eq!((estimate.position, estimate.velocity), (800, -200_000));
+        assert_eq!(reader.try_changed().map(|e| e.position), Some(800));
+    }
+}
@@ -0,0 +1,423 @@
+//! Async access to a blocking flash that stalls the bus while it writes.
+//!
+//! On many parts a flash write or erase stalls every access to the flash, including instruction
+//! fetches, so an interrupt arriving meanwhile waits for the operation to end whatever its
+//! priority. A whole page erase can take tens of milliseconds. [`FlashArbiter`] splits reads,
+//! writes and erases into chunks of `CHUNK` bytes, or one erase sector, and runs them from
+//! [`FlashArbiter::serve`], called in `idle`. Chunks then only run when no task is ready, and a
+//! chunk delays an interrupt by at most its own duration.
+//!
+//! Any number of tasks use the async [`read`](FlashArbiter::read),
+//! [`write`](FlashArbiter::write) and [`erase`](FlashArbiter::erase), which wait for each other
+//! in FIFO order.
+//!
+//! ```ignore
+//! static FLASH: FlashArbiter<hal::Flash, 32> = FlashArbiter::new();
+//!
+//! #[idle(local = [flash])]
+//! fn idle(cx: idle::Context) -> ! {
+//!     loop {
+//!         if !FLASH.serve(cx.local.flash) {
+//!             cortex_m::asm::wfi();
+//!         }
+//!     }
+//! }
+//!
+//! #[task(priority = 2)]
+//! async fn log(_: log::Context, record: [u8; 32]) {
+//!     FLASH.write(LOG_OFFSET, &record).await.unwrap();
+//! }
+//! ```
+
+use crate::arbiter::Arbiter;
+use core::{cell::RefCell, future::poll_fn, marker::PhantomData, task::Poll};
+use critical_section::Mutex;
+use embedded_storage::nor_flash::{self, NorFlash, NorFlashErrorKind};
+use rtic_common::waker_registration::CriticalSectionWakerRegistration;
+
+/// Error of a flash operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt-03", derive(defmt::Format))]
+pub enum Error<E> {
+    /// The range is not aligned or out of bounds, checked before the first chunk.
+    Range(NorFlashErrorKind),
+    /// The flash reported an error.
+    Flash(E),
+}
+
+enum Job<E> {
+    Idle,
+    Read {
+        offset: u32,
+        bytes: *mut u8,
+        len: usize,
+        done: usize,
+    },
+    Write {
+        offset: u32,
+        bytes: *const u8,
+        len: usize,
+        done: usize,
+    },
+    Erase {
+        next: u32,
+        to: u32,
+    },
+    Done(Result<(), Error<E>>),
+}
+
+struct State<E> {
+    job: Job<E>,
+    /// Identifies the job, a chunk finishing after its job was cancelled is discarded.
+    id: u32,
+    checked: bool,
+}
+
+/// Serializes the accesses to a flash and runs them in chunks from `idle`.
+///
+/// `CHUNK` is the number of bytes read or written at once, a multiple of the read and write sizes
+/// of the flash.
+pub struct FlashArbiter<F: NorFlash, const CHUNK: usize> {
+    users: Arbiter<()>,
+    state: Mutex<RefCell<State<F::Error>>>,
+    waker: CriticalSectionWakerRegistration,
+    _flash: PhantomData<fn(&mut F)>,
+}
+
+// SAFETY: The buffer pointers of a job are only dereferenced in critical sections while the
+// future that owns the buffers is alive, it cancels the job when dropped.
+unsafe impl<F: NorFlash, const CHUNK: usize> Sync for FlashArbiter<F, CHUNK> where F::Error: Send {}
+
+impl<F: NorFlash, const CHUNK: usize> Default for FlashArbiter<F, CHUNK> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: NorFlash, const CHUNK: usize> FlashArbiter<F, CHUNK> {
+    /// Create an arbiter without pending operations.
+    pub const fn new() -> Self {
+        Self {
+            users: Arbiter::new(()),
+            state: Mutex::new(RefCell::new(State {
+                job: Job::Idle,
+                id: 0,
+                checked: false,
+            })),
+            waker: CriticalSectionWakerRegistration::new(),
+            _flash: PhantomData,
+        }
+    }
+
+    /// Read `bytes.len()` bytes at `offset`.
+    pub async fn read(&self, offset: u32, bytes: &mut [u8]) -> Result<(), Error<F::Error>> {
+        self.run(Job::Read {
+            offset,
+            bytes: bytes.as_mut_ptr(),
+            len: bytes.len(),
+            done: 0,
+        })
+        .await
+    }
+
+    /// Write `bytes` at `offset`, which must have been erased.
+    pub async fn write(&self, offset: u32, bytes: &[u8]) -> Result<(), Error<F::Error>> {
+        self.run(Job::Write {
+            offset,
+            bytes: bytes.as_ptr(),
+            len: bytes.len(),
+            done: 0,
+        })
+        .await
+    }
+
+    /// Erase the sectors in `from..to`.
+    pub async fn erase(&self, from: u32, to: u32) -> Result<(), Error<F::Error>> {
+        if from == to {
+            return Ok(());
+        }
+
+        self.run(Job::Erase { next: from, to }).await
+    }
+
+    async fn run(&self, job: Job<F::Error>) -> Result<(), Error<F::Error>> {
+        let _user = self.users.access().await;
+
+        critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            state.job = job;
+            state.id = state.id.wrapping_add(1);
+            state.checked = false;
+        });
+
+        // Cancels the job if the caller drops the future, before its buffer goes away.
+        let cancel = OnDrop(|| {
+            critical_section::with(|cs| {
+                let mut state = self.state.borrow_ref_mut(cs);
+                state.job = Job::Idle;
+                state.id = state.id.wrapping_add(1);
+            });
+        });
+
+        let result = poll_fn(|cx| {
+            self.waker.register(cx.waker());
+            critical_section::with(|cs| {
+                let mut state = self.state.borrow_ref_mut(cs);
+                match core::mem::replace(&mut state.job, Job::Idle) {
+                    Job::Done(result) => Poll::Ready(result),
+                    job => {
+                        state.job = job;
+                        Poll::Pending
+                    }
+                }
+            })
+        })
+        .await;
+
+        core::mem::forget(cancel);
+        result
+    }
+
+    /// Runs the next chunk of the pending operation, returns `true` if it is not done yet.
+    ///
+    /// Call it from `idle` with the flash, e.g. as a local resource of `idle`, and sleep when it
+    /// returns `false`.
+    pub fn serve(&self, flash: &mut F) -> bool {
+        assert!(
+            CHUNK.is_multiple_of(F::READ_SIZE) && CHUNK.is_multiple_of(F::WRITE_SIZE),
+            "CHUNK must be a multiple of the read and write sizes"
+        );
+
+        let mut chunk = [0; CHUNK];
+
+        // Pick the next chunk, copying the data to write while the buffer is known to be alive.
+        let step = critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            let id = state.id;
+
+            if !state.checked {
+                let check = match state.job {
+                    Job::Read { offset, len, .. } => nor_flash::check_read(flash, offset, len),
+                    Job::Write { offset, len, .. } => nor_flash::check_write(flash, offset, len),
+                    Job::Erase { next, to } => nor_flash::check_erase(flash, next, to),
+                    Job::Idle | Job::Done(_) => return None,
+                };
+
+                if let Err(kind) = check {
+                    state.job = Job::Done(Err(Error::Range(kind)));
+                    self.waker.wake();
+                    return None;
+                }
+
+                state.checked = true;
+            }
+
+            match state.job {
+                Job::Read {
+                    offset, len, done, ..
+                } => Some((
+                    id,
+                    Step::Read(offset + done as u32, (len - done).min(CHUNK)),
+                )),
+                Job::Write {
+                    offset,
+                    bytes,
+                    len,
+                    done,
+                } => {
+                    let n = (len - done).min(CHUNK);
+                    // SAFETY: The caller's buffer is alive while the job is pending.
+                    let src = unsafe { core::slice::from_raw_parts(bytes.add(done), n) };
+                    chunk[..n].copy_from_slice(src);
+                    Some((id, Step::Write(offset + done as u32, n)))
+                }
+                Job::Erase { next, .. } => Some((id, Step::Erase(next))),
+                Job::Idle | Job::Done(_) => None,
+            }
+        });
+
+        let Some((id, step)) = step else {
+            return false;
+        };
+
+        // The flash access itself, outside of any critical section.
+        let result = match step {
+            Step::Read(offset, n) => flash.read(offset, &mut chunk[..n]),
+            Step::Write(offset, n) => flash.write(offset, &chunk[..n]),
+            Step::Erase(from) => flash.erase(from, from + F::ERASE_SIZE as u32),
+        };
+
+        critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            if state.id != id {
+                return false;
+            }
+
+            let finished = match (&mut state.job, result) {
+                (_, Err(e)) => Some(Err(Error::Flash(e))),
+                (
+                    Job::Read {
+                        bytes, len, done, ..
+                    },
+                    Ok(()),
+                ) => {
+                    let n = (*len - *done).min(CHUNK);
+                    // SAFETY: The caller's buffer is alive while the job is pending.
+                    let dst = unsafe { core::slice::from_raw_parts_mut(bytes.add(*done), n) };
+                    dst.copy_from_slice(&chunk[..n]);
+                    *done += n;
+                    (*done == *len).then_some(Ok(()))
+                }
+                (Job::Write { len, done, .. }, Ok(())) => {
+                    *done += (*len - *done).min(CHUNK);
+                    (*done == *len).then_some(Ok(()))
+                }
+                (Job::Erase { next, to }, Ok(())) => {
+                    *next += F::ERASE_SIZE as u32;
+                    (*next >= *to).then_some(Ok(()))
+                }
+                // Cancelled while the chunk ran
+                (Job::Idle | Job::Done(_), Ok(())) => return false,
+            };
+
+            match finished {
+                Some(result) => {
+                    state.job = Job::Done(result);
+                    self.waker.wake();
+                    false
+                }
+                None => true,
+            }
+        })
+    }
+}
+
+enum Step {
+    Read(u32, usize),
+    Write(u32, usize),
+    Erase(u32),
+}
+
+struct OnDrop<F: FnMut()>(F);
+
+impl<F: FnMut()> Drop for OnDrop<F> {
+    fn drop(&mut self) {
+        (self.0)();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_storage::nor_flash::{ErrorType, ReadNorFlash};
+    use std::vec::Vec;
+
+    /// 4 sectors of 16 bytes, logs the accesses
+    struct Ram {
+        data: [u8; 64],
+        log: Vec<(&'static str, u32, usize)>,
+    }
+
+    impl ErrorType for Ram {
+        type Error = NorFlashErrorKind;
+    }
+
+    impl ReadNorFlash for Ram {
+        const READ_SIZE: usize = 1;
+
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            self.log.push(("read", offset, bytes.len()));
+            let offset = offset as usize;
+            bytes.copy_from_slice(&self.data[offset..offset + bytes.len()]);
+            Ok(())
+        }
+
+        fn capacity(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    impl NorFlash for Ram {
+        const WRITE_SIZE: usize = 4;
+        const ERASE_SIZE: usize = 16;
+
+        fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+            self.log.push(("erase", from, (to - from) as usize));
+            self.data[from as usize..to as usize].fill(0xff);
+            Ok(())
+        }
+
+        fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+            self.log.push(("write", offset, bytes.len()));
+            let offset = offset as usize;
+            self.data[offset..offset + bytes.len()].copy_from_slice(bytes);
+            Ok(())
+        }
+    }
+
+    fn drive<T>(
+        arbiter: &FlashArbiter<Ram, 8>,
+        flash: &mut Ram,
+        future: impl core::future::Future<Output = T>,
+    ) -> T {
+        let mut future = core::pin::pin!(future);
+        let mut cassette = cassette::Cassette::new(&mut future);
+
+        loop {
+            if let Some(output) = cassette.poll_on() {
+                return output;
+            }
+            while arbiter.serve(flash) {}
+        }
+    }
+
+    #[test]
+    fn chunks() {
+        static FLASH: FlashArbiter<Ram, 8> = FlashArbiter::new();
+        let mut flash = Ram {
+            data: [0; 64],
+            log: Vec::new(),
+        };
+
+        drive(&FLASH, &mut flash, FLASH.erase(16, 48)).unwrap();
+        drive(&FLASH, &mut flash, FLASH.write(16, &[1; 20])).unwrap();
+
+        let mut bytes = [0; 24];
+        drive(&FLASH, &mut flash, FLASH.read(12, &mut bytes)).unwrap();
+        assert_eq!(bytes[..4], [0; 4]);
+        assert_eq!(bytes[4..], [1; 20]);
+
+        assert_eq!(
+            flash.log,
+            [
+                ("erase", 16, 16),
+                ("erase", 32, 16),
+                ("write", 16, 8),
+                ("write", 24, 8),
+                ("write", 32, 4),
+                ("read", 12, 8),
+                ("read", 20, 8),
+                ("read", 28, 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn range() {
+        static FLASH: FlashArbiter<Ram, 8> = FlashArbiter::new();
+        let mut flash = Ram {
+            data: [0; 64],
+            log: Vec::new(),
+        };
+
+        assert_eq!(
+            drive(&FLASH, &mut flash, FLASH.write(2, &[0; 4])),
+            Err(Error::Range(NorFlashErrorKind::NotAligned))
+        );
+        assert_eq!(
+            drive(&FLASH, &mut flash, FLASH.erase(48, 80)),
+            Err(Error::Range(NorFlashErrorKind::OutOfBounds))
+        );
+        assert!(flash.log.is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+//! A single-shot cell moving ownership of a value, e.g. a peripheral, from one task to another.
+//!
+//! A task owning a peripheral as a `local` resource gives it away once it is done with it and
+//! another task takes it, waiting until it is given if needed:
+//!
+//! ```ignore
+//! static UART: Handoff<Uart> = Handoff::new();
+//!
+//! #[task(local = [uart])]
+//! async fn bootloader(cx: bootloader::Context) {
+//!     // Run the bootloader protocol on the UART, then hand it over
+//!     UART.give(cx.local.uart.take().unwrap()).ok().unwrap();
+//! }
+//!
+//! #[task]
+//! async fn application(_: application::Context) {
+//!     let mut uart = UART.take().await.unwrap();
+//! }
+//! ```
+//!
+//! The handoff happens at most once: giving a second time returns the value back and taking
+//! after the value has been taken fails, so a peripheral can never end up owned by two tasks.
+
+use core::{cell::UnsafeCell, future::poll_fn, task::Poll};
+use rtic_common::waker_registration::CriticalSectionWakerRegistration;
+
+enum State<T> {
+    Empty,
+    Given(T),
+    Taken,
+}
+
+/// The value has already been taken out of the handoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt-03", derive(defmt::Format))]
+pub struct AlreadyTaken;
+
+/// Error of [`Handoff::try_take`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt-03", derive(defmt::Format))]
+pub enum TryTakeError {
+    /// The value has not been given yet.
+    Empty,
+    /// The value has already been taken.
+    AlreadyTaken,
+}
+
+/// A single-shot cell handing a value over from one task to another.
+pub struct Handoff<T> {
+    waker: CriticalSectionWakerRegistration,
+    state: UnsafeCell<State<T>>,
+}
+
+impl<T> Default for Handoff<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<T: Send> Send for Handoff<T> {}
+unsafe impl<T: Send> Sync for Handoff<T> {}
+
+impl<T> Handoff<T> {
+    /// Create a new, empty, handoff.
+    pub const fn new() -> Self {
+        Self {
+            waker: CriticalSectionWakerRegistration::new(),
+            state: UnsafeCell::new(State::Empty),
+        }
+    }
+
+    /// Give the value to the task taking it.
+    ///
+    /// Returns the value back if a value has already been given, whether it has been taken or
+    /// not.
+    pub fn give(&self, value: T) -> Result<(), T> {
+        let given = critical_section::with(|_| {
+            // SAFETY: in a cs: exclusive access
+            let state = unsafe { &mut *self.state.get() };
+            match state {
+                State::Empty => {
+                    *state = State::Given(value);
+                    Ok(())
+                }
+                _ => Err(value),
+            }
+        });
+
+        if given.is_ok() {
+            self.waker.wake();
+        }
+
+        given
+    }
+
+    /// Take the value if it has been given.
+    pub fn try_take(&self) -> Result<T, TryTakeError> {
+        critical_section::with(|_| {
+            // SAFETY: in a cs: exclusive access
+            let state = unsafe { &mut *self.state.get() };
+            match state {
+                State::Empty => Err(TryTakeError::Empty),
+                State::Taken => Err(TryTakeError::AlreadyTaken),
+                State::Given(_) => match core::mem::replace(state, State::Taken) {
+                    State::Given(value) => Ok(value),
+                    _ => unreachable!(),
+                },
+            }
+        })
+    }
+
+    /// Wait for the value to be given and take it.
+    ///
+    /// Only one task at a time may wait for the value.
+    pub async fn take(&self) -> Result<T, AlreadyTaken> {
+        poll_fn(|ctx| {
+            self.waker.register(ctx.waker());
+            match self.try_take() {
+                Ok(value) => Poll::Ready(Ok(value)),
+                Err(TryTakeError::AlreadyTaken) => Poll::Ready(Err(AlreadyTaken)),
+                Err(TryTakeError::Empty) => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    /// Check if the value has been given, whether it has been taken or not.
+    pub fn is_given(&self) -> bool {
+        // SAFETY: in a cs: exclusive access
+        critical_section::with(|_| unsafe { !matches!(*self.state.get(), State::Empty) })
+    }
+
+    /// Check if the value has been taken.
+    pub fn is_taken(&self) -> bool {
+        // SAFETY: in a cs: exclusive access
+        critical_section::with(|_| unsafe { matches!(*self.state.get(), State::Taken) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_shot() {
+        let handoff = Handoff::new();
+
+        assert_eq!(handoff.try_take(), Err(TryTakeError::Empty));
+        assert_eq!(handoff.give(1u32), Ok(()));
+        assert_eq!(handoff.give(2), Err(2));
+        assert!(handoff.is_given() && !handoff.is_taken());
+
+        assert_eq!(handoff.try_take(), Ok(1));
+        assert_eq!(handoff.try_take(), Err(TryTakeError::AlreadyTaken));
+        assert_eq!(handoff.give(3), Err(3));
+        assert!(handoff.is_taken());
+    }
+
+    #[tokio::test]
+    async fn waiting() {
+        static HANDOFF: Handoff<u32> = Handoff::new();
+
+        let handle = tokio::spawn(HANDOFF.take());
+
+        tokio::task::yield_now().await; // encourage tokio executor to poll the taking future
+        assert!(!handle.is_finished());
+
+        HANDOFF.give(0xaa).unwrap();
+
+        assert_eq!(handle.await.unwrap(), Ok(0xaa));
+        assert_eq!(HANDOFF.take().await, Err(AlreadyTaken));
+    }
+}
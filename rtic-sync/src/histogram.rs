@@ -0,0 +1,212 @@
+//! A fixed-bucket histogram for latency measurements.
+//!
+//! A [`Histogram`] is a `static` recorded into from any priority with a single atomic add per
+//! sample, e.g. the response time of a task measured in its interrupt handler. A task reads it
+//! through a [`Snapshot`] for percentiles, and decays it to weigh recent samples more.
+//!
+//! ```ignore
+//! /// Response times in microseconds, the samples above 1 ms land in the overflow
+//! static LATENCY: Histogram<6> = Histogram::new([10, 20, 50, 100, 200, 1000]);
+//!
+//! #[task(binds = UART0, priority = 3)]
+//! fn uart0(_: uart0::Context) {
+//!     LATENCY.record((Mono::now() - arrival()).ticks());
+//! }
+//!
+//! #[task(priority = 1)]
+//! async fn telemetry(_: telemetry::Context) {
+//!     loop {
+//!         Mono::delay(10.secs()).await;
+//!         let p99 = LATENCY.snapshot().percentile(99);
+//!         // Report `p99`, then halve the counts
+//!         LATENCY.decay(1);
+//!     }
+//! }
+//! ```
+
+use portable_atomic::{AtomicU32, Ordering};
+
+#[cfg(feature = "defmt-03")]
+use crate::defmt;
+
+/// The counts of samples in `N` buckets with fixed upper bounds, recorded from any priority
+/// without a lock.
+///
+/// Bucket `i` counts the samples above `bounds[i - 1]` up to `bounds[i]`, the samples above the
+/// last bound are counted in the overflow. The counts wrap around at `u32::MAX`, a task decaying
+/// them keeps them far below that.
+pub struct Histogram<const N: usize> {
+    bounds: [u32; N],
+    counts: [AtomicU32; N],
+    overflow: AtomicU32,
+}
+
+impl<const N: usize> Histogram<N> {
+    /// Create an empty histogram with the upper `bounds` of its buckets, in increasing order.
+    ///
+    /// # Panics
+    ///
+    /// If the bounds are not increasing, at compile time for a `static`.
+    pub const fn new(bounds: [u32; N]) -> Self {
+        let mut i = 1;
+        while i < N {
+            assert!(bounds[i - 1] < bounds[i], "the bounds must be increasing");
+            i += 1;
+        }
+
+        Self {
+            bounds,
+            counts: [const { AtomicU32::new(0) }; N],
+            overflow: AtomicU32::new(0),
+        }
+    }
+
+    /// The upper bounds of the buckets.
+    pub fn bounds(&self) -> &[u32; N] {
+        &self.bounds
+    }
+
+    /// Count a sample in its bucket.
+    #[inline]
+    pub fn record(&self, value: u32) {
+        let bucket = self.bounds.partition_point(|bound| *bound < value);
+        self.counts
+            .get(bucket)
+            .unwrap_or(&self.overflow)
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Read the counts.
+    ///
+    /// Samples recorded during the read may be missing from it, or counted in the higher
+    /// buckets only.
+    pub fn snapshot(&self) -> Snapshot<N> {
+        Snapshot {
+            bounds: self.bounds,
+            counts: core::array::from_fn(|i| self.counts[i].load(Ordering::Relaxed)),
+            overflow: self.overflow.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Divide the counts by `2^shift`, rounding down.
+    ///
+    /// Called periodically this weighs the recent samples more, samples recorded during the
+    /// decay are kept in full. `shift` 32 or more clears the histogram.
+    pub fn decay(&self, shift: u32) {
+        for count in self.counts.iter().chain([&self.overflow]) {
+            let current = count.load(Ordering::Relaxed);
+            let kept = current.checked_shr(shift).unwrap_or(0);
+            count.fetch_sub(current - kept, Ordering::Relaxed);
+        }
+    }
+
+    /// Clear the counts, samples recorded during the reset may be kept.
+    pub fn reset(&self) {
+        self.decay(u32::MAX);
+    }
+}
+
+/// The counts of a [`Histogram`] at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt-03", derive(defmt::Format))]
+pub struct Snapshot<const N: usize> {
+    /// The upper bounds of the buckets.
+    pub bounds: [u32; N],
+    /// The samples counted in each bucket.
+    pub counts: [u32; N],
+    /// The samples above the last bound.
+    pub overflow: u32,
+}
+
+impl<const N: usize> Snapshot<N> {
+    /// The number of samples.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().map(|count| *count as u64).sum::<u64>() + self.overflow as u64
+    }
+
+    /// The upper bound of the bucket holding the `percent` percentile of the samples, e.g. 99
+    /// for the 99th percentile.
+    ///
+    /// `None` without samples, or if the percentile is above the last bound.
+    pub fn percentile(&self, percent: u8) -> Option<u32> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+
+        // The rank of the sample at the percentile, at least the first
+        let rank = (total * percent.min(100) as u64).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (bound, count) in self.bounds.iter().zip(self.counts) {
+            seen += count as u64;
+            if seen >= rank {
+                return Some(*bound);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets() {
+        let histogram = Histogram::new([10, 20, 50]);
+        for value in [0, 10, 11, 20, 49, 50, 51, u32::MAX] {
+            histogram.record(value);
+        }
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.counts, [2, 2, 2]);
+        assert_eq!(snapshot.overflow, 2);
+        assert_eq!(snapshot.total(), 8);
+    }
+
+    #[test]
+    fn percentiles() {
+        let histogram = Histogram::new([10, 20, 50]);
+        assert_eq!(histogram.snapshot().percentile(50), None);
+
+        for _ in 0..90 {
+            histogram.record(5);
+        }
+        for _ in 0..9 {
+            histogram.record(30);
+        }
+        histogram.record(100);
+
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.percentile(0), Some(10));
+        assert_eq!(snapshot.percentile(90), Some(10));
+        assert_eq!(snapshot.percentile(91), Some(50));
+        assert_eq!(snapshot.percentile(99), Some(50));
+        assert_eq!(snapshot.percentile(100), None);
+    }
+
+    #[test]
+    fn decay() {
+        let histogram = Histogram::new([10, 20]);
+        for _ in 0..7 {
+            histogram.record(10);
+        }
+        for _ in 0..4 {
+            histogram.record(100);
+        }
+
+        histogram.decay(1);
+        assert_eq!(histogram.snapshot().counts, [3, 0]);
+        assert_eq!(histogram.snapshot().overflow, 2);
+
+        histogram.reset();
+        assert_eq!(histogram.snapshot().total(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "the bounds must be increasing")]
+    fn unordered_bounds() {
+        Histogram::new([10, 10]);
+    }
+}
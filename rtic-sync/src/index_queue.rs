@@ -0,0 +1,258 @@
+//! A wait-free single producer, single consumer queue of slot indices.
+//!
+//! The queue passes `u8` indices into a pool of slots owned by the application, the payloads
+//! themselves never move. Two queues make a zero-copy pipeline: one returns free slots to the
+//! producer, the other passes filled slots to the consumer.
+//!
+//! ```ignore
+//! static mut FREE: IndexQueue<4> = IndexQueue::filled();
+//! static mut READY: IndexQueue<4> = IndexQueue::new();
+//! static POOL: [Buffer; 4] = ..;
+//!
+//! // Producer, e.g. a DMA complete interrupt
+//! if let Some(i) = free_rx.dequeue() {
+//!     // Fill `POOL[i]`
+//!     ready_tx.enqueue(i).ok();
+//! }
+//!
+//! // Consumer, e.g. a lower priority task
+//! while let Some(i) = ready_rx.dequeue() {
+//!     // Process `POOL[i]`
+//!     free_tx.enqueue(i).ok();
+//! }
+//! ```
+//!
+//! Neither side ever waits for the other or takes a critical section, so both ends may be used
+//! from tasks of any priority, including from tasks that preempt each other.
+
+use portable_atomic::{AtomicU8, AtomicUsize, Ordering};
+
+/// A wait-free queue of up to `N` indices, `N` is at most 256.
+pub struct IndexQueue<const N: usize> {
+    /// Position of the next dequeue, modulo `2 * N`.
+    head: AtomicUsize,
+    /// Position of the next enqueue, modulo `2 * N`.
+    tail: AtomicUsize,
+    buffer: [AtomicU8; N],
+}
+
+impl<const N: usize> Default for IndexQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> IndexQueue<N> {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: AtomicU8 = AtomicU8::new(0);
+
+    /// Create an empty queue.
+    pub const fn new() -> Self {
+        assert!(N > 0 && N <= 256, "the capacity must be in 1..=256");
+
+        Self {
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            buffer: [Self::EMPTY; N],
+        }
+    }
+
+    /// Create a queue holding every index `0..N` in order, e.g. the free list of a pool.
+    pub const fn filled() -> Self {
+        let mut queue = Self::new();
+
+        let mut i = 0;
+        while i < N {
+            queue.buffer[i] = AtomicU8::new(i as u8);
+            i += 1;
+        }
+
+        Self {
+            tail: AtomicUsize::new(N),
+            ..queue
+        }
+    }
+
+    /// The maximum number of indices in the queue.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// The number of indices in the queue.
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        (tail + 2 * N - head) % (2 * N)
+    }
+
+    /// Checks if the queue is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Checks if the queue is full.
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+
+    /// Split the queue into its producer and consumer ends.
+    pub fn split(&mut self) -> (Producer<'_, N>, Consumer<'_, N>) {
+        (Producer { queue: self }, Consumer { queue: self })
+    }
+}
+
+/// The enqueueing end of an [`IndexQueue`].
+pub struct Producer<'a, const N: usize> {
+    queue: &'a IndexQueue<N>,
+}
+
+impl<const N: usize> Producer<'_, N> {
+    /// Enqueue `index`, gives it back if the queue is full.
+    #[inline]
+    pub fn enqueue(&mut self, index: u8) -> Result<(), u8> {
+        let queue = self.queue;
+        let tail = queue.tail.load(Ordering::Relaxed);
+        let head = queue.head.load(Ordering::Acquire);
+
+        if (tail + 2 * N - head) % (2 * N) == N {
+            return Err(index);
+        }
+
+        queue.buffer[tail % N].store(index, Ordering::Relaxed);
+        // Publish the index together with the new tail.
+        queue.tail.store((tail + 1) % (2 * N), Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Checks if an enqueue would succeed.
+    #[inline]
+    pub fn ready(&self) -> bool {
+        !self.queue.is_full()
+    }
+
+    /// The number of indices in the queue.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Checks if the queue is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+/// The dequeueing end of an [`IndexQueue`].
+pub struct Consumer<'a, const N: usize> {
+    queue: &'a IndexQueue<N>,
+}
+
+impl<const N: usize> Consumer<'_, N> {
+    /// Dequeue the oldest index.
+    #[inline]
+    pub fn dequeue(&mut self) -> Option<u8> {
+        let queue = self.queue;
+        let head = queue.head.load(Ordering::Relaxed);
+        let tail = queue.tail.load(Ordering::Acquire);
+
+        if head == tail {
+            return None;
+        }
+
+        let index = queue.buffer[head % N].load(Ordering::Relaxed);
+        // Hand the slot of the buffer back to the producer once it is read.
+        queue.head.store((head + 1) % (2 * N), Ordering::Release);
+
+        Some(index)
+    }
+
+    /// The oldest index, without dequeueing it.
+    #[inline]
+    pub fn peek(&self) -> Option<u8> {
+        let queue = self.queue;
+        let head = queue.head.load(Ordering::Relaxed);
+        let tail = queue.tail.load(Ordering::Acquire);
+
+        (head != tail).then(|| queue.buffer[head % N].load(Ordering::Relaxed))
+    }
+
+    /// Checks if a dequeue would succeed.
+    #[inline]
+    pub fn ready(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    /// The number of indices in the queue.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Checks if the queue is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fifo_and_wrap() {
+        let mut queue = IndexQueue::<3>::new();
+        let (mut tx, mut rx) = queue.split();
+
+        assert_eq!(rx.dequeue(), None);
+        for round in 0..10u8 {
+            tx.enqueue(round).unwrap();
+            tx.enqueue(round + 1).unwrap();
+            assert_eq!(rx.peek(), Some(round));
+            assert_eq!(rx.dequeue(), Some(round));
+            assert_eq!(rx.dequeue(), Some(round + 1));
+            assert!(rx.is_empty());
+        }
+    }
+
+    #[test]
+    fn full() {
+        let mut queue = IndexQueue::<2>::filled();
+        assert!(queue.is_full());
+
+        let (mut tx, mut rx) = queue.split();
+        assert_eq!(tx.enqueue(7), Err(7));
+        assert_eq!(rx.dequeue(), Some(0));
+        tx.enqueue(7).unwrap();
+        assert_eq!(tx.len(), 2);
+        assert_eq!(rx.dequeue(), Some(1));
+        assert_eq!(rx.dequeue(), Some(7));
+        assert_eq!(rx.dequeue(), None);
+    }
+
+    #[test]
+    fn threads() {
+        static mut QUEUE: IndexQueue<4> = IndexQueue::new();
+        // SAFETY: The only reference to the queue.
+        let (mut tx, mut rx) = unsafe { (*core::ptr::addr_of_mut!(QUEUE)).split() };
+
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                for i in 0..=255u8 {
+                    while tx.enqueue(i).is_err() {}
+                }
+            });
+
+            for i in 0..=255u8 {
+                let received = loop {
+                    if let Some(received) = rx.dequeue() {
+                        break received;
+                    }
+                };
+                assert_eq!(received, i);
+            }
+        });
+    }
+}
@@ -0,0 +1,362 @@
+//! A channel between the two cores of the RP2040, carried by the SIO inter-core FIFO.
+//!
+//! The applications of both cores share a `static` [`Channel`] of the message type. The sending
+//! core takes the [`Sender`], which writes each message as one word into its TX FIFO, and the
+//! receiving core takes the [`Receiver`], an async task woken by the `SIO_IRQ_PROCn` interrupt of
+//! the FIFO. Messages are [`Word`]s, so nothing is allocated and both sides agree on the type.
+//!
+//! ```ignore
+//! static TO_CORE1: Channel<u32> = Channel::new();
+//!
+//! // Core 0
+//! #[task(priority = 1, local = [sender])]
+//! async fn produce(cx: produce::Context) {
+//!     cx.local.sender.send(42).await;
+//! }
+//!
+//! // Core 1
+//! #[task(binds = SIO_IRQ_PROC1, priority = 3)]
+//! fn fifo(_: fifo::Context) {
+//!     TO_CORE1.on_interrupt();
+//! }
+//!
+//! #[task(priority = 1, local = [receiver])]
+//! async fn consume(cx: consume::Context) {
+//!     loop {
+//!         let value = cx.local.receiver.recv().await;
+//!         // ..
+//!     }
+//! }
+//! ```
+//!
+//! The FIFO of each direction carries one channel, which takes it over: a core receiving on a
+//! channel can not also use the FIFO doorbell of `rtic::rp2040` for cross-core spawns. The FIFO is
+//! 8 words deep, [`Sender::try_send`] returns [`Full`] when the receiving core has not kept up.
+
+use core::{future::poll_fn, marker::PhantomData, task::Poll};
+use rtic_common::waker_registration::CriticalSectionWakerRegistration;
+
+/// A message that fits the 32 bit word of the FIFO.
+pub trait Word: Copy {
+    /// The word written to the FIFO.
+    fn to_word(self) -> u32;
+
+    /// The message read back from the word written by [`Word::to_word`].
+    fn from_word(word: u32) -> Self;
+}
+
+macro_rules! word {
+    ($($t:ty),*) => {
+        $(
+            impl Word for $t {
+                #[inline]
+                fn to_word(self) -> u32 {
+                    self as u32
+                }
+
+                #[inline]
+                fn from_word(word: u32) -> Self {
+                    word as $t
+                }
+            }
+        )*
+    };
+}
+
+word!(u8, u16, u32, i8, i16, i32);
+
+impl Word for bool {
+    #[inline]
+    fn to_word(self) -> u32 {
+        self as u32
+    }
+
+    #[inline]
+    fn from_word(word: u32) -> Self {
+        word != 0
+    }
+}
+
+/// The TX FIFO to the other core is full, the message was not sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Full<T>(pub T);
+
+/// A channel from one core of the RP2040 to the other.
+pub struct Channel<T: Word> {
+    waker: CriticalSectionWakerRegistration,
+    taken: critical_section::Mutex<core::cell::Cell<(bool, bool)>>,
+    _message: PhantomData<fn(T) -> T>,
+}
+
+impl<T: Word> Default for Channel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Word> Channel<T> {
+    /// Create a new channel.
+    pub const fn new() -> Self {
+        Self {
+            waker: CriticalSectionWakerRegistration::new(),
+            taken: critical_section::Mutex::new(core::cell::Cell::new((false, false))),
+            _message: PhantomData,
+        }
+    }
+
+    /// Takes the sending end, on the core sending the messages.
+    ///
+    /// Returns `None` if it was taken before.
+    pub fn sender(&self) -> Option<Sender<'_, T>> {
+        self.take(|taken| &mut taken.0)
+            .then_some(Sender { _channel: self })
+    }
+
+    /// Takes the receiving end, on the core receiving the messages.
+    ///
+    /// Returns `None` if it was taken before.
+    pub fn receiver(&self) -> Option<Receiver<'_, T>> {
+        self.take(|taken| &mut taken.1)
+            .then_some(Receiver { channel: self })
+    }
+
+    fn take(&self, end: impl FnOnce(&mut (bool, bool)) -> &mut bool) -> bool {
+        critical_section::with(|cs| {
+            let cell = self.taken.borrow(cs);
+            let mut taken = cell.get();
+            let free = !core::mem::replace(end(&mut taken), true);
+            cell.set(taken);
+            free
+        })
+    }
+
+    /// Wakes the [`Receiver`], call it from the hardware task bound to the `SIO_IRQ_PROCn` of
+    /// the receiving core.
+    ///
+    /// The interrupt is level triggered on a non-empty FIFO, so it is masked until the receiver
+    /// has emptied the FIFO.
+    pub fn on_interrupt(&self) {
+        fifo::mask();
+        self.waker.wake();
+    }
+}
+
+/// The sending end of a [`Channel`].
+pub struct Sender<'a, T: Word> {
+    _channel: &'a Channel<T>,
+}
+
+impl<T: Word> Sender<'_, T> {
+    /// Sends `value` if the FIFO has room.
+    pub fn try_send(&mut self, value: T) -> Result<(), Full<T>> {
+        if fifo::write(value.to_word()) {
+            Ok(())
+        } else {
+            Err(Full(value))
+        }
+    }
+
+    /// Sends `value`, waiting for the FIFO to have room.
+    ///
+    /// The FIFO has no interrupt when the other core makes room, so the task is polled again
+    /// right away while it is full.
+    pub async fn send(&mut self, value: T) {
+        poll_fn(|cx| {
+            if self.try_send(value).is_ok() {
+                Poll::Ready(())
+            } else {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+/// The receiving end of a [`Channel`].
+pub struct Receiver<'a, T: Word> {
+    channel: &'a Channel<T>,
+}
+
+impl<T: Word> Receiver<'_, T> {
+    /// Receives a message if one is in the FIFO.
+    pub fn try_recv(&mut self) -> Option<T> {
+        fifo::read().map(T::from_word)
+    }
+
+    /// Receives a message, waiting for the other core to send one.
+    pub async fn recv(&mut self) -> T {
+        poll_fn(|cx| {
+            if let Some(value) = self.try_recv() {
+                return Poll::Ready(value);
+            }
+
+            self.channel.waker.register(cx.waker());
+            fifo::unmask();
+
+            // A message written before the unmask raises the interrupt again, read it right away
+            match self.try_recv() {
+                Some(value) => Poll::Ready(value),
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(not(test))]
+mod fifo {
+    const SIO_BASE: usize = 0xd000_0000;
+    const CPUID: *const u32 = SIO_BASE as *const u32;
+    const FIFO_ST: *mut u32 = (SIO_BASE + 0x50) as *mut u32;
+    const FIFO_WR: *mut u32 = (SIO_BASE + 0x54) as *mut u32;
+    const FIFO_RD: *const u32 = (SIO_BASE + 0x58) as *const u32;
+    const NVIC_ISER: *mut u32 = 0xe000_e100 as *mut u32;
+    const NVIC_ICER: *mut u32 = 0xe000_e180 as *mut u32;
+
+    // `FIFO_ST` bits
+    const VLD: u32 = 1 << 0;
+    const RDY: u32 = 1 << 1;
+
+    /// `SIO_IRQ_PROC0` is interrupt 15, `SIO_IRQ_PROC1` is 16
+    fn irq() -> u32 {
+        // SAFETY: SIO registers are core-local and always accessible
+        1 << (15 + unsafe { CPUID.read_volatile() })
+    }
+
+    pub fn write(word: u32) -> bool {
+        // SAFETY: SIO registers are core-local and always accessible, the TX FIFO is only written
+        // by the sender on this core.
+        unsafe {
+            if FIFO_ST.read_volatile() & RDY == 0 {
+                return false;
+            }
+
+            // Make the memory the message may refer to visible before it
+            cortex_m::asm::dmb();
+            FIFO_WR.write_volatile(word);
+        }
+
+        // Wake the other core if it sleeps in `wfe`.
+        cortex_m::asm::sev();
+        true
+    }
+
+    pub fn read() -> Option<u32> {
+        // SAFETY: SIO registers are core-local and always accessible, the RX FIFO is only read by
+        // the receiver on this core.
+        unsafe {
+            // Any write clears the sticky `WOF` and `ROE` flags.
+            FIFO_ST.write_volatile(0);
+            (FIFO_ST.read_volatile() & VLD != 0).then(|| FIFO_RD.read_volatile())
+        }
+    }
+
+    pub fn mask() {
+        // SAFETY: writes to `ICER` only disable the FIFO interrupt of this core
+        unsafe { NVIC_ICER.write_volatile(irq()) };
+    }
+
+    pub fn unmask() {
+        // SAFETY: writes to `ISER` only enable the FIFO interrupt of this core, masked by
+        // `on_interrupt`
+        unsafe { NVIC_ISER.write_volatile(irq()) };
+    }
+}
+
+/// The FIFO of a single core looped back to itself
+#[cfg(test)]
+mod fifo {
+    use std::{cell::RefCell, collections::VecDeque};
+
+    std::thread_local! {
+        pub static FIFO: RefCell<VecDeque<u32>> = const { RefCell::new(VecDeque::new()) };
+        pub static MASKED: RefCell<bool> = const { RefCell::new(false) };
+    }
+
+    pub fn write(word: u32) -> bool {
+        FIFO.with_borrow_mut(|fifo| {
+            fifo.len() < 8 && {
+                fifo.push_back(word);
+                true
+            }
+        })
+    }
+
+    pub fn read() -> Option<u32> {
+        FIFO.with_borrow_mut(|fifo| fifo.pop_front())
+    }
+
+    pub fn mask() {
+        MASKED.set(true);
+    }
+
+    pub fn unmask() {
+        MASKED.set(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use static_cell::StaticCell;
+
+    use super::*;
+
+    #[test]
+    fn ends_taken_once() {
+        let channel = Channel::<u32>::new();
+
+        assert!(channel.sender().is_some());
+        assert!(channel.sender().is_none());
+        assert!(channel.receiver().is_some());
+        assert!(channel.receiver().is_none());
+    }
+
+    #[test]
+    fn words() {
+        let channel = Channel::<i16>::new();
+        let mut sender = channel.sender().unwrap();
+        let mut receiver = channel.receiver().unwrap();
+
+        sender.try_send(-2).unwrap();
+        sender.try_send(i16::MAX).unwrap();
+        assert_eq!(receiver.try_recv(), Some(-2));
+        assert_eq!(receiver.try_recv(), Some(i16::MAX));
+        assert_eq!(receiver.try_recv(), None);
+    }
+
+    #[test]
+    fn full() {
+        let channel = Channel::<u8>::new();
+        let mut sender = channel.sender().unwrap();
+        let mut receiver = channel.receiver().unwrap();
+
+        for i in 0..8 {
+            sender.try_send(i).unwrap();
+        }
+        assert_eq!(sender.try_send(8), Err(Full(8)));
+
+        assert_eq!(receiver.try_recv(), Some(0));
+        sender.try_send(8).unwrap();
+        while receiver.try_recv().is_some() {}
+    }
+
+    #[tokio::test]
+    async fn woken_by_interrupt() {
+        static CHANNEL: Channel<bool> = Channel::new();
+        static RECEIVER: StaticCell<Receiver<bool>> = StaticCell::new();
+        let mut sender = CHANNEL.sender().unwrap();
+        let receiver = RECEIVER.init(CHANNEL.receiver().unwrap());
+
+        let handle = tokio::spawn(receiver.recv());
+        tokio::task::yield_now().await;
+        assert!(!handle.is_finished());
+        assert!(!fifo::MASKED.with_borrow(|masked| *masked));
+
+        sender.send(true).await;
+        CHANNEL.on_interrupt();
+        assert!(fifo::MASKED.with_borrow(|masked| *masked));
+
+        assert!(handle.await.is_ok_and(|value| value));
+    }
+}
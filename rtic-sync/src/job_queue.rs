@@ -0,0 +1,452 @@
+//! A priority queue of jobs for a hardware engine that completes one job at a time.
+//!
+//! Crypto, AES and SHA accelerators take a job, raise an interrupt when it is done and are shared
+//! by many tasks. With a [`JobQueue`] the tasks [`submit`](JobQueue::submit) jobs and await their
+//! results, an engine task takes the most urgent job with [`next`](JobQueue::next) and starts the
+//! hardware, and the `binds` task of the completion interrupt hands the result back with
+//! [`complete`](JobQueue::complete).
+//!
+//! Example usage:
+//!
+//! ```ignore
+//! #[rtic::app(device = hal::pac, dispatchers = [EXTI0, EXTI1])]
+//! mod app {
+//!     use rtic_sync::job_queue::JobQueue;
+//!
+//!     pub enum Job {
+//!         Sha256(&'static [u8]),
+//!         Aes([u8; 16]),
+//!     }
+//!
+//!     pub enum Output {
+//!         Digest([u8; 32]),
+//!         Block([u8; 16]),
+//!     }
+//!
+//!     static CRYPTO: JobQueue<Job, Output, 4> = JobQueue::new();
+//!
+//!     #[task(shared = [engine], priority = 2)]
+//!     async fn engine(mut cx: engine::Context) {
+//!         loop {
+//!             // Waits until the previous job is complete
+//!             let job = CRYPTO.next().await;
+//!             cx.shared.engine.lock(|engine| engine.start(job));
+//!         }
+//!     }
+//!
+//!     #[task(binds = CRYPTO, shared = [engine], priority = 3)]
+//!     fn done(mut cx: done::Context) {
+//!         let output = cx.shared.engine.lock(|engine| engine.output());
+//!         CRYPTO.complete(output);
+//!     }
+//!
+//!     #[task(priority = 1)]
+//!     async fn logger(_: logger::Context) {
+//!         if let Output::Digest(digest) = CRYPTO.submit(0, Job::Sha256(b"log")).await {
+//!             // ..
+//!         }
+//!     }
+//! }
+//! ```
+
+use core::{
+    cell::UnsafeCell,
+    future::poll_fn,
+    pin::Pin,
+    task::{Poll, Waker},
+};
+use rtic_common::waker_registration::CriticalSectionWakerRegistration as WakerRegistration;
+use rtic_common::{
+    dropper::OnDrop,
+    wait_queue::{Link, WaitQueue},
+};
+
+/// A queue of up to `N` jobs `J` for a hardware engine producing results `R`.
+///
+/// Jobs are handed to the engine highest priority first, jobs of equal priority in the order they
+/// were submitted. All state is updated in short critical sections, jobs and results are moved
+/// but never copied around within the queue.
+pub struct JobQueue<J, R, const N: usize> {
+    state: UnsafeCell<State<J, R, N>>,
+    // Submitters waiting for a free slot.
+    wait_queue: WaitQueue,
+    // Waker for the engine task.
+    engine_waker: WakerRegistration,
+}
+
+unsafe impl<J, R, const N: usize> Send for JobQueue<J, R, N> {}
+
+unsafe impl<J, R, const N: usize> Sync for JobQueue<J, R, N> {}
+
+struct State<J, R, const N: usize> {
+    slots: [Slot<J, R>; N],
+    // Submission counter, orders jobs of equal priority.
+    seq: u32,
+    // Slot of the job the engine works on.
+    running: Option<usize>,
+}
+
+enum Slot<J, R> {
+    Free,
+    // Taken by a submitter that has not queued its job yet.
+    Reserved,
+    Queued {
+        priority: u8,
+        seq: u32,
+        job: J,
+        waker: Waker,
+    },
+    Running {
+        waker: Waker,
+    },
+    Done(R),
+    // The submitter is gone while the engine works on its job.
+    Abandoned,
+}
+
+impl<J, R, const N: usize> Default for JobQueue<J, R, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<J, R, const N: usize> JobQueue<J, R, N> {
+    /// Create a new job queue.
+    pub const fn new() -> Self {
+        Self {
+            state: UnsafeCell::new(State {
+                slots: [const { Slot::Free }; N],
+                seq: 0,
+                running: None,
+            }),
+            wait_queue: WaitQueue::new(),
+            engine_waker: WakerRegistration::new(),
+        }
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    fn state(&self, _cs: critical_section::CriticalSection) -> &mut State<J, R, N> {
+        // SAFETY: This is safe as are in a critical section.
+        unsafe { &mut *self.state.get() }
+    }
+
+    /// Submit `job` with `priority` and wait for its result, higher priorities run first.
+    ///
+    /// Waits for a free slot if `N` jobs are already queued. Dropping the future withdraws a
+    /// queued job, the result of a job the engine already works on is discarded.
+    pub async fn submit(&self, priority: u8, job: J) -> R {
+        let idx = self.slot().await;
+
+        let dropper = OnDrop::new(|| {
+            let freed = critical_section::with(|cs| {
+                let slot = &mut self.state(cs).slots[idx];
+                match slot {
+                    Slot::Running { .. } => {
+                        *slot = Slot::Abandoned;
+                        false
+                    }
+                    _ => {
+                        *slot = Slot::Free;
+                        true
+                    }
+                }
+            });
+
+            if freed {
+                self.wake_submitter();
+            }
+        });
+
+        let mut job = Some(job);
+        let result = poll_fn(|cx| {
+            critical_section::with(|cs| {
+                let state = self.state(cs);
+                let slot = &mut state.slots[idx];
+
+                match slot {
+                    Slot::Reserved => {
+                        *slot = Slot::Queued {
+                            priority,
+                            seq: state.seq,
+                            job: job.take().unwrap(),
+                            waker: cx.waker().clone(),
+                        };
+                        state.seq = state.seq.wrapping_add(1);
+                        self.engine_waker.wake();
+                        Poll::Pending
+                    }
+                    Slot::Queued { waker, .. } | Slot::Running { waker } => {
+                        if !waker.will_wake(cx.waker()) {
+                            *waker = cx.waker().clone();
+                        }
+                        Poll::Pending
+                    }
+                    Slot::Done(_) => match core::mem::replace(slot, Slot::Free) {
+                        Slot::Done(result) => Poll::Ready(result),
+                        _ => unreachable!(),
+                    },
+                    Slot::Free | Slot::Abandoned => unreachable!(),
+                }
+            })
+        })
+        .await;
+
+        dropper.defuse();
+        self.wake_submitter();
+
+        result
+    }
+
+    /// Wait for a free slot and reserve it, first come first served.
+    async fn slot(&self) -> usize {
+        let mut link_ptr: Option<Link<Waker>> = None;
+
+        // Make this future `Drop`-safe.
+        // SAFETY(link_ptr): Shadow the original definition of `link_ptr` so we can't abuse it.
+        let mut link_ptr = LinkPtr(&mut link_ptr as *mut Option<Link<Waker>>);
+
+        let mut link_ptr2 = link_ptr.clone();
+        let dropper = OnDrop::new(|| {
+            // SAFETY: We only run this closure and dereference the pointer if we have
+            // exited the `poll_fn` below in the `drop(dropper)` call. The other dereference
+            // of this pointer is in the `poll_fn`.
+            if let Some(link) = unsafe { link_ptr2.get() } {
+                if link.is_popped() {
+                    // Woken for a free slot but gone, pass the turn on
+                    self.wake_submitter();
+                } else {
+                    link.remove_from_list(&self.wait_queue);
+                }
+            }
+        });
+
+        let idx = poll_fn(|cx| {
+            critical_section::with(|cs| {
+                // SAFETY: This pointer is only dereferenced here and on drop of the future
+                // which happens outside this `poll_fn`'s stack frame.
+                let link = unsafe { link_ptr.get() };
+                let turn = match link {
+                    Some(link) => link.is_popped(),
+                    None => self.wait_queue.is_empty(),
+                };
+                let slots = &mut self.state(cs).slots;
+                let free = slots.iter().position(|slot| matches!(slot, Slot::Free));
+
+                match free {
+                    Some(idx) if turn => {
+                        slots[idx] = Slot::Reserved;
+                        *link = None;
+                        Poll::Ready(idx)
+                    }
+                    _ => {
+                        if link.as_ref().is_none_or(|link| link.is_popped()) {
+                            // Place the link in the wait queue, again if another submitter took
+                            // the slot it was woken for.
+                            let link_ref = link.insert(Link::new(cx.waker().clone()));
+
+                            // SAFETY(new_unchecked): The address to the link is stable as it is
+                            // defined outside this stack frame.
+                            // SAFETY(push): `link_ref` lifetime comes from `link_ptr` that is
+                            // shadowed, and we make sure in `dropper` that the link is removed
+                            // from the queue before dropping `link_ptr` AND `dropper` makes sure
+                            // that the shadowed `link_ptr` lives until the end of the stack frame.
+                            unsafe { self.wait_queue.push(Pin::new_unchecked(link_ref)) };
+                        }
+
+                        Poll::Pending
+                    }
+                }
+            })
+        })
+        .await;
+
+        // Make sure the link is removed from the queue.
+        drop(dropper);
+
+        idx
+    }
+
+    fn wake_submitter(&self) {
+        if let Some(waker) = self.wait_queue.pop() {
+            waker.wake();
+        }
+    }
+
+    /// Wait for the most urgent job once the engine is idle.
+    ///
+    /// The engine is busy from the return of this until [`complete`](Self::complete).
+    pub async fn next(&self) -> J {
+        poll_fn(|cx| {
+            critical_section::with(|cs| {
+                self.engine_waker.register(cx.waker());
+
+                let state = self.state(cs);
+                if state.running.is_some() {
+                    return Poll::Pending;
+                }
+
+                let seq = state.seq;
+                let next = state
+                    .slots
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(idx, slot)| match slot {
+                        // Earlier submissions have a larger distance to the counter
+                        Slot::Queued {
+                            priority, seq: s, ..
+                        } => Some((idx, (*priority, seq.wrapping_sub(*s)))),
+                        _ => None,
+                    })
+                    .max_by_key(|(_, key)| *key)
+                    .map(|(idx, _)| idx);
+
+                let Some(idx) = next else {
+                    return Poll::Pending;
+                };
+
+                let slot = &mut state.slots[idx];
+                match core::mem::replace(slot, Slot::Free) {
+                    Slot::Queued { job, waker, .. } => {
+                        *slot = Slot::Running { waker };
+                        state.running = Some(idx);
+                        Poll::Ready(job)
+                    }
+                    _ => unreachable!(),
+                }
+            })
+        })
+        .await
+    }
+
+    /// Hand the `result` of the running job to its submitter, from the completion interrupt.
+    ///
+    /// Returns `false` if no job is running, `result` is dropped.
+    pub fn complete(&self, result: R) -> bool {
+        let (waker, freed) = critical_section::with(|cs| {
+            let state = self.state(cs);
+            let Some(idx) = state.running.take() else {
+                return (None, false);
+            };
+
+            let slot = &mut state.slots[idx];
+            match core::mem::replace(slot, Slot::Done(result)) {
+                Slot::Running { waker } => (Some(waker), false),
+                _ => {
+                    *slot = Slot::Free;
+                    (None, true)
+                }
+            }
+        });
+
+        if freed {
+            self.wake_submitter();
+        }
+
+        self.engine_waker.wake();
+
+        match waker {
+            Some(waker) => {
+                waker.wake();
+                true
+            }
+            None => freed,
+        }
+    }
+
+    /// Is the engine working on a job.
+    pub fn is_busy(&self) -> bool {
+        critical_section::with(|cs| self.state(cs).running.is_some())
+    }
+}
+
+/// This is needed to make the async closure in `slot` accept that we "share"
+/// the link possible between threads.
+#[derive(Clone)]
+struct LinkPtr(*mut Option<Link<Waker>>);
+
+impl LinkPtr {
+    /// This will dereference the pointer stored within and give out an `&mut`.
+    unsafe fn get(&mut self) -> &mut Option<Link<Waker>> {
+        &mut *self.0
+    }
+}
+
+unsafe impl Send for LinkPtr {}
+
+unsafe impl Sync for LinkPtr {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cassette::Cassette;
+
+    #[test]
+    fn priority_order() {
+        let queue: JobQueue<u32, u32, 4> = JobQueue::new();
+
+        let low = core::pin::pin!(queue.submit(0, 1));
+        let mut low = Cassette::new(low);
+        let high = core::pin::pin!(queue.submit(5, 2));
+        let mut high = Cassette::new(high);
+        let low2 = core::pin::pin!(queue.submit(0, 3));
+        let mut low2 = Cassette::new(low2);
+        assert!(low.poll_on().is_none());
+        assert!(high.poll_on().is_none());
+        assert!(low2.poll_on().is_none());
+
+        let next = core::pin::pin!(queue.next());
+        assert_eq!(Cassette::new(next).poll_on(), Some(2));
+        assert!(queue.is_busy());
+
+        // The engine is busy until the job completes
+        let next = core::pin::pin!(queue.next());
+        let mut next = Cassette::new(next);
+        assert!(next.poll_on().is_none());
+
+        assert!(queue.complete(20));
+        assert_eq!(high.poll_on(), Some(20));
+        assert_eq!(next.poll_on(), Some(1));
+
+        assert!(queue.complete(10));
+        let next = core::pin::pin!(queue.next());
+        assert_eq!(Cassette::new(next).poll_on(), Some(3));
+        assert!(queue.complete(30));
+        assert_eq!(low2.poll_on(), Some(30));
+        assert_eq!(low.poll_on(), Some(10));
+
+        assert!(!queue.complete(0));
+    }
+
+    #[test]
+    fn full_and_abandoned() {
+        let queue: JobQueue<u32, u32, 1> = JobQueue::new();
+
+        {
+            let first = core::pin::pin!(queue.submit(0, 1));
+            let mut first = Cassette::new(first);
+            assert!(first.poll_on().is_none());
+
+            {
+                // Withdrawn while waiting for a slot
+                let second = core::pin::pin!(queue.submit(0, 2));
+                let mut second = Cassette::new(second);
+                assert!(second.poll_on().is_none());
+            }
+
+            let next = core::pin::pin!(queue.next());
+            assert_eq!(Cassette::new(next).poll_on(), Some(1));
+        }
+
+        let third = core::pin::pin!(queue.submit(0, 3));
+        let mut third = Cassette::new(third);
+        assert!(third.poll_on().is_none());
+
+        // The result of an abandoned job frees its slot for the next submitter
+        assert!(queue.complete(10));
+        assert!(third.poll_on().is_none());
+        let next = core::pin::pin!(queue.next());
+        assert_eq!(Cassette::new(next).poll_on(), Some(3));
+        assert!(queue.complete(30));
+        assert_eq!(third.poll_on(), Some(30));
+    }
+}
@@ -6,10 +6,27 @@
 #[cfg(feature = "defmt-03")]
 use defmt_03 as defmt;
 
+pub mod amp;
 pub mod arbiter;
+#[cfg(feature = "time")]
+pub mod blink;
 pub mod channel;
+pub mod counter;
+#[cfg(feature = "time")]
+pub mod encoder;
+#[cfg(feature = "flash")]
+pub mod flash;
+pub mod handoff;
+pub mod histogram;
+pub mod index_queue;
+#[cfg(any(feature = "rp2040", test))]
+pub mod inter_core;
+pub mod job_queue;
 pub use portable_atomic;
+pub mod scan;
+pub mod sdlog;
 pub mod signal;
+pub mod watch;
 
 #[cfg(test)]
 #[macro_use]
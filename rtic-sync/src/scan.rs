@@ -0,0 +1,193 @@
+//! Distribution of multi-channel sample frames, e.g. from a timer triggered ADC scan with DMA.
+//!
+//! The DMA writes each scan of `CH` channels into a frame buffer. The [`Producer`], called from
+//! the DMA completion interrupt, passes the filled buffer by reference through a
+//! [`Channel`](crate::channel::Channel) and hands a free one back for the next scan. The
+//! [`Distributor`], running in a task, publishes the reading of each channel on its own
+//! [`Watch`] and returns the buffer. Samples are never copied until a reader takes them.
+//!
+//! Example usage:
+//!
+//! ```ignore
+//! #[rtic::app(device = hal::pac, dispatchers = [EXTI0])]
+//! mod app {
+//!     use rtic_sync::{channel::{Receiver, Sender}, make_channel, scan, watch::Watch};
+//!
+//!     type Frame = &'static mut [u16; 4];
+//!
+//!     // Phase currents A, B, C and the DC link voltage
+//!     static READINGS: [Watch<u16>; 4] = [const { Watch::new() }; 4];
+//!
+//!     #[local]
+//!     struct Local {
+//!         dma: hal::dma::Transfer<Frame>,
+//!         producer: scan::Producer<'static, u16, 4, 3>,
+//!     }
+//!
+//!     #[init(local = [frames: [[u16; 4]; 3] = [[0; 4]; 3]])]
+//!     fn init(cx: init::Context) -> (Shared, Local) {
+//!         let (frames_tx, frames_rx) = make_channel!(Frame, 3);
+//!         let (mut free_tx, free_rx) = make_channel!(Frame, 3);
+//!
+//!         let [first, rest @ ..] = cx.local.frames;
+//!         for frame in rest {
+//!             free_tx.try_send(frame).ok();
+//!         }
+//!
+//!         // The timer triggers the scan, the DMA writes it to `first`
+//!         let dma = hal::adc::scan(cx.device.ADC1, cx.device.TIM1, first);
+//!
+//!         distribute::spawn(scan::Distributor::new(frames_rx, free_tx)).ok();
+//!
+//!         (Shared {}, Local { dma, producer: scan::Producer::new(frames_tx, free_rx) })
+//!     }
+//!
+//!     #[task(binds = DMA1_CHANNEL1, local = [dma, producer], priority = 3)]
+//!     fn scan_done(cx: scan_done::Context) {
+//!         let next = cx.local.producer.next_frame(cx.local.dma.current());
+//!         cx.local.dma.restart(next);
+//!     }
+//!
+//!     #[task(priority = 2)]
+//!     async fn distribute(_: distribute::Context, mut d: scan::Distributor<'static, u16, 4, 3>) {
+//!         d.run(&READINGS).await;
+//!     }
+//! }
+//! ```
+
+use crate::channel::{Receiver, Sender};
+use crate::watch::Watch;
+
+/// A frame buffer of one sample per channel.
+pub type Frame<T, const CH: usize> = &'static mut [T; CH];
+
+/// The interrupt side of a scan, passing filled frames on.
+pub struct Producer<'a, T: 'static, const CH: usize, const N: usize> {
+    frames: Sender<'a, Frame<T, CH>, N>,
+    free: Receiver<'a, Frame<T, CH>, N>,
+    overruns: u32,
+}
+
+impl<'a, T: 'static, const CH: usize, const N: usize> Producer<'a, T, CH, N> {
+    /// Create a producer sending filled frames to `frames` and taking buffers from `free`.
+    pub fn new(frames: Sender<'a, Frame<T, CH>, N>, free: Receiver<'a, Frame<T, CH>, N>) -> Self {
+        Self {
+            frames,
+            free,
+            overruns: 0,
+        }
+    }
+
+    /// Pass the filled `frame` on and return the buffer for the next scan.
+    ///
+    /// If the distribution lags and there is no free buffer, `frame` is dropped and returned for
+    /// reuse, which counts as an overrun. Never waits, so it can be called from the DMA
+    /// completion interrupt.
+    pub fn next_frame(&mut self, frame: Frame<T, CH>) -> Frame<T, CH> {
+        if self.frames.is_full() {
+            self.overruns = self.overruns.wrapping_add(1);
+            return frame;
+        }
+
+        match self.free.try_recv() {
+            Ok(next) => {
+                // Only fails if the distribution is gone, the frame is not needed then
+                self.frames.try_send(frame).ok();
+                next
+            }
+            Err(_) => {
+                self.overruns = self.overruns.wrapping_add(1);
+                frame
+            }
+        }
+    }
+
+    /// The number of frames dropped because the distribution lagged.
+    pub fn overruns(&self) -> u32 {
+        self.overruns
+    }
+}
+
+/// The task side of a scan, publishing the channels of each frame.
+pub struct Distributor<'a, T: 'static, const CH: usize, const N: usize> {
+    frames: Receiver<'a, Frame<T, CH>, N>,
+    free: Sender<'a, Frame<T, CH>, N>,
+}
+
+impl<'a, T: Copy + 'static, const CH: usize, const N: usize> Distributor<'a, T, CH, N> {
+    /// Create a distributor receiving filled frames from `frames` and returning them to `free`.
+    pub fn new(frames: Receiver<'a, Frame<T, CH>, N>, free: Sender<'a, Frame<T, CH>, N>) -> Self {
+        Self { frames, free }
+    }
+
+    /// Wait for the next frame and publish channel `i` on `watches[i]`.
+    ///
+    /// Returns `false` once the [`Producer`] is gone.
+    pub async fn distribute(&mut self, watches: &[Watch<T>; CH]) -> bool {
+        let Ok(frame) = self.frames.recv().await else {
+            return false;
+        };
+
+        for (watch, sample) in watches.iter().zip(frame.iter()) {
+            watch.sender().send(*sample);
+        }
+
+        // There is room for every buffer in the free channel
+        self.free.try_send(frame).ok();
+
+        true
+    }
+
+    /// Distribute frames until the [`Producer`] is gone.
+    pub async fn run(&mut self, watches: &[Watch<T>; CH]) {
+        while self.distribute(watches).await {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::make_channel;
+
+    #[tokio::test]
+    async fn scan() {
+        static WATCHES: [Watch<u16>; 2] = [const { Watch::new() }; 2];
+        static mut FRAMES: [[u16; 2]; 2] = [[0; 2]; 2];
+
+        let (frames_tx, frames_rx) = make_channel!(Frame<u16, 2>, 2);
+        let (mut free_tx, free_rx) = make_channel!(Frame<u16, 2>, 2);
+
+        // SAFETY: The only reference to the frames
+        let [first, second] = unsafe { &mut *core::ptr::addr_of_mut!(FRAMES) };
+        free_tx.try_send(second).ok();
+
+        let mut producer = Producer::new(frames_tx, free_rx);
+        let mut distributor = Distributor::new(frames_rx, free_tx);
+        let mut a = WATCHES[0].receiver();
+        let mut b = WATCHES[1].receiver();
+
+        // The DMA fills the first frame
+        *first = [1, 2];
+        let next = producer.next_frame(first);
+
+        // The distribution lags, the next frame is dropped
+        *next = [3, 4];
+        let next = producer.next_frame(next);
+        assert_eq!(producer.overruns(), 1);
+
+        assert!(distributor.distribute(&WATCHES).await);
+        assert_eq!((a.try_changed(), b.try_changed()), (Some(1), Some(2)));
+
+        // The buffer is back for the scan after the next one
+        *next = [5, 6];
+        let next = producer.next_frame(next);
+        assert_eq!(producer.overruns(), 1);
+        assert_eq!(*next, [1, 2]);
+
+        assert!(distributor.distribute(&WATCHES).await);
+        assert_eq!((a.try_changed(), b.try_changed()), (Some(5), Some(6)));
+
+        drop(producer);
+        assert!(!distributor.distribute(&WATCHES).await);
+    }
+}
@@ -0,0 +1,340 @@
+//! Write-behind logging to an SD card.
+//!
+//! A write to an SD card blocks for milliseconds, and far longer while the card erases or the FAT
+//! is updated, which is too long for a real-time task. With [`SdLog`] the tasks only copy their
+//! records into a ring with [`log`](SdLog::log), which never blocks, and a low priority task runs
+//! [`run`](SdLog::run) to write the ring to the card behind them, preempted by every other task.
+//!
+//! The card itself is behind the [`Card`] trait, implemented by the application over its SD/FAT
+//! stack, e.g. an `embedded-sdmmc` volume manager and an open file. The card detect interrupt
+//! reports insertion and removal with [`detect`](SdLog::detect): records are kept in the ring
+//! while there is no card and written once one is inserted.
+//!
+//! ```ignore
+//! static LOG: SdLog<4096> = SdLog::new();
+//!
+//! #[task(binds = EXTI0, priority = 3, local = [detect_pin])]
+//! fn card_detect(cx: card_detect::Context) {
+//!     LOG.detect(cx.local.detect_pin.is_low());
+//! }
+//!
+//! #[task(priority = 2)]
+//! async fn control(_: control::Context) {
+//!     LOG.log(b"setpoint reached\n").ok();
+//! }
+//!
+//! #[task(priority = 1, local = [card])]
+//! async fn writer(cx: writer::Context) {
+//!     loop {
+//!         let _error = LOG.run(cx.local.card).await;
+//!         // The card failed while inserted, e.g. retry after a delay
+//!     }
+//! }
+//! ```
+
+use core::{cell::RefCell, future::poll_fn, task::Poll};
+use critical_section::Mutex;
+use heapless::Deque;
+use rtic_common::waker_registration::CriticalSectionWakerRegistration;
+
+/// The most bytes written to the card at once, one SD block.
+pub const BLOCK: usize = 512;
+
+/// The log file on the card, implemented over the SD/FAT stack of the application.
+pub trait Card {
+    /// Error of the card.
+    type Error;
+
+    /// Mount the volume and open the log file for appending.
+    fn open(&mut self) -> Result<(), Self::Error>;
+
+    /// Append `bytes` to the log file.
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Flush the log file, updating its directory entry.
+    fn flush(&mut self) -> Result<(), Self::Error>;
+
+    /// Drop the open file and volume, the card has been removed.
+    fn close(&mut self);
+}
+
+/// The ring has no room for the record, it has been dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt-03", derive(defmt::Format))]
+pub struct Full;
+
+struct State<const N: usize> {
+    ring: Deque<u8, N>,
+    present: bool,
+    lost: usize,
+}
+
+/// A ring of `N` bytes of records written to an SD card by a low priority task.
+pub struct SdLog<const N: usize> {
+    state: Mutex<RefCell<State<N>>>,
+    waker: CriticalSectionWakerRegistration,
+}
+
+impl<const N: usize> Default for SdLog<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> SdLog<N> {
+    /// Create an empty log, without a card.
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(RefCell::new(State {
+                ring: Deque::new(),
+                present: false,
+                lost: 0,
+            })),
+            waker: CriticalSectionWakerRegistration::new(),
+        }
+    }
+
+    /// Queue `record` for the card, from any priority.
+    ///
+    /// A record is never cut: it is dropped as a whole and counted in [`lost`](Self::lost) if the
+    /// ring has no room for it.
+    pub fn log(&self, record: &[u8]) -> Result<(), Full> {
+        let logged = critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            if N - state.ring.len() < record.len() {
+                state.lost += 1;
+                return Err(Full);
+            }
+
+            for byte in record {
+                // Room checked above
+                state.ring.push_back(*byte).ok();
+            }
+            Ok(())
+        });
+
+        if logged.is_ok() {
+            self.waker.wake();
+        }
+
+        logged
+    }
+
+    /// Report the card as inserted or removed, from the card detect task.
+    pub fn detect(&self, present: bool) {
+        critical_section::with(|cs| self.state.borrow_ref_mut(cs).present = present);
+        self.waker.wake();
+    }
+
+    /// Check if a card is inserted, as last reported to [`detect`](Self::detect).
+    pub fn is_present(&self) -> bool {
+        critical_section::with(|cs| self.state.borrow_ref(cs).present)
+    }
+
+    /// The number of records dropped as the ring was full.
+    pub fn lost(&self) -> usize {
+        critical_section::with(|cs| self.state.borrow_ref(cs).lost)
+    }
+
+    /// The number of bytes waiting to be written.
+    pub fn pending(&self) -> usize {
+        critical_section::with(|cs| self.state.borrow_ref(cs).ring.len())
+    }
+
+    /// Write the ring to `card` behind the logging tasks, from a low priority task.
+    ///
+    /// Opens the card once inserted, writes the records in blocks of up to [`BLOCK`] bytes,
+    /// flushes once the ring is empty and closes the card once removed. Bytes leave the ring once
+    /// written, so a write cut by a removal is repeated on the next card.
+    ///
+    /// Returns the error of a card failing while inserted, with the card closed. Only one task may
+    /// run the log at a time.
+    pub async fn run<C: Card>(&self, card: &mut C) -> C::Error {
+        let mut open = false;
+        let mut dirty = false;
+        let mut block = [0; BLOCK];
+
+        loop {
+            let (present, len) = poll_fn(|cx| {
+                self.waker.register(cx.waker());
+                critical_section::with(|cs| {
+                    let state = self.state.borrow_ref(cs);
+                    let len = copy_front(&state.ring, &mut block);
+                    let work = if state.present {
+                        !open || len > 0 || dirty
+                    } else {
+                        open
+                    };
+
+                    if work {
+                        Poll::Ready((state.present, len))
+                    } else {
+                        Poll::Pending
+                    }
+                })
+            })
+            .await;
+
+            if !present {
+                card.close();
+                open = false;
+                dirty = false;
+                continue;
+            }
+
+            let result = if !open {
+                card.open().map(|()| open = true)
+            } else if len == 0 {
+                card.flush().map(|()| dirty = false)
+            } else {
+                card.write(&block[..len]).map(|()| {
+                    critical_section::with(|cs| {
+                        let mut state = self.state.borrow_ref_mut(cs);
+                        for _ in 0..len {
+                            state.ring.pop_front();
+                        }
+                    });
+                    dirty = true;
+                })
+            };
+
+            // A failure of a removed card is handled as the removal on the next turn
+            if let Err(e) = result {
+                if self.is_present() {
+                    if open {
+                        card.close();
+                    }
+                    return e;
+                }
+            }
+        }
+    }
+}
+
+/// Copies the front of the ring into `block`, returns the number of bytes copied.
+fn copy_front<const N: usize>(ring: &Deque<u8, N>, block: &mut [u8]) -> usize {
+    let (front, back) = ring.as_slices();
+    let first = front.len().min(block.len());
+    block[..first].copy_from_slice(&front[..first]);
+    let second = back.len().min(block.len() - first);
+    block[first..first + second].copy_from_slice(&back[..second]);
+    first + second
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[derive(Default)]
+    struct Ram {
+        data: Vec<u8>,
+        ops: Vec<&'static str>,
+        fail: bool,
+        /// Reports the removal of the card to this log when a write fails
+        removed: Option<&'static SdLog<16>>,
+    }
+
+    impl Card for Ram {
+        type Error = ();
+
+        fn open(&mut self) -> Result<(), ()> {
+            self.ops.push("open");
+            Ok(())
+        }
+
+        fn write(&mut self, bytes: &[u8]) -> Result<(), ()> {
+            self.ops.push("write");
+            if self.fail {
+                if let Some(log) = self.removed {
+                    log.detect(false);
+                }
+                return Err(());
+            }
+            self.data.extend_from_slice(bytes);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), ()> {
+            self.ops.push("flush");
+            Ok(())
+        }
+
+        fn close(&mut self) {
+            self.ops.push("close");
+        }
+    }
+
+    fn poll<T>(future: impl core::future::Future<Output = T>) -> Option<T> {
+        let mut future = core::pin::pin!(future);
+        cassette::Cassette::new(&mut future).poll_on()
+    }
+
+    #[test]
+    fn write_behind() {
+        static LOG: SdLog<16> = SdLog::new();
+        let mut card = Ram::default();
+
+        {
+            let mut run = core::pin::pin!(LOG.run(&mut card));
+            let mut cassette = cassette::Cassette::new(&mut run);
+
+            // Kept in the ring until a card is inserted
+            LOG.log(b"one\n").unwrap();
+            assert!(cassette.poll_on().is_none());
+            assert_eq!(LOG.pending(), 4);
+
+            LOG.detect(true);
+            LOG.log(b"two\n").unwrap();
+            assert!(cassette.poll_on().is_none());
+            assert_eq!(LOG.pending(), 0);
+
+            assert_eq!(LOG.log(&[0; 17]), Err(Full));
+            assert_eq!(LOG.lost(), 1);
+
+            LOG.detect(false);
+            assert!(cassette.poll_on().is_none());
+        }
+
+        assert_eq!(card.data, b"one\ntwo\n");
+        assert_eq!(card.ops, ["open", "write", "flush", "close"]);
+    }
+
+    #[test]
+    fn removed_while_writing() {
+        static LOG: SdLog<16> = SdLog::new();
+        let mut card = Ram {
+            fail: true,
+            removed: Some(&LOG),
+            ..Ram::default()
+        };
+
+        LOG.log(b"kept\n").unwrap();
+        LOG.detect(true);
+        assert!(poll(LOG.run(&mut card)).is_none());
+        assert_eq!(card.ops, ["open", "write", "close"]);
+        assert_eq!(LOG.pending(), 5);
+
+        // Written again to the next card
+        card.fail = false;
+        LOG.detect(true);
+        assert!(poll(LOG.run(&mut card)).is_none());
+        assert_eq!(card.data, b"kept\n");
+        assert_eq!(LOG.pending(), 0);
+    }
+
+    #[test]
+    fn failed_while_inserted() {
+        static LOG: SdLog<16> = SdLog::new();
+        let mut card = Ram {
+            fail: true,
+            ..Ram::default()
+        };
+
+        LOG.log(b"kept\n").unwrap();
+        LOG.detect(true);
+        assert_eq!(poll(LOG.run(&mut card)), Some(()));
+        assert_eq!(card.ops, ["open", "write", "close"]);
+        assert_eq!(LOG.pending(), 5);
+    }
+}
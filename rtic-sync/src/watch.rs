@@ -0,0 +1,285 @@
+//! A "latest only" value store with unlimited writers and unlimited readers.
+//!
+//! Unlike a [`Signal`](crate::signal::Signal), reading does not evict the value: every
+//! [`WatchReceiver`] sees each new value once, and can read the latest value at any time.
+
+use core::{cell::UnsafeCell, future::poll_fn, pin::Pin, task::Poll, task::Waker};
+use rtic_common::{
+    dropper::OnDrop,
+    wait_queue::{Link, WaitQueue},
+};
+
+struct Store<T> {
+    value: Option<T>,
+    // Incremented on every write, receivers compare it to the version they have seen.
+    version: u32,
+}
+
+/// A "latest only" value store with unlimited writers and unlimited readers.
+pub struct Watch<T: Copy> {
+    store: UnsafeCell<Store<T>>,
+    // Receivers waiting for a new value.
+    wait_queue: WaitQueue,
+}
+
+impl<T: Copy> Default for Watch<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<T: Copy> Send for Watch<T> {}
+unsafe impl<T: Copy> Sync for Watch<T> {}
+
+impl<T: Copy> Watch<T> {
+    /// Create a new watch without a value.
+    pub const fn new() -> Self {
+        Self {
+            store: UnsafeCell::new(Store {
+                value: None,
+                version: 0,
+            }),
+            wait_queue: WaitQueue::new(),
+        }
+    }
+
+    /// Create a writer for the watch.
+    pub fn sender(&self) -> WatchSender<'_, T> {
+        WatchSender { parent: self }
+    }
+
+    /// Create a reader for the watch, the current value counts as not seen yet.
+    pub fn receiver(&self) -> WatchReceiver<'_, T> {
+        WatchReceiver {
+            parent: self,
+            seen: 0,
+        }
+    }
+
+    /// Read the latest value and its version.
+    fn read(&self) -> (Option<T>, u32) {
+        critical_section::with(|_| {
+            // SAFETY: in a cs: exclusive access
+            let store = unsafe { &*self.store.get() };
+            (store.value, store.version)
+        })
+    }
+}
+
+/// Fascilitates the writing of values to a Watch.
+#[derive(Clone)]
+pub struct WatchSender<'a, T: Copy> {
+    parent: &'a Watch<T>,
+}
+
+impl<'a, T: Copy> WatchSender<'a, T> {
+    /// Write a value to the Watch and wake all waiting readers.
+    pub fn send(&mut self, value: T) {
+        critical_section::with(|_| {
+            // SAFETY: in a cs: exclusive access
+            let store = unsafe { &mut *self.parent.store.get() };
+            store.value = Some(value);
+            store.version = store.version.wrapping_add(1);
+        });
+
+        while let Some(waker) = self.parent.wait_queue.pop() {
+            waker.wake();
+        }
+    }
+}
+
+/// Fascilitates the async reading of values from the Watch.
+#[derive(Clone)]
+pub struct WatchReceiver<'a, T: Copy> {
+    parent: &'a Watch<T>,
+    seen: u32,
+}
+
+impl<'a, T: Copy> WatchReceiver<'a, T> {
+    /// Returns the latest value, or None if no value has been written yet.
+    ///
+    /// The value is not evicted, and counts as seen by this reader.
+    pub fn get(&mut self) -> Option<T> {
+        let (value, version) = self.parent.read();
+        self.seen = version;
+        value
+    }
+
+    /// Returns the latest value if this reader has not seen it yet.
+    pub fn try_changed(&mut self) -> Option<T> {
+        match self.parent.read() {
+            (Some(value), version) if version != self.seen => {
+                self.seen = version;
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Wait for a value this reader has not seen yet and read it.
+    ///
+    /// If such a value is already stored it will be returned immediately.
+    pub async fn changed(&mut self) -> T {
+        let mut link_ptr: Option<Link<Waker>> = None;
+
+        // Make this future `Drop`-safe.
+        // SAFETY(link_ptr): Shadow the original definition of `link_ptr` so we can't abuse it.
+        let mut link_ptr = LinkPtr(&mut link_ptr as *mut Option<Link<Waker>>);
+
+        let mut link_ptr2 = link_ptr.clone();
+        let parent = self.parent;
+        let dropper = OnDrop::new(|| {
+            // SAFETY: We only run this closure and dereference the pointer if we have
+            // exited the `poll_fn` below in the `drop(dropper)` call. The other dereference
+            // of this pointer is in the `poll_fn`.
+            if let Some(link) = unsafe { link_ptr2.get() } {
+                if !link.is_popped() {
+                    link.remove_from_list(&parent.wait_queue);
+                }
+            }
+        });
+
+        let value = poll_fn(|cx| {
+            critical_section::with(|_| {
+                if let Some(value) = self.try_changed() {
+                    return Poll::Ready(value);
+                }
+
+                // SAFETY: This pointer is only dereferenced here and on drop of the future
+                // which happens outside this `poll_fn`'s stack frame.
+                let link = unsafe { link_ptr.get() };
+                if link.as_ref().is_none_or(|link| link.is_popped()) {
+                    // Place the link in the wait queue, again after a wake for a value that was
+                    // overwritten by one already seen.
+                    let link_ref = link.insert(Link::new(cx.waker().clone()));
+
+                    // SAFETY(new_unchecked): The address to the link is stable as it is defined
+                    // outside this stack frame.
+                    // SAFETY(push): `link_ref` lifetime comes from `link_ptr` that is shadowed,
+                    // and we make sure in `dropper` that the link is removed from the queue
+                    // before dropping `link_ptr` AND `dropper` makes sure that the shadowed
+                    // `link_ptr` lives until the end of the stack frame.
+                    unsafe { parent.wait_queue.push(Pin::new_unchecked(link_ref)) };
+                }
+
+                Poll::Pending
+            })
+        })
+        .await;
+
+        // Make sure the link is removed from the queue.
+        drop(dropper);
+
+        value
+    }
+
+    /// Wait until the latest value satisfies `predicate` and read it, e.g. a temperature below a
+    /// threshold.
+    ///
+    /// The latest value is checked first, seen by this reader or not, then each new value. Values
+    /// overwritten before this reader is polled are never checked.
+    pub async fn wait_for(&mut self, mut predicate: impl FnMut(&T) -> bool) -> T {
+        if let Some(value) = self.get() {
+            if predicate(&value) {
+                return value;
+            }
+        }
+
+        loop {
+            let value = self.changed().await;
+            if predicate(&value) {
+                return value;
+            }
+        }
+    }
+}
+
+/// This is needed to make the async closure in `changed` accept that we "share"
+/// the link possible between threads.
+#[derive(Clone)]
+struct LinkPtr(*mut Option<Link<Waker>>);
+
+impl LinkPtr {
+    /// This will dereference the pointer stored within and give out an `&mut`.
+    unsafe fn get(&mut self) -> &mut Option<Link<Waker>> {
+        &mut *self.0
+    }
+}
+
+unsafe impl Send for LinkPtr {}
+
+unsafe impl Sync for LinkPtr {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        static WATCH: Watch<u32> = Watch::new();
+        let mut reader = WATCH.receiver();
+
+        assert!(reader.get().is_none());
+        assert!(reader.try_changed().is_none());
+    }
+
+    #[test]
+    fn all_readers_see_latest() {
+        static WATCH: Watch<u32> = Watch::new();
+        let mut writer = WATCH.sender();
+        let mut a = WATCH.receiver();
+        let mut b = WATCH.receiver();
+
+        writer.send(0xde);
+        writer.send(0xad);
+        assert_eq!(a.try_changed(), Some(0xad));
+        assert_eq!(a.try_changed(), None);
+        assert_eq!(a.get(), Some(0xad));
+        assert_eq!(b.try_changed(), Some(0xad));
+    }
+
+    #[tokio::test]
+    async fn waiting() {
+        static WATCH: Watch<u32> = Watch::new();
+        let mut writer = WATCH.sender();
+
+        writer.send(0xaa);
+
+        let mut handles = std::vec::Vec::new();
+        for _ in 0..2 {
+            let mut reader = WATCH.receiver();
+            reader.get();
+            handles.push(tokio::spawn(async move { reader.changed().await }));
+        }
+
+        tokio::task::yield_now().await; // encourage tokio executor to poll reader futures
+        assert!(handles.iter().all(|handle| !handle.is_finished()));
+
+        writer.send(0xab);
+
+        for handle in handles {
+            assert!(handle.await.is_ok_and(|value| value == 0xab));
+        }
+    }
+
+    #[tokio::test]
+    async fn wait_for_predicate() {
+        static WATCH: Watch<i32> = Watch::new();
+        let mut writer = WATCH.sender();
+        let mut reader = WATCH.receiver();
+
+        // Already satisfied, even if seen
+        writer.send(15);
+        reader.get();
+        assert_eq!(reader.wait_for(|t| *t < 20).await, 15);
+
+        let handle = tokio::spawn(async move { reader.wait_for(|t| *t < 10).await });
+
+        writer.send(12);
+        tokio::task::yield_now().await;
+        assert!(!handle.is_finished());
+
+        writer.send(8);
+        assert_eq!(handle.await.unwrap(), 8);
+    }
+}
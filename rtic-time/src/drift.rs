@@ -0,0 +1,184 @@
+//! Drift of a monotonic measured against a second one.
+//!
+//! Before trusting long delays on new hardware, a low priority task can check the clock
+//! configuration of a monotonic against an independent timer, e.g. SysTick from the PLL against
+//! an RTC from a 32.768 kHz crystal. A wrong prescaler shows up as a large drift, a poorly
+//! trimmed oscillator as a few hundred ppm, and a spread between intervals as jitter.
+//!
+//! ```ignore
+//! #[task(priority = 1)]
+//! async fn clock_check(_: clock_check::Context) {
+//!     // RTC ticks are 1 / 32 768 s, SysTick ticks 1 ms
+//!     let mut meter = DriftMeter::<Rtc, 1, 32_768, Mono, 1, 1_000>::new();
+//!
+//!     loop {
+//!         let stats = meter.next(10.secs()).await;
+//!         defmt::info!("drift {} ppm, jitter {} ppm", stats.drift_ppm, stats.jitter_ppm());
+//!     }
+//! }
+//! ```
+
+use crate::Monotonic;
+
+/// The drift of the measured monotonic relative to the reference.
+///
+/// Positive values mean the measured monotonic counts too fast.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DriftStats {
+    /// The intervals sampled since the first sample.
+    pub intervals: u32,
+    /// The drift over all intervals, in parts per million.
+    pub drift_ppm: i32,
+    /// The lowest drift of a single interval, in parts per million.
+    pub min_ppm: i32,
+    /// The highest drift of a single interval, in parts per million.
+    pub max_ppm: i32,
+}
+
+impl DriftStats {
+    /// The spread of the drift between intervals, in parts per million.
+    pub fn jitter_ppm(&self) -> u32 {
+        self.max_ppm.abs_diff(self.min_ppm)
+    }
+}
+
+/// Measures the drift of `Measured` against `Reference`.
+///
+/// `R_NOM / R_DENOM` and `M_NOM / M_DENOM` are the lengths of a tick of `Reference` and
+/// `Measured` in seconds, e.g. `1, 1_000_000` for a monotonic counting microseconds, as in
+/// their [`fugit`] duration types.
+pub struct DriftMeter<
+    Reference: Monotonic,
+    const R_NOM: u32,
+    const R_DENOM: u32,
+    Measured: Monotonic,
+    const M_NOM: u32,
+    const M_DENOM: u32,
+> {
+    start: Option<(Reference::Instant, Measured::Instant)>,
+    last: Option<(Reference::Instant, Measured::Instant)>,
+    stats: DriftStats,
+}
+
+impl<
+        Reference,
+        const R_NOM: u32,
+        const R_DENOM: u32,
+        Measured,
+        const M_NOM: u32,
+        const M_DENOM: u32,
+    > Default for DriftMeter<Reference, R_NOM, R_DENOM, Measured, M_NOM, M_DENOM>
+where
+    Reference: Monotonic,
+    Reference::Duration: Into<fugit::Duration<u64, R_NOM, R_DENOM>>,
+    Measured: Monotonic,
+    Measured::Duration: Into<fugit::Duration<u64, M_NOM, M_DENOM>>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<
+        Reference,
+        const R_NOM: u32,
+        const R_DENOM: u32,
+        Measured,
+        const M_NOM: u32,
+        const M_DENOM: u32,
+    > DriftMeter<Reference, R_NOM, R_DENOM, Measured, M_NOM, M_DENOM>
+where
+    Reference: Monotonic,
+    Reference::Duration: Into<fugit::Duration<u64, R_NOM, R_DENOM>>,
+    Measured: Monotonic,
+    Measured::Duration: Into<fugit::Duration<u64, M_NOM, M_DENOM>>,
+{
+    /// Create a meter without samples.
+    pub const fn new() -> Self {
+        Self {
+            start: None,
+            last: None,
+            stats: DriftStats {
+                intervals: 0,
+                drift_ppm: 0,
+                min_ppm: 0,
+                max_ppm: 0,
+            },
+        }
+    }
+
+    /// The statistics of the intervals sampled so far, all zero before the second sample.
+    pub fn stats(&self) -> DriftStats {
+        self.stats
+    }
+
+    /// Forget the samples, e.g. after changing the clock configuration.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// The drift of `measured` against `reference`, durations of both monotonics.
+    fn ppm(reference: Reference::Duration, measured: Measured::Duration) -> i32 {
+        // In units of 1 / (R_DENOM * M_DENOM) s, exact for any two tick rates
+        let reference =
+            u128::from(reference.into().ticks()) * u128::from(R_NOM) * u128::from(M_DENOM);
+        let measured =
+            u128::from(measured.into().ticks()) * u128::from(M_NOM) * u128::from(R_DENOM);
+
+        let ppm = (measured as i128 - reference as i128) * 1_000_000 / reference.max(1) as i128;
+        ppm.clamp(i32::MIN as i128, i32::MAX as i128) as i32
+    }
+
+    /// Sample both monotonics, read as close together as possible, e.g. in a critical section.
+    ///
+    /// The first sample only starts the measurement. Each later one ends an interval and returns
+    /// the updated statistics. Keep the intervals much shorter than the period over which either
+    /// monotonic wraps around.
+    pub fn sample(
+        &mut self,
+        reference: Reference::Instant,
+        measured: Measured::Instant,
+    ) -> Option<DriftStats> {
+        let (Some((start_reference, start_measured)), Some((last_reference, last_measured))) =
+            (self.start, self.last)
+        else {
+            self.start = Some((reference, measured));
+            self.last = Some((reference, measured));
+            return None;
+        };
+
+        let interval = Self::ppm(reference - last_reference, measured - last_measured);
+        let stats = &mut self.stats;
+        if stats.intervals == 0 {
+            stats.min_ppm = interval;
+            stats.max_ppm = interval;
+        } else {
+            stats.min_ppm = stats.min_ppm.min(interval);
+            stats.max_ppm = stats.max_ppm.max(interval);
+        }
+        stats.intervals = stats.intervals.saturating_add(1);
+        stats.drift_ppm = Self::ppm(reference - start_reference, measured - start_measured);
+
+        self.last = Some((reference, measured));
+
+        Some(self.stats)
+    }
+
+    /// Wait for `interval` of the reference, then sample both monotonics.
+    ///
+    /// Takes the first sample before waiting if there is none yet.
+    pub async fn next(&mut self, interval: Reference::Duration) -> DriftStats {
+        let now = || critical_section::with(|_| (Reference::now(), Measured::now()));
+
+        if self.last.is_none() {
+            let (reference, measured) = now();
+            self.sample(reference, measured);
+        }
+
+        let (last_reference, _) = self.last.expect("sampled above");
+        Reference::delay_until(last_reference + interval).await;
+
+        let (reference, measured) = now();
+        self.sample(reference, measured).expect("second sample")
+    }
+}
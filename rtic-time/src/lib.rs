@@ -7,14 +7,19 @@
 #![deny(missing_docs)]
 #![allow(async_fn_in_trait)]
 
+pub mod drift;
 pub mod half_period_counter;
 mod linked_list;
 pub mod monotonic;
+pub mod phase_lock;
+pub mod retry;
 pub mod timer_queue;
 
 /// This indicates that there was a timeout.
 pub struct TimeoutError;
 
+pub use retry::retry;
+
 /// Re-export for macros
 pub use embedded_hal;
 /// Re-export for macros
@@ -61,4 +66,47 @@ pub trait Monotonic {
         duration: Self::Duration,
         future: F,
     ) -> Result<F::Output, TimeoutError>;
+
+    /// Optional. Marks the start of a period in which the monotonic may stop counting, e.g. a
+    /// low-power mode that turns off the clock of its timer.
+    fn suspend() {}
+
+    /// Optional. Ends a period started with [`suspend`](Self::suspend).
+    ///
+    /// `correction` is the time that passed since `suspend` according to a clock that kept
+    /// running, e.g. an RTC. The monotonic accounts for the part of it that it did not count, so
+    /// queued deadlines do not slip by the time spent suspended.
+    fn resume(correction: Self::Duration) {
+        let _ = correction;
+    }
+
+    /// Optional. Corrects the frequency of the timer by `ppm` parts per million, e.g. from a
+    /// temperature measurement of an uncompensated oscillator. Positive values correct a timer
+    /// that counts too fast.
+    ///
+    /// The correction replaces the previous one and applies from now on, to [`now`](Self::now)
+    /// and to the deadlines already queued.
+    fn set_frequency_correction(ppm: i32) {
+        let _ = ppm;
+    }
+
+    /// Optional. Checks that the interrupt of the monotonic has not been missed and recovers from
+    /// it, to be called periodically at a priority below the monotonic, e.g. from `idle`.
+    ///
+    /// Returns `true` if a stall was found.
+    fn check_stall() -> bool {
+        false
+    }
+
+    /// Optional. The number of stalls found by [`check_stall`](Self::check_stall).
+    fn stall_recoveries() -> u32 {
+        0
+    }
+
+    /// Optional. Delays each deadline awaited from now on by a pseudo-random amount up to `max`,
+    /// `None` stops, to exercise the handling of late timers.
+    #[cfg(feature = "fault-injection")]
+    fn set_expiry_jitter(max: Option<Self::Duration>) {
+        let _ = max;
+    }
 }
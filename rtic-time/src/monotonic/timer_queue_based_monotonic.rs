@@ -54,6 +54,31 @@ impl<T: TimerQueueBasedMonotonic> Monotonic for T {
             .timeout_after(duration.ticks(), future)
             .await
     }
+
+    fn suspend() {
+        T::Backend::timer_queue().suspend();
+    }
+
+    fn resume(correction: Self::Duration) {
+        T::Backend::timer_queue().resume(correction.ticks());
+    }
+
+    fn set_frequency_correction(ppm: i32) {
+        T::Backend::timer_queue().set_frequency_correction(ppm);
+    }
+
+    fn check_stall() -> bool {
+        T::Backend::timer_queue().check_stall()
+    }
+
+    fn stall_recoveries() -> u32 {
+        T::Backend::timer_queue().stall_recoveries()
+    }
+
+    #[cfg(feature = "fault-injection")]
+    fn set_expiry_jitter(max: Option<Self::Duration>) {
+        T::Backend::timer_queue().set_expiry_jitter(max.map(|max| max.ticks()));
+    }
 }
 
 /// An instant that can be used in [`TimerQueueBasedMonotonic`].
@@ -0,0 +1,97 @@
+//! Phase locking of a task to a hardware event.
+//!
+//! Motor control runs its control law at a fixed offset to a timer event, e.g. 10 µs after each
+//! PWM update, when the current measurement has settled. The `binds` task of the event records
+//! when it [`arrived`](PhaseLock::arrived) and the control task waits for the
+//! [`next`](PhaseLock::next) event plus the offset, so its phase follows the timer rather than
+//! drifting with the software.
+//!
+//! ```ignore
+//! static PWM_UPDATE: PhaseLock<Mono> = PhaseLock::new();
+//!
+//! #[task(binds = TIM1_UP, local = [pwm])]
+//! fn pwm_update(cx: pwm_update::Context) {
+//!     PWM_UPDATE.arrived(Mono::now());
+//!     cx.local.pwm.clear_update_flag();
+//! }
+//!
+//! #[task(priority = 3)]
+//! async fn control(_: control::Context) {
+//!     loop {
+//!         PWM_UPDATE.next(10.micros()).await;
+//!         // Sample the phase currents and run the control law
+//!     }
+//! }
+//! ```
+
+use crate::Monotonic;
+use core::{cell::Cell, future::poll_fn, task::Poll};
+use critical_section::Mutex;
+use rtic_common::waker_registration::CriticalSectionWakerRegistration;
+
+/// The arrivals of a hardware event that a task locks its phase to.
+pub struct PhaseLock<Mono: Monotonic> {
+    // The arrival not yet waited for.
+    arrival: Mutex<Cell<Option<Mono::Instant>>>,
+    missed: Mutex<Cell<u32>>,
+    waker: CriticalSectionWakerRegistration,
+}
+
+impl<Mono: Monotonic> Default for PhaseLock<Mono> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Mono: Monotonic> PhaseLock<Mono> {
+    /// Create a new phase lock without arrivals.
+    pub const fn new() -> Self {
+        Self {
+            arrival: Mutex::new(Cell::new(None)),
+            missed: Mutex::new(Cell::new(0)),
+            waker: CriticalSectionWakerRegistration::new(),
+        }
+    }
+
+    /// Record that the event arrived at `at`, called by the task bound to the event.
+    ///
+    /// Take `at` as early as possible in the handler, so the offset is measured from the event
+    /// rather than from the handler running.
+    pub fn arrived(&self, at: Mono::Instant) {
+        critical_section::with(|cs| {
+            if self.arrival.borrow(cs).replace(Some(at)).is_some() {
+                let missed = self.missed.borrow(cs);
+                missed.set(missed.get().wrapping_add(1));
+            }
+        });
+
+        self.waker.wake();
+    }
+
+    /// Wait for the next arrival of the event, then until `offset` after it.
+    ///
+    /// Returns the instant the wait ended at, the arrival plus `offset`. An arrival before the
+    /// call counts if it was not waited for yet, if more than one did the latest is used.
+    pub async fn next(&self, offset: Mono::Duration) -> Mono::Instant {
+        let arrival = poll_fn(|cx| {
+            self.waker.register(cx.waker());
+
+            match critical_section::with(|cs| self.arrival.borrow(cs).take()) {
+                Some(arrival) => Poll::Ready(arrival),
+                None => Poll::Pending,
+            }
+        })
+        .await;
+
+        let at = arrival + offset;
+        Mono::delay_until(at).await;
+
+        at
+    }
+
+    /// The number of arrivals that were replaced by a later one before they were waited for,
+    /// i.e. events the task was too slow for.
+    pub fn missed(&self) -> u32 {
+        critical_section::with(|cs| self.missed.borrow(cs).get())
+    }
+}
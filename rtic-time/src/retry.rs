@@ -0,0 +1,163 @@
+//! Retrying fallible async operations with exponential backoff.
+//!
+//! A task talking to a flaky sensor or a network stack can hand the operation to [`retry`]
+//! instead of writing its own loop. Between attempts it waits on the monotonic, doubling the
+//! wait up to a maximum, and the waits are randomized so that devices which failed together do
+//! not retry in lockstep. An overall deadline bounds the attempts and the waits together.
+//!
+//! ```ignore
+//! #[task(priority = 1, local = [sensor])]
+//! async fn sample(cx: sample::Context) {
+//!     let sensor = &*cx.local.sensor;
+//!     let policy = Policy::<Mono>::new(5, 10.millis(), 500.millis())
+//!         .deadline(2.secs())
+//!         .seed(device_id());
+//!
+//!     match rtic_time::retry(policy, || sensor.read()).await {
+//!         Ok(value) => defmt::info!("{}", value),
+//!         Err(RetryError::Exhausted(e)) => defmt::warn!("sensor failed: {}", e),
+//!         Err(RetryError::Deadline(_)) => defmt::warn!("sensor timed out"),
+//!     }
+//! }
+//! ```
+
+use crate::Monotonic;
+use core::future::Future;
+use core::ops::{Add, Div, Mul};
+
+/// Why [`retry`] gave up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryError<E> {
+    /// Every attempt failed, with the error of the last one.
+    Exhausted(E),
+    /// The deadline passed, with the error of the last attempt that completed, if any.
+    Deadline(Option<E>),
+}
+
+/// How often and how long [`retry`] waits between the attempts of an operation.
+pub struct Policy<Mono: Monotonic> {
+    attempts: u32,
+    backoff: Mono::Duration,
+    max_backoff: Mono::Duration,
+    deadline: Option<Mono::Duration>,
+    state: u32,
+}
+
+impl<Mono: Monotonic> Clone for Policy<Mono> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Mono: Monotonic> Copy for Policy<Mono> {}
+
+impl<Mono> Policy<Mono>
+where
+    Mono: Monotonic,
+    Mono::Duration: Ord
+        + Add<Output = Mono::Duration>
+        + Mul<u32, Output = Mono::Duration>
+        + Div<u32, Output = Mono::Duration>,
+{
+    /// At most `attempts` attempts, waiting `backoff` after the first failure and twice as long
+    /// after each following one, up to `max_backoff`.
+    ///
+    /// Each wait is randomized between half of the backoff and the backoff itself.
+    pub fn new(attempts: u32, backoff: Mono::Duration, max_backoff: Mono::Duration) -> Self {
+        Self {
+            attempts,
+            backoff,
+            max_backoff,
+            deadline: None,
+            state: 0x9e37_79b9,
+        }
+    }
+
+    /// Gives up once `timeout` has passed since the start of the first attempt, cancelling an
+    /// attempt still running and not starting a wait that would end after it.
+    pub fn deadline(mut self, timeout: Mono::Duration) -> Self {
+        self.deadline = Some(timeout);
+        self
+    }
+
+    /// Seeds the randomization of the waits, e.g. with a unique ID of the device, so that
+    /// devices using the same policy spread their retries differently.
+    pub fn seed(mut self, seed: u32) -> Self {
+        // xorshift never leaves zero
+        self.state = if seed == 0 { 0x9e37_79b9 } else { seed };
+        self
+    }
+
+    fn random(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// The wait after the current failure, and the backoff of the next one
+    fn next_wait(&mut self) -> Mono::Duration {
+        let half = self.backoff / 2;
+        let wait = half + (half / 16) * (self.random() % 17);
+
+        self.backoff = if self.backoff > self.max_backoff / 2 {
+            self.max_backoff
+        } else {
+            self.backoff * 2
+        };
+
+        wait.min(self.max_backoff)
+    }
+}
+
+/// Runs `operation` until it succeeds, waiting between the attempts as given by `policy`.
+///
+/// At least one attempt is made, even with a policy of zero attempts.
+pub async fn retry<Mono, T, E, F, Fut>(
+    mut policy: Policy<Mono>,
+    mut operation: F,
+) -> Result<T, RetryError<E>>
+where
+    Mono: Monotonic,
+    Mono::Duration: Ord
+        + Add<Output = Mono::Duration>
+        + Mul<u32, Output = Mono::Duration>
+        + Div<u32, Output = Mono::Duration>,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let end = policy.deadline.map(|timeout| Mono::now() + timeout);
+    let mut attempt = 1;
+    let mut last = None;
+
+    loop {
+        let result = match end {
+            Some(end) => match Mono::timeout_at(end, operation()).await {
+                Ok(result) => result,
+                Err(_) => return Err(RetryError::Deadline(last)),
+            },
+            None => operation().await,
+        };
+
+        let error = match result {
+            Ok(value) => return Ok(value),
+            Err(error) => error,
+        };
+
+        if attempt >= policy.attempts {
+            return Err(RetryError::Exhausted(error));
+        }
+
+        let wait = policy.next_wait();
+        let until = Mono::now() + wait;
+        if end.is_some_and(|end| until >= end) {
+            return Err(RetryError::Deadline(Some(error)));
+        }
+
+        last = Some(error);
+        Mono::delay_until(until).await;
+        attempt += 1;
+    }
+}
@@ -3,21 +3,27 @@
 use crate::linked_list::{self, Link, LinkedList};
 use crate::TimeoutError;
 
+use core::cell::Cell;
 use core::future::{poll_fn, Future};
 use core::pin::Pin;
 use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use core::task::{Poll, Waker};
+use critical_section::Mutex;
 use futures_util::{
     future::{select, Either},
     pin_mut,
 };
 use rtic_common::dropper::OnDrop;
+use rtic_common::wake_source::{self, WakeSource};
 
 mod backend;
+mod correction;
 mod tick_type;
 pub use backend::TimerQueueBackend;
 pub use tick_type::TimerQueueTicks;
 
+use correction::Correction;
+
 /// Holds a waker and at which time instant this waker shall be awoken.
 struct WaitingWaker<Backend: TimerQueueBackend> {
     waker: Waker,
@@ -65,6 +71,19 @@ impl<Backend: TimerQueueBackend> PartialOrd for WaitingWaker<Backend> {
 pub struct TimerQueue<Backend: TimerQueueBackend> {
     queue: LinkedList<WaitingWaker<Backend>>,
     initialized: AtomicBool,
+    /// Set once the time of the backend needs correction
+    corrected: AtomicBool,
+    /// Maps the time of the backend to the time of the timer queue
+    correction: Mutex<Cell<Option<Correction<Backend::Ticks>>>>,
+    /// Backend time at which the timer queue was suspended
+    suspended_at: Mutex<Cell<Option<Backend::Ticks>>>,
+    /// Number of stalls found by `check_stall`
+    stall_recoveries: Mutex<Cell<u32>>,
+    /// Largest delay injected into the deadlines
+    #[cfg(feature = "fault-injection")]
+    jitter: Mutex<Cell<Option<Backend::Ticks>>>,
+    #[cfg(feature = "fault-injection")]
+    random: rtic_common::fault::Random,
 }
 
 /// This is needed to make the async closure in `delay_until` accept that we "share"
@@ -99,13 +118,177 @@ impl<Backend: TimerQueueBackend> TimerQueue<Backend> {
         Self {
             queue: LinkedList::new(),
             initialized: AtomicBool::new(false),
+            corrected: AtomicBool::new(false),
+            correction: Mutex::new(Cell::new(None)),
+            suspended_at: Mutex::new(Cell::new(None)),
+            stall_recoveries: Mutex::new(Cell::new(0)),
+            #[cfg(feature = "fault-injection")]
+            jitter: Mutex::new(Cell::new(None)),
+            #[cfg(feature = "fault-injection")]
+            random: rtic_common::fault::Random::new(),
         }
     }
 
-    /// Forwards the `Monotonic::now()` method.
+    /// Forwards the `Monotonic::now()` method, including the corrections of
+    /// [`resume`](Self::resume) and [`set_frequency_correction`](Self::set_frequency_correction).
     #[inline(always)]
     pub fn now(&self) -> Backend::Ticks {
-        Backend::now()
+        if !self.corrected.load(Ordering::Relaxed) {
+            return Backend::now();
+        }
+
+        critical_section::with(|cs| self.correct(cs, |correction| correction).now())
+    }
+
+    /// Marks the start of a period in which the backend timer may stop counting, e.g. a
+    /// low-power mode that turns off its clock.
+    pub fn suspend(&self) {
+        critical_section::with(|cs| self.suspended_at.borrow(cs).set(Some(Backend::now())));
+    }
+
+    /// Ends a period started with [`suspend`](Self::suspend).
+    ///
+    /// `correction` is the time that passed since `suspend` according to a clock that kept
+    /// running, e.g. an RTC. The part of it the backend timer did not count is added to
+    /// [`now`](Self::now), so deadlines that passed in the meantime expire instead of slipping.
+    /// Without a preceding `suspend` the whole `correction` is added.
+    pub fn resume(&self, correction: Backend::Ticks) {
+        critical_section::with(|cs| {
+            let now = Backend::now();
+            let counted = self
+                .suspended_at
+                .borrow(cs)
+                .take()
+                .map(|suspended_at| now.wrapping_sub(suspended_at));
+
+            let lost = match counted {
+                Some(counted) if counted.is_at_least(correction) => return,
+                Some(counted) => correction.wrapping_sub(counted),
+                None => correction,
+            };
+
+            self.correct(cs, |c| c.skip(lost));
+        });
+
+        // Expire the deadlines that passed and move the compare to the corrected next one
+        Backend::pend_interrupt();
+    }
+
+    /// Corrects the frequency of the backend timer by `ppm` parts per million, e.g. from a
+    /// temperature measurement of an uncompensated oscillator. Positive values correct a timer
+    /// that counts too fast.
+    ///
+    /// The correction applies from now on, to [`now`](Self::now) and to the deadlines already in
+    /// the queue, replacing the previous one.
+    ///
+    /// # Panics
+    ///
+    /// If `ppm` is not within ±999 999.
+    pub fn set_frequency_correction(&self, ppm: i32) {
+        assert!(
+            ppm.unsigned_abs() < 1_000_000,
+            "frequency correction out of range"
+        );
+
+        critical_section::with(|cs| {
+            self.correct(cs, |c| c.with_ppm(ppm));
+        });
+
+        // Move the compare to the corrected next deadline
+        Backend::pend_interrupt();
+    }
+
+    /// Moves the correction to the current time, applies `f` to it and stores the result.
+    fn correct(
+        &self,
+        cs: critical_section::CriticalSection,
+        f: impl FnOnce(Correction<Backend::Ticks>) -> Correction<Backend::Ticks>,
+    ) -> Correction<Backend::Ticks> {
+        let cell = self.correction.borrow(cs);
+        let now = Backend::now();
+        let correction = f(cell
+            .get()
+            .map_or_else(|| Correction::new(now), |c| c.advance(now)));
+
+        cell.set(Some(correction));
+        self.corrected.store(true, Ordering::Relaxed);
+
+        correction
+    }
+
+    /// Sets the compare of the backend, translating from corrected time.
+    #[inline(always)]
+    fn set_compare(&self, instant: Backend::Ticks) {
+        if !self.corrected.load(Ordering::Relaxed) {
+            return Backend::set_compare(instant);
+        }
+
+        let backend = critical_section::with(|cs| self.correct(cs, |c| c).backend(instant));
+        Backend::set_compare(backend);
+    }
+
+    /// Checks that the monotonic interrupt has not been missed, e.g. from `idle` or a low
+    /// priority watchdog task.
+    ///
+    /// Some timers drop the compare interrupt on errata, e.g. a compare written just as the
+    /// counter passes it, and then every delay waits forever. A deadline at the head of the queue
+    /// that has passed means the interrupt was lost, as long as this is called at a priority
+    /// below the monotonic interrupt: the interrupt is pended, expiring the overdue deadlines and
+    /// reprogramming the compare, and counted in [`stall_recoveries`](Self::stall_recoveries).
+    ///
+    /// Returns `true` if a stall was found.
+    pub fn check_stall(&self) -> bool {
+        let mut release_at = None;
+        self.queue.pop_if(|head| {
+            release_at = Some(head.release_at);
+            false
+        });
+
+        match release_at {
+            Some(instant) if self.now().is_at_least(instant) => {
+                critical_section::with(|cs| {
+                    let recoveries = self.stall_recoveries.borrow(cs);
+                    recoveries.set(recoveries.get().wrapping_add(1));
+                });
+
+                Backend::enable_timer();
+                Backend::pend_interrupt();
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The number of stalls found by [`check_stall`](Self::check_stall).
+    pub fn stall_recoveries(&self) -> u32 {
+        critical_section::with(|cs| self.stall_recoveries.borrow(cs).get())
+    }
+
+    /// Delays each deadline awaited from now on by a pseudo-random amount up to `max`, `None`
+    /// stops, to exercise the handling of late timers.
+    ///
+    /// The amounts repeat from run to run, [`seed_expiry_jitter`](Self::seed_expiry_jitter)
+    /// changes them.
+    #[cfg(feature = "fault-injection")]
+    pub fn set_expiry_jitter(&self, max: Option<Backend::Ticks>) {
+        critical_section::with(|cs| self.jitter.borrow(cs).set(max));
+    }
+
+    /// Restarts the pseudo-random amounts of [`set_expiry_jitter`](Self::set_expiry_jitter)
+    /// from `seed`.
+    #[cfg(feature = "fault-injection")]
+    pub fn seed_expiry_jitter(&self, seed: u32) {
+        self.random.seed(seed);
+    }
+
+    /// Delays `instant` by the injected jitter.
+    #[cfg(feature = "fault-injection")]
+    fn jittered(&self, instant: Backend::Ticks) -> Backend::Ticks {
+        match critical_section::with(|cs| self.jitter.borrow(cs).get()) {
+            Some(max) => instant.wrapping_add(max.parts_per_million(self.random.ppm()).0),
+            None => instant,
+        }
     }
 
     /// Takes the initialized monotonic to initialize the TimerQueue.
@@ -131,7 +314,7 @@ impl<Backend: TimerQueueBackend> TimerQueue<Backend> {
             let head = self.queue.pop_if(|head| {
                 release_at = Some(head.release_at);
 
-                let should_pop = Backend::now().is_at_least(head.release_at);
+                let should_pop = self.now().is_at_least(head.release_at);
                 head.was_popped.store(should_pop, Ordering::Relaxed);
 
                 should_pop
@@ -139,13 +322,13 @@ impl<Backend: TimerQueueBackend> TimerQueue<Backend> {
 
             match (head, release_at) {
                 (Some(link), _) => {
-                    link.waker.wake();
+                    wake_source::with(WakeSource::Timer, || link.waker.wake());
                 }
                 (None, Some(instant)) => {
                     Backend::enable_timer();
-                    Backend::set_compare(instant);
+                    self.set_compare(instant);
 
-                    if Backend::now().is_at_least(instant) {
+                    if self.now().is_at_least(instant) {
                         // The time for the next instant passed while handling it,
                         // continue dequeueing
                         continue;
@@ -187,7 +370,7 @@ impl<Backend: TimerQueueBackend> TimerQueue<Backend> {
         duration: Backend::Ticks,
         future: F,
     ) -> Result<F::Output, TimeoutError> {
-        let now = Backend::now();
+        let now = self.now();
         let mut timeout = now.wrapping_add(duration);
         if now != timeout {
             timeout = timeout.wrapping_add(Backend::Ticks::ONE_TICK);
@@ -201,7 +384,7 @@ impl<Backend: TimerQueueBackend> TimerQueue<Backend> {
     /// Delay for at least some duration of time.
     #[inline]
     pub async fn delay(&self, duration: Backend::Ticks) {
-        let now = Backend::now();
+        let now = self.now();
         let mut timeout = now.wrapping_add(duration);
         if now != timeout {
             timeout = timeout.wrapping_add(Backend::Ticks::ONE_TICK);
@@ -220,6 +403,9 @@ impl<Backend: TimerQueueBackend> TimerQueue<Backend> {
             );
         }
 
+        #[cfg(feature = "fault-injection")]
+        let instant = self.jittered(instant);
+
         let mut link_ptr: Option<linked_list::Link<WaitingWaker<Backend>>> = None;
 
         // Make this future `Drop`-safe
@@ -236,7 +422,7 @@ impl<Backend: TimerQueueBackend> TimerQueue<Backend> {
         });
 
         poll_fn(|cx| {
-            if Backend::now().is_at_least(instant) {
+            if self.now().is_at_least(instant) {
                 return Poll::Ready(());
             }
 
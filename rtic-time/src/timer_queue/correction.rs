@@ -0,0 +1,115 @@
+use super::TimerQueueTicks;
+
+/// Maps the time of a backend to the corrected time of the timer queue.
+///
+/// Corrected time advances by the backend ticks since `anchor`, minus the frequency error of
+/// `ppm` parts per million. The mapping is moved forward on every read of the time, carrying the
+/// fraction of a tick the error accumulated in `remainder`, so it stays exact over any number of
+/// backend wraps.
+#[derive(Clone, Copy)]
+pub(crate) struct Correction<Ticks> {
+    /// Backend time of the last read
+    anchor: Ticks,
+    /// Corrected time at `anchor`
+    base: Ticks,
+    /// Millionths of a tick of frequency error not yet applied to `base`
+    remainder: u32,
+    /// Frequency error of the backend, positive if it counts too fast
+    ppm: i32,
+}
+
+impl<Ticks: TimerQueueTicks> Correction<Ticks> {
+    /// The identity mapping, anchored at the backend time `now`.
+    pub fn new(now: Ticks) -> Self {
+        Self {
+            anchor: now,
+            base: now,
+            remainder: 0,
+            ppm: 0,
+        }
+    }
+
+    /// Moves the mapping forward to the backend time `now`.
+    pub fn advance(self, now: Ticks) -> Self {
+        let (elapsed, remainder) = self.elapsed(now);
+
+        Self {
+            anchor: now,
+            base: self.base.wrapping_add(elapsed),
+            remainder,
+            ..self
+        }
+    }
+
+    /// The corrected time at `anchor`.
+    pub fn now(&self) -> Ticks {
+        self.base
+    }
+
+    /// Converts corrected time to the earliest backend time at which it is reached.
+    ///
+    /// `corrected` must not be before [`now`](Self::now).
+    pub fn backend(&self, corrected: Ticks) -> Ticks {
+        // The first order inverse can be early due to rounding and the neglected higher orders,
+        // step forward until it is not. Late by a tick is fine, early could miss a compare.
+        let mut backend = self
+            .anchor
+            .wrapping_add(self.inverse(corrected.wrapping_sub(self.base)));
+
+        loop {
+            let reached = self.base.wrapping_add(self.elapsed(backend).0);
+            if reached.is_at_least(corrected) {
+                return backend;
+            }
+
+            backend = backend.wrapping_add(self.inverse(corrected.wrapping_sub(reached)));
+        }
+    }
+
+    /// Advances corrected time by `ticks` the backend did not count.
+    pub fn skip(self, ticks: Ticks) -> Self {
+        Self {
+            base: self.base.wrapping_add(ticks),
+            ..self
+        }
+    }
+
+    /// Applies the frequency error `ppm` from [`now`](Self::now) on.
+    pub fn with_ppm(self, ppm: i32) -> Self {
+        Self {
+            ppm,
+            remainder: 0,
+            ..self
+        }
+    }
+
+    /// Corrected ticks elapsed from `anchor` to the backend time `backend`, and the millionths of
+    /// a tick of frequency error left over.
+    fn elapsed(&self, backend: Ticks) -> (Ticks, u32) {
+        const MILLION: u32 = 1_000_000;
+
+        let elapsed = backend.wrapping_sub(self.anchor);
+        let (mut drift, mut remainder) = elapsed.parts_per_million(self.ppm.unsigned_abs());
+        remainder += self.remainder;
+        if remainder >= MILLION {
+            drift = drift.wrapping_add(Ticks::ONE_TICK);
+            remainder -= MILLION;
+        }
+
+        if self.ppm >= 0 {
+            (elapsed.wrapping_sub(drift), remainder)
+        } else {
+            (elapsed.wrapping_add(drift), remainder)
+        }
+    }
+
+    /// The first order approximation of the backend ticks needed for `corrected` ticks.
+    fn inverse(&self, corrected: Ticks) -> Ticks {
+        let (drift, _) = corrected.parts_per_million(self.ppm.unsigned_abs());
+        if self.ppm >= 0 {
+            corrected.wrapping_add(drift)
+        } else {
+            corrected.wrapping_sub(drift)
+        }
+    }
+}
@@ -25,6 +25,75 @@ pub trait TimerQueueTicks: Copy + PartialEq + Eq {
 
     /// Wrapping addition.
     fn wrapping_add(self, other: Self) -> Self;
+
+    /// Wrapping subtraction.
+    ///
+    /// The default implementation builds the difference bit by bit out of
+    /// [`compare`](Self::compare) and [`wrapping_add`](Self::wrapping_add), override it with the
+    /// native operation of the type.
+    fn wrapping_sub(self, other: Self) -> Self {
+        let mut difference = zero::<Self>();
+        for_each_bit(self, other, &mut |bit| {
+            difference = difference.wrapping_add(difference);
+            if bit {
+                difference = difference.wrapping_add(Self::ONE_TICK);
+            }
+        });
+        difference
+    }
+
+    /// `ppm` parts per million of `self`, as whole ticks and the millionths of a tick left over.
+    ///
+    /// The default implementation multiplies bit by bit like [`wrapping_sub`](Self::wrapping_sub),
+    /// override it with a wider native multiplication.
+    fn parts_per_million(self, ppm: u32) -> (Self, u32) {
+        let zero = zero::<Self>();
+        let (mut ticks, mut millionths) = (zero, 0u64);
+        for_each_bit(self, zero, &mut |bit| {
+            ticks = ticks.wrapping_add(ticks);
+            millionths = 2 * millionths + if bit { u64::from(ppm) } else { 0 };
+            while millionths >= 1_000_000 {
+                ticks = ticks.wrapping_add(Self::ONE_TICK);
+                millionths -= 1_000_000;
+            }
+        });
+        (ticks, millionths as u32)
+    }
+}
+
+/// The zero of the tick type, the only value which doubles to itself.
+fn zero<T: TimerQueueTicks>() -> T {
+    let mut zero = T::ONE_TICK;
+    loop {
+        let doubled = zero.wrapping_add(zero);
+        if doubled == zero {
+            return zero;
+        }
+        zero = doubled;
+    }
+}
+
+/// Calls `f` with the bits of `a - b`, most significant first.
+fn for_each_bit<T: TimerQueueTicks>(a: T, b: T, f: &mut dyn FnMut(bool)) {
+    let mut reached = b;
+    for_each_bit_from(a, &mut reached, T::ONE_TICK, f);
+}
+
+/// Decides the bits from `bit` upwards, `reached` is `b` plus the bits decided so far.
+fn for_each_bit_from<T: TimerQueueTicks>(a: T, reached: &mut T, bit: T, f: &mut dyn FnMut(bool)) {
+    let next = bit.wrapping_add(bit);
+    let set = if next.wrapping_add(next) == next {
+        // The top bit is the sign of the difference.
+        a.compare(*reached) == cmp::Ordering::Less
+    } else {
+        for_each_bit_from(a, reached, next, f);
+        // The rest of the difference is below `next`, so it compares without wrapping.
+        a.compare(reached.wrapping_add(bit)) != cmp::Ordering::Less
+    };
+    if set {
+        *reached = reached.wrapping_add(bit);
+    }
+    f(set);
 }
 
 impl TimerQueueTicks for u32 {
@@ -36,6 +105,13 @@ impl TimerQueueTicks for u32 {
     fn wrapping_add(self, other: Self) -> Self {
         u32::wrapping_add(self, other)
     }
+    fn wrapping_sub(self, other: Self) -> Self {
+        u32::wrapping_sub(self, other)
+    }
+    fn parts_per_million(self, ppm: u32) -> (Self, u32) {
+        let parts = u64::from(self) * u64::from(ppm);
+        ((parts / 1_000_000) as u32, (parts % 1_000_000) as u32)
+    }
 }
 impl TimerQueueTicks for u64 {
     const ONE_TICK: Self = 1;
@@ -46,4 +122,11 @@ impl TimerQueueTicks for u64 {
     fn wrapping_add(self, other: Self) -> Self {
         u64::wrapping_add(self, other)
     }
+    fn wrapping_sub(self, other: Self) -> Self {
+        u64::wrapping_sub(self, other)
+    }
+    fn parts_per_million(self, ppm: u32) -> (Self, u32) {
+        let parts = u128::from(self) * u128::from(ppm);
+        ((parts / 1_000_000) as u64, (parts % 1_000_000) as u32)
+    }
 }
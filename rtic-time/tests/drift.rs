@@ -0,0 +1,122 @@
+//! A test that verifies that [`DriftMeter`] measures the drift of one monotonic against another.
+//!
+//! To run this test, you need to activate the `critical-section/std` feature.
+
+use rtic_time::{drift::DriftMeter, Monotonic, TimeoutError};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+/// Ticks of the reference, at 32 768 Hz
+static RTC_NOW: AtomicU64 = AtomicU64::new(0);
+/// How much too fast the measured monotonic counts, in ppm
+static MONO_PPM: AtomicI64 = AtomicI64::new(0);
+
+/// The reference, its delays move the time to their end
+struct Rtc;
+
+impl Monotonic for Rtc {
+    type Instant = fugit::Instant<u64, 1, 32_768>;
+    type Duration = fugit::Duration<u64, 1, 32_768>;
+
+    fn now() -> Self::Instant {
+        Self::Instant::from_ticks(RTC_NOW.load(Ordering::Relaxed))
+    }
+
+    async fn delay(duration: Self::Duration) {
+        Self::delay_until(Self::now() + duration).await;
+    }
+
+    async fn delay_until(instant: Self::Instant) {
+        RTC_NOW.store(instant.ticks(), Ordering::Relaxed);
+    }
+
+    async fn timeout_at<F: core::future::Future>(
+        _: Self::Instant,
+        future: F,
+    ) -> Result<F::Output, TimeoutError> {
+        Ok(future.await)
+    }
+
+    async fn timeout_after<F: core::future::Future>(
+        _: Self::Duration,
+        future: F,
+    ) -> Result<F::Output, TimeoutError> {
+        Ok(future.await)
+    }
+}
+
+/// The measured monotonic, counting milliseconds of the reference `MONO_PPM` too fast
+struct Mono;
+
+impl Monotonic for Mono {
+    type Instant = fugit::Instant<u32, 1, 1_000>;
+    type Duration = fugit::Duration<u32, 1, 1_000>;
+
+    fn now() -> Self::Instant {
+        let micros = RTC_NOW.load(Ordering::Relaxed) as i64 * 1_000_000 / 32_768;
+        let micros = micros + micros * MONO_PPM.load(Ordering::Relaxed) / 1_000_000;
+        Self::Instant::from_ticks((micros / 1_000) as u32)
+    }
+
+    async fn delay(_: Self::Duration) {}
+
+    async fn delay_until(_: Self::Instant) {}
+
+    async fn timeout_at<F: core::future::Future>(
+        _: Self::Instant,
+        future: F,
+    ) -> Result<F::Output, TimeoutError> {
+        Ok(future.await)
+    }
+
+    async fn timeout_after<F: core::future::Future>(
+        _: Self::Duration,
+        future: F,
+    ) -> Result<F::Output, TimeoutError> {
+        Ok(future.await)
+    }
+}
+
+fn poll<T>(future: impl core::future::Future<Output = T>) -> Option<T> {
+    let mut future = core::pin::pin!(future);
+    cassette::Cassette::new(&mut future).poll_on()
+}
+
+#[test]
+fn drift() {
+    let mut meter = DriftMeter::<Rtc, 1, 32_768, Mono, 1, 1_000>::new();
+    let rtc = fugit::Instant::<u64, 1, 32_768>::from_ticks;
+    let mono = fugit::Instant::<u32, 1, 1_000>::from_ticks;
+
+    // 10 s of the reference, the measured monotonic 1 ms fast then 2 ms fast
+    assert_eq!(meter.sample(rtc(0), mono(0)), None);
+    let stats = meter.sample(rtc(327_680), mono(10_001)).unwrap();
+    assert_eq!((stats.intervals, stats.drift_ppm), (1, 100));
+    let stats = meter.sample(rtc(655_360), mono(20_003)).unwrap();
+    assert_eq!(stats.intervals, 2);
+    assert_eq!(stats.drift_ppm, 150);
+    assert_eq!((stats.min_ppm, stats.max_ppm), (100, 200));
+    assert_eq!(stats.jitter_ppm(), 100);
+
+    // A slow monotonic drifts negatively
+    meter.reset();
+    assert_eq!(meter.stats().intervals, 0);
+    meter.sample(rtc(0), mono(0));
+    let stats = meter.sample(rtc(32_768), mono(995)).unwrap();
+    assert_eq!(stats.drift_ppm, -5_000);
+}
+
+#[test]
+fn timed_by_the_reference() {
+    MONO_PPM.store(500, Ordering::Relaxed);
+    let mut meter = DriftMeter::<Rtc, 1, 32_768, Mono, 1, 1_000>::default();
+
+    for _ in 0..3 {
+        poll(meter.next(fugit::Duration::<u64, 1, 32_768>::from_ticks(20 * 32_768)));
+    }
+
+    let stats = meter.stats();
+    assert_eq!(stats.intervals, 3);
+    assert_eq!(stats.drift_ppm, 500);
+    assert_eq!(stats.jitter_ppm(), 0);
+    assert_eq!(RTC_NOW.load(Ordering::Relaxed), 60 * 32_768);
+}
@@ -0,0 +1,69 @@
+//! A test that verifies that [`TimerQueue::set_expiry_jitter`] delays deadlines, within bounds.
+//!
+//! To run this test, you need to activate the `critical-section/std` and `fault-injection`
+//! features.
+
+#![cfg(feature = "fault-injection")]
+
+use cassette::Cassette;
+use parking_lot::Mutex;
+use rtic_time::timer_queue::{TimerQueue, TimerQueueBackend};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NOW: AtomicU64 = AtomicU64::new(0);
+static COMPARE: Mutex<Option<u64>> = Mutex::new(None);
+static TIMER_QUEUE: TimerQueue<TestMonoBackend> = TimerQueue::new();
+
+pub struct TestMonoBackend;
+
+impl TimerQueueBackend for TestMonoBackend {
+    type Ticks = u64;
+
+    fn now() -> Self::Ticks {
+        NOW.load(Ordering::Relaxed)
+    }
+
+    fn set_compare(instant: Self::Ticks) {
+        *COMPARE.lock() = Some(instant);
+    }
+
+    fn clear_compare_flag() {}
+
+    fn pend_interrupt() {
+        unsafe { TIMER_QUEUE.on_monotonic_interrupt() };
+    }
+
+    fn timer_queue() -> &'static TimerQueue<Self> {
+        &TIMER_QUEUE
+    }
+}
+
+#[test]
+fn expiry_jitter() {
+    TIMER_QUEUE.initialize(TestMonoBackend);
+    TIMER_QUEUE.set_expiry_jitter(Some(1_000));
+
+    let mut released = vec![];
+    for deadline in (1..=20).map(|i| i * 10_000) {
+        let delay = core::pin::pin!(TIMER_QUEUE.delay_until(deadline));
+        let mut delay = Cassette::new(delay);
+        assert!(delay.poll_on().is_none());
+
+        // Never early, at most `max` late
+        let compare = COMPARE.lock().unwrap();
+        assert!((deadline..=deadline + 1_000).contains(&compare));
+        released.push(compare);
+
+        NOW.store(compare, Ordering::Relaxed);
+        unsafe { TIMER_QUEUE.on_monotonic_interrupt() };
+        assert!(delay.poll_on().is_some());
+    }
+    assert!(released.iter().zip(1..).any(|(at, i)| *at != i * 10_000));
+
+    // Without jitter the deadline is kept
+    TIMER_QUEUE.set_expiry_jitter(None);
+    let delay = core::pin::pin!(TIMER_QUEUE.delay_until(500_000));
+    let mut delay = Cassette::new(delay);
+    assert!(delay.poll_on().is_none());
+    assert_eq!(*COMPARE.lock(), Some(500_000));
+}
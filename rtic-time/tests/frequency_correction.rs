@@ -0,0 +1,93 @@
+//! A test that verifies that [`TimerQueue::set_frequency_correction`] corrects the time and the
+//! queued deadlines.
+//!
+//! To run this test, you need to activate the `critical-section/std` feature.
+
+use cassette::Cassette;
+use parking_lot::Mutex;
+use rtic_time::timer_queue::{TimerQueue, TimerQueueBackend};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static NOW: AtomicU32 = AtomicU32::new(0);
+static COMPARE: Mutex<Option<u32>> = Mutex::new(None);
+static TIMER_QUEUE: TimerQueue<TestMonoBackend> = TimerQueue::new();
+
+pub struct TestMonoBackend;
+
+impl TimerQueueBackend for TestMonoBackend {
+    type Ticks = u32;
+
+    fn now() -> Self::Ticks {
+        NOW.load(Ordering::Relaxed)
+    }
+
+    fn set_compare(instant: Self::Ticks) {
+        *COMPARE.lock() = Some(instant);
+    }
+
+    fn clear_compare_flag() {}
+
+    fn pend_interrupt() {
+        unsafe { TIMER_QUEUE.on_monotonic_interrupt() };
+    }
+
+    fn timer_queue() -> &'static TimerQueue<Self> {
+        &TIMER_QUEUE
+    }
+}
+
+fn advance(ticks: u32) {
+    NOW.fetch_add(ticks, Ordering::Relaxed);
+}
+
+#[test]
+fn frequency_correction() {
+    TIMER_QUEUE.initialize(TestMonoBackend);
+
+    let delay = core::pin::pin!(TIMER_QUEUE.delay_until(2_000_000));
+    let mut delay = Cassette::new(delay);
+    assert!(delay.poll_on().is_none());
+    assert_eq!(*COMPARE.lock(), Some(2_000_000));
+
+    // The timer counts 1000 ppm too fast, the queued deadline moves out
+    TIMER_QUEUE.set_frequency_correction(1000);
+    assert_eq!(TIMER_QUEUE.now(), 0);
+    assert_eq!(*COMPARE.lock(), Some(2_002_002));
+
+    advance(1_001_001);
+    assert_eq!(TIMER_QUEUE.now(), 1_000_000);
+
+    // The compare is never early
+    advance(1_001_000);
+    TestMonoBackend::pend_interrupt();
+    assert!(delay.poll_on().is_none());
+    assert!(TIMER_QUEUE.now() < 2_000_000);
+
+    advance(1);
+    TestMonoBackend::pend_interrupt();
+    assert!(delay.poll_on().is_some());
+    assert_eq!(TIMER_QUEUE.now(), 2_000_000);
+
+    // Fractions of a tick are carried across reads
+    for _ in 0..1000 {
+        advance(999);
+        TIMER_QUEUE.now();
+    }
+    assert_eq!(TIMER_QUEUE.now(), 2_998_001);
+
+    // A slow timer
+    TIMER_QUEUE.set_frequency_correction(-1000);
+    let before = TIMER_QUEUE.now();
+    advance(1_000_000);
+    assert_eq!(TIMER_QUEUE.now().wrapping_sub(before), 1_001_000);
+
+    // Across a wrap of the backend
+    TIMER_QUEUE.set_frequency_correction(1000);
+    let before = TIMER_QUEUE.now();
+    for _ in 0..2 {
+        advance(2_147_483_000);
+        TIMER_QUEUE.now();
+    }
+    assert!(NOW.load(Ordering::Relaxed) < 2_147_483_000);
+    assert_eq!(TIMER_QUEUE.now().wrapping_sub(before), 2 * 2_145_335_517);
+}
@@ -0,0 +1,87 @@
+//! A test that verifies that [`PhaseLock`] runs a task at its offset to the arrivals of an event.
+//!
+//! To run this test, you need to activate the `critical-section/std` feature.
+
+use cassette::Cassette;
+use parking_lot::Mutex;
+use rtic_time::{
+    monotonic::TimerQueueBasedMonotonic,
+    phase_lock::PhaseLock,
+    timer_queue::{TimerQueue, TimerQueueBackend},
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NOW: AtomicU64 = AtomicU64::new(0);
+static COMPARE: Mutex<Option<u64>> = Mutex::new(None);
+static TIMER_QUEUE: TimerQueue<TestMonoBackend> = TimerQueue::new();
+
+pub struct TestMonoBackend;
+
+impl TimerQueueBackend for TestMonoBackend {
+    type Ticks = u64;
+
+    fn now() -> Self::Ticks {
+        NOW.load(Ordering::Relaxed)
+    }
+
+    fn set_compare(instant: Self::Ticks) {
+        *COMPARE.lock() = Some(instant);
+    }
+
+    fn clear_compare_flag() {}
+
+    fn pend_interrupt() {
+        unsafe { TIMER_QUEUE.on_monotonic_interrupt() };
+    }
+
+    fn timer_queue() -> &'static TimerQueue<Self> {
+        &TIMER_QUEUE
+    }
+}
+
+pub struct TestMono;
+
+impl TimerQueueBasedMonotonic for TestMono {
+    type Backend = TestMonoBackend;
+    type Instant = fugit::Instant<u64, 1, 1_000_000>;
+    type Duration = fugit::Duration<u64, 1, 1_000_000>;
+}
+
+fn advance(ticks: u64) {
+    NOW.fetch_add(ticks, Ordering::Relaxed);
+    TestMonoBackend::pend_interrupt();
+}
+
+#[test]
+fn phase_lock() {
+    static PWM_UPDATE: PhaseLock<TestMono> = PhaseLock::new();
+    TIMER_QUEUE.initialize(TestMonoBackend);
+
+    let at = |ticks| fugit::Instant::<u64, 1, 1_000_000>::from_ticks(ticks);
+    let offset = fugit::Duration::<u64, 1, 1_000_000>::from_ticks(10);
+
+    let next = core::pin::pin!(PWM_UPDATE.next(offset));
+    let mut next = Cassette::new(next);
+    assert!(next.poll_on().is_none());
+
+    // The handler runs late, the offset is kept to the arrival
+    advance(100);
+    PWM_UPDATE.arrived(at(95));
+    assert!(next.poll_on().is_none());
+    assert_eq!(*COMPARE.lock(), Some(105));
+
+    advance(10);
+    assert_eq!(next.poll_on(), Some(at(105)));
+
+    // Arrivals while the task is busy are counted as missed, the latest one is used
+    PWM_UPDATE.arrived(at(200));
+    PWM_UPDATE.arrived(at(300));
+    assert_eq!(PWM_UPDATE.missed(), 1);
+
+    advance(190);
+    let next = core::pin::pin!(PWM_UPDATE.next(offset));
+    let mut next = Cassette::new(next);
+    assert!(next.poll_on().is_none());
+    advance(10);
+    assert_eq!(next.poll_on(), Some(at(310)));
+}
@@ -0,0 +1,146 @@
+//! A test that verifies that [`retry`] backs off between attempts and respects its deadline.
+//!
+//! To run this test, you need to activate the `critical-section/std` feature.
+
+use rtic_time::{
+    retry,
+    retry::{Policy, RetryError},
+    Monotonic, TimeoutError,
+};
+use std::cell::{Cell, RefCell};
+
+thread_local! {
+    /// The time in ms, per thread as the tests run in parallel
+    static NOW: Cell<u32> = const { Cell::new(0) };
+    /// The start of each attempt
+    static ATTEMPTS: RefCell<Vec<u32>> = const { RefCell::new(vec![]) };
+}
+
+/// Counts milliseconds, its delays move the time to their end
+struct Mono;
+
+impl Monotonic for Mono {
+    type Instant = fugit::Instant<u32, 1, 1_000>;
+    type Duration = fugit::Duration<u32, 1, 1_000>;
+
+    fn now() -> Self::Instant {
+        Self::Instant::from_ticks(NOW.get())
+    }
+
+    async fn delay(duration: Self::Duration) {
+        Self::delay_until(Self::now() + duration).await;
+    }
+
+    async fn delay_until(instant: Self::Instant) {
+        NOW.set(NOW.get().max(instant.ticks()));
+    }
+
+    async fn timeout_at<F: core::future::Future>(
+        instant: Self::Instant,
+        future: F,
+    ) -> Result<F::Output, TimeoutError> {
+        let output = future.await;
+        if Self::now() > instant {
+            NOW.set(instant.ticks());
+            Err(TimeoutError)
+        } else {
+            Ok(output)
+        }
+    }
+
+    async fn timeout_after<F: core::future::Future>(
+        duration: Self::Duration,
+        future: F,
+    ) -> Result<F::Output, TimeoutError> {
+        Self::timeout_at(Self::now() + duration, future).await
+    }
+}
+
+fn ms(ticks: u32) -> fugit::Duration<u32, 1, 1_000> {
+    fugit::Duration::<u32, 1, 1_000>::from_ticks(ticks)
+}
+
+fn poll<T>(future: impl core::future::Future<Output = T>) -> T {
+    let mut future = core::pin::pin!(future);
+    cassette::Cassette::new(&mut future).block_on()
+}
+
+/// An operation taking `duration` ms that fails until its `succeed_at`th attempt
+async fn flaky(succeed_at: usize, duration: u32) -> Result<usize, usize> {
+    let attempt = ATTEMPTS.with_borrow_mut(|attempts| {
+        attempts.push(NOW.get());
+        attempts.len()
+    });
+    NOW.set(NOW.get() + duration);
+
+    if attempt == succeed_at {
+        Ok(attempt)
+    } else {
+        Err(attempt)
+    }
+}
+
+#[test]
+fn backoff() {
+    NOW.set(0);
+    let policy = Policy::<Mono>::new(10, ms(100), ms(400)).seed(7);
+
+    assert_eq!(poll(retry(policy, || flaky(6, 0))), Ok(6));
+
+    // The waits double up to the maximum, each between half of the backoff and the backoff
+    let started = ATTEMPTS.take();
+    let waits: Vec<_> = started.windows(2).map(|w| w[1] - w[0]).collect();
+    for (wait, backoff) in waits.iter().zip([100, 200, 400, 400, 400]) {
+        assert!((backoff / 2..=backoff).contains(wait), "{waits:?}");
+    }
+
+    // A different seed spreads the retries differently
+    NOW.set(0);
+    let policy = Policy::<Mono>::new(10, ms(100), ms(400)).seed(8);
+    poll(retry(policy, || flaky(6, 0))).ok();
+    assert_ne!(ATTEMPTS.take(), started);
+}
+
+#[test]
+fn exhausted() {
+    NOW.set(0);
+    let policy = Policy::<Mono>::new(3, ms(10), ms(100));
+
+    assert_eq!(
+        poll(retry(policy, || flaky(0, 0))),
+        Err(RetryError::Exhausted(3))
+    );
+    assert_eq!(ATTEMPTS.take().len(), 3);
+
+    // A policy of no attempts still makes one
+    let policy = Policy::<Mono>::new(0, ms(10), ms(100));
+    assert_eq!(
+        poll(retry(policy, || flaky(0, 0))),
+        Err(RetryError::Exhausted(1))
+    );
+}
+
+#[test]
+fn deadline() {
+    // No wait is started that would end after the deadline
+    NOW.set(0);
+    let policy = Policy::<Mono>::new(10, ms(100), ms(100)).deadline(ms(40));
+
+    assert_eq!(
+        poll(retry(policy, || flaky(0, 0))),
+        Err(RetryError::Deadline(Some(1)))
+    );
+    assert_eq!(NOW.get(), 0);
+
+    // An attempt running past the deadline is cancelled
+    NOW.set(0);
+    ATTEMPTS.take();
+    let policy = Policy::<Mono>::new(10, ms(20), ms(20)).deadline(ms(100));
+
+    assert_eq!(
+        poll(retry(policy, || flaky(0, 60))),
+        Err(RetryError::Deadline(Some(1)))
+    );
+    assert_eq!(ATTEMPTS.take().len(), 2);
+    assert_eq!(NOW.get(), 100);
+}
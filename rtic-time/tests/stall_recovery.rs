@@ -0,0 +1,64 @@
+//! A test that verifies that [`TimerQueue::check_stall`] recovers from a lost monotonic interrupt.
+//!
+//! To run this test, you need to activate the `critical-section/std` feature.
+
+use cassette::Cassette;
+use parking_lot::Mutex;
+use rtic_time::timer_queue::{TimerQueue, TimerQueueBackend};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NOW: AtomicU64 = AtomicU64::new(0);
+static COMPARE: Mutex<Option<u64>> = Mutex::new(None);
+static TIMER_QUEUE: TimerQueue<TestMonoBackend> = TimerQueue::new();
+
+pub struct TestMonoBackend;
+
+impl TimerQueueBackend for TestMonoBackend {
+    type Ticks = u64;
+
+    fn now() -> Self::Ticks {
+        NOW.load(Ordering::Relaxed)
+    }
+
+    fn set_compare(instant: Self::Ticks) {
+        *COMPARE.lock() = Some(instant);
+    }
+
+    fn clear_compare_flag() {}
+
+    fn pend_interrupt() {
+        unsafe { TIMER_QUEUE.on_monotonic_interrupt() };
+    }
+
+    fn timer_queue() -> &'static TimerQueue<Self> {
+        &TIMER_QUEUE
+    }
+}
+
+#[test]
+fn stall_recovery() {
+    TIMER_QUEUE.initialize(TestMonoBackend);
+
+    // Nothing queued, nothing to recover
+    assert!(!TIMER_QUEUE.check_stall());
+
+    let delay = core::pin::pin!(TIMER_QUEUE.delay_until(100));
+    let mut delay = Cassette::new(delay);
+    assert!(delay.poll_on().is_none());
+    assert_eq!(*COMPARE.lock(), Some(100));
+
+    // Not yet due
+    NOW.store(50, Ordering::Relaxed);
+    assert!(!TIMER_QUEUE.check_stall());
+    assert!(delay.poll_on().is_none());
+
+    // The compare passes without an interrupt, the deadline stays queued
+    NOW.store(120, Ordering::Relaxed);
+    assert!(TIMER_QUEUE.check_stall());
+    assert_eq!(TIMER_QUEUE.stall_recoveries(), 1);
+
+    // The pended interrupt expired it
+    assert!(!TIMER_QUEUE.check_stall());
+    assert!(delay.poll_on().is_some());
+    assert_eq!(TIMER_QUEUE.stall_recoveries(), 1);
+}
@@ -0,0 +1,72 @@
+//! A test that verifies that [`TimerQueue::resume`] accounts for the time lost while suspended.
+//!
+//! To run this test, you need to activate the `critical-section/std` feature.
+
+use cassette::Cassette;
+use parking_lot::Mutex;
+use rtic_time::timer_queue::{TimerQueue, TimerQueueBackend};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NOW: AtomicU64 = AtomicU64::new(0);
+static COMPARE: Mutex<Option<u64>> = Mutex::new(None);
+static TIMER_QUEUE: TimerQueue<TestMonoBackend> = TimerQueue::new();
+
+pub struct TestMonoBackend;
+
+impl TimerQueueBackend for TestMonoBackend {
+    type Ticks = u64;
+
+    fn now() -> Self::Ticks {
+        NOW.load(Ordering::Relaxed)
+    }
+
+    fn set_compare(instant: Self::Ticks) {
+        *COMPARE.lock() = Some(instant);
+    }
+
+    fn clear_compare_flag() {}
+
+    fn pend_interrupt() {
+        unsafe { TIMER_QUEUE.on_monotonic_interrupt() };
+    }
+
+    fn timer_queue() -> &'static TimerQueue<Self> {
+        &TIMER_QUEUE
+    }
+}
+
+#[test]
+fn suspend_resume() {
+    TIMER_QUEUE.initialize(TestMonoBackend);
+
+    let delay = core::pin::pin!(TIMER_QUEUE.delay_until(100));
+    let mut delay = Cassette::new(delay);
+    assert!(delay.poll_on().is_none());
+    assert_eq!(*COMPARE.lock(), Some(100));
+
+    // The timer counts 10 ticks, then its clock stops for the rest of the 200 ticks suspended
+    TIMER_QUEUE.suspend();
+    NOW.fetch_add(10, Ordering::Relaxed);
+    TIMER_QUEUE.resume(200);
+
+    assert_eq!(TIMER_QUEUE.now(), 200);
+    assert!(delay.poll_on().is_some());
+
+    // Compares are translated back to the time of the backend
+    let delay = core::pin::pin!(TIMER_QUEUE.delay_until(250));
+    let mut delay = Cassette::new(delay);
+    assert!(delay.poll_on().is_none());
+    assert_eq!(*COMPARE.lock(), Some(60));
+
+    // A timer that kept counting needs no correction
+    TIMER_QUEUE.suspend();
+    NOW.fetch_add(30, Ordering::Relaxed);
+    TIMER_QUEUE.resume(20);
+
+    assert_eq!(TIMER_QUEUE.now(), 230);
+    assert!(delay.poll_on().is_none());
+
+    NOW.fetch_add(20, Ordering::Relaxed);
+    TestMonoBackend::pend_interrupt();
+    assert!(delay.poll_on().is_some());
+}
@@ -0,0 +1,58 @@
+//! A test that verifies the default methods of [`TimerQueueTicks`] against the native arithmetic.
+
+use core::cmp;
+use rtic_time::timer_queue::TimerQueueTicks;
+
+/// A tick type implementing only the required methods
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Ticks(u16);
+
+impl TimerQueueTicks for Ticks {
+    const ONE_TICK: Self = Ticks(1);
+
+    fn compare(self, other: Self) -> cmp::Ordering {
+        (self.0.wrapping_sub(other.0) as i16).cmp(&0)
+    }
+    fn wrapping_add(self, other: Self) -> Self {
+        Ticks(self.0.wrapping_add(other.0))
+    }
+    fn parts_per_million(self, ppm: u32) -> (Self, u32) {
+        let parts = u64::from(self.0) * u64::from(ppm);
+        (
+            Ticks((parts / 1_000_000) as u16),
+            (parts % 1_000_000) as u32,
+        )
+    }
+}
+
+const VALUES: [u16; 10] = [0, 1, 2, 3, 0x7fff, 0x8000, 0x8001, 0xfffe, 0xffff, 12345];
+
+#[test]
+fn default_wrapping_sub() {
+    for a in VALUES {
+        for b in VALUES {
+            assert_eq!(
+                TimerQueueTicks::wrapping_sub(Ticks(a), Ticks(b)),
+                Ticks(a.wrapping_sub(b)),
+                "{a} - {b}"
+            );
+        }
+    }
+}
+
+#[test]
+fn default_parts_per_million() {
+    for ticks in VALUES {
+        for ppm in [0, 1, 999, 1_000_000, 1_234_567] {
+            let parts = u64::from(ticks) * u64::from(ppm);
+            assert_eq!(
+                Ticks(ticks).parts_per_million(ppm),
+                (
+                    Ticks((parts / 1_000_000) as u16),
+                    (parts % 1_000_000) as u32
+                ),
+                "{ppm} ppm of {ticks}"
+            );
+        }
+    }
+}
@@ -18,18 +18,59 @@ fn main() {
     let backend = backends.pop().expect("No backend feature selected.");
 
     match backend.as_str() {
-        "thumbv6-backend" | "thumbv8base-backend" => {
+        "thumbv6-backend" | "thumbv8base-backend" | "thumbv6-rp2040-backend" => {
             println!("cargo:rustc-cfg=feature=\"cortex-m-source-masking\"");
         }
         "thumbv7-backend" | "thumbv8main-backend" => {
             println!("cargo:rustc-cfg=feature=\"cortex-m-basepri\"");
         }
+        "thumbv8main-nrf5340-backend" => {
+            // Both cores are Cortex-M33, as with the `thumbv8main-backend`
+            println!("cargo:rustc-cfg=feature=\"cortex-m-basepri\"");
+            println!("cargo:rustc-cfg=feature=\"thumbv8main-backend\"");
+        }
         "riscv-esp32c3-backend" => {
             println!("cargo:rustc-cfg=feature=\"riscv-esp32c3\"");
         }
         "riscv-esp32c6-backend" => {
             println!("cargo:rustc-cfg=feature=\"riscv-esp32c6\"");
         }
+        "riscv-esp32p4-backend" => {
+            println!("cargo:rustc-cfg=feature=\"riscv-esp32p4\"");
+        }
+        "xtensa-esp32s3-backend" => {
+            println!("cargo:rustc-cfg=feature=\"xtensa-esp32s3\"");
+        }
+        "avr-backend" => {
+            println!("cargo:rustc-cfg=feature=\"avr\"");
+        }
+        "null-backend" => {
+            println!("cargo:rustc-cfg=feature=\"null\"");
+        }
+        "std-backend" => {
+            println!("cargo:rustc-cfg=feature=\"std\"");
+        }
+        "wasm-backend" => {
+            println!("cargo:rustc-cfg=feature=\"wasm\"");
+        }
+        "polling-backend" => {
+            println!("cargo:rustc-cfg=feature=\"polling\"");
+        }
+        "riscv-rp2350-backend" => {
+            println!("cargo:rustc-cfg=feature=\"riscv-rp2350\"");
+        }
+        "riscv-eclic-backend" => {
+            println!("cargo:rustc-cfg=feature=\"riscv-eclic\"");
+        }
+        "riscv-plic-backend" => {
+            println!("cargo:rustc-cfg=feature=\"riscv-plic\"");
+        }
+        "riscv-soft-slic-backend" => {
+            println!("cargo:rustc-cfg=feature=\"riscv-soft-slic\"");
+        }
+        "riscv-ch32-backend" => {
+            println!("cargo:rustc-cfg=feature=\"riscv-ch32\"");
+        }
 
         "riscv-clint-backend" => {
             println!("cargo:rustc-cfg=feature=\"riscv-slic\"");
@@ -0,0 +1,45 @@
+//! Checks against blocking at high priority
+//!
+//! A blocking delay or a busy-wait flash erase holds off every task of lower priority for its
+//! whole duration. The `blocking_priority = N` argument of `#[app]` rejects calls to known
+//! blocking helpers from tasks above priority `N` at compile time. Helpers the macro can not
+//! see, e.g. in a driver crate, call [`assert_may_block`] to catch the same mistake at run time
+//! in debug builds.
+
+use portable_atomic::{AtomicU8, Ordering};
+
+// No limit until `init` returns, and in applications without `blocking_priority`
+static BLOCKING_PRIORITY: AtomicU8 = AtomicU8::new(u8::MAX);
+
+/// Sets the highest priority allowed to block, done by the `#[app]` after `init`
+#[doc(hidden)]
+#[inline]
+pub fn set_blocking_priority(priority: u8) {
+    BLOCKING_PRIORITY.store(priority, Ordering::Relaxed);
+}
+
+/// The highest priority allowed to block, `u8::MAX` if not limited
+#[inline]
+pub fn blocking_priority() -> u8 {
+    BLOCKING_PRIORITY.load(Ordering::Relaxed)
+}
+
+/// Panics in debug builds if the caller runs above the `blocking_priority` of the application
+///
+/// To be called at the start of blocking helpers. Within a lock the ceiling of the resource
+/// counts, see [`current_priority`](crate::current_priority). Does nothing in release builds.
+#[track_caller]
+#[inline]
+pub fn assert_may_block() {
+    #[cfg(debug_assertions)]
+    {
+        let limit = blocking_priority();
+        if limit != u8::MAX {
+            let priority = crate::current_priority();
+            assert!(
+                priority <= limit,
+                "blocking at priority {priority}, above the `blocking_priority = {limit}` of the app"
+            );
+        }
+    }
+}
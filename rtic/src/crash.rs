@@ -0,0 +1,145 @@
+//! Bounded-time failure handling
+//!
+//! [`assert_bounded!`](crate::assert_bounded) fails without the panic machinery in release
+//! builds: no formatting, no unwinding hooks, no panic handler. The failure is recorded in the
+//! crash ring and the shutdown hook runs, which takes a bounded and known number of cycles.
+//!
+//! The shutdown hook is registered in `init` with [`set_shutdown_hook`]. It typically pends a
+//! `binds` task at the highest priority that brings the outputs to a safe state and resets the
+//! device. The failing task spins after calling the hook, it never continues past the assertion.
+//!
+//! The `#[shutdown]` function of an application is registered as the hook at the start of `init`.
+//! With `#[shutdown(binds = [PVD])]` a brown-out or supply voltage warning on `PVD` also runs it:
+//! the interrupt is bound to a hardware task at `priority = max` calling [`fail`] with the message
+//! `"power warning: PVD"`.
+
+use core::cell::RefCell;
+use critical_section::Mutex;
+use portable_atomic::{AtomicPtr, Ordering};
+
+/// The number of failures kept in the crash ring, older ones are overwritten
+pub const CAPACITY: usize = 8;
+
+/// A failed bounded assertion
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Failure {
+    /// The source file of the assertion
+    pub file: &'static str,
+    /// The line of the assertion
+    pub line: u32,
+    /// The message of the assertion, or its condition
+    pub message: &'static str,
+}
+
+struct Ring {
+    failures: [Option<Failure>; CAPACITY],
+    // Total number of failures recorded, the next one goes to `count % CAPACITY`
+    count: u32,
+}
+
+static RING: Mutex<RefCell<Ring>> = Mutex::new(RefCell::new(Ring {
+    failures: [None; CAPACITY],
+    count: 0,
+}));
+
+static SHUTDOWN_HOOK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Registers `hook` to run when a bounded assertion fails
+pub fn set_shutdown_hook(hook: fn()) {
+    SHUTDOWN_HOOK.store(hook as *mut (), Ordering::Release);
+}
+
+/// Records `failure` in the crash ring
+pub fn record(failure: Failure) {
+    critical_section::with(|cs| {
+        let mut ring = RING.borrow_ref_mut(cs);
+        let idx = ring.count as usize % CAPACITY;
+        ring.failures[idx] = Some(failure);
+        ring.count = ring.count.wrapping_add(1);
+    });
+}
+
+/// Calls `f` with the failures in the crash ring, the latest first
+pub fn failures(mut f: impl FnMut(&Failure)) {
+    let ring = critical_section::with(|cs| {
+        let ring = RING.borrow_ref(cs);
+        (ring.failures, ring.count)
+    });
+    let (failures, count) = ring;
+
+    for i in 1..=CAPACITY.min(count as usize) {
+        let idx = (count as usize).wrapping_sub(i) % CAPACITY;
+        if let Some(failure) = &failures[idx] {
+            f(failure);
+        }
+    }
+}
+
+/// The total number of failures recorded, including the ones overwritten in the crash ring
+pub fn count() -> u32 {
+    critical_section::with(|cs| RING.borrow_ref(cs).count)
+}
+
+/// Records the failure, runs the shutdown hook and spins
+///
+/// Called by [`assert_bounded!`](crate::assert_bounded) in release builds.
+#[inline(never)]
+#[cold]
+pub fn fail(file: &'static str, line: u32, message: &'static str) -> ! {
+    record(Failure {
+        file,
+        line,
+        message,
+    });
+
+    let hook = SHUTDOWN_HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        // SAFETY: only `set_shutdown_hook` stores to `SHUTDOWN_HOOK`, from a `fn()`
+        let hook: fn() = unsafe { core::mem::transmute::<*mut (), fn()>(hook) };
+        hook();
+    }
+
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+/// Asserts that a condition holds, failing in bounded time in release builds
+///
+/// In debug builds this is [`assert!`]. In release builds a failure is handled by
+/// [`crash::fail`](crate::crash::fail): it is recorded in the crash ring and the shutdown hook
+/// runs, see the [`crash`](crate::crash) module. The message must be a string literal, it is not
+/// formatted.
+///
+/// ```ignore
+/// rtic::assert_bounded!(duty <= PERIOD, "duty cycle above the period");
+/// ```
+#[macro_export]
+macro_rules! assert_bounded {
+    ($cond:expr $(,)?) => {
+        $crate::assert_bounded!(@check $cond, concat!("assertion failed: ", stringify!($cond)))
+    };
+    ($cond:expr, $message:literal $(,)?) => {
+        $crate::assert_bounded!(@check $cond, $message)
+    };
+    (@check $cond:expr, $message:expr) => {
+        if !$cond {
+            #[cfg(debug_assertions)]
+            ::core::panic!("{}", $message);
+            #[cfg(not(debug_assertions))]
+            $crate::crash::fail(file!(), line!(), $message);
+        }
+    };
+}
+
+/// Like [`assert_bounded!`](crate::assert_bounded), but only checked in debug builds
+///
+/// The bounded-time counterpart of [`debug_assert!`].
+#[macro_export]
+macro_rules! debug_assert_bounded {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            $crate::assert_bounded!($($arg)*);
+        }
+    };
+}
@@ -0,0 +1,39 @@
+//! Executors picking the software tasks a dispatcher polls
+//!
+//! A dispatcher keeps track of which of its software tasks are ready and polls them when its
+//! interrupt runs. By default it polls the tasks that were ready when the pass started in the
+//! order of their numbers. A dispatcher can be given an executor implementing another policy, e.g.
+//! earliest deadline first, with the `#[executor(PATH)]` attribute, where `PATH` names a `static`
+//! implementing [`Executor`]:
+//!
+//! ```ignore
+//! #[rtic::app(device = lm3s6965, dispatchers = [#[executor(crate::EDF)] SSI0])]
+//! ```
+//!
+//! The tasks of a dispatcher are numbered from 0 in the alphabetical order of their names,
+//! `foo::EXECUTOR_INDEX` is the number of task `foo`.
+
+/// Tracks the ready software tasks of a dispatcher and picks the ones to poll
+///
+/// [`wake`](Executor::wake) is called on spawns and wakes, from any priority and from
+/// interrupts, so implementations must be interrupt safe. The other methods are called by the
+/// dispatcher only.
+pub trait Executor: Sync {
+    /// The tasks to poll in one pass of the dispatcher
+    type Pass: Iterator<Item = usize>;
+
+    /// Marks task `index` as ready
+    ///
+    /// The dispatcher is pended after the call, tasks woken while it runs can be polled in the
+    /// same pass or left for the next one.
+    fn wake(&self, index: usize);
+
+    /// Takes ready tasks for a pass of the dispatcher, polled in the order they are returned
+    ///
+    /// A task that is returned but not ready is polled anyway, which is harmless. A task that
+    /// is ready but never returned starves.
+    fn poll_ready(&self) -> Self::Pass;
+
+    /// Checks if no task is ready, used by `drain-dispatchers` to decide when to return
+    fn is_empty(&self) -> bool;
+}
@@ -0,0 +1,205 @@
+//! DMA transfers completed by hardware tasks
+//!
+//! A [`Transfer`] owns the buffer and the peripheral for as long as the DMA accesses them and
+//! gives both back, together with the [`Status`] of the transfer, when it is awaited. It is
+//! completed by a hardware task: every hardware task module has a `dma()` function returning the
+//! [`Completion`] of that task, so a transfer can only name the interrupt of a task actually bound
+//! in the `app`.
+//!
+//! ```ignore
+//! #[task(priority = 1, local = [buf, uart_tx])]
+//! async fn send(cx: send::Context) {
+//!     let transfer = Transfer::start(buf, uart_tx, dma1_ch4::dma()).ok().unwrap();
+//!     let (buf, uart_tx, status) = transfer.await;
+//! }
+//!
+//! #[task(binds = DMA1_CH4, priority = 2)]
+//! fn dma1_ch4(_: dma1_ch4::Context) {
+//!     // Read and clear the flags of the channel
+//!     dma1_ch4::dma().complete(Status { transferred: 16, error: false });
+//! }
+//! ```
+
+use core::{
+    cell::{Cell, RefCell},
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use critical_section::Mutex;
+
+/// A DMA channel together with the peripheral it feeds, as provided by a HAL
+pub trait Peripheral {
+    /// The unit of a transfer
+    type Word;
+
+    /// Points the channel at `buffer` and starts the transfer
+    fn start(&mut self, buffer: &mut [Self::Word]);
+
+    /// Stops a transfer which is not complete yet
+    fn abort(&mut self);
+}
+
+/// The outcome of a transfer, reported by the hardware task completing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Status {
+    /// Words transferred
+    pub transferred: usize,
+    /// The channel reported a transfer error
+    pub error: bool,
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    Idle,
+    Busy,
+    Done(Status),
+}
+
+/// The completion of the transfers finished by one hardware task
+///
+/// One transfer at a time can be in flight on a completion.
+pub struct Completion {
+    state: Mutex<Cell<State>>,
+    waker: Mutex<RefCell<Option<Waker>>>,
+}
+
+impl Default for Completion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Completion {
+    /// Create a completion without a transfer in flight
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(Cell::new(State::Idle)),
+            waker: Mutex::new(RefCell::new(None)),
+        }
+    }
+
+    /// Completes the transfer in flight with `status`, called from the hardware task
+    ///
+    /// Does nothing if no transfer is in flight, e.g. for a spurious interrupt.
+    pub fn complete(&self, status: Status) {
+        let waker = critical_section::with(|cs| {
+            let state = self.state.borrow(cs);
+            match state.get() {
+                State::Busy => {
+                    state.set(State::Done(status));
+                    self.waker.borrow_ref_mut(cs).take()
+                }
+                _ => None,
+            }
+        });
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Checks if a transfer is in flight
+    pub fn is_busy(&self) -> bool {
+        critical_section::with(|cs| !matches!(self.state.borrow(cs).get(), State::Idle))
+    }
+}
+
+/// A DMA transfer in flight
+///
+/// Resolves to the buffer, the peripheral and the [`Status`] of the transfer. Dropping it before
+/// it completes aborts the transfer.
+pub struct Transfer<P: Peripheral + 'static> {
+    buffer: Option<&'static mut [P::Word]>,
+    peripheral: Option<P>,
+    completion: &'static Completion,
+}
+
+impl<P: Peripheral> Transfer<P> {
+    /// Starts transferring `buffer` with `peripheral`, completed by the hardware task owning
+    /// `completion`
+    ///
+    /// Gives both back if a transfer is already in flight on `completion`.
+    pub fn start(
+        buffer: &'static mut [P::Word],
+        mut peripheral: P,
+        completion: &'static Completion,
+    ) -> Result<Self, (&'static mut [P::Word], P)> {
+        let claimed = critical_section::with(|cs| {
+            let state = completion.state.borrow(cs);
+            let idle = matches!(state.get(), State::Idle);
+            if idle {
+                state.set(State::Busy);
+            }
+            idle
+        });
+
+        if !claimed {
+            return Err((buffer, peripheral));
+        }
+
+        peripheral.start(buffer);
+
+        Ok(Self {
+            buffer: Some(buffer),
+            peripheral: Some(peripheral),
+            completion,
+        })
+    }
+
+    /// Checks if the hardware task has completed the transfer
+    pub fn is_done(&self) -> bool {
+        critical_section::with(|cs| {
+            matches!(self.completion.state.borrow(cs).get(), State::Done(_))
+        })
+    }
+}
+
+impl<P: Peripheral> Unpin for Transfer<P> {}
+
+impl<P: Peripheral> Future for Transfer<P> {
+    type Output = (&'static mut [P::Word], P, Status);
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let status = critical_section::with(|cs| {
+            let state = self.completion.state.borrow(cs);
+            match state.get() {
+                State::Done(status) => {
+                    state.set(State::Idle);
+                    Some(status)
+                }
+                _ => {
+                    self.completion
+                        .waker
+                        .borrow_ref_mut(cs)
+                        .replace(cx.waker().clone());
+                    None
+                }
+            }
+        });
+
+        match status {
+            Some(status) => Poll::Ready((
+                self.buffer.take().unwrap(),
+                self.peripheral.take().unwrap(),
+                status,
+            )),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<P: Peripheral> Drop for Transfer<P> {
+    fn drop(&mut self) {
+        // Already resolved
+        let Some(peripheral) = &mut self.peripheral else {
+            return;
+        };
+
+        peripheral.abort();
+        critical_section::with(|cs| {
+            self.completion.state.borrow(cs).set(State::Idle);
+            self.completion.waker.borrow_ref_mut(cs).take();
+        });
+    }
+}
@@ -0,0 +1,187 @@
+//! Energy accounting of software tasks
+//!
+//! With the `energy-stats` feature the executor of every software task measures how long its
+//! polls run and charges that time at the current the device draws in the mode the application
+//! is in. The result is part of the [`TaskStats`](crate::stats::TaskStats) returned by
+//! `task::stats()`, to see which task of a duty-cycled firmware spends the battery.
+//!
+//! Nothing is measured until `init` registers a clock, and the figures are the ones of the board,
+//! e.g. from the datasheet or measured with a power analyzer:
+//!
+//! ```ignore
+//! #[init]
+//! fn init(cx: init::Context) -> (Shared, Local) {
+//!     cx.core.DCB.enable_trace();
+//!     cx.core.DWT.enable_cycle_counter();
+//!     rtic::energy::set_clock(cortex_m::peripheral::DWT::cycle_count, 48_000_000);
+//!     rtic::energy::set_supply(3_300);
+//!     // µA while running tasks in `Mode::Active` and `Mode::LowPower`
+//!     rtic::energy::set_currents(&[4_200, 900]);
+//!     ..
+//! }
+//! ```
+//!
+//! The current is picked by the index of the mode in `modes = [..]`, applications without modes
+//! only use the first one. A poll is charged at the current of the mode when it completes, and
+//! only for its own time if other polls preempted it. Hardware tasks are not metered, their time
+//! counts towards the poll they preempt. Polls must be shorter than the period over which the
+//! clock wraps around.
+
+use core::cell::Cell;
+use critical_section::Mutex;
+
+#[derive(Clone, Copy)]
+struct Config {
+    now: Option<fn() -> u32>,
+    ticks_per_second: u32,
+    millivolts: u32,
+    microamps: &'static [u32],
+    mode: u8,
+}
+
+static CONFIG: Mutex<Cell<Config>> = Mutex::new(Cell::new(Config {
+    now: None,
+    ticks_per_second: 0,
+    millivolts: 0,
+    microamps: &[],
+    mode: 0,
+}));
+
+/// Ticks charged to polls so far, wrapping, to leave the time of preempting polls out
+static CHARGED: Mutex<Cell<u32>> = Mutex::new(Cell::new(0));
+
+fn update(f: impl FnOnce(&mut Config)) {
+    critical_section::with(|cs| {
+        let cell = CONFIG.borrow(cs);
+        let mut config = cell.get();
+        f(&mut config);
+        cell.set(config);
+    });
+}
+
+/// Registers the clock the polls are timed with, a free running counter such as the cycle
+/// counter, and its rate.
+pub fn set_clock(now: fn() -> u32, ticks_per_second: u32) {
+    update(|config| {
+        config.now = Some(now);
+        config.ticks_per_second = ticks_per_second;
+    });
+}
+
+/// Sets the supply voltage, in millivolts.
+pub fn set_supply(millivolts: u32) {
+    update(|config| config.millivolts = millivolts);
+}
+
+/// Sets the current drawn while running tasks in each mode of the application, in microamps.
+///
+/// Modes without a figure are charged nothing.
+pub fn set_currents(microamps: &'static [u32]) {
+    update(|config| config.microamps = microamps);
+}
+
+/// Records the mode of the application, called by `set_mode`
+#[doc(hidden)]
+#[inline]
+pub fn set_mode(mode: u8) {
+    update(|config| config.mode = mode);
+}
+
+/// The start of a poll, see [`Meter::stop`]
+#[doc(hidden)]
+pub struct Start {
+    at: u32,
+    charged: u32,
+}
+
+/// Starts timing a poll, `None` without a clock
+#[doc(hidden)]
+#[inline]
+pub fn start() -> Option<Start> {
+    critical_section::with(|cs| {
+        let now = CONFIG.borrow(cs).get().now?;
+        Some(Start {
+            at: now(),
+            charged: CHARGED.borrow(cs).get(),
+        })
+    })
+}
+
+#[derive(Clone, Copy)]
+struct Totals {
+    ticks: u64,
+    /// µA · ticks
+    charge: u64,
+}
+
+/// The time and charge of the polls of a task
+#[doc(hidden)]
+pub struct Meter {
+    totals: Mutex<Cell<Totals>>,
+}
+
+impl Default for Meter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Meter {
+    pub const fn new() -> Self {
+        Self {
+            totals: Mutex::new(Cell::new(Totals {
+                ticks: 0,
+                charge: 0,
+            })),
+        }
+    }
+
+    /// Charges the poll begun at `start`, less the time of the polls that preempted it
+    pub fn stop(&self, start: Option<Start>) {
+        let Some(start) = start else {
+            return;
+        };
+
+        critical_section::with(|cs| {
+            let config = CONFIG.borrow(cs).get();
+            let Some(now) = config.now else {
+                return;
+            };
+
+            let charged = CHARGED.borrow(cs);
+            let preempted = charged.get().wrapping_sub(start.charged);
+            let ticks = now().wrapping_sub(start.at).saturating_sub(preempted);
+            charged.set(charged.get().wrapping_add(ticks));
+
+            let microamps = config
+                .microamps
+                .get(config.mode as usize)
+                .copied()
+                .unwrap_or(0);
+
+            let cell = self.totals.borrow(cs);
+            let mut totals = cell.get();
+            totals.ticks = totals.ticks.wrapping_add(ticks as u64);
+            totals.charge = totals.charge.wrapping_add(ticks as u64 * microamps as u64);
+            cell.set(totals);
+        });
+    }
+
+    /// The time polled in ticks of the clock, and the energy in nanojoules
+    pub fn read(&self) -> (u64, u64) {
+        critical_section::with(|cs| {
+            let config = CONFIG.borrow(cs).get();
+            let totals = self.totals.borrow(cs).get();
+
+            // µA · mV · s = nJ
+            let nanojoules = if config.ticks_per_second == 0 {
+                0
+            } else {
+                (totals.charge as u128 * config.millivolts as u128
+                    / config.ticks_per_second as u128) as u64
+            };
+
+            (totals.ticks, nanojoules)
+        })
+    }
+}
@@ -0,0 +1,163 @@
+//! ESP32-C6 LP core tasks
+//!
+//! With the `esp32c6-lp-core` feature, tasks of an application on the HP core can be placed on
+//! the low-power RISC-V core of the ESP32-C6 with `#[lp_task]`. They keep their own resources,
+//! declared in `local = [..]`, and run to completion, one at a time, in declaration order:
+//!
+//! ```ignore
+//! #[rtic::app(device = esp32c6, dispatchers = [FROM_CPU_INTR0])]
+//! mod app {
+//!     #[init]
+//!     fn init(_: init::Context) -> (Shared, Local) {
+//!         sample::spawn();
+//!         // ..
+//!     }
+//!
+//!     #[lp_task(local = [count: u32 = 0], spawns = [report])]
+//!     fn sample(cx: sample::Context) {
+//!         *cx.local.count += 1;
+//!         hp::report::spawn();
+//!     }
+//!
+//!     #[task(priority = 1)]
+//!     async fn report(_: report::Context) {
+//!         // ..
+//!     }
+//! }
+//! ```
+//!
+//! The `app` macro places the HP application behind `#[cfg(not(rtic_core = "lp"))]` and the LP
+//! tasks behind `#[cfg(rtic_core = "lp")]`, so the crate is built once more with
+//! `--cfg rtic_core="lp"` for the image of the LP core. That image runs the tasks from the
+//! generated `app::run()`, called from the entry point of the LP runtime, e.g. the `#[entry]` of
+//! `esp-lp-hal`. The body of an LP task only sees the paths it names itself, not the imports of
+//! the application.
+//!
+//! # Cross-core spawns
+//!
+//! The HP core spawns an LP task with the generated `sample::spawn()`, which posts the slot of
+//! the task to the [`Mailbox::HP_TO_LP`] and raises `HP_TRIGGER_LP` in the PMU. The LP core waits
+//! for that trigger and runs the tasks of the posted slots.
+//!
+//! An LP task spawns the HP software tasks listed in its `spawns = [..]`, which take no
+//! arguments, with `hp::report::spawn()`. This posts to the [`Mailbox::LP_TO_HP`] and raises
+//! `LP_TRIGGER_HP`, whose `PMU` interrupt is bound to a hardware task, at the highest priority of
+//! these tasks, spawning the tasks of the posted slots.
+//!
+//! A cross-core spawn carries no payload, the data are passed through LP memory. The mailboxes
+//! take the last 64 bytes of the LP SRAM, which the linker script of the LP image must leave out.
+//! Posts for a task still pending are coalesced, as for a spawn that fails.
+
+#[cfg(not(feature = "riscv-esp32c6"))]
+compile_error!("The ESP32-C6 LP core tasks need the 'riscv-esp32c6-backend'");
+
+use core::sync::atomic::{fence, Ordering};
+
+// PMU registers, at the same address for both cores
+const PMU_INT_ENA: usize = 0x600b_0164;
+const PMU_INT_CLR: usize = 0x600b_0168;
+const PMU_LP_INT_RAW: usize = 0x600b_016c;
+const PMU_LP_INT_CLR: usize = 0x600b_0178;
+const PMU_HP_LP_CPU_COMM: usize = 0x600b_0184;
+
+// `SW` of the HP interrupts, raised by `LP_TRIGGER_HP`
+const HP_SW: u32 = 1 << 29;
+// `HP_SW_TRIGGER` of the LP interrupts, raised by `HP_TRIGGER_LP`
+const LP_HP_SW_TRIGGER: u32 = 1 << 31;
+
+const LP_TRIGGER_HP: u32 = 1 << 30;
+const HP_TRIGGER_LP: u32 = 1 << 31;
+
+/// The number of slots of a mailbox
+pub const SLOTS: u8 = 32;
+
+/// The spawns posted from one core to the other, one byte per slot in LP SRAM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mailbox {
+    base: usize,
+    trigger: u32,
+}
+
+impl Mailbox {
+    /// The LP tasks spawned by the HP core, slot `n` for the `n`th `#[lp_task]`
+    pub const HP_TO_LP: Mailbox = Mailbox {
+        base: 0x5000_3fc0,
+        trigger: HP_TRIGGER_LP,
+    };
+
+    /// The HP tasks spawned by the LP core, slot `n` for the `n`th task of the `spawns = [..]`
+    pub const LP_TO_HP: Mailbox = Mailbox {
+        base: 0x5000_3fe0,
+        trigger: LP_TRIGGER_HP,
+    };
+
+    #[inline(always)]
+    fn slot(self, slot: u8) -> *mut u8 {
+        assert!(slot < SLOTS, "a mailbox has slots 0 to 31");
+        (self.base + slot as usize) as *mut u8
+    }
+
+    /// Posts `slot` and triggers the other core
+    #[inline]
+    pub fn post(self, slot: u8) {
+        let slot = self.slot(slot);
+
+        // Make the data of the spawn visible before the post
+        fence(Ordering::SeqCst);
+
+        // SAFETY: a slot is only set by the posting core and the trigger bits only raise events
+        unsafe {
+            slot.write_volatile(1);
+            fence(Ordering::SeqCst);
+            (PMU_HP_LP_CPU_COMM as *mut u32).write_volatile(self.trigger);
+        }
+    }
+
+    /// Clears `slot`, returns `true` if it was posted
+    #[inline]
+    pub fn take(self, slot: u8) -> bool {
+        let slot = self.slot(slot);
+
+        // SAFETY: a slot is only cleared by the receiving core
+        unsafe {
+            if slot.read_volatile() == 0 {
+                return false;
+            }
+            slot.write_volatile(0);
+        }
+
+        // Accesses to the data of the spawn stay after the post
+        fence(Ordering::SeqCst);
+        true
+    }
+}
+
+/// HP core: enables the `PMU` interrupt raised by the LP core
+#[inline]
+pub fn listen() {
+    // SAFETY: read-modify-write of the HP interrupt enables, before the interrupts are enabled
+    unsafe {
+        let ena = PMU_INT_ENA as *mut u32;
+        ena.write_volatile(ena.read_volatile() | HP_SW);
+    }
+}
+
+/// HP core: clears the `PMU` interrupt raised by the LP core, before taking the slots
+#[inline]
+pub fn acknowledge() {
+    // SAFETY: the clear register only clears the written bits
+    unsafe { (PMU_INT_CLR as *mut u32).write_volatile(HP_SW) };
+}
+
+/// LP core: waits until the HP core triggered it, then clears the trigger
+#[inline]
+pub fn wait() {
+    // SAFETY: the raw LP interrupts are only read here, the clear register only clears the
+    // written bits
+    unsafe {
+        while (PMU_LP_INT_RAW as *const u32).read_volatile() & LP_HP_SW_TRIGGER == 0 {
+            core::hint::spin_loop();
+        }
+        (PMU_LP_INT_CLR as *mut u32).write_volatile(LP_HP_SW_TRIGGER);
+    }
+}
@@ -1,7 +1,26 @@
 pub use bare_metal::CriticalSection;
 pub use portable_atomic as atomic;
 
+pub mod batch;
+#[cfg(feature = "coalesce-wakes")]
+pub mod coalesce;
+#[cfg(feature = "rp2040")]
+pub mod cross_core;
+#[cfg(feature = "rp2040")]
+pub use cross_core::{core_id, pend_remote};
 pub mod executor;
+pub mod period;
+#[cfg(feature = "position-independent")]
+pub mod pic;
+#[cfg(all(
+    feature = "position-independent",
+    not(any(feature = "cortex-m-basepri", feature = "cortex-m-source-masking"))
+))]
+compile_error!("the `position-independent` feature is only supported by the Cortex-M backends");
+pub mod ready;
+pub mod shed;
+#[cfg(feature = "cortex-m")]
+pub mod storm;
 
 // Cortex-M target (any)
 #[cfg(feature = "cortex-m")]
@@ -40,6 +59,66 @@ mod riscv_esp32c6;
 #[cfg(feature = "riscv-esp32c6")]
 pub use riscv_esp32c6::*;
 
+#[cfg(feature = "riscv-esp32p4")]
+mod riscv_esp32p4;
+#[cfg(feature = "riscv-esp32p4")]
+pub use riscv_esp32p4::*;
+
+#[cfg(feature = "xtensa-esp32s3")]
+mod xtensa_esp32s3;
+#[cfg(feature = "xtensa-esp32s3")]
+pub use xtensa_esp32s3::*;
+
+#[cfg(feature = "avr")]
+mod avr;
+#[cfg(feature = "avr")]
+pub use avr::*;
+
+#[cfg(feature = "null")]
+mod null;
+#[cfg(feature = "null")]
+pub use null::*;
+
+#[cfg(feature = "std")]
+mod std_sim;
+#[cfg(feature = "std")]
+pub use std_sim::*;
+
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "wasm")]
+pub use wasm::*;
+
+#[cfg(feature = "polling")]
+mod polling;
+#[cfg(feature = "polling")]
+pub use polling::*;
+
+#[cfg(feature = "riscv-rp2350")]
+mod riscv_rp2350;
+#[cfg(feature = "riscv-rp2350")]
+pub use riscv_rp2350::*;
+
+#[cfg(feature = "riscv-eclic")]
+mod riscv_eclic;
+#[cfg(feature = "riscv-eclic")]
+pub use riscv_eclic::*;
+
+#[cfg(feature = "riscv-plic")]
+mod riscv_plic;
+#[cfg(feature = "riscv-plic")]
+pub use riscv_plic::*;
+
+#[cfg(feature = "riscv-ch32")]
+mod riscv_ch32;
+#[cfg(feature = "riscv-ch32")]
+pub use riscv_ch32::*;
+
+#[cfg(feature = "riscv-soft-slic")]
+mod riscv_soft_slic;
+#[cfg(feature = "riscv-soft-slic")]
+pub use riscv_soft_slic::*;
+
 #[cfg(feature = "riscv-slic")]
 mod slic;
 #[cfg(feature = "riscv-slic")]
@@ -0,0 +1,259 @@
+//! AVR (ATmega, ATtiny)
+//!
+//! AVR has no interrupt priorities: a handler runs with interrupts disabled until its `reti`, so
+//! the hardware tasks share the highest priority of the application. The priorities below it are
+//! kept in software, `CURRENT` holds the priority of the running task and the ceiling of the
+//! locks of resources shared between software tasks only. The locks of resources shared with a
+//! hardware task disable interrupts.
+//!
+//! The dispatchers are software, pending one sets its bit in `PENDING`. The pending dispatchers
+//! above `CURRENT` run, with interrupts enabled, when they are pended from a lower priority and
+//! when a handler, a dispatcher or a lock lowers `CURRENT` again. The handlers of the hardware
+//! tasks enable interrupts for this before their `reti`.
+
+use core::arch::asm;
+use portable_atomic::{AtomicBool, AtomicU16, AtomicU8, Ordering};
+
+#[cfg(all(feature = "avr", not(feature = "avr-backend")))]
+compile_error!("Building for AVR, but 'avr-backend' not selected");
+
+/// The highest logical priority
+pub const MAX_PRIORITY: u8 = 15;
+
+/// Configuration of the software priorities, provided by the `#[app]` macro as `__RTIC_AVR`
+pub struct AvrConfig {
+    /// The priority of the hardware tasks, above all software tasks
+    pub hardware_priority: u8,
+    /// The priority and handler of each dispatcher, by decreasing priority
+    pub dispatchers: &'static [(u8, unsafe fn())],
+}
+
+#[inline(always)]
+fn config() -> &'static AvrConfig {
+    extern "Rust" {
+        static __RTIC_AVR: AvrConfig;
+    }
+    // SAFETY: the static is generated by the `#[app]` macro
+    unsafe { &__RTIC_AVR }
+}
+
+/// The global interrupt enable bit of `SREG`
+const SREG_I: u8 = 1 << 7;
+
+/// The priority of the running code, see the module documentation
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// The dispatchers pended and not yet run, bit `n` for dispatcher `n`
+static PENDING: AtomicU16 = AtomicU16::new(0);
+
+/// Disabling and enabling all interrupts through `SREG.I`
+pub mod interrupt {
+    /// Disables all interrupts
+    #[inline(always)]
+    pub fn disable() {
+        // SAFETY: only masks interrupts
+        unsafe { core::arch::asm!("cli") };
+    }
+
+    /// Enables all interrupts and runs the dispatchers pended while they were disabled, e.g. by
+    /// the spawns in `init`
+    ///
+    /// # Safety
+    ///
+    /// Interrupt handlers may run from here on, as in a critical section being left.
+    #[inline(always)]
+    pub unsafe fn enable() {
+        core::arch::asm!("sei");
+        super::dispatch();
+    }
+}
+
+#[inline(always)]
+fn read_sreg() -> u8 {
+    let sreg;
+    // SAFETY: reading `SREG` has no side effects
+    unsafe { asm!("in {0}, 0x3F", out(reg) sreg, options(nomem, nostack, preserves_flags)) };
+    sreg
+}
+
+#[inline(always)]
+fn interrupts_enabled() -> bool {
+    read_sreg() & SREG_I != 0
+}
+
+/// Read the stack pointer.
+#[inline(always)]
+pub fn read_sp() -> u16 {
+    let (low, high): (u8, u8);
+    unsafe {
+        asm!(
+            "in {0}, 0x3D",
+            "in {1}, 0x3E",
+            out(reg) low,
+            out(reg) high,
+            options(nomem, nostack, preserves_flags),
+        )
+    };
+    u16::from_le_bytes([low, high])
+}
+
+/// A wake of `main` not yet seen by [`idle_wait`]
+static IDLE_WAKE: AtomicBool = AtomicBool::new(false);
+
+/// Wakes `main` from [`idle_wait`], called when a task of priority 0 is spawned or woken
+///
+/// Latched until the next [`idle_wait`] as the other backends do, see `riscv_common::wake_idle`.
+#[inline(always)]
+pub fn wake_idle() {
+    IDLE_WAKE.store(true, Ordering::SeqCst);
+}
+
+/// Sleeps in the zero priority dispatcher of `main` if no wake is latched and `is_idle` reports
+/// no task ready
+///
+/// The check runs with interrupts disabled, the instruction after `sei` runs before any
+/// interrupt, so `sleep` is entered before a handler can wake a task. The sleep mode is set in
+/// `SMCR` by the application, without `SMCR.SE` the `sleep` does nothing.
+#[inline(always)]
+pub fn idle_wait(is_idle: impl FnOnce() -> bool) {
+    interrupt::disable();
+
+    // No handler runs between the load and the store, no read-modify-write is needed
+    let woken = IDLE_WAKE.load(Ordering::SeqCst);
+    IDLE_WAKE.store(false, Ordering::SeqCst);
+
+    // SAFETY: `main` runs with interrupts enabled once `init` has returned
+    unsafe {
+        if !woken && is_idle() {
+            asm!("sei", "sleep");
+        } else {
+            asm!("sei");
+        }
+    }
+}
+
+/// Runs the pending dispatchers above the current priority, highest first
+///
+/// Called with interrupts enabled, each dispatcher sets the current priority to its own.
+#[inline(never)]
+fn dispatch() {
+    loop {
+        interrupt::disable();
+        let current = CURRENT.load(Ordering::Relaxed);
+        let pending = PENDING.load(Ordering::Relaxed);
+        let next = config()
+            .dispatchers
+            .iter()
+            .enumerate()
+            .find(|(index, (priority, _))| *priority > current && pending & 1 << index != 0);
+        if let Some((index, _)) = next {
+            PENDING.store(pending & !(1 << index), Ordering::Relaxed);
+        }
+        // SAFETY: called with interrupts enabled
+        unsafe { asm!("sei") };
+
+        match next {
+            Some((_, (_, dispatcher))) => unsafe { (*dispatcher)() },
+            None => break,
+        }
+    }
+}
+
+/// Runs the handler of a task at `priority`
+///
+/// The handlers of the hardware tasks run with interrupts disabled, the dispatchers with them
+/// enabled. Once `f` returns the dispatchers pended above the restored priority run, a handler
+/// enables interrupts for them and disables them again for its `reti`.
+#[inline(always)]
+pub fn run<F>(priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    let previous = CURRENT.load(Ordering::Relaxed);
+    CURRENT.store(priority, Ordering::Relaxed);
+
+    f();
+
+    CURRENT.store(previous, Ordering::Relaxed);
+
+    if PENDING.load(Ordering::Relaxed) != 0 {
+        if interrupts_enabled() {
+            dispatch();
+        } else {
+            // SAFETY: the handler has returned, its priority is given up
+            unsafe { interrupt::enable() };
+            interrupt::disable();
+        }
+    }
+}
+
+/// The logical priority of the running code, the ceiling of the held locks included
+///
+/// Kept in software, see the module documentation. With interrupts disabled, e.g. in `init` or
+/// in a lock of a resource shared with a hardware task, this is at least the priority of the
+/// hardware tasks.
+#[inline]
+pub fn current_priority() -> u8 {
+    let current = CURRENT.load(Ordering::Relaxed);
+    if interrupts_enabled() {
+        current
+    } else {
+        current.max(config().hardware_priority)
+    }
+}
+
+/// Lock implementation using the software priority and `SREG.I`
+///
+/// # Safety
+///
+/// The system ceiling is raised from current to ceiling by:
+///
+/// - disabling interrupts if the ceiling is the priority of the hardware tasks, which nothing
+///   preempts.
+/// - raising the software priority to the ceiling otherwise, which keeps the dispatchers at or
+///   below it from running. A handler interrupting the lock restores the software priority
+///   before returning.
+///
+/// Once `f` returns the dispatchers pended above the restored priority run.
+#[inline(always)]
+pub unsafe fn lock<T, R>(ptr: *mut T, ceiling: u8, f: impl FnOnce(&mut T) -> R) -> R {
+    if ceiling >= config().hardware_priority {
+        let sreg = read_sreg();
+        interrupt::disable();
+        let r = f(&mut *ptr);
+        if sreg & SREG_I != 0 {
+            interrupt::enable();
+        }
+        r
+    } else {
+        let current = CURRENT.load(Ordering::Relaxed);
+        if current < ceiling {
+            CURRENT.store(ceiling, Ordering::Relaxed);
+            let r = f(&mut *ptr);
+            CURRENT.store(current, Ordering::Relaxed);
+            if PENDING.load(Ordering::Relaxed) != 0 {
+                dispatch();
+            }
+            r
+        } else {
+            f(&mut *ptr)
+        }
+    }
+}
+
+/// Pends software dispatcher `n`, see the module documentation
+///
+/// With interrupts enabled a dispatcher above the current priority runs before this returns.
+#[inline(always)]
+pub fn pend(n: u8) {
+    PENDING.fetch_or(1 << n, Ordering::SeqCst);
+    if interrupts_enabled() {
+        dispatch();
+    }
+}
+
+/// AVR has no software interrupts to request
+#[inline]
+pub fn swi_request(_n: u16) -> bool {
+    false
+}
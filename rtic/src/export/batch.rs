@@ -0,0 +1,153 @@
+use core::{cell::UnsafeCell, mem::MaybeUninit, ptr};
+
+/// Payloads of a software task waiting for the executor to become free.
+///
+/// All accesses happen inside critical sections, which also make the check of the executor and
+/// the following pop or push atomic with respect to the dispatcher of the task. The iterator
+/// passed to [`extend`](Self::extend) runs outside of them.
+pub struct BatchQueue<T, const N: usize> {
+    inner: UnsafeCell<Inner<T, N>>,
+}
+
+struct Inner<T, const N: usize> {
+    buffer: MaybeUninit<[T; N]>,
+    head: usize,
+    len: usize,
+    /// Slots kept free for payloads being taken out of an iterator
+    reserved: usize,
+}
+
+unsafe impl<T: Send, const N: usize> Sync for BatchQueue<T, N> {}
+
+impl<T, const N: usize> Default for BatchQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> Inner<T, N> {
+    fn slot(&mut self, index: usize) -> *mut T {
+        // SAFETY: `index` is always reduced modulo `N` by the callers.
+        unsafe { (self.buffer.as_mut_ptr() as *mut T).add(index) }
+    }
+
+    fn push(&mut self, value: T) {
+        let slot = self.slot((self.head + self.len) % N);
+        // SAFETY: The slot is outside of the `len` initialized ones.
+        unsafe { ptr::write(slot, value) };
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> T {
+        let slot = self.slot(self.head);
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        // SAFETY: The slot was the first of the `len` initialized ones.
+        unsafe { ptr::read(slot) }
+    }
+}
+
+impl<T, const N: usize> BatchQueue<T, N> {
+    /// Create an empty queue.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            inner: UnsafeCell::new(Inner {
+                buffer: MaybeUninit::uninit(),
+                head: 0,
+                len: 0,
+                reserved: 0,
+            }),
+        }
+    }
+
+    /// Moves payloads out of `payloads` until it is exhausted or the batch is full.
+    ///
+    /// The room left is reserved in a first critical section, `payloads` fills a local buffer of
+    /// that many outside of it and a second one queues them all. When nothing is queued `first`
+    /// is asked to take the first payload directly, it returns it back if the task cannot be
+    /// spawned. Returns the number of payloads taken.
+    pub fn extend<I: Iterator<Item = T>>(
+        &self,
+        payloads: &mut I,
+        first: impl FnOnce(T) -> Option<T>,
+    ) -> usize {
+        let room = critical_section::with(|_| {
+            // SAFETY: Only accessed inside critical sections.
+            let inner = unsafe { &mut *self.inner.get() };
+            let room = N - inner.len - inner.reserved;
+            inner.reserved += room;
+            room
+        });
+
+        let mut taken = MaybeUninit::<[T; N]>::uninit();
+        let slots = taken.as_mut_ptr() as *mut T;
+        let mut len = 0;
+        for payload in payloads.take(room) {
+            // SAFETY: `len` is below `room`, which is at most `N`.
+            unsafe { ptr::write(slots.add(len), payload) };
+            len += 1;
+        }
+
+        critical_section::with(|_| {
+            // SAFETY: Only accessed inside critical sections.
+            let inner = unsafe { &mut *self.inner.get() };
+            inner.reserved -= room;
+            let mut first = Some(first).filter(|_| inner.len == 0);
+
+            for index in 0..len {
+                // SAFETY: The first `len` slots were written above and each is read once.
+                let payload = unsafe { ptr::read(slots.add(index)) };
+                let payload = match first.take() {
+                    Some(first) => first(payload),
+                    None => Some(payload),
+                };
+                if let Some(payload) = payload {
+                    // Fits, the reservation kept the room free.
+                    inner.push(payload);
+                }
+            }
+        });
+
+        len
+    }
+
+    /// The number of queued payloads.
+    pub fn len(&self) -> usize {
+        // SAFETY: Only accessed inside critical sections.
+        critical_section::with(|_| unsafe { (*self.inner.get()).len })
+    }
+
+    /// Whether no payload is queued.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops the queued payloads and returns how many there were.
+    pub fn clear(&self) -> usize {
+        critical_section::with(|_| {
+            // SAFETY: Only accessed inside critical sections.
+            let inner = unsafe { &mut *self.inner.get() };
+            let dropped = inner.len;
+            while inner.len > 0 {
+                drop(inner.pop());
+            }
+
+            dropped
+        })
+    }
+
+    /// Pops the oldest payload if `allocate` succeeds in reserving the executor for it.
+    pub fn pop_if(&self, allocate: impl FnOnce() -> bool) -> Option<T> {
+        critical_section::with(|_| {
+            // SAFETY: Only accessed inside critical sections.
+            let inner = unsafe { &mut *self.inner.get() };
+
+            if inner.len > 0 && allocate() {
+                Some(inner.pop())
+            } else {
+                None
+            }
+        })
+    }
+}
@@ -0,0 +1,50 @@
+use super::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// Tracks whether a dispatcher has been pended since it last started a pass over its tasks.
+pub struct Coalescer {
+    pended: AtomicBool,
+    requests: AtomicU32,
+    coalesced: AtomicU32,
+}
+
+impl Default for Coalescer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Coalescer {
+    pub const fn new() -> Self {
+        Self {
+            pended: AtomicBool::new(false),
+            requests: AtomicU32::new(0),
+            coalesced: AtomicU32::new(0),
+        }
+    }
+
+    /// Records a spawn or wake, returns `true` if the dispatcher needs to be pended.
+    #[inline(always)]
+    pub fn request(&self) -> bool {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+
+        if self.pended.swap(true, Ordering::SeqCst) {
+            self.coalesced.fetch_add(1, Ordering::Relaxed);
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Called by the dispatcher before its pass, requests from then on pend it again.
+    #[inline(always)]
+    pub fn start_pass(&self) {
+        self.pended.store(false, Ordering::SeqCst);
+    }
+
+    pub fn stats(&self) -> crate::stats::CoalesceStats {
+        crate::stats::CoalesceStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            coalesced: self.coalesced.load(Ordering::Relaxed),
+        }
+    }
+}
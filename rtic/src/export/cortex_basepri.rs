@@ -1,9 +1,14 @@
-use super::cortex_logical2hw;
+use super::{
+    active_priority, cortex_hw2logical, cortex_logical2hw, max_priority, nvic_barrier,
+    nvic_prio_bits,
+};
+#[cfg(feature = "thumbv8main-backend")]
+use cortex_m::interrupt::InterruptNumber;
 use cortex_m::register::{basepri, basepri_max};
 pub use cortex_m::{
     asm::wfi,
     interrupt,
-    peripheral::{scb::SystemHandler, DWT, SCB, SYST},
+    peripheral::{scb::SystemHandler, DWT, NVIC, SCB, SYST},
     Peripherals,
 };
 
@@ -28,6 +33,25 @@ where
     }
 }
 
+/// The logical priority of the running code, the ceiling of the held locks included
+///
+/// The priority of the active handler, raised by `BASEPRI` when it is masking more. With
+/// interrupts disabled, e.g. in `init` or a critical section, this is the highest priority.
+#[inline]
+pub fn current_priority() -> u8 {
+    let nvic_prio_bits = nvic_prio_bits();
+    if cortex_m::register::primask::read().is_inactive() {
+        return max_priority(nvic_prio_bits);
+    }
+
+    let active = active_priority(nvic_prio_bits);
+
+    match basepri::read() {
+        0 => active,
+        hw => active.max(cortex_hw2logical(hw, nvic_prio_bits)),
+    }
+}
+
 /// Lock implementation using BASEPRI and global Critical Section (CS)
 ///
 /// # Safety
@@ -73,9 +97,154 @@ pub unsafe fn lock<T, R>(
         critical_section::with(|_| f(&mut *ptr))
     } else {
         let current = basepri::read();
-        basepri_max::write(cortex_logical2hw(ceiling, nvic_prio_bits));
+        raise_basepri(cortex_logical2hw(ceiling, nvic_prio_bits));
         let r = f(&mut *ptr);
         basepri::write(current);
         r
     }
 }
+
+/// ARMv8-M with TrustZone: lock of a gateway, a resource shared between secure tasks and tasks
+/// serving the non-secure state
+///
+/// # Safety
+///
+/// As [`lock`], which runs in the secure state and raises `BASEPRI_S`. Here `BASEPRI_NS` is raised
+/// to the ceiling as well, so the handlers of the non-secure image up to the ceiling cannot run,
+/// and change the non-secure memory of the resource, until `f` returns. A ceiling at the maximum
+/// priority disables all interrupts of both states.
+#[cfg(feature = "thumbv8main-backend")]
+#[inline(always)]
+pub unsafe fn lock_gateway<T, R>(
+    ptr: *mut T,
+    ceiling: u8,
+    nvic_prio_bits: u8,
+    f: impl FnOnce(&mut T) -> R,
+) -> R {
+    if ceiling == (1 << nvic_prio_bits) {
+        critical_section::with(|_| f(&mut *ptr))
+    } else {
+        let hw = cortex_logical2hw(ceiling, nvic_prio_bits);
+        let current_ns = basepri_ns::read();
+        if current_ns == 0 || current_ns > hw {
+            basepri_ns::write(hw);
+        }
+        let r = lock(ptr, ceiling, nvic_prio_bits, f);
+        basepri_ns::write(current_ns);
+        r
+    }
+}
+
+/// `BASEPRI_NS`, the `BASEPRI` of the non-secure state, accessed from the secure state
+///
+/// Encoded by hand, the assembler only accepts the register with the security extension enabled.
+/// Off target, e.g. when checking on the host, the accessors are not implemented, as the ones of
+/// `cortex_m`.
+#[cfg(feature = "thumbv8main-backend")]
+mod basepri_ns {
+    #[cfg(target_arch = "arm")]
+    use core::arch::asm;
+
+    #[cfg(target_arch = "arm")]
+    #[inline(always)]
+    pub fn read() -> u8 {
+        let r: u32;
+        // SAFETY: `mrs r0, BASEPRI_NS` reads a register without side effects
+        unsafe {
+            asm!(".inst.w 0xf3ef8091", out("r0") r, options(nomem, nostack, preserves_flags))
+        };
+        r as u8
+    }
+
+    #[cfg(target_arch = "arm")]
+    #[inline(always)]
+    pub unsafe fn write(hw: u8) {
+        // `msr BASEPRI_NS, r0`, a compiler fence as for `BASEPRI`
+        asm!(".inst.w 0xf3808891", in("r0") hw as u32, options(nostack, preserves_flags));
+    }
+
+    #[cfg(not(target_arch = "arm"))]
+    pub fn read() -> u8 {
+        unimplemented!()
+    }
+
+    #[cfg(not(target_arch = "arm"))]
+    pub unsafe fn write(_hw: u8) {
+        unimplemented!()
+    }
+}
+
+/// ARMv8-M with TrustZone: `interrupt` is taken from the secure vector table
+///
+/// Clears its bit in the NVIC Interrupt Target Non-secure Registers, only writable from the
+/// secure state.
+#[cfg(feature = "thumbv8main-backend")]
+#[inline]
+pub fn set_target_secure<I: InterruptNumber>(interrupt: I) {
+    let (itns, bit) = itns(interrupt);
+    // NOTE(unsafe): called before interrupts are enabled, no other context modifies `ITNS`
+    unsafe { itns.write_volatile(itns.read_volatile() & !bit) };
+}
+
+/// ARMv8-M with TrustZone: `interrupt` is taken from the vector table of the non-secure image
+///
+/// Sets its bit in the NVIC Interrupt Target Non-secure Registers, the non-secure image sets its
+/// priority and enables it.
+#[cfg(feature = "thumbv8main-backend")]
+#[inline]
+pub fn set_target_nonsecure<I: InterruptNumber>(interrupt: I) {
+    let (itns, bit) = itns(interrupt);
+    // NOTE(unsafe): called before interrupts are enabled, no other context modifies `ITNS`
+    unsafe { itns.write_volatile(itns.read_volatile() | bit) };
+}
+
+/// The `ITNS` word of `interrupt` and its bit in it
+#[cfg(feature = "thumbv8main-backend")]
+fn itns<I: InterruptNumber>(interrupt: I) -> (*mut u32, u32) {
+    const ITNS: usize = 0xE000_E380;
+
+    let n = usize::from(interrupt.number());
+    ((ITNS + 4 * (n / 32)) as *mut u32, 1 << (n % 32))
+}
+
+/// Raises `BASEPRI` to `hw`, if it is lower
+///
+/// With `strict-memory-ordering` the write is done with interrupts disabled, the workaround for
+/// Cortex-M7 erratum 837070 where an interrupt can still be taken right after raising `BASEPRI`,
+/// and followed by the barriers making it take effect before the resource is accessed.
+#[inline(always)]
+unsafe fn raise_basepri(hw: u8) {
+    #[cfg(feature = "strict-memory-ordering")]
+    {
+        let primask = cortex_m::register::primask::read();
+        interrupt::disable();
+        basepri_max::write(hw);
+        nvic_barrier();
+        if primask.is_active() {
+            interrupt::enable();
+        }
+    }
+
+    #[cfg(not(feature = "strict-memory-ordering"))]
+    basepri_max::write(hw);
+}
+
+/// Requests device interrupt `n` through the NVIC Software Trigger Interrupt Register
+///
+/// Returns `false` if `n` is not a valid interrupt number.
+#[inline]
+pub fn swi_request(n: u16) -> bool {
+    #[cfg(feature = "thumbv7-backend")]
+    const INTERRUPTS: u16 = 496;
+    #[cfg(not(feature = "thumbv7-backend"))]
+    const INTERRUPTS: u16 = 480;
+
+    if n >= INTERRUPTS {
+        return false;
+    }
+
+    // NOTE(unsafe): atomic write to a stateless register
+    unsafe { (*NVIC::PTR).stir.write(u32::from(n)) };
+    nvic_barrier();
+    true
+}
@@ -1,11 +1,78 @@
+use cortex_m::peripheral::{scb::SystemHandler, SCB};
 pub use cortex_m::{interrupt::InterruptNumber, peripheral::NVIC, register::msp};
 
+/// The highest logical priority of a device implementing `nvic_prio_bits` NVIC priority bits
+///
+/// On cores with `BASEPRI` this is device specific, call it with `NVIC_PRIO_BITS` of the PAC.
+#[inline]
+#[must_use]
+pub const fn max_priority(nvic_prio_bits: u8) -> u8 {
+    1 << nvic_prio_bits
+}
+
 #[inline]
 #[must_use]
 pub const fn cortex_logical2hw(logical: u8, nvic_prio_bits: u8) -> u8 {
     ((1 << nvic_prio_bits) - logical) << (8 - nvic_prio_bits)
 }
 
+#[inline]
+#[must_use]
+pub(crate) const fn cortex_hw2logical(hw: u8, nvic_prio_bits: u8) -> u8 {
+    (1 << nvic_prio_bits) - (hw >> (8 - nvic_prio_bits))
+}
+
+/// The number of NVIC priority bits of the device, provided by the `#[app]` macro
+#[inline]
+pub(crate) fn nvic_prio_bits() -> u8 {
+    extern "Rust" {
+        fn __rtic_nvic_prio_bits() -> u8;
+    }
+    // SAFETY: the function is generated by the `#[app]` macro
+    unsafe { __rtic_nvic_prio_bits() }
+}
+
+/// The logical priority of the active exception or interrupt, `0` in thread mode
+///
+/// `NMI` and `HardFault` are reported as the highest logical priority.
+pub(crate) fn active_priority(nvic_prio_bits: u8) -> u8 {
+    #[derive(Clone, Copy)]
+    struct Irq(u16);
+
+    // SAFETY: `Irq` is only created from the active vector, which is a valid interrupt
+    unsafe impl InterruptNumber for Irq {
+        fn number(self) -> u16 {
+            self.0
+        }
+    }
+
+    // NOTE(unsafe): atomic read of a stateless register
+    let vector = unsafe { (*SCB::PTR).icsr.read() } & 0x1ff;
+
+    let handler = match vector {
+        0 => return 0,
+        2 | 3 => return max_priority(nvic_prio_bits),
+        #[cfg(feature = "cortex-m-basepri")]
+        4 => SystemHandler::MemoryManagement,
+        #[cfg(feature = "cortex-m-basepri")]
+        5 => SystemHandler::BusFault,
+        #[cfg(feature = "cortex-m-basepri")]
+        6 => SystemHandler::UsageFault,
+        11 => SystemHandler::SVCall,
+        #[cfg(feature = "cortex-m-basepri")]
+        12 => SystemHandler::DebugMonitor,
+        14 => SystemHandler::PendSV,
+        15 => SystemHandler::SysTick,
+        16.. => {
+            let hw = NVIC::get_priority(Irq(vector as u16 - 16));
+            return cortex_hw2logical(hw, nvic_prio_bits);
+        }
+        _ => return max_priority(nvic_prio_bits),
+    };
+
+    cortex_hw2logical(SCB::get_priority(handler), nvic_prio_bits)
+}
+
 /// Sets the given `interrupt` as pending
 ///
 /// This is a convenience function around
@@ -15,4 +82,188 @@ where
     I: InterruptNumber,
 {
     NVIC::pend(interrupt);
+    nvic_barrier();
+}
+
+/// Moves `interrupt` to the logical `priority`, backing `set_priority` of the application
+///
+/// Backend contract: the new priority is in effect when the function returns, also for a pending
+/// interrupt or for its running handler, which the NVIC moves to the new priority at once.
+///
+/// # Safety
+///
+/// `priority` must be within `1..=max_priority` and must not exceed the ceiling of any resource
+/// the task bound to `interrupt` accesses, which the generated `set_priority` checks.
+#[inline]
+pub unsafe fn set_priority<I>(interrupt: I, priority: u8)
+where
+    I: InterruptNumber,
+{
+    let hw = cortex_logical2hw(priority, nvic_prio_bits());
+    // The core peripherals were taken before `init`. The ARMv6-M NVIC holds four priorities per
+    // word, updated read-modify-write.
+    critical_section::with(|_| {
+        cortex_m::Peripherals::steal()
+            .NVIC
+            .set_priority(interrupt, hw)
+    });
+    nvic_barrier();
+}
+
+/// Wakes `main` from [`idle_wait`], called when a task of priority 0 is spawned or woken
+///
+/// Backend contract: a wake is latched until the next [`idle_wait`], which then returns without
+/// sleeping, also when the wake lands between its check for ready tasks and the sleep. Here the
+/// event register of `SEV`/`WFE` latches it, the `DSB` makes the ready flag visible first.
+#[inline(always)]
+pub fn wake_idle() {
+    cortex_m::asm::dsb();
+    cortex_m::asm::sev();
+}
+
+/// Sleeps in the zero priority dispatcher of `main` if `is_idle` reports no task ready
+///
+/// `WFE` returns at once if [`wake_idle`] ran since the last one, an interrupt preempting
+/// `main` wakes it as well. The wakes of earlier passes only cost an extra pass.
+#[inline(always)]
+pub fn idle_wait(is_idle: impl FnOnce() -> bool) {
+    if is_idle() {
+        cortex_m::asm::wfe();
+    }
+}
+
+/// Makes a write to the NVIC or to `BASEPRI` take effect before the next instruction
+///
+/// The architecture only guarantees this after a `DSB` and an `ISB`. Cores with a store buffer
+/// or dual issue, like the Cortex-M7, can otherwise execute a few more instructions with the old
+/// configuration, e.g. start accessing a resource before the interrupts sharing it are masked,
+/// or continue before a pended task runs. Emitted with the `strict-memory-ordering` feature.
+#[inline(always)]
+pub(crate) fn nvic_barrier() {
+    #[cfg(feature = "strict-memory-ordering")]
+    {
+        cortex_m::asm::dsb();
+        cortex_m::asm::isb();
+    }
+}
+
+/// Sets `SCR.SLEEPONEXIT` and sleeps, ending `main` with `#[app(sleep_on_exit = true)]`
+///
+/// The core goes back to sleep when the last handler returns instead of returning to thread
+/// mode, saving the transitions to and from `main` of an application without `idle`.
+#[inline]
+pub fn sleep_on_exit() -> ! {
+    // NOTE(unsafe): read-modify-write of `SCR`, only done here, once `init` has returned
+    unsafe { (*SCB::PTR).scr.modify(|scr| scr | 1 << 1) };
+    cortex_m::asm::dsb();
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
+
+/// The number of vector table entries covering the exceptions and the interrupts `irqs`
+#[inline]
+#[must_use]
+pub const fn vector_table_len(irqs: &[usize]) -> usize {
+    let mut highest = 0;
+    let mut i = 0;
+    while i < irqs.len() {
+        if irqs[i] + 1 > highest {
+            highest = irqs[i] + 1;
+        }
+        i += 1;
+    }
+    16 + highest
+}
+
+/// `VTOR` requires the table to be aligned to its size rounded up to a power of two, at least
+/// 32 words
+const fn vector_table_align(len: usize) -> usize {
+    let words = len.next_power_of_two();
+    if words < 32 {
+        32
+    } else {
+        words
+    }
+}
+
+/// The words of a [`RamVectors`] holding a table of `len` entries
+///
+/// The buffer is only aligned to 32 words, the table is placed at the first offset with the
+/// alignment `VTOR` requires.
+#[inline]
+#[must_use]
+pub const fn ram_vectors_words(len: usize) -> usize {
+    len + vector_table_align(len) - 32
+}
+
+/// The vector table of the application copied to RAM, `#[app(relocate_vectors = true)]`
+#[repr(C, align(128))]
+pub struct RamVectors<const WORDS: usize>(core::cell::UnsafeCell<[usize; WORDS]>);
+
+// SAFETY: Only written by `install`, before interrupts are enabled
+unsafe impl<const WORDS: usize> Sync for RamVectors<WORDS> {}
+
+impl<const WORDS: usize> RamVectors<WORDS> {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self(core::cell::UnsafeCell::new([0; WORDS]))
+    }
+
+    /// Copies the first `len` entries of the vector table the application is linked with and
+    /// points `VTOR` at the copy
+    ///
+    /// A bootloader launching the application leaves `VTOR` at its own table, this switches to the
+    /// one of the application wherever it was linked. Interrupts above the `len` entries are not
+    /// covered by the copy and must stay disabled.
+    ///
+    /// # Safety
+    ///
+    /// Must be called once before any interrupt is enabled, on a core with `VTOR`, i.e. not a
+    /// Cortex-M0.
+    #[inline]
+    pub unsafe fn install(&self, len: usize) {
+        self.install_moved(len, 0);
+    }
+
+    /// As [`install`](Self::install), for an image running `offset` bytes from the address it
+    /// was linked at, see `position-independent`
+    ///
+    /// The table is read from the running image and its handlers moved by `offset`, the initial
+    /// stack pointer and the reserved entries are left as they are.
+    ///
+    /// # Safety
+    ///
+    /// As [`install`](Self::install).
+    #[inline]
+    pub unsafe fn install_moved(&self, len: usize, offset: usize) {
+        extern "C" {
+            // Start of the vector table, provided by `cortex-m-rt`
+            static __vector_table: u32;
+        }
+
+        let align = vector_table_align(len) * 4;
+        let base = self.0.get() as usize;
+        let table = (base + align - 1) & !(align - 1);
+
+        core::ptr::copy_nonoverlapping(
+            (core::ptr::addr_of!(__vector_table) as usize).wrapping_add(offset) as *const usize,
+            table as *mut usize,
+            len,
+        );
+
+        if offset != 0 {
+            for i in 1..len {
+                let entry = (table as *mut usize).add(i);
+                if *entry != 0 {
+                    *entry = (*entry).wrapping_add(offset);
+                }
+            }
+        }
+
+        (*SCB::PTR).vtor.write(table as u32);
+        cortex_m::asm::dsb();
+        cortex_m::asm::isb();
+    }
 }
@@ -5,11 +5,20 @@ pub use cortex_m::{
     Peripherals,
 };
 
-#[cfg(not(any(feature = "thumbv6-backend", feature = "thumbv8base-backend")))]
+use super::{active_priority, nvic_barrier, nvic_prio_bits};
+
+#[cfg(not(any(
+    feature = "thumbv6-backend",
+    feature = "thumbv8base-backend",
+    feature = "thumbv6-rp2040-backend"
+)))]
 compile_error!(
-    "Building for Cortex-M with source masking, but 'thumbv6-backend' or 'thumbv8base-backend' backend not selected"
+    "Building for Cortex-M with source masking, but 'thumbv6-backend', 'thumbv8base-backend' or 'thumbv6-rp2040-backend' backend not selected"
 );
 
+/// The highest logical priority, ARMv6-M and ARMv8-M Baseline implement 2 NVIC priority bits
+pub const MAX_PRIORITY: u8 = super::max_priority(2);
+
 /// Mask is used to store interrupt masks on systems without a BASEPRI register (M0, M0+, M23).
 /// It needs to be large enough to cover all the relevant interrupts in use.
 /// For M0/M0+ there are only 32 interrupts so we only need one u32 value.
@@ -74,13 +83,27 @@ where
     f();
 }
 
+/// The logical priority of the running code
+///
+/// The priority of the active handler. Masking the interrupts of a lock does not change it, so
+/// unlike with `BASEPRI` the ceiling of the held locks is not included. With interrupts
+/// disabled, e.g. in `init` or a lock at the highest ceiling, this is the highest priority.
+#[inline]
+pub fn current_priority() -> u8 {
+    if cortex_m::register::primask::read().is_inactive() {
+        return MAX_PRIORITY;
+    }
+
+    active_priority(nvic_prio_bits())
+}
+
 /// Lock implementation using interrupt masking
 ///
 /// # Safety
 ///
-/// The system ceiling is raised from current to ceiling
-/// by computing a 32 bit `mask` (1 bit per interrupt)
-/// 1: ceiling >= priority > current
+/// The tasks that can contend for the resource are kept from running
+/// by a 32 bit `mask` (1 bit per interrupt)
+/// 1: the interrupt of a task accessing the resource
 /// 0: else
 ///
 /// On CS entry, `clear_enable_mask(mask)` disables interrupts
@@ -118,11 +141,16 @@ where
 /// - Temporary lower exception priority
 ///
 /// These possible solutions are set goals for future work
+///
+/// `mask` holds the interrupts of the tasks that access the resource, computed at compile time by
+/// the `#[app]` macro so the lock costs the same in debug builds. Interrupts of other tasks keep
+/// preempting the critical section, even below the ceiling. Only for resources whose accessors
+/// are not all known, those exported to C, is it [`ceiling_mask`]`(ceiling, masks)`.
 #[inline(always)]
 pub unsafe fn lock<T, R, const M: usize>(
     ptr: *mut T,
     ceiling: u8,
-    masks: &[Mask<M>; 3],
+    mask: Mask<M>,
     f: impl FnOnce(&mut T) -> R,
 ) -> R {
     if ceiling >= 4 {
@@ -132,10 +160,9 @@ pub unsafe fn lock<T, R, const M: usize>(
         // safe to manipulate outside critical section
         critical_section::with(|_| f(&mut *ptr))
     } else {
-        // safe to manipulate outside critical section
-        let mask = compute_mask(0, ceiling, masks);
         let old_mask = read_mask(mask);
         clear_enable_mask(mask);
+        nvic_barrier();
 
         // execute closure under protection of raised system ceiling
         let r = f(&mut *ptr);
@@ -147,6 +174,18 @@ pub unsafe fn lock<T, R, const M: usize>(
     }
 }
 
+/// The interrupts to disable for a lock at `ceiling`, the ones of all priorities up to it
+///
+/// Empty if `ceiling` is above the priorities that can be masked, such a lock is a global
+/// critical section.
+pub const fn ceiling_mask<const M: usize>(ceiling: u8, masks: &[Mask<M>; 3]) -> Mask<M> {
+    if ceiling >= 4 {
+        Mask([0; M])
+    } else {
+        compute_mask(0, ceiling, masks)
+    }
+}
+
 #[inline(always)]
 pub const fn compute_mask<const M: usize>(
     from_prio: u8,
@@ -177,24 +216,35 @@ pub const fn compute_mask<const M: usize>(
     res
 }
 
-// enables interrupts
+// reads the enable state of the interrupts in `mask`
 #[inline(always)]
 unsafe fn read_mask<const M: usize>(mask: Mask<M>) -> Mask<M> {
     let mut out = Mask([0; M]);
 
+    if M == 1 {
+        // A single register covers all interrupts, e.g. on all ARMv6-M
+        out.0[0] = (*NVIC::PTR).iser[0].read() & mask.0[0];
+        return out;
+    }
+
     for i in 0..M {
         // This check should involve compile time constants and be optimized out.
         if mask.0[i] != 0 {
-            out.0[i] = (*NVIC::PTR).iser[i].read();
+            out.0[i] = (*NVIC::PTR).iser[i].read() & mask.0[i];
         }
     }
 
     out
 }
 
-// enables interrupts
+// enables the interrupts in `mask` that were enabled in `old_mask`
 #[inline(always)]
 unsafe fn set_enable_mask<const M: usize>(mask: Mask<M>, old_mask: Mask<M>) {
+    if M == 1 {
+        (*NVIC::PTR).iser[0].write(old_mask.0[0]);
+        return;
+    }
+
     for i in 0..M {
         // This check should involve compile time constants and be optimized out.
         if mask.0[i] != 0 {
@@ -206,6 +256,11 @@ unsafe fn set_enable_mask<const M: usize>(mask: Mask<M>, old_mask: Mask<M>) {
 // disables interrupts
 #[inline(always)]
 unsafe fn clear_enable_mask<const M: usize>(mask: Mask<M>) {
+    if M == 1 {
+        (*NVIC::PTR).icer[0].write(mask.0[0]);
+        return;
+    }
+
     for i in 0..M {
         // This check should involve compile time constants and be optimized out.
         if mask.0[i] != 0 {
@@ -213,3 +268,24 @@ unsafe fn clear_enable_mask<const M: usize>(mask: Mask<M>) {
         }
     }
 }
+
+/// Requests device interrupt `n` by setting its bit in the NVIC Interrupt Set-Pending Register
+///
+/// ARMv6-M and ARMv8-M Baseline do not implement the Software Trigger Interrupt Register.
+/// Returns `false` if `n` is not a valid interrupt number.
+#[inline]
+pub fn swi_request(n: u16) -> bool {
+    #[cfg(feature = "thumbv6-backend")]
+    const INTERRUPTS: u16 = 32;
+    #[cfg(not(feature = "thumbv6-backend"))]
+    const INTERRUPTS: u16 = 480;
+
+    if n >= INTERRUPTS {
+        return false;
+    }
+
+    // NOTE(unsafe): atomic write to a stateless register
+    unsafe { (*NVIC::PTR).ispr[usize::from(n / 32)].write(1 << (n % 32)) };
+    nvic_barrier();
+    true
+}
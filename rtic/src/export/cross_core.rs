@@ -0,0 +1,93 @@
+//! Spawns of the tasks declared with `cross_core = N` from the other core, see
+//! [`crate::rp2040`]
+//!
+//! The payloads go through a [`CrossCoreQueue`] of the task in memory both cores reach. The
+//! spawning core pushes to it with the interrupts masked, so its tasks push one at a time, and
+//! only the dispatcher of the task on its own core pops from it, so the queue needs no lock.
+
+use core::{cell::UnsafeCell, mem::MaybeUninit, ptr};
+use portable_atomic::{AtomicUsize, Ordering};
+
+pub use crate::rp2040::{core as core_id, ring_with as pend_remote};
+
+/// Payloads spawned from the other core, waiting for the dispatcher of the task.
+///
+/// A single producer, the other core, and a single consumer, the dispatcher of the task.
+pub struct CrossCoreQueue<T, const N: usize> {
+    buffer: UnsafeCell<MaybeUninit<[T; N]>>,
+    /// Payloads pushed so far, wrapping, written by the other core
+    tail: AtomicUsize,
+    /// Payloads popped so far, wrapping, written by the dispatcher
+    head: AtomicUsize,
+}
+
+unsafe impl<T: Send, const N: usize> Sync for CrossCoreQueue<T, N> {}
+
+impl<T, const N: usize> Default for CrossCoreQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> CrossCoreQueue<T, N> {
+    /// Create an empty queue.
+    #[inline(always)]
+    pub const fn new() -> Self {
+        Self {
+            buffer: UnsafeCell::new(MaybeUninit::uninit()),
+            tail: AtomicUsize::new(0),
+            head: AtomicUsize::new(0),
+        }
+    }
+
+    fn slot(&self, index: usize) -> *mut T {
+        // SAFETY: The index is reduced modulo `N`.
+        unsafe { (self.buffer.get() as *mut T).add(index % N) }
+    }
+
+    /// Queues `payload`, returns it back if the queue is full.
+    ///
+    /// Only called by the other core, with its interrupts masked.
+    pub fn push(&self, payload: T) -> Result<(), T> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail.wrapping_sub(self.head.load(Ordering::Acquire)) == N {
+            return Err(payload);
+        }
+
+        // SAFETY: The slot is outside of the queued ones, the dispatcher does not read it.
+        unsafe { ptr::write(self.slot(tail), payload) };
+        // Publishes the payload to the dispatcher
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+
+        Ok(())
+    }
+
+    /// Pops the oldest payload if `allocate` succeeds in reserving the executor for it.
+    ///
+    /// Only called by the dispatcher of the task.
+    pub fn pop_if(&self, allocate: impl FnOnce() -> bool) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed);
+        if head == self.tail.load(Ordering::Acquire) || !allocate() {
+            return None;
+        }
+
+        // SAFETY: The slot is the oldest of the queued ones, the other core does not write it.
+        let payload = unsafe { ptr::read(self.slot(head)) };
+        // Hands the slot back to the other core
+        self.head.store(head.wrapping_add(1), Ordering::Release);
+
+        Some(payload)
+    }
+
+    /// The number of queued payloads.
+    pub fn len(&self) -> usize {
+        self.tail
+            .load(Ordering::Acquire)
+            .wrapping_sub(self.head.load(Ordering::Acquire))
+    }
+
+    /// Whether no payload is queued.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -7,11 +7,57 @@ use core::{
     task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
 };
 
+#[cfg(not(feature = "position-independent"))]
 static WAKER_VTABLE: RawWakerVTable =
     RawWakerVTable::new(waker_clone, waker_wake, waker_wake, waker_drop);
 
+/// In RAM, the functions of the running image are only known once it runs, see
+/// [`relocate_waker_vtable`]
+#[cfg(feature = "position-independent")]
+static WAKER_VTABLE: crate::RacyCell<RawWakerVTable> = crate::RacyCell::new(RawWakerVTable::new(
+    waker_clone,
+    waker_wake,
+    waker_wake,
+    waker_drop,
+));
+
+#[inline(always)]
+fn waker_vtable() -> &'static RawWakerVTable {
+    #[cfg(not(feature = "position-independent"))]
+    {
+        &WAKER_VTABLE
+    }
+
+    // SAFETY: Only written by `relocate_waker_vtable`, before any task runs
+    #[cfg(feature = "position-independent")]
+    unsafe {
+        &*WAKER_VTABLE.get()
+    }
+}
+
+/// Fills the waker vtable with the addresses of the functions in the running image
+///
+/// The initializer of the static holds the addresses the image was linked at, the ones taken
+/// here are computed relative to the code. The `black_box` keeps the compiler from folding them
+/// back into a copy of the initializer.
+///
+/// # Safety
+///
+/// Called by [`super::pic::fixup`], before any waker is created.
+#[cfg(feature = "position-independent")]
+pub(crate) unsafe fn relocate_waker_vtable() {
+    use core::hint::black_box;
+
+    *WAKER_VTABLE.get_mut() = RawWakerVTable::new(
+        black_box(waker_clone as unsafe fn(*const ()) -> RawWaker),
+        black_box(waker_wake as unsafe fn(*const ())),
+        black_box(waker_wake as unsafe fn(*const ())),
+        black_box(waker_drop as unsafe fn(*const ())),
+    );
+}
+
 unsafe fn waker_clone(p: *const ()) -> RawWaker {
-    RawWaker::new(p, &WAKER_VTABLE)
+    RawWaker::new(p, waker_vtable())
 }
 
 unsafe fn waker_wake(p: *const ()) {
@@ -64,6 +110,54 @@ pub struct AsyncTaskExecutor<F: Future> {
     task: UnsafeCell<MaybeUninit<F>>,
     running: AtomicBool,
     pending: AtomicBool,
+    #[cfg(feature = "executor-stats")]
+    stats: Stats,
+    #[cfg(feature = "starvation-detector")]
+    starvation: Starvation,
+}
+
+/// Tracks how long a task has been ready without running.
+#[cfg(feature = "starvation-detector")]
+struct Starvation {
+    /// Consecutive checks that found the task ready and not polled since the previous one
+    checks: super::atomic::AtomicU32,
+    /// Set when the task is polled, cleared by each check
+    polled: AtomicBool,
+}
+
+/// Statistics kept by an executor.
+#[cfg(feature = "executor-stats")]
+struct Stats {
+    polls: super::atomic::AtomicU32,
+    /// `WakeSource as u8 + 1`, 0 if never woken
+    last_wake: super::atomic::AtomicU8,
+    /// Set while the future is polled, to attribute wakes from within the task to it
+    polling: AtomicBool,
+    #[cfg(feature = "energy-stats")]
+    energy: crate::energy::Meter,
+}
+
+#[cfg(feature = "executor-stats")]
+impl Stats {
+    const fn new() -> Self {
+        Self {
+            polls: super::atomic::AtomicU32::new(0),
+            last_wake: super::atomic::AtomicU8::new(0),
+            polling: AtomicBool::new(false),
+            #[cfg(feature = "energy-stats")]
+            energy: crate::energy::Meter::new(),
+        }
+    }
+
+    fn record_wake(&self, source: crate::stats::WakeSource) {
+        self.last_wake.store(source as u8 + 1, Ordering::Relaxed);
+    }
+
+    // Only the dispatcher of the task polls, a plain load and store suffice.
+    fn record_poll(&self) {
+        let polls = self.polls.load(Ordering::Relaxed);
+        self.polls.store(polls.wrapping_add(1), Ordering::Relaxed);
+    }
 }
 
 unsafe impl<F: Future> Sync for AsyncTaskExecutor<F> {}
@@ -100,6 +194,13 @@ impl<F: Future> AsyncTaskExecutor<F> {
             task: UnsafeCell::new(MaybeUninit::uninit()),
             running: AtomicBool::new(false),
             pending: AtomicBool::new(false),
+            #[cfg(feature = "executor-stats")]
+            stats: Stats::new(),
+            #[cfg(feature = "starvation-detector")]
+            starvation: Starvation {
+                checks: super::atomic::AtomicU32::new(0),
+                polled: AtomicBool::new(false),
+            },
         }
     }
 
@@ -158,6 +259,12 @@ impl<F: Future> AsyncTaskExecutor<F> {
         self.running.load(Ordering::Relaxed)
     }
 
+    /// Check if the task in the executor was spawned or woken and waits to be polled.
+    #[inline(always)]
+    pub fn is_ready(&self) -> bool {
+        self.is_running() && self.pending.load(Ordering::Relaxed)
+    }
+
     /// Checks if a waker has pended the executor and simultaneously clears the flag.
     #[inline(always)]
     fn check_and_clear_pending(&self) -> bool {
@@ -170,9 +277,59 @@ impl<F: Future> AsyncTaskExecutor<F> {
     // Used by wakers to indicate that the executor needs to run.
     #[inline(always)]
     pub fn set_pending(&self) {
+        #[cfg(feature = "executor-stats")]
+        self.stats
+            .record_wake(match rtic_common::wake_source::current() {
+                // Woken without a hint while polling, most likely by the task itself
+                crate::stats::WakeSource::Unknown if self.stats.polling.load(Ordering::Relaxed) => {
+                    crate::stats::WakeSource::Task
+                }
+                source => source,
+            });
+
         self.pending.store(true, Ordering::Release);
     }
 
+    /// The statistics of the task in the executor.
+    #[cfg(feature = "executor-stats")]
+    pub fn stats(&self) -> crate::stats::TaskStats {
+        #[cfg(feature = "energy-stats")]
+        let (busy_ticks, energy_nj) = self.stats.energy.read();
+
+        crate::stats::TaskStats {
+            polls: self.stats.polls.load(Ordering::Relaxed),
+            last_wake: match self.stats.last_wake.load(Ordering::Relaxed) {
+                0 => None,
+                raw => Some(crate::stats::WakeSource::from_u8(raw - 1)),
+            },
+            #[cfg(feature = "energy-stats")]
+            busy_ticks,
+            #[cfg(feature = "energy-stats")]
+            energy_nj,
+        }
+    }
+
+    /// Returns for how many consecutive calls the task has been ready without being polled.
+    ///
+    /// Meant to be called periodically from a priority above the task's.
+    #[cfg(feature = "starvation-detector")]
+    pub fn check_starvation(&self) -> u32 {
+        let polled = self.starvation.polled.swap(false, Ordering::Relaxed);
+        let ready = self.is_ready();
+
+        let checks = if ready && !polled {
+            self.starvation
+                .checks
+                .load(Ordering::Relaxed)
+                .saturating_add(1)
+        } else {
+            0
+        };
+        self.starvation.checks.store(checks, Ordering::Relaxed);
+
+        checks
+    }
+
     /// Allocate the executor. To use with `spawn`.
     #[inline(always)]
     pub unsafe fn try_allocate(&self) -> bool {
@@ -189,18 +346,41 @@ impl<F: Future> AsyncTaskExecutor<F> {
         unsafe {
             self.task.get().write(MaybeUninit::new(future));
         }
-        self.set_pending();
+        #[cfg(feature = "executor-stats")]
+        self.stats.record_wake(crate::stats::WakeSource::Spawn);
+        self.pending.store(true, Ordering::Release);
     }
 
     /// Poll the future in the executor.
     #[inline(always)]
     pub fn poll(&self, wake: fn()) {
         if self.is_running() && self.check_and_clear_pending() {
-            let waker = unsafe { Waker::from_raw(RawWaker::new(wake as *const (), &WAKER_VTABLE)) };
+            let waker =
+                unsafe { Waker::from_raw(RawWaker::new(wake as *const (), waker_vtable())) };
             let mut cx = Context::from_waker(&waker);
             let future = unsafe { Pin::new_unchecked(&mut *(self.task.get() as *mut F)) };
 
-            match future.poll(&mut cx) {
+            #[cfg(feature = "executor-stats")]
+            {
+                self.stats.record_poll();
+                self.stats.polling.store(true, Ordering::Relaxed);
+            }
+
+            #[cfg(feature = "starvation-detector")]
+            self.starvation.polled.store(true, Ordering::Relaxed);
+
+            #[cfg(feature = "energy-stats")]
+            let start = crate::energy::start();
+
+            let poll = future.poll(&mut cx);
+
+            #[cfg(feature = "energy-stats")]
+            self.stats.energy.stop(start);
+
+            #[cfg(feature = "executor-stats")]
+            self.stats.polling.store(false, Ordering::Relaxed);
+
+            match poll {
                 Poll::Ready(_) => {
                     self.running.store(false, Ordering::Release);
                 }
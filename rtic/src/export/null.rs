@@ -0,0 +1,109 @@
+//! Host backend without an interrupt controller, for building applications in doctests and CI
+//!
+//! Nothing preempts: no hardware calls the handlers of the hardware tasks or the dispatchers, a
+//! pend only records the dispatcher as pending. Locks are critical sections of the
+//! `critical-section` implementation of the host.
+
+use portable_atomic::{AtomicU32, AtomicU8, Ordering};
+
+/// The highest logical priority, priorities only order the pends of the host
+pub const MAX_PRIORITY: u8 = u8::MAX;
+
+#[cfg(all(feature = "null", not(feature = "null-backend")))]
+compile_error!("Building for the null backend, but 'null-backend' not selected");
+
+/// The priority of the running handler
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// The dispatchers pended, bit `n` for dispatcher `n`
+static PENDING: AtomicU32 = AtomicU32::new(0);
+
+/// Nothing interrupts on the host, there is nothing to disable
+pub mod interrupt {
+    /// Does nothing
+    #[inline(always)]
+    pub fn disable() {}
+
+    /// Does nothing
+    ///
+    /// # Safety
+    ///
+    /// Safe on this backend, `unsafe` as on the others.
+    #[inline(always)]
+    pub unsafe fn enable() {}
+}
+
+/// The interrupt masking of the modes, which does nothing on the host
+pub struct NVIC;
+
+impl NVIC {
+    /// Does nothing
+    #[inline(always)]
+    pub fn mask<I>(_interrupt: I) {}
+
+    /// Does nothing
+    ///
+    /// # Safety
+    ///
+    /// Safe on this backend, `unsafe` as on Cortex-M.
+    #[inline(always)]
+    pub unsafe fn unmask<I>(_interrupt: I) {}
+}
+
+/// Wakes `main` from [`idle_wait`], which does not sleep on the host
+#[inline(always)]
+pub fn wake_idle() {}
+
+/// Spins in the zero priority dispatcher of `main` while `is_idle` reports no task ready
+#[inline(always)]
+pub fn idle_wait(is_idle: impl FnOnce() -> bool) {
+    if is_idle() {
+        core::hint::spin_loop();
+    }
+}
+
+/// Spins forever in place of sleeping on exit
+pub fn sleep_on_exit() -> ! {
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+/// Runs the handler of a task at `priority`, if the application calls it
+#[inline(always)]
+pub fn run<F>(priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    let previous = CURRENT.swap(priority, Ordering::Relaxed);
+    f();
+    CURRENT.store(previous, Ordering::Relaxed);
+}
+
+/// The priority of the running handler, without the ceiling of the held locks
+#[inline]
+pub fn current_priority() -> u8 {
+    CURRENT.load(Ordering::Relaxed)
+}
+
+/// Lock implementation using a critical section
+///
+/// # Safety
+///
+/// The critical section of the host excludes every other access, whatever the ceiling.
+#[inline(always)]
+pub unsafe fn lock<T, R>(ptr: *mut T, _ceiling: u8, f: impl FnOnce(&mut T) -> R) -> R {
+    critical_section::with(|_| f(&mut *ptr))
+}
+
+/// Records dispatcher `n` as pending, nothing runs it
+#[inline(always)]
+pub fn pend(n: u8) {
+    PENDING.fetch_or(1 << n, Ordering::Relaxed);
+}
+
+/// The null backend has no software interrupts to request
+#[inline]
+pub fn swi_request(_n: u16) -> bool {
+    false
+}
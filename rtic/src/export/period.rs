@@ -0,0 +1,80 @@
+use core::{
+    cell::RefCell,
+    future::poll_fn,
+    task::{Poll, Waker},
+};
+
+use critical_section::Mutex;
+
+/// The period of a software task with `period = ..`, once changed at runtime, and the waker of
+/// the task waiting for its next release.
+pub struct Period<D> {
+    inner: Mutex<RefCell<Inner<D>>>,
+}
+
+struct Inner<D> {
+    period: Option<D>,
+    changes: u32,
+    waker: Option<Waker>,
+}
+
+impl<D: Copy> Default for Period<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D: Copy> Period<D> {
+    /// No period set, the task runs at its initial period.
+    pub const fn new() -> Self {
+        Self {
+            inner: Mutex::new(RefCell::new(Inner {
+                period: None,
+                changes: 0,
+                waker: None,
+            })),
+        }
+    }
+
+    /// The period last set, if any.
+    #[inline]
+    pub fn get(&self) -> Option<D> {
+        critical_section::with(|cs| self.inner.borrow_ref(cs).period)
+    }
+
+    /// Sets the period and wakes the task, which computes its next release again.
+    pub fn set(&self, period: D) {
+        let waker = critical_section::with(|cs| {
+            let mut inner = self.inner.borrow_ref_mut(cs);
+            inner.period = Some(period);
+            inner.changes = inner.changes.wrapping_add(1);
+            inner.waker.take()
+        });
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// The number of changes so far, to wait for the next one with [`Period::changed`].
+    #[inline]
+    pub fn changes(&self) -> u32 {
+        critical_section::with(|cs| self.inner.borrow_ref(cs).changes)
+    }
+
+    /// Completes once the period has changed since `changes` were counted.
+    pub async fn changed(&self, changes: u32) {
+        poll_fn(|cx| {
+            critical_section::with(|cs| {
+                let mut inner = self.inner.borrow_ref_mut(cs);
+                if inner.changes != changes {
+                    Poll::Ready(())
+                } else {
+                    inner.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            })
+        })
+        .await
+    }
+}
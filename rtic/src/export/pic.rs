@@ -0,0 +1,60 @@
+//! Position independent images on Cortex-M, the `position-independent` feature
+//!
+//! An image built with `-C relocation-model=ropi` addresses its code and read-only data relative
+//! to the program counter, so it runs from whichever flash slot it was written to. What is read
+//! from absolute addresses still refers to the slot the image was linked for: the startup code of
+//! `cortex-m-rt` copies `.data` from there, and the vector table holds the linked addresses of
+//! the handlers. The `app` macro calls [`fixup`] first thing in `main`, then installs a copy of
+//! the vector table moved by the load offset, see `RamVectors::install_moved`.
+//!
+//! RAM does not move. Statics of the application holding references to code or read-only data,
+//! e.g. a `&'static str`, keep the linked addresses, the generated code has none.
+
+use core::ptr::{self, addr_of};
+
+extern "C" {
+    // Provided by `cortex-m-rt`
+    static __sdata: u32;
+    static __edata: u32;
+    static __sidata: u32;
+    static __vector_table: u32;
+
+    /// `__vector_table` again, declared as code so the compiler addresses it relative to the
+    /// program counter, i.e. where it is in the running image
+    #[link_name = "__vector_table"]
+    fn __rtic_running_vector_table();
+}
+
+/// How many bytes the running image is past the address it was linked at, `0` when it runs
+/// from there or was not built position independent
+#[inline]
+pub fn load_offset() -> usize {
+    let linked = addr_of!(__vector_table) as usize;
+    let running = __rtic_running_vector_table as *const () as usize & !1;
+
+    running.wrapping_sub(linked)
+}
+
+/// Copies `.data` from the running image and fills the waker vtable with the functions of the
+/// running image, returns the load offset
+///
+/// # Safety
+///
+/// Called once, at the start of `main` with the interrupts disabled, before any static in
+/// `.data` is used. On the RP2040 only by core 0, core 1 calls [`load_offset`].
+pub unsafe fn fixup() -> usize {
+    let offset = load_offset();
+
+    if offset != 0 {
+        let start = addr_of!(__sdata) as *mut u32;
+        let words = (addr_of!(__edata) as usize - start as usize) / 4;
+        let from = (addr_of!(__sidata) as usize).wrapping_add(offset) as *const u32;
+
+        // As the startup code does, without anything else in `.data` in use yet
+        ptr::copy_nonoverlapping(from, start, words);
+    }
+
+    super::executor::relocate_waker_vtable();
+
+    offset
+}
@@ -0,0 +1,155 @@
+//! Polling backend without interrupts, for bootloaders and other contexts that can not enable
+//! them
+//!
+//! All tasks run cooperatively from a single superloop in `main`. A pend only records the
+//! dispatcher as pending, the superloop runs the pending dispatchers one at a time, the highest
+//! priority first, so priorities order the runs instead of preempting. The dispatchers are the
+//! entries of the `__RTIC_POLLING` table generated by the `#[app]` macro, numbered from the
+//! highest priority down by the `dispatchers` module of the application.
+//!
+//! As nothing ever runs in the middle of another task, locks are plain accesses to the resource.
+
+use portable_atomic::{AtomicU32, AtomicU8, Ordering};
+
+/// The highest logical priority, ordering the runs of the superloop
+pub const MAX_PRIORITY: u8 = u8::MAX;
+
+#[cfg(all(feature = "polling", not(feature = "polling-backend")))]
+compile_error!("Building for the polling backend, but 'polling-backend' not selected");
+
+/// The dispatchers of the application, provided by the `#[app]` macro as `__RTIC_POLLING`
+pub struct PollingConfig {
+    /// The handler of dispatcher `n`, at index `n`, highest priority first
+    pub dispatchers: &'static [fn()],
+}
+
+#[inline(always)]
+fn config() -> &'static PollingConfig {
+    extern "Rust" {
+        static __RTIC_POLLING: PollingConfig;
+    }
+    // SAFETY: the static is generated by the `#[app]` macro
+    unsafe { &__RTIC_POLLING }
+}
+
+/// The priority of the running dispatcher, `0` in the superloop
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// The dispatchers pended, bit `n` for dispatcher `n`
+static PENDING: AtomicU32 = AtomicU32::new(0);
+
+/// The highest number of dispatchers an application can have
+pub const MAX_DISPATCHERS: usize = u32::BITS as usize;
+
+/// Runs the pending dispatchers, the highest priority first, until none is left pending
+///
+/// A dispatcher pended by another one runs after it returns, as do the tasks it spawns.
+fn superloop() {
+    loop {
+        let bits = PENDING.load(Ordering::Relaxed);
+        if bits == 0 {
+            return;
+        }
+
+        // The dispatchers are numbered by decreasing priority
+        let n = bits.trailing_zeros();
+        PENDING.fetch_and(!(1 << n), Ordering::Relaxed);
+        config().dispatchers[n as usize]();
+    }
+}
+
+/// Nothing interrupts, "enabling" starts running the tasks
+pub mod interrupt {
+    /// Does nothing
+    #[inline(always)]
+    pub fn disable() {}
+
+    /// Runs the tasks spawned by `init`, before `main` polls the priority 0 tasks
+    ///
+    /// # Safety
+    ///
+    /// Safe on this backend, `unsafe` as on the others.
+    #[inline(always)]
+    pub unsafe fn enable() {
+        super::superloop();
+    }
+}
+
+/// The interrupt masking of the modes, which does nothing without interrupts
+pub struct NVIC;
+
+impl NVIC {
+    /// Does nothing
+    #[inline(always)]
+    pub fn mask<I>(_interrupt: I) {}
+
+    /// Does nothing
+    ///
+    /// # Safety
+    ///
+    /// Safe on this backend, `unsafe` as on Cortex-M.
+    #[inline(always)]
+    pub unsafe fn unmask<I>(_interrupt: I) {}
+}
+
+/// Wakes `main` from [`idle_wait`], which does not sleep
+#[inline(always)]
+pub fn wake_idle() {}
+
+/// Runs the superloop from the zero priority dispatcher of `main`, spinning while `is_idle`
+/// reports no task ready
+#[inline(always)]
+pub fn idle_wait(is_idle: impl FnOnce() -> bool) {
+    superloop();
+    if is_idle() {
+        core::hint::spin_loop();
+    }
+}
+
+/// Runs the superloop forever in place of sleeping on exit
+pub fn sleep_on_exit() -> ! {
+    loop {
+        superloop();
+        core::hint::spin_loop();
+    }
+}
+
+/// Runs the handler of a dispatcher at `priority`
+#[inline(always)]
+pub fn run<F>(priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    let previous = CURRENT.swap(priority, Ordering::Relaxed);
+    f();
+    CURRENT.store(previous, Ordering::Relaxed);
+}
+
+/// The priority of the running dispatcher
+#[inline]
+pub fn current_priority() -> u8 {
+    CURRENT.load(Ordering::Relaxed)
+}
+
+/// Lock implementation, a plain access to the resource
+///
+/// # Safety
+///
+/// The tasks run one at a time from the superloop and none runs in the middle of another, so
+/// the access is exclusive without masking anything.
+#[inline(always)]
+pub unsafe fn lock<T, R>(ptr: *mut T, f: impl FnOnce(&mut T) -> R) -> R {
+    f(&mut *ptr)
+}
+
+/// Records dispatcher `n` as pending, the superloop runs it
+#[inline(always)]
+pub fn pend(n: u8) {
+    PENDING.fetch_or(1 << n, Ordering::Relaxed);
+}
+
+/// The polling backend has no software interrupts to request
+#[inline]
+pub fn swi_request(_n: u16) -> bool {
+    false
+}
@@ -0,0 +1,99 @@
+use super::atomic::{AtomicU32, Ordering};
+
+/// Ready software tasks of a dispatcher, one bit per task in `W` words.
+///
+/// Task `i` is the bit `31 - i % 32` of word `i / 32`, so the leading zeros of a word give the
+/// first ready task in it.
+pub struct ReadyBitmap<const W: usize> {
+    words: [AtomicU32; W],
+}
+
+impl<const W: usize> Default for ReadyBitmap<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: usize> ReadyBitmap<W> {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: AtomicU32 = AtomicU32::new(0);
+
+    pub const fn new() -> Self {
+        Self {
+            words: [Self::EMPTY; W],
+        }
+    }
+
+    /// Marks task `index` as ready.
+    #[inline(always)]
+    pub fn set(&self, index: usize) {
+        self.words[index / 32].fetch_or(1 << (31 - index % 32), Ordering::Release);
+    }
+
+    /// Checks if any task is marked as ready.
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.words
+            .iter()
+            .all(|word| word.load(Ordering::Acquire) == 0)
+    }
+
+    /// Takes the tasks marked as ready, tasks marked from then on are left for the next pass.
+    #[inline(always)]
+    pub fn take(&self) -> Ready<W> {
+        let mut words = [0; W];
+        for (taken, word) in words.iter_mut().zip(&self.words) {
+            *taken = word.swap(0, Ordering::Acquire);
+        }
+
+        Ready { words, word: 0 }
+    }
+}
+
+impl<const W: usize> crate::dispatcher::Executor for ReadyBitmap<W> {
+    type Pass = Ready<W>;
+
+    #[inline(always)]
+    fn wake(&self, index: usize) {
+        self.set(index);
+    }
+
+    #[inline(always)]
+    fn poll_ready(&self) -> Ready<W> {
+        self.take()
+    }
+
+    #[inline(always)]
+    fn is_empty(&self) -> bool {
+        ReadyBitmap::is_empty(self)
+    }
+}
+
+/// Snapshot of the ready tasks, iterated in index order.
+pub struct Ready<const W: usize> {
+    words: [u32; W],
+    /// All words before this one are empty
+    word: usize,
+}
+
+impl<const W: usize> Iterator for Ready<W> {
+    type Item = usize;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<usize> {
+        while self.word < W {
+            let bits = self.words[self.word];
+
+            if bits != 0 {
+                let bit = bits.leading_zeros() as usize;
+                self.words[self.word] = bits & !(1 << (31 - bit));
+
+                return Some(self.word * 32 + bit);
+            }
+
+            self.word += 1;
+        }
+
+        None
+    }
+}
@@ -0,0 +1,217 @@
+//! WCH CH32V2/V3 series, QingKe V4 cores with the PFIC interrupt controller
+//!
+//! The PFIC nests interrupts in hardware: an interrupt with a higher preemption priority than
+//! the running one preempts it, the hardware prologue/epilogue (HPE) stacking the caller saved
+//! registers, so handlers need no software nesting. Lower priority values preempt higher ones,
+//! and only the top `preemption_bits` of a priority byte preempt, the remaining ones only order
+//! pending interrupts. RTIC maps its priorities to the preemption bits and leaves the others zero.
+//!
+//! Locks raise the threshold in `ITHRESDR`, which masks the interrupts with a priority value at
+//! or above it, as `BASEPRI` does on Cortex-M. The dispatchers are device interrupts pended
+//! through the `IPSR` registers, the PFIC clears the pending bit when the interrupt is taken.
+
+use core::arch::asm;
+pub use riscv::interrupt;
+use riscv::register::mstatus;
+
+#[cfg(all(feature = "riscv-ch32", not(feature = "riscv-ch32-backend")))]
+compile_error!("Building for the CH32 PFIC, but 'riscv-ch32-backend' not selected");
+
+/// Configuration of the controller, provided by the `#[app]` macro as `__RTIC_PFIC`
+pub struct PficConfig {
+    /// Bits of the priority byte that preempt, `1..=3`
+    pub preemption_bits: u8,
+}
+
+#[inline(always)]
+fn config() -> &'static PficConfig {
+    extern "Rust" {
+        static __RTIC_PFIC: PficConfig;
+    }
+    // SAFETY: the static is generated by the `#[app]` macro
+    unsafe { &__RTIC_PFIC }
+}
+
+/// The PFIC is at the same address on all QingKe cores
+const PFIC: usize = 0xE000_E000;
+
+// Register offsets, the word arrays cover 32 interrupts per word
+const ISR: usize = 0x000;
+const ITHRESDR: usize = 0x040;
+const IENR: usize = 0x100;
+const IPSR: usize = 0x200;
+const IACTR: usize = 0x300;
+const IPRIOR: usize = 0x400;
+
+/// The number of interrupts the PFIC supports
+const IRQS: u16 = 256;
+
+/// `INTSYSCR`: `INESTEN` enables nesting, `PMTCFG` selects the preemption bits
+const INTSYSCR_INESTEN: usize = 1 << 1;
+const INTSYSCR_PMTCFG_SHIFT: usize = 2;
+const INTSYSCR_PMTCFG_MASK: usize = 0b11 << INTSYSCR_PMTCFG_SHIFT;
+
+/// The highest logical priority with the configured preemption bits
+#[inline(always)]
+pub(crate) fn max_priority() -> u8 {
+    1 << config().preemption_bits
+}
+
+/// Encodes logical priority `level` in the preemption bits, `0` for the highest one
+#[inline(always)]
+fn encode(level: u8) -> u8 {
+    let bits = config().preemption_bits;
+    (max_priority() - level) << (8 - bits)
+}
+
+#[inline(always)]
+fn decode(priority: u8) -> u8 {
+    let bits = config().preemption_bits;
+    max_priority() - (priority >> (8 - bits))
+}
+
+#[inline(always)]
+fn word_reg(offset: usize, irq: u16) -> *mut u32 {
+    (PFIC + offset + 4 * (irq as usize / 32)) as *mut u32
+}
+
+#[inline(always)]
+fn priority_reg(irq: u16) -> *mut u8 {
+    (PFIC + IPRIOR + irq as usize) as *mut u8
+}
+
+/// The raw threshold, `0` when no interrupt is masked
+#[inline(always)]
+fn threshold() -> u8 {
+    // SAFETY: reading the threshold has no side effects
+    unsafe { ((PFIC + ITHRESDR) as *const u32).read_volatile() as u8 }
+}
+
+/// Writes the threshold and reads it back, so it is in effect before the next access
+#[inline(always)]
+fn set_threshold(raw: u8) {
+    unsafe {
+        ((PFIC + ITHRESDR) as *mut u32).write_volatile(raw as u32);
+        ((PFIC + ITHRESDR) as *const u32).read_volatile();
+    }
+}
+
+/// Enables nesting with the configured preemption bits and clears the threshold
+///
+/// `HWSTKEN` is left as the runtime set it, the stacking of the HPE does not change how RTIC
+/// nests.
+pub fn configure() {
+    let pmtcfg = (config().preemption_bits as usize) << INTSYSCR_PMTCFG_SHIFT;
+    unsafe {
+        let intsyscr: usize;
+        asm!("csrr {0}, 0x804", out(reg) intsyscr);
+        let intsyscr = intsyscr & !INTSYSCR_PMTCFG_MASK | pmtcfg | INTSYSCR_INESTEN;
+        asm!("csrw 0x804, {0}", in(reg) intsyscr);
+    }
+    set_threshold(0);
+}
+
+/// Sets the priority of `irq` and enables it
+pub fn enable(irq: u16, level: u8) {
+    unsafe {
+        priority_reg(irq).write_volatile(encode(level));
+        word_reg(IENR, irq).write_volatile(1 << (irq % 32));
+    }
+}
+
+/// Whether `irq` is still enabled at logical priority `level`, as set by [`enable`]
+pub fn is_enabled(irq: u16, level: u8) -> bool {
+    unsafe {
+        word_reg(ISR, irq).read_volatile() & 1 << (irq % 32) != 0
+            && decode(priority_reg(irq).read_volatile()) == level
+    }
+}
+
+#[inline(always)]
+pub fn run<F>(priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    if priority == 1 {
+        // If the priority of this interrupt is `1` then the threshold can only be `0`
+        f();
+        set_threshold(0);
+    } else {
+        let initial = threshold();
+        f();
+        set_threshold(initial);
+    }
+}
+
+/// The logical priority of the running code, the ceiling of the held locks included
+///
+/// The highest priority of the interrupts active in `IACTR`, raised by the threshold when it is
+/// masking more. Outside of interrupts with `mstatus.MIE` cleared, e.g. in `init`, this is the
+/// highest priority.
+#[inline]
+pub fn current_priority() -> u8 {
+    let mut active = 0;
+    for word in 0..(IRQS / 32) {
+        // SAFETY: reading the active interrupts has no side effects
+        let mut bits = unsafe { word_reg(IACTR, word * 32).read_volatile() };
+        while bits != 0 {
+            let irq = word * 32 + bits.trailing_zeros() as u16;
+            active = active.max(decode(unsafe { priority_reg(irq).read_volatile() }));
+            bits &= bits - 1;
+        }
+    }
+
+    if active == 0 && !mstatus::read().mie() {
+        return max_priority();
+    }
+
+    match threshold() {
+        0 => active,
+        raw => active.max(decode(raw)),
+    }
+}
+
+/// Lock implementation using the interrupt threshold
+///
+/// # Safety
+///
+/// The system ceiling is raised from current to ceiling by raising the threshold to the
+/// priority of the ceiling, if it is masking less. A threshold of `0` masks nothing, so the
+/// highest ceiling is a global critical section instead.
+#[inline(always)]
+pub unsafe fn lock<T, R>(ptr: *mut T, ceiling: u8, f: impl FnOnce(&mut T) -> R) -> R {
+    if ceiling == 0 {
+        f(&mut *ptr)
+    } else if ceiling >= max_priority() {
+        critical_section::with(|_| f(&mut *ptr))
+    } else {
+        let current = threshold();
+        let raw = encode(ceiling);
+        if current == 0 || current > raw {
+            set_threshold(raw);
+        }
+        let r = f(&mut *ptr);
+        set_threshold(current);
+        r
+    }
+}
+
+/// Sets `irq` as pending through `IPSR`
+#[inline(always)]
+pub fn pend(irq: u16) {
+    super::swi_fence();
+    unsafe { word_reg(IPSR, irq).write_volatile(1 << (irq % 32)) };
+}
+
+/// Pends interrupt `n` through `IPSR`
+///
+/// The core interrupts are numbered with the device ones, e.g. `14` is the software interrupt
+/// `SW`. Returns `false` if `n` is not an interrupt of the PFIC.
+#[inline]
+pub fn swi_request(n: u16) -> bool {
+    if n >= IRQS {
+        return false;
+    }
+    pend(n);
+    true
+}
@@ -1,4 +1,5 @@
 /// GENERIC RE-EXPORTS: needed for all RTIC backends
+use portable_atomic::{AtomicBool, Ordering};
 
 /// Read the stack pointer.
 #[inline(always)]
@@ -7,3 +8,51 @@ pub fn read_sp() -> u32 {
     unsafe { core::arch::asm!("mv {}, sp", out(reg) r, options(nomem, nostack, preserves_flags)) };
     r
 }
+
+/// A wake of `main` not yet seen by [`idle_wait`]
+static IDLE_WAKE: AtomicBool = AtomicBool::new(false);
+
+/// Wakes `main` from [`idle_wait`], called when a task of priority 0 is spawned or woken
+///
+/// Backend contract: a wake is latched until the next [`idle_wait`], which then returns without
+/// sleeping, also when the wake lands between its check for ready tasks and the sleep. RISC-V
+/// has no event register, a flag takes its place.
+#[inline(always)]
+pub fn wake_idle() {
+    IDLE_WAKE.store(true, Ordering::SeqCst);
+}
+
+/// Sleeps in the zero priority dispatcher of `main` if no wake is latched and `is_idle` reports
+/// no task ready
+///
+/// The check and the `wfi` run with `mstatus.MIE` cleared, so no handler can wake a task in
+/// between. `wfi` still resumes on a pending interrupt, which runs once `MIE` is set again.
+#[inline(always)]
+pub fn idle_wait(is_idle: impl FnOnce() -> bool) {
+    riscv::interrupt::disable();
+
+    // No handler runs between the load and the store, no read-modify-write is needed
+    let woken = IDLE_WAKE.load(Ordering::SeqCst);
+    IDLE_WAKE.store(false, Ordering::SeqCst);
+
+    if !woken && is_idle() {
+        riscv::asm::wfi();
+    }
+
+    // SAFETY: `main` runs with interrupts enabled once `init` has returned
+    unsafe { riscv::interrupt::enable() };
+}
+
+/// Orders memory accesses around a software interrupt request.
+///
+/// Backend contract: `pend` issues this before raising the interrupt, so everything written
+/// before the request, e.g. the message of a channel or the ready flag of a task, is visible to
+/// the handler, also when it runs on another hart. `unpend` issues it after clearing the request,
+/// so the clear reaches the interrupt controller before the handler reads the shared state.
+///
+/// The RISC-V memory model does not order device writes after memory writes without a `fence`.
+/// `fence.i` is not needed: it only synchronizes instruction fetches with stores to code.
+#[inline(always)]
+pub fn swi_fence() {
+    riscv::asm::fence();
+}
@@ -0,0 +1,289 @@
+use bare_metal_0_2::Nr;
+pub use riscv::interrupt;
+use riscv::register::{mepc, mstatus};
+
+#[cfg(all(feature = "riscv-eclic", not(feature = "riscv-eclic-backend")))]
+compile_error!("Building for the ECLIC, but 'riscv-eclic-backend' not selected");
+
+/// Vendor flavour of the ECLIC-like interrupt controller
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EclicVariant {
+    /// Nuclei N-class ECLIC: byte wide `mth`, `msubm` saved when nesting
+    Nuclei,
+    /// T-Head E9xx CLIC: threshold in the top byte of the word wide `mintthresh`
+    THead,
+}
+
+/// Configuration of the controller, provided by the `#[app]` macro as `__RTIC_ECLIC`
+pub struct EclicConfig {
+    pub variant: EclicVariant,
+    pub base: usize,
+    /// Bits of `clicintctl` encoding the level, `1..=8`
+    pub level_bits: u8,
+}
+
+#[inline(always)]
+fn config() -> &'static EclicConfig {
+    extern "Rust" {
+        static __RTIC_ECLIC: EclicConfig;
+    }
+    // SAFETY: the static is generated by the `#[app]` macro
+    unsafe { &__RTIC_ECLIC }
+}
+
+// Register offsets shared by both variants
+const CLICCFG: usize = 0x0000;
+const CLICINFO: usize = 0x0004;
+const CLICINT: usize = 0x1000;
+const INTIP: usize = 0;
+const INTIE: usize = 1;
+const INTATTR: usize = 2;
+const INTCTL: usize = 3;
+
+/// Nuclei `mth`
+const NUCLEI_MTH: usize = 0x000B;
+/// T-Head `mintthresh`, the threshold is in bits 31:24
+const THEAD_MINTTHRESH: usize = 0x0008;
+
+/// `clicintattr` selective hardware vectoring through the `mtvt` table
+const ATTR_SHV: u8 = 0b1;
+/// `clicintattr` trigger: positive edge
+const ATTR_TRIG_EDGE: u8 = 0b01 << 1;
+const ATTR_TRIG_MASK: u8 = 0b11 << 1;
+
+/// The highest logical priority with the configured level bits
+#[inline(always)]
+pub(crate) fn max_priority() -> u8 {
+    ((1u16 << config().level_bits) - 1) as u8
+}
+
+/// Encodes logical priority `level` in the level bits of `clicintctl`, the unused priority bits
+/// are set as the specification recommends for unimplemented bits
+#[inline(always)]
+fn encode(level: u8) -> u8 {
+    let level_bits = config().level_bits as u32;
+    if level_bits == 8 {
+        level
+    } else {
+        (level << (8 - level_bits)) | (0xff >> level_bits)
+    }
+}
+
+#[inline(always)]
+fn decode(ctl: u8) -> u8 {
+    ctl.checked_shr(8 - config().level_bits as u32).unwrap_or(0)
+}
+
+#[inline(always)]
+fn int_reg(n: u16, reg: usize) -> *mut u8 {
+    (config().base + CLICINT + 4 * n as usize + reg) as *mut u8
+}
+
+/// The raw threshold, interrupts with an encoded level at or below it are masked
+#[inline(always)]
+fn threshold() -> u8 {
+    let base = config().base;
+    unsafe {
+        match config().variant {
+            EclicVariant::Nuclei => ((base + NUCLEI_MTH) as *const u8).read_volatile(),
+            EclicVariant::THead => {
+                (((base + THEAD_MINTTHRESH) as *const u32).read_volatile() >> 24) as u8
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn set_threshold(raw: u8) {
+    let base = config().base;
+    unsafe {
+        match config().variant {
+            EclicVariant::Nuclei => ((base + NUCLEI_MTH) as *mut u8).write_volatile(raw),
+            EclicVariant::THead => {
+                ((base + THEAD_MINTTHRESH) as *mut u32).write_volatile((raw as u32) << 24)
+            }
+        }
+    }
+}
+
+/// The raw threshold masking logical priorities up to `ceiling`
+#[inline(always)]
+fn ceiling_threshold(ceiling: u8) -> u8 {
+    if ceiling == 0 {
+        0
+    } else {
+        encode(ceiling)
+    }
+}
+
+/// Sets the level/priority split of `clicintctl` and clears the threshold
+pub fn configure() {
+    let level_bits = config().level_bits;
+    unsafe { ((config().base + CLICCFG) as *mut u8).write_volatile(level_bits << 1) };
+    set_threshold(0);
+}
+
+/// Enables `int` at logical priority `level`, switched to edge triggering if `edge` and to
+/// hardware vectoring if `vectored`
+pub fn enable<I: Nr>(int: I, level: u8, edge: bool, vectored: bool) {
+    let n = int.nr() as u16;
+    unsafe {
+        if edge || vectored {
+            let mut attr = int_reg(n, INTATTR).read_volatile();
+            if edge {
+                attr = attr & !ATTR_TRIG_MASK | ATTR_TRIG_EDGE;
+            }
+            if vectored {
+                attr |= ATTR_SHV;
+            }
+            int_reg(n, INTATTR).write_volatile(attr);
+        }
+        int_reg(n, INTCTL).write_volatile(encode(level));
+        int_reg(n, INTIE).write_volatile(1);
+    }
+}
+
+/// Points `mtvt` at the vector table of the interrupts enabled with hardware vectoring
+///
+/// # Safety
+///
+/// `table` must hold an entry saving the caller saved registers and returning with `mret` for
+/// each of them, and be aligned as the controller requires for its length.
+#[inline]
+pub unsafe fn set_vector_table(table: *const ()) {
+    core::arch::asm!("csrw 0x307, {}", in(reg) table);
+}
+
+/// Whether `int` is still enabled at logical priority `level`, as set by [`enable`]
+pub fn is_enabled<I: Nr>(int: I, level: u8) -> bool {
+    let n = int.nr() as u16;
+    unsafe {
+        int_reg(n, INTIE).read_volatile() & 1 != 0
+            && decode(int_reg(n, INTCTL).read_volatile()) == level
+    }
+}
+
+#[inline(always)]
+pub fn run<F>(priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    if priority == 1 {
+        // If the priority of this interrupt is `1` then the threshold can only be `0`
+        f();
+        set_threshold(0);
+    } else {
+        let initial = threshold();
+        f();
+        set_threshold(initial);
+    }
+}
+
+/// The logical priority of the running code, the ceiling of the held locks included
+///
+/// The level of the active interrupt from `mintstatus`, raised by the threshold when it is
+/// masking more. With interrupts disabled, e.g. in `init`, this is the highest priority.
+#[inline]
+pub fn current_priority() -> u8 {
+    if !mstatus::read().mie() {
+        return max_priority();
+    }
+
+    let mintstatus: usize;
+    // SAFETY: reading `mintstatus` has no side effects
+    unsafe { core::arch::asm!("csrr {}, 0x346", out(reg) mintstatus) };
+    let active = decode((mintstatus >> 24) as u8);
+
+    match threshold() {
+        0 => active,
+        raw => active.max(decode(raw)),
+    }
+}
+
+/// Lock implementation using the interrupt threshold
+///
+/// # Safety
+///
+/// The system ceiling is raised from current to ceiling by raising the threshold to the level
+/// of the ceiling, if it is lower. The controller masks interrupts with a level at or below the
+/// threshold, so the highest ceiling needs no critical section.
+#[inline(always)]
+pub unsafe fn lock<T, R>(ptr: *mut T, ceiling: u8, f: impl FnOnce(&mut T) -> R) -> R {
+    let current = threshold();
+    set_threshold(current.max(ceiling_threshold(ceiling)));
+    let r = f(&mut *ptr);
+    set_threshold(current);
+    r
+}
+
+#[inline(always)]
+pub fn pend<I: Nr>(int: I) {
+    super::swi_fence();
+    unsafe { int_reg(int.nr() as u16, INTIP).write_volatile(1) };
+}
+
+#[inline(always)]
+pub fn unpend<I: Nr>(int: I) {
+    unsafe { int_reg(int.nr() as u16, INTIP).write_volatile(0) };
+    super::swi_fence();
+}
+
+/// Pends interrupt `n` through its `clicintip` register
+///
+/// Only edge triggered interrupts latch the request, RTIC configures its dispatchers as such.
+/// Returns `false` if `n` is not an interrupt of the controller.
+#[inline]
+pub fn swi_request(n: u16) -> bool {
+    let info = unsafe { ((config().base + CLICINFO) as *const u32).read_volatile() };
+    if n as u32 >= info & 0x1fff {
+        return false;
+    }
+
+    super::swi_fence();
+    unsafe { int_reg(n, INTIP).write_volatile(1) };
+    true
+}
+
+/// State of the interrupted code that a nested interrupt overwrites
+pub struct Nested {
+    mepc: usize,
+    mcause: usize,
+    msubm: usize,
+}
+
+/// Saves the interrupted state and re-enables interrupts, so higher levels can preempt the
+/// handler
+///
+/// `mcause` holds the previous interrupt level that `mret` restores, on Nuclei cores `msubm`
+/// holds the previous trap type.
+#[inline(always)]
+pub fn enter_nested() -> Nested {
+    let mcause: usize;
+    let mut msubm = 0;
+    unsafe {
+        core::arch::asm!("csrr {}, mcause", out(reg) mcause);
+        if config().variant == EclicVariant::Nuclei {
+            core::arch::asm!("csrr {}, 0x7c4", out(reg) msubm);
+        }
+    }
+    let nested = Nested {
+        mepc: mepc::read(),
+        mcause,
+        msubm,
+    };
+    unsafe { mstatus::set_mie() };
+    nested
+}
+
+/// Disables interrupts and restores the state saved by [`enter_nested`] for `mret`
+#[inline(always)]
+pub fn exit_nested(nested: Nested) {
+    unsafe {
+        mstatus::clear_mie();
+        mepc::write(nested.mepc);
+        core::arch::asm!("csrw mcause, {}", in(reg) nested.mcause);
+        if config().variant == EclicVariant::Nuclei {
+            core::arch::asm!("csrw 0x7c4, {}", in(reg) nested.msubm);
+        }
+    }
+}
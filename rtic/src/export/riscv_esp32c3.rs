@@ -4,7 +4,10 @@ pub use riscv::interrupt;
 pub use riscv::register::mcause; //low level interrupt enable/disable
 
 #[cfg(all(feature = "riscv-esp32c3", not(feature = "riscv-esp32c3-backend")))]
-compile_error!("Building for the esp32c3, but 'riscv-esp32c3-backend not selected'");
+compile_error!("Building for the esp32c3, but 'riscv-esp32c3-backend' not selected");
+
+/// The highest logical priority supported by the interrupt controller
+pub const MAX_PRIORITY: u8 = 15;
 
 #[inline(always)]
 pub fn run<F>(priority: u8, f: F)
@@ -38,6 +41,22 @@ where
     }
 }
 
+/// The logical priority of the running code, the ceiling of the held locks included
+///
+/// Derived from the interrupt threshold, which is one above the priority that is running.
+/// A lock at the highest ceiling disables interrupts instead and is not included.
+#[inline]
+pub fn current_priority() -> u8 {
+    let threshold = unsafe {
+        (*INTERRUPT_CORE0::ptr())
+            .cpu_int_thresh()
+            .read()
+            .cpu_int_thresh()
+            .bits()
+    };
+    threshold.saturating_sub(1)
+}
+
 /// Lock implementation using threshold and global Critical Section (CS)
 ///
 /// # Safety
@@ -87,6 +106,7 @@ pub unsafe fn lock<T, R>(ptr: *mut T, ceiling: u8, f: impl FnOnce(&mut T) -> R)
 /// Sets the given software interrupt as pending
 #[inline(always)]
 pub fn pend(int: Interrupt) {
+    super::swi_fence();
     unsafe {
         let peripherals = Peripherals::steal();
         match int {
@@ -135,20 +155,34 @@ pub fn unpend(int: Interrupt) {
             _ => panic!("Unsupported software interrupt"),
         }
     }
+    super::swi_fence();
+}
+
+/// Requests the `FROM_CPU_INTR{n}` software interrupt
+///
+/// Returns `false` if `n` is not in `0..4`.
+#[inline]
+pub fn swi_request(n: u16) -> bool {
+    let int = match n {
+        0 => Interrupt::FROM_CPU_INTR0,
+        1 => Interrupt::FROM_CPU_INTR1,
+        2 => Interrupt::FROM_CPU_INTR2,
+        3 => Interrupt::FROM_CPU_INTR3,
+        _ => return false,
+    };
+    pend(int);
+    true
 }
 
 pub fn enable(int: Interrupt, prio: u8, cpu_int_id: u8) {
-    const INTERRUPT_MAP_BASE: u32 = 0x600c2000; //this isn't exposed properly in the PAC,
-                                                //should maybe figure out a workaround that
-                                                //doesnt involve raw pointers.
-                                                //Again, this is how they do it in the HAL
-                                                //but i'm really not a fan.
+    // The interrupt map registers start at the base of `INTERRUPT_CORE0`, one per peripheral
+    // interrupt
+    const INTERRUPT_MAP_BASE: *mut u32 = INTERRUPT_CORE0::ptr().cast_mut().cast();
     let interrupt_number = int as isize;
     let cpu_interrupt_number = cpu_int_id as isize;
 
     unsafe {
-        let intr_map_base = INTERRUPT_MAP_BASE as *mut u32;
-        intr_map_base
+        INTERRUPT_MAP_BASE
             .offset(interrupt_number)
             .write_volatile(cpu_interrupt_number as u32);
         //map peripheral interrupt to CPU interrupt
@@ -163,3 +197,25 @@ pub fn enable(int: Interrupt, prio: u8, cpu_int_id: u8) {
             .write_volatile(prio as u32);
     }
 }
+
+/// Whether `int` is still mapped to `cpu_int_id`, enabled and at `prio`, as set by [`enable`]
+pub fn is_enabled(int: Interrupt, prio: u8, cpu_int_id: u8) -> bool {
+    const INTERRUPT_MAP_BASE: *const u32 = INTERRUPT_CORE0::ptr().cast();
+
+    let interrupt_number = int as isize;
+    let cpu_interrupt_number = cpu_int_id as isize;
+
+    unsafe {
+        let mapped = INTERRUPT_MAP_BASE.offset(interrupt_number).read_volatile();
+        let enabled = (*INTERRUPT_CORE0::ptr()).cpu_int_enable().read().bits()
+            & (1 << cpu_interrupt_number)
+            != 0;
+        let priority = (*INTERRUPT_CORE0::ptr())
+            .cpu_int_pri_0()
+            .as_ptr()
+            .offset(cpu_interrupt_number)
+            .read_volatile();
+
+        mapped == cpu_interrupt_number as u32 && enabled && priority == prio as u32
+    }
+}
@@ -8,6 +8,9 @@ pub use riscv::register::mie; // Low level interrupt enable/disable
 #[cfg(all(feature = "riscv-esp32c6", not(feature = "riscv-esp32c6-backend")))]
 compile_error!("Building for the esp32c6, but 'riscv-esp32c6-backend' not selected");
 
+/// The highest logical priority supported by the interrupt controller
+pub const MAX_PRIORITY: u8 = 15;
+
 #[inline(always)]
 pub fn run<F>(priority: u8, f: F)
 where
@@ -39,6 +42,22 @@ where
         }
     }
 }
+/// The logical priority of the running code, the ceiling of the held locks included
+///
+/// Derived from the interrupt threshold, which is one above the priority that is running.
+/// A lock at the highest ceiling disables interrupts instead and is not included.
+#[inline]
+pub fn current_priority() -> u8 {
+    let threshold = unsafe {
+        (*INTPRI::ptr())
+            .cpu_int_thresh()
+            .read()
+            .cpu_int_thresh()
+            .bits()
+    };
+    threshold.saturating_sub(1)
+}
+
 /// Lock implementation using threshold and global Critical Section (CS)
 ///
 /// # Safety
@@ -94,6 +113,7 @@ pub unsafe fn lock<T, R>(ptr: *mut T, ceiling: u8, f: impl FnOnce(&mut T) -> R)
 
 #[inline(always)]
 pub fn pend(int: Interrupt) {
+    super::swi_fence();
     unsafe {
         let peripherals = Peripherals::steal();
         match int {
@@ -142,11 +162,38 @@ pub fn unpend(int: Interrupt) {
             _ => panic!("Unsupported software interrupt"), //should never happen, checked at compile time
         }
     }
+    super::swi_fence();
 }
 
-pub fn enable(int: Interrupt, prio: u8, cpu_int_id: u8) {
-    const INTERRUPT_MAP_BASE: *mut u32 =
-        unsafe { core::mem::transmute::<_, *mut u32>(INTERRUPT_CORE0::ptr()) };
+/// Requests the `FROM_CPU_INTR{n}` software interrupt
+///
+/// Returns `false` if `n` is not in `0..4`.
+#[inline]
+pub fn swi_request(n: u16) -> bool {
+    let int = match n {
+        0 => Interrupt::FROM_CPU_INTR0,
+        1 => Interrupt::FROM_CPU_INTR1,
+        2 => Interrupt::FROM_CPU_INTR2,
+        3 => Interrupt::FROM_CPU_INTR3,
+        _ => return false,
+    };
+    pend(int);
+    true
+}
+
+/// The interrupt type of a CPU interrupt, the `trigger` of the hardware task bound to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// Taken for as long as the source is asserted
+    Level = 0,
+    /// Taken once per edge of the source, the default
+    Edge = 1,
+}
+
+/// Routes `int` to CPU interrupt `cpu_int_id`, allocated by the `app` macro, and enables it at
+/// `prio` with the interrupt type `trigger`
+pub fn enable(int: Interrupt, prio: u8, cpu_int_id: u8, trigger: Trigger) {
+    const INTERRUPT_MAP_BASE: *mut u32 = INTERRUPT_CORE0::ptr().cast_mut().cast();
 
     let interrupt_number = int as isize;
     let cpu_interrupt_number = cpu_int_id as isize;
@@ -166,10 +213,37 @@ pub fn enable(int: Interrupt, prio: u8, cpu_int_id: u8) {
             .write(|w| w.bits(prio as u32));
 
         (*INTPRI::ptr()).cpu_int_type().modify(|r, w| {
-            let interrupt_type = 1;
+            let interrupt_type = trigger as u32;
             w.bits(
                 r.bits() & !(1 << cpu_interrupt_number) | (interrupt_type << cpu_interrupt_number),
             )
         });
     }
 }
+
+/// Whether `int` is still mapped to `cpu_int_id`, enabled, at `prio` and of the interrupt type
+/// `trigger`, as set by [`enable`]
+pub fn is_enabled(int: Interrupt, prio: u8, cpu_int_id: u8, trigger: Trigger) -> bool {
+    const INTERRUPT_MAP_BASE: *const u32 = INTERRUPT_CORE0::ptr().cast();
+
+    let interrupt_number = int as isize;
+    let cpu_interrupt_number = cpu_int_id as isize;
+
+    unsafe {
+        let mapped = INTERRUPT_MAP_BASE.offset(interrupt_number).read_volatile();
+        let enabled =
+            (*INTPRI::ptr()).cpu_int_enable().read().bits() & (1 << cpu_interrupt_number) != 0;
+        let priority = (*INTPRI::ptr())
+            .cpu_int_pri(cpu_interrupt_number as usize)
+            .read()
+            .bits();
+
+        let interrupt_type =
+            ((*INTPRI::ptr()).cpu_int_type().read().bits() >> cpu_interrupt_number) & 1;
+
+        mapped == cpu_interrupt_number as u32
+            && enabled
+            && priority == prio as u32
+            && interrupt_type == trigger as u32
+    }
+}
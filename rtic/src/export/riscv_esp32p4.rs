@@ -0,0 +1,204 @@
+//! ESP32-P4, first HP core
+//!
+//! The two HP cores are RISC-V cores with a CLIC. Peripheral sources are routed to the CPU
+//! interrupts of a core through its interrupt matrix, the CPU interrupts are the external
+//! interrupts of the CLIC, following its 16 local ones. RTIC priorities are the CLIC levels of
+//! the CPU interrupts, `mintthresh` masks the levels up to it and locks raise it to the ceiling.
+//! Only the interrupt matrix of the first core is used, the application runs there.
+//!
+//! The registers are accessed by address, the PAC of the application provides the
+//! `Interrupt` enumeration of the sources.
+
+use core::arch::asm;
+pub use riscv::interrupt;
+use riscv::register::mstatus;
+
+#[cfg(all(feature = "riscv-esp32p4", not(feature = "riscv-esp32p4-backend")))]
+compile_error!("Building for the esp32p4, but 'riscv-esp32p4-backend' not selected");
+
+/// The highest logical priority, the CLIC has 3 level bits
+pub const MAX_PRIORITY: u8 = 7;
+
+/// The level bits in the top of `clicintctl` and `mintthresh`
+const NLBITS: u8 = 3;
+
+/// Base of the interrupt matrix of the first HP core, one map register per source
+const INTERRUPT_MAP_BASE: usize = 0x500d_6000;
+
+/// `HP_SYSTEM_CPU_INT_FROM_CPU_0_REG`, followed by the ones of the other three software sources
+const FROM_CPU_INTR_BASE: usize = 0x500e_5140;
+
+/// The control words of the CLIC interrupts: `clicintip`, `clicintie`, `clicintattr` and
+/// `clicintctl`, one byte each
+const CLIC_CTRL_BASE: usize = 0x2080_1000;
+
+/// The CLIC interrupt of CPU interrupt `0`, the first 16 are the local interrupts of the core
+const CLIC_EXT_INTR_OFFSET: usize = 16;
+
+/// The software interrupt sources used as dispatchers
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoftwareInterrupt {
+    FROM_CPU_INTR0 = 0,
+    FROM_CPU_INTR1 = 1,
+    FROM_CPU_INTR2 = 2,
+    FROM_CPU_INTR3 = 3,
+}
+
+/// Encodes logical priority `level` in the level bits, the bits below them set
+#[inline(always)]
+fn encode(level: u8) -> u8 {
+    (level << (8 - NLBITS)) | ((1 << (8 - NLBITS)) - 1)
+}
+
+#[inline(always)]
+fn decode(raw: u8) -> u8 {
+    raw >> (8 - NLBITS)
+}
+
+#[inline(always)]
+fn clic_ctrl(cpu_int: u8) -> *mut u8 {
+    (CLIC_CTRL_BASE + 4 * (CLIC_EXT_INTR_OFFSET + cpu_int as usize)) as *mut u8
+}
+
+/// The raw interrupt threshold, `mintthresh`
+#[inline(always)]
+fn threshold() -> u8 {
+    let thresh: usize;
+    // SAFETY: reading `mintthresh` has no side effects
+    unsafe { asm!("csrr {0}, 0x347", out(reg) thresh) };
+    thresh as u8
+}
+
+#[inline(always)]
+fn set_threshold(raw: u8) {
+    unsafe { asm!("csrw 0x347, {0}", in(reg) raw as usize) };
+}
+
+/// The level of the running interrupt, `mintstatus.MIL`
+#[inline(always)]
+fn interrupt_level() -> u8 {
+    let mintstatus: usize;
+    // SAFETY: reading `mintstatus` has no side effects
+    unsafe { asm!("csrr {0}, 0xfb1", out(reg) mintstatus) };
+    (mintstatus >> 24) as u8
+}
+
+#[inline(always)]
+pub fn run<F>(priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    if priority == 1 {
+        // If the priority of this interrupt is `1` then the threshold can only be `0`
+        f();
+        set_threshold(0);
+    } else {
+        let initial = threshold();
+        f();
+        set_threshold(initial);
+    }
+}
+
+/// The logical priority of the running code, the ceiling of the held locks included
+///
+/// The level of the running interrupt in `mintstatus`, raised by `mintthresh` when it is masking
+/// more. Outside of interrupts with `mstatus.MIE` cleared, e.g. in `init`, this is the highest
+/// priority.
+#[inline]
+pub fn current_priority() -> u8 {
+    let active = decode(interrupt_level());
+    if active == 0 && !mstatus::read().mie() {
+        return MAX_PRIORITY;
+    }
+
+    active.max(decode(threshold()))
+}
+
+/// Lock implementation using `mintthresh`
+///
+/// # Safety
+///
+/// The system ceiling is raised from current to ceiling by raising `mintthresh` to the level of
+/// the ceiling, if it is masking less. The threshold masks the levels up to it, the highest one
+/// included, so no critical section is needed.
+#[inline(always)]
+pub unsafe fn lock<T, R>(ptr: *mut T, ceiling: u8, f: impl FnOnce(&mut T) -> R) -> R {
+    let current = threshold();
+    let raw = encode(ceiling);
+    if current < raw {
+        set_threshold(raw);
+    }
+    let r = f(&mut *ptr);
+    set_threshold(current);
+    r
+}
+
+/// Sets the given software interrupt as pending
+#[inline(always)]
+pub fn pend(int: SoftwareInterrupt) {
+    super::swi_fence();
+    unsafe {
+        (FROM_CPU_INTR_BASE as *mut u32)
+            .add(int as usize)
+            .write_volatile(1)
+    };
+}
+
+/// Sets the given software interrupt as not pending
+#[inline(always)]
+pub fn unpend(int: SoftwareInterrupt) {
+    unsafe {
+        (FROM_CPU_INTR_BASE as *mut u32)
+            .add(int as usize)
+            .write_volatile(0)
+    };
+    super::swi_fence();
+}
+
+/// Requests the `FROM_CPU_INTR{n}` software interrupt
+///
+/// Returns `false` if `n` is not in `0..4`.
+#[inline]
+pub fn swi_request(n: u16) -> bool {
+    let int = match n {
+        0 => SoftwareInterrupt::FROM_CPU_INTR0,
+        1 => SoftwareInterrupt::FROM_CPU_INTR1,
+        2 => SoftwareInterrupt::FROM_CPU_INTR2,
+        3 => SoftwareInterrupt::FROM_CPU_INTR3,
+        _ => return false,
+    };
+    pend(int);
+    true
+}
+
+/// Routes `source` to CPU interrupt `cpu_int`, sets its level to `prio` and enables it
+///
+/// The CPU interrupt is left level triggered, the software sources stay pending until
+/// [`unpend`] clears them.
+pub fn enable(source: u16, prio: u8, cpu_int: u8) {
+    unsafe {
+        (INTERRUPT_MAP_BASE as *mut u32)
+            .add(source as usize)
+            .write_volatile(cpu_int as u32);
+
+        let ctrl = clic_ctrl(cpu_int);
+        ctrl.add(3).write_volatile(encode(prio));
+        ctrl.add(2).write_volatile(0);
+        ctrl.add(1).write_volatile(1);
+    }
+}
+
+/// Whether `source` is still routed to `cpu_int`, enabled and at `prio`, as set by [`enable`]
+pub fn is_enabled(source: u16, prio: u8, cpu_int: u8) -> bool {
+    unsafe {
+        let mapped = (INTERRUPT_MAP_BASE as *const u32)
+            .add(source as usize)
+            .read_volatile();
+        let ctrl = clic_ctrl(cpu_int);
+
+        mapped == cpu_int as u32
+            && ctrl.add(1).read_volatile() & 1 != 0
+            && decode(ctrl.add(3).read_volatile()) == prio
+    }
+}
@@ -0,0 +1,243 @@
+//! RISC-V application class cores with a standard PLIC, e.g. the SiFive FU540, the Kendryte K210
+//! or the Allwinner D1
+//!
+//! The PLIC priority of a source is its RTIC priority, and the threshold of the context of the
+//! hart masks the priorities up to it. Handlers raise the threshold to their priority and
+//! re-enable interrupts so higher priorities preempt them, locks raise it to the ceiling.
+//!
+//! The PLIC cannot pend a source from software, so the dispatchers are software interrupts: a
+//! pend sets the pending bit of the dispatcher and raises the machine software interrupt of the
+//! hart through its CLINT `msip` register. The `MachineSoft` handler of the application runs the
+//! pending dispatchers above the threshold, and lowering the threshold raises `msip` again while
+//! dispatchers are pending, so a dispatcher pended below a lock runs once the lock is released.
+
+use portable_atomic::{AtomicU32, Ordering};
+pub use riscv::interrupt;
+use riscv::register::{mepc, mie, mstatus};
+
+#[cfg(all(feature = "riscv-plic", not(feature = "riscv-plic-backend")))]
+compile_error!("Building for the PLIC, but 'riscv-plic-backend' not selected");
+
+/// Configuration of the controller, provided by the `#[app]` macro as `__RTIC_PLIC`
+pub struct PlicConfig {
+    pub base: usize,
+    /// The context of the machine mode of the hart running the application
+    pub context: usize,
+    /// The CLINT `msip` register of the hart
+    pub msip: usize,
+    /// The priority levels of the PLIC, `1..=levels`
+    pub levels: u8,
+}
+
+#[inline(always)]
+fn config() -> &'static PlicConfig {
+    extern "Rust" {
+        static __RTIC_PLIC: PlicConfig;
+    }
+    // SAFETY: the static is generated by the `#[app]` macro
+    unsafe { &__RTIC_PLIC }
+}
+
+// Register offsets of the PLIC specification
+const PRIORITY: usize = 0x0000;
+const ENABLE: usize = 0x2000;
+const ENABLE_STRIDE: usize = 0x80;
+const CONTEXT: usize = 0x20_0000;
+const CONTEXT_STRIDE: usize = 0x1000;
+const THRESHOLD: usize = 0x0;
+const CLAIM: usize = 0x4;
+
+/// The dispatchers pended and not yet run, bit `n` for dispatcher `n`
+static PENDING: AtomicU32 = AtomicU32::new(0);
+
+/// The highest logical priority, the highest PLIC priority level
+#[inline(always)]
+pub(crate) fn max_priority() -> u8 {
+    config().levels
+}
+
+#[inline(always)]
+fn context_reg(offset: usize) -> *mut u32 {
+    let config = config();
+    (config.base + CONTEXT + CONTEXT_STRIDE * config.context + offset) as *mut u32
+}
+
+#[inline(always)]
+fn enable_reg(id: u16) -> *mut u32 {
+    let config = config();
+    (config.base + ENABLE + ENABLE_STRIDE * config.context + 4 * (id as usize / 32)) as *mut u32
+}
+
+#[inline(always)]
+fn priority_reg(id: u16) -> *mut u32 {
+    (config().base + PRIORITY + 4 * id as usize) as *mut u32
+}
+
+#[inline(always)]
+fn threshold() -> u8 {
+    // SAFETY: reading the threshold has no side effects
+    unsafe { context_reg(THRESHOLD).read_volatile() as u8 }
+}
+
+/// Writes the threshold and reads it back, so it is in effect before the next access
+#[inline(always)]
+fn write_threshold(threshold: u8) {
+    unsafe {
+        context_reg(THRESHOLD).write_volatile(threshold as u32);
+        context_reg(THRESHOLD).read_volatile();
+    }
+}
+
+/// Lowers the threshold back to `threshold`, raising `msip` if dispatchers are pending
+#[inline(always)]
+fn restore_threshold(threshold: u8) {
+    write_threshold(threshold);
+    if PENDING.load(Ordering::SeqCst) != 0 {
+        raise_soft();
+    }
+}
+
+#[inline(always)]
+fn raise_soft() {
+    super::swi_fence();
+    unsafe { (config().msip as *mut u32).write_volatile(1) };
+}
+
+/// Clears the machine software interrupt of the hart, on entry of `MachineSoft`
+#[inline(always)]
+pub fn clear_soft() {
+    unsafe { (config().msip as *mut u32).write_volatile(0) };
+    super::swi_fence();
+}
+
+/// Clears the threshold and enables the external and software interrupts of the hart
+pub fn configure() {
+    write_threshold(0);
+    unsafe {
+        mie::set_mext();
+        mie::set_msoft();
+    }
+}
+
+/// Sets the priority of source `id` and enables it in the context of the hart
+pub fn enable(id: u16, priority: u8) {
+    unsafe {
+        priority_reg(id).write_volatile(priority as u32);
+        let enable = enable_reg(id).read_volatile();
+        enable_reg(id).write_volatile(enable | 1 << (id % 32));
+    }
+}
+
+/// Whether source `id` is still enabled at `priority`, as set by [`enable`]
+pub fn is_enabled(id: u16, priority: u8) -> bool {
+    unsafe {
+        enable_reg(id).read_volatile() & 1 << (id % 32) != 0
+            && priority_reg(id).read_volatile() == priority as u32
+    }
+}
+
+/// Claims the highest priority pending source above the threshold, `0` if there is none
+#[inline(always)]
+pub fn claim() -> u16 {
+    unsafe { context_reg(CLAIM).read_volatile() as u16 }
+}
+
+/// Completes the handling of source `id`, which the PLIC may then signal again
+#[inline(always)]
+pub fn complete(id: u16) {
+    unsafe { context_reg(CLAIM).write_volatile(id as u32) };
+}
+
+/// Runs the handler of a task at `priority`
+///
+/// The trap masks all interrupts, the handler raises the threshold to its priority and
+/// re-enables them so higher priorities can preempt it. A nested trap overwrites `mepc` and
+/// `mstatus.MPIE`, they are restored for the `mret` of this one.
+#[inline(always)]
+pub fn run<F>(priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    let initial = threshold();
+    write_threshold(priority);
+
+    let mepc = mepc::read();
+    let mpie = mstatus::read().mpie();
+    unsafe { mstatus::set_mie() };
+
+    f();
+
+    unsafe {
+        mstatus::clear_mie();
+        mepc::write(mepc);
+        if mpie {
+            mstatus::set_mpie();
+        }
+    }
+
+    restore_threshold(initial);
+}
+
+/// The logical priority of the running code, the ceiling of the held locks included
+///
+/// The threshold, raised to the priority of each handler and the ceiling of each lock. With
+/// interrupts disabled, e.g. in `init`, this is the highest priority.
+#[inline]
+pub fn current_priority() -> u8 {
+    if !mstatus::read().mie() {
+        return max_priority();
+    }
+
+    threshold()
+}
+
+/// Lock implementation using the threshold of the PLIC
+///
+/// # Safety
+///
+/// The system ceiling is raised from current to ceiling by raising the threshold to the
+/// ceiling, if it is lower. The PLIC masks the sources at or below the threshold and
+/// `MachineSoft` leaves the dispatchers at or below it pending, so the highest ceiling needs no
+/// critical section. The threshold is read back after each write, so the PLIC has stopped
+/// signaling the masked sources before `f` runs.
+#[inline(always)]
+pub unsafe fn lock<T, R>(ptr: *mut T, ceiling: u8, f: impl FnOnce(&mut T) -> R) -> R {
+    let current = threshold();
+    if current < ceiling {
+        write_threshold(ceiling);
+        let r = f(&mut *ptr);
+        restore_threshold(current);
+        r
+    } else {
+        f(&mut *ptr)
+    }
+}
+
+/// Pends software dispatcher `n`, see the module documentation
+#[inline(always)]
+pub fn pend(n: u8) {
+    PENDING.fetch_or(1 << n, Ordering::SeqCst);
+    raise_soft();
+}
+
+/// Takes the pending dispatcher to run next from `dispatchers`, `(index, priority)` pairs sorted
+/// by decreasing priority, called by `MachineSoft` until it returns `None`
+///
+/// Dispatchers at or below the threshold stay pending.
+#[inline(always)]
+pub fn next_dispatcher(dispatchers: &[(u8, u8)]) -> Option<u8> {
+    let threshold = threshold();
+    let pending = PENDING.load(Ordering::SeqCst);
+    let (index, _) = dispatchers
+        .iter()
+        .find(|(index, priority)| *priority > threshold && pending & 1 << index != 0)?;
+
+    PENDING.fetch_and(!(1 << index), Ordering::SeqCst);
+    Some(*index)
+}
+
+/// The PLIC cannot pend its sources from software, there are no software interrupts to request
+#[inline]
+pub fn swi_request(_n: u16) -> bool {
+    false
+}
@@ -0,0 +1,198 @@
+//! RP2350 Hazard3 cores, through the `Xh3irq` interrupt controller
+//!
+//! The controller is programmed through arrays of CSRs, accessed in windows of 16 interrupts:
+//! the index of the window in the low bits of the written value and its contents in the top
+//! half. `meiea` enables the interrupts, `meifa` forces them pending and `meipra` holds their
+//! 4-bit priorities, higher values preempting lower ones. The external interrupt handler of the
+//! runtime takes them by priority through `meinext`, which records the interrupt in
+//! `meicontext`.
+//!
+//! Locks mask the sources of the priorities up to the ceiling in `meiea`, as source masking on
+//! Cortex-M does, and the dispatchers are pended by forcing them in `meifa`. The PAC of the
+//! application provides the `Interrupt` enumeration, the interrupt numbers are taken from it by
+//! the `#[app]` macro.
+
+use core::arch::asm;
+pub use riscv::interrupt;
+use riscv::register::mstatus;
+
+#[cfg(all(feature = "riscv-rp2350", not(feature = "riscv-rp2350-backend")))]
+compile_error!("Building for the RP2350, but 'riscv-rp2350-backend' not selected");
+
+/// The highest logical priority, the highest of the 4-bit priorities of `meipra`
+pub const MAX_PRIORITY: u8 = 15;
+
+/// The number of interrupts of the RP2350
+const IRQS: u16 = 52;
+
+/// Windows of 16 interrupts covering them
+const WINDOWS: usize = (IRQS as usize).div_ceil(16);
+
+/// `meicontext.NOIRQ`, no interrupt is being handled
+const MEICONTEXT_NOIRQ: u32 = 1 << 15;
+
+/// Sets `bits` in window `index` of an interrupt array CSR
+macro_rules! array_set {
+    ($csr:literal, $index:expr, $bits:expr) => {
+        asm!(concat!("csrs ", $csr, ", {0}"), in(reg) ($index as u32) | ($bits as u32) << 16)
+    };
+}
+
+/// Clears `bits` in window `index` of an interrupt array CSR
+macro_rules! array_clear {
+    ($csr:literal, $index:expr, $bits:expr) => {
+        asm!(concat!("csrc ", $csr, ", {0}"), in(reg) ($index as u32) | ($bits as u32) << 16)
+    };
+}
+
+/// Reads window `index` of an interrupt array CSR, setting no bits
+macro_rules! array_read {
+    ($csr:literal, $index:expr) => {{
+        let window: u32;
+        asm!(concat!("csrrs {0}, ", $csr, ", {1}"), out(reg) window, in(reg) $index as u32);
+        (window >> 16) as u16
+    }};
+}
+
+/// The interrupts masked by a lock, one bit per interrupt in windows of 16
+#[derive(Copy, Clone)]
+pub struct Mask([u16; WINDOWS]);
+
+/// The mask of the interrupts `irqs`, computed at compile time by the `#[app]` macro
+pub const fn create_mask<const N: usize>(irqs: [u16; N]) -> Mask {
+    let mut mask = [0; WINDOWS];
+    let mut i = 0;
+
+    while i < N {
+        let irq = irqs[i];
+        if irq >= IRQS {
+            panic!("the RP2350 has 52 interrupts");
+        }
+        mask[irq as usize / 16] |= 1 << (irq % 16);
+        i += 1;
+    }
+
+    Mask(mask)
+}
+
+#[inline(always)]
+pub fn run<F>(_priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    f();
+}
+
+/// The priority of `irq` in `meipra`
+#[inline(always)]
+fn priority(irq: u16) -> u8 {
+    // SAFETY: reading a window has no side effects
+    let window = unsafe { array_read!("0xbe3", irq / 4) };
+    (window >> (4 * (irq % 4)) & 0xf) as u8
+}
+
+/// The logical priority of the running code
+///
+/// The priority of the interrupt being handled according to `meicontext`, `0` outside of
+/// interrupts. Masking the sources of a lock does not change it, so the ceiling of the held
+/// locks is not included. With interrupts disabled, e.g. in `init`, this is the highest
+/// priority.
+#[inline]
+pub fn current_priority() -> u8 {
+    if !mstatus::read().mie() {
+        return MAX_PRIORITY;
+    }
+
+    let meicontext: u32;
+    // SAFETY: reading `meicontext` has no side effects
+    unsafe { asm!("csrr {0}, 0xbe5", out(reg) meicontext) };
+
+    if meicontext & MEICONTEXT_NOIRQ != 0 {
+        0
+    } else {
+        priority((meicontext >> 4 & 0x1ff) as u16)
+    }
+}
+
+/// Lock implementation using source masking in `meiea`
+///
+/// # Safety
+///
+/// The system ceiling is raised from current to ceiling by disabling the interrupts of the
+/// priorities up to the ceiling in `mask`, restoring the ones that were enabled afterwards. A
+/// lock at the highest ceiling is a global critical section instead.
+///
+/// The CSR accesses are not reordered with the accesses of `f`, as the `asm!` blocks are
+/// compiler barriers and the CSRs take effect in program order.
+#[inline(always)]
+pub unsafe fn lock<T, R>(ptr: *mut T, ceiling: u8, mask: Mask, f: impl FnOnce(&mut T) -> R) -> R {
+    if ceiling >= MAX_PRIORITY {
+        return critical_section::with(|_| f(&mut *ptr));
+    }
+
+    let mut enabled = [0; WINDOWS];
+    for (index, bits) in mask.0.iter().enumerate() {
+        // Constant folded, only the windows of the masked interrupts are accessed
+        if *bits != 0 {
+            enabled[index] = array_read!("0xbe0", index) & bits;
+            array_clear!("0xbe0", index, *bits);
+        }
+    }
+
+    let r = f(&mut *ptr);
+
+    for (index, bits) in enabled.iter().enumerate() {
+        if mask.0[index] != 0 {
+            array_set!("0xbe0", index, *bits);
+        }
+    }
+
+    r
+}
+
+/// Sets `irq` as pending by forcing it in `meifa`
+#[inline(always)]
+pub fn pend(irq: u16) {
+    super::swi_fence();
+    // SAFETY: forcing an interrupt only runs its handler
+    unsafe { array_set!("0xbe2", irq / 16, 1 << (irq % 16)) };
+}
+
+/// Clears the force of `irq` in `meifa`, the forced interrupts stay pending until cleared
+#[inline(always)]
+pub fn unpend(irq: u16) {
+    // SAFETY: clearing the force of an interrupt has no other effect
+    unsafe { array_clear!("0xbe2", irq / 16, 1 << (irq % 16)) };
+    super::swi_fence();
+}
+
+/// Forces interrupt `n` pending
+///
+/// Returns `false` if `n` is not one of the 52 interrupts.
+#[inline]
+pub fn swi_request(n: u16) -> bool {
+    if n >= IRQS {
+        return false;
+    }
+    pend(n);
+    true
+}
+
+/// Sets the priority of `irq` in `meipra` and enables it in `meiea`
+pub fn enable(irq: u16, priority: u8) {
+    let shift = 4 * (irq % 4);
+
+    unsafe {
+        array_clear!("0xbe3", irq / 4, 0xf << shift);
+        array_set!("0xbe3", irq / 4, (priority as u32 & 0xf) << shift);
+        array_set!("0xbe0", irq / 16, 1 << (irq % 16));
+    }
+}
+
+/// Whether `irq` is still enabled at `priority`, as set by [`enable`]
+pub fn is_enabled(irq: u16, priority: u8) -> bool {
+    // SAFETY: reading a window has no side effects
+    let enabled = unsafe { array_read!("0xbe0", irq / 16) } & 1 << (irq % 16) != 0;
+
+    enabled && self::priority(irq) == priority
+}
@@ -0,0 +1,229 @@
+//! RISC-V cores with a single machine external interrupt and no interrupt priorities, with a
+//! software interrupt controller (soft SLIC)
+//!
+//! The priorities are kept in software. `CURRENT` holds the priority of the running task and the
+//! ceiling of its locks, `PENDING` the interrupts waiting for it to drop: the dispatchers and the
+//! hardware tasks are entries of the `__RTIC_SOFT_SLIC` table generated by the `#[app]` macro,
+//! numbered by the `irq` module of the application, bit `n` for entry `n`.
+//!
+//! The `MachineExternal` handler claims the pending sources with the `claim` function of the
+//! application, which masks them, and pends their hardware tasks. It then runs the pending entries
+//! above the current priority with interrupts enabled, so a source of a higher priority preempts
+//! them, and a hardware task completes its source with `complete` once it has run. Pending an
+//! entry from a task runs it right away if its priority is above the running one, otherwise it
+//! runs when a handler or a lock lowers the priority below it. Locks never disable interrupts.
+
+use portable_atomic::{AtomicU32, AtomicU8, Ordering};
+use riscv::register::{mepc, mie, mstatus};
+
+/// The highest logical priority, kept in software
+pub const MAX_PRIORITY: u8 = u8::MAX;
+
+#[cfg(all(feature = "riscv-soft-slic", not(feature = "riscv-soft-slic-backend")))]
+compile_error!("Building for the soft SLIC, but 'riscv-soft-slic-backend' not selected");
+
+/// The interrupts of the application, provided by the `#[app]` macro as `__RTIC_SOFT_SLIC`
+pub struct SoftSlicConfig {
+    /// The priority and the handler of interrupt `n`, at index `n`
+    pub interrupts: &'static [(u8, fn())],
+}
+
+#[inline(always)]
+fn config() -> &'static SoftSlicConfig {
+    extern "Rust" {
+        static __RTIC_SOFT_SLIC: SoftSlicConfig;
+    }
+    // SAFETY: the static is generated by the `#[app]` macro
+    unsafe { &__RTIC_SOFT_SLIC }
+}
+
+/// The priority of the running code, see the module documentation
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// The pended interrupts, bit `n` for interrupt `n`
+static PENDING: AtomicU32 = AtomicU32::new(0);
+
+/// The highest number of interrupts an application can have
+pub const MAX_INTERRUPTS: usize = u32::BITS as usize;
+
+/// Disabling and enabling all interrupts through `mstatus.MIE`
+pub mod interrupt {
+    use riscv::register::mstatus;
+
+    /// Disables all interrupts
+    #[inline(always)]
+    pub fn disable() {
+        // SAFETY: only masks interrupts
+        unsafe { mstatus::clear_mie() };
+    }
+
+    /// Enables all interrupts and runs the interrupts pended while they were disabled, e.g. by
+    /// the spawns in `init`
+    ///
+    /// # Safety
+    ///
+    /// Interrupt handlers may run from here on, as in a critical section being left.
+    #[inline(always)]
+    pub unsafe fn enable() {
+        mstatus::set_mie();
+        super::dispatch();
+    }
+}
+
+/// Clears the software priority and enables the machine external interrupt of the hart
+pub fn configure() {
+    CURRENT.store(0, Ordering::Relaxed);
+    PENDING.store(0, Ordering::Relaxed);
+    // SAFETY: `MIE` is cleared until `init` returns
+    unsafe { mie::set_mext() };
+}
+
+/// Takes the highest priority pending interrupt above the current priority, its bit cleared
+///
+/// Called with interrupts disabled. Ties go to the lowest number, the dispatchers before the
+/// hardware tasks.
+fn next() -> Option<fn()> {
+    let interrupts = config().interrupts;
+    let current = CURRENT.load(Ordering::Relaxed);
+    let mut bits = PENDING.load(Ordering::Relaxed);
+    let mut best: Option<(usize, u8)> = None;
+    while bits != 0 {
+        let n = bits.trailing_zeros() as usize;
+        let prio = interrupts[n].0;
+        if prio > current && best.is_none_or(|(_, best)| prio > best) {
+            best = Some((n, prio));
+        }
+        bits &= bits - 1;
+    }
+
+    let (n, _) = best?;
+    PENDING.store(
+        PENDING.load(Ordering::Relaxed) & !(1 << n),
+        Ordering::Relaxed,
+    );
+    Some(interrupts[n].1)
+}
+
+/// Runs the pending interrupts above the current priority, highest first
+///
+/// Called with interrupts enabled, each handler sets the current priority to its own.
+#[inline(never)]
+fn dispatch() {
+    while let Some(handler) = riscv::interrupt::free(next) {
+        handler();
+    }
+}
+
+/// Runs the pending interrupts above the current priority from `MachineExternal`, once the
+/// claimed sources are pended
+///
+/// The trap masks all interrupts, they are enabled while the handlers run so higher priorities
+/// preempt them. A nested trap overwrites `mepc` and `mstatus.MPIE`, they are restored for the
+/// `mret` of this one.
+#[inline(always)]
+pub fn on_external() {
+    let mepc = mepc::read();
+    let mpie = mstatus::read().mpie();
+
+    // SAFETY: the sources pended by the trap are claimed, so masked until they complete
+    unsafe { mstatus::set_mie() };
+    dispatch();
+
+    unsafe {
+        mstatus::clear_mie();
+        mepc::write(mepc);
+        if mpie {
+            mstatus::set_mpie();
+        }
+    }
+}
+
+/// Runs the handler of a task at `priority`
+///
+/// Called from the dispatch of the soft SLIC, which runs the pending interrupts above the restored
+/// priority once `f` returns.
+#[inline(always)]
+pub fn run<F>(priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    let previous = CURRENT.load(Ordering::Relaxed);
+    CURRENT.store(priority, Ordering::Relaxed);
+    f();
+    CURRENT.store(previous, Ordering::Relaxed);
+}
+
+/// The logical priority of the running code, the ceiling of the held locks included
+///
+/// Kept in software, see the module documentation. With interrupts disabled, e.g. in `init`,
+/// this is the highest priority.
+#[inline]
+pub fn current_priority() -> u8 {
+    if !mstatus::read().mie() {
+        return u8::MAX;
+    }
+
+    CURRENT.load(Ordering::Relaxed)
+}
+
+/// Lock implementation using the software priority
+///
+/// # Safety
+///
+/// The system ceiling is raised from current to ceiling by raising the software priority to the
+/// ceiling, if it is lower. The dispatch of the soft SLIC leaves the interrupts at or below it
+/// pending, and a handler preempting the lock restores the priority before returning. Once `f`
+/// returns the interrupts pended above the restored priority run.
+#[inline(always)]
+pub unsafe fn lock<T, R>(ptr: *mut T, ceiling: u8, f: impl FnOnce(&mut T) -> R) -> R {
+    let current = CURRENT.load(Ordering::Relaxed);
+    if current < ceiling {
+        CURRENT.store(ceiling, Ordering::Relaxed);
+        let r = f(&mut *ptr);
+        CURRENT.store(current, Ordering::Relaxed);
+        if mstatus::read().mie() && PENDING.load(Ordering::Relaxed) != 0 {
+            dispatch();
+        }
+        r
+    } else {
+        f(&mut *ptr)
+    }
+}
+
+/// Pends interrupt `n`, see the module documentation
+///
+/// With interrupts enabled an interrupt above the current priority runs before this returns.
+#[inline]
+pub fn pend(n: u16) {
+    super::swi_fence();
+    riscv::interrupt::free(|| {
+        PENDING.store(PENDING.load(Ordering::Relaxed) | 1 << n, Ordering::Relaxed);
+    });
+    if mstatus::read().mie() {
+        dispatch();
+    }
+}
+
+/// Clears the pending interrupt `n`
+#[inline]
+pub fn unpend(n: u16) {
+    riscv::interrupt::free(|| {
+        PENDING.store(
+            PENDING.load(Ordering::Relaxed) & !(1 << n),
+            Ordering::Relaxed,
+        );
+    });
+    super::swi_fence();
+}
+
+/// Pends interrupt `n` of the application, a dispatcher or a hardware task
+///
+/// Returns `false` if the application has no interrupt `n`.
+#[inline]
+pub fn swi_request(n: u16) -> bool {
+    if n as usize >= config().interrupts.len() {
+        return false;
+    }
+    pend(n);
+    true
+}
@@ -0,0 +1,32 @@
+use super::atomic::{AtomicU32, Ordering};
+
+/// Counts the spawns of a software task dropped while its dispatcher was overloaded.
+pub struct Shed {
+    dropped: AtomicU32,
+}
+
+impl Default for Shed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shed {
+    pub const fn new() -> Self {
+        Self {
+            dropped: AtomicU32::new(0),
+        }
+    }
+
+    /// Records `n` dropped spawns.
+    #[inline(always)]
+    pub fn record(&self, n: usize) {
+        self.dropped.fetch_add(n as u32, Ordering::Relaxed);
+    }
+
+    /// The spawns dropped so far, wrapping around at `u32::MAX`.
+    #[inline(always)]
+    pub fn count(&self) -> u32 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
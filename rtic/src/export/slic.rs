@@ -1,4 +1,4 @@
-pub use riscv_slic::{lock, pend, run, InterruptNumber};
+pub use riscv_slic::{lock, run, InterruptNumber};
 
 #[cfg(all(feature = "riscv-slic", not(feature = "riscv-clint-backend")))]
 compile_error!("Building for the riscv-slic, but 'riscv-clint-backend' not selected");
@@ -6,6 +6,38 @@ compile_error!("Building for the riscv-slic, but 'riscv-clint-backend' not selec
 /// USE CASE RE-EXPORTS: needed for SLIC-only
 pub use riscv_slic::{self, clear_interrupts, codegen, set_interrupts, set_priority};
 
+/// The highest logical priority supported by the SLIC
+pub const MAX_PRIORITY: u8 = u8::MAX;
+
+/// The logical priority of the running code, the ceiling of the held locks included
+///
+/// The SLIC threshold, raised to the priority of each handler and the ceiling of each lock.
+#[inline]
+pub fn current_priority() -> u8 {
+    riscv_slic::get_threshold()
+}
+
+/// Pends the SLIC software interrupt `interrupt`, see [`swi_fence`](super::swi_fence)
+#[inline]
+pub fn pend<I: InterruptNumber>(interrupt: I) {
+    super::swi_fence();
+    riscv_slic::pend(interrupt);
+}
+
+/// Pends the SLIC software interrupt number `n`, the SLIC raises it through the CLINT MSIP
+///
+/// The SLIC interrupts are generated by the `#[app]` macro, which also provides
+/// `__rtic_swi_request` to check `n` against them.
+/// Returns `false` if `n` is not a SLIC software interrupt of the application.
+#[inline]
+pub fn swi_request(n: u16) -> bool {
+    extern "Rust" {
+        fn __rtic_swi_request(n: u16) -> bool;
+    }
+    // SAFETY: the function is generated by the `#[app]` macro
+    unsafe { __rtic_swi_request(n) }
+}
+
 pub mod interrupt {
     pub fn disable() {
         riscv_slic::disable();
@@ -0,0 +1,279 @@
+//! Host backend simulating the priorities of the application with threads, for `cargo test`
+//!
+//! One simulated CPU runs the application: a thread owns it while it runs a handler or holds a
+//! lock, other threads wait for it on a mutex. The dispatchers and the hardware tasks are entries
+//! of the `__RTIC_STD` table generated by the `#[app]` macro, numbered by the `irq` module of the
+//! application. A pend from the owner runs the entry right away if its priority is above the
+//! running one, otherwise it runs when the priority drops below it, before the CPU is released.
+//! A pend from another thread takes the CPU if it is free, or leaves the entry to the owner.
+//!
+//! `main` is exported, so a test can run the application on a thread and pend the hardware tasks
+//! with `rtic::pend(app::irq::NAME)`:
+//!
+//! ```ignore
+//! std::thread::spawn(|| unsafe { app::main() });
+//! rtic::pend(app::irq::UART0);
+//! ```
+
+extern crate std;
+
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::thread::{self, ThreadId};
+
+/// The highest logical priority, simulated in software
+pub const MAX_PRIORITY: u8 = u8::MAX;
+
+#[cfg(all(feature = "std", not(feature = "std-backend")))]
+compile_error!("Building for the std backend, but 'std-backend' not selected");
+
+/// The interrupts of the application, provided by the `#[app]` macro as `__RTIC_STD`
+pub struct StdConfig {
+    /// The priority and the handler of interrupt `n`, at index `n`
+    pub interrupts: &'static [(u8, fn())],
+}
+
+#[inline(always)]
+fn config() -> &'static StdConfig {
+    extern "Rust" {
+        static __RTIC_STD: StdConfig;
+    }
+    // SAFETY: the static is generated by the `#[app]` macro
+    unsafe { &__RTIC_STD }
+}
+
+/// The state of the simulated CPU
+struct Cpu {
+    /// The thread running the application, if any
+    owner: Option<ThreadId>,
+    /// The nested `run`s and locks of the owner
+    depth: usize,
+    /// The priority of the running code, the ceiling of the held locks included
+    current: u8,
+    /// The pended interrupts, bit `n` for interrupt `n`
+    pending: u64,
+}
+
+static CPU: Mutex<Cpu> = Mutex::new(Cpu {
+    owner: None,
+    depth: 0,
+    current: 0,
+    pending: 0,
+});
+
+/// Signalled when the CPU is released
+static RELEASED: Condvar = Condvar::new();
+
+/// The highest number of interrupts an application can have
+pub const MAX_INTERRUPTS: usize = u64::BITS as usize;
+
+#[inline(always)]
+fn cpu() -> MutexGuard<'static, Cpu> {
+    // A panicking task poisons the mutex, the state is still consistent
+    CPU.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Takes the CPU for the calling thread, waiting for another owner to release it
+fn acquire() -> MutexGuard<'static, Cpu> {
+    let me = thread::current().id();
+    let mut cpu = cpu();
+    while cpu.owner.is_some_and(|owner| owner != me) {
+        cpu = RELEASED
+            .wait(cpu)
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+    }
+    cpu.owner = Some(me);
+    cpu.depth += 1;
+    cpu
+}
+
+/// The highest priority pending interrupt that preempts the running code, its bit cleared
+fn next(cpu: &mut Cpu) -> Option<fn()> {
+    let interrupts = config().interrupts;
+    let mut bits = cpu.pending;
+    let mut best: Option<(usize, u8)> = None;
+    while bits != 0 {
+        let n = bits.trailing_zeros() as usize;
+        let prio = interrupts[n].0;
+        if prio > cpu.current && best.is_none_or(|(_, best)| prio > best) {
+            best = Some((n, prio));
+        }
+        bits &= bits - 1;
+    }
+
+    let (n, _) = best?;
+    cpu.pending &= !(1 << n);
+    Some(interrupts[n].1)
+}
+
+/// Runs the pending interrupts that preempt the running code, the CPU owned by the caller
+fn dispatch(mut cpu: MutexGuard<'static, Cpu>) -> MutexGuard<'static, Cpu> {
+    while let Some(handler) = next(&mut cpu) {
+        drop(cpu);
+        handler();
+        cpu = self::cpu();
+    }
+    cpu
+}
+
+/// Runs the pending interrupts that preempt the running code and gives up one level of
+/// ownership, releasing the CPU at the last one
+fn release(cpu: MutexGuard<'static, Cpu>) {
+    let mut cpu = dispatch(cpu);
+    cpu.depth -= 1;
+    if cpu.depth == 0 {
+        cpu.owner = None;
+        drop(cpu);
+        RELEASED.notify_all();
+    }
+}
+
+/// Interrupts "disabled" means `init` owns the CPU at the highest priority
+pub mod interrupt {
+    /// Takes the CPU at the highest priority, the interrupts pended meanwhile wait for
+    /// [`enable`]
+    #[inline(always)]
+    pub fn disable() {
+        let mut cpu = super::acquire();
+        cpu.current = u8::MAX;
+    }
+
+    /// Drops the priority to `0` and releases the CPU taken by [`disable`], running the pended
+    /// interrupts first
+    ///
+    /// # Safety
+    ///
+    /// Must follow [`disable`] on the same thread.
+    #[inline(always)]
+    pub unsafe fn enable() {
+        let mut cpu = super::cpu();
+        cpu.current = 0;
+        super::release(cpu);
+    }
+}
+
+/// The interrupt masking of the modes, which does nothing on the host
+pub struct NVIC;
+
+impl NVIC {
+    /// Does nothing
+    #[inline(always)]
+    pub fn mask<I>(_interrupt: I) {}
+
+    /// Does nothing
+    ///
+    /// # Safety
+    ///
+    /// Safe on this backend, `unsafe` as on Cortex-M.
+    #[inline(always)]
+    pub unsafe fn unmask<I>(_interrupt: I) {}
+}
+
+/// Wakes `main` from [`idle_wait`], which only yields on the host
+#[inline(always)]
+pub fn wake_idle() {}
+
+/// Yields the zero priority dispatcher of `main` to the other threads while `is_idle` reports no
+/// task ready
+#[inline(always)]
+pub fn idle_wait(is_idle: impl FnOnce() -> bool) {
+    if is_idle() {
+        thread::yield_now();
+    }
+}
+
+/// Parks the thread of `main` for good, the pended interrupts run on the threads pending them
+pub fn sleep_on_exit() -> ! {
+    loop {
+        thread::park();
+    }
+}
+
+/// Runs the handler of an interrupt at `priority`, the CPU owned by the calling thread
+#[inline(always)]
+pub fn run<F>(priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    let mut cpu = acquire();
+    let previous = cpu.current;
+    cpu.current = priority;
+    drop(cpu);
+
+    f();
+
+    let mut cpu = self::cpu();
+    cpu.current = previous;
+    release(cpu);
+}
+
+/// The priority of the running code, the ceiling of the held locks included
+///
+/// `0` on the threads not owning the CPU, e.g. in `idle`.
+#[inline]
+pub fn current_priority() -> u8 {
+    let cpu = cpu();
+    if cpu.owner == Some(thread::current().id()) {
+        cpu.current
+    } else {
+        0
+    }
+}
+
+/// Lock implementation owning the CPU at the ceiling
+///
+/// # Safety
+///
+/// The CPU is taken, so no other thread runs the application, and the priority raised to the
+/// ceiling, so no interrupt sharing the resource runs on this one. The interrupts pended meanwhile
+/// run when the lock is released.
+#[inline(always)]
+pub unsafe fn lock<T, R>(ptr: *mut T, ceiling: u8, f: impl FnOnce(&mut T) -> R) -> R {
+    let mut cpu = acquire();
+    let previous = cpu.current;
+    cpu.current = previous.max(ceiling);
+    drop(cpu);
+
+    let r = f(&mut *ptr);
+
+    let mut cpu = self::cpu();
+    cpu.current = previous;
+    release(cpu);
+    r
+}
+
+/// Pends interrupt `n`
+///
+/// Runs it on the calling thread if the CPU is free or owned by it and the running code has a
+/// lower priority, otherwise leaves it to the owner.
+#[inline]
+pub fn pend(n: u16) {
+    let mut cpu = cpu();
+    cpu.pending |= 1 << n;
+    match cpu.owner {
+        None => {
+            cpu.owner = Some(thread::current().id());
+            cpu.depth = 1;
+            release(cpu);
+        }
+        Some(owner) if owner == thread::current().id() => drop(dispatch(cpu)),
+        Some(_) => {}
+    }
+}
+
+/// Clears the pending interrupt `n`
+#[inline]
+pub fn unpend(n: u16) {
+    cpu().pending &= !(1 << n);
+}
+
+/// Pends interrupt `n` of the application
+///
+/// Returns `false` if the application has no interrupt `n`.
+#[inline]
+pub fn swi_request(n: u16) -> bool {
+    if n as usize >= config().interrupts.len() {
+        return false;
+    }
+    pend(n);
+    true
+}
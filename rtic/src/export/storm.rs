@@ -0,0 +1,59 @@
+use super::atomic::{AtomicU32, Ordering};
+use cortex_m::{interrupt::InterruptNumber, peripheral::NVIC};
+
+/// Counts the interrupts of a hardware task within the current window.
+pub struct Storm {
+    count: AtomicU32,
+}
+
+impl Default for Storm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Storm {
+    pub const fn new() -> Self {
+        Self {
+            count: AtomicU32::new(0),
+        }
+    }
+
+    /// Records an interrupt, returns `true` if it is over the limit of the window.
+    #[inline(always)]
+    pub fn record(&self, limit: u32) -> bool {
+        self.count.fetch_add(1, Ordering::Relaxed) >= limit
+    }
+
+    /// Starts counting from zero.
+    #[inline(always)]
+    pub fn reset(&self) {
+        self.count.store(0, Ordering::Relaxed);
+    }
+
+    /// Ends the window unless the guard tripped in it, returns `true` if it tripped.
+    ///
+    /// Done in one step so a trip racing with the end of the window is not lost.
+    #[inline(always)]
+    pub fn close_window(&self, limit: u32) -> bool {
+        self.count
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                (count <= limit).then_some(0)
+            })
+            .is_err()
+    }
+}
+
+/// Masks `interrupt` after its guard tripped.
+#[inline(always)]
+pub fn mask<I: InterruptNumber>(interrupt: I) {
+    NVIC::mask(interrupt);
+}
+
+/// Unmasks `interrupt` after the cooldown.
+#[inline(always)]
+pub fn unmask<I: InterruptNumber>(interrupt: I) {
+    // SAFETY: The interrupt is bound to a hardware task, its priority was set before `init` and
+    // masking it was only done by the storm guard.
+    unsafe { NVIC::unmask(interrupt) };
+}
@@ -0,0 +1,222 @@
+//! WebAssembly backend (`wasm32-unknown-unknown`) with a cooperative scheduler, for in-browser
+//! demos and simulations in CI
+//!
+//! The application runs in a Web Worker, or under Node.js, with a single thread. The dispatchers
+//! and the hardware tasks are entries of the `__RTIC_WASM` table generated by the `#[app]` macro,
+//! numbered by the `irq` module of the application. A pend runs the entry right away if its
+//! priority is above the running one, otherwise it runs when the priority drops below it, so
+//! tasks only give way to each other at pends, lock releases and `await`s.
+//!
+//! The host instantiates the module with the `rtic` imports below and calls the exported
+//! `rtic_main`, which runs `init` and then waits on the host for good with [`sleep_on_exit`]. The
+//! host pends the hardware tasks from its `wait` import, by calling the exported `rtic_pend`:
+//!
+//! ```js
+//! const { instance } = await WebAssembly.instantiate(bytes, {
+//!     rtic: {
+//!         // Blocks until an event arrives, e.g. with `Atomics.wait`, and delivers it
+//!         wait: () => { const n = nextEvent(); instance.exports.rtic_pend(n); },
+//!     },
+//! });
+//! instance.exports.rtic_main();
+//! ```
+
+use portable_atomic::{AtomicU64, AtomicU8, Ordering};
+
+/// The highest logical priority, kept in software
+pub const MAX_PRIORITY: u8 = u8::MAX;
+
+#[cfg(all(feature = "wasm", not(feature = "wasm-backend")))]
+compile_error!("Building for the wasm backend, but 'wasm-backend' not selected");
+
+/// The interrupts of the application, provided by the `#[app]` macro as `__RTIC_WASM`
+pub struct WasmConfig {
+    /// The priority and the handler of interrupt `n`, at index `n`
+    pub interrupts: &'static [(u8, fn())],
+}
+
+#[inline(always)]
+fn config() -> &'static WasmConfig {
+    extern "Rust" {
+        static __RTIC_WASM: WasmConfig;
+    }
+    // SAFETY: the static is generated by the `#[app]` macro
+    unsafe { &__RTIC_WASM }
+}
+
+#[link(wasm_import_module = "rtic")]
+extern "C" {
+    /// Blocks until the host has an event for the application and delivers it, by calling the
+    /// exported `rtic_pend` or the handler of a monotonic
+    fn wait();
+}
+
+/// The priority of the running code, the ceiling of the held locks included
+///
+/// Starts at the highest priority, nothing pended by the host runs before `init` is done.
+static CURRENT: AtomicU8 = AtomicU8::new(u8::MAX);
+
+/// The pended interrupts, bit `n` for interrupt `n`
+static PENDING: AtomicU64 = AtomicU64::new(0);
+
+/// The highest number of interrupts an application can have
+pub const MAX_INTERRUPTS: usize = u64::BITS as usize;
+
+/// The highest priority pending interrupt that preempts the running code, its bit cleared
+fn next() -> Option<fn()> {
+    let interrupts = config().interrupts;
+    let current = CURRENT.load(Ordering::Relaxed);
+    let mut bits = PENDING.load(Ordering::Relaxed);
+    let mut best: Option<(usize, u8)> = None;
+    while bits != 0 {
+        let n = bits.trailing_zeros() as usize;
+        let prio = interrupts[n].0;
+        if prio > current && best.is_none_or(|(_, best)| prio > best) {
+            best = Some((n, prio));
+        }
+        bits &= bits - 1;
+    }
+
+    let (n, _) = best?;
+    PENDING.fetch_and(!(1 << n), Ordering::Relaxed);
+    Some(interrupts[n].1)
+}
+
+/// Runs the pending interrupts that preempt the running code
+#[inline]
+fn dispatch() {
+    while let Some(handler) = next() {
+        handler();
+    }
+}
+
+/// Interrupts "disabled" means the running code is at the highest priority
+pub mod interrupt {
+    use super::{Ordering, CURRENT};
+
+    /// Raises the priority above every interrupt, the interrupts pended meanwhile wait for
+    /// [`enable`]
+    #[inline(always)]
+    pub fn disable() {
+        CURRENT.store(u8::MAX, Ordering::Relaxed);
+    }
+
+    /// Drops the priority to `0`, running the pended interrupts
+    ///
+    /// # Safety
+    ///
+    /// Safe on this backend, `unsafe` as on Cortex-M.
+    #[inline(always)]
+    pub unsafe fn enable() {
+        CURRENT.store(0, Ordering::Relaxed);
+        super::dispatch();
+    }
+}
+
+/// The interrupt masking of the modes, which does nothing on wasm
+pub struct NVIC;
+
+impl NVIC {
+    /// Does nothing
+    #[inline(always)]
+    pub fn mask<I>(_interrupt: I) {}
+
+    /// Does nothing
+    ///
+    /// # Safety
+    ///
+    /// Safe on this backend, `unsafe` as on Cortex-M.
+    #[inline(always)]
+    pub unsafe fn unmask<I>(_interrupt: I) {}
+}
+
+/// Wakes `main` from [`idle_wait`], which returns after each event of the host anyway
+#[inline(always)]
+pub fn wake_idle() {}
+
+/// Waits on the host for an event while `is_idle` reports no task ready in the zero priority
+/// dispatcher of `main`
+#[inline(always)]
+pub fn idle_wait(is_idle: impl FnOnce() -> bool) {
+    if is_idle() {
+        // SAFETY: the host runs the events on the calling thread, before returning
+        unsafe { wait() };
+    }
+}
+
+/// Waits on the host for good, the events it delivers run from its `wait` import
+pub fn sleep_on_exit() -> ! {
+    loop {
+        // SAFETY: as in `idle_wait`
+        unsafe { wait() };
+    }
+}
+
+/// Runs the handler of an interrupt at `priority`
+#[inline(always)]
+pub fn run<F>(priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    let previous = CURRENT.swap(priority, Ordering::Relaxed);
+    f();
+    CURRENT.store(previous, Ordering::Relaxed);
+    dispatch();
+}
+
+/// The priority of the running code, the ceiling of the held locks included
+#[inline]
+pub fn current_priority() -> u8 {
+    CURRENT.load(Ordering::Relaxed)
+}
+
+/// Lock implementation raising the priority to the ceiling
+///
+/// # Safety
+///
+/// There is a single thread, and the priority raised to the ceiling, so no interrupt sharing the
+/// resource runs until the lock is released. The interrupts pended meanwhile run then.
+#[inline(always)]
+pub unsafe fn lock<T, R>(ptr: *mut T, ceiling: u8, f: impl FnOnce(&mut T) -> R) -> R {
+    let previous = CURRENT.load(Ordering::Relaxed);
+    if ceiling > previous {
+        CURRENT.store(ceiling, Ordering::Relaxed);
+    }
+
+    let r = f(&mut *ptr);
+
+    CURRENT.store(previous, Ordering::Relaxed);
+    dispatch();
+    r
+}
+
+/// Pends interrupt `n`, running it right away if the running code has a lower priority
+#[inline]
+pub fn pend(n: u16) {
+    PENDING.fetch_or(1 << n, Ordering::Relaxed);
+    dispatch();
+}
+
+/// Clears the pending interrupt `n`
+#[inline]
+pub fn unpend(n: u16) {
+    PENDING.fetch_and(!(1 << n), Ordering::Relaxed);
+}
+
+/// Pends interrupt `n` of the application
+///
+/// Returns `false` if the application has no interrupt `n`.
+#[inline]
+pub fn swi_request(n: u16) -> bool {
+    if n as usize >= config().interrupts.len() {
+        return false;
+    }
+    pend(n);
+    true
+}
+
+/// Pends interrupt `n` of the application for the host, see [`swi_request`]
+#[no_mangle]
+pub extern "C" fn rtic_pend(n: u32) -> bool {
+    u16::try_from(n).is_ok_and(swi_request)
+}
@@ -0,0 +1,235 @@
+//! ESP32-S3 (Xtensa LX7), first core
+//!
+//! The priority of a CPU interrupt is fixed by its number, RTIC priorities `1..=3` are the CPU
+//! interrupt levels `1..=3`, the highest levels still handled in Rust. Each hardware task and
+//! dispatcher gets a CPU interrupt of its level, its source is routed to it through the
+//! interrupt matrix. `PS.INTLEVEL` masks the levels up to it, locks raise it to the ceiling.
+//!
+//! The registers are accessed by address, the PAC of the application provides the
+//! `Interrupt` enumeration of the sources.
+
+use core::arch::asm;
+use portable_atomic::{AtomicBool, Ordering};
+
+#[cfg(all(feature = "xtensa-esp32s3", not(feature = "xtensa-esp32s3-backend")))]
+compile_error!("Building for the esp32s3, but 'xtensa-esp32s3-backend' not selected");
+
+/// The highest logical priority, the highest interrupt level handled in Rust (`EXCMLEVEL`)
+pub const MAX_PRIORITY: u8 = 3;
+
+/// Base of the interrupt matrix of the first core, one map register per source
+const INTERRUPT_MAP_BASE: usize = 0x600c_2000;
+
+/// `SYSTEM_CPU_INTR_FROM_CPU_0_REG`, followed by the ones of the other three software sources
+const FROM_CPU_INTR_BASE: usize = 0x600c_0030;
+
+/// `PS.INTLEVEL`
+const PS_INTLEVEL: u32 = 0xf;
+
+/// The software interrupt sources used as dispatchers
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoftwareInterrupt {
+    FROM_CPU_INTR0 = 0,
+    FROM_CPU_INTR1 = 1,
+    FROM_CPU_INTR2 = 2,
+    FROM_CPU_INTR3 = 3,
+}
+
+/// Disabling and enabling all interrupts through `PS.INTLEVEL`
+pub mod interrupt {
+    /// Masks all interrupt levels
+    #[inline(always)]
+    pub fn disable() {
+        // SAFETY: only raises the interrupt level
+        unsafe { core::arch::asm!("rsil {0}, 15", out(reg) _) };
+    }
+
+    /// Unmasks all interrupt levels
+    ///
+    /// # Safety
+    ///
+    /// Interrupt handlers may run from here on, as in a critical section being left.
+    #[inline(always)]
+    pub unsafe fn enable() {
+        core::arch::asm!("rsil {0}, 0", out(reg) _);
+    }
+}
+
+#[inline(always)]
+fn read_ps() -> u32 {
+    let ps;
+    // SAFETY: reading `PS` has no side effects
+    unsafe { asm!("rsr.ps {0}", out(reg) ps) };
+    ps
+}
+
+#[inline(always)]
+unsafe fn write_ps(ps: u32) {
+    asm!("wsr.ps {0}", "rsync", in(reg) ps);
+}
+
+#[inline(always)]
+fn read_intenable() -> u32 {
+    let intenable;
+    // SAFETY: reading `INTENABLE` has no side effects
+    unsafe { asm!("rsr.intenable {0}", out(reg) intenable) };
+    intenable
+}
+
+/// The CPU interrupts that are pending and enabled, called by the level handlers of the app
+#[inline(always)]
+pub fn pending_cpu_interrupts() -> u32 {
+    let interrupt: u32;
+    // SAFETY: reading `INTERRUPT` has no side effects
+    unsafe { asm!("rsr.interrupt {0}", out(reg) interrupt) };
+    interrupt & read_intenable()
+}
+
+/// Read the stack pointer.
+#[inline(always)]
+pub fn read_sp() -> u32 {
+    let r;
+    unsafe { asm!("mov {0}, a1", out(reg) r, options(nomem, nostack, preserves_flags)) };
+    r
+}
+
+/// Orders memory accesses around a software interrupt request, see `riscv_common::swi_fence`
+#[inline(always)]
+fn swi_fence() {
+    unsafe { asm!("memw") };
+}
+
+/// A wake of `main` not yet seen by [`idle_wait`]
+static IDLE_WAKE: AtomicBool = AtomicBool::new(false);
+
+/// Wakes `main` from [`idle_wait`], called when a task of priority 0 is spawned or woken
+///
+/// Latched until the next [`idle_wait`] as the other backends do, see `riscv_common::wake_idle`.
+#[inline(always)]
+pub fn wake_idle() {
+    IDLE_WAKE.store(true, Ordering::SeqCst);
+}
+
+/// Sleeps in the zero priority dispatcher of `main` if no wake is latched and `is_idle` reports
+/// no task ready
+///
+/// The check runs with all levels masked, `WAITI 0` then unmasks them and sleeps in one
+/// instruction, so no handler can wake a task in between.
+#[inline(always)]
+pub fn idle_wait(is_idle: impl FnOnce() -> bool) {
+    interrupt::disable();
+
+    // No handler runs between the load and the store, no read-modify-write is needed
+    let woken = IDLE_WAKE.load(Ordering::SeqCst);
+    IDLE_WAKE.store(false, Ordering::SeqCst);
+
+    if !woken && is_idle() {
+        // SAFETY: `main` runs with all levels unmasked once `init` has returned
+        unsafe { asm!("waiti 0") };
+    } else {
+        // SAFETY: as above
+        unsafe { interrupt::enable() };
+    }
+}
+
+/// Routes `source` to CPU interrupt `cpu_int` and enables it, its level is the priority
+pub fn enable(source: u16, cpu_int: u8) {
+    unsafe {
+        (INTERRUPT_MAP_BASE as *mut u32)
+            .add(source as usize)
+            .write_volatile(cpu_int as u32);
+
+        let intenable = read_intenable() | 1 << cpu_int;
+        asm!("wsr.intenable {0}", "rsync", in(reg) intenable);
+    }
+}
+
+/// Whether `source` is still routed to CPU interrupt `cpu_int` and enabled, as set by [`enable`]
+pub fn is_enabled(source: u16, cpu_int: u8) -> bool {
+    let mapped = unsafe {
+        (INTERRUPT_MAP_BASE as *const u32)
+            .add(source as usize)
+            .read_volatile()
+    };
+
+    mapped == cpu_int as u32 && read_intenable() & 1 << cpu_int != 0
+}
+
+/// Runs the handler of a task
+///
+/// The vectors of the runtime enter the handler of a level at that `PS.INTLEVEL` and restore
+/// `PS` on return, there is nothing left to do.
+#[inline(always)]
+pub fn run<F>(_priority: u8, f: F)
+where
+    F: FnOnce(),
+{
+    f();
+}
+
+/// The logical priority of the running code, the ceiling of the held locks included
+///
+/// Read from `PS.INTLEVEL`. With interrupts disabled, e.g. in `init`, this is the highest
+/// priority.
+#[inline]
+pub fn current_priority() -> u8 {
+    ((read_ps() & PS_INTLEVEL) as u8).min(MAX_PRIORITY)
+}
+
+/// Lock implementation using `PS.INTLEVEL`
+///
+/// # Safety
+///
+/// The system ceiling is raised from current to ceiling by raising `PS.INTLEVEL` to the
+/// ceiling, if it is lower, which masks the interrupt levels up to it. An interrupt between the
+/// read and the write of `PS` restores it on return, and `PS` is restored as a whole once `f`
+/// returns.
+#[inline(always)]
+pub unsafe fn lock<T, R>(ptr: *mut T, ceiling: u8, f: impl FnOnce(&mut T) -> R) -> R {
+    let ps = read_ps();
+    if ps & PS_INTLEVEL < ceiling as u32 {
+        write_ps(ps & !PS_INTLEVEL | ceiling as u32);
+    }
+    let r = f(&mut *ptr);
+    write_ps(ps);
+    r
+}
+
+/// Sets the given software interrupt as pending
+#[inline(always)]
+pub fn pend(int: SoftwareInterrupt) {
+    swi_fence();
+    unsafe {
+        (FROM_CPU_INTR_BASE as *mut u32)
+            .add(int as usize)
+            .write_volatile(1)
+    };
+}
+
+/// Sets the given software interrupt as not pending
+#[inline(always)]
+pub fn unpend(int: SoftwareInterrupt) {
+    unsafe {
+        (FROM_CPU_INTR_BASE as *mut u32)
+            .add(int as usize)
+            .write_volatile(0)
+    };
+    swi_fence();
+}
+
+/// Requests the `FROM_CPU_INTR{n}` software interrupt
+///
+/// Returns `false` if `n` is not in `0..4`.
+#[inline]
+pub fn swi_request(n: u16) -> bool {
+    let int = match n {
+        0 => SoftwareInterrupt::FROM_CPU_INTR0,
+        1 => SoftwareInterrupt::FROM_CPU_INTR1,
+        2 => SoftwareInterrupt::FROM_CPU_INTR2,
+        3 => SoftwareInterrupt::FROM_CPU_INTR3,
+        _ => return false,
+    };
+    pend(int);
+    true
+}
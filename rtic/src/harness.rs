@@ -0,0 +1,139 @@
+//! On-target test harness of `#[app(test)]`
+//!
+//! In the test harness mode the application runs its `#[test_case]` software tasks one after
+//! the other instead of `idle`, so an application can be tested on the target, e.g. with
+//! probe-rs, without a separate crate structure:
+//!
+//! ```ignore
+//! #[rtic::app(device = hal::pac, dispatchers = [EXTI0], test)]
+//! mod app {
+//!     #[init]
+//!     fn init(_: init::Context) -> (Shared, Local) {
+//!         rtic::harness::set_reporter(|event| match event {
+//!             rtic::harness::Event::Start { name, .. } => defmt::println!("running {}", name),
+//!             rtic::harness::Event::Pass { .. } => {}
+//!             rtic::harness::Event::Finished { total } => {
+//!                 defmt::println!("all {} tests passed", total);
+//!                 cortex_m::asm::bkpt();
+//!             }
+//!         });
+//!
+//!         (Shared {}, Local {})
+//!     }
+//!
+//!     #[test_case]
+//!     #[task(priority = 1)]
+//!     async fn adds(_: adds::Context) {
+//!         defmt::assert_eq!(1 + 1, 2);
+//!     }
+//! }
+//! ```
+//!
+//! Each test case is also an entry point of its own: building with the environment variable
+//! `RTIC_TEST_CASE` set to the name of a test case, e.g. `RTIC_TEST_CASE=adds cargo run`, makes
+//! the harness run only that one, so a runner such as probe-rs can flash one test case at a time.
+//!
+//! A test case passes when it returns. A failing test case panics: the panic handler, e.g.
+//! `panic-probe`, reports the failure and ends the run.
+
+use portable_atomic::{AtomicPtr, Ordering};
+
+/// A `#[test_case]` task, generated by `#[app(test)]`
+#[doc(hidden)]
+pub struct TestCase {
+    /// The name of the task
+    pub name: &'static str,
+    /// Spawns the task, `false` if it is already running
+    pub spawn: fn() -> bool,
+    /// Whether the task has not completed yet
+    pub is_running: fn() -> bool,
+}
+
+/// The progress of the test run
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// Test case `index` of `total` is about to run
+    Start {
+        /// The name of the test case
+        name: &'static str,
+        /// The position of the test case, starting at `0`
+        index: usize,
+        /// The number of test cases
+        total: usize,
+    },
+    /// The test case completed
+    Pass {
+        /// The name of the test case
+        name: &'static str,
+    },
+    /// All test cases passed
+    Finished {
+        /// The number of test cases
+        total: usize,
+    },
+}
+
+static REPORTER: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Sets the function reporting the progress of the test run
+///
+/// Typically set in `init`. On [`Event::Finished`] it ends the run, e.g. with a breakpoint or a
+/// semihosting exit; otherwise the harness idles once all test cases passed.
+pub fn set_reporter(reporter: fn(Event)) {
+    REPORTER.store(reporter as *mut (), Ordering::Release);
+}
+
+fn report(event: Event) {
+    let reporter = REPORTER.load(Ordering::Acquire);
+    if !reporter.is_null() {
+        // SAFETY: only ever set from a `fn(Event)` in `set_reporter`
+        let reporter = unsafe { core::mem::transmute::<*mut (), fn(Event)>(reporter) };
+        reporter(event);
+    }
+}
+
+/// Runs the test cases in order, or only the `selected` one, used as `idle` by `#[app(test)]`
+#[doc(hidden)]
+pub fn run(cases: &[TestCase], selected: Option<&str>) -> ! {
+    let total = match selected {
+        Some(name) => {
+            assert!(
+                cases.iter().any(|case| case.name == name),
+                "no test case `{}`",
+                name
+            );
+            1
+        }
+        None => cases.len(),
+    };
+
+    let cases = cases
+        .iter()
+        .filter(|case| selected.is_none_or(|name| case.name == name));
+
+    for (index, case) in cases.enumerate() {
+        report(Event::Start {
+            name: case.name,
+            index,
+            total,
+        });
+
+        assert!(
+            (case.spawn)(),
+            "test case `{}` was spawned before the harness",
+            case.name
+        );
+
+        while (case.is_running)() {
+            core::hint::spin_loop();
+        }
+
+        report(Event::Pass { name: case.name });
+    }
+
+    report(Event::Finished { total });
+
+    loop {
+        core::hint::spin_loop();
+    }
+}
@@ -32,9 +32,16 @@
 )]
 #![allow(clippy::inline_always)]
 #![allow(unexpected_cfgs)]
+#![cfg_attr(
+    any(
+        all(feature = "xtensa-esp32s3", target_arch = "xtensa"),
+        all(feature = "avr", target_arch = "avr")
+    ),
+    feature(asm_experimental_arch)
+)]
 
 pub use rtic_core::{prelude as mutex_prelude, Exclusive, Mutex};
-pub use rtic_macros::app;
+pub use rtic_macros::{app, task_interface};
 
 /// module `mutex::prelude` provides `Mutex` and multi-lock variants. Recommended over `mutex_prelude`
 pub mod mutex {
@@ -42,11 +49,100 @@ pub mod mutex {
     pub use rtic_core::Mutex;
 }
 
+pub mod blocking;
+
+pub mod crash;
+
+pub mod dispatcher;
+
+pub mod dma;
+
+#[cfg(feature = "energy-stats")]
+pub mod energy;
+
+#[cfg(feature = "fault-injection")]
+pub use rtic_common::fault;
+
+pub mod harness;
+
+pub mod log;
+
+pub mod nesting;
+
+pub mod schedule;
+
 #[doc(hidden)]
 pub mod export;
 
+#[cfg(feature = "esp32c6-lp-core")]
+pub mod esp32c6_lp;
+
+#[cfg(feature = "nrf5340")]
+pub mod nrf5340;
+
+#[cfg(feature = "riscv-pmp")]
+pub mod pmp;
+
+#[cfg(feature = "rp2040")]
+pub mod rp2040;
+
+#[cfg(any(feature = "executor-stats", feature = "coalesce-wakes"))]
+pub mod stats;
+
+pub mod swi;
+
 pub use export::pend;
 
+/// The logical priority the calling code runs at
+///
+/// `0` for `idle`, the priority of the task within a task. Lets library code check that it
+/// runs at the priority it expects, e.g. to refuse to block above a certain priority.
+///
+/// | Backend              | Read from                                                    |
+/// |----------------------|--------------------------------------------------------------|
+/// | Cortex-M (`BASEPRI`) | active exception and its NVIC priority, `BASEPRI`, `PRIMASK` |
+/// | Cortex-M (masking)   | active exception and its NVIC priority, `PRIMASK`            |
+/// | ESP32-C3/C6          | `cpu_int_thresh`                                             |
+/// | ESP32-P4             | level in `mintstatus`, `mintthresh`, `mstatus.MIE`           |
+/// | RISC-V SLIC          | SLIC threshold                                               |
+/// | RISC-V soft SLIC     | software priority, `mstatus.MIE`                             |
+/// | RISC-V ECLIC         | level in `mintstatus`, the threshold, `mstatus.MIE`          |
+/// | ESP32-S3 (Xtensa)    | `PS.INTLEVEL`                                                |
+/// | RP2350 (Hazard3)     | IRQ in `meicontext` and its `meipra` priority, `mstatus.MIE` |
+/// | RISC-V PLIC          | PLIC threshold of the hart context, `mstatus.MIE`            |
+/// | WCH CH32 (PFIC)      | priority of the interrupts in `IACTR`, the threshold, `MIE`  |
+/// | AVR                  | software priority, `SREG.I`                                  |
+/// | Null (host)          | priority of the running handler, without lock ceilings       |
+/// | std (host)           | simulated priority of the thread owning the CPU, else `0`    |
+/// | wasm                 | software priority                                            |
+/// | Polling              | priority of the running dispatcher, `0` in the superloop     |
+///
+/// Within a lock the result includes the ceiling of the resource, except with source masking
+/// where the masked interrupts do not change the active priority. With interrupts disabled,
+/// e.g. in `init`, the Cortex-M, ECLIC, ESP32-S3, ESP32-P4, RP2350, PLIC, CH32, soft SLIC, std
+/// and wasm backends return the highest priority.
+#[inline]
+pub fn current_priority() -> u8 {
+    export::current_priority()
+}
+
+/// Why `set_priority` of the application refused to move a hardware task
+///
+/// Generated on the Cortex-M backends, the other ones keep the priorities of the tasks fixed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriorityError {
+    /// The interrupt is not bound to a hardware task of the application
+    Unbound,
+    /// The task accesses a `#[lock_free]` resource, which all its users access at one priority
+    Fixed,
+    /// The priority is `0`, above the ceiling of a shared resource of the task or above the
+    /// highest priority of the device, `max` being the highest the task can be moved to
+    OutOfRange {
+        /// The highest priority the task can be moved to
+        max: u8,
+    },
+}
+
 use core::cell::UnsafeCell;
 
 /// Internal replacement for `static mut T`
@@ -0,0 +1,239 @@
+//! Heap-free formatted logging
+//!
+//! [`log!`](crate::log!) formats its message into the scratch buffer of the priority it runs at,
+//! then copies the record into the log ring in a short critical section. Nothing allocates and
+//! nothing waits, so any task, `init` and `idle` can log. A record is dropped and counted in
+//! [`dropped`] when the ring is full, or when its scratch buffer is in use by the code it
+//! preempted, which only happens above the [`LEVELS`] buffers or with the null and std backends.
+//!
+//! Messages longer than [`RECORD_SIZE`] bytes are truncated. The text of the format string, without
+//! its placeholders, must fit in a record, which is checked at compile time.
+//!
+//! The records are read with [`drain`], typically in `idle` or a low priority task that sends them
+//! over a serial port or RTT.
+
+use core::cell::{RefCell, UnsafeCell};
+use core::fmt;
+use critical_section::Mutex;
+use portable_atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// The largest message of a record, in bytes
+pub const RECORD_SIZE: usize = 64;
+
+/// The number of records kept in the log ring until they are drained
+pub const CAPACITY: usize = 16;
+
+/// The number of scratch buffers, the priorities above `LEVELS - 1` share the last one
+pub const LEVELS: usize = 16;
+
+/// A logged message
+#[derive(Clone, Copy)]
+pub struct Record {
+    /// The source file and line of the `log!`
+    pub location: &'static str,
+    /// The priority the record was logged at, lock ceilings included
+    pub priority: u8,
+    /// Whether the message was cut at [`RECORD_SIZE`] bytes
+    pub truncated: bool,
+    len: usize,
+    bytes: [u8; RECORD_SIZE],
+}
+
+impl Record {
+    const EMPTY: Self = Self {
+        location: "",
+        priority: 0,
+        truncated: false,
+        len: 0,
+        bytes: [0; RECORD_SIZE],
+    };
+
+    /// The formatted message
+    pub fn message(&self) -> &str {
+        // SAFETY: `Cursor` only cuts the formatted text at character boundaries
+        unsafe { core::str::from_utf8_unchecked(&self.bytes[..self.len]) }
+    }
+}
+
+impl fmt::Debug for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Record")
+            .field("location", &self.location)
+            .field("priority", &self.priority)
+            .field("truncated", &self.truncated)
+            .field("message", &self.message())
+            .finish()
+    }
+}
+
+/// The formatted text of a message, cut at [`RECORD_SIZE`] bytes
+struct Cursor {
+    bytes: [u8; RECORD_SIZE],
+    len: usize,
+    truncated: bool,
+}
+
+impl fmt::Write for Cursor {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let room = RECORD_SIZE - self.len;
+        let mut n = s.len().min(room);
+        while !s.is_char_boundary(n) {
+            n -= 1;
+        }
+        self.bytes[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        self.truncated |= n < s.len();
+        Ok(())
+    }
+}
+
+/// The scratch buffer of a priority, `busy` while a message is formatted in it
+struct Scratch {
+    busy: AtomicBool,
+    cursor: UnsafeCell<Cursor>,
+}
+
+// SAFETY: `cursor` is only accessed by the holder of `busy`
+unsafe impl Sync for Scratch {}
+
+impl Scratch {
+    const fn new() -> Self {
+        Self {
+            busy: AtomicBool::new(false),
+            cursor: UnsafeCell::new(Cursor {
+                bytes: [0; RECORD_SIZE],
+                len: 0,
+                truncated: false,
+            }),
+        }
+    }
+}
+
+static SCRATCH: [Scratch; LEVELS] = [const { Scratch::new() }; LEVELS];
+
+struct Ring {
+    records: [Record; CAPACITY],
+    // Total number of records read and written, the next one goes to `written % CAPACITY`
+    read: u32,
+    written: u32,
+}
+
+static RING: Mutex<RefCell<Ring>> = Mutex::new(RefCell::new(Ring {
+    records: [Record::EMPTY; CAPACITY],
+    read: 0,
+    written: 0,
+}));
+
+static DROPPED: AtomicU32 = AtomicU32::new(0);
+
+/// Formats `args` and pushes the record into the log ring
+///
+/// Called by [`log!`](crate::log!), which checks the format string at compile time.
+pub fn write(location: &'static str, args: fmt::Arguments<'_>) {
+    let priority = crate::current_priority();
+    let scratch = &SCRATCH[(priority as usize).min(LEVELS - 1)];
+    if scratch.busy.swap(true, Ordering::Acquire) {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+
+    // SAFETY: `busy` is held until the record is copied
+    let cursor = unsafe { &mut *scratch.cursor.get() };
+    cursor.len = 0;
+    cursor.truncated = false;
+    let _ = fmt::write(cursor, args);
+
+    let pushed = critical_section::with(|cs| {
+        let mut ring = RING.borrow_ref_mut(cs);
+        if ring.written.wrapping_sub(ring.read) as usize == CAPACITY {
+            return false;
+        }
+
+        let idx = ring.written as usize % CAPACITY;
+        let record = &mut ring.records[idx];
+        record.location = location;
+        record.priority = priority;
+        record.truncated = cursor.truncated;
+        record.len = cursor.len;
+        record.bytes[..cursor.len].copy_from_slice(&cursor.bytes[..cursor.len]);
+        ring.written = ring.written.wrapping_add(1);
+        true
+    });
+    scratch.busy.store(false, Ordering::Release);
+
+    if !pushed {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Calls `f` with the records in the log ring, the oldest first, and removes them
+///
+/// The records are taken one at a time, `f` runs outside of the critical section.
+pub fn drain(mut f: impl FnMut(&Record)) {
+    while let Some(record) = critical_section::with(|cs| {
+        let mut ring = RING.borrow_ref_mut(cs);
+        if ring.read == ring.written {
+            return None;
+        }
+
+        let record = ring.records[ring.read as usize % CAPACITY];
+        ring.read = ring.read.wrapping_add(1);
+        Some(record)
+    }) {
+        f(&record);
+    }
+}
+
+/// The number of records dropped, because the ring was full or the scratch buffer in use
+pub fn dropped() -> u32 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// The length of the text of format string `fmt`, without its placeholders
+///
+/// Used by [`log!`](crate::log!) to check at compile time that the text fits in a record.
+pub const fn text_len(fmt: &str) -> usize {
+    let bytes = fmt.as_bytes();
+    let mut len = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if (bytes[i] == b'{' || bytes[i] == b'}') && i + 1 < bytes.len() && bytes[i + 1] == bytes[i]
+        {
+            // `{{` and `}}` are a single brace
+            len += 1;
+            i += 2;
+        } else if bytes[i] == b'{' {
+            while i < bytes.len() && bytes[i] != b'}' {
+                i += 1;
+            }
+            i += 1;
+        } else {
+            len += 1;
+            i += 1;
+        }
+    }
+    len
+}
+
+/// Logs a formatted message into the log ring, without blocking or allocating
+///
+/// Takes the arguments of [`format_args!`], with a string literal as the format string. The text of
+/// the format string must fit in [`RECORD_SIZE`](crate::log::RECORD_SIZE) bytes, the formatted
+/// message is truncated to it. See the [`log`](mod@crate::log) module.
+///
+/// ```ignore
+/// rtic::log!("adc {}: {} mV", channel, millivolts);
+/// ```
+#[macro_export]
+macro_rules! log {
+    ($fmt:literal $(, $arg:expr)* $(,)?) => {{
+        const _: () = ::core::assert!(
+            $crate::log::text_len($fmt) <= $crate::log::RECORD_SIZE,
+            "the text of the format string does not fit in a log record"
+        );
+        $crate::log::write(
+            ::core::concat!(::core::file!(), ":", ::core::line!()),
+            ::core::format_args!($fmt $(, $arg)*),
+        );
+    }};
+}
@@ -0,0 +1,76 @@
+//! Interrupt nesting depth limit
+//!
+//! Every level of preemption adds the frame of a handler to the main stack, so a priority
+//! configuration nesting deeper than budgeted overflows the stack long before it shows up as a
+//! scheduling problem. The `max_nesting = N` argument of `#[app]` tracks the nesting depth of the
+//! hardware tasks and dispatchers and fails when a handler is entered at a depth above `N`.
+//!
+//! The failure calls the hook registered with [`set_hook`], or panics if there is none. The
+//! deepest nesting seen so far is available from [`max_depth`] to tune the budget. Tracking costs
+//! two atomic updates per handler and is meant for debugging: applications without `max_nesting`
+//! do not track the depth and [`depth`] stays 0.
+
+use portable_atomic::{AtomicPtr, AtomicU8, Ordering};
+
+static DEPTH: AtomicU8 = AtomicU8::new(0);
+static MAX_DEPTH: AtomicU8 = AtomicU8::new(0);
+
+// No limit until `init` returns
+static LIMIT: AtomicU8 = AtomicU8::new(u8::MAX);
+
+static HOOK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Registers `hook` to run instead of a panic when the nesting depth exceeds the limit
+///
+/// The hook gets the depth of the handler being entered. If it returns the handler runs as usual.
+pub fn set_hook(hook: fn(u8)) {
+    HOOK.store(hook as *mut (), Ordering::Release);
+}
+
+/// Sets the maximum nesting depth, done by the `#[app]` after `init`
+#[doc(hidden)]
+#[inline]
+pub fn set_limit(limit: u8) {
+    LIMIT.store(limit, Ordering::Relaxed);
+}
+
+/// The nesting depth of the running code, 0 in `init` and `idle`
+#[inline]
+pub fn depth() -> u8 {
+    DEPTH.load(Ordering::Relaxed)
+}
+
+/// The deepest nesting seen since reset
+#[inline]
+pub fn max_depth() -> u8 {
+    MAX_DEPTH.load(Ordering::Relaxed)
+}
+
+/// Records the entry of a handler, called by the `#[app]` before running a task
+#[doc(hidden)]
+#[inline]
+pub fn enter() {
+    // A preempting handler always returns before the one it preempted resumes, so the depth seen
+    // by each handler is stable for its whole run.
+    let depth = DEPTH.fetch_add(1, Ordering::Relaxed) + 1;
+    MAX_DEPTH.fetch_max(depth, Ordering::Relaxed);
+
+    let limit = LIMIT.load(Ordering::Relaxed);
+    if depth > limit {
+        let hook = HOOK.load(Ordering::Acquire);
+        if hook.is_null() {
+            panic!("interrupt nesting depth {depth} exceeds `max_nesting = {limit}`");
+        }
+
+        // SAFETY: only `set_hook` stores to `HOOK`, from a `fn(u8)`
+        let hook: fn(u8) = unsafe { core::mem::transmute::<*mut (), fn(u8)>(hook) };
+        hook(depth);
+    }
+}
+
+/// Records the exit of a handler, called by the `#[app]` after running a task
+#[doc(hidden)]
+#[inline]
+pub fn exit() {
+    DEPTH.fetch_sub(1, Ordering::Relaxed);
+}
@@ -0,0 +1,135 @@
+//! nRF5340 dual core support
+//!
+//! With the `thumbv8main-nrf5340-backend` each Cortex-M33 core of the nRF5340, the application
+//! core and the network core, runs its own application, from its own image. Both applications
+//! are usually written in the same crate, the one of the network core is declared with
+//! `backend = [core = 1]`:
+//!
+//! ```ignore
+//! #[rtic::app(device = nrf5340_app_pac, dispatchers = [EGU0])]
+//! mod app_core {
+//!     // ..
+//! }
+//!
+//! #[rtic::app(device = nrf5340_net_pac, dispatchers = [EGU0], backend = [core = 1])]
+//! mod net_core {
+//!     // ..
+//! }
+//! ```
+//!
+//! The `app` macro places each application behind `#[cfg(rtic_core = "N")]`, so the crate is
+//! built once per core, e.g. with `--cfg rtic_core="0"` for `thumbv8m.main-none-eabihf` and the
+//! memory layout of the application core, and with `--cfg rtic_core="1"` for
+//! `thumbv8m.main-none-eabi` and the one of the network core. The network core is released from
+//! reset by the application core, e.g. through `RESET.NETWORK.FORCEOFF` of the HAL.
+//!
+//! # Cross-core spawns
+//!
+//! A software task declared with `ipc = N` is spawned from the other core through channel `N`,
+//! `0..=15`, of the IPC peripheral. The `app` macro binds the `IPC` interrupt of the core to a
+//! hardware task that spawns the tasks whose channel was signalled, at the highest priority of
+//! these tasks, and enables the channels at the start of `init`. The other core signals a channel
+//! with the generated `spawn_remote(N)`, or with [`Ipc::send`]:
+//!
+//! ```ignore
+//! // Network core
+//! #[task(priority = 1, ipc = 3)]
+//! async fn radio_on(_: radio_on::Context) {
+//!     // ..
+//! }
+//!
+//! // Application core
+//! spawn_remote(3);
+//! ```
+//!
+//! A cross-core spawn carries no payload: the data are passed through memory both cores reach,
+//! e.g. the RAM of the application core. All memory writes made before the spawn are visible to
+//! the spawned task. Signals for a task still running are coalesced, as for a spawn that fails.
+
+#[cfg(not(feature = "cortex-m-basepri"))]
+compile_error!("The nRF5340 IPC needs the 'thumbv8main-nrf5340-backend'");
+
+use cortex_m::asm;
+
+// Register offsets of the IPC peripheral
+const TASKS_SEND: usize = 0x000;
+const EVENTS_RECEIVE: usize = 0x100;
+const INTENSET: usize = 0x304;
+const SEND_CNF: usize = 0x510;
+const RECEIVE_CNF: usize = 0x590;
+
+/// The number of IPC channels
+pub const CHANNELS: u8 = 16;
+
+/// The IPC peripheral of a core
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipc {
+    base: usize,
+}
+
+impl Ipc {
+    /// The IPC of the application core, at its secure address
+    pub const APP: Ipc = Ipc { base: 0x5002_a000 };
+
+    /// The IPC of the application core, at its non-secure address
+    pub const APP_NONSECURE: Ipc = Ipc { base: 0x4002_a000 };
+
+    /// The IPC of the network core
+    pub const NET: Ipc = Ipc { base: 0x4101_2000 };
+
+    #[inline(always)]
+    fn register(self, offset: usize, channel: u8) -> *mut u32 {
+        (self.base + offset + 4 * channel as usize) as *mut u32
+    }
+
+    /// Receives on the `channels`, bit `n` for channel `n`, and enables their interrupts
+    ///
+    /// Receive event `n` is raised by channel `n` only.
+    #[inline]
+    pub fn listen(self, channels: u16) {
+        for channel in (0..CHANNELS).filter(|channel| channels & 1 << channel != 0) {
+            // SAFETY: the receive configuration of a channel is only written by its core
+            unsafe {
+                self.register(RECEIVE_CNF, channel)
+                    .write_volatile(1 << channel)
+            };
+        }
+
+        // SAFETY: `INTENSET` only enables the written bits
+        unsafe { self.register(INTENSET, 0).write_volatile(channels as u32) };
+    }
+
+    /// Clears the receive event of `channel`, returns `true` if it was raised
+    #[inline]
+    pub fn take(self, channel: u8) -> bool {
+        let event = self.register(EVENTS_RECEIVE, channel);
+
+        // SAFETY: the receive events are only cleared by the core receiving them
+        unsafe {
+            if event.read_volatile() == 0 {
+                return false;
+            }
+            event.write_volatile(0);
+        }
+
+        // Accesses to the data of the spawn stay after the event
+        asm::dmb();
+        true
+    }
+
+    /// Signals `channel` to the other core, which spawns its task with `ipc = channel`
+    #[inline]
+    pub fn send(self, channel: u8) {
+        assert!(channel < CHANNELS, "the IPC has channels 0 to 15");
+
+        // Make the data of the spawn visible before the signal
+        asm::dmb();
+
+        // SAFETY: send task `n` is only configured and triggered for channel `n`
+        unsafe {
+            self.register(SEND_CNF, channel)
+                .write_volatile(1 << channel);
+            self.register(TASKS_SEND, channel).write_volatile(1);
+        }
+    }
+}
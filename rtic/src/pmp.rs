@@ -0,0 +1,304 @@
+//! RISC-V PMP isolation of software tasks
+//!
+//! With the `riscv-pmp` feature, a software task declared with `isolated = N` is polled by its
+//! dispatcher in user mode, on a stack of its own of `N` bytes, with the PMP granting access to
+//! this stack, the executor of the task and its local resources, and read and execute access to
+//! the code set with [`set_code`]. A memory access outside of these, or any other exception of
+//! the task, ends the poll: the task is marked faulted and never polled again, its spawns fail,
+//! and the rest of the application keeps running. This contains vendor blobs and experimental
+//! code without a separate image:
+//!
+//! ```ignore
+//! #[task(priority = 1, isolated = 1024, local = [state: State = State::new()])]
+//! async fn blob(cx: blob::Context) {
+//!     vendor::step(cx.local.state);
+//! }
+//! ```
+//!
+//! The exceptions of user mode are taken by the exception handler of the runtime, which must call
+//! [`exception`] first, e.g. with `riscv-rt`:
+//!
+//! ```ignore
+//! #[export_name = "ExceptionHandler"]
+//! fn exception_handler(_: &riscv_rt::TrapFrame) -> ! {
+//!     unsafe { rtic::pmp::exception() };
+//!     panic!("exception in machine mode");
+//! }
+//! ```
+//!
+//! An isolated task has no shared resources. Within a poll it can not spawn tasks, wake futures
+//! or access peripherals, e.g. a monotonic: these are memory accesses outside of its regions and
+//! end it as a fault. It is woken from the outside, e.g. by spawning it again. Interrupts
+//! preempting the task run on its stack, which is sized for them too, and a task that does not
+//! return from a poll is not contained. The PMP regions are rounded to 4 bytes, and the core needs
+//! user mode, 16 PMP entries and the A extension, e.g. the ESP32-C3 and ESP32-C6.
+
+#[cfg(not(feature = "riscv"))]
+compile_error!("The PMP isolation of tasks needs a RISC-V backend");
+
+use core::{
+    arch::{asm, global_asm},
+    cell::UnsafeCell,
+    ptr,
+};
+use portable_atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+/// The PMP entries of the core, two per region
+const ENTRIES: usize = 16;
+
+// `pmpcfg` fields
+const TOR: u8 = 0b01 << 3;
+const R: u8 = 1 << 0;
+const W: u8 = 1 << 1;
+const X: u8 = 1 << 2;
+
+// `mcause` of an environment call from user mode, the end of a poll
+const ECALL_FROM_U: usize = 8;
+
+/// A range of memory the task accesses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    start: usize,
+    end: usize,
+}
+
+impl Region {
+    /// The memory from `start` up to `end`
+    pub const fn new(start: usize, end: usize) -> Self {
+        Region { start, end }
+    }
+
+    /// The memory of `value`
+    #[inline]
+    pub fn of<T>(value: &T) -> Self {
+        let start = value as *const T as usize;
+        Region {
+            start,
+            end: start + core::mem::size_of::<T>(),
+        }
+    }
+}
+
+static CODE_START: AtomicUsize = AtomicUsize::new(0);
+static CODE_END: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the code and read-only data isolated tasks can execute and read, e.g. the flash
+///
+/// Called in `init`, before the isolated tasks are spawned. Without it their first poll faults.
+pub fn set_code(code: Region) {
+    CODE_START.store(code.start, Ordering::Relaxed);
+    CODE_END.store(code.end, Ordering::Relaxed);
+}
+
+/// The registers saved when entering user mode and the PMP configuration of a poll, one per
+/// isolated task being polled, the innermost is `ACTIVE`
+#[repr(C)]
+struct Frame {
+    // `ra`, `sp`, `s0` to `s11`, saved and restored by `rtic_pmp_enter` and `rtic_pmp_leave`
+    #[allow(dead_code)]
+    registers: [usize; 14],
+    cfg: [u8; ENTRIES],
+    addr: [usize; ENTRIES],
+    previous: *mut Frame,
+}
+
+impl Frame {
+    fn configure(&mut self, regions: &[(Region, u8)]) {
+        for (i, (region, permissions)) in regions.iter().enumerate() {
+            // The first entry of a pair only bounds the second, which is top of range
+            self.addr[2 * i] = region.start >> 2;
+            self.addr[2 * i + 1] = (region.end + 3) >> 2;
+            self.cfg[2 * i + 1] = TOR | permissions;
+        }
+    }
+
+    /// Writes the PMP entries, an unlocked entry only restricts user mode
+    unsafe fn apply(&self) {
+        macro_rules! write_addr {
+            ($($i:literal),*) => {
+                $(asm!(concat!("csrw pmpaddr", $i, ", {0}"), in(reg) self.addr[$i]);)*
+            };
+        }
+        write_addr!(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+
+        let cfg =
+            |i: usize| u32::from_le_bytes(self.cfg[4 * i..4 * i + 4].try_into().unwrap()) as usize;
+        asm!("csrw pmpcfg0, {0}", in(reg) cfg(0));
+        asm!("csrw pmpcfg1, {0}", in(reg) cfg(1));
+        asm!("csrw pmpcfg2, {0}", in(reg) cfg(2));
+        asm!("csrw pmpcfg3, {0}", in(reg) cfg(3));
+    }
+}
+
+static ACTIVE: AtomicPtr<Frame> = AtomicPtr::new(ptr::null_mut());
+
+extern "C" {
+    /// Saves the registers to `frame`, then runs `entry` in user mode on `stack`; returns `0`
+    /// once `entry` returned, `1` after a fault
+    fn rtic_pmp_enter(entry: extern "C" fn(), stack: *mut u8, frame: *mut Frame) -> usize;
+
+    /// Restores the registers of `frame`, returning `result` from its `rtic_pmp_enter`
+    fn rtic_pmp_leave(frame: *mut Frame, result: usize) -> !;
+}
+
+global_asm!(
+    ".section .text.rtic_pmp_enter, \"ax\"",
+    ".global rtic_pmp_enter",
+    ".align 2",
+    "rtic_pmp_enter:",
+    "    sw ra, 0(a2)",
+    "    sw sp, 4(a2)",
+    "    sw s0, 8(a2)",
+    "    sw s1, 12(a2)",
+    "    sw s2, 16(a2)",
+    "    sw s3, 20(a2)",
+    "    sw s4, 24(a2)",
+    "    sw s5, 28(a2)",
+    "    sw s6, 32(a2)",
+    "    sw s7, 36(a2)",
+    "    sw s8, 40(a2)",
+    "    sw s9, 44(a2)",
+    "    sw s10, 48(a2)",
+    "    sw s11, 52(a2)",
+    // `mret` to the trampoline in user mode, `MPP = 0`, with interrupts enabled
+    "    la t0, rtic_pmp_trampoline",
+    "    csrw mepc, t0",
+    "    li t0, 0x1800",
+    "    csrc mstatus, t0",
+    "    li t0, 0x80",
+    "    csrs mstatus, t0",
+    "    mv sp, a1",
+    "    mret",
+    "rtic_pmp_trampoline:",
+    "    jalr a0",
+    "    ecall",
+    "    j rtic_pmp_trampoline",
+    ".global rtic_pmp_leave",
+    ".align 2",
+    "rtic_pmp_leave:",
+    "    lw ra, 0(a0)",
+    "    lw sp, 4(a0)",
+    "    lw s0, 8(a0)",
+    "    lw s1, 12(a0)",
+    "    lw s2, 16(a0)",
+    "    lw s3, 20(a0)",
+    "    lw s4, 24(a0)",
+    "    lw s5, 28(a0)",
+    "    lw s6, 32(a0)",
+    "    lw s7, 36(a0)",
+    "    lw s8, 40(a0)",
+    "    lw s9, 44(a0)",
+    "    lw s10, 48(a0)",
+    "    lw s11, 52(a0)",
+    "    mv a0, a1",
+    "    ret",
+);
+
+/// Ends the poll of the isolated task that raised the exception being handled
+///
+/// Called first by the exception handler of the runtime. Returns if the exception was not raised
+/// in user mode by an isolated task, for the handler to deal with it.
+///
+/// # Safety
+///
+/// Only to be called from the exception handler, with `mcause` and `mstatus` of the exception.
+pub unsafe fn exception() {
+    let frame = ACTIVE.load(Ordering::Relaxed);
+    let mstatus: usize;
+    let mcause: usize;
+    asm!("csrr {0}, mstatus", out(reg) mstatus);
+    asm!("csrr {0}, mcause", out(reg) mcause);
+
+    // `MPP = 0`: the exception was raised in user mode, `mcause < 0` is an interrupt
+    if frame.is_null() || mstatus & 0x1800 != 0 || (mcause as isize) < 0 {
+        return;
+    }
+
+    rtic_pmp_leave(frame, (mcause != ECALL_FROM_U) as usize);
+}
+
+/// The stack of an isolated task and whether it faulted
+#[repr(C, align(16))]
+pub struct Task<const N: usize> {
+    stack: UnsafeCell<[u8; N]>,
+    faulted: AtomicBool,
+}
+
+// SAFETY: the stack is only used by the polls of the task, which its dispatcher runs one at a
+// time
+unsafe impl<const N: usize> Sync for Task<N> {}
+
+impl<const N: usize> Default for Task<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Task<N> {
+    /// A task that has not faulted
+    pub const fn new() -> Self {
+        Task {
+            stack: UnsafeCell::new([0; N]),
+            faulted: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether a poll of the task faulted, it is not polled anymore
+    #[inline]
+    pub fn faulted(&self) -> bool {
+        self.faulted.load(Ordering::Relaxed)
+    }
+
+    /// Polls the task with `entry` in user mode, with access to its stack and `regions` only
+    ///
+    /// # Safety
+    ///
+    /// Only called by the dispatcher of the task, `entry` only polls it.
+    pub unsafe fn run(&self, regions: &[Region], entry: extern "C" fn()) {
+        if self.faulted() {
+            return;
+        }
+
+        let stack = Region::of(&*self.stack.get());
+        let code = Region::new(
+            CODE_START.load(Ordering::Relaxed),
+            CODE_END.load(Ordering::Relaxed),
+        );
+
+        let mut frame = Frame {
+            registers: [0; 14],
+            cfg: [0; ENTRIES],
+            addr: [0; ENTRIES],
+            previous: ACTIVE.load(Ordering::Relaxed),
+        };
+        let mut granted = [(Region::new(0, 0), 0); ENTRIES / 2];
+        granted[0] = (code, R | X);
+        granted[1] = (stack, R | W);
+        for (grant, region) in granted[2..].iter_mut().zip(regions) {
+            *grant = (*region, R | W);
+        }
+        frame.configure(&granted[..(2 + regions.len()).min(ENTRIES / 2)]);
+
+        // The trap of the dispatcher returns with these
+        let mepc: usize;
+        let mstatus: usize;
+        asm!("csrr {0}, mepc", out(reg) mepc);
+        asm!("csrr {0}, mstatus", out(reg) mstatus);
+
+        frame.apply();
+        ACTIVE.store(&mut frame, Ordering::Relaxed);
+        let faulted = rtic_pmp_enter(entry, stack.end as *mut u8, &mut frame) != 0;
+        ACTIVE.store(frame.previous, Ordering::Relaxed);
+
+        // A preempted isolated task gets its regions back
+        if let Some(previous) = frame.previous.as_ref() {
+            previous.apply();
+        }
+        asm!("csrw mepc, {0}", in(reg) mepc);
+        asm!("csrw mstatus, {0}", in(reg) mstatus);
+
+        if faulted {
+            self.faulted.store(true, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! RP2040 dual core support
+//!
+//! With the `thumbv6-rp2040-backend` each core runs its own application, the one of core 1 is
+//! declared with `backend = [core = 1]` and its `main` is launched from core 0. Locks mask the
+//! sources on the NVIC of their own core, the cores share data through [`CoreMutex`] and pend the
+//! dispatchers of each other through the doorbell.
+//!
+//! ```ignore
+//! #[rtic::app(device = rp2040_pac, dispatchers = [SW0_IRQ])]
+//! mod app0 {
+//!     #[init]
+//!     fn init(cx: init::Context) -> (Shared, Local) {
+//!         // Launch `app1::main` on core 1, e.g. with the `multicore` module of the HAL
+//!         // ..
+//!     }
+//! }
+//!
+//! #[rtic::app(device = rp2040_pac, dispatchers = [SW1_IRQ], backend = [core = 1])]
+//! mod app1 {
+//!     // ..
+//! }
+//! ```
+//!
+//! The two cores share the vector table, so each interrupt is bound by the application of one
+//! core only, and the dispatchers of the cores differ. The statics the HALs look up by name are
+//! provided by the application of core 0.
+//!
+//! # Doorbell
+//!
+//! Each core of the RP2040 has its own NVIC, so one core cannot pend a dispatcher of the other.
+//! The SIO inter-core FIFOs are used as a doorbell instead: the sending core writes the number of
+//! a dispatcher interrupt of the other core into its TX FIFO, which raises `SIO_IRQ_PROCn` on the
+//! receiving core. A hardware task bound to that interrupt calls [`drain`], which pends every
+//! dispatcher it finds in the FIFO on the local NVIC.
+//!
+//! ```ignore
+//! // Core 1
+//! #[task(binds = SIO_IRQ_PROC1, priority = 3)]
+//! fn doorbell(_: doorbell::Context) {
+//!     rtic::rp2040::drain();
+//! }
+//! ```
+//!
+//! # Draining
+//!
+//! The FIFO interrupt is level triggered on `FIFO_ST.VLD`, so [`drain`] empties the FIFO
+//! completely before returning. Rings received while draining are handled in the same pass and
+//! rings for the same dispatcher coalesce into a single pend. [`drain`] also clears the sticky
+//! `WOF` and `ROE` flags, which would otherwise keep the interrupt asserted.
+//!
+//! The FIFOs are 8 words deep per direction. [`ring`] does not block: it returns [`Full`] when
+//! the receiving core has not drained for 8 rings, typically because its doorbell task is
+//! masked by a lock, and the sender decides whether to retry or drop the ring.
+//!
+//! The doorbell is the transport for cross-core spawns, the payloads themselves are passed
+//! through memory shared between the cores. All memory writes made before [`ring`] are visible to
+//! the receiving core once its dispatcher runs.
+//!
+//! # Cross-core spawns
+//!
+//! A software task declared with `cross_core = N` is spawned with its usual `spawn` from either
+//! core. From the other core the payload is queued for the task, up to `N` of them, and the
+//! doorbell rung with the dispatcher of the task, which spawns it with the queued payloads one
+//! after the other. The `spawn` fails, returning the payload, when the queue or the FIFO is full.
+//!
+//! ```ignore
+//! // Core 1
+//! #[task(core = 1, priority = 1, cross_core = 4)]
+//! async fn consume(_: consume::Context, sample: u32) {
+//!     // ..
+//! }
+//!
+//! // Core 0
+//! #[task(priority = 1)]
+//! async fn produce(_: produce::Context) {
+//!     core1::consume::spawn(42).ok();
+//! }
+//! ```
+//!
+//! An application with such tasks drains its doorbell from a generated hardware task bound to
+//! `SIO_IRQ_PROCn`, unless it binds that interrupt itself. Spawn to the other core only once it
+//! runs its application, the launch of core 1 goes through the FIFO as well.
+
+#[cfg(not(feature = "cortex-m-source-masking"))]
+compile_error!("The RP2040 doorbell needs the 'thumbv6-rp2040-backend'");
+
+use core::cell::UnsafeCell;
+use cortex_m::{asm, interrupt::InterruptNumber, peripheral::NVIC};
+
+const SIO_BASE: usize = 0xd000_0000;
+const CPUID: *const u32 = SIO_BASE as *const u32;
+const FIFO_ST: *mut u32 = (SIO_BASE + 0x50) as *mut u32;
+const FIFO_WR: *mut u32 = (SIO_BASE + 0x54) as *mut u32;
+const FIFO_RD: *const u32 = (SIO_BASE + 0x58) as *const u32;
+const SPINLOCK_BASE: usize = SIO_BASE + 0x100;
+
+// `FIFO_ST` bits
+const VLD: u32 = 1 << 0;
+const RDY: u32 = 1 << 1;
+
+/// Interrupt number of the doorbell of core 0
+pub const SIO_IRQ_PROC0: u16 = 15;
+
+/// Interrupt number of the doorbell of core 1
+pub const SIO_IRQ_PROC1: u16 = 16;
+
+/// The TX FIFO to the other core is full, the ring was not sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Full;
+
+#[derive(Clone, Copy)]
+struct Irq(u16);
+
+// SAFETY: Only constructed from interrupt numbers received from the other core, which rings with
+// the numbers of dispatchers of this core.
+unsafe impl InterruptNumber for Irq {
+    fn number(self) -> u16 {
+        self.0
+    }
+}
+
+/// Rings the doorbell of the other core to pend its dispatcher `interrupt`
+///
+/// `interrupt` is the device interrupt number of a dispatcher of the *other* core.
+#[inline]
+pub fn ring(interrupt: impl InterruptNumber) -> Result<(), Full> {
+    // SAFETY: SIO registers are core-local and always accessible, the TX FIFO is only written by
+    // this core.
+    unsafe {
+        if FIFO_ST.read_volatile() & RDY == 0 {
+            return Err(Full);
+        }
+
+        // Make the payloads written for the spawn visible before the ring.
+        asm::dmb();
+        FIFO_WR.write_volatile(interrupt.number() as u32);
+    }
+
+    // Wake the other core if it sleeps in `wfe`.
+    asm::sev();
+    Ok(())
+}
+
+/// Runs `enqueue` with `payload` and rings the doorbell of the other core to pend its dispatcher
+/// `interrupt`, returning `payload` without running `enqueue` if the TX FIFO is full
+///
+/// The interrupts of this core are masked throughout, and only this core writes its TX FIFO, so
+/// the room found before `enqueue` is still there for the ring: a queued payload is never left
+/// without a ring.
+#[inline]
+pub fn ring_with<T>(
+    interrupt: impl InterruptNumber,
+    payload: T,
+    enqueue: impl FnOnce(T) -> Result<(), T>,
+) -> Result<(), T> {
+    cortex_m::interrupt::free(|_| {
+        // SAFETY: SIO registers are core-local and always accessible
+        if unsafe { FIFO_ST.read_volatile() } & RDY == 0 {
+            return Err(payload);
+        }
+
+        enqueue(payload)?;
+        let rung = ring(interrupt);
+        debug_assert!(rung.is_ok());
+        Ok(())
+    })
+}
+
+/// The core running the caller, 0 or 1
+#[inline]
+pub fn core() -> u8 {
+    // SAFETY: SIO registers are core-local and always accessible
+    unsafe { CPUID.read_volatile() as u8 }
+}
+
+/// Pends the dispatchers rung by the other core until the RX FIFO is empty
+///
+/// Returns the number of rings received. Call it from the hardware task bound to the
+/// `SIO_IRQ_PROCn` of this core.
+#[inline]
+pub fn drain() -> usize {
+    let mut rings = 0;
+
+    // SAFETY: SIO registers are core-local and always accessible, the RX FIFO is only read by
+    // this core.
+    unsafe {
+        while FIFO_ST.read_volatile() & VLD != 0 {
+            let interrupt = FIFO_RD.read_volatile() as u16;
+            NVIC::pend(Irq(interrupt));
+            rings += 1;
+        }
+
+        // Any write clears the sticky `WOF` and `ROE` flags.
+        FIFO_ST.write_volatile(0);
+    }
+
+    rings
+}
+
+/// SIO hardware spinlock `N`, shared by both cores
+///
+/// Spinlock 31 is taken by the `critical-section` implementation of the HAL and can not be used.
+pub struct Spinlock<const N: usize>;
+
+impl<const N: usize> Spinlock<N> {
+    const VALID: () = assert!(N < 31, "the spinlocks are 0 to 30, 31 is used by the HAL");
+
+    const ADDR: *mut u32 = (SPINLOCK_BASE + 4 * N) as *mut u32;
+
+    /// Claims the spinlock if it is free, returns `true` if claimed
+    #[inline]
+    pub fn try_claim() -> bool {
+        let () = Self::VALID;
+
+        // SAFETY: reading a spinlock register claims it if it is free
+        let claimed = unsafe { Self::ADDR.read_volatile() } != 0;
+        if claimed {
+            // Accesses to the guarded data stay after the claim
+            asm::dmb();
+        }
+        claimed
+    }
+
+    /// Claims the spinlock, waiting for the other core to release it
+    #[inline]
+    pub fn claim() {
+        while !Self::try_claim() {}
+    }
+
+    /// Releases the spinlock
+    ///
+    /// # Safety
+    ///
+    /// The spinlock must have been claimed by this core.
+    #[inline]
+    pub unsafe fn release() {
+        // Accesses to the guarded data stay before the release
+        asm::dmb();
+        Self::ADDR.write_volatile(1);
+    }
+}
+
+/// Data shared between the cores, guarded by the [`Spinlock`] `N`
+///
+/// A lock masks all interrupts of its core while it holds the spinlock, so a task of the same
+/// core can not preempt it and spin on the spinlock forever. The other core waits for the lock
+/// to be released, keep the closures short.
+///
+/// ```ignore
+/// static SAMPLES: CoreMutex<u32, 0> = CoreMutex::new(0);
+///
+/// // On either core
+/// SAMPLES.lock(|samples| *samples += 1);
+/// ```
+pub struct CoreMutex<T, const N: usize> {
+    data: UnsafeCell<T>,
+}
+
+// SAFETY: The data is only accessed with the spinlock claimed
+unsafe impl<T: Send, const N: usize> Sync for CoreMutex<T, N> {}
+
+impl<T, const N: usize> CoreMutex<T, N> {
+    /// Creates a new mutex guarded by spinlock `N`
+    pub const fn new(value: T) -> Self {
+        Self {
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    /// Runs `f` with exclusive access to the data, from either core
+    #[inline]
+    pub fn lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        cortex_m::interrupt::free(|_| {
+            Spinlock::<N>::claim();
+            // SAFETY: the spinlock is held and the interrupts of this core are masked
+            let r = f(unsafe { &mut *self.data.get() });
+            // SAFETY: claimed above
+            unsafe { Spinlock::<N>::release() };
+            r
+        })
+    }
+}
@@ -0,0 +1,24 @@
+//! Time-triggered schedule
+//!
+//! The `schedule = (cycle = .., table = [(offset, task), ..])` argument of `#[app]` spawns the
+//! tasks of the table at fixed offsets within every major cycle, from a task the `app` generates.
+//! The tasks of the table are ordinary software tasks without arguments, so they coexist with the
+//! event-driven tasks of the application and share resources with them as usual.
+//!
+//! A slot whose task has not finished since its last slot is skipped, the task is not queued
+//! twice. Such overruns are counted and read with [`overruns`].
+
+use portable_atomic::{AtomicU32, Ordering};
+
+static OVERRUNS: AtomicU32 = AtomicU32::new(0);
+
+/// The number of slots skipped so far because their task was still running
+pub fn overruns() -> u32 {
+    OVERRUNS.load(Ordering::Relaxed)
+}
+
+#[doc(hidden)]
+#[inline(always)]
+pub fn overrun() {
+    OVERRUNS.fetch_add(1, Ordering::Relaxed);
+}
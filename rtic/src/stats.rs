@@ -0,0 +1,61 @@
+//! Executor statistics of software tasks and dispatchers
+//!
+//! With the `executor-stats` feature every software task module gets a `stats()` function
+//! returning the [`TaskStats`] of the task. A task polled far more often than it makes progress,
+//! e.g. because it keeps waking itself, shows up as a high poll count and a
+//! [`WakeSource::Task`] last wake.
+//!
+//! Wakes are attributed by the primitives that perform them: `rtic-time` timer queues report
+//! [`WakeSource::Timer`] and `rtic-sync` channels report [`WakeSource::Channel`]. Drivers can
+//! report their wakes from interrupt handlers with
+//! `rtic_common::wake_source::with(WakeSource::Interrupt, || waker.wake())`.
+//!
+//! With the `energy-stats` feature the statistics include the time and energy the polls of the
+//! task took, as measured by [`crate::energy`].
+//!
+//! With the `coalesce-wakes` feature the app gets a `coalesce_stats(priority)` function returning
+//! the [`CoalesceStats`] of the dispatcher at that priority.
+
+#[cfg(feature = "executor-stats")]
+pub use rtic_common::wake_source::WakeSource;
+
+/// Statistics of a software task
+#[cfg(feature = "executor-stats")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskStats {
+    /// Number of times the task has been polled, wrapping on overflow
+    pub polls: u32,
+    /// The cause of the latest wake, `None` if the task was never woken
+    pub last_wake: Option<WakeSource>,
+    /// Time spent polling the task, in ticks of the clock of [`crate::energy`]
+    #[cfg(feature = "energy-stats")]
+    pub busy_ticks: u64,
+    /// Energy used by the polls of the task, in nanojoules, see [`crate::energy`]
+    #[cfg(feature = "energy-stats")]
+    pub energy_nj: u64,
+}
+
+/// Pend statistics of a dispatcher
+///
+/// Spawns and wakes arriving while the dispatcher is already pended share its next pass over the
+/// tasks instead of pending it again.
+#[cfg(feature = "coalesce-wakes")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoalesceStats {
+    /// Number of spawns and wakes targeting the dispatcher, wrapping on overflow
+    pub requests: u32,
+    /// Number of those that found the dispatcher already pended, wrapping on overflow
+    pub coalesced: u32,
+}
+
+#[cfg(feature = "coalesce-wakes")]
+impl CoalesceStats {
+    /// Share of the requests that were coalesced, in per mille
+    pub fn rate_permille(&self) -> u32 {
+        if self.requests == 0 {
+            0
+        } else {
+            (self.coalesced as u64 * 1000 / self.requests as u64) as u32
+        }
+    }
+}
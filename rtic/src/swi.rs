@@ -0,0 +1,46 @@
+//! Software interrupt requests for driver crates
+//!
+//! Drivers often need to defer work from a high priority interrupt handler to a lower priority
+//! one. [`request`] lets them do so without naming the backend specific interrupt: the
+//! application binds a hardware task to the software interrupt and hands its number `n` to the
+//! driver.
+//!
+//! | Backend              | `n`                                  | Mechanism                     |
+//! |----------------------|--------------------------------------|-------------------------------|
+//! | Cortex-M (`BASEPRI`) | device interrupt number              | NVIC `STIR`                   |
+//! | Cortex-M (masking)   | device interrupt number              | NVIC `ISPR`                   |
+//! | ESP32-C3/C6/P4, S3   | `0..4`, selecting `FROM_CPU_INTRn`   | `FROM_CPU_INTRn` registers    |
+//! | RISC-V SLIC          | SLIC software interrupt number       | SLIC, raised via CLINT `MSIP` |
+//! | RISC-V soft SLIC     | index in the `irq` module of the app | software pending bits         |
+//! | RISC-V ECLIC         | edge triggered interrupt number      | `clicintip`                   |
+//! | RP2350 (Hazard3)     | device interrupt number, `0..52`     | `meifa` force                 |
+//! | WCH CH32 (PFIC)      | interrupt number, `0..256`           | PFIC `IPSR`                   |
+//! | RISC-V PLIC          | none, the PLIC cannot pend a source  | —                             |
+//! | AVR                  | none, AVR cannot pend an interrupt   | —                             |
+//! | Null (host)          | none                                 | —                             |
+//! | std (host)           | index in the `irq` module of the app | simulated pending bits        |
+//! | wasm                 | index in the `irq` module of the app | software pending bits         |
+//! | Polling              | none                                 | —                             |
+//!
+//! On the SLIC the software interrupts are numbered in the order of the hardware tasks of the
+//! application, followed by its dispatchers.
+//!
+//! All memory writes made before [`request`] are visible to the handler of the interrupt. The
+//! RISC-V backends issue a `fence` before raising the interrupt for this, the ESP32-S3 a `memw`.
+
+/// The requested software interrupt does not exist on this backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidSwi(pub u16);
+
+/// Requests software interrupt `n`
+///
+/// The interrupt is pended and runs as soon as its priority allows, see the
+/// [module documentation](self) for how `n` is interpreted by each backend.
+#[inline]
+pub fn request(n: u16) -> Result<(), InvalidSwi> {
+    if crate::export::swi_request(n) {
+        Ok(())
+    } else {
+        Err(InvalidSwi(n))
+    }
+}
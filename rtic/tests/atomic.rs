@@ -0,0 +1,66 @@
+//! `#[atomic]` resources, run on the host by the `std-backend`
+
+#![cfg(feature = "std-backend")]
+
+use std::sync::mpsc;
+
+/// The host has no peripherals, the application only needs a device path
+pub mod pac {}
+
+static DONE: std::sync::Mutex<Option<mpsc::Sender<(u32, bool)>>> = std::sync::Mutex::new(None);
+
+#[rtic::app(device = crate::pac, peripherals = false)]
+mod app {
+    use core::sync::atomic::Ordering;
+
+    #[shared]
+    struct Shared {
+        #[atomic]
+        count: u32,
+        #[atomic]
+        ready: bool,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        low::spawn().ok();
+        (
+            Shared {
+                count: 40,
+                ready: false,
+            },
+            Local {},
+        )
+    }
+
+    #[task(priority = 2, shared = [count, ready])]
+    async fn high(cx: high::Context) {
+        cx.shared.count.fetch_add(1, Ordering::Relaxed);
+        cx.shared.ready.store(true, Ordering::Release);
+    }
+
+    #[task(priority = 1, shared = [count, ready])]
+    async fn low(cx: low::Context) {
+        cx.shared.count.fetch_add(1, Ordering::Relaxed);
+        high::spawn().ok();
+
+        let seen = (
+            cx.shared.count.load(Ordering::Relaxed),
+            cx.shared.ready.load(Ordering::Acquire),
+        );
+        super::DONE.lock().unwrap().take().unwrap().send(seen).ok();
+    }
+}
+
+#[test]
+fn tasks_of_all_priorities_update_in_place() {
+    let (done, finished) = mpsc::channel();
+    *DONE.lock().unwrap() = Some(done);
+
+    std::thread::spawn(|| unsafe { app::main() });
+
+    assert_eq!(finished.recv().unwrap(), (42, true));
+}
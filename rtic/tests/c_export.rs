@@ -0,0 +1,68 @@
+//! Resources exported to C, run on the host by the `std-backend`
+
+#![cfg(feature = "std-backend")]
+
+use std::sync::mpsc;
+
+/// The host has no peripherals, the application only needs a device path
+pub mod pac {}
+
+static DONE: std::sync::Mutex<Option<mpsc::Sender<(u32, bool)>>> = std::sync::Mutex::new(None);
+
+// The accessors as declared by `rtic-resources-<crate>.h`
+extern "C" {
+    fn app_speed_get() -> u32;
+    fn app_speed_set(value: u32);
+    fn app_armed_get() -> bool;
+}
+
+#[rtic::app(device = crate::pac, peripherals = false)]
+mod app {
+    #[shared]
+    struct Shared {
+        #[c_export(priority = 1)]
+        speed: u32,
+        #[c_export]
+        armed: bool,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        telemetry::spawn().ok();
+        (
+            Shared {
+                speed: 10,
+                armed: true,
+            },
+            Local {},
+        )
+    }
+
+    #[task(priority = 2, shared = [speed])]
+    async fn control(mut cx: control::Context) {
+        cx.shared.speed.lock(|speed| *speed += 1);
+    }
+
+    #[task(priority = 1)]
+    async fn telemetry(_: telemetry::Context) {
+        // What C code called from this task sees
+        unsafe { super::app_speed_set(super::app_speed_get() * 2) };
+        control::spawn().ok();
+
+        let seen = unsafe { (super::app_speed_get(), super::app_armed_get()) };
+        super::DONE.lock().unwrap().take().unwrap().send(seen).ok();
+    }
+}
+
+#[test]
+fn c_callers_see_the_locked_values() {
+    let (done, finished) = mpsc::channel();
+    *DONE.lock().unwrap() = Some(done);
+
+    std::thread::spawn(|| unsafe { app::main() });
+
+    assert_eq!(finished.recv().unwrap(), (21, true));
+}
@@ -0,0 +1,79 @@
+//! Energy accounting of software tasks, run on the host by the `std-backend`
+
+#![cfg(all(feature = "std-backend", feature = "energy-stats"))]
+
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    mpsc,
+};
+
+/// The host has no peripherals, the application only needs a device path
+pub mod pac {}
+
+/// A clock the tasks advance themselves, 1 000 ticks per second
+static TICKS: AtomicU32 = AtomicU32::new(0);
+
+static DONE: std::sync::Mutex<Option<mpsc::Sender<()>>> = std::sync::Mutex::new(None);
+
+#[rtic::app(device = crate::pac, peripherals = false, modes = [Active, Sleep])]
+mod app {
+    use super::TICKS;
+    use core::sync::atomic::Ordering;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        rtic::energy::set_clock(|| TICKS.load(Ordering::Relaxed), 1_000);
+        rtic::energy::set_supply(3_000);
+        rtic::energy::set_currents(&[1_000, 10]);
+
+        sampler::spawn().ok();
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 1)]
+    async fn sampler(_: sampler::Context) {
+        TICKS.fetch_add(100, Ordering::Relaxed);
+        // Preempts the sampler, its 50 ticks are only charged to `radio`
+        radio::spawn().ok();
+
+        set_mode(Mode::Sleep);
+        TICKS.fetch_add(100, Ordering::Relaxed);
+
+        super::DONE.lock().unwrap().take().unwrap().send(()).ok();
+    }
+
+    #[task(priority = 2)]
+    async fn radio(_: radio::Context) {
+        TICKS.fetch_add(50, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn polls_are_charged_at_the_current_of_the_mode() {
+    let (done, finished) = mpsc::channel();
+    *DONE.lock().unwrap() = Some(done);
+
+    std::thread::spawn(|| unsafe { app::main() });
+    finished.recv().unwrap();
+
+    // 50 ticks = 50 ms at 1 mA and 3 V
+    let radio = app::radio::stats();
+    assert_eq!(radio.busy_ticks, 50);
+    assert_eq!(radio.energy_nj, 150_000);
+
+    // Charged at the current of `Sleep` once its poll completes, after `DONE` was sent
+    let mut sampler = app::sampler::stats();
+    while sampler.busy_ticks == 0 {
+        std::thread::yield_now();
+        sampler = app::sampler::stats();
+    }
+    assert_eq!(sampler.busy_ticks, 200);
+    assert_eq!(sampler.energy_nj, 6_000);
+    assert_eq!(TICKS.load(Ordering::Relaxed), 250);
+}
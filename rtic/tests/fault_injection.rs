@@ -0,0 +1,60 @@
+//! Injected spawn failures, run on the host by the `std-backend`
+
+#![cfg(all(feature = "std-backend", feature = "fault-injection"))]
+
+use std::sync::mpsc;
+
+/// The host has no peripherals, the application only needs a device path
+pub mod pac {}
+
+/// The results of the spawns of `check`
+type Results = Vec<Result<(), u32>>;
+
+static DONE: std::sync::Mutex<Option<mpsc::Sender<Results>>> = std::sync::Mutex::new(None);
+
+#[rtic::app(device = crate::pac, peripherals = false)]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        worker::spawn_fault().fail_next(1);
+        worker::spawn_fault().fail_every(2);
+        check::spawn().ok();
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 1)]
+    async fn check(_: check::Context) {
+        // `worker` completes before the next spawn, only the injected failures remain
+        let spawns = (0..5).map(worker::spawn).collect();
+        super::DONE
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap()
+            .send(spawns)
+            .ok();
+    }
+
+    #[task(priority = 2)]
+    async fn worker(_: worker::Context, _: u32) {}
+}
+
+#[test]
+fn spawns_fail_as_injected() {
+    let (done, finished) = mpsc::channel();
+    *DONE.lock().unwrap() = Some(done);
+
+    std::thread::spawn(|| unsafe { app::main() });
+
+    assert_eq!(
+        finished.recv().unwrap(),
+        [Err(0), Ok(()), Err(2), Ok(()), Err(4)]
+    );
+    assert_eq!(app::worker::spawn_fault().injected(), 3);
+}
@@ -0,0 +1,70 @@
+//! Tasks run from the superloop of the `polling-backend`, on the host
+
+#![cfg(feature = "polling-backend")]
+
+use std::sync::mpsc;
+
+/// The host has no peripherals, the application only needs a device path
+pub mod pac {}
+
+static DONE: std::sync::Mutex<Option<mpsc::Sender<Vec<&'static str>>>> =
+    std::sync::Mutex::new(None);
+
+#[rtic::app(device = crate::pac, peripherals = false)]
+mod app {
+    #[shared]
+    struct Shared {
+        log: Vec<&'static str>,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        background::spawn().ok();
+        low::spawn().ok();
+        high::spawn().ok();
+        (Shared { log: Vec::new() }, Local {})
+    }
+
+    #[task(priority = 0, shared = [log])]
+    async fn background(mut cx: background::Context) {
+        cx.shared.log.lock(|log| log.push("background"));
+
+        let log = cx.shared.log.lock(core::mem::take);
+        super::DONE.lock().unwrap().take().unwrap().send(log).ok();
+    }
+
+    #[task(priority = 1, shared = [log])]
+    async fn low(mut cx: low::Context) {
+        cx.shared.log.lock(|log| log.push("low start"));
+        // Runs once `low` returns to the superloop, nothing preempts
+        highest::spawn().ok();
+        cx.shared.log.lock(|log| log.push("low end"));
+    }
+
+    #[task(priority = 2, shared = [log])]
+    async fn high(mut cx: high::Context) {
+        assert_eq!(rtic::current_priority(), 2);
+        cx.shared.log.lock(|log| log.push("high"));
+    }
+
+    #[task(priority = 3, shared = [log])]
+    async fn highest(mut cx: highest::Context) {
+        cx.shared.log.lock(|log| log.push("highest"));
+    }
+}
+
+#[test]
+fn tasks_run_in_priority_order() {
+    let (done, finished) = mpsc::channel();
+    *DONE.lock().unwrap() = Some(done);
+
+    std::thread::spawn(|| unsafe { app::main() });
+
+    assert_eq!(
+        finished.recv().unwrap(),
+        ["high", "low start", "low end", "highest", "background"]
+    );
+}
@@ -0,0 +1,110 @@
+//! An application run on the host by the `std-backend`
+
+#![cfg(feature = "std-backend")]
+
+use std::sync::mpsc;
+
+/// The host has no peripherals, the application only needs a device path
+pub mod pac {}
+
+static EVENTS: std::sync::Mutex<Vec<&'static str>> = std::sync::Mutex::new(Vec::new());
+
+fn log(event: &'static str) {
+    EVENTS.lock().unwrap().push(event);
+}
+
+#[rtic::app(device = crate::pac, peripherals = false)]
+mod app {
+    use super::log;
+
+    #[shared]
+    struct Shared {
+        counter: u32,
+    }
+
+    #[local]
+    struct Local {
+        done: Option<std::sync::mpsc::Sender<u32>>,
+    }
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        low::spawn().ok();
+        log("init");
+        (
+            Shared { counter: 0 },
+            Local {
+                done: super::DONE.lock().unwrap().take(),
+            },
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        log("idle");
+        loop {
+            std::thread::park();
+        }
+    }
+
+    #[task(priority = 1, shared = [counter])]
+    async fn low(mut cx: low::Context) {
+        log("low start");
+        cx.shared.counter.lock(|counter| {
+            *counter += 1;
+            // `high` has a higher priority than the ceiling, so it preempts the lock
+            high::spawn().ok();
+            log("low locked");
+        });
+        log("low end");
+    }
+
+    #[task(priority = 3)]
+    async fn high(_: high::Context) {
+        log("high");
+    }
+
+    #[task(binds = UART0, priority = 2, shared = [counter], local = [done])]
+    fn uart0(mut cx: uart0::Context) {
+        let counter = cx.shared.counter.lock(|counter| {
+            *counter += 1;
+            *counter
+        });
+        log("uart0");
+        if let Some(done) = cx.local.done.take() {
+            done.send(counter).ok();
+        }
+    }
+}
+
+static DONE: std::sync::Mutex<Option<mpsc::Sender<u32>>> = std::sync::Mutex::new(None);
+
+#[test]
+fn priorities_are_simulated() {
+    let (done, finished) = mpsc::channel();
+    *DONE.lock().unwrap() = Some(done);
+
+    std::thread::spawn(|| unsafe { app::main() });
+
+    // The tasks spawned by `init` run before `idle`
+    while !EVENTS.lock().unwrap().contains(&"idle") {
+        std::thread::yield_now();
+    }
+
+    // Pended from the test thread, `uart0` runs on it
+    rtic::pend(app::irq::UART0);
+    assert_eq!(finished.recv().unwrap(), 2);
+
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        [
+            "init",
+            "low start",
+            "high",
+            "low locked",
+            "low end",
+            "idle",
+            "uart0"
+        ]
+    );
+}
@@ -1,3 +1,6 @@
+// The applications are for the Cortex-M `lm3s6965`, not for the host backends
+#![cfg(not(any(feature = "std-backend", feature = "polling-backend")))]
+
 use trybuild::TestCases;
 
 #[test]
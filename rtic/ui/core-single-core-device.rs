@@ -0,0 +1,19 @@
+#![no_main]
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0])]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (Shared {}, Local {})
+    }
+
+    // The LM3S6965 has a single core
+    #[task(core = 1, priority = 1)]
+    async fn foo(_: foo::Context) {}
+}
@@ -0,0 +1,20 @@
+#![no_main]
+
+#[rtic::app(device = lm3s6965)]
+mod app {
+    #[shared]
+    struct Shared {
+        #[depends_on(dma)]
+        clocks: u32,
+        #[depends_on(clocks)]
+        dma: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        cx.resources.build()
+    }
+}
@@ -0,0 +1,21 @@
+#![no_main]
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0 => 1, UART0 => 1])]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(cx: init::Context) -> (Shared, Local) {
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 1)]
+    async fn a(_: a::Context) {}
+
+    #[task(priority = 2)]
+    async fn b(_: b::Context) {}
+}
@@ -0,0 +1,24 @@
+#![no_main]
+
+#[rtic::app(device = lm3s6965, dispatchers = [SSI0])]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 1)]
+    async fn foo(_: foo::Context) {
+        // No task is bound to `UART1`
+        let _ = uart1::dma();
+    }
+
+    #[task(binds = UART0)]
+    fn uart0(_: uart0::Context) {}
+}
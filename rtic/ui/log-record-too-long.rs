@@ -0,0 +1,19 @@
+#![no_main]
+
+#[rtic::app(device = lm3s6965)]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        rtic::log!(
+            "this message is longer than the 64 bytes of a record, {} placeholder or not",
+            1
+        );
+        (Shared {}, Local {})
+    }
+}
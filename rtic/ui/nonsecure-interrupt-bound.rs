@@ -0,0 +1,21 @@
+#![no_main]
+
+#[rtic::app(
+    device = lm3s6965,
+    backend = [security = secure, nonsecure_interrupts = [UART1]],
+)]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (Shared {}, Local {})
+    }
+
+    #[task(binds = UART1)]
+    fn uart1(_: uart1::Context) {}
+}
@@ -0,0 +1,25 @@
+#![no_main]
+
+#[rtic::app(
+    device = lm3s6965,
+    backend = [nonsecure_sections = [".ram_ns"], nonsecure_tasks = [uart0]],
+)]
+mod app {
+    #[shared]
+    struct Shared {
+        #[link_section = ".ram_ns"]
+        rx: u32,
+        tx: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (Shared { rx: 0, tx: 0 }, Local {})
+    }
+
+    #[task(binds = UART0, shared = [rx, tx])]
+    fn uart0(_: uart0::Context) {}
+}
@@ -0,0 +1,27 @@
+#![no_main]
+
+#[rtic::app(
+    device = lm3s6965,
+    backend = [nonsecure_sections = [".ram_ns"], nonsecure_tasks = [uart0]],
+)]
+mod app {
+    #[shared]
+    struct Shared {
+        #[link_section = ".ram_ns"]
+        rx: u32,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (Shared { rx: 0 }, Local {})
+    }
+
+    #[task(binds = UART0, shared = [rx])]
+    fn uart0(_: uart0::Context) {}
+
+    #[task(binds = UART1, shared = [rx])]
+    fn uart1(_: uart1::Context) {}
+}
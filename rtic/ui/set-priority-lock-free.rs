@@ -0,0 +1,34 @@
+#![no_main]
+#![deny(warnings)]
+
+#[rtic::app(device = lm3s6965)]
+mod app {
+    use lm3s6965::Interrupt;
+
+    #[shared]
+    struct Shared {
+        #[lock_free]
+        counter: u64,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        // All tasks keep their priority, `set_priority` still compiles without warnings
+        let _: () = set_priority(Interrupt::UART0, 1);
+
+        (Shared { counter: 0 }, Local {})
+    }
+
+    #[task(binds = UART0, shared = [counter])]
+    fn foo(c: foo::Context) {
+        *c.shared.counter += 1;
+    }
+
+    #[task(binds = UART1, shared = [counter])]
+    fn bar(c: bar::Context) {
+        *c.shared.counter += 1;
+    }
+}
@@ -0,0 +1,18 @@
+#![no_main]
+
+#[rtic::app(device = lm3s6965, sleep_on_exit = true)]
+mod app {
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(_: init::Context) -> (Shared, Local) {
+        (Shared {}, Local {})
+    }
+
+    #[task(priority = 0)]
+    async fn background(_: background::Context) {}
+}
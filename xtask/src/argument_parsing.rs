@@ -1,6 +1,6 @@
 use crate::{
-    cargo_command::CargoCommand, Target, ARMV6M, ARMV7M, ARMV8MBASE, ARMV8MMAIN, RISCV32IMAC,
-    RISCV32IMC,
+    cargo_command::CargoCommand, Target, ARMV6M, ARMV7M, ARMV8MBASE, ARMV8MMAIN, AVR_ATMEGA328,
+    RISCV32IMAC, RISCV32IMAFC, RISCV32IMC, RISCV64GC, WASM32, X86_64, XTENSA_ESP32S3,
 };
 use clap::{Args, Parser, Subcommand};
 use core::fmt;
@@ -93,7 +93,7 @@ impl Package {
                 features
                     .iter()
                     .map(|&s| {
-                        if matches!(backend, Backends::Thumbv6) {
+                        if matches!(backend, Backends::Thumbv6 | Backends::Thumbv6Rp2040) {
                             format!("{s},portable-atomic/critical-section")
                         } else {
                             s.to_string()
@@ -103,7 +103,7 @@ impl Package {
                     .chain(std::iter::once(None))
                     .collect()
             }
-            Package::RticSync if matches!(backend, Backends::Thumbv6) => {
+            Package::RticSync if matches!(backend, Backends::Thumbv6 | Backends::Thumbv6Rp2040) => {
                 vec![Some("portable-atomic/critical-section".into())]
             }
             _ => vec![None],
@@ -116,6 +116,20 @@ pub struct TestMetadata {}
 impl TestMetadata {
     pub fn match_package(package: Package, backend: Backends) -> CargoCommand<'static> {
         match package {
+            // The host backends run the applications of all integration tests, with the energy
+            // accounting and the fault injection some of them need; the examples are for targets
+            Package::Rtic if matches!(backend, Backends::Std | Backends::Polling) => {
+                let features = backend.to_target().and_features(&format!(
+                    "{},energy-stats,fault-injection,critical-section/std",
+                    backend.to_rtic_feature()
+                ));
+                CargoCommand::Test {
+                    package: Some(package.name()),
+                    features: Some(features),
+                    test: Some("*".to_owned()),
+                    deny_warnings: true,
+                }
+            }
             Package::Rtic => {
                 let features = Some(backend.to_target().and_features(backend.to_rtic_feature()));
                 CargoCommand::Test {
@@ -133,7 +147,7 @@ impl TestMetadata {
             },
             Package::RticSync => CargoCommand::Test {
                 package: Some(package.name()),
-                features: Some("testing".to_owned()),
+                features: Some("testing,time,flash,rng".to_owned()),
                 test: None,
                 deny_warnings: true,
             },
@@ -162,25 +176,52 @@ impl TestMetadata {
 #[derive(clap::ValueEnum, Copy, Clone, Default, Debug)]
 pub enum Backends {
     Thumbv6,
+    Thumbv6Rp2040,
     #[default]
     Thumbv7,
     Thumbv8Base,
     Thumbv8Main,
+    Thumbv8MainNrf5340,
     RiscvEsp32C3,
+    RiscvEsp32C6,
+    RiscvEsp32P4,
     Riscv32ImcClint, // not working yet (issues with portable-atomic features...)
     Riscv32ImacClint,
+    RiscvEclic,
+    RiscvRp2350,
+    RiscvPlic,
+    RiscvCh32,
+    RiscvSoftSlic,
+    Avr,
+    Null,
+    Std,
+    Wasm,
+    Polling,
+    XtensaEsp32S3,
 }
 
 impl Backends {
     #[allow(clippy::wrong_self_convention)]
     pub fn to_target(&self) -> Target<'static> {
         match self {
-            Backends::Thumbv6 => ARMV6M,
+            Backends::Thumbv6 | Backends::Thumbv6Rp2040 => ARMV6M,
             Backends::Thumbv7 => ARMV7M,
             Backends::Thumbv8Base => ARMV8MBASE,
-            Backends::Thumbv8Main => ARMV8MMAIN,
-            Backends::Riscv32ImcClint | Backends::RiscvEsp32C3 => RISCV32IMC,
-            Backends::Riscv32ImacClint => RISCV32IMAC,
+            Backends::Thumbv8Main | Backends::Thumbv8MainNrf5340 => ARMV8MMAIN,
+            Backends::Riscv32ImcClint | Backends::RiscvEsp32C3 | Backends::RiscvSoftSlic => {
+                RISCV32IMC
+            }
+            Backends::Riscv32ImacClint
+            | Backends::RiscvEsp32C6
+            | Backends::RiscvEclic
+            | Backends::RiscvRp2350
+            | Backends::RiscvCh32 => RISCV32IMAC,
+            Backends::RiscvEsp32P4 => RISCV32IMAFC,
+            Backends::RiscvPlic => RISCV64GC,
+            Backends::Avr => AVR_ATMEGA328,
+            Backends::Null | Backends::Std | Backends::Polling => X86_64,
+            Backends::Wasm => WASM32,
+            Backends::XtensaEsp32S3 => XTENSA_ESP32S3,
         }
     }
 
@@ -188,20 +229,50 @@ impl Backends {
     pub fn to_rtic_feature(&self) -> &'static str {
         match self {
             Backends::Thumbv6 => "thumbv6-backend",
+            Backends::Thumbv6Rp2040 => "thumbv6-rp2040-backend",
             Backends::Thumbv7 => "thumbv7-backend",
             Backends::Thumbv8Base => "thumbv8base-backend",
             Backends::Thumbv8Main => "thumbv8main-backend",
+            Backends::Thumbv8MainNrf5340 => "thumbv8main-nrf5340-backend",
             Backends::RiscvEsp32C3 => "riscv-esp32c3-backend",
+            Backends::RiscvEsp32C6 => "riscv-esp32c6-backend",
+            Backends::RiscvEsp32P4 => "riscv-esp32p4-backend",
             Backends::Riscv32ImcClint | Backends::Riscv32ImacClint => "riscv-clint-backend",
+            Backends::RiscvEclic => "riscv-eclic-backend",
+            Backends::RiscvRp2350 => "riscv-rp2350-backend",
+            Backends::RiscvPlic => "riscv-plic-backend",
+            Backends::RiscvCh32 => "riscv-ch32-backend",
+            Backends::RiscvSoftSlic => "riscv-soft-slic-backend",
+            Backends::Avr => "avr-backend",
+            Backends::Null => "null-backend",
+            Backends::Std => "std-backend",
+            Backends::Wasm => "wasm-backend",
+            Backends::Polling => "polling-backend",
+            Backends::XtensaEsp32S3 => "xtensa-esp32s3-backend",
         }
     }
     #[allow(clippy::wrong_self_convention)]
     pub fn to_rtic_macros_feature(&self) -> &'static str {
         match self {
             Backends::Thumbv6 | Backends::Thumbv8Base => "cortex-m-source-masking",
+            Backends::Thumbv6Rp2040 => "cortex-m-source-masking,rp2040",
             Backends::Thumbv7 | Backends::Thumbv8Main => "cortex-m-basepri",
+            Backends::Thumbv8MainNrf5340 => "cortex-m-basepri,nrf5340",
             Backends::RiscvEsp32C3 => "riscv-esp32c3",
+            Backends::RiscvEsp32C6 => "riscv-esp32c6",
+            Backends::RiscvEsp32P4 => "riscv-esp32p4",
             Backends::Riscv32ImcClint | Backends::Riscv32ImacClint => "riscv-clint",
+            Backends::RiscvEclic => "riscv-eclic",
+            Backends::RiscvRp2350 => "riscv-rp2350",
+            Backends::RiscvPlic => "riscv-plic",
+            Backends::RiscvCh32 => "riscv-ch32",
+            Backends::RiscvSoftSlic => "riscv-soft-slic",
+            Backends::Avr => "avr",
+            Backends::Null => "null",
+            Backends::Std => "std",
+            Backends::Wasm => "wasm",
+            Backends::Polling => "polling",
+            Backends::XtensaEsp32S3 => "xtensa-esp32s3",
         }
     }
 }
@@ -216,6 +287,7 @@ pub enum BuildOrCheck {
 #[derive(clap::ValueEnum, Copy, Clone, Default, Debug)]
 pub enum Platforms {
     Esp32C3,
+    Esp32C6,
     Hifive1,
     #[default]
     Lm3s6965,
@@ -230,6 +302,7 @@ impl Platforms {
     pub fn name(&self) -> String {
         let name = match self {
             Platforms::Esp32C3 => "esp32c3",
+            Platforms::Esp32C6 => "esp32c6",
             Platforms::Hifive1 => "hifive1",
             Platforms::Lm3s6965 => "lm3s6965",
             Platforms::Nrf52840 => "nrf52840",
@@ -245,7 +318,9 @@ impl Platforms {
     pub fn rust_flags(&self) -> Vec<String> {
         let c = "-C".to_string();
         match self {
-            Platforms::Esp32C3 => vec![c, "link-arg=-Tlinkall.x".to_string()],
+            Platforms::Esp32C3 | Platforms::Esp32C6 => {
+                vec![c, "link-arg=-Tlinkall.x".to_string()]
+            }
             Platforms::Hifive1 => vec![c, "link-arg=-Thifive1-link.x".to_string()],
             Platforms::Lm3s6965 => vec![c, "link-arg=-Tlink.x".to_string()],
             Platforms::Nrf52840 => vec![
@@ -284,6 +359,7 @@ impl Platforms {
     pub fn default_backend(&self) -> Backends {
         match self {
             Platforms::Esp32C3 => Backends::RiscvEsp32C3,
+            Platforms::Esp32C6 => Backends::RiscvEsp32C6,
             Platforms::Hifive1 => Backends::Riscv32ImcClint,
             Platforms::Lm3s6965 => Backends::Thumbv7,
             Platforms::Nrf52840 => unimplemented!(),
@@ -303,8 +379,12 @@ impl Platforms {
                 RISCV32IMC => Ok(None),
                 _ => Err(()),
             },
-            Platforms::Hifive1 => match backend.to_target() {
-                RISCV32IMC | RISCV32IMAC => Ok(None),
+            Platforms::Esp32C6 => match backend {
+                Backends::RiscvEsp32C6 => Ok(None),
+                _ => Err(()),
+            },
+            Platforms::Hifive1 => match backend {
+                Backends::Riscv32ImcClint | Backends::Riscv32ImacClint => Ok(None),
                 _ => Err(()),
             },
             Platforms::Lm3s6965 => match backend.to_target() {
@@ -421,6 +501,18 @@ pub enum Commands {
     /// Example: `cargo xtask size -- -A`
     Size(Arg),
 
+    /// Estimate the worst-case stack usage per priority level of selected or all examples
+    ///
+    /// Builds with `-Z emit-stack-sizes` on the nightly toolchain and reads the frame sizes
+    /// with `cargo readobj`, next to the sizes reported by `cargo xtask size`
+    Stack,
+
+    /// Report the interrupt vectors of selected or all examples
+    ///
+    /// Lists every interrupt of the device as a hardware task, a dispatcher, a monotonic, another
+    /// handler or unused, from the symbols read with `cargo nm` and the `device.x` of the PAC
+    Vectors,
+
     /// Run examples in QEMU and compare against expected output
     ///
     /// Example runtime output is matched against `rtic/ci/expected/`
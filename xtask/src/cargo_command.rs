@@ -109,6 +109,26 @@ pub enum CargoCommand<'a> {
         dir: Option<PathBuf>,
         deny_warnings: bool,
     },
+    ExampleStack {
+        cargoarg: &'a Option<&'a str>,
+        platform: Platforms, // to tell which platform. If None, it assumes lm3s6965
+        example: &'a str,
+        target: Option<Target<'a>>,
+        features: Option<String>,
+        mode: BuildMode,
+        dir: Option<PathBuf>,
+        deny_warnings: bool,
+    },
+    ExampleVectors {
+        cargoarg: &'a Option<&'a str>,
+        platform: Platforms, // to tell which platform. If None, it assumes lm3s6965
+        example: &'a str,
+        target: Option<Target<'a>>,
+        features: Option<String>,
+        mode: BuildMode,
+        dir: Option<PathBuf>,
+        deny_warnings: bool,
+    },
 }
 
 impl core::fmt::Display for CargoCommand<'_> {
@@ -362,6 +382,34 @@ impl core::fmt::Display for CargoCommand<'_> {
                 let details = details(warns, target, Some(mode), features, cargoarg, dir.as_ref());
                 write!(f, "Compute size of example {example} {details}")
             }
+            CargoCommand::ExampleStack {
+                cargoarg,
+                platform: _,
+                example,
+                target,
+                features,
+                mode,
+                dir,
+                deny_warnings,
+            } => {
+                let warns = *deny_warnings;
+                let details = details(warns, target, Some(mode), features, cargoarg, dir.as_ref());
+                write!(f, "Estimate stack usage of example {example} {details}")
+            }
+            CargoCommand::ExampleVectors {
+                cargoarg,
+                platform: _,
+                example,
+                target,
+                features,
+                mode,
+                dir,
+                deny_warnings,
+            } => {
+                let warns = *deny_warnings;
+                let details = details(warns, target, Some(mode), features, cargoarg, dir.as_ref());
+                write!(f, "Report interrupt vectors of example {example} {details}")
+            }
         }
     }
 }
@@ -391,6 +439,8 @@ impl<'a> CargoCommand<'a> {
             CargoCommand::ExampleCheck { .. } | CargoCommand::Check { .. } => "check",
             CargoCommand::ExampleBuild { .. } | CargoCommand::Build { .. } => "build",
             CargoCommand::ExampleSize { .. } => "size",
+            CargoCommand::ExampleStack { .. } => "readobj",
+            CargoCommand::ExampleVectors { .. } => "nm",
             CargoCommand::Clippy { .. } => "clippy",
             CargoCommand::Format { .. } => "fmt",
             CargoCommand::Doc { .. } => "doc",
@@ -407,6 +457,8 @@ impl<'a> CargoCommand<'a> {
             | CargoCommand::ExampleBuild { .. }
             | CargoCommand::Build { .. }
             | CargoCommand::ExampleSize { .. }
+            | CargoCommand::ExampleStack { .. }
+            | CargoCommand::ExampleVectors { .. }
             | CargoCommand::Clippy { .. }
             | CargoCommand::Format { .. }
             | CargoCommand::Test { .. }
@@ -673,6 +725,39 @@ impl<'a> CargoCommand<'a> {
 
                 self.build_args(false, cargoarg, features, Some(mode), extra)
             }
+            CargoCommand::ExampleStack {
+                cargoarg,
+                platform: _,
+                example,
+                features,
+                mode,
+                // Target is added by build_args
+                target: _,
+                // dir is exposed through `chdir`
+                dir: _,
+                // deny_warnings and the stack size flag are exposed through `extra_env`
+                deny_warnings: _,
+            } => {
+                // `-Z emit-stack-sizes` needs a nightly compiler
+                let extra = ["--example", example, "--", "--stack-sizes", "--demangle"];
+                self.build_args(true, cargoarg, features, Some(mode), extra.into_iter())
+            }
+            CargoCommand::ExampleVectors {
+                cargoarg,
+                platform: _,
+                example,
+                features,
+                mode,
+                // Target is added by build_args
+                target: _,
+                // dir is exposed through `chdir`
+                dir: _,
+                // deny_warnings is exposed through `extra_env`
+                deny_warnings: _,
+            } => {
+                let extra = ["--example", example, "--", "--defined-only"];
+                self.build_args(false, cargoarg, features, Some(mode), extra.into_iter())
+            }
         }
     }
 
@@ -683,6 +768,8 @@ impl<'a> CargoCommand<'a> {
             | CargoCommand::ExampleCheck { dir, .. }
             | CargoCommand::ExampleBuild { dir, .. }
             | CargoCommand::ExampleSize { dir, .. }
+            | CargoCommand::ExampleStack { dir, .. }
+            | CargoCommand::ExampleVectors { dir, .. }
             | CargoCommand::Build { dir, .. }
             | CargoCommand::Run { dir, .. }
             | CargoCommand::Check { dir, .. } => dir.as_ref(),
@@ -699,7 +786,9 @@ impl<'a> CargoCommand<'a> {
             | CargoCommand::Build { target, .. }
             | CargoCommand::Check { target, .. }
             | CargoCommand::Clippy { target, .. }
-            | CargoCommand::ExampleSize { target, .. } => target.as_ref(),
+            | CargoCommand::ExampleSize { target, .. }
+            | CargoCommand::ExampleStack { target, .. }
+            | CargoCommand::ExampleVectors { target, .. } => target.as_ref(),
             _ => None,
         }
     }
@@ -725,6 +814,11 @@ impl<'a> CargoCommand<'a> {
                 platform,
                 deny_warnings,
                 ..
+            }
+            | CargoCommand::ExampleVectors {
+                platform,
+                deny_warnings,
+                ..
             } => {
                 if *deny_warnings {
                     let rust_flags = platform.rust_flags().join(" ");
@@ -738,6 +832,20 @@ impl<'a> CargoCommand<'a> {
                 }
             }
 
+            CargoCommand::ExampleStack {
+                platform,
+                deny_warnings,
+                ..
+            } => {
+                // Setting RUSTFLAGS drops the flags of .cargo/config.toml, add the link-args back
+                let mut rust_flags = vec!["-Z emit-stack-sizes".to_string()];
+                if *deny_warnings {
+                    rust_flags.push("-D warnings".to_string());
+                }
+                rust_flags.extend(platform.rust_flags());
+                Some(("RUSTFLAGS", rust_flags.join(" ")))
+            }
+
             CargoCommand::Check { deny_warnings, .. }
             | CargoCommand::ExampleCheck { deny_warnings, .. }
             | CargoCommand::Build { deny_warnings, .. }
@@ -2,6 +2,8 @@ mod argument_parsing;
 mod build;
 mod cargo_command;
 mod run;
+mod stack;
+mod vectors;
 
 use argument_parsing::ExtraArguments;
 use clap::Parser;
@@ -49,13 +51,18 @@ impl core::fmt::Display for Target<'_> {
 }
 
 // x86_64-unknown-linux-gnu
-const _X86_64: Target = Target::new("x86_64-unknown-linux-gnu", true);
+const X86_64: Target = Target::new("x86_64-unknown-linux-gnu", true);
 const ARMV6M: Target = Target::new("thumbv6m-none-eabi", false);
 const ARMV7M: Target = Target::new("thumbv7m-none-eabi", false);
 const ARMV8MBASE: Target = Target::new("thumbv8m.base-none-eabi", false);
 const ARMV8MMAIN: Target = Target::new("thumbv8m.main-none-eabi", false);
 const RISCV32IMC: Target = Target::new("riscv32imc-unknown-none-elf", false);
 const RISCV32IMAC: Target = Target::new("riscv32imac-unknown-none-elf", false);
+const RISCV32IMAFC: Target = Target::new("riscv32imafc-unknown-none-elf", false);
+const WASM32: Target = Target::new("wasm32-unknown-unknown", true);
+const RISCV64GC: Target = Target::new("riscv64gc-unknown-none-elf", false);
+const AVR_ATMEGA328: Target = Target::new("avr-unknown-gnu-atmega328", false);
+const XTENSA_ESP32S3: Target = Target::new("xtensa-esp32s3-none-elf", false);
 
 fn main() -> anyhow::Result<()> {
     // if there's an `xtask` folder, we're *probably* at the root of this repo (we can't just
@@ -110,12 +117,16 @@ fn main() -> anyhow::Result<()> {
         ));
     }
     let examples_path = format!("./examples/{}/examples", platform.name());
-    let examples: Vec<_> = std::fs::read_dir(examples_path)?
+    // Platforms building only the shared examples have no examples of their own
+    let mut examples: Vec<_> = std::fs::read_dir(examples_path)
+        .into_iter()
+        .flatten()
         .filter_map(|p| p.ok())
         .map(|p| p.path())
         .filter(|p| p.display().to_string().ends_with(".rs"))
         .map(|path| path.file_stem().unwrap().to_str().unwrap().to_string())
         .collect();
+    examples.extend(shared_examples(platform)?);
 
     let example = globals.example.clone();
     let exampleexclude = globals.exampleexclude.clone();
@@ -224,6 +235,16 @@ fn main() -> anyhow::Result<()> {
                 &args.arguments,
             )
         }
+        Commands::Stack => {
+            // x86_64 target not valid
+            info!("Measuring stack for platform: {platform:?}, backend: {backend:?}");
+            build_and_report_stack(globals, &cargologlevel, platform, backend, &examples_to_run)
+        }
+        Commands::Vectors => {
+            // x86_64 target not valid
+            info!("Reporting vectors for platform: {platform:?}, backend: {backend:?}");
+            build_and_report_vectors(globals, &cargologlevel, platform, backend, &examples_to_run)
+        }
         Commands::Qemu(args) | Commands::Run(args) => {
             // x86_64 target not valid
             info!("Testing for platform: {platform:?}, backend: {backend:?}");
@@ -252,3 +273,19 @@ fn main() -> anyhow::Result<()> {
 
     handle_results(globals, final_run_results).map_err(|_| anyhow::anyhow!("Commands failed"))
 }
+
+/// The backend independent examples of `examples/shared` the platform builds
+///
+/// A platform lists the shared examples whose requirements its board support meets as
+/// `[[example]]` targets with a `path` into `examples/shared`.
+fn shared_examples(platform: Platforms) -> anyhow::Result<Vec<String>> {
+    let manifest = std::fs::read_to_string(format!("./examples/{}/Cargo.toml", platform.name()))?;
+
+    Ok(manifest
+        .lines()
+        .filter_map(|line| {
+            let path = line.trim().strip_prefix("path = \"../shared/")?;
+            Some(path.strip_suffix(".rs\"")?.to_string())
+        })
+        .collect())
+}
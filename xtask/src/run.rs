@@ -64,6 +64,88 @@ fn command_parser(
     };
 
     match *command {
+        CargoCommand::ExampleStack {
+            platform,
+            example,
+            target,
+            ..
+        } => {
+            let cargo_result = run_command(command, output_mode, false)?;
+
+            if cargo_result.exit_status.success() {
+                // Written by `#[rtic::app]` when the example was built
+                let crate_name = example.replace('-', "_");
+                let model_file: PathBuf = [
+                    "examples",
+                    platform.name().as_str(),
+                    "target",
+                    &format!("rtic-stack-model-{crate_name}.txt"),
+                ]
+                .iter()
+                .collect();
+                let model =
+                    std::fs::read_to_string(&model_file).map_err(|_| TestRunError::FileError {
+                        file: model_file.display().to_string(),
+                    })?;
+
+                let riscv = target.is_some_and(|t| t.triple().starts_with("riscv"));
+                let exception_frame = if riscv {
+                    // The trap entry of the RISC-V runtimes saves the caller-saved registers
+                    64
+                } else {
+                    // Cortex-M: R0-R3, R12, LR, PC and xPSR
+                    32
+                };
+                info!(
+                    "\n{}",
+                    crate::stack::report(example, &model, &cargo_result.stdout, exception_frame)
+                );
+            }
+
+            Ok(cargo_result)
+        }
+        CargoCommand::ExampleVectors {
+            platform,
+            example,
+            target,
+            ..
+        } => {
+            let cargo_result = run_command(command, output_mode, false)?;
+
+            if cargo_result.exit_status.success() {
+                let platform_dir: PathBuf = ["examples", platform.name().as_str(), "target"]
+                    .iter()
+                    .collect();
+
+                // Written by `#[rtic::app]` when the example was built
+                let crate_name = example.replace('-', "_");
+                let model_file = platform_dir.join(format!("rtic-vector-model-{crate_name}.txt"));
+                let model =
+                    std::fs::read_to_string(&model_file).map_err(|_| TestRunError::FileError {
+                        file: model_file.display().to_string(),
+                    })?;
+
+                // Copied to its `OUT_DIR` by the build script of the PAC
+                let build_dir = platform_dir
+                    .join(target.as_ref().map_or("", |t| t.triple()))
+                    .join("release")
+                    .join("build");
+                let device_x = newest_device_x(&build_dir).ok_or(TestRunError::FileError {
+                    file: build_dir.join("*/out/device.x").display().to_string(),
+                })?;
+                let device_x =
+                    std::fs::read_to_string(&device_x).map_err(|_| TestRunError::FileError {
+                        file: device_x.display().to_string(),
+                    })?;
+
+                info!(
+                    "\n{}",
+                    crate::vectors::report(example, &model, &device_x, &cargo_result.stdout)
+                );
+            }
+
+            Ok(cargo_result)
+        }
         CargoCommand::Qemu {
             platform, example, ..
         }
@@ -460,6 +542,83 @@ pub fn build_and_check_size<'c>(
     runner.run_and_coalesce()
 }
 
+pub fn build_and_report_stack<'c>(
+    globals: &Globals,
+    cargoarg: &'c Option<&'c str>,
+    platform: Platforms,
+    backend: Backends,
+    examples: &'c [String],
+) -> Vec<FinalRunResult<'c>> {
+    let target = backend.to_target();
+    let features = Some(target.and_features(backend.to_rtic_feature()));
+
+    // `cargo readobj` builds the example before reading its stack sizes
+    let runner = into_iter(examples)
+        .map(|example| {
+            let path = format!("examples/{}", platform.name());
+            let dir = Some(PathBuf::from(path));
+
+            CargoCommand::ExampleStack {
+                cargoarg,
+                platform,
+                example,
+                target: Some(target),
+                features: features.clone(),
+                mode: BuildMode::Release,
+                dir,
+                deny_warnings: globals.deny_warnings,
+            }
+        })
+        .map(|cmd| (globals, cmd, false));
+
+    runner.run_and_coalesce()
+}
+
+pub fn build_and_report_vectors<'c>(
+    globals: &Globals,
+    cargoarg: &'c Option<&'c str>,
+    platform: Platforms,
+    backend: Backends,
+    examples: &'c [String],
+) -> Vec<FinalRunResult<'c>> {
+    let target = backend.to_target();
+    let features = Some(target.and_features(backend.to_rtic_feature()));
+
+    // `cargo nm` builds the example before reading its symbols
+    let runner = into_iter(examples)
+        .map(|example| {
+            let path = format!("examples/{}", platform.name());
+            let dir = Some(PathBuf::from(path));
+
+            CargoCommand::ExampleVectors {
+                cargoarg,
+                platform,
+                example,
+                target: Some(target),
+                features: features.clone(),
+                mode: BuildMode::Release,
+                dir,
+                deny_warnings: globals.deny_warnings,
+            }
+        })
+        .map(|cmd| (globals, cmd, false));
+
+    runner.run_and_coalesce()
+}
+
+/// The most recently written `device.x` in the `OUT_DIR`s under `build_dir`
+fn newest_device_x(build_dir: &std::path::Path) -> Option<PathBuf> {
+    std::fs::read_dir(build_dir)
+        .ok()?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path().join("out").join("device.x");
+            let modified = path.metadata().ok()?.modified().ok()?;
+            Some((modified, path))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
 fn run_command(
     command: &CargoCommand,
     stderr_mode: OutputMode,
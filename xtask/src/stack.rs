@@ -0,0 +1,186 @@
+//! Worst-case stack usage per priority level
+//!
+//! Combines the stack model written by `#[rtic::app]` (`rtic-stack-model-<example>.txt`) with the
+//! frame sizes `-Z emit-stack-sizes` stores in the binary, as printed by
+//! `llvm-readobj --stack-sizes`. At most one handler per priority level is on the stack at a time,
+//! so a level needs the frame of its deepest handler plus the frame the hardware stacks on entry.
+//! Level 0 is `main` and the deepest of the functions it calls in turn.
+//!
+//! Only the frames of the listed functions are known: callees that are not inlined into them are
+//! not counted, check those with a call graph tool such as `cargo call-stack`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+/// The frame size of each function in the output of `llvm-readobj --stack-sizes`
+fn frame_sizes(readobj: &str) -> HashMap<String, u64> {
+    let mut sizes = HashMap::new();
+    let mut functions = vec![];
+
+    for line in readobj.lines().map(str::trim) {
+        if let Some(list) = line
+            .strip_prefix("Functions: [")
+            .and_then(|l| l.strip_suffix(']'))
+        {
+            functions = split_functions(list);
+        } else if let Some(size) = line.strip_prefix("Size: ") {
+            let size = size.trim_start_matches("0x");
+            if let Ok(size) = u64::from_str_radix(size, 16) {
+                for function in functions.drain(..) {
+                    let size = sizes.get(&function).copied().unwrap_or(0).max(size);
+                    sizes.insert(function, size);
+                }
+            }
+        }
+    }
+
+    sizes
+}
+
+/// Splits a list of demangled names at the commas outside of generic arguments, and normalizes
+/// each of them
+fn split_functions(list: &str) -> Vec<String> {
+    let mut functions = vec![];
+    let mut current = String::new();
+
+    for (c, depth) in with_depth(list) {
+        if c == ',' && depth == 0 {
+            functions.push(normalize(&current));
+            current.clear();
+        } else {
+            current.push(c);
+        }
+    }
+    functions.push(normalize(&current));
+
+    functions
+}
+
+/// The characters of `name` with their nesting depth in generic arguments, the angle brackets
+/// themselves are one level deeper and the `>` of `->` does not close one
+fn with_depth(name: &str) -> impl Iterator<Item = (char, usize)> + '_ {
+    let mut depth = 0usize;
+    let mut previous = ' ';
+
+    name.chars().map(move |c| {
+        let level = match c {
+            '<' => {
+                depth += 1;
+                depth
+            }
+            '>' if previous != '-' => {
+                depth = depth.saturating_sub(1);
+                depth + 1
+            }
+            _ => depth,
+        };
+        previous = c;
+        (c, level)
+    })
+}
+
+/// Strips the generic arguments and the hash of a demangled name
+fn normalize(name: &str) -> String {
+    let path: String = with_depth(name.trim())
+        .filter(|(_, depth)| *depth == 0)
+        .map(|(c, _)| c)
+        .collect();
+    let mut path = path
+        .replace("::::", "::")
+        .trim_end_matches("::")
+        .to_string();
+
+    // Legacy mangling ends in a hash, `v0` mangling is demangled without it
+    if let Some((head, hash)) = path.rsplit_once("::h") {
+        if hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            path.truncate(head.len());
+        }
+    }
+
+    path
+}
+
+/// The frame size of the model symbol `symbol`, matching paths relative to the crate root
+fn lookup(sizes: &HashMap<String, u64>, symbol: &str) -> Option<u64> {
+    let suffix = format!("::{symbol}");
+    sizes
+        .iter()
+        .filter(|(name, _)| *name == symbol || name.ends_with(&suffix))
+        .map(|(_, size)| *size)
+        .max()
+}
+
+/// The report of `example`, `model` is the stack model of the app and `readobj` the output of
+/// `llvm-readobj --stack-sizes`
+pub fn report(example: &str, model: &str, readobj: &str, exception_frame: u64) -> String {
+    let sizes = frame_sizes(readobj);
+
+    let mut levels: BTreeMap<u8, Vec<&str>> = BTreeMap::new();
+    for line in model.lines().filter(|l| !l.starts_with('#')) {
+        if let Some((level, symbol)) = line.split_once(' ') {
+            if let Ok(level) = level.parse() {
+                levels.entry(level).or_default().push(symbol);
+            }
+        }
+    }
+
+    let mut s = String::new();
+    let mut total = 0;
+    let mut missing = vec![];
+
+    writeln!(s, "Stack usage of example {example}:").ok();
+    for (level, symbols) in levels {
+        let mut frames = vec![];
+        for symbol in symbols {
+            match lookup(&sizes, symbol) {
+                Some(size) => frames.push((symbol, size)),
+                None => missing.push(symbol),
+            }
+        }
+
+        let (usage, detail) = if level == 0 {
+            // `main` calls the others one after the other
+            let main = frames.iter().find(|(s, _)| *s == "main").copied();
+            let deepest = frames
+                .iter()
+                .filter(|(s, _)| *s != "main")
+                .max_by_key(|(_, size)| *size)
+                .copied();
+
+            let mut usage = 0;
+            let mut detail = vec![];
+            for (symbol, size) in main.into_iter().chain(deepest) {
+                usage += size;
+                detail.push(format!("{symbol} ({size})"));
+            }
+            (usage, detail.join(" + "))
+        } else {
+            let deepest = frames.iter().max_by_key(|(_, size)| *size).copied();
+            match deepest {
+                Some((symbol, size)) => (
+                    size + exception_frame,
+                    format!("{symbol} ({size}) + exception frame ({exception_frame})"),
+                ),
+                None => (
+                    exception_frame,
+                    format!("exception frame ({exception_frame})"),
+                ),
+            }
+        };
+
+        total += usage;
+        writeln!(s, "  priority {level:>3}: {usage:>6} bytes  {detail}").ok();
+    }
+    writeln!(s, "  main stack:   {total:>6} bytes").ok();
+
+    if !missing.is_empty() {
+        writeln!(
+            s,
+            "  no frame size for {} (inlined or not linked), counted as 0",
+            missing.join(", ")
+        )
+        .ok();
+    }
+
+    s
+}
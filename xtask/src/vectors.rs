@@ -0,0 +1,125 @@
+//! Interrupt vector report
+//!
+//! Combines the vector model written by `#[rtic::app]` (`rtic-vector-model-<example>.txt`) with
+//! the interrupts of the device, the `PROVIDE(<interrupt> = DefaultHandler)` lines of the
+//! `device.x` of the PAC, and the symbols of the linked example as printed by `llvm-nm`. An
+//! interrupt whose symbol is at the address of `DefaultHandler` is unused. The monotonics of
+//! `rtic-monotonics` mark the interrupts they take with a `__rtic_monotonic_<interrupt>` symbol,
+//! any other handler comes from the HAL or the application outside of `#[rtic::app]`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+/// The interrupts listed in `device.x`, in vector order
+fn device_interrupts(device_x: &str) -> Vec<&str> {
+    device_x
+        .lines()
+        .filter_map(|line| {
+            let (name, handler) = line
+                .trim()
+                .strip_prefix("PROVIDE(")?
+                .strip_suffix(");")?
+                .split_once('=')?;
+            (handler.trim() == "DefaultHandler").then(|| name.trim())
+        })
+        .collect()
+}
+
+/// The address of each symbol in the output of `llvm-nm`
+fn symbols(nm: &str) -> HashMap<&str, &str> {
+    nm.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let address = fields.next()?;
+            let _kind = fields.next()?;
+            let name = fields.next()?;
+            Some((name, address))
+        })
+        .collect()
+}
+
+/// The report of `example`, `model` is the vector model of the app, `device_x` the linker script
+/// of the PAC and `nm` the output of `llvm-nm --defined-only`
+pub fn report(example: &str, model: &str, device_x: &str, nm: &str) -> String {
+    let symbols = symbols(nm);
+    let default_handler = symbols.get("DefaultHandler");
+
+    let mut app: BTreeMap<&str, String> = BTreeMap::new();
+    for line in model.lines().filter(|l| !l.starts_with('#')) {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        if let [interrupt, kind, priority, task] = fields[..] {
+            let usage = match kind {
+                "task" => format!("hardware task `{task}`, priority {priority}"),
+                _ => format!("dispatcher, priority {priority}"),
+            };
+            app.insert(interrupt, usage);
+        }
+    }
+
+    let interrupts = device_interrupts(device_x);
+    let width = interrupts
+        .iter()
+        .chain(app.keys())
+        .map(|i| i.len())
+        .max()
+        .unwrap_or(0);
+
+    let mut s = String::new();
+    let mut unused = 0;
+    let mut conflicts = vec![];
+
+    writeln!(s, "Interrupt vectors of example {example}:").ok();
+    for interrupt in &interrupts {
+        let monotonic = symbols.contains_key(format!("__rtic_monotonic_{interrupt}").as_str());
+        let usage = match (app.get(interrupt), monotonic) {
+            (Some(usage), true) => {
+                conflicts.push(*interrupt);
+                format!("{usage}, and a monotonic")
+            }
+            (Some(usage), false) => usage.clone(),
+            (None, true) => "monotonic".to_string(),
+            (None, false) => match symbols.get(interrupt) {
+                Some(address) if Some(address) != default_handler => {
+                    "other handler (HAL or application)".to_string()
+                }
+                _ => {
+                    unused += 1;
+                    "unused".to_string()
+                }
+            },
+        };
+        writeln!(s, "  {interrupt:<width$}  {usage}").ok();
+    }
+
+    // Core exceptions bound by hardware tasks or monotonics, e.g. `SysTick`
+    for (interrupt, usage) in &app {
+        if !interrupts.contains(interrupt) {
+            writeln!(s, "  {interrupt:<width$}  {usage} (core exception)").ok();
+        }
+    }
+    let mut exceptions = symbols
+        .keys()
+        .filter_map(|symbol| symbol.strip_prefix("__rtic_monotonic_"))
+        .filter(|exception| !interrupts.contains(exception))
+        .collect::<Vec<_>>();
+    exceptions.sort_unstable();
+    for exception in exceptions {
+        if app.contains_key(exception) {
+            conflicts.push(exception);
+        } else {
+            writeln!(s, "  {exception:<width$}  monotonic (core exception)").ok();
+        }
+    }
+
+    writeln!(s, "  {unused} of {} interrupts unused", interrupts.len()).ok();
+    if !conflicts.is_empty() {
+        writeln!(
+            s,
+            "  taken by the application and a monotonic: {}",
+            conflicts.join(", ")
+        )
+        .ok();
+    }
+
+    s
+}